@@ -1,14 +1,32 @@
-use tokio::sync::mpsc::UnboundedReceiver;
+use std::{collections::HashMap, path::PathBuf};
+
+use tokio::sync::{
+    mpsc::{UnboundedReceiver, UnboundedSender},
+    oneshot,
+};
+
+use defluencer::erasure::{add_parity, ErasureCoding};
 
 use ipfs_api::{responses::Codec, IpfsService};
 
-use linked_data::media::video::{Day, Hour, Minute, Second, Timecode};
+use linked_data::{
+    media::video::{Chapter, Day, Hour, Minute, Second, Timecode},
+    types::IPLDLink,
+};
 
 use cid::Cid;
 
+use super::{Diagnostics, Mirroring, Recording};
+
 pub enum Archive {
-    //Chat(Cid),
-    Video(Cid),
+    /// Chat message CID.
+    Chat(Cid),
+    /// Video node CID and size in bytes of all its tracks.
+    Video((Cid, u64)),
+    /// Segment index and duration in seconds.
+    Duration((usize, f64)),
+    /// Title of a marker placed at the latest segment.
+    Marker(String),
 }
 
 pub struct Archivist {
@@ -16,20 +34,45 @@ pub struct Archivist {
 
     archive_rx: UnboundedReceiver<Archive>,
 
+    diagnostics: Diagnostics,
+
     video_chat_buffer: Option<Second>,
 
     minute_node: Minute,
     hour_node: Hour,
     day_node: Day,
+
+    total_size: u64,
+    segment_count: u64,
+    durations: HashMap<usize, f64>,
+    markers: Vec<Chapter>,
+
+    recording_tx: Option<oneshot::Sender<Recording>>,
+
+    mirror_tx: Option<UnboundedSender<Mirroring>>,
+
+    erasure: Option<ErasureCoding>,
+    parity_group: Vec<Cid>,
+    parity: Vec<IPLDLink>,
 }
 
 impl Archivist {
-    pub fn new(ipfs: IpfsService, archive_rx: UnboundedReceiver<Archive>) -> Self {
+    pub fn new(
+        ipfs: IpfsService,
+        archive_rx: UnboundedReceiver<Archive>,
+        report_dir: Option<PathBuf>,
+        recording_tx: Option<oneshot::Sender<Recording>>,
+        mirror_tx: Option<UnboundedSender<Mirroring>>,
+    ) -> Self {
+        let diagnostics = Diagnostics::new(ipfs.clone(), report_dir);
+
         Self {
             ipfs,
 
             archive_rx,
 
+            diagnostics,
+
             video_chat_buffer: None,
 
             minute_node: Minute {
@@ -43,16 +86,56 @@ impl Archivist {
             day_node: Day {
                 links_to_hours: Vec::with_capacity(24),
             },
+
+            total_size: 0,
+            segment_count: 0,
+            durations: HashMap::with_capacity(64),
+            markers: Vec::new(),
+
+            recording_tx,
+
+            mirror_tx,
+
+            erasure: None,
+            parity_group: Vec::new(),
+            parity: Vec::new(),
         }
     }
 
+    /// Add Reed-Solomon parity to each group of segments archived.
+    pub fn with_parity(mut self, erasure: ErasureCoding) -> Self {
+        self.parity_group = Vec::with_capacity(erasure.segments);
+        self.erasure = Some(erasure);
+        self
+    }
+
     pub async fn start(mut self) {
         println!("✅ Archive System Online");
 
         while let Some(event) = self.archive_rx.recv().await {
             match event {
-                //Archive::Chat(cid) => self.archive_chat_message(cid),
-                Archive::Video(cid) => self.archive_video_segment(cid).await,
+                Archive::Chat(cid) => self.archive_chat_message(cid),
+                Archive::Video((cid, size)) => {
+                    self.total_size += size;
+                    self.segment_count += 1;
+
+                    self.mirror(Mirroring::Segment(cid));
+
+                    self.archive_video_segment(cid).await;
+
+                    self.protect_segment(cid).await
+                }
+                Archive::Duration((index, duration)) => {
+                    // Every track list the same segments, keep the first.
+                    self.durations.entry(index).or_insert(duration);
+                }
+                Archive::Marker(title) => {
+                    let start = self.elapsed();
+
+                    println!("Marker {} at {:.2} Seconds", title, start);
+
+                    self.markers.push(Chapter { start, title });
+                }
             }
         }
 
@@ -61,7 +144,59 @@ impl Archivist {
         println!("❌ Archive System Offline");
     }
 
-    /* /// Link chat message to Seconds.
+    /// Sum of the durations of the segments archived so far, in seconds.
+    fn elapsed(&self) -> f64 {
+        let count = self.segment_count as usize;
+
+        self.durations
+            .iter()
+            .filter(|(index, _)| **index < count)
+            .map(|(_, duration)| duration)
+            .sum()
+    }
+
+    /// Forward to the mirror, if any.
+    fn mirror(&mut self, msg: Mirroring) {
+        let Some(mirror_tx) = self.mirror_tx.as_ref() else {
+            return;
+        };
+
+        if mirror_tx.send(msg).is_err() {
+            eprintln!("❗ Mirror receiver hung up!");
+            self.mirror_tx = None;
+        }
+    }
+
+    /// Buffers segments until a group can be erasure coded.
+    async fn protect_segment(&mut self, cid: Cid) {
+        let Some(erasure) = self.erasure else {
+            return;
+        };
+
+        self.parity_group.push(cid);
+
+        if self.parity_group.len() < erasure.segments {
+            return;
+        }
+
+        self.collect_parity().await;
+    }
+
+    /// Create the parity of the buffered segments.
+    async fn collect_parity(&mut self) {
+        let Some(erasure) = self.erasure else {
+            return;
+        };
+
+        let segments = std::mem::take(&mut self.parity_group);
+
+        match add_parity(&self.ipfs, &segments, erasure.parity).await {
+            Ok(cids) => self.parity.extend(cids.into_iter().map(IPLDLink::from)),
+            Err(e) => eprintln!("❗ Parity of {} segments failed {}", segments.len(), e),
+        }
+    }
+
+    /// Link chat message to Seconds.
     fn archive_chat_message(&mut self, msg_cid: Cid) {
         let node = match self.video_chat_buffer.as_mut() {
             Some(node) => node,
@@ -69,7 +204,7 @@ impl Archivist {
         };
 
         node.links_to_chat.push(msg_cid.into());
-    } */
+    }
 
     /// Buffers Seconds, waiting for chat messages to be linked.
     async fn archive_video_segment(&mut self, cid: Cid) {
@@ -113,10 +248,13 @@ impl Archivist {
             Ok(cid) => cid,
             Err(e) => {
                 eprintln!("❗ IPFS: dag put failed {}", e);
+                self.diagnostics.capture("dag put", &e).await;
                 return;
             }
         };
 
+        self.mirror(Mirroring::Block(cid));
+
         self.minute_node.links_to_seconds.push(cid.into());
     }
 
@@ -130,10 +268,13 @@ impl Archivist {
             Ok(cid) => cid,
             Err(e) => {
                 eprintln!("❗ IPFS: dag put failed {}", e);
+                self.diagnostics.capture("dag put", &e).await;
                 return;
             }
         };
 
+        self.mirror(Mirroring::Block(cid));
+
         self.minute_node.links_to_seconds.clear();
 
         self.hour_node.links_to_minutes.push(cid.into());
@@ -149,10 +290,13 @@ impl Archivist {
             Ok(cid) => cid,
             Err(e) => {
                 eprintln!("❗ IPFS: dag put failed {}", e);
+                self.diagnostics.capture("dag put", &e).await;
                 return;
             }
         };
 
+        self.mirror(Mirroring::Block(cid));
+
         self.hour_node.links_to_minutes.clear();
 
         self.day_node.links_to_hours.push(cid.into());
@@ -176,6 +320,10 @@ impl Archivist {
             self.collect_hour().await;
         }
 
+        if !self.parity_group.is_empty() {
+            self.collect_parity().await;
+        }
+
         if self.day_node.links_to_hours.is_empty() {
             println!("0 Nodes Found");
             return;
@@ -189,12 +337,25 @@ impl Archivist {
             Ok(cid) => cid,
             Err(e) => {
                 eprintln!("❗ IPFS: dag put failed {}", e);
+                self.diagnostics.capture("dag put", &e).await;
                 return;
             }
         };
 
+        self.mirror(Mirroring::Block(cid));
+
+        let duration = if self.durations.is_empty() {
+            None
+        } else {
+            Some(self.elapsed())
+        };
+
         let stream = Timecode {
             timecode: cid.into(),
+            size: Some(self.total_size),
+            segment_count: Some(self.segment_count),
+            duration,
+            parity: std::mem::take(&mut self.parity),
         };
 
         let cid = match self
@@ -205,15 +366,40 @@ impl Archivist {
             Ok(cid) => cid,
             Err(e) => {
                 eprintln!("❗ IPFS: dag put failed {}", e);
+                self.diagnostics.capture("dag put", &e).await;
                 return;
             }
         };
 
+        self.mirror(Mirroring::Pin(cid));
+
         println!("Pinning Nodes...");
 
         match self.ipfs.pin_add(cid, true).await {
-            Ok(_) => println!("Final Timecode-addressable Node => {}", cid.to_string()),
-            Err(e) => eprintln!("❗ IPFS: pin add failed {}", e),
+            Ok(_) => {
+                println!(
+                    "{} Segments, {} Bytes, {:.2} Seconds",
+                    self.segment_count,
+                    self.total_size,
+                    duration.unwrap_or_default()
+                );
+                println!("Final Timecode-addressable Node => {}", cid.to_string());
+
+                if let Some(recording_tx) = self.recording_tx.take() {
+                    let recording = Recording {
+                        timecode: cid,
+                        markers: std::mem::take(&mut self.markers),
+                    };
+
+                    if recording_tx.send(recording).is_err() {
+                        eprintln!("❗ Post-processing receiver hung up!");
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("❗ IPFS: pin add failed {}", e);
+                self.diagnostics.capture("pin add", &e).await;
+            }
         }
     }
 }
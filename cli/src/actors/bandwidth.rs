@@ -0,0 +1,77 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::time::sleep;
+
+/// Archiving bandwidth cap in bytes per second, shared with the control API.
+///
+/// Zero means unlimited.
+pub type BandwidthCap = Arc<AtomicU64>;
+
+/// Token bucket delaying writes to stay under the cap, clones share the same bucket.
+///
+/// Bursts of up to one second worth of bytes are allowed.
+#[derive(Clone)]
+pub struct Throttle {
+    cap: BandwidthCap,
+
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+struct Bucket {
+    /// Bytes that can be written without waiting, negative when in debt.
+    allowance: f64,
+
+    last: Instant,
+}
+
+impl Throttle {
+    pub fn new(cap: BandwidthCap) -> Self {
+        let bucket = Bucket {
+            allowance: 0.0,
+            last: Instant::now(),
+        };
+
+        Self {
+            cap,
+            bucket: Arc::new(Mutex::new(bucket)),
+        }
+    }
+
+    /// Wait until these bytes can be written.
+    pub async fn consume(&self, bytes: u64) {
+        let rate = self.cap.load(Ordering::Relaxed);
+
+        let wait = {
+            let mut bucket = self.bucket.lock().expect("Lock Poisoned");
+
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.last).as_secs_f64();
+            bucket.last = now;
+
+            if rate == 0 {
+                bucket.allowance = 0.0;
+                return;
+            }
+
+            let rate = rate as f64;
+
+            bucket.allowance = (bucket.allowance + elapsed * rate).min(rate);
+            bucket.allowance -= bytes as f64;
+
+            if bucket.allowance >= 0.0 {
+                return;
+            }
+
+            // The debt is paid back by the time elapsed while waiting.
+            Duration::from_secs_f64(-bucket.allowance / rate)
+        };
+
+        sleep(wait).await;
+    }
+}
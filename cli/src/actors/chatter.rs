@@ -1,10 +1,22 @@
-use crate::actors::archivist::Archive;
+use std::{collections::HashSet, path::PathBuf, time::Duration};
 
-use defluencer::{crypto::dag_jose::JsonWebSignature, moderation_cache::ChatModerationCache};
+use chrono::Utc;
 
-use futures_util::{future::AbortHandle, StreamExt, TryStreamExt};
+use defluencer::{
+    channel::{local::LocalUpdater, threshold::ThresholdUpdater, Channel},
+    chat::{decode_message, ChatRoom, Verdict},
+    reputation::Reputation,
+};
+
+use futures_util::{pin_mut, TryStreamExt};
 
-use tokio::sync::{mpsc::UnboundedSender, watch::Receiver};
+use tokio::{
+    sync::{
+        mpsc::{UnboundedReceiver, UnboundedSender},
+        watch::Receiver,
+    },
+    time::interval,
+};
 
 use ipfs_api::{
     responses::{Codec, PubSubMessage},
@@ -12,65 +24,100 @@ use ipfs_api::{
 };
 
 use linked_data::{
-    media::chat::{ChatMessage, MessageType},
-    moderation::{Ban, Bans, Moderators},
-    signature::RawJWS,
-    types::PeerId,
+    media::chat::{ChatMessage, ChatSettings, MessageType},
+    types::Address,
 };
 
+use super::Archive;
+
+/// Active settings are broadcast this often for late joiners.
+const BROADCAST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Reputation is saved this often, a crash loses at most this much.
+const SAVE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Enforce the live chat rules then broadcast the active settings.
 pub struct Chatter {
     ipfs: IpfsService,
 
-    archive_tx: UnboundedSender<Archive>,
-
     shutdown: Receiver<()>,
 
-    mod_db: ChatModerationCache,
+    room: ChatRoom,
 
     topic: String,
 
-    bans: Bans,
+    settings_topic: String,
 
-    new_ban_count: usize,
+    /// File where sender reputation is kept between streams.
+    reputation_file: Option<PathBuf>,
 
-    mods: Moderators,
+    archive_tx: Option<UnboundedSender<Archive>>,
+
+    /// Channel updated with the bans made in chat.
+    channel: Option<Channel<ThresholdUpdater<LocalUpdater>>>,
+
+    /// Addresses banned before the stream started.
+    initial_bans: HashSet<Address>,
+
+    /// Refreshed addresses of the channel followers.
+    followers_rx: Option<UnboundedReceiver<HashSet<Address>>>,
 }
 
 impl Chatter {
     pub fn new(
         ipfs: IpfsService,
-        archive_tx: UnboundedSender<Archive>,
         shutdown: Receiver<()>,
+        room: ChatRoom,
         topic: String,
-        bans: Bans,
-        mods: Moderators,
+        settings_topic: String,
+        reputation_file: Option<PathBuf>,
     ) -> Self {
+        let initial_bans = room.bans().banned_addrs.clone();
+
         Self {
             ipfs,
-
-            archive_tx,
-
             shutdown,
-
-            mod_db: ChatModerationCache::new(100, 0),
-
+            room,
             topic,
+            settings_topic,
+            reputation_file,
+            archive_tx: None,
+            channel: None,
+            initial_bans,
+            followers_rx: None,
+        }
+    }
 
-            bans,
+    /// Link allowed chat messages to the stream archive.
+    pub fn with_archive(mut self, archive_tx: UnboundedSender<Archive>) -> Self {
+        self.archive_tx = Some(archive_tx);
+        self
+    }
 
-            new_ban_count: 0,
+    /// Save the bans made in chat to this channel once the stream ends.
+    pub fn with_channel(mut self, channel: Channel<ThresholdUpdater<LocalUpdater>>) -> Self {
+        self.channel = Some(channel);
+        self
+    }
 
-            mods,
-        }
+    /// Allow these followers to chat in followers only mode, replaced on each receive.
+    pub fn with_followers(mut self, followers_rx: UnboundedReceiver<HashSet<Address>>) -> Self {
+        self.followers_rx = Some(followers_rx);
+        self
     }
 
     pub async fn start(mut self) {
         let ipfs = self.ipfs.clone();
 
-        let (_, regis) = AbortHandle::new_pair();
-        let mut stream = ipfs
-            .pubsub_sub(self.topic.as_bytes().to_owned(), regis)
-            .boxed();
+        let stream = ipfs.pubsub_sub(self.topic.clone().into_bytes());
+        pin_mut!(stream);
+
+        let mut broadcast = interval(BROADCAST_INTERVAL);
+
+        let mut save = interval(SAVE_INTERVAL);
+        save.reset();
+
+        self.load_reputation().await;
 
         println!("✅ Chat System Online");
 
@@ -80,114 +127,115 @@ impl Chatter {
 
                 _ = self.shutdown.changed() => break,
 
+                _ = broadcast.tick() => {
+                    let settings = self.room.settings().clone();
+                    self.broadcast(&settings).await;
+                },
+
+                _ = save.tick() => self.save_reputation().await,
+
+                Some(followers) = recv_followers(&mut self.followers_rx) => {
+                    self.room.set_followers(followers)
+                },
+
                 res = stream.try_next() => match res {
-                    Ok(option) => match option {
-                        Some(msg) => self.on_pubsub_message(msg).await,
-                        None => {},
-                    },
-                    Err(e) => eprintln!("{}", e),
+                    Ok(Some(msg)) => self.on_pubsub_message(msg).await,
+                    Ok(None) => break,
+                    Err(e) => eprintln!("❗ IPFS: pubsub sub failed {}", e),
                 },
             }
         }
 
-        if self.new_ban_count > 0 {
-            match self.ipfs.dag_put(&self.bans, Codec::default()).await {
-                Ok(cid) => println!(
-                    "Updating Banned List with {} New Users 👍\nNew List CID: {}",
-                    self.new_ban_count, cid
-                ),
-                Err(e) => eprintln!("❗ IPFS DAG Put Failed. {}", e),
-            }
-        }
+        self.save_reputation().await;
+
+        self.save_bans().await;
 
         println!("❌ Chat System Offline");
     }
 
-    async fn on_pubsub_message(&mut self, msg: PubSubMessage) {
-        let PubSubMessage { from, data } = msg;
-        let peer: PeerId = from.into();
-
-        if self.mod_db.is_banned(&peer) {
+    async fn load_reputation(&mut self) {
+        let Some(path) = self.reputation_file.as_ref() else {
             return;
-        }
+        };
 
-        let msg: ChatMessage = match serde_json::from_slice(&data) {
-            Ok(data) => data,
+        let bytes = match tokio::fs::read(path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
             Err(e) => {
-                eprintln!("❗ PubSub Message Deserialization Failed. {}", e);
+                eprintln!("❗ Reputation: read failed {}", e);
                 return;
             }
         };
 
-        if !self.mod_db.is_verified(&peer, &msg.signature.link) {
-            return self.get_origin(peer, msg).await;
+        match serde_json::from_slice::<Reputation>(&bytes) {
+            Ok(reputation) => self.room.set_reputation(reputation),
+            Err(e) => eprintln!("❗ Reputation Deserialization Failed. {}", e),
         }
-
-        self.process_msg(&peer, msg).await
     }
 
-    async fn get_origin(&mut self, peer: PeerId, msg: ChatMessage) {
-        let jws: JsonWebSignature = match self
-            .ipfs
-            .dag_get::<&str, RawJWS>(msg.signature.link, Option::<&str>::None)
-            .await
-        {
-            Ok(raw_jws) => match raw_jws.try_into() {
-                Ok(jws) => jws,
-                Err(e) => {
-                    eprintln!("❗ {}", e);
-                    return;
-                }
-            },
-            Err(e) => {
-                eprintln!("❗ IPFS: dag get failed {}", e);
-                return;
-            }
+    async fn save_reputation(&self) {
+        let Some(path) = self.reputation_file.as_ref() else {
+            return;
         };
 
-        let address = match jws.get_eth_address() {
-            Some(addr) => addr,
-            None => {
-                self.mod_db
-                    .add_peer(peer, msg.signature.link, [0u8; 20], None);
-
-                self.mod_db.ban_peer(&peer);
-
+        let bytes = match serde_json::to_vec(self.room.reputation()) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("❗ Reputation Serialization Failed. {}", e);
                 return;
             }
         };
 
-        self.mod_db
-            .add_peer(peer, msg.signature.link, address, None);
+        // Replace the file at once, a crash while writing keeps the previous save.
+        let temp = path.with_extension("tmp");
 
-        if peer != jws.link.into() {
-            self.mod_db.ban_peer(&peer);
+        if let Err(e) = tokio::fs::write(&temp, bytes).await {
+            eprintln!("❗ Reputation: write failed {}", e);
             return;
         }
 
-        if !jws.verify().is_ok() {
-            self.mod_db.ban_peer(&peer);
-            return;
+        if let Err(e) = tokio::fs::rename(&temp, path).await {
+            eprintln!("❗ Reputation: write failed {}", e);
         }
+    }
 
-        if self.bans.banned_addrs.contains(&address) {
-            self.mod_db.ban_peer(&peer);
-            return;
-        }
+    async fn on_pubsub_message(&mut self, msg: PubSubMessage) {
+        let PubSubMessage { from, data } = msg;
 
-        self.process_msg(&peer, msg).await
-    }
+        let msg = match decode_message(&data) {
+            Some(msg) => msg,
+            None => {
+                eprintln!("❗ PubSub Message Deserialization Failed.");
+                return;
+            }
+        };
+
+        match self.room.process(from, &msg, Utc::now().timestamp()).await {
+            Ok(Verdict::Allow) => match &msg.message {
+                MessageType::Text(_) => self.archive(&msg).await,
+                MessageType::Ban(ban) => println!("Chat: 0x{} Banned", hex::encode(ban.ban_addrs)),
+                _ => {}
+            },
+            Ok(Verdict::Reject(_)) => {}
+            Ok(Verdict::Settings(settings)) => {
+                println!("Chat Settings Updated");
 
-    async fn process_msg(&mut self, peer: &PeerId, chat: ChatMessage) {
-        match chat.message {
-            MessageType::Text(text) => self.mint_and_archive(text).await,
-            MessageType::Ban(ban) => self.update_bans(peer, ban),
-            MessageType::Mod(_) => {}
+                self.broadcast(&settings).await;
+            }
+            Err(e) => eprintln!("❗ Chat: {}", e),
         }
     }
 
-    async fn mint_and_archive(&self, msg: String) {
-        let cid = match self.ipfs.dag_put(&msg, Codec::default()).await {
+    async fn archive(&self, msg: &ChatMessage) {
+        let Some(archive_tx) = self.archive_tx.as_ref() else {
+            return;
+        };
+
+        let cid = match self
+            .ipfs
+            .dag_put(msg, Codec::default(), Codec::default())
+            .await
+        {
             Ok(cid) => cid,
             Err(e) => {
                 eprintln!("❗ IPFS: dag put failed {}", e);
@@ -195,23 +243,78 @@ impl Chatter {
             }
         };
 
-        let msg = Archive::Chat(cid);
-
-        if let Err(error) = self.archive_tx.send(msg) {
+        if let Err(error) = archive_tx.send(Archive::Chat(cid)) {
             eprintln!("❗ Archive receiver hung up. {}", error);
         }
     }
 
-    fn update_bans(&mut self, peer: &PeerId, ban: Ban) {
-        let address = self.mod_db.get_address(peer).unwrap();
-
-        if !self.mods.moderator_addrs.contains(address) {
+    /// Add the bans made in chat to the channel ban list.
+    async fn save_bans(&self) {
+        let new_bans: Vec<Address> = self
+            .room
+            .bans()
+            .banned_addrs
+            .difference(&self.initial_bans)
+            .copied()
+            .collect();
+
+        if new_bans.is_empty() {
             return;
         }
 
-        self.mod_db.ban_peer(&ban.ban_peer);
-        self.bans.banned_addrs.insert(ban.ban_addrs);
+        if let Some(channel) = self.channel.as_ref() {
+            println!("Updating Banned List with {} New Users...", new_bans.len());
+
+            let mut saved = true;
+
+            for addr in new_bans.iter() {
+                if let Err(e) = channel.ban_user(*addr).await {
+                    eprintln!("❗ Ban 0x{} not saved. {}", hex::encode(addr), e);
+                    saved = false;
+                }
+            }
+
+            if saved {
+                return;
+            }
+        }
+
+        // Keep the list around so it can be set manually.
+        match self
+            .ipfs
+            .dag_put(self.room.bans(), Codec::default(), Codec::default())
+            .await
+        {
+            Ok(cid) => println!(
+                "Updating Banned List with {} New Users 👍\nNew List CID: {}",
+                new_bans.len(),
+                cid
+            ),
+            Err(e) => eprintln!("❗ IPFS DAG Put Failed. {}", e),
+        }
+    }
+
+    async fn broadcast(&self, settings: &ChatSettings) {
+        let data = match serde_json::to_vec(settings) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("❗ Settings Serialization Failed. {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = self.ipfs.pubsub_pub(&self.settings_topic, data).await {
+            eprintln!("❗ IPFS: pubsub pub failed {}", e);
+        }
+    }
+}
 
-        self.new_ban_count += 1;
+/// Pending forever without a follower receiver.
+async fn recv_followers(
+    followers_rx: &mut Option<UnboundedReceiver<HashSet<Address>>>,
+) -> Option<HashSet<Address>> {
+    match followers_rx.as_mut() {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
     }
 }
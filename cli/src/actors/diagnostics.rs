@@ -0,0 +1,124 @@
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use futures_util::{pin_mut, TryStreamExt};
+
+use ipfs_api::{errors::Error, responses::LogEntry, IpfsService};
+
+/// Number of daemon log entries kept in memory.
+const LOG_CAPACITY: usize = 100;
+
+/// Duration of the CPU profile bundled with error reports.
+const PROFILE_SECS: u64 = 5;
+
+/// Capture IPFS daemon context when a request fail.
+///
+/// The daemon event log is buffered in the background.
+/// On failure, the buffer is printed or, if a report directory is set,
+/// saved alongside a daemon profile for bug filing.
+#[derive(Clone)]
+pub struct Diagnostics {
+    ipfs: IpfsService,
+
+    logs: Arc<Mutex<VecDeque<LogEntry>>>,
+
+    report_dir: Option<PathBuf>,
+}
+
+impl Diagnostics {
+    pub fn new(ipfs: IpfsService, report_dir: Option<PathBuf>) -> Self {
+        let logs = Arc::new(Mutex::new(VecDeque::with_capacity(LOG_CAPACITY)));
+
+        tokio::spawn(tail_logs(ipfs.clone(), logs.clone()));
+
+        Self {
+            ipfs,
+            logs,
+            report_dir,
+        }
+    }
+
+    pub async fn capture(&self, context: &str, error: &Error) {
+        let logs: Vec<LogEntry> = self.logs.lock().unwrap().iter().cloned().collect();
+
+        let dir = match self.report_dir.as_ref() {
+            Some(dir) => dir,
+            None => {
+                for entry in logs.iter() {
+                    eprintln!(
+                        "   {} {} {}: {}",
+                        entry.ts, entry.level, entry.logger, entry.msg
+                    );
+                }
+
+                return;
+            }
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let report = serde_json::json!({
+            "timestamp": timestamp,
+            "context": context,
+            "error": error.to_string(),
+            "logs": logs,
+        });
+
+        let path = dir.join(format!("error-report-{}.json", timestamp));
+
+        if let Err(e) = tokio::fs::write(&path, report.to_string()).await {
+            eprintln!("❗ Cannot write error report {}", e);
+            return;
+        }
+
+        eprintln!("Error Report => {}", path.display());
+
+        let profile = match self.ipfs.diag_profile(Some(PROFILE_SECS)).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("❗ IPFS: diag profile failed {}", e);
+                return;
+            }
+        };
+
+        let path = dir.join(format!("error-report-{}.zip", timestamp));
+
+        if let Err(e) = tokio::fs::write(&path, profile).await {
+            eprintln!("❗ Cannot write daemon profile {}", e);
+            return;
+        }
+
+        eprintln!("Daemon Profile => {}", path.display());
+    }
+}
+
+async fn tail_logs(ipfs: IpfsService, logs: Arc<Mutex<VecDeque<LogEntry>>>) {
+    let stream = ipfs.log_tail();
+    pin_mut!(stream);
+
+    loop {
+        match stream.try_next().await {
+            Ok(Some(entry)) => {
+                let mut logs = logs.lock().unwrap();
+
+                if logs.len() >= LOG_CAPACITY {
+                    logs.pop_front();
+                }
+
+                logs.push_back(entry);
+            }
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("❗ IPFS: log tail failed {}", e);
+                return;
+            }
+        }
+    }
+}
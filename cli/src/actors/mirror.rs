@@ -0,0 +1,232 @@
+use std::collections::{HashSet, VecDeque};
+
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use defluencer::denylist::Denylist;
+
+use ipfs_api::{errors::Error, responses::Codec, IpfsService};
+
+use linked_data::media::video::Segment;
+
+use cid::Cid;
+
+use crate::cli::denylist::DenylistArgs;
+
+/// Number of times a block is copied before giving up.
+const MAX_ATTEMPTS: u8 = 3;
+
+pub enum Mirroring {
+    /// Video segment node, copied with its tracks and setup.
+    Segment(Cid),
+    /// Archive node whose links were already mirrored.
+    Block(Cid),
+    /// Final archive node, pinned recursively on the remote node.
+    Pin(Cid),
+}
+
+/// Copy the live archive to a secondary IPFS node as it is created.
+///
+/// Failures of the remote node never affect archiving on the local node,
+/// failed blocks are retried on the next messages. Denied blocks are never copied.
+pub struct Mirror {
+    local: IpfsService,
+    remote: IpfsService,
+
+    mirror_rx: UnboundedReceiver<Mirroring>,
+
+    compliance: DenylistArgs,
+    denylist: Denylist,
+
+    copied: HashSet<Cid>,
+    retries: VecDeque<(Cid, u8)>,
+    lost: Vec<Cid>,
+}
+
+impl Mirror {
+    pub fn new(
+        local: IpfsService,
+        remote: IpfsService,
+        mirror_rx: UnboundedReceiver<Mirroring>,
+        compliance: DenylistArgs,
+        denylist: Denylist,
+    ) -> Self {
+        Self {
+            local,
+            remote,
+
+            mirror_rx,
+
+            compliance,
+            denylist,
+
+            copied: HashSet::with_capacity(1024),
+            retries: VecDeque::new(),
+            lost: Vec::new(),
+        }
+    }
+
+    pub async fn start(mut self) {
+        if let Err(e) = self.remote.peer_id().await {
+            eprintln!("❗ Mirror: remote node unreachable {}", e);
+        }
+
+        println!("✅ Mirror System Online");
+
+        let mut refresh = self.compliance.interval();
+
+        loop {
+            tokio::select! {
+                _ = refresh.tick() => {
+                    if let Some(latest) = self.compliance.refresh().await {
+                        self.denylist = latest;
+                    }
+                },
+
+                event = self.mirror_rx.recv() => {
+                    let Some(event) = event else {
+                        break;
+                    };
+
+                    self.retry().await;
+
+                    match event {
+                        Mirroring::Segment(cid) => self.mirror_segment(cid).await,
+                        Mirroring::Block(cid) => self.copy(cid, 0).await,
+                        Mirroring::Pin(cid) => self.pin(cid).await,
+                    }
+                },
+            }
+        }
+
+        while !self.retries.is_empty() {
+            self.retry().await;
+        }
+
+        if self.lost.is_empty() {
+            println!("{} Blocks Mirrored", self.copied.len());
+        } else {
+            eprintln!(
+                "❗ Mirror: {} blocks mirrored, {} lost",
+                self.copied.len(),
+                self.lost.len()
+            );
+
+            for cid in self.lost.iter() {
+                eprintln!("   {}", cid);
+            }
+        }
+
+        println!("❌ Mirror System Offline");
+    }
+
+    /// Copy the segment node, its tracks and setup.
+    async fn mirror_segment(&mut self, cid: Cid) {
+        let segment = match self
+            .local
+            .dag_get::<&str, Segment>(cid, None, Codec::default())
+            .await
+        {
+            Ok(segment) => segment,
+            Err(e) => {
+                eprintln!("❗ Mirror: dag get failed {}", e);
+                return;
+            }
+        };
+
+        let roots = segment
+            .tracks
+            .values()
+            .chain(segment.setup.iter())
+            .map(|ipld| ipld.link)
+            .collect::<Vec<_>>();
+
+        for root in roots {
+            if self.copied.contains(&root) {
+                continue;
+            }
+
+            let refs = match self.local.refs(root).await {
+                Ok(refs) => refs,
+                Err(e) => {
+                    eprintln!("❗ Mirror: refs failed {}", e);
+                    continue;
+                }
+            };
+
+            for cid in refs {
+                self.copy(cid, 0).await;
+            }
+
+            self.copy(root, 0).await;
+        }
+
+        // Links first, a node is only mirrored once its links are.
+        self.copy(cid, 0).await;
+    }
+
+    /// Copy one block from the local to the remote node.
+    async fn copy(&mut self, cid: Cid, attempts: u8) {
+        if self.copied.contains(&cid) {
+            return;
+        }
+
+        if self.denylist.contains(cid) {
+            self.compliance.audit("mirror", cid).await;
+            self.lost.push(cid);
+            return;
+        }
+
+        match self.transfer(cid).await {
+            Ok(_) => {
+                self.copied.insert(cid);
+            }
+            Err(e) if attempts + 1 < MAX_ATTEMPTS => {
+                eprintln!("❗ Mirror: copy of {} failed {}", cid, e);
+                self.retries.push_back((cid, attempts + 1));
+            }
+            Err(e) => {
+                eprintln!("❗ Mirror: copy of {} failed {}, giving up", cid, e);
+                self.lost.push(cid);
+            }
+        }
+    }
+
+    async fn transfer(&self, cid: Cid) -> Result<(), Error> {
+        let data = self.local.block_get(cid).await?;
+
+        let remote_cid = self.remote.block_put(cid, data).await?;
+
+        if remote_cid != cid {
+            eprintln!("❗ Mirror: {} was stored as {}", cid, remote_cid);
+        }
+
+        Ok(())
+    }
+
+    /// Copy blocks that failed previously.
+    async fn retry(&mut self) {
+        for (cid, attempts) in std::mem::take(&mut self.retries) {
+            self.copy(cid, attempts).await;
+        }
+    }
+
+    async fn pin(&mut self, cid: Cid) {
+        self.copy(cid, 0).await;
+
+        while !self.retries.is_empty() {
+            self.retry().await;
+        }
+
+        if !self.lost.is_empty() {
+            eprintln!("❗ Mirror: archive incomplete, {} not pinned remotely", cid);
+            return;
+        }
+
+        println!("Pinning Mirror...");
+
+        match self.remote.pin_add(cid, true).await {
+            Ok(_) => println!("✅ Archive {} Pinned On Mirror", cid),
+            Err(e) => eprintln!("❗ Mirror: pin add failed {}", e),
+        }
+    }
+}
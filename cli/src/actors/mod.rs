@@ -1,9 +1,19 @@
 mod archivist;
-//mod chatter;
+mod bandwidth;
+mod chatter;
+mod diagnostics;
+mod mirror;
+mod post;
 mod setup;
+mod stats;
 mod video;
 
 pub use archivist::{Archive, Archivist};
-//pub use chatter::Chatter;
+pub use bandwidth::{BandwidthCap, Throttle};
+pub use chatter::Chatter;
+pub use diagnostics::Diagnostics;
+pub use mirror::{Mirror, Mirroring};
+pub use post::{PostProcessor, Processed, Recording};
 pub use setup::{Setter, SetupData};
+pub use stats::{LatestStats, Statistician};
 pub use video::{VideoData, Videograph};
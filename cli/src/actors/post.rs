@@ -0,0 +1,173 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::Command,
+};
+
+use defluencer::errors::Error;
+
+use linked_data::{media::video::Chapter, types::IPLDLink};
+
+use cid::Cid;
+
+/// Archive of a finished live stream.
+pub struct Recording {
+    /// Timecode node of the raw archive.
+    pub timecode: Cid,
+
+    /// Markers placed while streaming.
+    pub markers: Vec<Chapter>,
+}
+
+/// Result of the post-processing pipeline.
+pub struct Processed {
+    pub timecode: Cid,
+    pub chapters: Vec<Chapter>,
+    pub thumbnails: Vec<IPLDLink>,
+}
+
+/// Run hooks one after the other on a stream archive.
+///
+/// Each hook is executed with the current timecode CID as argument and the markers on stdin,
+/// one per line as seconds and title separated by a tab.
+///
+/// Hooks report on stdout, one line each;
+/// "video <CID>" replaces the timecode for the next hooks,
+/// "chapter <SECONDS> <TITLE>" and "thumbnail <CID>" add to the video node.
+///
+/// Chapters reported by a hook replace those of previous hooks.
+pub struct PostProcessor {
+    hooks: Vec<PathBuf>,
+}
+
+impl PostProcessor {
+    pub fn new(hooks: Vec<PathBuf>) -> Self {
+        Self { hooks }
+    }
+
+    pub async fn run(self, recording: Recording) -> Processed {
+        println!("✅ Post-Processing System Online");
+
+        let Recording { timecode, markers } = recording;
+
+        let mut processed = Processed {
+            timecode,
+            chapters: Vec::new(),
+            thumbnails: Vec::new(),
+        };
+
+        for hook in self.hooks.iter() {
+            println!("Running {}...", hook.display());
+
+            match run_hook(hook, &markers, &processed).await {
+                Ok(Some(output)) => {
+                    processed.timecode = output.timecode;
+
+                    if !output.chapters.is_empty() {
+                        processed.chapters = output.chapters;
+                    }
+
+                    processed.thumbnails.extend(output.thumbnails);
+                }
+                Ok(None) => eprintln!("❗ Hook {} failed, skipping", hook.display()),
+                Err(e) => eprintln!("❗ Hook {} failed to run. {}", hook.display(), e),
+            }
+        }
+
+        println!(
+            "{} Chapters, {} Thumbnails",
+            processed.chapters.len(),
+            processed.thumbnails.len()
+        );
+        println!(
+            "Processed Timecode-addressable Node => {}",
+            processed.timecode
+        );
+
+        println!("❌ Post-Processing System Offline");
+
+        processed
+    }
+}
+
+/// Run one hook, none if it exited with an error.
+async fn run_hook(
+    hook: &Path,
+    markers: &[Chapter],
+    current: &Processed,
+) -> Result<Option<Processed>, Error> {
+    let mut child = Command::new(hook)
+        .arg(current.timecode.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let mut stdin = child.stdin.take().expect("Piped Stdin");
+
+    for marker in markers {
+        let line = format!("{}\t{}\n", marker.start, marker.title);
+
+        // Hooks are free to ignore markers.
+        if stdin.write_all(line.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+
+    drop(stdin);
+
+    let stdout = child.stdout.take().expect("Piped Stdout");
+    let mut lines = BufReader::new(stdout).lines();
+
+    let mut output = Processed {
+        timecode: current.timecode,
+        chapters: Vec::new(),
+        thumbnails: Vec::new(),
+    };
+
+    while let Some(line) = lines.next_line().await? {
+        if !parse_line(&line, &mut output) {
+            println!("{}", line);
+        }
+    }
+
+    if !child.wait().await?.success() {
+        return Ok(None);
+    }
+
+    output
+        .chapters
+        .sort_unstable_by(|a, b| a.start.total_cmp(&b.start));
+
+    Ok(Some(output))
+}
+
+/// Apply one line of hook output, false if it was not a report.
+fn parse_line(line: &str, output: &mut Processed) -> bool {
+    let mut words = line.trim().splitn(3, ' ');
+
+    match (words.next(), words.next(), words.next()) {
+        (Some("video"), Some(cid), None) => match Cid::try_from(cid) {
+            Ok(cid) => output.timecode = cid,
+            Err(_) => return false,
+        },
+        (Some("thumbnail"), Some(cid), None) => match Cid::try_from(cid) {
+            Ok(cid) => output.thumbnails.push(cid.into()),
+            Err(_) => return false,
+        },
+        (Some("chapter"), Some(start), Some(title)) => match start.parse::<f64>() {
+            Ok(start) => output.chapters.push(Chapter {
+                start,
+                title: title.to_owned(),
+            }),
+            Err(_) => return false,
+        },
+        _ => return false,
+    }
+
+    true
+}
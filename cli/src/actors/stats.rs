@@ -0,0 +1,83 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use ipfs_api::IpfsService;
+
+use linked_data::channel::live::EncoderStats;
+
+/// Latest encoder statistics, shared with the ingest server.
+pub type LatestStats = Arc<Mutex<Option<EncoderStats>>>;
+
+/// Encoding slower than this fraction of real time is reported.
+const MIN_SPEED: f64 = 0.95;
+
+/// Publish encoder statistics and warn about degradation.
+pub struct Statistician {
+    ipfs: IpfsService,
+
+    stats_rx: UnboundedReceiver<EncoderStats>,
+
+    pubsub_topic: String,
+
+    latest: LatestStats,
+}
+
+impl Statistician {
+    pub fn new(
+        ipfs: IpfsService,
+        stats_rx: UnboundedReceiver<EncoderStats>,
+        pubsub_topic: String,
+    ) -> Self {
+        Self {
+            ipfs,
+            stats_rx,
+            pubsub_topic,
+            latest: Arc::default(),
+        }
+    }
+
+    pub fn latest(&self) -> LatestStats {
+        self.latest.clone()
+    }
+
+    pub async fn start(mut self) {
+        println!("✅ Statistics System Online");
+
+        while let Some(stats) = self.stats_rx.recv().await {
+            self.on_stats(stats).await;
+        }
+
+        println!("❌ Statistics System Offline");
+    }
+
+    async fn on_stats(&mut self, stats: EncoderStats) {
+        let dropped = {
+            let latest = self.latest.lock().unwrap();
+
+            match latest.as_ref() {
+                Some(previous) => stats.dropped_frames.saturating_sub(previous.dropped_frames),
+                None => stats.dropped_frames,
+            }
+        };
+
+        if dropped > 0 {
+            eprintln!("❗ Encoder: {} frames dropped", dropped);
+        }
+
+        if stats.speed > 0.0 && stats.speed < MIN_SPEED {
+            eprintln!("❗ Encoder: running at {:.2}x real time", stats.speed);
+        }
+
+        match serde_json::to_vec(&stats) {
+            Ok(data) => {
+                if let Err(e) = self.ipfs.pubsub_pub(&self.pubsub_topic, data).await {
+                    eprintln!("❗ IPFS: pubsub pub failed {}", e);
+                }
+            }
+            Err(e) => eprintln!("❗ Stats Serialization Failed. {}", e),
+        }
+
+        *self.latest.lock().unwrap() = Some(stats);
+    }
+}
@@ -5,7 +5,12 @@ use std::{
     path::PathBuf,
 };
 
-use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use defluencer::live::{encode_segment, sign_segment, SigningKey};
+
+use tokio::sync::{
+    mpsc::{UnboundedReceiver, UnboundedSender},
+    oneshot,
+};
 
 use ipfs_api::{responses::Codec, IpfsService};
 
@@ -20,20 +25,27 @@ pub struct Videograph {
     archive_tx: Option<UnboundedSender<Archive>>,
 
     pubsub_topic: Option<String>,
+    segment_key: Option<SigningKey>,
+
+    /// Notified once the first segment is published.
+    started_tx: Option<oneshot::Sender<()>>,
 
     track_len: usize,
     setup_link: Option<IPLDLink>,
 
     node_mint_count: usize,
     segment_nodes: VecDeque<Segment>,
+    segment_sizes: VecDeque<u64>,
 
     previous: Option<IPLDLink>,
 }
 
 #[derive(Debug)]
 pub enum VideoData {
-    Segment((PathBuf, Cid)),
+    Segment((PathBuf, Cid, u64)),
     Setup((IPLDLink, usize)),
+    Duration((usize, f64)),
+    Marker(String),
 }
 
 impl Videograph {
@@ -42,6 +54,7 @@ impl Videograph {
         service_rx: UnboundedReceiver<VideoData>,
         archive_tx: Option<UnboundedSender<Archive>>,
         pubsub_topic: Option<String>,
+        segment_key: Option<SigningKey>,
     ) -> Self {
         Self {
             ipfs,
@@ -50,26 +63,49 @@ impl Videograph {
             archive_tx,
 
             pubsub_topic,
+            segment_key,
+
+            started_tx: None,
 
             track_len: 0,
             setup_link: None,
 
             node_mint_count: 0,
             segment_nodes: VecDeque::with_capacity(5),
+            segment_sizes: VecDeque::with_capacity(5),
             previous: None,
         }
     }
 
+    pub fn with_started(mut self, started_tx: oneshot::Sender<()>) -> Self {
+        self.started_tx = Some(started_tx);
+        self
+    }
+
     pub async fn start(mut self) {
         println!("✅ Video System Online");
 
         while let Some(msg) = self.service_rx.recv().await {
             match msg {
-                VideoData::Segment((path, cid)) => self.media_seg(path, cid).await,
+                VideoData::Segment((path, cid, size)) => self.media_seg(path, cid, size).await,
                 VideoData::Setup((link, len)) => {
                     self.track_len = len;
                     self.setup_link = Some(link);
                 }
+                VideoData::Duration(duration) => {
+                    if let Some(archive_tx) = self.archive_tx.as_ref() {
+                        if let Err(error) = archive_tx.send(Archive::Duration(duration)) {
+                            eprintln!("❗ Archive receiver hung up! Error: {}", error);
+                        }
+                    }
+                }
+                VideoData::Marker(title) => {
+                    if let Some(archive_tx) = self.archive_tx.as_ref() {
+                        if let Err(error) = archive_tx.send(Archive::Marker(title)) {
+                            eprintln!("❗ Archive receiver hung up! Error: {}", error);
+                        }
+                    }
+                }
             }
         }
 
@@ -77,7 +113,7 @@ impl Videograph {
     }
 
     /// Update or create VideoNode in queue then try to mint one.
-    async fn media_seg(&mut self, path: PathBuf, cid: Cid) {
+    async fn media_seg(&mut self, path: PathBuf, cid: Cid, size: u64) {
         let quality = path
             .parent()
             .expect("Orphan path!")
@@ -101,6 +137,8 @@ impl Videograph {
         if let Some(node) = self.segment_nodes.get_mut(buffer_index) {
             node.tracks.insert(quality.to_owned(), cid.into());
 
+            self.segment_sizes[buffer_index] += size;
+
             node.setup = self.setup_link;
 
             // Set previous field only for the next node to be minted
@@ -123,12 +161,13 @@ impl Videograph {
             };
 
             self.segment_nodes.push_back(node);
+            self.segment_sizes.push_back(size);
         }
 
         // try to mint in case something failed previously
-        while let Some(cid) = self.mint_video_node().await {
+        while let Some((cid, size)) = self.mint_video_node().await {
             if let Some(archive_tx) = self.archive_tx.as_ref() {
-                let msg = Archive::Video(cid);
+                let msg = Archive::Video((cid, size));
 
                 if let Err(error) = archive_tx.send(msg) {
                     eprintln!("❗ Archive receiver hung up! Error: {}", error);
@@ -136,10 +175,25 @@ impl Videograph {
             }
 
             if let Some(topic) = self.pubsub_topic.as_ref() {
-                if let Err(e) = self.ipfs.pubsub_pub(topic, cid.to_bytes()).await {
+                let data = match self.segment_key.as_ref() {
+                    Some(key) => match encode_segment(&sign_segment(key, cid)) {
+                        Ok(data) => data,
+                        Err(e) => {
+                            eprintln!("❗ Segment Serialization Failed. {}", e);
+                            continue;
+                        }
+                    },
+                    None => cid.to_bytes(),
+                };
+
+                if let Err(e) = self.ipfs.pubsub_pub(topic, data).await {
                     eprintln!("❗ IPFS: pubsub pub failed {}", e);
                 }
             }
+
+            if let Some(started_tx) = self.started_tx.take() {
+                let _ = started_tx.send(());
+            }
         }
 
         #[cfg(debug_assertions)]
@@ -147,7 +201,9 @@ impl Videograph {
     }
 
     /// Mint the first VideoNode in queue if it meets all requirements.
-    async fn mint_video_node(&mut self) -> Option<Cid> {
+    ///
+    /// Returns the node CID and the size of all its tracks.
+    async fn mint_video_node(&mut self) -> Option<(Cid, u64)> {
         let node = self.segment_nodes.front_mut()?;
 
         node.setup = self.setup_link;
@@ -175,11 +231,12 @@ impl Videograph {
         };
 
         self.segment_nodes.pop_front();
+        let size = self.segment_sizes.pop_front().unwrap_or_default();
         self.node_mint_count += 1;
         self.previous = Some(cid.into());
 
         println!("Video Node Minted => {}", &cid.to_string());
 
-        Some(cid)
+        Some((cid, size))
     }
 }
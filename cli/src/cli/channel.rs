@@ -1,10 +1,28 @@
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+
 use cid::Cid;
 
+use crate::{
+    cli::{
+        site::{export_calendar, export_site, ExportCalendar, ExportSite},
+        user::{invalid_identity, UpdateIdentity},
+    },
+    errors::CliError,
+};
+
 use defluencer::{
-    channel::{local::LocalUpdater, Channel},
-    errors::Error,
+    channel::{local::LocalUpdater, threshold::ThresholdUpdater, Channel, OrphanAction},
+    live::{generate_segment_key, segment_public_key, SigningKey},
+    rate_limit::RateLimiter,
+    timestamp::TimestampPolicy,
+    utils::add_image,
+    Defluencer,
 };
 
+use futures_util::{stream, StreamExt, TryStreamExt};
+
 use heck::ToSnakeCase;
 
 use ipfs_api::{responses::Codec, IpfsService};
@@ -12,7 +30,9 @@ use ipfs_api::{responses::Codec, IpfsService};
 use clap::{Parser, Subcommand};
 
 use linked_data::{
+    channel::theme::{FocalPoint, Layout, LinkButton},
     identity::Identity,
+    media::comments::CommentPermission,
     types::{IPNSAddress, PeerId},
 };
 
@@ -47,23 +67,71 @@ enum Command {
     /// Create a new channel.
     Create,
 
+    /// Update your channel identity, unset fields are kept.
+    Identity(UpdateIdentity),
+
     /// Manage your content.
-    Content(Manage),
+    Content(ManageContent),
 
     /// Manage your comments.
     Comment(Manage),
 
+    /// Manage your comment upvotes.
+    Upvote(ManageUpvotes),
+
+    /// Manage comments pinned on top of your media.
+    Pin(Manage),
+
     /// Manage your followees.
     Follow(Friends),
 
+    /// Curate your content in playlists.
+    Playlist(Playlist),
+
     /// Update your live settings.
     Live(Live),
 
     /// Moderate live chat.
     Moderation(Moderation),
+
+    /// Manage your channel registry listings.
+    Registry(Registry),
+
+    /// Copy the content of another channel into yours.
+    Fork(Fork),
+
+    /// Share ownership of your channel. Updates will require approval from other owners.
+    Owners(Owners),
+
+    /// Move old media from this node to a remote pinning service.
+    Archive(Archive),
+
+    /// Customize the look of your channel page.
+    Theme(Theme),
+
+    /// Generate a static website of your channel for regular web hosts.
+    ExportSite(ExportSite),
+
+    /// Export your channel events as an iCalendar file.
+    ExportCalendar(ExportCalendar),
+
+    /// Snapshot your latest content so web pages load faster.
+    Snapshot(Snapshot),
+
+    /// Designate successors who take over if your channel stays inactive.
+    Succession(Succession),
+
+    /// Find comments on media no longer in your content, move them aside or remove them.
+    Orphans(Orphans),
+
+    /// Choose who can comment on your media.
+    CommentPermission(Permission),
+
+    /// Analyze your channel activity.
+    Analytics(Analytics),
 }
 
-pub async fn channel_cli(cli: ChannelCLI) {
+pub async fn channel_cli(cli: ChannelCLI) -> Result<(), CliError> {
     /* let res = match cli.blockchain {
         Blockchain::Bitcoin => {
             let app = BitcoinLedgerApp::default();
@@ -125,20 +193,38 @@ pub async fn channel_cli(cli: ChannelCLI) {
         }
     }; */
 
-    let res = match cli.cmd {
+    match cli.cmd {
         Command::Create => create_channel(cli.identity).await,
+        Command::Identity(args) => update_identity(cli.identity, args).await,
         Command::Content(args) => match args.cmd {
-            AddRemoveCommand::Add(args) => add_content(cli.identity, args).await,
-            AddRemoveCommand::Remove(args) => remove_content(cli.identity, args).await,
+            ContentCommand::Add(args) => add_content(cli.identity, args).await,
+            ContentCommand::Remove(args) => remove_content(cli.identity, args).await,
+            ContentCommand::List(args) => list_content(cli.identity, args).await,
+            ContentCommand::Permalinks => backfill_permalinks(cli.identity).await,
         },
         Command::Comment(args) => match args.cmd {
             AddRemoveCommand::Add(args) => add_comment(cli.identity, args).await,
             AddRemoveCommand::Remove(args) => remove_comment(cli.identity, args).await,
         },
+        Command::Upvote(args) => match args.cmd {
+            UpvoteCommand::Add(args) => add_upvotes(cli.identity, args).await,
+            UpvoteCommand::Remove(args) => remove_upvote(cli.identity, args).await,
+        },
+        Command::Pin(args) => match args.cmd {
+            AddRemoveCommand::Add(args) => pin_comment(cli.identity, args).await,
+            AddRemoveCommand::Remove(args) => unpin_comment(cli.identity, args).await,
+        },
         Command::Follow(args) => match args.cmd {
             FollowCommand::Add(args) => add_followee(cli.identity, args).await,
             FollowCommand::Remove(args) => remove_followee(cli.identity, args).await,
         },
+        Command::Playlist(args) => match args.cmd {
+            PlaylistCommand::Create(args) => create_playlist(cli.identity, args).await,
+            PlaylistCommand::Delete(args) => remove_playlist(cli.identity, args).await,
+            PlaylistCommand::Add(args) => add_to_playlist(cli.identity, args).await,
+            PlaylistCommand::Remove(args) => remove_from_playlist(cli.identity, args).await,
+            PlaylistCommand::Move(args) => reorder_playlist(cli.identity, args).await,
+        },
         Command::Live(args) => update_live(cli.identity, args).await,
         Command::Moderation(args) => match args.cmd {
             ModerationCommand::Ban(args) => ban_user(cli.identity, args).await,
@@ -146,14 +232,32 @@ pub async fn channel_cli(cli: ChannelCLI) {
             ModerationCommand::Mod(args) => mod_user(cli.identity, args).await,
             ModerationCommand::Unmod(args) => unmod_user(cli.identity, args).await,
         },
-    };
-
-    if let Err(e) = res {
-        eprintln!("❗ IPFS: {:#?}", e);
+        Command::Registry(args) => match args.cmd {
+            RegistryCommand::Add(args) => add_listing(cli.identity, args).await,
+            RegistryCommand::Remove(args) => remove_listing(cli.identity, args).await,
+            RegistryCommand::Purge(args) => purge_topic(cli.identity, args).await,
+        },
+        Command::Fork(args) => fork_channel(cli.identity, args).await,
+        Command::Owners(args) => set_owners(cli.identity, args).await,
+        Command::Archive(args) => archive_media(cli.identity, args).await,
+        Command::Theme(args) => update_theme(cli.identity, args).await,
+        Command::ExportSite(args) => export_site(cli.identity, args).await,
+        Command::ExportCalendar(args) => export_calendar(cli.identity, args).await,
+        Command::Snapshot(args) => update_snapshot(cli.identity, args).await,
+        Command::Succession(args) => match args.cmd {
+            SuccessionCommand::Set(args) => set_succession(cli.identity, args).await,
+            SuccessionCommand::Remove => remove_succession(cli.identity).await,
+            SuccessionCommand::Heartbeat => heartbeat(cli.identity).await,
+        },
+        Command::Orphans(args) => collect_orphans(cli.identity, args).await,
+        Command::CommentPermission(args) => set_comment_permission(cli.identity, args).await,
+        Command::Analytics(args) => match args.cmd {
+            AnalyticsCommand::Export(args) => export_analytics(cli.identity, args).await,
+        },
     }
 }
 
-async fn create_channel(identity: Cid) -> Result<(), Error> {
+async fn create_channel(identity: Cid) -> Result<(), CliError> {
     let ipfs = IpfsService::default();
 
     println!("Wait For Your Channel To Be Created...");
@@ -177,48 +281,116 @@ pub struct Manage {
 
 #[derive(Debug, Parser)]
 enum AddRemoveCommand {
-    /// Add content/comment to your channel.
+    /// Add content/comment/upvote to your channel.
     Add(Content),
 
-    /// Remove content/comment from your channel.
+    /// Remove content/comment/upvote from your channel.
     Remove(Content),
 }
 
 #[derive(Debug, Parser)]
 pub struct Content {
-    /// The CID of the content/comment.
+    /// The CID of the content/comment/upvote.
     #[arg(long)]
     cid: Cid,
 }
 
-async fn local_setup(identity: Cid) -> Result<Channel<LocalUpdater>, Error> {
+#[derive(Debug, Parser)]
+pub struct ManageContent {
+    #[command(subcommand)]
+    cmd: ContentCommand,
+}
+
+#[derive(Debug, Parser)]
+enum ContentCommand {
+    /// Add content to your channel.
+    Add(Content),
+
+    /// Remove content from your channel.
+    Remove(Content),
+
+    /// List your content, newest first.
+    List(ListContent),
+
+    /// Give permalinks to content added before them.
+    Permalinks,
+}
+
+#[derive(Debug, Parser)]
+pub struct ListContent {
+    /// Only list content with this tag, in no particular order. (Optional)
+    #[arg(long)]
+    tag: Option<String>,
+}
+
+pub async fn local_setup(
+    identity: Cid,
+) -> Result<Channel<ThresholdUpdater<LocalUpdater>>, CliError> {
     let ipfs = IpfsService::default();
 
     let identity = ipfs
         .dag_get::<String, Identity>(identity, None, Codec::default())
         .await?;
-    let addr = identity.ipns_addr.expect("IPNS Address");
+    let addr = match identity.ipns_addr {
+        Some(addr) => addr,
+        None => {
+            return Err(CliError::NotFound(format!(
+                "Identity {} has no IPNS address",
+                identity.name
+            )))
+        }
+    };
     let key = identity.name.to_snake_case();
 
     let updater = LocalUpdater::new(ipfs.clone(), key);
+    let updater = ThresholdUpdater::new(ipfs.clone(), addr, updater);
     let channel = Channel::new(ipfs, addr, updater);
 
     Ok(channel)
 }
 
-async fn add_content(identity: Cid, args: Content) -> Result<(), Error> {
+async fn update_identity(identity: Cid, args: UpdateIdentity) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let patch = args.into_patch(&ipfs).await?;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Update Its Identity...");
+
+    let cid = match channel.update_identity(patch).await {
+        Ok(cid) => cid,
+        Err(e @ defluencer::errors::Error::Identity) => return Err(invalid_identity(e)),
+        Err(e) => return Err(e.into()),
+    };
+
+    println!("✅ Updated Identity\nCID: {}", cid);
+
+    Ok(())
+}
+
+async fn add_content(identity: Cid, args: Content) -> Result<(), CliError> {
     let channel = local_setup(identity).await?;
 
     println!("Wait For Your Channel To Add Content...");
 
-    channel.add_content(args.cid).await?;
+    let checked = channel
+        .add_content_checked(args.cid, &TimestampPolicy::default())
+        .await?;
+
+    if checked.ancient {
+        eprintln!(
+            "❗ Content Dated {}, Check The Signer Clock",
+            checked.date_time.format("%Y-%m-%d")
+        );
+    }
 
     println!("✅ Added Content {}", args.cid);
 
     Ok(())
 }
 
-async fn remove_content(identity: Cid, args: Content) -> Result<(), Error> {
+async fn remove_content(identity: Cid, args: Content) -> Result<(), CliError> {
     let channel = local_setup(identity).await?;
 
     println!("Wait For Your Channel To Remove Content...");
@@ -230,7 +402,47 @@ async fn remove_content(identity: Cid, args: Content) -> Result<(), Error> {
     Ok(())
 }
 
-async fn add_comment(identity: Cid, args: Content) -> Result<(), Error> {
+async fn list_content(identity: Cid, args: ListContent) -> Result<(), CliError> {
+    let channel = local_setup(identity).await?;
+    let defluencer = Defluencer::default();
+
+    let (_, metadata) = channel.get_metadata().await?;
+
+    let mut stream = match (args.tag, metadata.content_index, metadata.tag_index) {
+        (Some(tag), _, Some(index)) => defluencer.stream_content_by_tag(index, &tag).boxed_local(),
+        (None, Some(index), _) => defluencer.stream_content_rev_chrono(index).boxed_local(),
+        _ => stream::empty().boxed_local(),
+    };
+
+    let mut count = 0;
+
+    while let Some(cid) = stream.try_next().await? {
+        println!("{}", cid);
+
+        count += 1;
+    }
+
+    if count == 0 {
+        eprintln!("❗ No Content Found");
+    }
+
+    Ok(())
+}
+
+async fn backfill_permalinks(identity: Cid) -> Result<(), CliError> {
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Content To Get Permalinks...");
+
+    match channel.backfill_slugs().await? {
+        Some(cid) => println!("✅ Added Permalinks\nCID: {}", cid),
+        None => println!("❗ All Content Already Has Permalinks"),
+    }
+
+    Ok(())
+}
+
+async fn add_comment(identity: Cid, args: Content) -> Result<(), CliError> {
     let channel = local_setup(identity).await?;
 
     println!("Wait For Your Channel To Add Comment...");
@@ -242,7 +454,7 @@ async fn add_comment(identity: Cid, args: Content) -> Result<(), Error> {
     Ok(())
 }
 
-async fn remove_comment(identity: Cid, args: Content) -> Result<(), Error> {
+async fn remove_comment(identity: Cid, args: Content) -> Result<(), CliError> {
     let channel = local_setup(identity).await?;
 
     println!("Wait For Your Channel To Remove Comment.");
@@ -254,6 +466,101 @@ async fn remove_comment(identity: Cid, args: Content) -> Result<(), Error> {
     Ok(())
 }
 
+async fn pin_comment(identity: Cid, args: Content) -> Result<(), CliError> {
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Pin Comment...");
+
+    channel.pin_comment(args.cid).await?;
+
+    println!("✅ Pinned Comment {}", args.cid);
+
+    Ok(())
+}
+
+async fn unpin_comment(identity: Cid, args: Content) -> Result<(), CliError> {
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Unpin Comment...");
+
+    channel.unpin_comment(args.cid).await?;
+
+    println!("✅ Unpinned Comment {}", args.cid);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct ManageUpvotes {
+    #[command(subcommand)]
+    cmd: UpvoteCommand,
+}
+
+#[derive(Debug, Parser)]
+enum UpvoteCommand {
+    /// Add signed upvotes to your channel.
+    Add(AddUpvotes),
+
+    /// Remove an upvote from your channel.
+    Remove(Content),
+}
+
+#[derive(Debug, Parser)]
+pub struct AddUpvotes {
+    /// The CID of the signed upvote, repeat for more.
+    #[arg(long, required = true)]
+    cid: Vec<Cid>,
+
+    /// Maximum upvotes per voter during the rate limit window.
+    #[arg(long, default_value_t = 5)]
+    max_upvotes: usize,
+
+    /// Rate limit window in seconds.
+    #[arg(long, default_value_t = 600)]
+    window: i64,
+}
+
+async fn add_upvotes(identity: Cid, args: AddUpvotes) -> Result<(), CliError> {
+    let AddUpvotes {
+        cid: cids,
+        max_upvotes,
+        window,
+    } = args;
+
+    let channel = local_setup(identity).await?;
+
+    let limiter = RateLimiter::new(max_upvotes, window);
+
+    println!("Wait For Your Channel To Add Upvotes...");
+
+    for cid in cids {
+        match channel.add_upvote(cid, Some(&limiter)).await {
+            Ok(_) => println!("✅ Added Upvote {}", cid),
+            Err(defluencer::errors::Error::RateLimited) => {
+                println!("❗ Rate Limited Upvote {}", cid)
+            }
+            Err(defluencer::errors::Error::AlreadyAdded) => {
+                println!("❗ Duplicate Upvote {}", cid)
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(())
+}
+
+async fn remove_upvote(identity: Cid, args: Content) -> Result<(), CliError> {
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Remove Upvote...");
+
+    channel.remove_upvote(args.cid).await?;
+
+    println!("✅ Removed Upvote {}", args.cid);
+
+    Ok(())
+}
+
 #[derive(Debug, Parser)]
 pub struct Friends {
     #[command(subcommand)]
@@ -274,26 +581,36 @@ pub struct Followee {
     /// Followee's channel address.
     #[arg(long)]
     address: IPNSAddress,
+
+    /// Name of the list of followees. eg. friends, news, music (Optional)
+    #[arg(long)]
+    list: Option<String>,
 }
 
-async fn add_followee(identity: Cid, args: Followee) -> Result<(), Error> {
+async fn add_followee(identity: Cid, args: Followee) -> Result<(), CliError> {
     let channel = local_setup(identity).await?;
 
     println!("Wait For Your Channel To Add Followee...");
 
-    channel.follow(args.address).await?;
+    match args.list {
+        Some(name) => channel.follow_in_list(name, args.address).await?,
+        None => channel.follow(args.address).await?,
+    };
 
     println!("✅ Added Followee {}", args.address);
 
     Ok(())
 }
 
-async fn remove_followee(identity: Cid, args: Followee) -> Result<(), Error> {
+async fn remove_followee(identity: Cid, args: Followee) -> Result<(), CliError> {
     let channel = local_setup(identity).await?;
 
     println!("Wait For Your Channel To Remove Followee...");
 
-    channel.unfollow(args.address).await?;
+    match args.list {
+        Some(name) => channel.unfollow_in_list(name, args.address).await?,
+        None => channel.unfollow(args.address).await?,
+    };
 
     println!("✅ Removed Followee {}", args.address);
 
@@ -301,151 +618,891 @@ async fn remove_followee(identity: Cid, args: Followee) -> Result<(), Error> {
 }
 
 #[derive(Debug, Parser)]
-pub struct Live {
-    /// Peer Id of the node live streaming.
+pub struct Playlist {
+    #[command(subcommand)]
+    cmd: PlaylistCommand,
+}
+
+#[derive(Debug, Parser)]
+enum PlaylistCommand {
+    /// Create a new empty playlist.
+    Create(NewPlaylist),
+
+    /// Delete a playlist, its content stays on your channel.
+    Delete(PlaylistName),
+
+    /// Add content at the end of a playlist.
+    Add(PlaylistEntry),
+
+    /// Remove content from a playlist.
+    Remove(PlaylistEntry),
+
+    /// Move content of a playlist to another position.
+    Move(PlaylistMove),
+}
+
+#[derive(Debug, Parser)]
+pub struct NewPlaylist {
+    /// Name of the playlist.
     #[arg(long)]
-    peer_id: Option<PeerId>,
+    name: String,
 
-    /// PubSub Topic for live video.
+    /// Short description of the playlist. (Optional)
     #[arg(long)]
-    video_topic: Option<String>,
+    description: Option<String>,
 
-    /// PubSub Topic for live chat.
+    /// Path to thumbnail image file. (Optional)
     #[arg(long)]
-    chat_topic: Option<String>,
+    image: Option<PathBuf>,
+}
 
-    /// Should live chat be archived.
+#[derive(Debug, Parser)]
+pub struct PlaylistName {
+    /// Name of the playlist.
     #[arg(long)]
-    archiving: Option<bool>,
+    name: String,
 }
 
-async fn update_live(identity: Cid, args: Live) -> Result<(), Error> {
-    let Live {
-        peer_id,
-        video_topic,
-        chat_topic,
-        archiving,
+#[derive(Debug, Parser)]
+pub struct PlaylistEntry {
+    /// Name of the playlist.
+    #[arg(long)]
+    name: String,
+
+    /// The CID of the content.
+    #[arg(long)]
+    cid: Cid,
+}
+
+#[derive(Debug, Parser)]
+pub struct PlaylistMove {
+    /// Name of the playlist.
+    #[arg(long)]
+    name: String,
+
+    /// The CID of the content.
+    #[arg(long)]
+    cid: Cid,
+
+    /// New position, starting at 0. Out of bounds moves to the end.
+    #[arg(long)]
+    position: usize,
+}
+
+async fn create_playlist(identity: Cid, args: NewPlaylist) -> Result<(), CliError> {
+    let NewPlaylist {
+        name,
+        description,
+        image,
     } = args;
 
+    let ipfs = IpfsService::default();
+
+    let image = match image {
+        Some(path) => Some(add_image(&ipfs, path).await?.into()),
+        None => None,
+    };
+
     let channel = local_setup(identity).await?;
 
-    println!("Wait For Your Channel To Update Live Settings...");
+    println!("Wait For Your Channel To Create Playlist...");
 
     let cid = channel
-        .update_live_settings(peer_id, video_topic, chat_topic, archiving)
+        .create_playlist(name.clone(), description, image)
         .await?;
 
-    println!("✅ Updated Live Settings {}", cid);
+    println!("✅ Created Playlist {}\nCID: {}", name, cid);
 
     Ok(())
 }
 
-#[derive(Debug, Parser)]
-struct Moderation {
-    #[command(subcommand)]
-    cmd: ModerationCommand,
-}
-
-#[derive(Debug, Parser)]
-enum ModerationCommand {
-    /// Ban users.
-    Ban(EthAddress),
+async fn remove_playlist(identity: Cid, args: PlaylistName) -> Result<(), CliError> {
+    let channel = local_setup(identity).await?;
 
-    /// Unban users.
-    Unban(EthAddress),
+    println!("Wait For Your Channel To Delete Playlist...");
 
-    /// Promote user to moderator position.
-    Mod(EthAddress),
+    channel.remove_playlist(args.name.clone()).await?;
 
-    /// Demote user from moderator position.
-    Unmod(EthAddress),
-}
+    println!("✅ Deleted Playlist {}", args.name);
 
-#[derive(Debug, Parser)]
-pub struct EthAddress {
-    /// Ethereum address.
-    #[arg(long)]
-    address: String,
+    Ok(())
 }
 
-async fn ban_user(identity: Cid, args: EthAddress) -> Result<(), Error> {
-    let address = parse_address(&args.address);
-
+async fn add_to_playlist(identity: Cid, args: PlaylistEntry) -> Result<(), CliError> {
     let channel = local_setup(identity).await?;
 
-    println!("Wait For Your Channel To Ban A User...");
-
-    if channel.ban_user(address).await?.is_some() {
-        println!("✅ User {} Banned", args.address);
+    println!("Wait For Your Channel To Update Playlist...");
 
-        return Ok(());
-    }
+    channel.add_to_playlist(args.name.clone(), args.cid).await?;
 
-    println!("❗ User {} was already banned", args.address);
+    println!("✅ Added {} To Playlist {}", args.cid, args.name);
 
     Ok(())
 }
 
-async fn unban_user(identity: Cid, args: EthAddress) -> Result<(), Error> {
-    let address = parse_address(&args.address);
-
+async fn remove_from_playlist(identity: Cid, args: PlaylistEntry) -> Result<(), CliError> {
     let channel = local_setup(identity).await?;
 
-    println!("Wait For Your Channel To Unban A User.");
-
-    if channel.unban_user(&address).await?.is_some() {
-        println!("✅ User {} Unbanned", args.address);
+    println!("Wait For Your Channel To Update Playlist...");
 
-        return Ok(());
-    }
+    channel
+        .remove_from_playlist(args.name.clone(), args.cid)
+        .await?;
 
-    println!("❗ User {} was not banned", args.address);
+    println!("✅ Removed {} From Playlist {}", args.cid, args.name);
 
     Ok(())
 }
 
-async fn mod_user(identity: Cid, args: EthAddress) -> Result<(), Error> {
-    let address = parse_address(&args.address);
+async fn reorder_playlist(identity: Cid, args: PlaylistMove) -> Result<(), CliError> {
+    let PlaylistMove {
+        name,
+        cid,
+        position,
+    } = args;
 
     let channel = local_setup(identity).await?;
 
-    println!("Wait For Your Channel To Add A Moderator.");
-
-    if channel.add_moderator(address).await?.is_some() {
-        println!("✅ User {} Promoted To Moderator", args.address);
+    println!("Wait For Your Channel To Update Playlist...");
 
-        return Ok(());
-    }
+    channel
+        .reorder_playlist(name.clone(), cid, position)
+        .await?;
 
-    println!("❗ User {} was already banned", args.address);
+    println!("✅ Moved {} In Playlist {}", cid, name);
 
     Ok(())
 }
 
-async fn unmod_user(identity: Cid, args: EthAddress) -> Result<(), Error> {
-    let address = parse_address(&args.address);
+#[derive(Debug, Parser)]
+pub struct Fork {
+    /// Address of the channel to copy.
+    #[arg(long)]
+    address: IPNSAddress,
+}
 
+async fn fork_channel(identity: Cid, args: Fork) -> Result<(), CliError> {
     let channel = local_setup(identity).await?;
 
-    println!("Wait For Your Channel To Remove A Moderator.");
-
-    if channel.remove_moderator(&address).await?.is_some() {
-        println!("✅ Moderator {} Demoted", args.address);
+    println!("Wait For Your Channel To Fork...");
 
-        return Ok(());
-    }
+    let cid = channel.fork(args.address).await?;
 
-    println!("❗ User {} Was Not A Moderator", args.address);
+    println!("✅ Forked Channel {}\nCID: {}", args.address, cid);
 
     Ok(())
 }
 
-fn parse_address(addrs: &str) -> [u8; 20] {
-    use hex::FromHex;
+#[derive(Debug, Parser)]
+pub struct Live {
+    /// Peer Id of the node live streaming.
+    #[arg(long)]
+    peer_id: Option<PeerId>,
 
-    if let Some(end) = addrs.strip_prefix("0x") {
-        return <[u8; 20]>::from_hex(end).expect("Invalid Ethereum Address");
+    /// PubSub Topic for live video.
+    #[arg(long)]
+    video_topic: Option<String>,
+
+    /// PubSub Topic for live chat.
+    #[arg(long)]
+    chat_topic: Option<String>,
+
+    /// Should live chat be archived.
+    #[arg(long)]
+    archiving: Option<bool>,
+
+    /// Key file signing live segments, created if missing. (Optional)
+    ///
+    /// Pass the same file to the stream daemon.
+    #[arg(long)]
+    segment_key: Option<PathBuf>,
+
+    /// Stop requiring signed live segments.
+    #[arg(long, conflicts_with = "segment_key")]
+    unsigned: bool,
+
+    /// Hub channel address notified when a stream starts, repeat for more. (Optional)
+    #[arg(long)]
+    announce_hub: Vec<IPNSAddress>,
+
+    /// Hub channel address no longer notified, repeat for more. (Optional)
+    #[arg(long)]
+    mute_hub: Vec<IPNSAddress>,
+}
+
+async fn update_live(identity: Cid, args: Live) -> Result<(), CliError> {
+    let Live {
+        peer_id,
+        video_topic,
+        chat_topic,
+        archiving,
+        segment_key,
+        unsigned,
+        announce_hub,
+        mute_hub,
+    } = args;
+
+    let public_key = match segment_key {
+        Some(path) => {
+            let key = load_or_create_segment_key(&path).await?;
+
+            Some(segment_public_key(&key))
+        }
+        None => None,
+    };
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Update Live Settings...");
+
+    let mut cid = channel
+        .update_live_settings(peer_id, video_topic, chat_topic, archiving)
+        .await?;
+
+    if public_key.is_some() || unsigned {
+        cid = channel.set_segment_key(public_key).await?;
     }
 
-    <[u8; 20]>::from_hex(&addrs).expect("Invalid Ethereum Address")
+    let hubs: Vec<(IPNSAddress, bool)> = announce_hub
+        .into_iter()
+        .map(|hub| (hub, true))
+        .chain(mute_hub.into_iter().map(|hub| (hub, false)))
+        .collect();
+
+    if !hubs.is_empty() {
+        cid = channel.set_hub_announcements(&hubs).await?;
+    }
+
+    println!("✅ Updated Live Settings {}", cid);
+
+    Ok(())
+}
+
+async fn load_or_create_segment_key(path: &Path) -> Result<SigningKey, CliError> {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => SigningKey::from_slice(&bytes).map_err(|_| {
+            CliError::InvalidInput(format!("Invalid segment key file {}", path.display()))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let key = generate_segment_key();
+
+            tokio::fs::write(path, key.to_bytes())
+                .await
+                .map_err(defluencer::errors::Error::from)?;
+
+            println!("✅ Created Segment Key\nPath: {}", path.display());
+
+            Ok(key)
+        }
+        Err(e) => Err(defluencer::errors::Error::from(e).into()),
+    }
+}
+
+#[derive(Debug, Parser)]
+struct Moderation {
+    #[command(subcommand)]
+    cmd: ModerationCommand,
+}
+
+#[derive(Debug, Parser)]
+enum ModerationCommand {
+    /// Ban users.
+    Ban(EthAddress),
+
+    /// Unban users.
+    Unban(EthAddress),
+
+    /// Promote user to moderator position.
+    Mod(EthAddress),
+
+    /// Demote user from moderator position.
+    Unmod(EthAddress),
+}
+
+#[derive(Debug, Parser)]
+pub struct EthAddress {
+    /// Ethereum address.
+    #[arg(long)]
+    address: String,
+}
+
+async fn ban_user(identity: Cid, args: EthAddress) -> Result<(), CliError> {
+    let address = parse_address(&args.address)?;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Ban A User...");
+
+    if channel.ban_user(address).await?.is_some() {
+        println!("✅ User {} Banned", args.address);
+
+        return Ok(());
+    }
+
+    println!("❗ User {} was already banned", args.address);
+
+    Ok(())
+}
+
+async fn unban_user(identity: Cid, args: EthAddress) -> Result<(), CliError> {
+    let address = parse_address(&args.address)?;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Unban A User.");
+
+    if channel.unban_user(&address).await?.is_some() {
+        println!("✅ User {} Unbanned", args.address);
+
+        return Ok(());
+    }
+
+    println!("❗ User {} was not banned", args.address);
+
+    Ok(())
+}
+
+async fn mod_user(identity: Cid, args: EthAddress) -> Result<(), CliError> {
+    let address = parse_address(&args.address)?;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Add A Moderator.");
+
+    if channel.add_moderator(address).await?.is_some() {
+        println!("✅ User {} Promoted To Moderator", args.address);
+
+        return Ok(());
+    }
+
+    println!("❗ User {} was already banned", args.address);
+
+    Ok(())
+}
+
+async fn unmod_user(identity: Cid, args: EthAddress) -> Result<(), CliError> {
+    let address = parse_address(&args.address)?;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Remove A Moderator.");
+
+    if channel.remove_moderator(&address).await?.is_some() {
+        println!("✅ Moderator {} Demoted", args.address);
+
+        return Ok(());
+    }
+
+    println!("❗ User {} Was Not A Moderator", args.address);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct Registry {
+    #[command(subcommand)]
+    cmd: RegistryCommand,
+}
+
+#[derive(Debug, Parser)]
+enum RegistryCommand {
+    /// Add a signed listing to your registry.
+    Add(Content),
+
+    /// Remove a channel from your registry.
+    Remove(Unlist),
+
+    /// Remove every channel listed under a topic from your registry.
+    Purge(Purge),
+}
+
+#[derive(Debug, Parser)]
+pub struct Unlist {
+    /// Address of the listed channel.
+    #[arg(long)]
+    address: IPNSAddress,
+
+    /// Topics to remove the channel from.
+    #[arg(long, required = true)]
+    topic: Vec<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct Purge {
+    /// Topic to remove.
+    #[arg(long)]
+    topic: String,
+}
+
+async fn add_listing(identity: Cid, args: Content) -> Result<(), CliError> {
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Add Listing...");
+
+    let cid = channel.add_listing(args.cid).await?;
+
+    println!("✅ Added Listing {}\nRegistry: {}", args.cid, cid);
+
+    Ok(())
+}
+
+async fn remove_listing(identity: Cid, args: Unlist) -> Result<(), CliError> {
+    let Unlist { address, topic } = args;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Remove Listing...");
+
+    if channel.remove_listing(address, topic).await?.is_some() {
+        println!("✅ Removed Listing {}", address);
+
+        return Ok(());
+    }
+
+    println!("❗ This channel has no registry");
+
+    Ok(())
+}
+
+async fn purge_topic(identity: Cid, args: Purge) -> Result<(), CliError> {
+    let Purge { topic } = args;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Remove Topic...");
+
+    if let Some(cid) = channel.remove_topic(&topic).await? {
+        println!("✅ Removed Topic {}\nRegistry: {}", topic, cid);
+
+        return Ok(());
+    }
+
+    println!("❗ This channel has no registry");
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Owners {
+    /// Number of owners required to approve an update.
+    #[arg(long)]
+    threshold: usize,
+
+    /// Public key of each owner, hex encoded SEC1.
+    #[arg(long, required = true)]
+    key: Vec<String>,
+}
+
+async fn set_owners(identity: Cid, args: Owners) -> Result<(), CliError> {
+    let Owners { threshold, key } = args;
+
+    let keys = key
+        .iter()
+        .map(|key| {
+            hex::decode(key.strip_prefix("0x").unwrap_or(key))
+                .map_err(|e| CliError::InvalidInput(format!("public key {}: {}", key, e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Owners To Approve...");
+
+    let cid = channel.set_owners(threshold, keys).await?;
+
+    println!("✅ Channel Co-Owned By {} Keys\nCID: {}", key.len(), cid);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Succession {
+    #[command(subcommand)]
+    cmd: SuccessionCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum SuccessionCommand {
+    /// Designate successor keys.
+    Set(SetSuccession),
+
+    /// Remove all successors.
+    Remove,
+
+    /// Update your channel without changes to show you are still active.
+    Heartbeat,
+}
+
+#[derive(Debug, Parser)]
+pub struct SetSuccession {
+    /// Number of days without channel updates before successors take over.
+    #[arg(long)]
+    days: u32,
+
+    /// Public key of each successor, hex encoded SEC1.
+    #[arg(long, required = true)]
+    key: Vec<String>,
+}
+
+async fn set_succession(identity: Cid, args: SetSuccession) -> Result<(), CliError> {
+    let SetSuccession { days, key } = args;
+
+    let keys = key
+        .iter()
+        .map(|key| {
+            hex::decode(key.strip_prefix("0x").unwrap_or(key))
+                .map_err(|e| CliError::InvalidInput(format!("public key {}: {}", key, e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Be Updated...");
+
+    let inactivity = days as i64 * 24 * 3600;
+
+    let cid = channel.set_succession(keys, inactivity).await?;
+
+    println!(
+        "✅ {} Successors Take Over After {} Days Of Inactivity\nCID: {}",
+        key.len(),
+        days,
+        cid
+    );
+
+    Ok(())
+}
+
+async fn remove_succession(identity: Cid) -> Result<(), CliError> {
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Be Updated...");
+
+    if channel.remove_succession().await?.is_some() {
+        println!("✅ Successors Removed");
+
+        return Ok(());
+    }
+
+    println!("❗ Channel has no successors");
+
+    Ok(())
+}
+
+async fn heartbeat(identity: Cid) -> Result<(), CliError> {
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Be Updated...");
+
+    match channel.heartbeat().await? {
+        Some(cid) => println!("✅ Heartbeat Published\nCID: {}", cid),
+        None => println!("❗ Channel has no successors"),
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Archive {
+    /// Archive media older than this many months.
+    #[arg(long)]
+    months: u32,
+
+    /// Name of the remote pinning service configured on your IPFS node.
+    #[arg(long)]
+    service: String,
+}
+
+async fn archive_media(identity: Cid, args: Archive) -> Result<(), CliError> {
+    let Archive { months, service } = args;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Archive Old Media...");
+
+    let archived = channel.archive_media(months, service.clone()).await?;
+
+    for cid in archived.iter() {
+        println!("Archived {}", cid);
+    }
+
+    println!("✅ {} Media Moved To {}", archived.len(), service);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Orphans {
+    /// Remove orphaned comments instead of moving them to the orphan index.
+    ///
+    /// Moved comments are restored if their media is added back.
+    #[arg(long)]
+    remove: bool,
+}
+
+async fn collect_orphans(identity: Cid, args: Orphans) -> Result<(), CliError> {
+    let action = match args.remove {
+        true => OrphanAction::Remove,
+        false => OrphanAction::Move,
+    };
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Collect Orphaned Comments...");
+
+    let missing = channel.collect_orphan_comments(action).await?;
+
+    for cid in missing.iter() {
+        println!("Missing Media {}", cid);
+    }
+
+    match action {
+        OrphanAction::Remove => println!("✅ Removed Comments Of {} Media", missing.len()),
+        OrphanAction::Move => println!("✅ Moved Comments Of {} Media", missing.len()),
+    }
+
+    Ok(())
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum PermissionLevel {
+    Everyone,
+    Followers,
+    Vouched,
+    Closed,
+}
+
+impl From<PermissionLevel> for CommentPermission {
+    fn from(level: PermissionLevel) -> Self {
+        match level {
+            PermissionLevel::Everyone => CommentPermission::Everyone,
+            PermissionLevel::Followers => CommentPermission::Followers,
+            PermissionLevel::Vouched => CommentPermission::Vouched,
+            PermissionLevel::Closed => CommentPermission::Closed,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct Permission {
+    /// Who can comment. Vouched users are those of the channels you follow.
+    #[arg(long, value_enum)]
+    level: PermissionLevel,
+
+    /// Media CID. All media without their own permission if absent. (Optional)
+    #[arg(long)]
+    media: Option<Cid>,
+}
+
+async fn set_comment_permission(identity: Cid, args: Permission) -> Result<(), CliError> {
+    let Permission { level, media } = args;
+
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Update Comment Permissions...");
+
+    let cid = channel.set_comment_permission(media, level.into()).await?;
+
+    match media {
+        Some(media) => println!("✅ Comments On {} Set To {:?}\nCID: {}", media, level, cid),
+        None => println!("✅ Comments Set To {:?}\nCID: {}", level, cid),
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Snapshot {
+    /// Number of latest content included.
+    #[arg(long, default_value = "10")]
+    count: usize,
+}
+
+async fn update_snapshot(identity: Cid, args: Snapshot) -> Result<(), CliError> {
+    let channel = local_setup(identity).await?;
+
+    println!("Wait For Your Channel To Be Snapshotted...");
+
+    let cid = channel.update_snapshot(args.count).await?;
+
+    println!("✅ Updated Snapshot\nCID: {}", cid);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Theme {
+    /// Accent color in hex RGB, most important first. eg. "#1e90ff"
+    #[arg(long)]
+    accent_color: Vec<String>,
+
+    /// Layout of the content list. grid, list or featured
+    #[arg(long)]
+    layout: Option<String>,
+
+    /// Point of your banner to keep in view, from 0.0 to 1.0. eg. "0.5,0.25"
+    #[arg(long)]
+    focal_point: Option<String>,
+
+    /// Link button, label and URL separated by "=". eg. "Blog=https://example.com"
+    #[arg(long)]
+    link: Vec<String>,
+
+    /// Remove the theme instead.
+    #[arg(long)]
+    clear: bool,
+}
+
+async fn update_theme(identity: Cid, args: Theme) -> Result<(), CliError> {
+    let Theme {
+        accent_color,
+        layout,
+        focal_point,
+        link,
+        clear,
+    } = args;
+
+    let layout = match layout.as_deref() {
+        Some("grid") => Some(Layout::Grid),
+        Some("list") => Some(Layout::List),
+        Some("featured") => Some(Layout::Featured),
+        Some(other) => return Err(CliError::InvalidInput(format!("layout {}", other))),
+        None => None,
+    };
+
+    let focal_point = match focal_point {
+        Some(point) => {
+            let parsed = point
+                .split_once(',')
+                .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)));
+
+            match parsed {
+                Some((x, y)) => Some(FocalPoint { x, y }),
+                None => return Err(CliError::InvalidInput(format!("focal point {}", point))),
+            }
+        }
+        None => None,
+    };
+
+    let links = link
+        .iter()
+        .map(|link| match link.split_once('=') {
+            Some((label, url)) => Ok(LinkButton {
+                label: label.to_owned(),
+                url: url.to_owned(),
+            }),
+            None => Err(CliError::InvalidInput(format!("link {}", link))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let channel = local_setup(identity).await?;
+
+    if clear {
+        println!("Wait For Your Channel To Remove Theme...");
+
+        match channel.remove_theme().await? {
+            Some(cid) => println!("✅ Removed Theme\nCID: {}", cid),
+            None => println!("❗ This channel has no theme"),
+        }
+
+        return Ok(());
+    }
+
+    println!("Wait For Your Channel To Update Theme...");
+
+    let accent_colors = (!accent_color.is_empty()).then_some(accent_color);
+    let links = (!links.is_empty()).then_some(links);
+
+    let cid = channel
+        .update_theme(accent_colors, layout, focal_point, links)
+        .await?;
+
+    println!("✅ Updated Theme {}", cid);
+
+    Ok(())
+}
+
+fn parse_address(addrs: &str) -> Result<[u8; 20], CliError> {
+    use hex::FromHex;
+
+    let hex = addrs.strip_prefix("0x").unwrap_or(addrs);
+
+    <[u8; 20]>::from_hex(hex)
+        .map_err(|e| CliError::InvalidInput(format!("Ethereum address {}: {}", addrs, e)))
+}
+
+#[derive(Debug, Parser)]
+pub struct Analytics {
+    #[command(subcommand)]
+    cmd: AnalyticsCommand,
+}
+
+#[derive(Debug, Parser)]
+enum AnalyticsCommand {
+    /// Export a daily time series of publications, comments, followers and live stream durations.
+    Export(ExportAnalytics),
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum AnalyticsFormat {
+    Ndjson,
+    Csv,
+}
+
+#[derive(Debug, Parser)]
+pub struct ExportAnalytics {
+    /// Output format.
+    #[arg(long, value_enum, default_value = "ndjson")]
+    format: AnalyticsFormat,
+
+    /// File path, printed if absent.
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Crawl snapshot address, today's followers are counted from it. (Optional)
+    #[arg(long)]
+    snapshot: Option<IPNSAddress>,
+}
+
+async fn export_analytics(identity: Cid, args: ExportAnalytics) -> Result<(), CliError> {
+    let ExportAnalytics {
+        format,
+        output,
+        snapshot,
+    } = args;
+
+    let channel = local_setup(identity).await?;
+    let defluencer = Defluencer::default();
+
+    eprintln!("Wait For Your Channel Content & Comments To Be Fetched...");
+
+    let (_, metadata) = channel.get_metadata().await?;
+
+    let mut analytics = defluencer.channel_analytics(&metadata).await?;
+
+    if let Some(snapshot) = snapshot {
+        let followers = defluencer
+            .crawled_followers(snapshot, channel.get_address())
+            .await?;
+
+        analytics.set_followers(Utc::now().timestamp(), followers);
+    }
+
+    let data = match format {
+        AnalyticsFormat::Ndjson => analytics.to_ndjson()?,
+        AnalyticsFormat::Csv => analytics.to_csv(),
+    };
+
+    match output {
+        Some(path) => {
+            tokio::fs::write(&path, data)
+                .await
+                .map_err(defluencer::errors::Error::from)?;
+
+            println!("✅ Analytics Written To {}", path.display());
+        }
+        None => print!("{}", data),
+    }
+
+    Ok(())
 }
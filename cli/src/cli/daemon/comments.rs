@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use crate::{
+    cli::{channel::local_setup, denylist::DenylistArgs},
+    errors::CliError,
+};
+
+use cid::Cid;
+
+use defluencer::{comments::CommentPolicy, rate_limit::RateLimiter, Defluencer};
+
+use futures_util::{pin_mut, TryStreamExt};
+
+use tokio::{
+    signal::ctrl_c,
+    time::{interval, timeout},
+};
+
+use ipfs_api::IpfsService;
+
+use clap::Parser;
+
+/// How long the denylist check of a submission may take, it fetches attacker supplied nodes.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Failed updates wait at most this many flushes before trying again.
+const MAX_BACKOFF: u32 = 32;
+
+#[derive(Debug, Parser)]
+pub struct Comments {
+    /// Channel identity CID.
+    #[arg(long)]
+    identity: Cid,
+
+    /// Seconds between channel updates.
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+
+    /// Maximum comment length in bytes.
+    #[arg(long, default_value_t = 2000)]
+    max_length: usize,
+
+    /// Maximum comment node size in bytes.
+    #[arg(long, default_value_t = 16384)]
+    max_size: u64,
+
+    /// Maximum comments per commenter during the rate limit window.
+    #[arg(long, default_value_t = 5)]
+    max_comments: usize,
+
+    /// Rate limit window in seconds.
+    #[arg(long, default_value_t = 600)]
+    window: i64,
+
+    #[command(flatten)]
+    denylist: DenylistArgs,
+}
+
+pub async fn comments_cli(args: Comments) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    println!("Initialization...");
+
+    if let Err(e) = ipfs.peer_id().await {
+        return Err(CliError::IpfsOffline(e.into()));
+    }
+
+    let Comments {
+        identity,
+        interval: period,
+        max_length,
+        max_size,
+        max_comments,
+        window,
+        denylist: compliance,
+    } = args;
+
+    let channel = local_setup(identity).await?;
+    let defluencer = Defluencer::from(ipfs.clone());
+
+    let mut policy = CommentPolicy::new(max_length, RateLimiter::new(max_comments, window));
+    policy.max_size = max_size;
+
+    let (_, metadata) = channel.get_metadata().await?;
+
+    policy.load_channel(&ipfs, &metadata).await?;
+
+    let stream = defluencer.subscribe_comment_submissions(channel.get_address(), &policy);
+    pin_mut!(stream);
+
+    let mut denylist = compliance.load().await?;
+    let mut refresh = compliance.interval();
+
+    let mut flush = interval(Duration::from_secs(period.max(1)));
+
+    let mut batch = Vec::new();
+
+    // Flushes left to skip and how many the next failure will skip.
+    let mut skip = 0;
+    let mut backoff = 1;
+
+    println!("✅ Comment Ingestion Online");
+
+    loop {
+        tokio::select! {
+            biased;
+
+            _ = ctrl_c() => break,
+
+            _ = flush.tick() => {
+                if skip > 0 {
+                    skip -= 1;
+                } else if !batch.is_empty() {
+                    // The batch is kept on failure and tried again later.
+                    match channel.add_comments(&batch).await {
+                        Ok(added) => {
+                            println!("✅ Added {} Comments", added.len());
+
+                            batch.clear();
+                            backoff = 1;
+                        }
+                        Err(e) => {
+                            eprintln!(
+                                "❗ Channel Update Failed, Retry In {} Flushes. {}",
+                                backoff, e
+                            );
+
+                            skip = backoff;
+                            backoff = (backoff * 2).min(MAX_BACKOFF);
+                        }
+                    }
+                }
+
+                // Permissions and follows may have changed since.
+                let reload = match channel.get_metadata().await {
+                    Ok((_, metadata)) => policy.load_channel(&ipfs, &metadata).await,
+                    Err(e) => Err(e),
+                };
+
+                if let Err(e) = reload {
+                    eprintln!("❗ Comment Policy Not Reloaded. {}", e);
+                }
+            },
+
+            _ = refresh.tick() => {
+                if let Some(latest) = compliance.refresh().await {
+                    denylist = latest;
+                }
+            },
+
+            res = stream.try_next() => match res {
+                Ok(Some(cid)) => {
+                    if batch.contains(&cid) {
+                        continue;
+                    }
+
+                    // The signed link, the comment and the media it replies to.
+                    match timeout(CHECK_TIMEOUT, denylist.first_denied(&ipfs, cid, 2)).await {
+                        Ok(Ok(None)) => batch.push(cid),
+                        Ok(Ok(Some(_))) => compliance.audit("comment", cid).await,
+                        Ok(Err(e)) => eprintln!("❗ Comment {} Not Checked. {}", cid, e),
+                        Err(_) => eprintln!("❗ Comment {} Not Checked In Time.", cid),
+                    }
+                },
+                Ok(None) => break,
+                Err(e) => eprintln!("❗ Comment Submission Failed. {}", e),
+            },
+        }
+    }
+
+    if !batch.is_empty() {
+        println!("Wait For The Last {} Comments To Be Added...", batch.len());
+
+        channel.add_comments(&batch).await?;
+    }
+
+    println!("❌ Comment Ingestion Offline");
+
+    Ok(())
+}
@@ -1,12 +1,15 @@
-use std::net::SocketAddr;
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{atomic::AtomicU64, Arc},
+};
 
 use crate::{
-    actors::{Archivist, Setter, Videograph},
+    actors::{Archivist, Setter, Throttle, Videograph},
+    errors::CliError,
     server::start_server,
 };
 
-use defluencer::errors::Error;
-
 use tokio::{
     signal::ctrl_c,
     sync::{mpsc::unbounded_channel, watch},
@@ -21,27 +24,34 @@ pub struct File {
     /// Socket Address used to ingress video.
     #[arg(long, default_value = "127.0.0.1:2526")]
     socket_addr: SocketAddr,
-}
-
-pub async fn file_cli(args: File) {
-    let res = file(args).await;
 
-    if let Err(e) = res {
-        eprintln!("❗ IPFS: {:#?}", e);
-    }
+    /// Directory where error reports are saved when archiving fail. (Optional)
+    ///
+    /// Reports contain recent IPFS daemon logs and a daemon profile.
+    #[arg(long)]
+    report_dir: Option<PathBuf>,
+
+    /// Maximum archiving bandwidth in bytes per second, 0 for unlimited.
+    ///
+    /// Can be changed while running with a PUT request to /archive/bandwidth.
+    #[arg(long, default_value = "0")]
+    archive_bandwidth: u64,
 }
 
-async fn file(args: File) -> Result<(), Error> {
+pub async fn file_cli(args: File) -> Result<(), CliError> {
     let ipfs = IpfsService::default();
 
     println!("Initialization...");
 
-    if let Err(_) = ipfs.peer_id().await {
-        eprintln!("❗ IPFS must be started beforehand.\nAborting...");
-        return Ok(());
+    if let Err(e) = ipfs.peer_id().await {
+        return Err(CliError::IpfsOffline(e.into()));
     }
 
-    let File { socket_addr } = args;
+    let File {
+        socket_addr,
+        report_dir,
+        archive_bandwidth,
+    } = args;
 
     //let mut handles = Vec::with_capacity(5);
 
@@ -64,14 +74,18 @@ async fn file(args: File) -> Result<(), Error> {
 
     let (archive_tx, archive_rx) = unbounded_channel();
 
-    let archivist = Archivist::new(ipfs.clone(), archive_rx);
+    let bandwidth = Arc::new(AtomicU64::new(archive_bandwidth));
+
+    let throttle = Throttle::new(bandwidth.clone());
+
+    let archivist = Archivist::new(ipfs.clone(), archive_rx, report_dir, None, None);
     tokio::spawn(archivist.start());
     //let handle = tokio::spawn(archivist.start());
     //handles.push(handle);
 
     let (video_tx, video_rx) = unbounded_channel();
 
-    let video = Videograph::new(ipfs.clone(), video_rx, Some(archive_tx.clone()), None);
+    let video = Videograph::new(ipfs.clone(), video_rx, Some(archive_tx.clone()), None, None);
     tokio::spawn(video.start());
     //let handle = tokio::spawn(video.start());
     //handles.push(handle);
@@ -98,9 +112,17 @@ async fn file(args: File) -> Result<(), Error> {
         }
     } */
 
-    if let Err(e) = start_server(socket_addr, video_tx, setup_tx, ipfs, shutdown).await {
-        eprintln!("❗ Server: {}", e);
-    }
+    start_server(
+        socket_addr,
+        video_tx,
+        setup_tx,
+        ipfs,
+        None,
+        bandwidth,
+        Some(throttle),
+        shutdown,
+    )
+    .await?;
 
     Ok(())
 }
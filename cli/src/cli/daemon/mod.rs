@@ -1,2 +1,4 @@
+pub mod comments;
 pub mod file;
+pub mod rsvp;
 pub mod stream;
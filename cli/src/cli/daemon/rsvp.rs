@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use crate::{
+    cli::{channel::local_setup, denylist::DenylistArgs},
+    errors::CliError,
+};
+
+use cid::Cid;
+
+use defluencer::Defluencer;
+
+use futures_util::{pin_mut, TryStreamExt};
+
+use tokio::{signal::ctrl_c, time::interval};
+
+use ipfs_api::IpfsService;
+
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+pub struct Rsvp {
+    /// Channel identity CID.
+    #[arg(long)]
+    identity: Cid,
+
+    /// Seconds between channel updates.
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+
+    #[command(flatten)]
+    denylist: DenylistArgs,
+}
+
+pub async fn rsvp_cli(args: Rsvp) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    println!("Initialization...");
+
+    if let Err(e) = ipfs.peer_id().await {
+        return Err(CliError::IpfsOffline(e.into()));
+    }
+
+    let Rsvp {
+        identity,
+        interval: period,
+        denylist: compliance,
+    } = args;
+
+    let channel = local_setup(identity).await?;
+    let defluencer = Defluencer::from(ipfs.clone());
+
+    let stream = defluencer.subscribe_rsvp_submissions(channel.get_address());
+    pin_mut!(stream);
+
+    let mut denylist = compliance.load().await?;
+    let mut refresh = compliance.interval();
+
+    let mut flush = interval(Duration::from_secs(period.max(1)));
+
+    let mut batch = Vec::new();
+
+    println!("✅ RSVP Ingestion Online");
+
+    loop {
+        tokio::select! {
+            biased;
+
+            _ = ctrl_c() => break,
+
+            _ = flush.tick() => {
+                if batch.is_empty() {
+                    continue;
+                }
+
+                match channel.add_rsvps(&batch).await {
+                    Ok(added) => println!("✅ Counted {} RSVPs", added.len()),
+                    Err(e) => eprintln!("❗ Channel Update Failed. {}", e),
+                }
+
+                batch.clear();
+            },
+
+            _ = refresh.tick() => {
+                if let Some(latest) = compliance.refresh().await {
+                    denylist = latest;
+                }
+            },
+
+            res = stream.try_next() => match res {
+                Ok(Some(cid)) => {
+                    if batch.contains(&cid) {
+                        continue;
+                    }
+
+                    // The signed link, the RSVP and the event it answers.
+                    match denylist.first_denied(&ipfs, cid, 2).await {
+                        Ok(None) => batch.push(cid),
+                        Ok(Some(_)) => compliance.audit("rsvp", cid).await,
+                        Err(e) => eprintln!("❗ RSVP {} Not Checked. {}", cid, e),
+                    }
+                },
+                Ok(None) => break,
+                Err(e) => eprintln!("❗ RSVP Submission Failed. {}", e),
+            },
+        }
+    }
+
+    if !batch.is_empty() {
+        println!("Wait For The Last {} RSVPs To Be Counted...", batch.len());
+
+        channel.add_rsvps(&batch).await?;
+    }
+
+    println!("❌ RSVP Ingestion Offline");
+
+    Ok(())
+}
@@ -1,25 +1,65 @@
-use std::net::SocketAddr;
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{atomic::AtomicU64, Arc},
+    time::Duration,
+};
 
 use crate::{
-    actors::{Archivist, Setter, Videograph},
-    server::start_server,
+    actors::{
+        Archivist, Chatter, Mirror, PostProcessor, Processed, Setter, Statistician, Throttle,
+        Videograph,
+    },
+    cli::{channel::local_setup, denylist::DenylistArgs},
+    errors::CliError,
+    server::{start_rtmp_server, start_server, Encoding, Quality},
 };
 
-use defluencer::errors::Error;
+use defluencer::{
+    chat::ChatRoom,
+    crypto::{
+        ledger::{BitcoinLedgerApp, DerivationPath, EthereumLedgerApp},
+        signers::{BitcoinSigner, EthereumSigner, Signer},
+    },
+    erasure::ErasureCoding,
+    live::{live_beacon, segment_public_key, SigningKey},
+    user::User,
+    Defluencer,
+};
 
 use linked_data::{
-    channel::{live::LiveSettings, ChannelMetadata},
-    types::IPNSAddress,
+    channel::{
+        live::LiveSettings,
+        moderation::{Bans, Moderators},
+        ChannelMetadata,
+    },
+    identity::Identity,
+    media::Media,
+    types::{Address, IPNSAddress},
 };
 
 use tokio::{
     signal::ctrl_c,
-    sync::{mpsc::unbounded_channel, watch},
+    sync::{
+        mpsc::{unbounded_channel, UnboundedSender},
+        oneshot, watch,
+    },
+    time::interval,
 };
 
+use futures_util::{pin_mut, TryStreamExt};
+
+use chrono::Utc;
+
+use cid::Cid;
+
 use ipfs_api::{responses::Codec, IpfsService};
 
-use clap::Parser;
+use clap::{builder::RangedU64ValueParser, Parser};
+
+/// Followers only chat is refreshed this often.
+const FOLLOWERS_INTERVAL: Duration = Duration::from_secs(600);
 
 #[derive(Debug, Parser)]
 pub struct Stream {
@@ -27,35 +67,161 @@ pub struct Stream {
     #[arg(long, default_value = "127.0.0.1:2526")]
     socket_addr: SocketAddr,
 
+    /// Socket Address used to receive RTMP from broadcasting software. (Optional)
+    ///
+    /// When set, FFMPEG is started for you and remux the stream into HLS.
+    /// Encoder statistics are then published on the stats topic and served at /stats.
+    #[arg(long)]
+    rtmp_addr: Option<SocketAddr>,
+
+    /// Transcode the RTMP stream into qualities picked from the source resolution, fps and bitrate.
+    ///
+    /// The chosen qualities are printed when the stream starts.
+    #[arg(long, requires = "rtmp_addr")]
+    transcode: bool,
+
+    /// Quality to transcode into instead of the picked ones, repeat for each. (Optional)
+    ///
+    /// Eg. 720p30 or 720p30:3000 with the bitrate in kbps.
+    #[arg(long, requires = "rtmp_addr")]
+    quality: Vec<Quality>,
+
     /// Channel IPNS Address.
     #[arg(long)]
     ipns_addr: IPNSAddress,
-}
 
-pub async fn stream_cli(args: Stream) {
-    let res = stream(args).await;
+    /// Directory where error reports are saved when archiving fail. (Optional)
+    ///
+    /// Reports contain recent IPFS daemon logs and a daemon profile.
+    #[arg(long)]
+    report_dir: Option<PathBuf>,
 
-    if let Err(e) = res {
-        eprintln!("❗ IPFS: {:#?}", e);
-    }
+    /// Maximum archiving bandwidth in bytes per second, 0 for unlimited.
+    ///
+    /// Can be changed while streaming with a PUT request to /archive/bandwidth.
+    #[arg(long, default_value = "0")]
+    archive_bandwidth: u64,
+
+    /// API URL of a secondary IPFS node where the archive is copied as it is created. (Optional)
+    ///
+    /// Eg. http://my-vps:5001/api/v0/ The stream is unaffected if this node fails.
+    #[arg(long)]
+    mirror: Option<String>,
+
+    /// API token of the mirror node, for hosted IPFS providers. (Optional)
+    #[arg(long, requires = "mirror")]
+    mirror_token: Option<String>,
+
+    #[command(flatten)]
+    denylist: DenylistArgs,
+
+    /// Parity shards added to each track of every group of segments archived. (Optional)
+    ///
+    /// Up to that many missing segments per group can be repaired, see the node repair command.
+    #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    parity: Option<usize>,
+
+    /// Number of segments per parity group.
+    #[arg(
+        long,
+        default_value = "8",
+        requires = "parity",
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    parity_group: usize,
+
+    /// File where chat sender statistics are kept between streams. (Optional)
+    ///
+    /// Browse it with the node reputation command.
+    #[arg(long)]
+    reputation_file: Option<PathBuf>,
+
+    /// Crawl snapshot address where chat followers are found. (Optional)
+    ///
+    /// Required by followers only chat, the follower list is reloaded every 10 minutes.
+    #[arg(long)]
+    followers_snapshot: Option<IPNSAddress>,
+
+    /// Key file signing live segments, see the channel live command. (Optional)
+    ///
+    /// Required when the channel live settings include a segment key.
+    #[arg(long)]
+    segment_key: Option<PathBuf>,
+
+    /// Program run on the archive once the stream ends, repeat to chain them. (Optional)
+    ///
+    /// Hooks can re-encode, add chapters or thumbnails, see the README.
+    #[arg(long)]
+    post_process: Vec<PathBuf>,
+
+    /// Creator identity CID. (Optional)
+    ///
+    /// When set, the published video of this stream is replaced by the processed version.
+    #[arg(long)]
+    creator: Option<Cid>,
+
+    /// Bitcoin or Ethereum based signatures.
+    #[arg(long, value_enum, default_value = "bitcoin")]
+    blockchain: Blockchain,
+
+    /// Account index (BIP-44).
+    #[arg(long, default_value = "0")]
+    account: u32,
+
+    /// Hardened account, m/44'/0'/<BITCOIN_ACCOUNT>'/0/<ACCOUNT> (BIP-44). Bitcoin only.
+    ///
+    /// Bitcoin signatures use the account index as the address index of this account.
+    #[arg(long, default_value = "0")]
+    bitcoin_account: u32,
+
+    /// Change, 0 for receiving and 1 for change addresses (BIP-44). Bitcoin only.
+    #[arg(long, default_value = "0")]
+    change: u32,
+
+    /// Use the testnet coin type. Bitcoin only.
+    #[arg(long)]
+    testnet: bool,
 }
 
-async fn stream(args: Stream) -> Result<(), Error> {
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum Blockchain {
+    Bitcoin,
+    Ethereum,
+}
+
+pub async fn stream_cli(args: Stream) -> Result<(), CliError> {
     let ipfs = IpfsService::default();
 
     println!("Initialization...");
 
     let peer_id = match ipfs.peer_id().await {
         Ok(peer_id) => peer_id,
-        Err(_) => {
-            eprintln!("❗ IPFS must be started beforehand.\nAborting...");
-            return Ok(());
-        }
+        Err(e) => return Err(CliError::IpfsOffline(e.into())),
     };
 
     let Stream {
         ipns_addr,
         socket_addr,
+        rtmp_addr,
+        transcode,
+        quality,
+        report_dir,
+        archive_bandwidth,
+        mirror,
+        mirror_token,
+        denylist: compliance,
+        parity,
+        parity_group,
+        reputation_file,
+        followers_snapshot,
+        segment_key,
+        post_process,
+        creator,
+        blockchain,
+        account,
+        bitcoin_account,
+        change,
+        testnet,
     } = args;
 
     let cid = ipfs.name_resolve(ipns_addr).await?;
@@ -69,14 +235,80 @@ async fn stream(args: Stream) -> Result<(), Error> {
                 .await?
         }
         None => {
-            eprintln!("❗ Channel live settings not found.\nAborting...");
-            return Ok(());
+            return Err(CliError::NotFound(format!(
+                "Channel {} has no live settings",
+                ipns_addr
+            )))
         }
     };
 
     if settings.peer_id != peer_id {
-        eprintln!("❗ This peer is not allowed to stream on this channel. Update your channel live settings!\nAborting...");
-        return Ok(());
+        return Err(CliError::Unauthorized(format!(
+            "Peer {} is not allowed to stream on this channel, update your channel live settings",
+            peer_id
+        )));
+    }
+
+    let segment_key = match (segment_key, settings.segment_key.as_deref()) {
+        (Some(path), Some(public_key)) => {
+            let bytes = tokio::fs::read(&path)
+                .await
+                .map_err(defluencer::errors::Error::from)?;
+
+            let key = match SigningKey::from_slice(&bytes) {
+                Ok(key) => key,
+                Err(_) => {
+                    return Err(CliError::InvalidInput(format!(
+                        "Invalid segment key file {}",
+                        path.display()
+                    )))
+                }
+            };
+
+            if segment_public_key(&key) != public_key {
+                return Err(CliError::Unauthorized(format!(
+                    "Segment key {} does not match your channel live settings",
+                    path.display()
+                )));
+            }
+
+            Some(key)
+        }
+        (None, Some(_)) => {
+            return Err(CliError::InvalidInput(String::from(
+                "Your channel live settings require signed segments",
+            )))
+        }
+        (Some(_), None) => {
+            return Err(CliError::InvalidInput(String::from(
+                "Segment key missing from your channel live settings",
+            )))
+        }
+        (None, None) => None,
+    };
+
+    let mirror = match mirror {
+        Some(url) => {
+            if !settings.archiving {
+                return Err(CliError::InvalidInput(String::from(
+                    "mirroring, your channel live settings disable archiving",
+                )));
+            }
+
+            let remote = IpfsService::new(&url)?;
+
+            match mirror_token {
+                Some(token) => Some(remote.with_bearer_token(&token)?),
+                None => Some(remote),
+            }
+        }
+        None => None,
+    };
+
+    if !post_process.is_empty() && !settings.archiving {
+        return Err(CliError::InvalidInput(String::from(
+            "post-processing, your channel live settings disable archiving",
+        )));
     }
 
     //let mut handles = Vec::with_capacity(6);
@@ -98,34 +330,52 @@ async fn stream(args: Stream) -> Result<(), Error> {
         rx
     };
 
+    let bandwidth = Arc::new(AtomicU64::new(archive_bandwidth));
+
+    // Segments are only archived when the live settings allow it.
+    let throttle = settings.archiving.then(|| Throttle::new(bandwidth.clone()));
+
+    let (recording_tx, recording_rx) = if post_process.is_empty() {
+        (None, None)
+    } else {
+        let (tx, rx) = oneshot::channel();
+
+        (Some(tx), Some(rx))
+    };
+
+    let (mirror_tx, mirror_handle) = match mirror {
+        Some(remote) => {
+            let (mirror_tx, mirror_rx) = unbounded_channel();
+
+            let denylist = compliance.load().await?;
+
+            let mirror = Mirror::new(ipfs.clone(), remote, mirror_rx, compliance, denylist);
+            let handle = tokio::spawn(mirror.start());
+
+            (Some(mirror_tx), Some(handle))
+        }
+        None => (None, None),
+    };
+
     let archive_tx = {
         if settings.archiving {
             let (archive_tx, archive_rx) = unbounded_channel();
 
-            /* if let Some(chat_topic) = settings.chat_topic {
-                let bans = match settings.bans {
-                    Some(ipld) => ipfs.dag_get::<&str, Bans>(ipld.link, None).await?,
-                    None => Default::default(),
-                };
-
-                let mods = match settings.mods {
-                    Some(ipld) => ipfs.dag_get::<&str, Moderators>(ipld.link, None).await?,
-                    None => Default::default(),
-                };
-
-                let chat = Chatter::new(
-                    ipfs.clone(),
-                    archive_tx.clone(),
-                    shutdown.clone(),
-                    chat_topic,
-                    bans,
-                    mods,
-                );
-                let handle = tokio::spawn(chat.start());
-                handles.push(handle);
-            } */
-
-            let archivist = Archivist::new(ipfs.clone(), archive_rx);
+            let archivist = Archivist::new(
+                ipfs.clone(),
+                archive_rx,
+                report_dir,
+                recording_tx,
+                mirror_tx,
+            );
+
+            let archivist = match parity {
+                Some(parity) => archivist.with_parity(ErasureCoding {
+                    segments: parity_group,
+                    parity,
+                }),
+                None => archivist,
+            };
             tokio::spawn(archivist.start());
             //let handle = tokio::spawn(archivist.start());
             //handles.push(handle);
@@ -136,14 +386,98 @@ async fn stream(args: Stream) -> Result<(), Error> {
         }
     };
 
+    let chat_handle = if let (Some(chat_topic), Some(settings_topic)) =
+        (settings.chat_topic.clone(), settings.chat_settings_topic())
+    {
+        let bans = match settings.bans {
+            Some(ipld) => {
+                ipfs.dag_get::<&str, Bans>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => Default::default(),
+        };
+
+        let mods = match settings.mods {
+            Some(ipld) => {
+                ipfs.dag_get::<&str, Moderators>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => Default::default(),
+        };
+
+        let room = ChatRoom::new(ipfs.clone(), bans, mods);
+
+        let chat = Chatter::new(
+            ipfs.clone(),
+            shutdown.clone(),
+            room,
+            chat_topic,
+            settings_topic,
+            reputation_file,
+        );
+
+        let chat = match archive_tx.clone() {
+            Some(archive_tx) => chat.with_archive(archive_tx),
+            None => chat,
+        };
+
+        let chat = match followers_snapshot {
+            Some(snapshot) => {
+                let (followers_tx, followers_rx) = unbounded_channel();
+
+                tokio::spawn(refresh_followers(
+                    ipfs.clone(),
+                    shutdown.clone(),
+                    snapshot,
+                    ipns_addr,
+                    followers_tx,
+                ));
+
+                chat.with_followers(followers_rx)
+            }
+            None => chat,
+        };
+
+        // Bans made in chat are saved when this node holds the channel key.
+        let chat = match local_setup(metadata.identity.link).await {
+            Ok(channel) => chat.with_channel(channel),
+            Err(_) => chat,
+        };
+
+        Some(tokio::spawn(chat.start()))
+    } else {
+        None
+    };
+
+    let stats_topic = settings.stats_topic();
+    let hubs: Vec<IPNSAddress> = settings.announced_hubs().collect();
+
     let (video_tx, video_rx) = unbounded_channel();
 
     let video = Videograph::new(
         ipfs.clone(),
         video_rx,
-        archive_tx.clone(),
+        archive_tx,
         Some(settings.video_topic),
+        segment_key,
     );
+
+    let video = match hubs.is_empty() {
+        true => video,
+        false => {
+            let (started_tx, started_rx) = oneshot::channel();
+
+            tokio::spawn(announce_live(
+                ipfs.clone(),
+                metadata.identity.link,
+                ipns_addr,
+                hubs,
+                started_rx,
+            ));
+
+            video.with_started(started_tx)
+        }
+    };
     tokio::spawn(video.start());
     //let handle = tokio::spawn(video.start());
     //handles.push(handle);
@@ -161,9 +495,250 @@ async fn stream(args: Stream) -> Result<(), Error> {
         }
     } */
 
-    if let Err(e) = start_server(socket_addr, video_tx, setup_tx, ipfs, shutdown).await {
-        eprintln!("❗ Server: {}", e);
+    let stats = match rtmp_addr {
+        Some(rtmp_addr) => {
+            let (stats_tx, stats_rx) = unbounded_channel();
+
+            let statistician = Statistician::new(ipfs.clone(), stats_rx, stats_topic);
+            let latest = statistician.latest();
+            tokio::spawn(statistician.start());
+
+            let shutdown = shutdown.clone();
+
+            let encoding = match (transcode, quality.is_empty()) {
+                (_, false) => Encoding::Ladder(quality),
+                (true, true) => Encoding::Auto,
+                (false, true) => Encoding::Copy,
+            };
+
+            tokio::spawn(async move {
+                if let Err(e) =
+                    start_rtmp_server(rtmp_addr, socket_addr, encoding, Some(stats_tx), shutdown)
+                        .await
+                {
+                    eprintln!("❗ RTMP Server: {}", e);
+                }
+            });
+
+            Some(latest)
+        }
+        None => None,
+    };
+
+    start_server(
+        socket_addr,
+        video_tx,
+        setup_tx,
+        ipfs.clone(),
+        stats,
+        bandwidth,
+        throttle,
+        shutdown,
+    )
+    .await?;
+
+    // Reputation and bans are saved as the chat stops.
+    if let Some(handle) = chat_handle {
+        if let Err(e) = handle.await {
+            eprintln!("❗ Chat: {}", e);
+        }
+    }
+
+    if let Some(handle) = mirror_handle {
+        println!("Waiting For The Mirror...");
+
+        if let Err(e) = handle.await {
+            eprintln!("❗ Mirror: {}", e);
+        }
+    }
+
+    let Some(recording_rx) = recording_rx else {
+        return Ok(());
+    };
+
+    println!("Waiting For The Archive...");
+
+    let recording = match recording_rx.await {
+        Ok(recording) => recording,
+        Err(_) => {
+            return Err(CliError::NotFound(String::from(
+                "Nothing was archived, post-processing skipped",
+            )))
+        }
+    };
+
+    let raw = recording.timecode;
+
+    let processed = PostProcessor::new(post_process).run(recording).await;
+
+    let Some(creator) = creator else {
+        println!("Publish the processed version with the user video command");
+        return Ok(());
+    };
+
+    match blockchain {
+        Blockchain::Bitcoin => {
+            let path = DerivationPath {
+                coin_type: if testnet {
+                    DerivationPath::TESTNET
+                } else {
+                    DerivationPath::MAINNET
+                },
+                account: bitcoin_account,
+                change,
+                index: account,
+            };
+
+            let signer = BitcoinSigner::with_path(BitcoinLedgerApp::default(), path);
+
+            replace_archive(ipfs, creator, signer, raw, processed).await
+        }
+        Blockchain::Ethereum => {
+            let signer = EthereumSigner::new(EthereumLedgerApp::default(), account);
+
+            replace_archive(ipfs, creator, signer, raw, processed).await
+        }
+    }
+}
+
+/// Submit a live beacon to each hub once the first segment is published.
+async fn announce_live(
+    ipfs: IpfsService,
+    identity: Cid,
+    channel: IPNSAddress,
+    hubs: Vec<IPNSAddress>,
+    started: oneshot::Receiver<()>,
+) {
+    // The stream ended before it started.
+    if started.await.is_err() {
+        return;
+    }
+
+    let title = match ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await
+    {
+        Ok(id) => format!("{} is live", id.name),
+        Err(_) => String::from("Live"),
+    };
+
+    let beacon = live_beacon(identity, channel, title, Utc::now().timestamp());
+
+    let cid = match ipfs
+        .dag_put(&beacon, Codec::default(), Codec::default())
+        .await
+    {
+        Ok(cid) => cid,
+        Err(e) => {
+            eprintln!("❗ Live Beacon: {}", e);
+            return;
+        }
+    };
+
+    let defluencer = Defluencer::from(ipfs);
+
+    for hub in hubs {
+        match defluencer.announce_to_hub(hub, cid).await {
+            Ok(()) => println!("✅ Stream Announced To Hub {}", hub),
+            Err(e) => eprintln!("❗ Hub {}: {}", hub, e),
+        }
+    }
+}
+
+/// Send the chat addresses of the channel followers now and every [FOLLOWERS_INTERVAL].
+async fn refresh_followers(
+    ipfs: IpfsService,
+    mut shutdown: watch::Receiver<()>,
+    snapshot: IPNSAddress,
+    channel: IPNSAddress,
+    followers_tx: UnboundedSender<HashSet<Address>>,
+) {
+    let defluencer = Defluencer::from(ipfs);
+
+    let mut refresh = interval(FOLLOWERS_INTERVAL);
+
+    loop {
+        tokio::select! {
+            biased;
+
+            _ = shutdown.changed() => return,
+
+            _ = refresh.tick() => {
+                match defluencer.crawled_follower_addresses(snapshot, channel).await {
+                    Ok(followers) => {
+                        println!("Chat: {} Followers Loaded", followers.len());
+
+                        if followers_tx.send(followers).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => eprintln!("❗ Followers: {}", e),
+                }
+            },
+        }
     }
+}
+
+/// Find the video published with the raw archive and replace it with the processed version.
+async fn replace_archive(
+    ipfs: IpfsService,
+    creator: Cid,
+    signer: impl Signer + Clone,
+    raw: Cid,
+    processed: Processed,
+) -> Result<(), CliError> {
+    let channel = local_setup(creator).await?;
+    let defluencer = Defluencer::from(ipfs.clone());
+
+    let (_, metadata) = channel.get_metadata().await?;
+
+    let Some(index) = metadata.content_index else {
+        return Err(CliError::NotFound(String::from("Channel has no content")));
+    };
+
+    let stream = defluencer.stream_content_rev_chrono(index);
+
+    pin_mut!(stream);
+
+    let mut published = None;
+
+    while let Some(cid) = stream.try_next().await? {
+        let media: Media = ipfs.dag_get(cid, Some("/link"), Codec::default()).await?;
+
+        if let Media::Video(video) = media {
+            if video.video.link == raw {
+                published = Some(cid);
+                break;
+            }
+        }
+    }
+
+    let Some(published) = published else {
+        println!(
+            "Stream video not published, use the user video command with the processed version"
+        );
+        return Ok(());
+    };
+
+    let Processed {
+        timecode,
+        chapters,
+        thumbnails,
+    } = processed;
+
+    let user = User::new(ipfs, signer, creator);
+
+    println!("Confirm Signature...");
+
+    let (cid, _) = user
+        .replace_video_archive(published, timecode, chapters, thumbnails, false)
+        .await?;
+
+    println!("Wait For Your Channel To Update...");
+
+    channel.replace_content(published, cid).await?;
+
+    println!("✅ Replaced Video {}\nCID: {}", published, cid);
 
     Ok(())
 }
@@ -0,0 +1,103 @@
+use std::{path::PathBuf, time::Duration};
+
+use chrono::Utc;
+
+use cid::Cid;
+
+use clap::Args;
+
+use defluencer::denylist::Denylist;
+
+use tokio::{
+    io::AsyncWriteExt,
+    time::{interval_at, Instant, Interval},
+};
+
+use crate::errors::CliError;
+
+/// Content takedown compliance of the daemons.
+#[derive(Debug, Args)]
+pub struct DenylistArgs {
+    /// File path or URL of a bad-bits style denylist. (Optional)
+    ///
+    /// Denied CIDs are never aggregated, pinned or mirrored, nor added to your channel.
+    #[arg(long)]
+    denylist: Option<String>,
+
+    /// Seconds between downloads of the denylist.
+    #[arg(long, default_value_t = 3600)]
+    denylist_refresh: u64,
+
+    /// File where refused CIDs are logged, instead of the standard error. (Optional)
+    #[arg(long, requires = "denylist")]
+    audit_log: Option<PathBuf>,
+}
+
+impl DenylistArgs {
+    /// Returns the denylist, empty if none was given.
+    pub async fn load(&self) -> Result<Denylist, CliError> {
+        let source = match self.denylist.as_ref() {
+            Some(source) => source,
+            None => return Ok(Denylist::default()),
+        };
+
+        let denylist = Denylist::load(source).await?;
+
+        println!("✅ Denylist Loaded {} Entries", denylist.len());
+
+        Ok(denylist)
+    }
+
+    /// Ticks each time the denylist should be downloaded again.
+    pub fn interval(&self) -> Interval {
+        let period = Duration::from_secs(self.denylist_refresh.max(1));
+
+        interval_at(Instant::now() + period, period)
+    }
+
+    /// Returns the latest denylist or None if it could not be loaded, keep the previous one then.
+    pub async fn refresh(&self) -> Option<Denylist> {
+        let source = self.denylist.as_ref()?;
+
+        match Denylist::load(source).await {
+            Ok(denylist) => {
+                println!("✅ Denylist Refreshed {} Entries", denylist.len());
+
+                Some(denylist)
+            }
+            Err(e) => {
+                eprintln!("❗ Denylist Refresh Failed. {}", e);
+
+                None
+            }
+        }
+    }
+
+    /// Record a CID refused because of the denylist.
+    pub async fn audit(&self, action: &str, cid: Cid) {
+        let line = format!("{} {} {}\n", Utc::now().to_rfc3339(), action, cid);
+
+        let path = match self.audit_log.as_ref() {
+            Some(path) => path,
+            None => {
+                eprint!("❗ Denylist: {}", line);
+                return;
+            }
+        };
+
+        let result = async {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+
+            file.write_all(line.as_bytes()).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            eprintln!("❗ Audit Log Failed. {} {}", e, line.trim_end());
+        }
+    }
+}
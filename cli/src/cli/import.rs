@@ -0,0 +1,460 @@
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{atomic::AtomicU64, Arc},
+};
+
+use crate::{
+    actors::{Archivist, Setter, Videograph},
+    cli::channel::local_setup,
+    errors::CliError,
+    server::start_server,
+};
+
+use cid::Cid;
+
+use clap::{Parser, Subcommand};
+
+use defluencer::{
+    crypto::signers::Signer,
+    import::{fetch_rss, parse_info_json, parse_rss, ImportEntry},
+    probe::probe,
+    user::User,
+    utils::{add_image, add_url},
+};
+
+use futures_util::{pin_mut, TryStreamExt};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{
+    identity::Identity,
+    media::{
+        audio::Audio,
+        video::{Timecode, Video},
+        Media,
+    },
+};
+
+use tokio::{
+    process::Command,
+    sync::{mpsc::unbounded_channel, oneshot, watch},
+};
+
+#[derive(Debug, Parser)]
+pub struct Import {
+    /// Socket Address used to ingress imported videos.
+    #[arg(long, default_value = "127.0.0.1:2526")]
+    socket_addr: SocketAddr,
+
+    /// Pin imported content.
+    #[arg(long)]
+    pin: bool,
+
+    #[command(subcommand)]
+    source: Source,
+}
+
+#[derive(Debug, Subcommand)]
+enum Source {
+    /// Directory downloaded with yt-dlp, see the README.
+    Youtube {
+        /// Directory of the .info.json files.
+        dir: PathBuf,
+    },
+
+    /// Podcast RSS feed.
+    Rss {
+        /// URL or path of the feed.
+        feed: String,
+    },
+}
+
+pub async fn import(
+    args: Import,
+    identity: Cid,
+    addr: String,
+    signer: impl Signer + Clone,
+) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let Import {
+        socket_addr,
+        pin,
+        source,
+    } = args;
+
+    let mut entries = match source {
+        Source::Youtube { dir } => youtube_entries(&dir).await?,
+        Source::Rss { feed } if is_url(&feed) => fetch_rss(&feed).await?,
+        Source::Rss { feed } => {
+            let xml = tokio::fs::read_to_string(&feed)
+                .await
+                .map_err(defluencer::errors::Error::from)?;
+
+            parse_rss(&xml)
+        }
+    };
+
+    if entries.is_empty() {
+        return Err(CliError::NotFound(String::from("Nothing to import")));
+    }
+
+    entries.sort_by_key(|entry| entry.user_timestamp);
+
+    let id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    let addr = Some(addr);
+    if id.eth_addr != addr && id.btc_addr != addr {
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
+    }
+
+    let channel = local_setup(identity).await?;
+    let user = User::new(ipfs.clone(), signer, identity);
+
+    let total = entries.len();
+    let mut imported = Vec::with_capacity(total);
+
+    for (i, entry) in entries.into_iter().enumerate() {
+        println!("Importing {}/{} {}...", i + 1, total, entry.title);
+
+        let media = match entry.is_audio() {
+            true => import_audio(&ipfs, identity, entry).await,
+            false => import_video(&ipfs, identity, socket_addr, entry).await,
+        };
+
+        let media = match media {
+            Ok(media) => media,
+            Err(e) => {
+                eprintln!("❗ Skipped: {}", e);
+                continue;
+            }
+        };
+
+        println!("Confirm Signature...");
+
+        let cid = user.add_imported(&media, pin).await?;
+
+        println!("✅ Imported\nCID: {}", cid);
+
+        imported.push(cid);
+    }
+
+    if imported.is_empty() {
+        return Err(CliError::NotFound(String::from("Nothing was imported")));
+    }
+
+    println!("Wait For Your Channel To Update...");
+
+    let added = channel.add_contents(&imported).await?;
+
+    println!(
+        "✅ Added {} Of {} Entries To Your Channel",
+        added.len(),
+        total
+    );
+
+    Ok(())
+}
+
+/// Read the metadata of all the videos in a yt-dlp download directory.
+async fn youtube_entries(dir: &Path) -> Result<Vec<ImportEntry>, CliError> {
+    let mut entries = Vec::new();
+
+    let mut read_dir = tokio::fs::read_dir(dir)
+        .await
+        .map_err(defluencer::errors::Error::from)?;
+
+    while let Some(file) = read_dir
+        .next_entry()
+        .await
+        .map_err(defluencer::errors::Error::from)?
+    {
+        let path = file.path();
+
+        if !path.to_string_lossy().ends_with(".info.json") {
+            continue;
+        }
+
+        let json = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(defluencer::errors::Error::from)?;
+
+        // Playlists have an info file too, without media.
+        let Some(mut entry) = parse_info_json(&json) else {
+            continue;
+        };
+
+        // Downloaded elsewhere then moved, look next to the info file.
+        entry.media = local_path(dir, &entry.media);
+        entry.thumbnail = entry.thumbnail.map(|path| local_path(dir, &path));
+
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+fn local_path(dir: &Path, path: &str) -> String {
+    let path = Path::new(path);
+
+    if path.exists() {
+        return path.to_string_lossy().into_owned();
+    }
+
+    match path.file_name() {
+        Some(name) => dir.join(name).to_string_lossy().into_owned(),
+        None => path.to_string_lossy().into_owned(),
+    }
+}
+
+fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Add the thumbnail if it can be found.
+async fn add_thumbnail(ipfs: &IpfsService, thumbnail: Option<String>) -> Option<Cid> {
+    let thumbnail = thumbnail?;
+
+    let res = match is_url(&thumbnail) {
+        true => add_url(ipfs, &thumbnail).await.map(|(cid, _)| cid),
+        false if Path::new(&thumbnail).exists() => add_image(ipfs, thumbnail.into()).await,
+        false => return None,
+    };
+
+    match res {
+        Ok(cid) => Some(cid),
+        Err(e) => {
+            eprintln!("❗ Thumbnail skipped: {}", e);
+            None
+        }
+    }
+}
+
+/// Add a local file, printing the upload progress.
+async fn add_file(ipfs: &IpfsService, path: &str, size: u64) -> Result<Cid, CliError> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(defluencer::errors::Error::from)?;
+
+    let stream = ipfs.add_with_progress(tokio_util::io::ReaderStream::new(file));
+    pin_mut!(stream);
+
+    let mut cid = None;
+
+    while let Some(progress) = stream.try_next().await? {
+        let percent = (progress.bytes * 100).checked_div(size).unwrap_or(100);
+
+        eprint!("\rUploading {}% ({} Blocks)", percent, progress.blocks);
+
+        if progress.cid.is_some() {
+            cid = progress.cid;
+        }
+    }
+
+    eprintln!();
+
+    cid.ok_or_else(|| CliError::NotFound(format!("CID of {} after upload", path)))
+}
+
+async fn import_audio(
+    ipfs: &IpfsService,
+    identity: Cid,
+    entry: ImportEntry,
+) -> Result<Media, CliError> {
+    let probe = probe(&entry.media).await?;
+    let (mime_type, format) = probe.audio()?;
+
+    let (audio, size) = match is_url(&entry.media) {
+        true => add_url(ipfs, &entry.media).await?,
+        false => {
+            let size = tokio::fs::metadata(&entry.media)
+                .await
+                .map_err(defluencer::errors::Error::from)?
+                .len();
+
+            let cid = add_file(ipfs, &entry.media, size).await?;
+
+            (cid, size)
+        }
+    };
+
+    let image = add_thumbnail(ipfs, entry.thumbnail).await;
+
+    Ok(Media::Audio(Audio {
+        identity: identity.into(),
+        user_timestamp: entry.user_timestamp,
+        audio: audio.into(),
+        title: entry.title,
+        mime_type: Some(mime_type),
+        duration: entry.duration.or(probe.duration()),
+        size: Some(size),
+        image: image.map(Into::into),
+        summary: entry.summary,
+        variants: Vec::new(),
+        format: Some(format),
+        tags: Vec::new(),
+    }))
+}
+
+async fn import_video(
+    ipfs: &IpfsService,
+    identity: Cid,
+    socket_addr: SocketAddr,
+    entry: ImportEntry,
+) -> Result<Media, CliError> {
+    if is_url(&entry.media) {
+        return Err(CliError::InvalidInput(format!(
+            "video {}, download it first",
+            entry.media
+        )));
+    }
+
+    let path = Path::new(&entry.media);
+
+    if !path.exists() {
+        return Err(CliError::NotFound(format!(
+            "Video file {} not found",
+            path.display()
+        )));
+    }
+
+    // Reject what ffmpeg cannot encode before spinning up the archive.
+    let format = probe(path).await?.video()?;
+
+    let cid = archive_video(ipfs, socket_addr, path).await?;
+
+    let timecode = ipfs
+        .dag_get::<&str, Timecode>(cid, None, Codec::default())
+        .await?;
+
+    let image = add_thumbnail(ipfs, entry.thumbnail).await;
+
+    Ok(Media::Video(Video {
+        identity: identity.into(),
+        user_timestamp: entry.user_timestamp,
+        video: cid.into(),
+        title: entry.title,
+        duration: timecode.duration.or(entry.duration),
+        size: timecode.size,
+        segment_count: timecode.segment_count,
+        image: image.map(Into::into),
+        summary: entry.summary,
+        format: Some(format),
+        ..Default::default()
+    }))
+}
+
+/// Encode a video file to HLS and archive it like the file daemon does.
+///
+/// Returns the timecode CID.
+async fn archive_video(
+    ipfs: &IpfsService,
+    socket_addr: SocketAddr,
+    path: &Path,
+) -> Result<Cid, CliError> {
+    let (shutdown_tx, shutdown) = watch::channel::<()>(());
+
+    let bandwidth = Arc::new(AtomicU64::new(0));
+
+    let (archive_tx, archive_rx) = unbounded_channel();
+    let (recording_tx, recording_rx) = oneshot::channel();
+
+    let archivist = Archivist::new(
+        ipfs.clone(),
+        archive_rx,
+        None,
+        bandwidth.clone(),
+        Some(recording_tx),
+        None,
+    );
+    tokio::spawn(archivist.start());
+
+    let (video_tx, video_rx) = unbounded_channel();
+
+    let video = Videograph::new(ipfs.clone(), video_rx, Some(archive_tx), None, None);
+    tokio::spawn(video.start());
+
+    let (setup_tx, setup_rx) = unbounded_channel();
+
+    let setup = Setter::new(ipfs.clone(), setup_rx, video_tx.clone());
+    tokio::spawn(setup.start());
+
+    let server = start_server(
+        socket_addr,
+        video_tx,
+        setup_tx,
+        ipfs.clone(),
+        None,
+        bandwidth,
+        shutdown,
+    );
+
+    let encode = async {
+        let status = encode_hls(path, socket_addr).await;
+
+        if let Err(e) = shutdown_tx.send(()) {
+            eprintln!("{}", e);
+        }
+
+        status
+    };
+
+    let (served, encoded) = tokio::join!(server, encode);
+    served?;
+
+    let status = encoded.map_err(defluencer::errors::Error::from)?;
+
+    if !status.success() {
+        return Err(CliError::InvalidInput(format!(
+            "video {}, ffmpeg exited with {}",
+            path.display(),
+            status
+        )));
+    }
+
+    match recording_rx.await {
+        Ok(recording) => Ok(recording.timecode),
+        Err(_) => Err(CliError::NotFound(format!(
+            "Nothing was archived from {}",
+            path.display()
+        ))),
+    }
+}
+
+/// Re-encode with key frames every second, as the archive expects.
+async fn encode_hls(
+    path: &Path,
+    ingest_addr: SocketAddr,
+) -> std::io::Result<std::process::ExitStatus> {
+    let segment = format!("http://{}/%v/%d.m4s", ingest_addr);
+    let playlist = format!("http://{}/%v/index.m3u8", ingest_addr);
+
+    Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error"])
+        .arg("-i")
+        .arg(path)
+        .args(["-map", "0:v:0", "-c:v", "libx264", "-preset", "fast"])
+        .args(["-force_key_frames", "expr:gte(t,n_forced*1)"])
+        .args(["-map", "0:a:0", "-c:a", "aac", "-b:a", "128k"])
+        .args(["-f", "hls"])
+        .args(["-var_stream_map", "v:0,name:source a:0,name:audio"])
+        .args(["-hls_init_time", "1", "-hls_time", "1"])
+        .args(["-hls_flags", "independent_segments"])
+        .args(["-master_pl_name", "master.m3u8"])
+        .args(["-hls_segment_type", "fmp4"])
+        .args(["-hls_segment_filename", &segment])
+        .args(["-http_persistent", "0", "-ignore_io_errors", "1"])
+        .args(["-method", "PUT", &playlist])
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .status()
+        .await
+}
@@ -1,4 +1,7 @@
 pub mod channel;
 pub mod daemon;
+pub mod denylist;
+pub mod import;
 pub mod node;
+pub mod site;
 pub mod user;
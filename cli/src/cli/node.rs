@@ -1,12 +1,34 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
+
+use chrono::{NaiveDate, TimeZone, Utc};
+
+use cid::Cid;
 
 use clap::{Parser, Subcommand};
 
-use defluencer::{errors::Error, utils::add_image, Defluencer};
+use crate::{cli::denylist::DenylistArgs, errors::CliError};
+
+use defluencer::{
+    aggregation::Aggregator,
+    canonical::audit_dag,
+    channel::local::LocalUpdater,
+    crawl::{CrawlCheckpoint, CrawlEvent},
+    crawl_snapshot::CrawlSnapshot,
+    dedup::DedupReport,
+    erasure::repair_archive,
+    errors::Error,
+    gateway::Gateway,
+    gossip::{gossip_topic, GossipCache},
+    reputation::Reputation,
+    utils::add_image,
+    Defluencer,
+};
 
 use futures_util::{future::AbortHandle, pin_mut, stream::Abortable, StreamExt};
 
-use ipfs_api::{responses::Codec, IpfsService};
+use ipfs_api::{responses::Codec, retry::RetryPolicy, IpfsService};
+
+use tokio::time;
 
 use linked_data::{channel::ChannelMetadata, types::IPNSAddress};
 
@@ -19,31 +41,57 @@ pub enum NodeCLI {
     Address(Address), */
     /// Recursively pin all channel data on this node.
     /// CAUTION: The amount of data to download could be MASSIVE.
-    Pin(Address),
+    Pin(Pin),
+
+    /// Pin only the channel content created between two dates.
+    PinRange(PinRange),
 
     /// Recursively unpin all channel data from this node.
     /// CAUTION: The data can now be deleted by the garbage collector at any time.
     Unpin(Address),
 
     /// Receive channel updates in real time.
-    /// The first CID received is the most up to date channel metadata not a live update.
+    /// Each update is printed as the changes made since the previous one.
     Subscribe(Address),
 
     /// Receive requests for content aggregation.
-    Aggregate(Address),
+    Aggregate(Aggregate),
 
     /// Stream all content & comments from a channel.
     Stream(Stream),
 
     /// Crawl the social web, returns channel metadata CIDs without duplicates.
-    Webcrawl(Address),
+    Webcrawl(WebCrawl),
+
+    /// Browse the listings of a channel registry.
+    Registry(Registry),
+
+    /// Compare two contents block by block and report shared & unique bytes.
+    Dedup(Dedup),
+
+    /// List chat senders from least to most trusted.
+    Reputation(ChatReputation),
+
+    /// Gossip the latest verified roots of channels with friends.
+    /// Helps peers with poor DHT connectivity learn updates faster.
+    Gossip(Gossip),
+
+    /// Create a DASH manifest of a video, for players without HLS support.
+    Dash(Dash),
+
+    /// Reconstruct the missing segments of an archive from its parity.
+    Repair(Repair),
+
+    /// Re-encode every block of a DAG and list those whose CID is not canonical.
+    Audit(Audit),
 }
 
-pub async fn node_cli(cli: NodeCLI) {
-    let res = match cli {
+pub async fn node_cli(cli: NodeCLI) -> Result<(), CliError> {
+    match cli {
         NodeCLI::Identity(args) => create_id(args).await,
         //Command::Address(args) => address(args).await,
         NodeCLI::Pin(args) => pin(args).await,
+        NodeCLI::PinRange(args) => pin_range(args).await,
         NodeCLI::Unpin(args) => unpin(args).await,
         NodeCLI::Subscribe(args) => subscribe(args).await,
         NodeCLI::Aggregate(args) => agregate(args).await,
@@ -52,10 +100,13 @@ pub async fn node_cli(cli: NodeCLI) {
             SubCommand::Comments => stream_comments(stream_cli.address).await,
         },
         NodeCLI::Webcrawl(args) => web_crawl(args).await,
-    };
-
-    if let Err(e) = res {
-        eprintln!("❗ IPFS: {:#?}", e);
+        NodeCLI::Registry(args) => registry(args).await,
+        NodeCLI::Dedup(args) => dedup(args).await,
+        NodeCLI::Reputation(args) => reputation(args).await,
+        NodeCLI::Gossip(args) => gossip(args).await,
+        NodeCLI::Dash(args) => dash(args).await,
+        NodeCLI::Repair(args) => repair(args).await,
+        NodeCLI::Audit(args) => audit(args).await,
     }
 }
 
@@ -90,7 +141,7 @@ pub struct Identity {
     eth_addr: Option<String>,
 }
 
-async fn create_id(args: Identity) -> Result<(), Error> {
+async fn create_id(args: Identity) -> Result<(), CliError> {
     let ipfs = IpfsService::default();
 
     let Identity {
@@ -123,6 +174,7 @@ async fn create_id(args: Identity) -> Result<(), Error> {
         ipns_addr,
         btc_addr,
         eth_addr,
+        ..Default::default()
     };
 
     let cid = ipfs
@@ -151,7 +203,7 @@ enum Blockchain {
     Ethereum,
 } */
 
-/* async fn address(args: Address) -> Result<(), Error> {
+/* async fn address(args: Address) -> Result<(), CliError> {
     println!("Authorize Your Hardware Wallet...");
 
     let ipns: Cid = match args.blockchain {
@@ -183,17 +235,105 @@ pub struct Address {
     address: IPNSAddress,
 }
 
-async fn pin(args: Address) -> Result<(), Error> {
+#[derive(Debug, Parser)]
+pub struct Pin {
+    /// Channel IPNS address.
+    #[arg(long)]
+    address: IPNSAddress,
+
+    #[command(flatten)]
+    denylist: DenylistArgs,
+}
+
+async fn pin(args: Pin) -> Result<(), CliError> {
+    let denylist = args.denylist.load().await?;
+
     let defluencer = Defluencer::default();
 
-    defluencer.pin_channel(args.address).await?;
+    match defluencer.pin_channel(args.address, &denylist).await {
+        Ok(()) => {}
+        Err(Error::Denied(cid)) => {
+            args.denylist.audit("pin", cid).await;
+
+            return Err(Error::Denied(cid).into());
+        }
+        Err(e) => return Err(e.into()),
+    }
 
     println!("✅ Channel's Content Pinned");
 
     Ok(())
 }
 
-async fn unpin(args: Address) -> Result<(), Error> {
+#[derive(Debug, Parser)]
+pub struct PinRange {
+    /// Channel IPNS address.
+    #[arg(long)]
+    address: IPNSAddress,
+
+    /// First day included. (YYYY-MM-DD)
+    #[arg(long)]
+    from: NaiveDate,
+
+    /// Last day included. (YYYY-MM-DD)
+    #[arg(long)]
+    to: NaiveDate,
+
+    #[command(flatten)]
+    denylist: DenylistArgs,
+}
+
+async fn pin_range(args: PinRange) -> Result<(), CliError> {
+    let PinRange {
+        address,
+        from,
+        to,
+        denylist: compliance,
+    } = args;
+
+    if from > to {
+        return Err(CliError::InvalidInput(format!(
+            "Start date {} is after end date {}",
+            from, to
+        )));
+    }
+
+    let from = Utc.from_utc_datetime(&from.and_hms_opt(0, 0, 0).expect("Valid Time"));
+    let to = Utc.from_utc_datetime(&to.and_hms_opt(23, 59, 59).expect("Valid Time"));
+
+    let denylist = compliance.load().await?;
+
+    let defluencer = Defluencer::default();
+
+    println!("Wait For Content To Be Pinned...");
+
+    let (content, denied) = match defluencer
+        .pin_channel_range(address, from, to, &denylist)
+        .await
+    {
+        Ok(pinned) => pinned,
+        Err(Error::Denied(cid)) => {
+            compliance.audit("pin", cid).await;
+
+            return Err(Error::Denied(cid).into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    for cid in denied.iter() {
+        compliance.audit("pin", *cid).await;
+    }
+
+    println!("✅ Pinned {} Channel's Content", content.len());
+
+    if !denied.is_empty() {
+        println!("{} Denied Content Skipped", denied.len());
+    }
+
+    Ok(())
+}
+
+async fn unpin(args: Address) -> Result<(), CliError> {
     let defluencer = Defluencer::default();
 
     defluencer.unpin_channel(args.address).await?;
@@ -203,13 +343,13 @@ async fn unpin(args: Address) -> Result<(), Error> {
     Ok(())
 }
 
-async fn subscribe(args: Address) -> Result<(), Error> {
+async fn subscribe(args: Address) -> Result<(), CliError> {
     use futures_util::TryStreamExt;
 
     let defluencer = Defluencer::default();
 
     let (handle, regis) = AbortHandle::new_pair();
-    let stream = defluencer.subscribe_channel_updates(args.address);
+    let stream = defluencer.subscribe_channel_changes(args.address);
     let stream = Abortable::new(stream, regis);
     pin_mut!(stream);
 
@@ -230,16 +370,38 @@ async fn subscribe(args: Address) -> Result<(), Error> {
 
             result = stream.try_next() => match result {
                 Ok(option) => match option {
-                    Some(cid) => println!("Channel Root Signature: {}", cid),
+                    Some(change) => println!("{}", change),
                     None => continue,
                 },
-                Err(e) => return Err(e),
+                Err(e) => return Err(e.into()),
             }
         }
     }
 }
 
-async fn agregate(args: Address) -> Result<(), Error> {
+#[derive(Debug, Parser)]
+pub struct Aggregate {
+    /// Channel IPNS address.
+    #[arg(long)]
+    address: IPNSAddress,
+
+    /// Number of recent CIDs remembered to drop duplicates.
+    #[arg(long, default_value = "1000")]
+    window: usize,
+
+    /// Maximum content age in seconds.
+    #[arg(long, default_value = "86400")]
+    max_age: i64,
+
+    /// Maximum content node size in bytes.
+    #[arg(long, default_value = "16384")]
+    max_size: u64,
+
+    #[command(flatten)]
+    denylist: DenylistArgs,
+}
+
+async fn agregate(args: Aggregate) -> Result<(), CliError> {
     use futures_util::TryStreamExt;
 
     let ipfs = IpfsService::default();
@@ -254,13 +416,22 @@ async fn agregate(args: Address) -> Result<(), Error> {
     let topic = match meta.agregation_channel {
         Some(tp) => tp,
         None => {
-            eprintln!("❗ This channel has no aggregation topic");
-            return Ok(());
+            return Err(CliError::NotFound(format!(
+                "Channel {} has no aggregation topic",
+                args.address
+            )))
         }
     };
 
+    let aggregator = Aggregator::new(args.window, args.max_age)
+        .with_max_size(args.max_size)
+        .with_denylist(args.denylist.load().await?);
+
+    let mut refresh = args.denylist.interval();
+    let mut audit_log = time::interval(Duration::from_secs(1));
+
     let (handle, regis) = AbortHandle::new_pair();
-    let stream = defluencer.subscribe_agregation_updates(topic);
+    let stream = defluencer.subscribe_aggregator(topic, &aggregator);
     let stream = Abortable::new(stream, regis);
     pin_mut!(stream);
 
@@ -275,16 +446,37 @@ async fn agregate(args: Address) -> Result<(), Error> {
 
             _ = &mut control => {
                 handle.abort();
-                println!("✅ Aggregation Stopped");
+
+                for cid in aggregator.take_denied() {
+                    args.denylist.audit("aggregate", cid).await;
+                }
+
+                let stats = aggregator.stats();
+                println!(
+                    "✅ Aggregation Stopped\nAccepted: {}\nDuplicates: {}\nInvalid: {}\nStale: {}\nOversized: {}\nDenied: {}",
+                    stats.accepted, stats.duplicates, stats.invalid, stats.stale, stats.oversized, stats.denied
+                );
                 return Ok(());
             }
 
+            _ = refresh.tick() => {
+                if let Some(denylist) = args.denylist.refresh().await {
+                    aggregator.set_denylist(denylist);
+                }
+            }
+
+            _ = audit_log.tick() => {
+                for cid in aggregator.take_denied() {
+                    args.denylist.audit("aggregate", cid).await;
+                }
+            }
+
             result = stream.try_next() => match result {
                 Ok(option) => match option {
                     Some(cid) => println!("Content CID: {}", cid),
                     None => continue,
                 },
-                Err(e) => return Err(e),
+                Err(e) => return Err(e.into()),
             }
         }
     }
@@ -309,7 +501,7 @@ pub enum SubCommand {
     Comments,
 }
 
-async fn stream_comments(addr: IPNSAddress) -> Result<(), Error> {
+async fn stream_comments(addr: IPNSAddress) -> Result<(), CliError> {
     use futures_util::TryStreamExt;
 
     let ipfs = IpfsService::default();
@@ -323,8 +515,10 @@ async fn stream_comments(addr: IPNSAddress) -> Result<(), Error> {
     let index = match metadata.comment_index {
         Some(ipns) => ipns,
         None => {
-            eprintln!("❗ This channel has no comments.");
-            return Ok(());
+            return Err(CliError::NotFound(format!(
+                "Channel {} has no comments",
+                addr
+            )))
         }
     };
 
@@ -343,7 +537,7 @@ async fn stream_comments(addr: IPNSAddress) -> Result<(), Error> {
     Ok(())
 }
 
-async fn stream_content(addr: IPNSAddress) -> Result<(), Error> {
+async fn stream_content(addr: IPNSAddress) -> Result<(), CliError> {
     use futures_util::TryStreamExt;
 
     let ipfs = IpfsService::default();
@@ -357,8 +551,10 @@ async fn stream_content(addr: IPNSAddress) -> Result<(), Error> {
     let index = match metadata.content_index {
         Some(ipns) => ipns,
         None => {
-            eprintln!("❗ This channel has no content.");
-            return Ok(());
+            return Err(CliError::NotFound(format!(
+                "Channel {} has no content",
+                addr
+            )))
         }
     };
 
@@ -377,10 +573,81 @@ async fn stream_content(addr: IPNSAddress) -> Result<(), Error> {
     Ok(())
 }
 
-async fn web_crawl(args: Address) -> Result<(), Error> {
-    let defluencer = Defluencer::default();
+#[derive(Debug, Parser)]
+pub struct WebCrawl {
+    /// Channel IPNS address.
+    #[arg(long)]
+    address: IPNSAddress,
+
+    /// File used to save progress. The crawl resume from it when present. (Optional)
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+
+    /// Number of channels between each checkpoint.
+    #[arg(long, default_value = "100")]
+    interval: usize,
+
+    /// Key name used to publish a snapshot of the crawled web. Generated if missing. (Optional)
+    #[arg(long)]
+    snapshot: Option<String>,
+
+    /// Seconds allowed to resolve a channel or fetch its metadata. Channels timing out are skipped.
+    #[arg(long, default_value = "60")]
+    timeout: u64,
+
+    /// Attempts per request before skipping a channel.
+    #[arg(long, default_value = "3")]
+    attempts: u32,
+}
+
+async fn web_crawl(args: WebCrawl) -> Result<(), CliError> {
+    let WebCrawl {
+        address,
+        checkpoint,
+        interval,
+        snapshot,
+        timeout,
+        attempts,
+    } = args;
+
+    let timeout = Duration::from_secs(timeout);
+
+    let retry = RetryPolicy::default()
+        .with_attempts(attempts)
+        .with_endpoint_timeout("name/resolve", timeout)
+        .with_endpoint_timeout("dag/get", timeout);
+
+    let ipfs = IpfsService::default().with_retry(retry);
+    let defluencer = Defluencer::from(ipfs.clone());
+
+    let resume = match checkpoint.as_ref() {
+        Some(path) if path.exists() => {
+            let bytes = tokio::fs::read(path).await.map_err(Error::from)?;
+            let resume: CrawlCheckpoint = serde_json::from_slice(&bytes).map_err(Error::from)?;
+
+            println!("Resuming Crawl From {}", path.display());
+
+            Some(resume)
+        }
+        _ => None,
+    };
 
-    let stream = defluencer.streaming_web_crawl(std::iter::once(args.address));
+    let mut snapshot = match snapshot {
+        Some(key) if resume.is_some() => {
+            Some(CrawlSnapshot::resume_local(ipfs.clone(), key).await?)
+        }
+        Some(key) => Some(CrawlSnapshot::create_local(ipfs.clone(), key).await?),
+        None => None,
+    };
+
+    let interval = if checkpoint.is_some() || snapshot.is_some() {
+        interval
+    } else {
+        0
+    };
+
+    let stream =
+        defluencer.streaming_web_crawl_addressed(std::iter::once(address), resume, interval);
     let control = tokio::signal::ctrl_c();
 
     pin_mut!(stream);
@@ -388,27 +655,385 @@ async fn web_crawl(args: Address) -> Result<(), Error> {
 
     println!("✅ Crawling Start\nPress CRTL-C to exit...");
 
+    let mut published = time::Instant::now();
+
     loop {
         tokio::select! {
             biased;
 
             _ = &mut control => {
+                if let Some(snapshot) = snapshot.as_mut() {
+                    publish_snapshot(snapshot).await?;
+                }
+
                 println!("✅ Web Crawl Stopped");
                 return Ok(());
             }
 
             option = stream.next() => match option {
                 Some(result) => match result {
-                    Ok((cid, _channel)) => {
+                    Ok((addr, CrawlEvent::Channel(cid, channel))) => {
                         println!("Channel Metadata CID: {}",  cid);
+
+                        if let (Some(snapshot), Some(addr)) = (snapshot.as_mut(), addr) {
+                            if let Err(e) = snapshot.record(addr, cid, &channel).await {
+                                eprintln!("❗ Snapshot Error: {}", e);
+                            }
+                        }
+                    },
+                    Ok((_, CrawlEvent::Checkpoint(state))) => {
+                        // Publish first, resumed crawls skip the channels before a checkpoint.
+                        // Checkpoints are saved only when the snapshot is, at most every interval.
+                        if let Some(snapshot) = snapshot.as_mut() {
+                            if published.elapsed() < SNAPSHOT_INTERVAL {
+                                continue;
+                            }
+
+                            publish_snapshot(snapshot).await?;
+
+                            published = time::Instant::now();
+                        }
+
+                        if let Some(path) = checkpoint.as_ref() {
+                            let bytes = serde_json::to_vec(&state).map_err(Error::from)?;
+                            tokio::fs::write(path, bytes).await.map_err(Error::from)?;
+                        }
                     },
                     Err(_) => continue,
 
                 },
                 None => {
+                    if let Some(path) = checkpoint.as_ref() {
+                        let _ = tokio::fs::remove_file(path).await;
+                    }
+
+                    if let Some(snapshot) = snapshot.as_mut() {
+                        publish_snapshot(snapshot).await?;
+                    }
+
                     println!("✅ Web Crawl Finished");
                     return Ok(())},
             }
         }
     }
 }
+
+/// Minimum delay between crawl snapshot publications at checkpoints.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(600);
+
+async fn publish_snapshot(snapshot: &mut CrawlSnapshot<LocalUpdater>) -> Result<(), CliError> {
+    let cid = snapshot.publish().await?;
+
+    println!(
+        "✅ Crawl Snapshot {} Published At {}",
+        cid,
+        snapshot.get_address()
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Registry {
+    /// Registry channel IPNS address.
+    #[arg(long)]
+    address: IPNSAddress,
+
+    /// Only list channels under this topic. (Optional)
+    #[arg(long)]
+    topic: Option<String>,
+
+    /// Skip unreadable tree nodes instead of failing. Listings may be missing.
+    #[arg(long)]
+    force: bool,
+}
+
+async fn registry(args: Registry) -> Result<(), CliError> {
+    use futures_util::TryStreamExt;
+
+    let ipfs = IpfsService::default();
+    let defluencer = Defluencer::from(ipfs.clone());
+
+    let cid = ipfs.name_resolve(args.address).await?;
+    let metadata = ipfs
+        .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+        .await?;
+
+    let index = match metadata.registry {
+        Some(index) => index,
+        None => {
+            return Err(CliError::NotFound(format!(
+                "Channel {} has no registry",
+                args.address
+            )))
+        }
+    };
+
+    let stream = defluencer.stream_registry(index, args.topic, args.force);
+
+    pin_mut!(stream);
+
+    println!("✅ Streaming Registry Listings...");
+
+    while let Some((topic, channel, listing)) = stream.try_next().await? {
+        println!("Topic: {} Channel: {} Listing: {}", topic, channel, listing);
+    }
+
+    println!("✅ Registry Stream Finished");
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Dedup {
+    /// First content CID.
+    a: Cid,
+
+    /// Second content CID.
+    b: Cid,
+}
+
+async fn dedup(args: Dedup) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    println!("Wait For Both Videos To Be Compared...");
+
+    let report = DedupReport::compare(&ipfs, args.a, args.b).await?;
+
+    let DedupReport {
+        shared_blocks,
+        shared_bytes,
+        unique_blocks_a,
+        unique_bytes_a,
+        unique_blocks_b,
+        unique_bytes_b,
+    } = report;
+
+    println!("Shared: {} Blocks {} Bytes", shared_blocks, shared_bytes);
+    println!(
+        "Unique To {}: {} Blocks {} Bytes",
+        args.a, unique_blocks_a, unique_bytes_a
+    );
+    println!(
+        "Unique To {}: {} Blocks {} Bytes",
+        args.b, unique_blocks_b, unique_bytes_b
+    );
+    println!(
+        "✅ {:.1}% Of All Bytes Are Shared",
+        report.shared_ratio() * 100.0
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct ChatReputation {
+    /// File written by the stream daemon.
+    #[arg(long)]
+    file: PathBuf,
+
+    /// Number of senders listed.
+    #[arg(long, default_value = "20")]
+    count: usize,
+}
+
+async fn reputation(args: ChatReputation) -> Result<(), CliError> {
+    let bytes = tokio::fs::read(&args.file).await.map_err(Error::from)?;
+
+    let reputation: Reputation = serde_json::from_slice(&bytes).map_err(Error::from)?;
+
+    let mut senders: Vec<_> = reputation.senders().collect();
+    senders.sort_by(|(_, a), (_, b)| a.trust().total_cmp(&b.trust()));
+
+    for (address, stats) in senders.into_iter().take(args.count) {
+        println!(
+            "0x{} Trust: {:.2} Messages: {} Rejected: {} Banned: {}",
+            hex::encode(address),
+            stats.trust(),
+            stats.messages,
+            stats.rejected,
+            stats.banned
+        );
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Gossip {
+    /// Name of the group of friends sharing a gossip topic.
+    #[arg(long, default_value = "defluencer")]
+    group: String,
+
+    /// Channel to fetch from the routing system and gossip, usually one you follow.
+    #[arg(long)]
+    channel: Vec<IPNSAddress>,
+
+    /// Seconds between gossip rounds.
+    #[arg(long, default_value = "60")]
+    interval: u64,
+}
+
+async fn gossip(args: Gossip) -> Result<(), CliError> {
+    use futures_util::TryStreamExt;
+
+    let Gossip {
+        group,
+        channel,
+        interval,
+    } = args;
+
+    let defluencer = Defluencer::default();
+    let cache = GossipCache::default();
+
+    let updates = defluencer.subscribe_gossip(&group, &cache);
+
+    pin_mut!(updates);
+
+    let mut interval = time::interval(Duration::from_secs(interval.max(1)));
+
+    println!("✅ Gossiping On {}", gossip_topic(&group));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                for (addr, root) in defluencer.refresh_gossip(&cache, &channel).await {
+                    println!("Routing: Channel {} => {}", addr, root);
+                }
+
+                if let Err(e) = defluencer.publish_gossip(&group, &cache).await {
+                    eprintln!("❗ IPFS: pubsub pub failed {}", e);
+                }
+            }
+
+            res = updates.try_next() => {
+                match res? {
+                    Some((addr, root)) => println!("Gossip: Channel {} => {}", addr, root),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Dash {
+    /// Video CID.
+    #[arg(long)]
+    video: Cid,
+
+    /// Gateway serving the video segments.
+    #[arg(long, default_value = "https://ipfs.io")]
+    gateway: String,
+
+    /// Address segments as subdomains of the gateway.
+    #[arg(long)]
+    subdomain: bool,
+
+    /// Manifest file path, printed if absent.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+async fn dash(args: Dash) -> Result<(), CliError> {
+    let defluencer = Defluencer::default();
+
+    println!("Wait For Video Archive To Be Traversed...");
+
+    let gateway = match args.subdomain {
+        true => Gateway::subdomain(&args.gateway),
+        false => Gateway::path(&args.gateway),
+    };
+
+    let manifest = defluencer.dash_manifest(args.video, &gateway).await?;
+
+    match args.output {
+        Some(path) => {
+            tokio::fs::write(&path, manifest)
+                .await
+                .map_err(Error::from)?;
+
+            println!("✅ DASH Manifest Written To {}", path.display());
+        }
+        None => println!("{}", manifest),
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Repair {
+    /// Archive CID, as linked from the video post.
+    #[arg(long)]
+    archive: Cid,
+
+    /// Seconds to wait for each block before considering it missing.
+    #[arg(long, default_value = "30")]
+    timeout: u64,
+}
+
+async fn repair(args: Repair) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    println!("Wait For Archive Segments To Be Checked...");
+
+    let repair = repair_archive(&ipfs, args.archive, Duration::from_secs(args.timeout)).await?;
+
+    for cid in repair.repaired.iter() {
+        println!("Repaired {}", cid);
+    }
+
+    for cid in repair.lost.iter() {
+        eprintln!("❗ Lost {}", cid);
+    }
+
+    if !repair.lost.is_empty() {
+        return Err(CliError::NotFound(format!(
+            "{} segments tracks cannot be reconstructed",
+            repair.lost.len()
+        )));
+    }
+
+    println!("✅ {} Segment Tracks Repaired", repair.repaired.len());
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Audit {
+    /// Root CID of the DAG.
+    root: Cid,
+}
+
+async fn audit(args: Audit) -> Result<(), CliError> {
+    use futures_util::TryStreamExt;
+
+    let ipfs = IpfsService::default();
+
+    let stream = audit_dag(&ipfs, args.root);
+
+    pin_mut!(stream);
+
+    println!("Wait For All Blocks To Be Re-encoded...");
+
+    let mut count = 0;
+
+    while let Some(mismatch) = stream.try_next().await? {
+        eprintln!("❗ {} Should Be {}", mismatch.cid, mismatch.canonical);
+
+        count += 1;
+    }
+
+    if count > 0 {
+        return Err(CliError::InvalidInput(format!(
+            "{} blocks are not canonically encoded",
+            count
+        )));
+    }
+
+    println!("✅ All Blocks Are Canonical");
+
+    Ok(())
+}
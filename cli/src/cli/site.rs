@@ -0,0 +1,502 @@
+use std::{fmt::Write, path::PathBuf};
+
+use chrono::{TimeZone, Utc};
+
+use cid::Cid;
+
+use clap::Parser;
+
+use defluencer::{
+    crypto::signed_link::{HashAlgorithm, SignedLink},
+    events::{calendar, CalendarEntry},
+    gateway::Gateway,
+    Defluencer,
+};
+
+use futures_util::{pin_mut, TryStreamExt};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{channel::ChannelMetadata, identity::Identity, media::Media, types::IPNSAddress};
+
+use crate::errors::CliError;
+
+#[derive(Debug, Parser)]
+pub struct ExportSite {
+    /// Directory where the site is written.
+    dir: PathBuf,
+
+    /// Gateway used for links to IPFS content.
+    #[arg(long, default_value = "https://ipfs.io")]
+    gateway: String,
+
+    /// Address content as subdomains of the gateway.
+    #[arg(long)]
+    subdomain: bool,
+
+    /// Public URL of the site, used for absolute links in the RSS feed. (Optional)
+    #[arg(long)]
+    site_url: Option<String>,
+
+    /// Also add the site to IPFS.
+    #[arg(long)]
+    publish: bool,
+}
+
+/// One media page of the site.
+struct Page {
+    cid: Cid,
+    title: String,
+    timestamp: i64,
+    summary: Option<String>,
+}
+
+pub async fn export_site(identity: Cid, args: ExportSite) -> Result<(), CliError> {
+    let ExportSite {
+        dir,
+        gateway,
+        subdomain,
+        site_url,
+        publish,
+    } = args;
+
+    let gateway = match subdomain {
+        true => Gateway::subdomain(&gateway),
+        false => Gateway::path(&gateway),
+    };
+    let site_url = site_url.map(|url| url.trim_end_matches('/').to_owned());
+
+    let ipfs = IpfsService::default();
+    let defluencer = Defluencer::from(ipfs.clone());
+
+    let (identity, addr, metadata) = resolve_channel(&ipfs, identity).await?;
+
+    let index = match metadata.content_index {
+        Some(index) => index,
+        None => {
+            return Err(CliError::NotFound(format!(
+                "Channel {} has no content",
+                addr
+            )))
+        }
+    };
+
+    let media_dir = dir.join("media");
+    tokio::fs::create_dir_all(&media_dir)
+        .await
+        .map_err(defluencer::errors::Error::from)?;
+
+    println!("Wait For Pages To Be Generated...");
+
+    let stream = defluencer.stream_content_rev_chrono(index);
+    pin_mut!(stream);
+
+    let mut pages = Vec::new();
+
+    while let Some(cid) = stream.try_next().await? {
+        let signed_link = ipfs
+            .dag_get::<&str, SignedLink>(cid, None, Codec::default())
+            .await?;
+
+        let media = ipfs
+            .dag_get::<&str, Media>(signed_link.link.link, None, Codec::default())
+            .await?;
+
+        let (page, body) = match media {
+            Media::Blog(post) => {
+                let mut body = String::new();
+
+                if let Some(image) = post.image {
+                    let _ = writeln!(body, "<img src=\"{}\">", gateway.ipfs(image.link));
+                }
+
+                let _ = writeln!(
+                    body,
+                    "<p><a href=\"{}\">Read the article</a></p>",
+                    escape(&gateway.file(post.content.link, &format!("{}.md", post.title)))
+                );
+
+                let page = Page {
+                    cid,
+                    title: post.title,
+                    timestamp: post.user_timestamp,
+                    summary: post.summary,
+                };
+
+                (page, body)
+            }
+            Media::Video(video) => {
+                let mut body = String::new();
+
+                if let Some(image) = video.image {
+                    let _ = writeln!(body, "<img src=\"{}\">", gateway.ipfs(image.link));
+                }
+
+                let _ = writeln!(
+                    body,
+                    "<p><a href=\"{}\">Watch the video</a></p>",
+                    gateway.ipfs(video.video.link)
+                );
+
+                if let Some(duration) = video.duration {
+                    let _ = writeln!(body, "<p>Duration: {:.0} seconds</p>", duration);
+                }
+
+                let page = Page {
+                    cid,
+                    title: video.title,
+                    timestamp: video.user_timestamp,
+                    summary: video.summary,
+                };
+
+                (page, body)
+            }
+            Media::Event(event) => {
+                let mut body = String::new();
+
+                if let Some(image) = event.image {
+                    let _ = writeln!(body, "<img src=\"{}\">", gateway.ipfs(image.link));
+                }
+
+                if let Some(date) = Utc.timestamp_opt(event.start, 0).single() {
+                    let _ = writeln!(body, "<p>Starts: {}</p>", date.to_rfc2822());
+                }
+
+                if let Some(location) = event.location.as_deref() {
+                    let _ = writeln!(body, "<p>Location: {}</p>", escape(location));
+                }
+
+                if let Some(stream) = event.stream {
+                    let _ = writeln!(
+                        body,
+                        "<p><a href=\"{}\">Watch the stream</a></p>",
+                        gateway.ipns(stream)
+                    );
+                }
+
+                let page = Page {
+                    cid,
+                    title: event.title,
+                    timestamp: event.user_timestamp,
+                    summary: event.summary,
+                };
+
+                (page, body)
+            }
+            Media::Audio(audio) => {
+                let mut body = String::new();
+
+                if let Some(image) = audio.image {
+                    let _ = writeln!(body, "<img src=\"{}\">", gateway.ipfs(image.link));
+                }
+
+                let _ = writeln!(
+                    body,
+                    "<p><a href=\"{}\">Listen to the episode</a></p>",
+                    gateway.ipfs(audio.audio.link)
+                );
+
+                let extension = audio
+                    .mime_type
+                    .as_deref()
+                    .and_then(mime_guess::get_mime_extensions_str)
+                    .and_then(|extensions| extensions.first())
+                    .map(|extension| format!(".{}", extension))
+                    .unwrap_or_default();
+                let filename = format!("{}{}", audio.title, extension);
+
+                let _ = writeln!(
+                    body,
+                    "<p><a href=\"{}\">Download</a></p>",
+                    escape(&gateway.download(audio.audio.link, &filename))
+                );
+
+                if let Some(duration) = audio.duration {
+                    let _ = writeln!(body, "<p>Duration: {:.0} seconds</p>", duration);
+                }
+
+                let page = Page {
+                    cid,
+                    title: audio.title,
+                    timestamp: audio.user_timestamp,
+                    summary: audio.summary,
+                };
+
+                (page, body)
+            }
+            Media::Comment(_) => continue,
+        };
+
+        let html = media_page(&identity.name, &page, &body, &signed_link, &gateway);
+
+        tokio::fs::write(media_dir.join(format!("{}.html", cid)), html)
+            .await
+            .map_err(defluencer::errors::Error::from)?;
+
+        pages.push(page);
+    }
+
+    let html = index_page(&identity, &pages, &gateway);
+
+    tokio::fs::write(dir.join("index.html"), html)
+        .await
+        .map_err(defluencer::errors::Error::from)?;
+
+    let rss = rss_feed(&identity, &pages, site_url.as_deref(), &gateway);
+
+    tokio::fs::write(dir.join("rss.xml"), rss)
+        .await
+        .map_err(defluencer::errors::Error::from)?;
+
+    println!("✅ Exported {} Pages To {}", pages.len(), dir.display());
+
+    if publish {
+        let cid = ipfs.add_dir(&dir).await?;
+
+        println!("✅ Site Added\nCID: {}", cid);
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct ExportCalendar {
+    /// Path of the iCalendar file written.
+    file: PathBuf,
+}
+
+pub async fn export_calendar(identity: Cid, args: ExportCalendar) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+    let defluencer = Defluencer::from(ipfs.clone());
+
+    let (identity, addr, metadata) = resolve_channel(&ipfs, identity).await?;
+
+    let index = match metadata.content_index {
+        Some(index) => index,
+        None => {
+            return Err(CliError::NotFound(format!(
+                "Channel {} has no content",
+                addr
+            )))
+        }
+    };
+
+    println!("Wait For Events To Be Found...");
+
+    let stream = defluencer.stream_content_rev_chrono(index);
+    pin_mut!(stream);
+
+    let mut entries = Vec::new();
+
+    while let Some(cid) = stream.try_next().await? {
+        let event = match ipfs
+            .dag_get::<&str, Media>(cid, Some("/link"), Codec::default())
+            .await?
+        {
+            Media::Event(event) => event,
+            _ => continue,
+        };
+
+        let attendees = match metadata.rsvp_index {
+            Some(index) => defluencer
+                .event_attendees(index, cid)
+                .await?
+                .attendees
+                .len(),
+            None => 0,
+        };
+
+        entries.push(CalendarEntry {
+            cid,
+            event,
+            attendees,
+        });
+    }
+
+    let ics = calendar(&identity.name, &entries);
+
+    tokio::fs::write(&args.file, ics)
+        .await
+        .map_err(defluencer::errors::Error::from)?;
+
+    println!(
+        "✅ Exported {} Events To {}",
+        entries.len(),
+        args.file.display()
+    );
+
+    Ok(())
+}
+
+async fn resolve_channel(
+    ipfs: &IpfsService,
+    identity: Cid,
+) -> Result<(Identity, IPNSAddress, ChannelMetadata), CliError> {
+    let identity = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    let addr = match identity.ipns_addr {
+        Some(addr) => addr,
+        None => {
+            return Err(CliError::NotFound(format!(
+                "Identity {} has no IPNS address",
+                identity.name
+            )))
+        }
+    };
+
+    let cid = ipfs.name_resolve(addr).await?;
+    let metadata = ipfs
+        .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+        .await?;
+
+    Ok((identity, addr, metadata))
+}
+
+fn index_page(identity: &Identity, pages: &[Page], gateway: &Gateway) -> String {
+    let mut list = String::new();
+
+    for page in pages {
+        let _ = writeln!(
+            list,
+            "<li><a href=\"media/{}.html\">{}</a> <time>{}</time></li>",
+            page.cid,
+            escape(&page.title),
+            date(page.timestamp)
+        );
+    }
+
+    let mut header = String::new();
+
+    if let Some(avatar) = identity.avatar {
+        let _ = writeln!(header, "<img src=\"{}\">", gateway.ipfs(avatar.link));
+    }
+
+    if let Some(bio) = identity.bio.as_ref() {
+        let _ = writeln!(header, "<p>{}</p>", escape(bio));
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{name}</title>\n\
+        <link rel=\"alternate\" type=\"application/rss+xml\" href=\"rss.xml\">\n</head>\n<body>\n\
+        <h1>{name}</h1>\n{header}<ul>\n{list}</ul>\n</body>\n</html>\n",
+        name = escape(&identity.name),
+        header = header,
+        list = list,
+    )
+}
+
+fn media_page(
+    name: &str,
+    page: &Page,
+    body: &str,
+    signed_link: &SignedLink,
+    gateway: &Gateway,
+) -> String {
+    let summary = page
+        .summary
+        .as_ref()
+        .map(|summary| format!("<p>{}</p>\n", escape(summary)))
+        .unwrap_or_default();
+
+    let (algorithm, signer) = match signed_link.hash_algo {
+        HashAlgorithm::EthereumLedgerApp => ("Ethereum", signed_link.get_address()),
+        HashAlgorithm::BitcoinLedgerApp => ("Bitcoin", hex::encode(&signed_link.public_key)),
+    };
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n\
+        <p><a href=\"../index.html\">{name}</a></p>\n<h1>{title}</h1>\n<time>{date}</time>\n{summary}{body}\
+        <section>\n<h2>Signature</h2>\n<dl>\n\
+        <dt>Signed Content</dt><dd><a href=\"{url}\">{cid}</a></dd>\n\
+        <dt>Media</dt><dd>{media}</dd>\n\
+        <dt>Algorithm</dt><dd>{algorithm}</dd>\n\
+        <dt>Signer</dt><dd>{signer}</dd>\n\
+        <dt>Signature</dt><dd>{signature}</dd>\n\
+        </dl>\n</section>\n</body>\n</html>\n",
+        title = escape(&page.title),
+        name = escape(name),
+        date = date(page.timestamp),
+        summary = summary,
+        body = body,
+        url = gateway.ipfs(page.cid),
+        cid = page.cid,
+        media = signed_link.link.link,
+        algorithm = algorithm,
+        signer = signer,
+        signature = hex::encode(&signed_link.signature),
+    )
+}
+
+fn rss_feed(
+    identity: &Identity,
+    pages: &[Page],
+    site_url: Option<&str>,
+    gateway: &Gateway,
+) -> String {
+    let mut items = String::new();
+
+    for page in pages {
+        let link = match site_url {
+            Some(url) => format!("{}/media/{}.html", url, page.cid),
+            None => gateway.ipfs(page.cid),
+        };
+
+        let pub_date = Utc
+            .timestamp_opt(page.timestamp, 0)
+            .single()
+            .map(|date| date.to_rfc2822())
+            .unwrap_or_default();
+
+        let description = page.summary.as_deref().map(escape).unwrap_or_default();
+
+        let _ = writeln!(
+            items,
+            "<item><title>{}</title><link>{}</link><guid isPermaLink=\"false\">{}</guid>\
+            <pubDate>{}</pubDate><description>{}</description></item>",
+            escape(&page.title),
+            escape(&link),
+            page.cid,
+            pub_date,
+            description
+        );
+    }
+
+    let link = site_url
+        .map(|url| url.to_owned())
+        .unwrap_or_else(|| gateway.url());
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n\
+        <title>{}</title>\n<link>{}</link>\n<description>{}</description>\n{}</channel>\n</rss>\n",
+        escape(&identity.name),
+        escape(&link),
+        escape(identity.bio.as_deref().unwrap_or_default()),
+        items
+    )
+}
+
+fn date(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
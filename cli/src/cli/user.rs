@@ -1,23 +1,52 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, FixedOffset, Utc};
 
 use cid::Cid;
 
 use clap::{Parser, Subcommand};
 
 use defluencer::{
+    chat::encode_message,
+    claims::proof_source,
     crypto::{
-        ledger::{BitcoinLedgerApp, EthereumLedgerApp},
+        ledger::{BitcoinLedgerApp, DerivationPath, EthereumLedgerApp},
         signers::BitcoinSigner,
         signers::EthereumSigner,
         signers::Signer,
     },
-    errors::Error,
-    user::User,
+    dm::{
+        self,
+        x25519_dalek::{PublicKey, StaticSecret},
+        DirectMessages, History,
+    },
+    recovery::{collect_vouches, create_recovery},
+    user::{
+        validate_identity, IdentityPatch, Patch, User, MAX_LOCATION_LENGTH, MAX_PROFILE_LINKS,
+        MAX_PRONOUNS_LENGTH,
+    },
+    utils::add_image,
+    Defluencer,
 };
 
+use futures_util::{pin_mut, TryStreamExt};
+
 use ipfs_api::{responses::Codec, IpfsService};
 
-use linked_data::identity::Identity;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    cli::import::{import, Import},
+    errors::CliError,
+};
+
+use linked_data::{
+    channel::{live::LiveSettings, ChannelMetadata},
+    identity::{Claim, Identity, Platform, ProfileField, ProfileLink, Recovery},
+    media::chat::{ChatInfo, ChatMessage, ChatSettings, MessageType},
+    messaging::DirectMessage as DirectMessageSchema,
+    types::IPNSAddress,
+};
 
 #[derive(clap::ValueEnum, Clone, Debug)]
 enum Blockchain {
@@ -35,6 +64,20 @@ pub struct UserCLI {
     #[arg(long, default_value = "0")]
     account: u32,
 
+    /// Hardened account, m/44'/0'/<BITCOIN_ACCOUNT>'/0/<ACCOUNT> (BIP-44). Bitcoin only.
+    ///
+    /// Bitcoin signatures use the account index as the address index of this account.
+    #[arg(long, default_value = "0")]
+    bitcoin_account: u32,
+
+    /// Change, 0 for receiving and 1 for change addresses (BIP-44). Bitcoin only.
+    #[arg(long, default_value = "0")]
+    change: u32,
+
+    /// Use the testnet coin type. Bitcoin only.
+    #[arg(long)]
+    testnet: bool,
+
     /// Creators identity CID
     #[arg(long)]
     creator: Cid,
@@ -43,25 +86,50 @@ pub struct UserCLI {
     cmd: Media,
 }
 
-pub async fn user_cli(cli: UserCLI) {
-    let res = match cli.blockchain {
+pub async fn user_cli(cli: UserCLI) -> Result<(), CliError> {
+    // Direct messages use their own key, no wallet needed.
+    let cmd = match cli.cmd {
+        Media::Dm(args) => return direct_message(args, cli.creator).await,
+        Media::Identity(args) => return identity_cli(args, cli.creator).await,
+        cmd => cmd,
+    };
+
+    match cli.blockchain {
         Blockchain::Bitcoin => {
             let app = BitcoinLedgerApp::default();
 
-            let signer = BitcoinSigner::new(app, cli.account);
-
-            let addr = match signer.get_public_address() {
-                Ok(addr) => addr,
-                Err(e) => {
-                    eprintln!("❗ Wallet: {:#?}", e);
-                    return;
-                }
+            let path = DerivationPath {
+                coin_type: if cli.testnet {
+                    DerivationPath::TESTNET
+                } else {
+                    DerivationPath::MAINNET
+                },
+                account: cli.bitcoin_account,
+                change: cli.change,
+                index: cli.account,
             };
 
-            match cli.cmd {
+            let signer = BitcoinSigner::with_path(app, path);
+
+            let addr = signer.get_public_address().map_err(CliError::Wallet)?;
+
+            match cmd {
                 Media::Microblog(args) => micro_blog(args, cli.creator, addr, signer).await,
                 Media::Blog(args) => blog(args, cli.creator, addr, signer).await,
                 Media::Video(args) => video(args, cli.creator, addr, signer).await,
+                Media::Listing(args) => listing(args, cli.creator, addr, signer).await,
+                Media::Upvote(args) => upvote(args, cli.creator, addr, signer).await,
+                Media::Event(args) => event(args, cli.creator, addr, signer).await,
+                Media::Rsvp(args) => rsvp(args, cli.creator, addr, signer).await,
+                Media::Approve(args) => approve(args, cli.creator, addr, signer).await,
+                Media::ChatSettings(args) => chat_settings(args, cli.creator, addr, signer).await,
+                Media::Succeed(args) => succeed(args, cli.creator, addr, signer).await,
+                Media::Claim(args) => claim(args, cli.creator, addr, signer).await,
+                Media::Import(args) => import(args, cli.creator, addr, signer).await,
+                Media::Vouch(_) => Err(CliError::InvalidInput(String::from(
+                    "vouches must be signed with Ethereum",
+                ))),
+                Media::Dm(_) | Media::Identity(_) => unreachable!(),
             }
         }
         Blockchain::Ethereum => {
@@ -69,24 +137,25 @@ pub async fn user_cli(cli: UserCLI) {
 
             let signer = EthereumSigner::new(app, cli.account);
 
-            let addr = match signer.get_public_address() {
-                Ok(addr) => addr,
-                Err(e) => {
-                    eprintln!("❗ Wallet: {:#?}", e);
-                    return;
-                }
-            };
+            let addr = signer.get_public_address().map_err(CliError::Wallet)?;
 
-            match cli.cmd {
+            match cmd {
                 Media::Microblog(args) => micro_blog(args, cli.creator, addr, signer).await,
                 Media::Blog(args) => blog(args, cli.creator, addr, signer).await,
                 Media::Video(args) => video(args, cli.creator, addr, signer).await,
+                Media::Listing(args) => listing(args, cli.creator, addr, signer).await,
+                Media::Upvote(args) => upvote(args, cli.creator, addr, signer).await,
+                Media::Event(args) => event(args, cli.creator, addr, signer).await,
+                Media::Rsvp(args) => rsvp(args, cli.creator, addr, signer).await,
+                Media::Approve(args) => approve(args, cli.creator, addr, signer).await,
+                Media::ChatSettings(args) => chat_settings(args, cli.creator, addr, signer).await,
+                Media::Succeed(args) => succeed(args, cli.creator, addr, signer).await,
+                Media::Claim(args) => claim(args, cli.creator, addr, signer).await,
+                Media::Import(args) => import(args, cli.creator, addr, signer).await,
+                Media::Vouch(args) => vouch(args, cli.creator, addr, signer).await,
+                Media::Dm(_) | Media::Identity(_) => unreachable!(),
             }
         }
-    };
-
-    if let Err(e) = res {
-        eprintln!("❗ IPFS: {:#?}", e);
     }
 }
 
@@ -100,6 +169,42 @@ enum Media {
 
     /// Create new video post.
     Video(Video),
+
+    /// Create new registry listing for a channel.
+    Listing(Listing),
+
+    /// Create new upvote on a comment.
+    Upvote(Upvote),
+
+    /// Create new event or premiere.
+    Event(Event),
+
+    /// Answer an event invitation.
+    Rsvp(Rsvp),
+
+    /// Approve an update proposal of a co-owned channel.
+    Approve(Approve),
+
+    /// Change the live chat settings of a channel you moderate.
+    ChatSettings(ChatRules),
+
+    /// Take over an inactive channel you were designated successor of.
+    Succeed(Succeed),
+
+    /// Claim an account on another platform.
+    Claim(ClaimAccount),
+
+    /// Import videos and podcast episodes from other platforms to your channel.
+    Import(Import),
+
+    /// Vouch for the recovery of an identity that trusts your channel. Ethereum only.
+    Vouch(Vouch),
+
+    /// Encrypted direct messages with another user.
+    Dm(DirectMessage),
+
+    /// Manage your identity.
+    Identity(IdentityCommand),
 }
 
 #[derive(Debug, Parser)]
@@ -111,6 +216,10 @@ pub struct MicroBlog {
     /// Cid of the media being commented on. (Optional)
     #[arg(long)]
     origin: Option<Cid>,
+
+    /// Submit the comment to this channel's comment ingestion daemon. (Optional)
+    #[arg(long)]
+    submit: Option<IPNSAddress>,
 }
 
 async fn micro_blog(
@@ -118,7 +227,7 @@ async fn micro_blog(
     identity: Cid,
     addr: String,
     signer: impl Signer + Clone,
-) -> Result<(), Error> {
+) -> Result<(), CliError> {
     let ipfs = IpfsService::default();
 
     let id = ipfs
@@ -127,11 +236,20 @@ async fn micro_blog(
 
     let addr = Some(addr);
     if id.eth_addr != addr && id.btc_addr != addr {
-        eprintln!("❗ Wallet address mismatch.");
-        return Ok(());
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
     }
 
-    let user = User::new(ipfs, signer, identity);
+    if args.submit.is_some() && args.origin.is_none() {
+        return Err(CliError::InvalidInput(String::from(
+            "Only comments can be submitted, specify an origin",
+        )));
+    }
+
+    let user = User::new(ipfs.clone(), signer, identity);
 
     println!("Confirm Signature...");
 
@@ -141,6 +259,12 @@ async fn micro_blog(
 
     println!("✅ Created Micro Blog Post\nCID: {}", cid);
 
+    if let Some(channel) = args.submit {
+        Defluencer::from(ipfs).submit_comment(channel, cid).await?;
+
+        println!("✅ Submitted Comment To {}", channel);
+    }
+
     Ok(())
 }
 
@@ -161,6 +285,14 @@ pub struct Blog {
     /// Total word count. (Optional)
     #[arg(long)]
     word_count: Option<u64>,
+
+    /// Short abstract of the text. (Optional)
+    #[arg(long)]
+    summary: Option<String>,
+
+    /// Comma separated topics, e.g. music,live. (Optional)
+    #[arg(long, value_delimiter = ',')]
+    tags: Vec<String>,
 }
 
 async fn blog(
@@ -168,7 +300,7 @@ async fn blog(
     identity: Cid,
     addr: String,
     signer: impl Signer + Clone,
-) -> Result<(), Error> {
+) -> Result<(), CliError> {
     let ipfs = IpfsService::default();
 
     let id = ipfs
@@ -177,8 +309,11 @@ async fn blog(
 
     let addr = Some(addr);
     if id.eth_addr != addr && id.btc_addr != addr {
-        eprintln!("❗ Wallet address mismatch.");
-        return Ok(());
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
     }
 
     let Blog {
@@ -186,6 +321,8 @@ async fn blog(
         image,
         content,
         word_count,
+        summary,
+        tags,
     } = args;
 
     let user = User::new(ipfs, signer, identity);
@@ -193,7 +330,7 @@ async fn blog(
     println!("Confirm Signature...");
 
     let (cid, _) = user
-        .create_blog_post(title, image, content, word_count, false)
+        .create_blog_post(title, image, content, word_count, summary, tags, false)
         .await?;
 
     println!("✅ Created Blog Post\nCID: {}", cid);
@@ -214,6 +351,18 @@ pub struct Video {
     /// Processed video timecode CID.
     #[arg(long)]
     video: Cid,
+
+    /// Short abstract of the video. (Optional)
+    #[arg(long)]
+    summary: Option<String>,
+
+    /// Comma separated topics, e.g. music,live. (Optional)
+    #[arg(long, value_delimiter = ',')]
+    tags: Vec<String>,
+
+    /// The video is the archive of a live stream.
+    #[arg(long)]
+    live: bool,
 }
 
 async fn video(
@@ -221,7 +370,7 @@ async fn video(
     identity: Cid,
     addr: String,
     signer: impl Signer + Clone,
-) -> Result<(), Error> {
+) -> Result<(), CliError> {
     let ipfs = IpfsService::default();
 
     let id = ipfs
@@ -230,23 +379,1083 @@ async fn video(
 
     let addr = Some(addr);
     if id.eth_addr != addr && id.btc_addr != addr {
-        eprintln!("❗ Wallet address mismatch.");
-        return Ok(());
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
     }
 
     let Video {
         title,
         image,
         video,
+        summary,
+        tags,
+        live,
     } = args;
 
     let user = User::new(ipfs, signer, identity);
 
     println!("Confirm Signature...");
 
-    let (cid, _) = user.create_video_post(title, video, image, false).await?;
+    let (cid, _) = user
+        .create_video_post(title, video, image, summary, tags, live, false)
+        .await?;
 
     println!("✅ Created Video\nCID: {}", cid);
 
     Ok(())
 }
+
+#[derive(Debug, Parser)]
+pub struct Listing {
+    /// Address of the channel to list.
+    #[arg(long)]
+    channel: IPNSAddress,
+
+    /// Topics to be listed under.
+    #[arg(long, required = true)]
+    topic: Vec<String>,
+
+    /// Registry channel address to submit the listing to. (Optional)
+    #[arg(long)]
+    registry: Option<IPNSAddress>,
+}
+
+async fn listing(
+    args: Listing,
+    identity: Cid,
+    addr: String,
+    signer: impl Signer + Clone,
+) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    let addr = Some(addr);
+    if id.eth_addr != addr && id.btc_addr != addr {
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
+    }
+
+    let Listing {
+        channel,
+        topic,
+        registry,
+    } = args;
+
+    let user = User::new(ipfs.clone(), signer, identity);
+
+    println!("Confirm Signature...");
+
+    let (cid, _) = user.create_listing(channel, topic, false).await?;
+
+    println!("✅ Created Listing\nCID: {}", cid);
+
+    let registry = match registry {
+        Some(registry) => registry,
+        None => return Ok(()),
+    };
+
+    let root = ipfs.name_resolve(registry).await?;
+    let metadata = ipfs
+        .dag_get::<&str, ChannelMetadata>(root, None, Codec::default())
+        .await?;
+
+    let topic = match metadata.agregation_channel {
+        Some(topic) => topic,
+        None => {
+            return Err(CliError::NotFound(format!(
+                "Registry {} has no aggregation topic",
+                registry
+            )))
+        }
+    };
+
+    let defluencer = Defluencer::from(ipfs);
+
+    defluencer.submit_listing(topic, cid).await?;
+
+    println!("✅ Listing Submitted To Registry {}", registry);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Upvote {
+    /// CID of the comment being upvoted.
+    #[arg(long)]
+    comment: Cid,
+}
+
+async fn upvote(
+    args: Upvote,
+    identity: Cid,
+    addr: String,
+    signer: impl Signer + Clone,
+) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    let addr = Some(addr);
+    if id.eth_addr != addr && id.btc_addr != addr {
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
+    }
+
+    let user = User::new(ipfs, signer, identity);
+
+    println!("Confirm Signature...");
+
+    let (cid, _) = user.create_upvote(args.comment, false).await?;
+
+    println!("✅ Created Upvote\nCID: {}", cid);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Event {
+    /// The event title.
+    #[arg(long)]
+    title: String,
+
+    /// Start time in RFC 3339 format. e.g. 2024-05-01T20:00:00-04:00
+    #[arg(long)]
+    start: DateTime<FixedOffset>,
+
+    /// Duration in minutes. (Optional)
+    #[arg(long)]
+    duration: Option<u64>,
+
+    /// Address or place name of an in person event. (Optional)
+    #[arg(long)]
+    location: Option<String>,
+
+    /// Address of the channel streaming the event. (Optional)
+    #[arg(long)]
+    stream: Option<IPNSAddress>,
+
+    /// Short abstract of the event. (Optional)
+    #[arg(long)]
+    summary: Option<String>,
+
+    /// Comma separated topics, e.g. music,live. (Optional)
+    #[arg(long, value_delimiter = ',')]
+    tags: Vec<String>,
+}
+
+async fn event(
+    args: Event,
+    identity: Cid,
+    addr: String,
+    signer: impl Signer + Clone,
+) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    let addr = Some(addr);
+    if id.eth_addr != addr && id.btc_addr != addr {
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
+    }
+
+    let Event {
+        title,
+        start,
+        duration,
+        location,
+        stream,
+        summary,
+        tags,
+    } = args;
+
+    if location.is_none() && stream.is_none() {
+        return Err(CliError::InvalidInput(String::from(
+            "Specify a location, a stream or both",
+        )));
+    }
+
+    let user = User::new(ipfs, signer, identity);
+
+    println!("Confirm Signature...");
+
+    let (cid, _) = user
+        .create_event(
+            title,
+            start.timestamp(),
+            duration.map(|minutes| minutes * 60),
+            location,
+            stream,
+            summary,
+            tags,
+            false,
+        )
+        .await?;
+
+    println!("✅ Created Event\nCID: {}", cid);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Rsvp {
+    /// CID of the event.
+    #[arg(long)]
+    event: Cid,
+
+    /// Decline a previous answer.
+    #[arg(long)]
+    decline: bool,
+
+    /// Submit the RSVP to this channel's RSVP daemon. (Optional)
+    #[arg(long)]
+    submit: Option<IPNSAddress>,
+}
+
+async fn rsvp(
+    args: Rsvp,
+    identity: Cid,
+    addr: String,
+    signer: impl Signer + Clone,
+) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    let addr = Some(addr);
+    if id.eth_addr != addr && id.btc_addr != addr {
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
+    }
+
+    let user = User::new(ipfs.clone(), signer, identity);
+
+    println!("Confirm Signature...");
+
+    let (cid, _) = user.create_rsvp(args.event, !args.decline, false).await?;
+
+    println!("✅ Created RSVP\nCID: {}", cid);
+
+    if let Some(channel) = args.submit {
+        Defluencer::from(ipfs).submit_rsvp(channel, cid).await?;
+
+        println!("✅ Submitted RSVP To {}", channel);
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Approve {
+    /// Co-owned channel IPNS address.
+    #[arg(long)]
+    channel: IPNSAddress,
+
+    /// CID of the proposed channel metadata.
+    #[arg(long)]
+    proposal: Cid,
+}
+
+async fn approve(
+    args: Approve,
+    identity: Cid,
+    addr: String,
+    signer: impl Signer + Clone,
+) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    let addr = Some(addr);
+    if id.eth_addr != addr && id.btc_addr != addr {
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
+    }
+
+    let user = User::new(ipfs, signer, identity);
+
+    println!("Confirm Signature...");
+
+    let cid = user
+        .approve_channel_update(args.channel, args.proposal)
+        .await?;
+
+    println!("✅ Sent Approval\nCID: {}", cid);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Succeed {
+    /// Inactive channel IPNS address.
+    #[arg(long)]
+    channel: IPNSAddress,
+
+    /// Your channel IPNS address, where followers should go.
+    #[arg(long)]
+    successor: IPNSAddress,
+}
+
+async fn succeed(
+    args: Succeed,
+    identity: Cid,
+    addr: String,
+    signer: impl Signer + Clone,
+) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    let addr = Some(addr);
+    if id.eth_addr != addr && id.btc_addr != addr {
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
+    }
+
+    let user = User::new(ipfs, signer, identity);
+
+    println!("Confirm Signature...");
+
+    let cid = user.claim_succession(args.channel, args.successor).await?;
+
+    println!("✅ Sent Succession Claim\nCID: {}", cid);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct Vouch {
+    /// Recovery node CID, shared by the identity owner.
+    #[arg(long)]
+    recovery: Cid,
+}
+
+async fn vouch(
+    args: Vouch,
+    identity: Cid,
+    addr: String,
+    signer: impl Signer + Clone,
+) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    if id.eth_addr.as_ref() != Some(&addr) {
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr, identity
+        )));
+    }
+
+    let user = User::new(ipfs, signer, identity);
+
+    println!("Confirm Signature...");
+
+    let cid = user.vouch_recovery(args.recovery).await?;
+
+    println!("✅ Sent Vouch\nCID: {}", cid);
+
+    Ok(())
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum ClaimPlatform {
+    Github,
+    Twitter,
+    Website,
+}
+
+impl From<ClaimPlatform> for Platform {
+    fn from(platform: ClaimPlatform) -> Self {
+        match platform {
+            ClaimPlatform::Github => Platform::GitHub,
+            ClaimPlatform::Twitter => Platform::Twitter,
+            ClaimPlatform::Website => Platform::Website,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct ClaimAccount {
+    /// Platform of the account.
+    #[arg(long, value_enum)]
+    platform: ClaimPlatform,
+
+    /// User name on the platform, domain name for websites.
+    #[arg(long)]
+    account: String,
+
+    /// Url of a public gist, post or https hosted text file containing your wallet address.
+    #[arg(long)]
+    proof: String,
+}
+
+async fn claim(
+    args: ClaimAccount,
+    identity: Cid,
+    addr: String,
+    signer: impl Signer + Clone,
+) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    let addr = Some(addr);
+    if id.eth_addr != addr && id.btc_addr != addr {
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
+    }
+
+    let ClaimAccount {
+        platform,
+        account,
+        proof,
+    } = args;
+
+    let claim = Claim {
+        user_timestamp: 0,
+        platform: platform.into(),
+        account,
+        proof,
+    };
+
+    if proof_source(&claim).is_none() {
+        return Err(CliError::InvalidInput(format!(
+            "Proof {} is not hosted by account {}",
+            claim.proof, claim.account
+        )));
+    }
+
+    let mut user = User::new(ipfs, signer, identity);
+
+    println!("Confirm Signature...");
+
+    let (cid, _) = user
+        .add_claim(claim.platform, claim.account, claim.proof)
+        .await?;
+
+    println!("✅ Added Claim To Identity\nCID: {}", cid);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct ChatRules {
+    /// Channel IPNS address.
+    #[arg(long)]
+    channel: IPNSAddress,
+
+    /// Minimum number of seconds between messages of the same user. (Optional)
+    #[arg(long)]
+    slow_mode: Option<u32>,
+
+    /// Only followers of the channel can chat.
+    #[arg(long)]
+    followers_only: bool,
+
+    /// Messages can only contain emotes.
+    #[arg(long)]
+    emote_only: bool,
+}
+
+async fn chat_settings(
+    args: ChatRules,
+    identity: Cid,
+    addr: String,
+    signer: impl Signer + Clone,
+) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    let addr = Some(addr);
+    if id.eth_addr != addr && id.btc_addr != addr {
+        return Err(CliError::Unauthorized(format!(
+            "Wallet address {} does not match identity {}",
+            addr.unwrap_or_default(),
+            identity
+        )));
+    }
+
+    let cid = ipfs.name_resolve(args.channel).await?;
+    let metadata = ipfs
+        .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+        .await?;
+
+    let topic = match metadata.live {
+        Some(ipld) => {
+            ipfs.dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                .await?
+                .chat_topic
+        }
+        None => None,
+    };
+
+    let topic = match topic {
+        Some(topic) => topic,
+        None => {
+            return Err(CliError::NotFound(format!(
+                "Channel {} has no live chat",
+                args.channel
+            )))
+        }
+    };
+
+    let peer_id = match ipfs.peer_id().await {
+        Ok(peer_id) => peer_id,
+        Err(e) => return Err(CliError::IpfsOffline(e.into())),
+    };
+
+    let user = User::new(ipfs.clone(), signer, identity);
+
+    let settings = ChatSettings {
+        slow_mode: args.slow_mode,
+        followers_only: args.followers_only,
+        emote_only: args.emote_only,
+        timestamp: Utc::now().timestamp(),
+    };
+
+    println!("Confirm Signatures...");
+
+    let info = ChatInfo {
+        name: id.name,
+        node: peer_id,
+    };
+
+    let signature = user.chat_signature(info).await?;
+    let settings = user.chat_settings(settings).await?;
+
+    let message = ChatMessage {
+        message: MessageType::Settings(settings.into()),
+        signature: signature.into(),
+    };
+
+    let data = encode_message(&message)?;
+
+    ipfs.pubsub_pub(&topic, data).await?;
+
+    println!("✅ Sent Chat Settings\nCID: {}", settings);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct IdentityCommand {
+    #[command(subcommand)]
+    cmd: IdentitySubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum IdentitySubcommand {
+    /// Update your identity, unset fields are kept.
+    Update(UpdateIdentity),
+
+    /// List the accounts on other platforms whose claim could be verified.
+    Claims,
+
+    /// Bind a new Ethereum address to your identity after losing your keys.
+    ///
+    /// Waits until enough trusted channels vouched, see the update recovery options.
+    Recover(Recover),
+}
+
+#[derive(Debug, Parser)]
+pub struct Recover {
+    /// Ethereum address replacing the lost one.
+    #[arg(long)]
+    eth_addr: String,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum DisplayField {
+    Bio,
+    Pronouns,
+    Location,
+    Links,
+}
+
+impl From<DisplayField> for ProfileField {
+    fn from(field: DisplayField) -> Self {
+        match field {
+            DisplayField::Bio => ProfileField::Bio,
+            DisplayField::Pronouns => ProfileField::Pronouns,
+            DisplayField::Location => ProfileField::Location,
+            DisplayField::Links => ProfileField::Links,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct UpdateIdentity {
+    /// Choosen name. (Optional)
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Short biography. (Optional)
+    #[arg(long)]
+    bio: Option<String>,
+
+    /// Path to banner image file. (Optional)
+    #[arg(long)]
+    banner: Option<PathBuf>,
+
+    /// Path to avatar image file. (Optional)
+    #[arg(long)]
+    avatar: Option<PathBuf>,
+
+    /// Pronouns. (Optional)
+    #[arg(long)]
+    pronouns: Option<String>,
+
+    /// Location. (Optional)
+    #[arg(long)]
+    location: Option<String>,
+
+    /// IPNS address of your channel. (Optional)
+    #[arg(long)]
+    ipns_addr: Option<IPNSAddress>,
+
+    /// Bitcoin address. (Optional)
+    #[arg(long)]
+    btc_addr: Option<String>,
+
+    /// Ethereum address. (Optional)
+    #[arg(long)]
+    eth_addr: Option<String>,
+
+    /// Number of trusted channels that must vouch to recover your identity. (Optional)
+    #[arg(long, requires = "recovery_channels")]
+    recovery_threshold: Option<usize>,
+
+    /// Channel trusted to vouch for your recovery, repeat for each. Replaces all channels.
+    #[arg(long = "recovery-channel", requires = "recovery_threshold")]
+    recovery_channels: Vec<IPNSAddress>,
+
+    /// External link as "title=url", repeat for each link. Replaces all links. (Optional)
+    #[arg(long = "link")]
+    links: Vec<String>,
+
+    /// Profile fields in display order, comma separated. Omitted fields are hidden. (Optional)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    display: Vec<DisplayField>,
+
+    /// Fields to remove, comma separated. (Optional)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    clear: Vec<ClearField>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ClearField {
+    Bio,
+    Banner,
+    Avatar,
+    IpnsAddr,
+    BtcAddr,
+    EthAddr,
+    Pronouns,
+    Location,
+    Recovery,
+    Links,
+    Display,
+}
+
+impl UpdateIdentity {
+    /// Add the images then return the changes to apply.
+    pub async fn into_patch(self, ipfs: &IpfsService) -> Result<IdentityPatch, CliError> {
+        let UpdateIdentity {
+            name,
+            bio,
+            banner,
+            avatar,
+            pronouns,
+            location,
+            ipns_addr,
+            btc_addr,
+            eth_addr,
+            recovery_threshold,
+            recovery_channels,
+            links,
+            display,
+            clear,
+        } = self;
+
+        let links = links
+            .into_iter()
+            .map(|link| match link.split_once('=') {
+                Some((title, url)) => Ok(ProfileLink {
+                    title: title.trim().to_owned(),
+                    url: url.trim().to_owned(),
+                }),
+                None => Err(CliError::InvalidInput(format!(
+                    "Link {} must be formatted as title=url",
+                    link
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let banner = match banner {
+            Some(path) => Some(add_image(ipfs, path).await?.into()),
+            None => None,
+        };
+
+        let avatar = match avatar {
+            Some(path) => Some(add_image(ipfs, path).await?.into()),
+            None => None,
+        };
+
+        let display = display.into_iter().map(Into::into).collect();
+
+        let recovery = recovery_threshold.map(|threshold| Recovery {
+            threshold,
+            channels: recovery_channels,
+        });
+
+        Ok(IdentityPatch {
+            name,
+            bio: patch(bio, ClearField::Bio, &clear)?,
+            banner: patch(banner, ClearField::Banner, &clear)?,
+            avatar: patch(avatar, ClearField::Avatar, &clear)?,
+            ipns_addr: patch(ipns_addr, ClearField::IpnsAddr, &clear)?,
+            btc_addr: patch(btc_addr, ClearField::BtcAddr, &clear)?,
+            eth_addr: patch(eth_addr, ClearField::EthAddr, &clear)?,
+            pronouns: patch(pronouns, ClearField::Pronouns, &clear)?,
+            location: patch(location, ClearField::Location, &clear)?,
+            recovery: patch(recovery, ClearField::Recovery, &clear)?,
+            links: list_patch(links, ClearField::Links, &clear)?,
+            display: list_patch(display, ClearField::Display, &clear)?,
+        })
+    }
+}
+
+fn patch<T>(
+    value: Option<T>,
+    field: ClearField,
+    clear: &[ClearField],
+) -> Result<Patch<T>, CliError> {
+    match (value, clear.contains(&field)) {
+        (Some(_), true) => Err(CliError::InvalidInput(format!(
+            "Cannot both set and clear {:?}",
+            field
+        ))),
+        (Some(value), false) => Ok(Patch::Set(value)),
+        (None, true) => Ok(Patch::Clear),
+        (None, false) => Ok(Patch::Keep),
+    }
+}
+
+/// Lists are replaced, clearing one replaces it with an empty list.
+fn list_patch<T>(
+    list: Vec<T>,
+    field: ClearField,
+    clear: &[ClearField],
+) -> Result<Option<Vec<T>>, CliError> {
+    let list = if list.is_empty() { None } else { Some(list) };
+
+    match patch(list, field, clear)? {
+        Patch::Keep => Ok(None),
+        Patch::Set(list) => Ok(Some(list)),
+        Patch::Clear => Ok(Some(Vec::new())),
+    }
+}
+
+/// Explain the limits of the structured profile fields.
+pub fn invalid_identity(error: defluencer::errors::Error) -> CliError {
+    CliError::InvalidInput(format!(
+        "{}, pronouns are limited to {} characters, locations to {}, \
+        links to {} with a title and a web or IPFS URL, \
+        recovery thresholds between 1 and the number of channels",
+        error, MAX_PRONOUNS_LENGTH, MAX_LOCATION_LENGTH, MAX_PROFILE_LINKS
+    ))
+}
+
+async fn identity_cli(args: IdentityCommand, identity: Cid) -> Result<(), CliError> {
+    match args.cmd {
+        IdentitySubcommand::Update(args) => update_identity(args, identity).await,
+        IdentitySubcommand::Claims => verified_claims(identity).await,
+        IdentitySubcommand::Recover(args) => recover(args, identity).await,
+    }
+}
+
+async fn recover(args: Recover, identity: Cid) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let recovery = create_recovery(&ipfs, identity, args.eth_addr).await?;
+
+    println!(
+        "Share This Recovery Node With Your Trusted Channels\nCID: {}",
+        recovery
+    );
+
+    println!("Wait For Vouches...");
+
+    let vouches = collect_vouches(&ipfs, recovery).await?;
+
+    println!("✅ Identity Recovered\nVouches CID: {}", vouches);
+
+    Ok(())
+}
+
+async fn verified_claims(identity: Cid) -> Result<(), CliError> {
+    let defluencer = Defluencer::default();
+
+    println!("Wait For Proofs To Be Fetched...");
+
+    let claims = defluencer.verified_claims(identity).await?;
+
+    for (cid, claim) in claims.iter() {
+        println!(
+            "✅ {:?} {} Proof: {} Claim: {}",
+            claim.platform, claim.account, claim.proof, cid
+        );
+    }
+
+    println!("{} Verified Claims", claims.len());
+
+    Ok(())
+}
+
+async fn update_identity(args: UpdateIdentity, identity: Cid) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let patch = args.into_patch(&ipfs).await?;
+
+    let mut id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    patch.apply(&mut id);
+
+    validate_identity(&id).map_err(invalid_identity)?;
+
+    let cid = ipfs
+        .dag_put(&id, Codec::default(), Codec::default())
+        .await?;
+
+    println!("✅ Updated Identity\nCID: {}", cid);
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct DirectMessage {
+    /// Path to the direct messages secret key file.
+    #[arg(long)]
+    key_file: PathBuf,
+
+    #[command(subcommand)]
+    cmd: DmCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum DmCommand {
+    /// Create a key if needed then add it to the identity.
+    Key,
+
+    /// Send a message.
+    Send(DmSend),
+
+    /// Print the conversation history then listen for new messages.
+    Stream(DmStream),
+}
+
+#[derive(Debug, Parser)]
+pub struct DmSend {
+    /// Identity CID of the recipient.
+    #[arg(long)]
+    to: Cid,
+
+    /// The message text content.
+    #[arg(long)]
+    text: String,
+
+    /// Path to the conversation history file. (Optional)
+    #[arg(long)]
+    history: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct DmStream {
+    /// Identity CID of the other user.
+    #[arg(long)]
+    to: Cid,
+
+    /// Path to the conversation history file. (Optional)
+    #[arg(long)]
+    history: Option<PathBuf>,
+}
+
+async fn direct_message(args: DirectMessage, identity: Cid) -> Result<(), CliError> {
+    let ipfs = IpfsService::default();
+
+    let secret = match tokio::fs::read(&args.key_file).await {
+        Ok(bytes) => match <[u8; 32]>::try_from(bytes) {
+            Ok(bytes) => StaticSecret::from(bytes),
+            Err(_) => {
+                return Err(CliError::InvalidInput(format!(
+                    "Invalid key file {}",
+                    args.key_file.display()
+                )))
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if !matches!(args.cmd, DmCommand::Key) {
+                return Err(CliError::NotFound(format!(
+                    "Key file {}, create one with the key command",
+                    args.key_file.display()
+                )));
+            }
+
+            let secret = dm::generate_secret();
+
+            write_key_file(&args.key_file, &secret.to_bytes())
+                .await
+                .map_err(defluencer::errors::Error::from)?;
+
+            println!("✅ Created Key\nPath: {}", args.key_file.display());
+
+            secret
+        }
+        Err(e) => return Err(defluencer::errors::Error::from(e).into()),
+    };
+
+    match args.cmd {
+        DmCommand::Key => dm_key(ipfs, identity, secret).await,
+        DmCommand::Send(args) => dm_send(ipfs, identity, secret, args).await,
+        DmCommand::Stream(args) => dm_stream(ipfs, identity, secret, args).await,
+    }
+}
+
+/// Create the key file, readable by its owner only.
+async fn write_key_file(path: &Path, key: &[u8]) -> std::io::Result<()> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(path).await?;
+
+    file.write_all(key).await?;
+
+    file.sync_all().await
+}
+
+async fn dm_key(ipfs: IpfsService, identity: Cid, secret: StaticSecret) -> Result<(), CliError> {
+    let mut id = ipfs
+        .dag_get::<&str, Identity>(identity, None, Codec::default())
+        .await?;
+
+    id.dm_key = Some(PublicKey::from(&secret).as_bytes().to_vec());
+
+    let cid = ipfs
+        .dag_put(&id, Codec::default(), Codec::default())
+        .await?;
+
+    println!("✅ Identity Updated\nCID: {}", cid);
+
+    Ok(())
+}
+
+async fn dm_send(
+    ipfs: IpfsService,
+    identity: Cid,
+    secret: StaticSecret,
+    args: DmSend,
+) -> Result<(), CliError> {
+    let dms = DirectMessages::new(ipfs, &secret, identity, args.to).await?;
+
+    let message = dms.send(args.text).await?;
+
+    if let Some(path) = args.history {
+        History::new(path).append(&message).await?;
+    }
+
+    println!("✅ Message Sent");
+
+    Ok(())
+}
+
+async fn dm_stream(
+    ipfs: IpfsService,
+    identity: Cid,
+    secret: StaticSecret,
+    args: DmStream,
+) -> Result<(), CliError> {
+    let dms = DirectMessages::new(ipfs, &secret, identity, args.to).await?;
+
+    let history = args.history.map(History::new);
+
+    if let Some(history) = history.as_ref() {
+        for message in history.load().await? {
+            print_message(&message, identity);
+        }
+    }
+
+    println!("Wait For Messages...");
+
+    let stream = dms.stream();
+    pin_mut!(stream);
+
+    while let Some(message) = stream.try_next().await? {
+        if let Some(history) = history.as_ref() {
+            history.append(&message).await?;
+        }
+
+        print_message(&message, identity);
+    }
+
+    Ok(())
+}
+
+fn print_message(message: &DirectMessageSchema, identity: Cid) {
+    let sender = if message.identity.link == identity {
+        String::from("You")
+    } else {
+        message.identity.link.to_string()
+    };
+
+    println!("[{}] {}: {}", message.user_timestamp, sender, message.text);
+}
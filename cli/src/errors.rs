@@ -0,0 +1,117 @@
+use std::process::ExitCode;
+
+use defluencer::errors::Error;
+
+use thiserror::Error;
+
+/// Exit code reserved by clap for invalid arguments.
+const USAGE: u8 = 2;
+
+const IPFS_OFFLINE: u8 = 3;
+const IPFS_API: u8 = 4;
+const INVALID_INPUT: u8 = 5;
+const NOT_FOUND: u8 = 6;
+const UNAUTHORIZED: u8 = 7;
+const WALLET: u8 = 8;
+const IO: u8 = 9;
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("IPFS must be started beforehand. {0}")]
+    IpfsOffline(Error),
+
+    #[error("Invalid {0}")]
+    InvalidInput(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Unauthorized(String),
+
+    #[error("Wallet: {0}")]
+    Wallet(Error),
+
+    #[error("{0}")]
+    Defluencer(#[from] Error),
+}
+
+impl From<ipfs_api::errors::Error> for CliError {
+    fn from(e: ipfs_api::errors::Error) -> Self {
+        Self::Defluencer(e.into())
+    }
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> ExitCode {
+        let code = match self {
+            Self::IpfsOffline(_) => IPFS_OFFLINE,
+            Self::InvalidInput(_) => INVALID_INPUT,
+            Self::NotFound(_) => NOT_FOUND,
+            Self::Unauthorized(_) => UNAUTHORIZED,
+            Self::Wallet(_) => WALLET,
+            Self::Defluencer(e) => match e {
+                Error::IpfsApi(ipfs_api::errors::Error::Reqwest(e)) if e.is_connect() => {
+                    IPFS_OFFLINE
+                }
+                Error::IpfsApi(_) => IPFS_API,
+                Error::Cid(_) | Error::Multibase(_) | Error::Multihash(_) | Error::Theme => {
+                    INVALID_INPUT
+                }
+                Error::Image | Error::Markdown | Error::Unsupported => USAGE,
+                Error::NotFound => NOT_FOUND,
+                Error::IPNSMismatch
+                | Error::Signature
+                | Error::Signatue(_)
+                | Error::DagJose(_)
+                | Error::Timestamp
+                | Error::FutureTimestamp
+                | Error::RateLimited => UNAUTHORIZED,
+                Error::Ledger(_) => WALLET,
+                Error::IO(_) => IO,
+                _ => 1,
+            },
+        };
+
+        ExitCode::from(code)
+    }
+
+    /// Suggest what to do next, if anything.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::IpfsOffline(Error::IpfsApi(ipfs_api::errors::Error::Reqwest(e)))
+            | Self::Defluencer(Error::IpfsApi(ipfs_api::errors::Error::Reqwest(e))) => {
+                let url = e.url()?;
+
+                if e.is_connect() {
+                    return Some(format!(
+                        "Could not connect to {}. Start your IPFS daemon then try again.",
+                        url.origin().ascii_serialization()
+                    ));
+                }
+
+                Some(format!("IPFS endpoint {} failed.", url.path()))
+            }
+            Self::IpfsOffline(_) => Some("Start your IPFS daemon then try again.".to_owned()),
+            Self::Wallet(_) => {
+                Some("Connect your Ledger, unlock it and open the right app.".to_owned())
+            }
+            Self::Defluencer(Error::IpfsApi(ipfs_api::errors::Error::Ipfs(_))) => {
+                Some("The IPFS node refused the request, check the CIDs used.".to_owned())
+            }
+            Self::Defluencer(Error::Cid(_)) => {
+                Some("A CID was malformed, check the CIDs and addresses used.".to_owned())
+            }
+            Self::Defluencer(Error::NotFound) => {
+                Some("Make sure the CID or address points to the right data.".to_owned())
+            }
+            Self::Defluencer(Error::Timestamp | Error::FutureTimestamp) => {
+                Some("The content is misdated, check the clock of the signer.".to_owned())
+            }
+            Self::Defluencer(Error::Conflict { .. }) => {
+                Some("Another process updated the channel, try again.".to_owned())
+            }
+            _ => None,
+        }
+    }
+}
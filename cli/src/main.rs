@@ -1,13 +1,18 @@
 mod actors;
 mod cli;
+mod errors;
 mod server;
 
+use std::process::ExitCode;
+
 use clap::{Parser, Subcommand};
 
 use crate::cli::{
     channel::{channel_cli, ChannelCLI},
     daemon::{
+        comments::{comments_cli, Comments},
         file::{file_cli, File},
+        rsvp::{rsvp_cli, Rsvp},
         stream::{stream_cli, Stream},
     },
     node::{node_cli, NodeCLI},
@@ -29,6 +34,12 @@ enum Commands {
     /// Start the video file streaming daemon.
     File(File),
 
+    /// Start the daemon adding comments submitted to your channel.
+    Comments(Comments),
+
+    /// Start the daemon counting RSVPs to your channel events.
+    Rsvp(Rsvp),
+
     /// Channel related commands.
     Channel(ChannelCLI),
 
@@ -41,14 +52,29 @@ enum Commands {
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let cli = Defluencer::parse();
 
-    match cli.command {
+    let res = match cli.command {
         Commands::Stream(args) => stream_cli(args).await,
         Commands::File(args) => file_cli(args).await,
+        Commands::Comments(args) => comments_cli(args).await,
+        Commands::Rsvp(args) => rsvp_cli(args).await,
         Commands::Channel(args) => channel_cli(args).await,
         Commands::User(args) => user_cli(args).await,
         Commands::Node(args) => node_cli(args).await,
+    };
+
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❗ {}", e);
+
+            if let Some(hint) = e.hint() {
+                eprintln!("{}", hint);
+            }
+
+            e.exit_code()
+        }
     }
 }
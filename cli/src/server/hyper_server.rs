@@ -1,5 +1,5 @@
 use crate::{
-    actors::{SetupData, VideoData},
+    actors::{BandwidthCap, LatestStats, SetupData, Throttle, VideoData},
     server::services::put_requests,
 };
 
@@ -23,6 +23,9 @@ pub async fn start_server(
     video_tx: UnboundedSender<VideoData>,
     setup_tx: UnboundedSender<SetupData>,
     ipfs: IpfsService,
+    stats: Option<LatestStats>,
+    bandwidth: BandwidthCap,
+    throttle: Option<Throttle>,
     mut shutdown: Receiver<()>,
 ) -> Result<(), Error> {
     let listener = TcpListener::bind(server_addr).await?;
@@ -45,13 +48,19 @@ pub async fn start_server(
                 let video_tx = video_tx.clone();
                 let setup_tx = setup_tx.clone();
                 let ipfs = ipfs.clone();
+                let stats = stats.clone();
+                let bandwidth = bandwidth.clone();
+                let throttle = throttle.clone();
 
                 let service = service_fn(move |req| {
                     let video_tx = video_tx.clone();
                     let setup_tx = setup_tx.clone();
                     let ipfs = ipfs.clone();
+                    let stats = stats.clone();
+                    let bandwidth = bandwidth.clone();
+                    let throttle = throttle.clone();
 
-                    put_requests(req, video_tx, setup_tx, ipfs)
+                    put_requests(req, video_tx, setup_tx, ipfs, stats, bandwidth, throttle)
                 });
 
                 let fut = http1::Builder::new()
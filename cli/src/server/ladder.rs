@@ -0,0 +1,333 @@
+use std::{fmt, str::FromStr};
+
+use rml_rtmp::sessions::StreamMetadata;
+
+/// Qualities encoded at most, each one costs a full encoder.
+const MAX_QUALITIES: usize = 4;
+
+/// Typical H.264 bitrates at 30 fps in kbps, 60 fps streams use half more.
+const BITRATES: [(u32, u32); 6] = [
+    (2160, 13000),
+    (1440, 8000),
+    (1080, 4500),
+    (720, 3000),
+    (480, 1500),
+    (360, 800),
+];
+
+/// How the RTMP stream is turned into HLS.
+#[derive(Debug, Clone, Default)]
+pub enum Encoding {
+    /// Remux the source as is.
+    #[default]
+    Copy,
+
+    /// Transcode into qualities picked from the source.
+    Auto,
+
+    /// Transcode into these qualities.
+    Ladder(Vec<Quality>),
+}
+
+/// Incoming video, as announced by the broadcast software.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Source {
+    pub height: u32,
+    pub fps: f32,
+    pub bitrate: Option<u32>,
+}
+
+impl Source {
+    /// Returns None if the resolution or frame rate is missing or invalid.
+    pub fn from_metadata(metadata: &StreamMetadata) -> Option<Self> {
+        let source = Self {
+            height: metadata.video_height?,
+            fps: metadata.video_frame_rate?,
+            bitrate: metadata.video_bitrate_kbps,
+        };
+
+        (source.height > 0 && source.fps >= 1.0).then_some(source)
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}p{}", self.height, self.fps.round())?;
+
+        if let Some(bitrate) = self.bitrate {
+            write!(f, " {}kbps", bitrate)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// One video track of the stream, named like ```720p30```.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quality {
+    pub height: u32,
+    pub fps: u32,
+
+    /// Target bitrate in kbps.
+    pub bitrate: u32,
+}
+
+impl Quality {
+    pub fn new(height: u32, fps: u32) -> Self {
+        let base = BITRATES
+            .iter()
+            .find(|(min, _)| height >= *min)
+            .map(|(min, bitrate)| bitrate * height / min)
+            .unwrap_or(BITRATES[5].1 * height / BITRATES[5].0);
+
+        let bitrate = if fps > 30 { base * 3 / 2 } else { base };
+
+        Self {
+            height,
+            fps,
+            bitrate,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}p{}", self.height, self.fps)
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}kbps", self.name(), self.bitrate)
+    }
+}
+
+/// Parse ```720p30``` or ```720p30:3000``` with the bitrate in kbps.
+impl FromStr for Quality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, bitrate) = match s.split_once(':') {
+            Some((name, bitrate)) => (name, Some(bitrate)),
+            None => (s, None),
+        };
+
+        let (height, fps) = name
+            .split_once('p')
+            .ok_or_else(|| format!("{} is not like 720p30", name))?;
+
+        let height: u32 = match height.parse() {
+            Ok(height) if height > 0 => height,
+            _ => return Err(format!("Height {}", height)),
+        };
+
+        let fps: u32 = match fps {
+            "" => 30,
+            fps => match fps.parse() {
+                Ok(fps) if fps > 0 => fps,
+                _ => return Err(format!("Frame rate {}", fps)),
+            },
+        };
+
+        let mut quality = Self::new(height, fps);
+
+        if let Some(bitrate) = bitrate {
+            quality.bitrate = bitrate
+                .parse()
+                .map_err(|_| format!("Bitrate {}", bitrate))?;
+        }
+
+        Ok(quality)
+    }
+}
+
+/// Pick the qualities to encode, from highest to lowest.
+///
+/// The first one matches the source, the others use lower standard resolutions at 30 fps.
+/// 60 fps sources keep a 720p60 quality too. Qualities never exceed the source bitrate.
+pub fn ladder(source: Source) -> Vec<Quality> {
+    let fps = source.fps.round() as u32;
+
+    let mut top = Quality::new(source.height, fps);
+
+    if let Some(bitrate) = source.bitrate {
+        top.bitrate = top.bitrate.min(bitrate);
+    }
+
+    let lower = BITRATES
+        .iter()
+        .map(|(height, _)| *height)
+        .filter(|height| *height < source.height)
+        .flat_map(|height| {
+            let high = (height == 720 && fps > 30).then(|| Quality::new(height, fps));
+
+            high.into_iter().chain([Quality::new(height, fps.min(30))])
+        });
+
+    let mut qualities = vec![top];
+
+    for quality in lower {
+        if qualities.len() == MAX_QUALITIES {
+            break;
+        }
+
+        let previous = qualities[qualities.len() - 1];
+
+        if quality.bitrate < previous.bitrate {
+            qualities.push(quality);
+        }
+    }
+
+    qualities
+}
+
+/// FFMPEG arguments encoding each quality from the first video and audio inputs.
+///
+/// Key frames are forced every second, segments of all qualities line up.
+pub fn ffmpeg_args(qualities: &[Quality]) -> Vec<String> {
+    let mut args = Vec::new();
+
+    let outputs: String = (0..qualities.len()).map(|i| format!("[s{}]", i)).collect();
+    let mut filter = format!("[0:v]split={}{}", qualities.len(), outputs);
+
+    for (i, quality) in qualities.iter().enumerate() {
+        filter.push_str(&format!(
+            ";[s{}]scale=-2:{},fps={}[v{}]",
+            i, quality.height, quality.fps, i
+        ));
+    }
+
+    args.extend(["-filter_complex".to_owned(), filter]);
+
+    let mut stream_map = Vec::with_capacity(qualities.len() + 1);
+
+    for (i, quality) in qualities.iter().enumerate() {
+        let bitrate = format!("{}k", quality.bitrate);
+        let buffer = format!("{}k", quality.bitrate * 2);
+        let gop = quality.fps.to_string();
+
+        args.extend(["-map".to_owned(), format!("[v{}]", i)]);
+        args.extend([format!("-c:v:{}", i), "libx264".to_owned()]);
+        args.extend([format!("-preset:v:{}", i), "veryfast".to_owned()]);
+        args.extend([format!("-b:v:{}", i), bitrate.clone()]);
+        args.extend([format!("-maxrate:v:{}", i), bitrate]);
+        args.extend([format!("-bufsize:v:{}", i), buffer]);
+        args.extend([format!("-g:v:{}", i), gop.clone()]);
+        args.extend([format!("-keyint_min:v:{}", i), gop]);
+
+        stream_map.push(format!("v:{},name:{}", i, quality.name()));
+    }
+
+    args.extend(["-sc_threshold", "0"].map(String::from));
+    args.extend(["-force_key_frames", "expr:gte(t,n_forced)"].map(String::from));
+    args.extend(["-map", "0:a:0", "-c:a", "copy"].map(String::from));
+
+    stream_map.push("a:0,name:audio".to_owned());
+
+    args.extend(["-var_stream_map".to_owned(), stream_map.join(" ")]);
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quality(height: u32, fps: u32, bitrate: u32) -> Quality {
+        Quality {
+            height,
+            fps,
+            bitrate,
+        }
+    }
+
+    #[test]
+    fn source_ladders() {
+        let source = Source {
+            height: 1080,
+            fps: 59.94,
+            bitrate: None,
+        };
+
+        assert_eq!(
+            ladder(source),
+            vec![
+                quality(1080, 60, 6750),
+                quality(720, 60, 4500),
+                quality(720, 30, 3000),
+                quality(480, 30, 1500),
+            ]
+        );
+
+        let source = Source {
+            height: 720,
+            fps: 30.0,
+            bitrate: None,
+        };
+
+        assert_eq!(
+            ladder(source),
+            vec![
+                quality(720, 30, 3000),
+                quality(480, 30, 1500),
+                quality(360, 30, 800),
+            ]
+        );
+    }
+
+    #[test]
+    fn low_bitrate_source() {
+        let source = Source {
+            height: 1080,
+            fps: 30.0,
+            bitrate: Some(1200),
+        };
+
+        assert_eq!(
+            ladder(source),
+            vec![quality(1080, 30, 1200), quality(360, 30, 800)]
+        );
+    }
+
+    #[test]
+    fn parse_qualities() {
+        assert_eq!("720p".parse(), Ok(quality(720, 30, 3000)));
+        assert_eq!("720p30:3000".parse(), Ok(quality(720, 30, 3000)));
+        assert_eq!("720p60:2500".parse(), Ok(quality(720, 60, 2500)));
+
+        for garbage in [
+            "",
+            "720",
+            "p30",
+            "hdp30",
+            "720pfast",
+            "720p30:fast",
+            "0p0",
+            "0p30",
+            "720p0",
+        ] {
+            assert!(garbage.parse::<Quality>().is_err(), "{}", garbage);
+        }
+    }
+
+    #[test]
+    fn ffmpeg_outputs() {
+        let args = ffmpeg_args(&[quality(720, 30, 3000), quality(360, 30, 800)]);
+
+        let value = |flag: &str| {
+            let i = args.iter().position(|arg| arg == flag).unwrap();
+
+            args[i + 1].as_str()
+        };
+
+        assert_eq!(
+            value("-filter_complex"),
+            "[0:v]split=2[s0][s1];[s0]scale=-2:720,fps=30[v0];[s1]scale=-2:360,fps=30[v1]"
+        );
+        assert_eq!(value("-b:v:0"), "3000k");
+        assert_eq!(value("-bufsize:v:1"), "1600k");
+        assert_eq!(value("-g:v:1"), "30");
+        assert_eq!(
+            value("-var_stream_map"),
+            "v:0,name:720p30 v:1,name:360p30 a:0,name:audio"
+        );
+    }
+}
@@ -1,5 +1,9 @@
 mod hyper_server;
+mod ladder;
+mod rtmp;
 mod services;
 
 pub use hyper_server::start_server;
+pub use ladder::{Encoding, Quality};
+pub use rtmp::start_rtmp_server;
 pub use services::{M4S, MP4};
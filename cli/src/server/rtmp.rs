@@ -0,0 +1,488 @@
+use std::{
+    net::SocketAddr,
+    process::Stdio,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use defluencer::errors::Error;
+
+use super::ladder::{self, Encoding, Quality, Source};
+
+use rml_rtmp::{
+    handshake::{Handshake, HandshakeProcessResult, PeerType},
+    sessions::{ServerSession, ServerSessionConfig, ServerSessionEvent, ServerSessionResult},
+};
+
+use linked_data::channel::live::EncoderStats;
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::{mpsc::UnboundedSender, watch::Receiver, OwnedSemaphorePermit, Semaphore},
+};
+
+const FLV_AUDIO_TAG: u8 = 8;
+const FLV_VIDEO_TAG: u8 = 9;
+
+/// Status code of publish requests rejected because a stream is already published.
+const PUBLISH_BAD_NAME: &str = "NetStream.Publish.BadName";
+
+/// Seconds between encoder statistics.
+const STATS_PERIOD: &str = "5";
+
+/// Accept RTMP streams from broadcasting software and remux them into HLS.
+///
+/// Incoming audio & video are wrapped in FLV and piped to FFMPEG which PUT segments to the ingest server.
+/// Unless transcoded, key frames must be set to 1 second intervals.
+/// Only one stream is published at a time, other publish requests are rejected.
+///
+/// FFMPEG progress is sent as encoder statistics, if a sender is provided.
+pub async fn start_rtmp_server(
+    rtmp_addr: SocketAddr,
+    ingest_addr: SocketAddr,
+    encoding: Encoding,
+    stats_tx: Option<UnboundedSender<EncoderStats>>,
+    mut shutdown: Receiver<()>,
+) -> Result<(), Error> {
+    let listener = TcpListener::bind(rtmp_addr).await?;
+
+    let publishing = Arc::new(Semaphore::new(1));
+
+    println!("✅ RTMP Server Online");
+
+    loop {
+        tokio::select! {
+            res = listener.accept() => {
+                let (tcp, remote_address) = match res {
+                    Ok(val) => val,
+                    Err(e) => {
+                        eprintln!("Tcp listener error: {:#?}", e);
+                        continue
+                    }
+                };
+
+                #[cfg(debug_assertions)]
+                println!("RTMP: connection from {}", remote_address);
+
+                let shutdown = shutdown.clone();
+                let stats_tx = stats_tx.clone();
+                let encoding = encoding.clone();
+                let publishing = publishing.clone();
+
+                tokio::task::spawn(async move {
+                    if let Err(e) = rtmp_connection(
+                        tcp,
+                        ingest_addr,
+                        encoding,
+                        stats_tx,
+                        publishing,
+                        shutdown,
+                    )
+                    .await
+                    {
+                        eprintln!("❗ RTMP {}: {}", remote_address, e);
+                    }
+                });
+            }
+
+            res = shutdown.changed() => {
+                match res {
+                    Ok(()) => break,
+                    Err(e) => {
+                        eprintln!("Shutdown receiver error: {:#?}", e);
+                        break
+                    }
+                }
+            }
+        }
+    }
+
+    println!("❌ RTMP Server Offline");
+
+    Ok(())
+}
+
+async fn rtmp_connection(
+    mut tcp: TcpStream,
+    ingest_addr: SocketAddr,
+    encoding: Encoding,
+    stats_tx: Option<UnboundedSender<EncoderStats>>,
+    publishing: Arc<Semaphore>,
+    mut shutdown: Receiver<()>,
+) -> Result<(), Error> {
+    let mut buf = vec![0u8; 4096];
+
+    let mut handshake = Handshake::new(PeerType::Server);
+
+    let remaining = loop {
+        let count = tcp.read(&mut buf).await?;
+
+        if count == 0 {
+            return Ok(());
+        }
+
+        match handshake.process_bytes(&buf[..count]) {
+            Ok(HandshakeProcessResult::InProgress { response_bytes }) => {
+                tcp.write_all(&response_bytes).await?;
+            }
+            Ok(HandshakeProcessResult::Completed {
+                response_bytes,
+                remaining_bytes,
+            }) => {
+                tcp.write_all(&response_bytes).await?;
+                break remaining_bytes;
+            }
+            Err(e) => return Err(rtmp_error(e)),
+        }
+    };
+
+    let (mut session, results) =
+        ServerSession::new(ServerSessionConfig::new()).map_err(rtmp_error)?;
+
+    let mut publisher: Option<Publisher> = None;
+
+    let mut pending = results;
+    pending.extend(session.handle_input(&remaining).map_err(rtmp_error)?);
+
+    loop {
+        for result in pending.drain(..) {
+            match result {
+                ServerSessionResult::OutboundResponse(packet) => {
+                    tcp.write_all(&packet.bytes).await?;
+                }
+                ServerSessionResult::RaisedEvent(event) => {
+                    let responses = on_event(
+                        &mut session,
+                        &mut publisher,
+                        ingest_addr,
+                        &encoding,
+                        stats_tx.as_ref(),
+                        &publishing,
+                        event,
+                    )
+                    .await?;
+
+                    for packet in responses {
+                        tcp.write_all(&packet).await?;
+                    }
+                }
+                ServerSessionResult::UnhandleableMessageReceived(_) => {}
+            }
+        }
+
+        tokio::select! {
+            res = tcp.read(&mut buf) => {
+                let count = res?;
+
+                if count == 0 {
+                    break;
+                }
+
+                pending = session.handle_input(&buf[..count]).map_err(rtmp_error)?;
+            }
+
+            _ = shutdown.changed() => break,
+        }
+    }
+
+    if let Some(publisher) = publisher {
+        publisher.finish().await?;
+    }
+
+    Ok(())
+}
+
+async fn on_event(
+    session: &mut ServerSession,
+    publisher: &mut Option<Publisher>,
+    ingest_addr: SocketAddr,
+    encoding: &Encoding,
+    stats_tx: Option<&UnboundedSender<EncoderStats>>,
+    publishing: &Arc<Semaphore>,
+    event: ServerSessionEvent,
+) -> Result<Vec<Vec<u8>>, Error> {
+    let mut responses = Vec::new();
+
+    match event {
+        ServerSessionEvent::ConnectionRequested { request_id, .. } => {
+            for result in session.accept_request(request_id).map_err(rtmp_error)? {
+                if let ServerSessionResult::OutboundResponse(packet) = result {
+                    responses.push(packet.bytes);
+                }
+            }
+        }
+        ServerSessionEvent::PublishStreamRequested { request_id, .. } => {
+            let permit = match publisher {
+                Some(_) => None,
+                None => publishing.clone().try_acquire_owned().ok(),
+            };
+
+            let Some(permit) = permit else {
+                eprintln!("❗ RTMP: Only one stream can be published at a time");
+
+                for result in session
+                    .reject_request(request_id, PUBLISH_BAD_NAME, "Already publishing")
+                    .map_err(rtmp_error)?
+                {
+                    if let ServerSessionResult::OutboundResponse(packet) = result {
+                        responses.push(packet.bytes);
+                    }
+                }
+
+                return Ok(responses);
+            };
+
+            // FFMPEG is started with the first audio or video, after the source metadata.
+            *publisher = Some(Publisher {
+                source: None,
+                remuxer: None,
+                _permit: permit,
+            });
+
+            for result in session.accept_request(request_id).map_err(rtmp_error)? {
+                if let ServerSessionResult::OutboundResponse(packet) = result {
+                    responses.push(packet.bytes);
+                }
+            }
+
+            println!("✅ RTMP Stream Started");
+        }
+        ServerSessionEvent::PublishStreamFinished { .. } => {
+            if let Some(publisher) = publisher.take() {
+                publisher.finish().await?;
+            }
+
+            println!("❌ RTMP Stream Ended");
+        }
+        ServerSessionEvent::StreamMetadataChanged { metadata, .. } => {
+            if let Some(publisher) = publisher.as_mut() {
+                publisher.source = Source::from_metadata(&metadata);
+            }
+        }
+        ServerSessionEvent::AudioDataReceived {
+            data, timestamp, ..
+        } => {
+            if let Some(publisher) = publisher.as_mut() {
+                publisher
+                    .remuxer(ingest_addr, encoding, stats_tx)
+                    .await?
+                    .write_tag(FLV_AUDIO_TAG, timestamp.value, &data)
+                    .await?;
+            }
+        }
+        ServerSessionEvent::VideoDataReceived {
+            data, timestamp, ..
+        } => {
+            if let Some(publisher) = publisher.as_mut() {
+                publisher
+                    .remuxer(ingest_addr, encoding, stats_tx)
+                    .await?
+                    .write_tag(FLV_VIDEO_TAG, timestamp.value, &data)
+                    .await?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(responses)
+}
+
+/// Stream being published.
+struct Publisher {
+    source: Option<Source>,
+    remuxer: Option<Remuxer>,
+
+    /// Released when the stream ends so that another can be published.
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Publisher {
+    /// Returns the running FFMPEG process, started with the qualities to encode if none.
+    async fn remuxer(
+        &mut self,
+        ingest_addr: SocketAddr,
+        encoding: &Encoding,
+        stats_tx: Option<&UnboundedSender<EncoderStats>>,
+    ) -> Result<&mut Remuxer, Error> {
+        if self.remuxer.is_none() {
+            let qualities = match (encoding, self.source) {
+                (Encoding::Copy, _) => None,
+                (Encoding::Ladder(qualities), _) => Some(qualities.clone()),
+                (Encoding::Auto, Some(source)) => {
+                    println!("Source {}", source);
+
+                    Some(ladder::ladder(source))
+                }
+                (Encoding::Auto, None) => {
+                    eprintln!("❗ RTMP: No source metadata, the stream is not transcoded");
+
+                    None
+                }
+            };
+
+            if let Some(qualities) = qualities.as_ref() {
+                let list: Vec<_> = qualities.iter().map(ToString::to_string).collect();
+
+                println!("✅ Qualities {}", list.join(", "));
+            }
+
+            let remuxer =
+                Remuxer::spawn(ingest_addr, qualities.as_deref(), stats_tx.cloned()).await?;
+
+            self.remuxer = Some(remuxer);
+        }
+
+        Ok(self.remuxer.as_mut().expect("Remuxer"))
+    }
+
+    /// Wait for FFMPEG to flush before another stream can be published.
+    async fn finish(self) -> Result<(), Error> {
+        if let Some(remuxer) = self.remuxer {
+            remuxer.finish().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// FFMPEG process fed with FLV.
+struct Remuxer {
+    child: Child,
+    stdin: ChildStdin,
+}
+
+impl Remuxer {
+    /// Encode these qualities or copy the source if none.
+    async fn spawn(
+        ingest_addr: SocketAddr,
+        qualities: Option<&[Quality]>,
+        stats_tx: Option<UnboundedSender<EncoderStats>>,
+    ) -> Result<Self, Error> {
+        let segment = format!("http://{}/%v/%d.m4s", ingest_addr);
+        let playlist = format!("http://{}/%v/index.m3u8", ingest_addr);
+
+        let mut command = Command::new("ffmpeg");
+
+        if stats_tx.is_some() {
+            command
+                .args(["-progress", "pipe:1", "-stats_period", STATS_PERIOD])
+                .stdout(Stdio::piped());
+        }
+
+        command
+            .args(["-hide_banner", "-loglevel", "error"])
+            .args(["-f", "flv", "-i", "pipe:0"]);
+
+        match qualities {
+            Some(qualities) => command.args(ladder::ffmpeg_args(qualities)),
+            None => command
+                .args(["-map", "0:v:0", "-c:v", "copy"])
+                .args(["-map", "0:a:0", "-c:a", "copy"])
+                .args(["-var_stream_map", "v:0,name:source a:0,name:audio"]),
+        };
+
+        let mut child = command
+            .args(["-f", "hls"])
+            .args(["-hls_init_time", "1", "-hls_time", "1"])
+            .args(["-hls_flags", "independent_segments"])
+            .args(["-master_pl_name", "master.m3u8"])
+            .args(["-hls_segment_type", "fmp4"])
+            .args(["-hls_segment_filename", &segment])
+            .args(["-http_persistent", "0", "-ignore_io_errors", "1"])
+            .args(["-method", "PUT", &playlist])
+            .stdin(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let mut stdin = child.stdin.take().expect("Piped Stdin");
+
+        if let (Some(stats_tx), Some(stdout)) = (stats_tx, child.stdout.take()) {
+            tokio::spawn(read_progress(stdout, stats_tx));
+        }
+
+        // FLV header with audio & video flags set followed by the first previous tag size.
+        stdin
+            .write_all(&[b'F', b'L', b'V', 1, 0b0000_0101, 0, 0, 0, 9, 0, 0, 0, 0])
+            .await?;
+
+        Ok(Self { child, stdin })
+    }
+
+    async fn write_tag(&mut self, tag_type: u8, timestamp: u32, data: &[u8]) -> Result<(), Error> {
+        let size = data.len() as u32;
+        let ts = timestamp.to_be_bytes();
+
+        let mut header = Vec::with_capacity(11);
+        header.push(tag_type);
+        header.extend_from_slice(&size.to_be_bytes()[1..]);
+        // Lower 24 bits then the extended upper 8 bits.
+        header.extend_from_slice(&[ts[1], ts[2], ts[3], ts[0]]);
+        // Stream id, always zero.
+        header.extend_from_slice(&[0, 0, 0]);
+
+        self.stdin.write_all(&header).await?;
+        self.stdin.write_all(data).await?;
+        self.stdin.write_all(&(size + 11).to_be_bytes()).await?;
+
+        Ok(())
+    }
+
+    /// Close the input and wait for FFMPEG to flush the last segments.
+    async fn finish(self) -> Result<(), Error> {
+        let Self { mut child, stdin } = self;
+
+        drop(stdin);
+
+        let status = child.wait().await?;
+
+        if !status.success() {
+            eprintln!("❗ FFMPEG: {}", status);
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse FFMPEG progress reports, blocks of key=value lines ending with "progress".
+async fn read_progress(stdout: ChildStdout, stats_tx: UnboundedSender<EncoderStats>) {
+    let mut lines = BufReader::new(stdout).lines();
+
+    let mut stats = EncoderStats::default();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let value = value.trim();
+
+        match key {
+            "fps" => stats.fps = value.parse().unwrap_or_default(),
+            "drop_frames" => stats.dropped_frames = value.parse().unwrap_or_default(),
+            "bitrate" => {
+                stats.bitrate = value
+                    .trim_end_matches("kbits/s")
+                    .parse()
+                    .unwrap_or_default()
+            }
+            "speed" => stats.speed = value.trim_end_matches('x').parse().unwrap_or_default(),
+            "progress" => {
+                stats.timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as i64;
+
+                if stats_tx.send(stats.clone()).is_err() {
+                    return;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn rtmp_error(e: impl std::fmt::Debug) -> Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{:?}", e)).into()
+}
@@ -1,23 +1,33 @@
-use crate::actors::{SetupData, VideoData};
-
-use std::{fmt::Debug, path::Path};
+use crate::actors::{BandwidthCap, LatestStats, SetupData, Throttle, VideoData};
+
+use std::{
+    fmt::Debug,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use futures_util::StreamExt;
 use tokio::sync::mpsc::UnboundedSender;
 
 use hyper::{
     body::{Bytes, Incoming},
-    header::{HeaderValue, LOCATION},
+    header::{HeaderValue, CONTENT_TYPE, LOCATION},
     Error, Method, Request, Response, StatusCode,
 };
 
-use http_body_util::{BodyExt, BodyStream, Empty};
+use http_body_util::{BodyExt, BodyStream, Full};
 
 use ipfs_api::IpfsService;
 
 use m3u8_rs::Playlist;
 
 const M3U8: &str = "m3u8";
+const STATS_PATH: &str = "/stats";
+const BANDWIDTH_PATH: &str = "/archive/bandwidth";
+const MARKER_PATH: &str = "/archive/marker";
 pub const MP4: &str = "mp4";
 pub const M4S: &str = "m4s";
 
@@ -26,14 +36,29 @@ pub async fn put_requests(
     video_tx: UnboundedSender<VideoData>,
     setup_tx: UnboundedSender<SetupData>,
     ipfs: IpfsService,
-) -> Result<Response<Empty<Bytes>>, Error> {
+    stats: Option<LatestStats>,
+    bandwidth: BandwidthCap,
+    throttle: Option<Throttle>,
+) -> Result<Response<Full<Bytes>>, Error> {
     #[cfg(debug_assertions)]
     println!("Service: {:#?}", req);
 
-    let mut res = Response::new(Empty::new());
+    let mut res = Response::new(Full::default());
 
     let (parts, body) = req.into_parts();
 
+    if parts.method == Method::GET && parts.uri.path() == STATS_PATH {
+        return stats_response(res, stats);
+    }
+
+    if parts.uri.path() == BANDWIDTH_PATH {
+        return bandwidth_response(res, &parts.method, body, bandwidth).await;
+    }
+
+    if parts.method == Method::POST && parts.uri.path() == MARKER_PATH {
+        return marker_response(res, body, video_tx).await;
+    }
+
     let path = Path::new(parts.uri.path());
 
     if parts.method != Method::PUT
@@ -48,19 +73,38 @@ pub async fn put_requests(
     let body_stream = BodyStream::new(body);
 
     if path.extension().unwrap() == M3U8 {
-        return manifest_response(res, body_stream, path, setup_tx).await;
+        return manifest_response(res, body_stream, path, video_tx, setup_tx).await;
     }
 
+    let size = Arc::new(AtomicU64::new(0));
+
     //Map frames to bytes dropping trailers frame
-    let byte_stream = body_stream.filter_map(|res| async move {
-        match res {
-            Ok(frame) => match frame.into_data() {
-                Ok(bytes) => Some(Ok(bytes)),
-                Err(_) => None,
-            },
-            Err(e) => Some(Err(e)),
-        }
-    });
+    let byte_stream = {
+        let size = size.clone();
+
+        body_stream.filter_map(move |res| {
+            let size = size.clone();
+            let throttle = throttle.clone();
+
+            async move {
+                match res {
+                    Ok(frame) => match frame.into_data() {
+                        Ok(bytes) => {
+                            // Pace archiving while reading so the stream keeps its uplink.
+                            if let Some(throttle) = throttle {
+                                throttle.consume(bytes.len() as u64).await;
+                            }
+
+                            size.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+                            Some(Ok(bytes))
+                        }
+                        Err(_) => None,
+                    },
+                    Err(e) => Some(Err(e)),
+                }
+            }
+        })
+    };
 
     let cid = match ipfs.add(byte_stream).await {
         Ok(res) => res,
@@ -71,7 +115,9 @@ pub async fn put_requests(
     println!("IPFS: add => {}", &cid.to_string());
 
     if path.extension().unwrap() == M4S {
-        let msg = VideoData::Segment((path.to_path_buf(), cid));
+        let size = size.load(Ordering::Relaxed);
+
+        let msg = VideoData::Segment((path.to_path_buf(), cid, size));
 
         if let Err(error) = video_tx.send(msg) {
             return internal_error_response(res, &error);
@@ -96,7 +142,98 @@ pub async fn put_requests(
     Ok(res)
 }
 
-fn not_found_response(mut res: Response<Empty<Bytes>>) -> Result<Response<Empty<Bytes>>, Error> {
+/// Latest encoder statistics as JSON, if the daemon started the encoder.
+fn stats_response(
+    mut res: Response<Full<Bytes>>,
+    stats: Option<LatestStats>,
+) -> Result<Response<Full<Bytes>>, Error> {
+    let latest = match stats {
+        Some(stats) => stats.lock().unwrap().clone(),
+        None => None,
+    };
+
+    let Some(latest) = latest else {
+        *res.status_mut() = StatusCode::NO_CONTENT;
+
+        return Ok(res);
+    };
+
+    let body = match serde_json::to_vec(&latest) {
+        Ok(body) => body,
+        Err(error) => return internal_error_response(res, &error),
+    };
+
+    *res.body_mut() = Full::new(Bytes::from(body));
+
+    res.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    Ok(res)
+}
+
+/// Read or set the archiving bandwidth cap, in bytes per second as plain text.
+async fn bandwidth_response(
+    mut res: Response<Full<Bytes>>,
+    method: &Method,
+    body: Incoming,
+    bandwidth: BandwidthCap,
+) -> Result<Response<Full<Bytes>>, Error> {
+    if *method == Method::PUT {
+        let bytes = BodyExt::collect(body).await?.to_bytes();
+
+        let cap = match std::str::from_utf8(&bytes)
+            .ok()
+            .and_then(|text| text.trim().parse::<u64>().ok())
+        {
+            Some(cap) => cap,
+            None => {
+                *res.status_mut() = StatusCode::BAD_REQUEST;
+
+                return Ok(res);
+            }
+        };
+
+        bandwidth.store(cap, Ordering::Relaxed);
+
+        println!("Archive bandwidth set to {} bytes/s", cap);
+    } else if *method != Method::GET {
+        return not_found_response(res);
+    }
+
+    let cap = bandwidth.load(Ordering::Relaxed);
+
+    *res.body_mut() = Full::new(Bytes::from(cap.to_string()));
+
+    Ok(res)
+}
+
+/// Place a marker at the latest archived segment, titled with the plain text body.
+async fn marker_response(
+    mut res: Response<Full<Bytes>>,
+    body: Incoming,
+    video_tx: UnboundedSender<VideoData>,
+) -> Result<Response<Full<Bytes>>, Error> {
+    let bytes = BodyExt::collect(body).await?.to_bytes();
+
+    let title = match std::str::from_utf8(&bytes) {
+        Ok(text) if !text.trim().is_empty() => text.trim().to_owned(),
+        _ => {
+            *res.status_mut() = StatusCode::BAD_REQUEST;
+
+            return Ok(res);
+        }
+    };
+
+    if let Err(error) = video_tx.send(VideoData::Marker(title)) {
+        return internal_error_response(res, &error);
+    }
+
+    *res.status_mut() = StatusCode::NO_CONTENT;
+
+    Ok(res)
+}
+
+fn not_found_response(mut res: Response<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Error> {
     *res.status_mut() = StatusCode::NOT_FOUND;
 
     #[cfg(debug_assertions)]
@@ -106,11 +243,12 @@ fn not_found_response(mut res: Response<Empty<Bytes>>) -> Result<Response<Empty<
 }
 
 async fn manifest_response(
-    mut res: Response<Empty<Bytes>>,
+    mut res: Response<Full<Bytes>>,
     body: BodyStream<Incoming>,
     path: &Path,
+    video_tx: UnboundedSender<VideoData>,
     setup_tx: UnboundedSender<SetupData>,
-) -> Result<Response<Empty<Bytes>>, Error> {
+) -> Result<Response<Full<Bytes>>, Error> {
     let bytes = BodyExt::collect(body).await?.to_bytes();
 
     let playlist = match m3u8_rs::parse_playlist(&bytes) {
@@ -118,11 +256,32 @@ async fn manifest_response(
         Err(e) => return internal_error_response(res, &e),
     };
 
-    if let Playlist::MasterPlaylist(playlist) = playlist {
-        let msg = SetupData::Playlist(playlist);
+    match playlist {
+        Playlist::MasterPlaylist(playlist) => {
+            let msg = SetupData::Playlist(playlist);
 
-        if let Err(error) = setup_tx.send(msg) {
-            return internal_error_response(res, &error);
+            if let Err(error) = setup_tx.send(msg) {
+                return internal_error_response(res, &error);
+            }
+        }
+        Playlist::MediaPlaylist(playlist) => {
+            // Segment durations, as written by ffmpeg.
+            for segment in playlist.segments {
+                let index = match Path::new(&segment.uri)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse::<usize>().ok())
+                {
+                    Some(index) => index,
+                    None => continue,
+                };
+
+                let msg = VideoData::Duration((index, segment.duration as f64));
+
+                if let Err(error) = video_tx.send(msg) {
+                    return internal_error_response(res, &error);
+                }
+            }
         }
     }
 
@@ -139,9 +298,9 @@ async fn manifest_response(
 }
 
 fn internal_error_response(
-    mut res: Response<Empty<Bytes>>,
+    mut res: Response<Full<Bytes>>,
     error: &dyn Debug,
-) -> Result<Response<Empty<Bytes>>, Error> {
+) -> Result<Response<Full<Bytes>>, Error> {
     eprintln!("Service: {:#?}", error);
 
     *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
@@ -5,9 +5,21 @@ pub enum Error {
     #[error("Key: Y coords missing")]
     Key,
 
+    #[error("Key: Invalid public key")]
+    PublicKey,
+
+    #[error("DAG-JOSE: Unsupported key or algorithm")]
+    Unsupported,
+
+    #[error("DAG-JOSE: Cannot encrypt or decrypt block")]
+    Encryption,
+
     #[error("DAG-JOSE: No header present")]
     Header,
 
+    #[error("DAG-JOSE: Signing key does not belong to this identity")]
+    IdentityMismatch,
+
     #[error("Signature: {0}")]
     Signatue(#[from] signature::Error),
 
@@ -0,0 +1,197 @@
+use aes_gcm::{
+    aead::{AeadInPlace, KeyInit},
+    Aes256Gcm, Nonce, Tag,
+};
+
+use multibase::Base;
+
+use rand_core::{OsRng, RngCore};
+
+use serde::{Deserialize, Serialize};
+
+use sha2::{Digest, Sha256};
+
+use crate::{Error, JsonWebKey, KeyAgreement};
+
+/// Size in bytes of the A256GCM initialization vector.
+const IV_SIZE: usize = 12;
+
+/// Size in bytes of the A256GCM authentication tag.
+const TAG_SIZE: usize = 16;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum KeyManagement {
+    //https://www.rfc-editor.org/rfc/rfc7518#section-4.6
+    #[serde(rename = "ECDH-ES")]
+    ECDHES,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ContentEncryption {
+    //https://www.rfc-editor.org/rfc/rfc7518#section-5.3
+    #[serde(rename = "A256GCM")]
+    A256GCM,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EncryptionHeader {
+    #[serde(rename = "alg")]
+    pub algorithm: KeyManagement,
+
+    #[serde(rename = "enc")]
+    pub encryption: ContentEncryption,
+
+    /// Public key of the sender ephemeral key pair.
+    #[serde(rename = "epk")]
+    pub ephemeral_key: JsonWebKey,
+
+    /// Agreement PartyUInfo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apu: Option<String>,
+
+    /// Agreement PartyVInfo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apv: Option<String>,
+}
+
+/// Json Web Encryption
+///
+/// The content key is agreed directly with the recipient key,
+/// anyone holding the recipient private key can decrypt.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JsonWebEncryption {
+    protected: String,
+
+    iv: String,
+
+    ciphertext: String,
+
+    tag: String,
+}
+
+impl JsonWebEncryption {
+    /// Encrypt some bytes, usually a DAG-CBOR encoded block, for the holder of the recipient key.
+    ///
+    /// The ephemeral key must be of the same curve and never reused.
+    pub fn new<E>(cleartext: &[u8], ephemeral: E, recipient: &JsonWebKey) -> Result<Self, Error>
+    where
+        E: KeyAgreement,
+    {
+        let header = EncryptionHeader {
+            algorithm: KeyManagement::ECDHES,
+            encryption: ContentEncryption::A256GCM,
+            ephemeral_key: ephemeral.web_key(),
+            apu: None,
+            apv: None,
+        };
+
+        let protected = serde_json::to_vec(&header)?;
+        let protected = Base::Base64Url.encode(protected);
+
+        let shared = ephemeral.agree(recipient)?;
+        let cipher = content_cipher(&shared, &header)?;
+
+        let mut iv = [0u8; IV_SIZE];
+        OsRng.fill_bytes(&mut iv);
+
+        let mut buffer = cleartext.to_vec();
+
+        let tag = cipher
+            .encrypt_in_place_detached(Nonce::from_slice(&iv), protected.as_bytes(), &mut buffer)
+            .map_err(|_| Error::Encryption)?;
+
+        let jwe = Self {
+            protected,
+            iv: Base::Base64Url.encode(iv),
+            ciphertext: Base::Base64Url.encode(buffer),
+            tag: Base::Base64Url.encode(tag),
+        };
+
+        Ok(jwe)
+    }
+
+    pub fn get_header(&self) -> Result<EncryptionHeader, Error> {
+        let data = Base::Base64Url.decode(&self.protected)?;
+        let header = serde_json::from_slice(&data)?;
+
+        Ok(header)
+    }
+
+    /// Decrypt and authenticate a dag-jose block with the recipient key.
+    pub fn decrypt<K>(&self, key: &K) -> Result<Vec<u8>, Error>
+    where
+        K: KeyAgreement,
+    {
+        let header = self.get_header()?;
+
+        let shared = key.agree(&header.ephemeral_key)?;
+        let cipher = content_cipher(&shared, &header)?;
+
+        let iv = Base::Base64Url.decode(&self.iv)?;
+        let tag = Base::Base64Url.decode(&self.tag)?;
+
+        if iv.len() != IV_SIZE || tag.len() != TAG_SIZE {
+            return Err(Error::Encryption);
+        }
+
+        let mut buffer = Base::Base64Url.decode(&self.ciphertext)?;
+
+        cipher
+            .decrypt_in_place_detached(
+                Nonce::from_slice(&iv),
+                self.protected.as_bytes(),
+                &mut buffer,
+                Tag::from_slice(&tag),
+            )
+            .map_err(|_| Error::Encryption)?;
+
+        Ok(buffer)
+    }
+}
+
+/// Derive the content encryption key from the shared secret.
+fn content_cipher(shared: &[u8], header: &EncryptionHeader) -> Result<Aes256Gcm, Error> {
+    let apu = match header.apu.as_ref() {
+        Some(apu) => Base::Base64Url.decode(apu)?,
+        None => Vec::new(),
+    };
+
+    let apv = match header.apv.as_ref() {
+        Some(apv) => Base::Base64Url.decode(apv)?,
+        None => Vec::new(),
+    };
+
+    let key = match header.encryption {
+        ContentEncryption::A256GCM => concat_kdf(shared, "A256GCM", &apu, &apv, 256),
+    };
+
+    Aes256Gcm::new_from_slice(&key).map_err(|_| Error::Encryption)
+}
+
+/// Concat KDF with SHA-256, single round so at most 256 bits.
+///
+/// https://www.rfc-editor.org/rfc/rfc7518#section-4.6.2
+pub(crate) fn concat_kdf(
+    shared: &[u8],
+    algorithm: &str,
+    apu: &[u8],
+    apv: &[u8],
+    key_bits: u32,
+) -> Vec<u8> {
+    let mut digest = Sha256::new();
+
+    digest.update(1u32.to_be_bytes()); // Round counter
+    digest.update(shared);
+
+    for info in [algorithm.as_bytes(), apu, apv] {
+        digest.update((info.len() as u32).to_be_bytes());
+        digest.update(info);
+    }
+
+    digest.update(key_bits.to_be_bytes());
+
+    let mut key = digest.finalize().to_vec();
+    key.truncate(key_bits as usize / 8);
+
+    key
+}
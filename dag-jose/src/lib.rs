@@ -1,18 +1,24 @@
 mod errors;
+mod jwe;
 mod tests;
 mod traits;
 
 pub use errors::Error;
 
+pub use jwe::{ContentEncryption, EncryptionHeader, JsonWebEncryption, KeyManagement};
+
 use cid::Cid;
 
-use linked_data::types::IPLDLink;
+use linked_data::{
+    identity::Identity,
+    types::{IPLDLink, IPNSAddress},
+};
 
 use multibase::Base;
 
 use serde::{Deserialize, Serialize};
 
-pub use traits::{AsyncBlockSigner, BlockSigner};
+pub use traits::{AsyncBlockSigner, BlockSigner, KeyAgreement};
 
 use signature::SignatureEncoding;
 
@@ -62,6 +68,10 @@ pub enum CurveType {
     // https://datatracker.ietf.org/doc/html/rfc7518#section-6.2.1.1
     #[serde(rename = "P-256")]
     P256,
+
+    // https://www.rfc-editor.org/rfc/rfc8037.html#section-3.2
+    #[serde(rename = "X25519")]
+    X25519,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -218,6 +228,62 @@ impl JsonWebSignature {
         Ok(())
     }
 
+    /// Verify a dag-jose block then check that the signing key belong to this identity.
+    ///
+    /// Secp256k1 keys are matched against the Ethereum address, Bitcoin extended public key or address and IPNS address.
+    /// Ed25519 keys are matched against the IPNS address.
+    pub fn verify_against(&self, identity: &Identity) -> Result<(), Error> {
+        self.verify()?;
+
+        let Some(jwk) = self.get_header()?.json_web_key else {
+            return Err(Error::Header);
+        };
+
+        let matched = match (&jwk.key_type, &jwk.curve) {
+            (KeyType::EllipticCurve, CurveType::Secp256k1) => {
+                let Some(y) = jwk.y else {
+                    return Err(Error::Key);
+                };
+
+                let mut public_key = vec![0x04]; // Uncompressed key prefix
+                public_key.extend(Base::Base64Url.decode(jwk.x)?);
+                public_key.extend(Base::Base64Url.decode(y)?);
+
+                let verif_key = k256::ecdsa::VerifyingKey::try_from(public_key.as_slice())?;
+                let compressed = verif_key.to_encoded_point(true);
+
+                let eth = identity.eth_addr.as_ref().map_or(false, |addr| {
+                    eth_address(&public_key).eq_ignore_ascii_case(addr)
+                });
+
+                let btc = identity
+                    .btc_addr
+                    .as_ref()
+                    .map_or(false, |addr| btc_match(addr, compressed.as_bytes()));
+
+                let ipns = identity.ipns_addr.map_or(false, |addr| {
+                    ipns_match(addr, /* Secp256k1 */ 2, compressed.as_bytes())
+                });
+
+                eth || btc || ipns
+            }
+            (KeyType::OctetString, CurveType::Ed25519) => {
+                let public_key = Base::Base64Url.decode(jwk.x)?;
+
+                identity.ipns_addr.map_or(false, |addr| {
+                    ipns_match(addr, /* Ed25519 */ 1, &public_key)
+                })
+            }
+            _ => false,
+        };
+
+        if !matched {
+            return Err(Error::IdentityMismatch);
+        }
+
+        Ok(())
+    }
+
     pub fn new<S, U>(cid: Cid, signer: S) -> Result<Self, Error>
     where
         S: BlockSigner<U>,
@@ -360,3 +426,49 @@ impl JsonWebSignature {
         Ok(jws)
     }
 }
+
+/// Ethereum address of an uncompressed SEC1 public key.
+fn eth_address(public_key: &[u8]) -> String {
+    use sha3::{Digest, Keccak256};
+
+    let hash = Keccak256::digest(&public_key[1..]); // the first byte is a flag
+
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+/// Match a compressed SEC1 public key with a Bitcoin extended public key or P2PKH address.
+fn btc_match(btc_addr: &str, public_key: &[u8]) -> bool {
+    use ripemd::Ripemd160;
+    use sha2::{Digest, Sha256};
+
+    let Ok(decoded) = Base::Base58Btc.decode(btc_addr) else {
+        return false;
+    };
+
+    match decoded.len() {
+        // BIP-32 serialized extended key with checksum
+        82 => &decoded[45..78] == public_key,
+        // Version byte, public key hash & checksum
+        25 => {
+            let hash = Ripemd160::digest(Sha256::digest(public_key));
+
+            decoded[1..21] == hash[..]
+        }
+        _ => false,
+    }
+}
+
+/// Match a public key with an IPNS address, only keys inlined in the address can be matched.
+fn ipns_match(addr: IPNSAddress, key_type: u8, public_key: &[u8]) -> bool {
+    let cid: Cid = addr.into();
+
+    if cid.hash().code() != /* Identity */ 0x00 {
+        return false;
+    }
+
+    // Protobuf encoded key type & key data
+    let mut expected = vec![0x08, key_type, 0x12, public_key.len() as u8];
+    expected.extend_from_slice(public_key);
+
+    cid.hash().digest() == expected.as_slice()
+}
@@ -172,3 +172,107 @@ fn ecdsa_roundtrip() {
 
     assert!(result.is_ok())
 }
+
+#[test]
+fn verify_against_identity() {
+    use linked_data::{identity::Identity, types::IPNSAddress};
+    use rand_core::OsRng;
+
+    let mut csprng = OsRng {};
+    let signing_key = ed25519_dalek::SigningKey::generate(&mut csprng);
+
+    let mut key = vec![0x08, 0x01, 0x12, 0x20];
+    key.extend_from_slice(signing_key.verifying_key().as_bytes());
+
+    let multihash = cid::multihash::Multihash::wrap(0x00, &key).unwrap();
+    let ipns_addr = IPNSAddress::try_from(Cid::new_v1(0x72, multihash)).unwrap();
+
+    let signer = Ed25519Signer { signing_key };
+
+    let value =
+        Cid::try_from("bafyreih223c6mqauz5ouolokqrofaekpuu45eblm33fm3g2rlwdkqfabo4").unwrap();
+
+    let jws = JsonWebSignature::new(value, signer).unwrap();
+
+    let identity = Identity {
+        ipns_addr: Some(ipns_addr),
+        ..Default::default()
+    };
+
+    assert!(jws.verify_against(&identity).is_ok());
+
+    let stranger = Identity {
+        eth_addr: Some(String::from("0x0000000000000000000000000000000000000000")),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        jws.verify_against(&stranger),
+        Err(Error::IdentityMismatch)
+    ));
+}
+
+#[test]
+fn concat_kdf_vector() {
+    // https://www.rfc-editor.org/rfc/rfc7518#appendix-C
+    let shared = [
+        158, 86, 217, 29, 129, 113, 53, 211, 114, 131, 66, 131, 191, 132, 38, 156, 251, 49, 110,
+        163, 218, 128, 106, 72, 246, 218, 167, 121, 140, 254, 144, 196,
+    ];
+
+    let key = crate::jwe::concat_kdf(&shared, "A128GCM", b"Alice", b"Bob", 128);
+
+    assert_eq!(Base::Base64Url.encode(key), "VqqN6vgjbSBcIijNcacQGg");
+}
+
+#[test]
+fn x25519_encryption_roundtrip() {
+    use rand_core::OsRng;
+
+    let recipient = x25519_dalek::StaticSecret::random_from_rng(OsRng);
+    let ephemeral = x25519_dalek::StaticSecret::random_from_rng(OsRng);
+    let stranger = x25519_dalek::StaticSecret::random_from_rng(OsRng);
+
+    let cleartext = b"followers only";
+
+    let jwe = JsonWebEncryption::new(cleartext, ephemeral, &recipient.web_key()).unwrap();
+
+    assert_eq!(jwe.decrypt(&recipient).unwrap(), cleartext);
+    assert!(matches!(jwe.decrypt(&stranger), Err(Error::Encryption)));
+
+    let json = serde_json::to_string(&jwe).unwrap();
+    let decoded: JsonWebEncryption = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(decoded.decrypt(&recipient).unwrap(), cleartext);
+
+    // The protected header is authenticated too.
+    let mut header = jwe.get_header().unwrap();
+    header.apu = Some(Base::Base64Url.encode(b"Mallory"));
+
+    let mut tampered: serde_json::Value = serde_json::from_str(&json).unwrap();
+    tampered["protected"] = Base::Base64Url
+        .encode(serde_json::to_vec(&header).unwrap())
+        .into();
+
+    let tampered: JsonWebEncryption = serde_json::from_value(tampered).unwrap();
+
+    assert!(tampered.decrypt(&recipient).is_err());
+}
+
+#[test]
+fn p256_encryption_roundtrip() {
+    use rand_core::OsRng;
+
+    let recipient = p256::SecretKey::random(&mut OsRng);
+    let ephemeral = p256::SecretKey::random(&mut OsRng);
+
+    let cleartext = b"followers only";
+
+    let jwe = JsonWebEncryption::new(cleartext, ephemeral, &recipient.web_key()).unwrap();
+
+    assert_eq!(jwe.decrypt(&recipient).unwrap(), cleartext);
+
+    let x25519 = x25519_dalek::StaticSecret::random_from_rng(OsRng);
+
+    assert!(matches!(jwe.decrypt(&x25519), Err(Error::Unsupported)));
+}
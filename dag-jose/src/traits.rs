@@ -1,10 +1,14 @@
 use async_trait::async_trait;
 
+use elliptic_curve::sec1::{Coordinates, ToEncodedPoint};
+
+use multibase::Base;
+
 use signature::{SignatureEncoding, Signer};
 
 use async_signature::AsyncSigner;
 
-use crate::{AlgorithmType, JsonWebKey};
+use crate::{AlgorithmType, CurveType, Error, JsonWebKey, KeyType};
 
 /// Impl'd the trait is not enough, one must follow the JOSE specs below.
 ///
@@ -39,3 +43,86 @@ where
 
     async fn web_key(&self) -> JsonWebKey;
 }
+
+/// Private key of an ECDH key agreement, used to encrypt and decrypt blocks.
+///
+/// https://www.rfc-editor.org/rfc/rfc7518#section-4.6
+pub trait KeyAgreement {
+    /// Public key of this key pair.
+    fn web_key(&self) -> JsonWebKey;
+
+    /// Raw shared secret with the owner of this public key.
+    fn agree(&self, public_key: &JsonWebKey) -> Result<Vec<u8>, Error>;
+}
+
+impl KeyAgreement for x25519_dalek::StaticSecret {
+    fn web_key(&self) -> JsonWebKey {
+        let public_key = x25519_dalek::PublicKey::from(self);
+
+        JsonWebKey {
+            key_type: KeyType::OctetString,
+            curve: CurveType::X25519,
+            x: Base::Base64Url.encode(public_key.as_bytes()),
+            y: None,
+        }
+    }
+
+    fn agree(&self, public_key: &JsonWebKey) -> Result<Vec<u8>, Error> {
+        let (KeyType::OctetString, CurveType::X25519) = (&public_key.key_type, &public_key.curve)
+        else {
+            return Err(Error::Unsupported);
+        };
+
+        let bytes: [u8; 32] = Base::Base64Url
+            .decode(&public_key.x)?
+            .try_into()
+            .map_err(|_| Error::PublicKey)?;
+
+        let shared = self.diffie_hellman(&x25519_dalek::PublicKey::from(bytes));
+
+        // Low order points would yield a secret known to all.
+        if !shared.was_contributory() {
+            return Err(Error::PublicKey);
+        }
+
+        Ok(shared.as_bytes().to_vec())
+    }
+}
+
+impl KeyAgreement for p256::SecretKey {
+    fn web_key(&self) -> JsonWebKey {
+        let point = self.public_key().to_encoded_point(false);
+
+        let Coordinates::Uncompressed { x, y } = point.coordinates() else {
+            unreachable!("Uncompressed point");
+        };
+
+        JsonWebKey {
+            key_type: KeyType::EllipticCurve,
+            curve: CurveType::P256,
+            x: Base::Base64Url.encode(x),
+            y: Some(Base::Base64Url.encode(y)),
+        }
+    }
+
+    fn agree(&self, public_key: &JsonWebKey) -> Result<Vec<u8>, Error> {
+        let (KeyType::EllipticCurve, CurveType::P256) = (&public_key.key_type, &public_key.curve)
+        else {
+            return Err(Error::Unsupported);
+        };
+
+        let Some(y) = public_key.y.as_ref() else {
+            return Err(Error::Key);
+        };
+
+        let mut sec1 = vec![0x04]; // Uncompressed key prefix
+        sec1.extend(Base::Base64Url.decode(&public_key.x)?);
+        sec1.extend(Base::Base64Url.decode(y)?);
+
+        let public_key = p256::PublicKey::from_sec1_bytes(&sec1).map_err(|_| Error::PublicKey)?;
+
+        let shared = p256::ecdh::diffie_hellman(self.to_nonzero_scalar(), public_key.as_affine());
+
+        Ok(shared.raw_secret_bytes().to_vec())
+    }
+}
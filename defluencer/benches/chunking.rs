@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use defluencer::indexing::ordered_trees::prolly::{BoundaryHash, HashThreshold};
+
+const ENTRIES: usize = 10_000;
+const EDITS: usize = 100;
+const CHUNKING_FACTOR: usize = 1 << 4;
+
+const HASHES: [BoundaryHash; 3] = [
+    BoundaryHash::Sha2_256,
+    BoundaryHash::Blake3,
+    BoundaryHash::Xxh3_64,
+];
+
+/// Deterministic pseudo random entries, the size of a CID.
+fn entries(count: usize, seed: u64) -> Vec<Vec<u8>> {
+    let mut state = seed;
+
+    (0..count)
+        .map(|_| {
+            (0..36)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Split entries into chunks at each boundary.
+fn chunks(strategy: &HashThreshold, entries: &[Vec<u8>]) -> Vec<Vec<Vec<u8>>> {
+    let mut chunks = vec![];
+    let mut chunk = vec![];
+
+    for entry in entries {
+        chunk.push(entry.clone());
+
+        if strategy.is_boundary(entry) {
+            chunks.push(std::mem::take(&mut chunk));
+        }
+    }
+
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+
+    chunks
+}
+
+/// Insert entries one at a time and count how many chunks had to be rebuilt.
+fn rebuild_stability(hash_function: BoundaryHash) {
+    let strategy = HashThreshold {
+        chunking_factor: CHUNKING_FACTOR,
+        hash_function,
+    };
+
+    let mut entries = entries(ENTRIES, 0x9E37_79B9_7F4A_7C15);
+    let edits = self::entries(EDITS, 0xD1B5_4A32_D192_ED03);
+
+    let mut previous: HashSet<_> = chunks(&strategy, &entries).into_iter().collect();
+    let mut rebuilt = 0;
+
+    for (i, edit) in edits.into_iter().enumerate() {
+        let index = (i * 7919) % entries.len();
+        entries.insert(index, edit);
+
+        let current: HashSet<_> = chunks(&strategy, &entries).into_iter().collect();
+
+        rebuilt += current.difference(&previous).count();
+
+        previous = current;
+    }
+
+    println!(
+        "{:?}: {} chunks, average {:.1} entries per chunk, {:.2} chunks rebuilt per insert",
+        hash_function,
+        previous.len(),
+        entries.len() as f64 / previous.len() as f64,
+        rebuilt as f64 / EDITS as f64,
+    );
+}
+
+fn boundary(c: &mut Criterion) {
+    for hash_function in HASHES {
+        rebuild_stability(hash_function);
+    }
+
+    let entries = entries(ENTRIES, 0x9E37_79B9_7F4A_7C15);
+
+    let mut group = c.benchmark_group("boundary");
+
+    for hash_function in HASHES {
+        let strategy = HashThreshold {
+            chunking_factor: CHUNKING_FACTOR,
+            hash_function,
+        };
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{:?}", hash_function)),
+            &entries,
+            |b, entries| {
+                b.iter(|| {
+                    entries
+                        .iter()
+                        .filter(|entry| strategy.is_boundary(black_box(entry)))
+                        .count()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, boundary);
+criterion_main!(benches);
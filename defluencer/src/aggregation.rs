@@ -0,0 +1,234 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Mutex, RwLock},
+};
+
+use cid::Cid;
+
+use crate::{denylist::Denylist, limits::MAX_NODE_SIZE};
+
+/// Submissions received on an aggregation topic, by outcome.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AggregationStats {
+    pub accepted: usize,
+
+    /// Already received within the window.
+    pub duplicates: usize,
+
+    /// Not a CID or not some media.
+    pub invalid: usize,
+
+    /// Media older than the maximum age.
+    pub stale: usize,
+
+    /// Media larger than the maximum size.
+    pub oversized: usize,
+
+    /// CIDs on the operator's denylist.
+    pub denied: usize,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    seen: HashSet<Cid>,
+    order: VecDeque<Cid>,
+    stats: AggregationStats,
+
+    /// Denied CIDs not yet taken for auditing.
+    denied: Vec<Cid>,
+}
+
+/// Filter for aggregation topic submissions.
+///
+/// Drops CIDs already received among the last `window` ones, CIDs on the denylist,
+/// media older than `max_age` and media nodes larger than `max_size`.
+#[derive(Debug)]
+pub struct Aggregator {
+    /// Number of recent CIDs remembered.
+    window: usize,
+
+    /// Maximum media age in seconds.
+    max_age: i64,
+
+    /// Maximum media node size in bytes.
+    max_size: u64,
+
+    denylist: RwLock<Denylist>,
+
+    state: Mutex<State>,
+}
+
+impl Aggregator {
+    pub fn new(window: usize, max_age: i64) -> Self {
+        Self {
+            window,
+            max_age,
+            max_size: MAX_NODE_SIZE,
+            denylist: Default::default(),
+            state: Default::default(),
+        }
+    }
+
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn with_denylist(self, denylist: Denylist) -> Self {
+        self.set_denylist(denylist);
+        self
+    }
+
+    /// Replace the denylist, eg. after downloading a new version.
+    pub fn set_denylist(&self, denylist: Denylist) {
+        *self.denylist.write().expect("Lock Poisoned") = denylist;
+    }
+
+    /// Returns true if this CID is not on the denylist.
+    pub fn is_allowed(&self, cid: Cid) -> bool {
+        if !self.denylist.read().expect("Lock Poisoned").contains(cid) {
+            return true;
+        }
+
+        let mut state = self.state.lock().expect("Lock Poisoned");
+
+        state.stats.denied += 1;
+        state.denied.push(cid);
+
+        false
+    }
+
+    /// Returns the CIDs denied since the last call.
+    pub fn take_denied(&self) -> Vec<Cid> {
+        std::mem::take(&mut self.state.lock().expect("Lock Poisoned").denied)
+    }
+
+    /// Returns true and remember this CID if it was not received within the window.
+    pub fn first_seen(&self, cid: Cid) -> bool {
+        let mut state = self.state.lock().expect("Lock Poisoned");
+
+        if state.seen.contains(&cid) {
+            state.stats.duplicates += 1;
+            return false;
+        }
+
+        if self.window == 0 {
+            return true;
+        }
+
+        while state.order.len() >= self.window {
+            if let Some(oldest) = state.order.pop_front() {
+                state.seen.remove(&oldest);
+            }
+        }
+
+        state.seen.insert(cid);
+        state.order.push_back(cid);
+
+        true
+    }
+
+    /// Returns true if media created at this time is recent enough.
+    pub fn is_fresh(&self, user_timestamp: i64, now: i64) -> bool {
+        let mut state = self.state.lock().expect("Lock Poisoned");
+
+        if now - user_timestamp > self.max_age {
+            state.stats.stale += 1;
+            return false;
+        }
+
+        state.stats.accepted += 1;
+
+        true
+    }
+
+    /// Returns true if a media node of this size is accepted.
+    pub fn is_small(&self, size: u64) -> bool {
+        if size > self.max_size {
+            self.state.lock().expect("Lock Poisoned").stats.oversized += 1;
+            return false;
+        }
+
+        true
+    }
+
+    /// Count a submission that is not a CID or not some media.
+    pub fn invalid(&self) {
+        self.state.lock().expect("Lock Poisoned").stats.invalid += 1;
+    }
+
+    pub fn stats(&self) -> AggregationStats {
+        self.state.lock().expect("Lock Poisoned").stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    fn cid(data: &[u8]) -> Cid {
+        Cid::new_v1(0x71, Code::Sha2_256.digest(data))
+    }
+
+    #[test]
+    fn sliding_dedup() {
+        let aggregator = Aggregator::new(2, 60);
+
+        assert!(aggregator.first_seen(cid(b"a")));
+        assert!(!aggregator.first_seen(cid(b"a")));
+        assert!(aggregator.first_seen(cid(b"b")));
+        assert!(aggregator.first_seen(cid(b"c")));
+
+        // "a" slid out of the window
+        assert!(aggregator.first_seen(cid(b"a")));
+        assert!(!aggregator.first_seen(cid(b"c")));
+
+        assert_eq!(aggregator.stats().duplicates, 2);
+    }
+
+    #[test]
+    fn stale_and_invalid() {
+        let aggregator = Aggregator::new(10, 60);
+
+        assert!(aggregator.is_fresh(100, 160));
+        assert!(!aggregator.is_fresh(100, 161));
+
+        let aggregator = aggregator.with_max_size(100);
+
+        assert!(aggregator.is_small(100));
+        assert!(!aggregator.is_small(101));
+
+        aggregator.invalid();
+
+        assert_eq!(
+            aggregator.stats(),
+            AggregationStats {
+                accepted: 1,
+                duplicates: 0,
+                invalid: 1,
+                stale: 1,
+                oversized: 1,
+                denied: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn denied() {
+        let denylist = Denylist::parse(&format!("{}\n", cid(b"a")));
+        let aggregator = Aggregator::new(10, 60).with_denylist(denylist);
+
+        assert!(!aggregator.is_allowed(cid(b"a")));
+        assert!(aggregator.is_allowed(cid(b"b")));
+
+        assert_eq!(aggregator.take_denied(), vec![cid(b"a")]);
+        assert!(aggregator.take_denied().is_empty());
+
+        aggregator.set_denylist(Denylist::default());
+
+        assert!(aggregator.is_allowed(cid(b"a")));
+        assert_eq!(aggregator.stats().denied, 1);
+    }
+}
@@ -0,0 +1,219 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use chrono::{Days, NaiveDate, TimeZone, Utc};
+
+use linked_data::media::{comments::Comment, Media};
+
+use serde::Serialize;
+
+use crate::errors::Error;
+
+/// Activity of a channel during one day, in UTC.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DailyActivity {
+    /// Date as YYYY-MM-DD.
+    pub date: String,
+
+    /// Media added, by publication date.
+    pub publications: u64,
+
+    /// Comments received, by comment date.
+    pub comments: u64,
+
+    /// Duration of the live streams that started this day, in seconds.
+    pub stream_seconds: f64,
+
+    /// Followers found by a web crawl, only known the day of the crawl.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub followers: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Counts {
+    publications: u64,
+    comments: u64,
+    stream_seconds: f64,
+    followers: Option<u64>,
+}
+
+/// Time series of a channel activity, one entry per day.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Analytics {
+    days: BTreeMap<NaiveDate, Counts>,
+}
+
+impl Analytics {
+    /// Count a publication and, for live streams, its duration.
+    pub fn add_media(&mut self, media: &Media) {
+        let Some(counts) = self.day(media.user_timestamp()) else {
+            return;
+        };
+
+        counts.publications += 1;
+
+        if let Media::Video(video) = media {
+            if video.live {
+                counts.stream_seconds += video.duration.unwrap_or_default();
+            }
+        }
+    }
+
+    pub fn add_comment(&mut self, comment: &Comment) {
+        if let Some(counts) = self.day(comment.user_timestamp) {
+            counts.comments += 1;
+        }
+    }
+
+    /// Record the number of followers at this time.
+    pub fn set_followers(&mut self, timestamp: i64, followers: u64) {
+        if let Some(counts) = self.day(timestamp) {
+            counts.followers = Some(followers);
+        }
+    }
+
+    /// Returns every day from the first to the last activity, in order.
+    pub fn series(&self) -> Vec<DailyActivity> {
+        let (Some(first), Some(last)) = (self.days.keys().next(), self.days.keys().last()) else {
+            return Vec::new();
+        };
+
+        let mut series = Vec::new();
+        let mut date = *first;
+
+        while date <= *last {
+            let counts = self.days.get(&date).copied().unwrap_or_default();
+
+            series.push(DailyActivity {
+                date: date.format("%Y-%m-%d").to_string(),
+                publications: counts.publications,
+                comments: counts.comments,
+                stream_seconds: counts.stream_seconds,
+                followers: counts.followers,
+            });
+
+            date = match date.checked_add_days(Days::new(1)) {
+                Some(date) => date,
+                None => break,
+            };
+        }
+
+        series
+    }
+
+    /// One JSON object per day and per line.
+    pub fn to_ndjson(&self) -> Result<String, Error> {
+        let mut ndjson = String::new();
+
+        for activity in self.series() {
+            ndjson.push_str(&serde_json::to_string(&activity)?);
+            ndjson.push('\n');
+        }
+
+        Ok(ndjson)
+    }
+
+    /// One row per day, followers are empty when unknown.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,publications,comments,stream_seconds,followers\n");
+
+        for activity in self.series() {
+            let followers = activity
+                .followers
+                .map(|count| count.to_string())
+                .unwrap_or_default();
+
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{}",
+                activity.date,
+                activity.publications,
+                activity.comments,
+                activity.stream_seconds,
+                followers
+            );
+        }
+
+        csv
+    }
+
+    fn day(&mut self, timestamp: i64) -> Option<&mut Counts> {
+        let date = Utc.timestamp_opt(timestamp, 0).single()?.date_naive();
+
+        Some(self.days.entry(date).or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use linked_data::media::{blog::BlogPost, video::Video, MediaFormat};
+
+    const DAY: i64 = 86400;
+
+    #[test]
+    fn daily_series() {
+        let mut analytics = Analytics::default();
+
+        assert!(analytics.series().is_empty());
+
+        analytics.add_media(&Media::Video(Video {
+            user_timestamp: DAY,
+            duration: Some(3600.0),
+            live: true,
+            ..Default::default()
+        }));
+
+        // Imported, not streamed.
+        analytics.add_media(&Media::Video(Video {
+            user_timestamp: DAY + 60,
+            duration: Some(600.0),
+            format: Some(MediaFormat::default()),
+            ..Default::default()
+        }));
+
+        // Uploaded, not streamed.
+        analytics.add_media(&Media::Video(Video {
+            user_timestamp: DAY + 120,
+            duration: Some(300.0),
+            ..Default::default()
+        }));
+
+        analytics.add_media(&Media::Blog(BlogPost {
+            user_timestamp: 3 * DAY,
+            ..Default::default()
+        }));
+
+        analytics.add_comment(&Comment {
+            user_timestamp: 2 * DAY + 1,
+            ..Default::default()
+        });
+
+        analytics.set_followers(3 * DAY + 5, 42);
+
+        let series = analytics.series();
+
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[0].date, "1970-01-02");
+        assert_eq!(series[0].publications, 3);
+        assert_eq!(series[0].stream_seconds, 3600.0);
+        assert_eq!(series[1].comments, 1);
+        assert_eq!(series[2].followers, Some(42));
+
+        assert_eq!(
+            analytics.to_csv(),
+            "date,publications,comments,stream_seconds,followers\n\
+            1970-01-02,3,0,3600,\n\
+            1970-01-03,0,1,0,\n\
+            1970-01-04,1,0,0,42\n"
+        );
+
+        let ndjson = analytics.to_ndjson().unwrap();
+        let last = ndjson.lines().last().unwrap();
+
+        assert_eq!(
+            last,
+            r#"{"date":"1970-01-04","publications":1,"comments":0,"stream_seconds":0.0,"followers":42}"#
+        );
+    }
+}
@@ -0,0 +1,577 @@
+use std::collections::{HashSet, VecDeque};
+
+use cid::Cid;
+
+use futures::{stream, Stream};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use libipld_core::ipld::Ipld;
+
+use multihash::{Code, MultihashDigest};
+
+use serde::Serialize;
+
+use crate::errors::Error;
+
+/// CBOR tag of IPLD links.
+const LINK_TAG: u64 = 42;
+
+/// Block whose encoding differs from the canonical one.
+///
+/// Writers disagreeing on key order or number encoding produce different CIDs for the same node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mismatch {
+    pub cid: Cid,
+
+    /// CID of the same node canonically encoded.
+    pub canonical: Cid,
+}
+
+/// Encode a node in strict canonical DAG-CBOR.
+///
+/// Map keys are sorted length first, integers use the shortest form
+/// and floats are always 64 bits, non-finite floats are rejected.
+pub fn encode<T: Serialize + ?Sized>(node: &T) -> Result<Vec<u8>, Error> {
+    let ipld =
+        libipld_core::serde::to_ipld(node).map_err(|e| Error::NonCanonical(e.to_string()))?;
+
+    encode_ipld(&ipld)
+}
+
+/// Returns the CID of a node canonically encoded, hashed with Sha2-256.
+pub fn cid<T: Serialize + ?Sized>(node: &T) -> Result<Cid, Error> {
+    let bytes = encode(node)?;
+
+    let cid = Cid::new_v1(Codec::DagCbor as u64, Code::Sha2_256.digest(&bytes));
+
+    Ok(cid)
+}
+
+/// Encode IPLD data in strict canonical DAG-CBOR.
+pub fn encode_ipld(ipld: &Ipld) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+
+    write_ipld(ipld, &mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// Re-encode a DAG-CBOR or DAG-JOSE block then compare CIDs.
+///
+/// Returns the mismatch if the block is not canonical, blocks of other codecs are not checked.
+pub fn check_block(cid: Cid, bytes: &[u8]) -> Result<Option<Mismatch>, Error> {
+    if !is_cbor(cid) {
+        return Ok(None);
+    }
+
+    let ipld: Ipld = serde_ipld_dagcbor::from_slice(bytes).map_err(|_| Error::InvalidBlock(cid))?;
+
+    check_ipld(cid, &ipld)
+}
+
+/// Stream the blocks of a DAG that are not canonically encoded.
+///
+/// Links are followed breadth first, including links serialized as maps with a "/" key.
+pub fn audit_dag(
+    ipfs: &IpfsService,
+    root: Cid,
+) -> impl Stream<Item = Result<Mismatch, Error>> + '_ {
+    let queue = VecDeque::from([root]);
+    let seen = HashSet::from([root]);
+
+    stream::try_unfold((queue, seen), move |(mut queue, mut seen)| async move {
+        while let Some(cid) = queue.pop_front() {
+            if !is_cbor(cid) {
+                continue;
+            }
+
+            let bytes = ipfs.block_get(cid).await?;
+
+            let ipld: Ipld =
+                serde_ipld_dagcbor::from_slice(&bytes).map_err(|_| Error::InvalidBlock(cid))?;
+
+            for link in links(&ipld) {
+                if seen.insert(link) {
+                    queue.push_back(link);
+                }
+            }
+
+            if let Some(mismatch) = check_ipld(cid, &ipld)? {
+                return Ok(Some((mismatch, (queue, seen))));
+            }
+        }
+
+        Ok::<_, Error>(None)
+    })
+}
+
+fn check_ipld(cid: Cid, ipld: &Ipld) -> Result<Option<Mismatch>, Error> {
+    let bytes = encode_ipld(ipld)?;
+
+    let code = Code::try_from(cid.hash().code())?;
+    let canonical = Cid::new_v1(cid.codec(), code.digest(&bytes));
+
+    if canonical == cid {
+        return Ok(None);
+    }
+
+    Ok(Some(Mismatch { cid, canonical }))
+}
+
+fn is_cbor(cid: Cid) -> bool {
+    cid.codec() == Codec::DagCbor as u64 || cid.codec() == Codec::DagJose as u64
+}
+
+/// Returns all the links in this node.
+fn links(ipld: &Ipld) -> Vec<Cid> {
+    let mut links = Vec::new();
+    let mut stack = vec![ipld];
+
+    while let Some(ipld) = stack.pop() {
+        match ipld {
+            Ipld::Link(cid) => links.push(*cid),
+            Ipld::List(list) => stack.extend(list.iter()),
+            Ipld::Map(map) => {
+                if let (1, Some(Ipld::String(link))) = (map.len(), map.get("/")) {
+                    if let Ok(cid) = Cid::try_from(link.as_str()) {
+                        links.push(cid);
+                        continue;
+                    }
+                }
+
+                stack.extend(map.values());
+            }
+            _ => {}
+        }
+    }
+
+    links
+}
+
+fn write_ipld(ipld: &Ipld, bytes: &mut Vec<u8>) -> Result<(), Error> {
+    match ipld {
+        Ipld::Null => bytes.push(0xf6),
+        Ipld::Bool(false) => bytes.push(0xf4),
+        Ipld::Bool(true) => bytes.push(0xf5),
+        Ipld::Integer(int) => {
+            let (major, value) = match *int {
+                int if int >= 0 => (0, u64::try_from(int)),
+                int => (1, u64::try_from(-1 - int)),
+            };
+
+            let value = value.map_err(|_| Error::NonCanonical(format!("integer {}", int)))?;
+
+            write_head(major, value, bytes);
+        }
+        Ipld::Float(float) => {
+            if !float.is_finite() {
+                return Err(Error::NonCanonical(format!("float {}", float)));
+            }
+
+            bytes.push(0xfb);
+            bytes.extend(float.to_be_bytes());
+        }
+        Ipld::String(string) => {
+            write_head(3, string.len() as u64, bytes);
+            bytes.extend(string.as_bytes());
+        }
+        Ipld::Bytes(data) => {
+            write_head(2, data.len() as u64, bytes);
+            bytes.extend(data);
+        }
+        Ipld::List(list) => {
+            write_head(4, list.len() as u64, bytes);
+
+            for ipld in list {
+                write_ipld(ipld, bytes)?;
+            }
+        }
+        Ipld::Map(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+
+            write_head(5, entries.len() as u64, bytes);
+
+            for (key, ipld) in entries {
+                write_head(3, key.len() as u64, bytes);
+                bytes.extend(key.as_bytes());
+
+                write_ipld(ipld, bytes)?;
+            }
+        }
+        Ipld::Link(cid) => {
+            let link = cid.to_bytes();
+
+            write_head(6, LINK_TAG, bytes);
+
+            // Multibase identity prefix
+            write_head(2, link.len() as u64 + 1, bytes);
+            bytes.push(0x00);
+            bytes.extend(link);
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a CBOR head in its shortest form.
+fn write_head(major: u8, value: u64, bytes: &mut Vec<u8>) {
+    let major = major << 5;
+
+    match value {
+        0..=23 => bytes.push(major | value as u8),
+        24..=0xff => bytes.extend([major | 24, value as u8]),
+        0x100..=0xffff => {
+            bytes.push(major | 25);
+            bytes.extend((value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            bytes.push(major | 26);
+            bytes.extend((value as u32).to_be_bytes());
+        }
+        _ => {
+            bytes.push(major | 27);
+            bytes.extend(value.to_be_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use serde::de::DeserializeOwned;
+
+    use linked_data::{
+        channel::{
+            archive::ArchiveRecord,
+            follows::{FollowLists, Follows},
+            gossip::Gossip,
+            live::{EncoderStats, LiveSettings, SignedSegment},
+            moderation::{Bans, Blocklist, Moderators},
+            owners::{Approvals, Owners},
+            registry::Listing,
+            snapshot::Snapshot,
+            succession::{Succession, SuccessionClaim},
+            theme::Theme,
+            ChannelMetadata,
+        },
+        identity::{Claim, Identity, Platform, Recovery, RecoveryNode, Vouches},
+        indexes::{
+            bloom::BloomFilter,
+            date_time::{Daily, Hourly, Minutes, Monthly, Seconds, Yearly},
+            hamt::HAMTRoot,
+            log::ChainLink,
+        },
+        media::{
+            audio::Audio,
+            blog::BlogPost,
+            chat::{ChatMessage, ChatSettings, MessageType},
+            comments::{ArchivedComments, Comment, CommentChunk, MediaComments, Upvote, Voters},
+            event::{Attendees, Event, Rsvp},
+            video::{
+                Chapter, Day, Hour, Minute, Parity, Second, Segment, Setup, Timecode, Track, Video,
+            },
+            Media, MediaFormat, Variant, VariantKind, Variants,
+        },
+        messaging::{DirectMessage, EncryptedMessage},
+        types::{IPLDLink, IPNSAddress},
+    };
+
+    use crate::crypto::signed_link::{HashAlgorithm, SignedLink};
+
+    fn link(data: &[u8]) -> IPLDLink {
+        Cid::new_v1(0x71, Code::Sha2_256.digest(data)).into()
+    }
+
+    /// Encoding is canonical, the same as serde_ipld_dagcbor and survives a decode and re-encode.
+    fn assert_canonical<T: Serialize + DeserializeOwned>(node: &T) {
+        let bytes = encode(node).unwrap();
+        let cid = cid(node).unwrap();
+
+        assert_eq!(bytes, serde_ipld_dagcbor::to_vec(node).unwrap());
+
+        assert_eq!(check_block(cid, &bytes).unwrap(), None);
+
+        let decoded: T = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+
+        assert_eq!(encode(&decoded).unwrap(), bytes);
+    }
+
+    macro_rules! assert_defaults_canonical {
+        ($($schema:ty),* $(,)?) => {
+            $(assert_canonical(&<$schema>::default());)*
+        };
+    }
+
+    #[test]
+    fn every_schema() {
+        assert_defaults_canonical!(
+            ArchiveRecord,
+            Follows,
+            FollowLists,
+            Gossip,
+            LiveSettings,
+            SignedSegment,
+            EncoderStats,
+            ChannelMetadata,
+            Bans,
+            Moderators,
+            Blocklist,
+            Owners,
+            Approvals,
+            Listing,
+            Snapshot,
+            Succession,
+            Theme,
+            Identity,
+            Recovery,
+            BloomFilter,
+            Yearly,
+            Monthly,
+            Daily,
+            Hourly,
+            Minutes,
+            Seconds,
+            HAMTRoot,
+            ChainLink,
+            Audio,
+            BlogPost,
+            ChatSettings,
+            Comment,
+            Upvote,
+            Voters,
+            ArchivedComments,
+            CommentChunk,
+            MediaComments,
+            Event,
+            Rsvp,
+            Attendees,
+            Variants,
+            MediaFormat,
+            Video,
+            Parity,
+            DirectMessage,
+            EncryptedMessage,
+        );
+
+        let addr =
+            IPNSAddress::try_from("bafzbeiegbnjh5uopd5vc22tgkz6chf7a6ala3x5e47vnhv5sq5bzo46tri")
+                .unwrap();
+
+        assert_canonical(&Media::Video(Video {
+            title: String::from("Title"),
+            duration: Some(60.0),
+            ..Default::default()
+        }));
+
+        assert_canonical(&Timecode {
+            timecode: link(b"day"),
+            size: Some(u32::MAX as u64 + 1),
+            segment_count: Some(300),
+            duration: Some(299.96),
+            parity: Vec::new(),
+        });
+
+        assert_canonical(&Day {
+            links_to_hours: vec![link(b"hour")],
+        });
+        assert_canonical(&Hour {
+            links_to_minutes: vec![link(b"minute")],
+        });
+        assert_canonical(&Minute {
+            links_to_seconds: vec![link(b"second")],
+        });
+        assert_canonical(&Second {
+            link_to_video: link(b"segment"),
+            links_to_chat: vec![link(b"chat")],
+        });
+
+        // Keys of different lengths, in random order.
+        assert_canonical(&Segment {
+            tracks: HashMap::from([
+                (String::from("1080p60"), link(b"1080p60")),
+                (String::from("audio"), link(b"audio")),
+                (String::from("480p30"), link(b"480p30")),
+                (String::from("720p"), link(b"720p")),
+            ]),
+            setup: Some(link(b"setup")),
+            previous: None,
+        });
+
+        assert_canonical(&Setup {
+            tracks: vec![Track {
+                name: String::from("audio"),
+                codec: String::from(r#"audio/mp4; codecs="mp4a.40.2""#),
+                initialization_segment: link(b"init"),
+                bandwidth: 128_000,
+            }],
+        });
+
+        assert_canonical(&ChatMessage {
+            message: MessageType::Text(String::from("gm")),
+            signature: link(b"signature"),
+        });
+
+        assert_canonical(&SuccessionClaim {
+            channel: addr,
+            metadata: link(b"metadata"),
+            successor: addr,
+            timestamp: -1,
+        });
+
+        assert_canonical(&Claim {
+            user_timestamp: 1_700_000_000,
+            platform: Platform::GitHub,
+            account: String::from("account"),
+            proof: String::from("https://gist.github.com/"),
+        });
+
+        assert_canonical(&RecoveryNode {
+            identity: link(b"identity"),
+            eth_addr: String::from("0x0000000000000000000000000000000000000000"),
+            timestamp: 1_700_000_000,
+        });
+
+        assert_canonical(&Vouches {
+            recovery: link(b"recovery"),
+            signatures: vec![link(b"a"), link(b"b")],
+        });
+    }
+
+    /// Every optional field set, binary fields and nested maps included.
+    #[test]
+    fn populated_schemas() {
+        assert_canonical(&Video {
+            identity: link(b"identity"),
+            user_timestamp: 1_700_000_000,
+            video: link(b"timecode"),
+            title: String::from("Title"),
+            duration: Some(3723.5),
+            size: Some(u32::MAX as u64 + 1),
+            segment_count: Some(1862),
+            image: Some(link(b"image")),
+            summary: Some(String::from("Summary")),
+            variants: vec![Variant {
+                link: link(b"dubbed"),
+                kind: VariantKind::Dubbed,
+                language: Some(String::from("fr-CA")),
+            }],
+            chapters: vec![Chapter {
+                start: 0.0,
+                title: String::from("Intro"),
+            }],
+            thumbnails: vec![link(b"thumbnail")],
+            format: Some(MediaFormat {
+                container: String::from("matroska,webm"),
+                codecs: vec![String::from("vp9"), String::from("opus")],
+            }),
+            live: true,
+            tags: vec![String::from("music")],
+        });
+
+        assert_canonical(&Comment {
+            identity: link(b"identity"),
+            user_timestamp: -1,
+            origin: Some(link(b"media").link),
+            reply_to: Some(link(b"parent")),
+            text: String::from("Reply"),
+        });
+
+        assert_canonical(&SignedLink {
+            link: link(b"media"),
+            public_key: vec![0x04; 65],
+            hash_algo: HashAlgorithm::EthereumLedgerApp,
+            signature: vec![0xff; 72],
+        });
+
+        assert_canonical(&LiveSettings {
+            video_topic: String::from("video"),
+            archiving: true,
+            chat_topic: Some(String::from("chat")),
+            bans: Some(link(b"bans")),
+            mods: Some(link(b"mods")),
+            segment_key: Some(vec![0x02; 33]),
+            ..Default::default()
+        });
+
+        assert_canonical(&ChannelMetadata {
+            identity: link(b"identity"),
+            content_index: Some(link(b"content")),
+            comment_index: Some(link(b"comments")),
+            live: Some(link(b"live")),
+            slug_index: Some(link(b"slugs")),
+            permalink_index: Some(link(b"permalinks")),
+            agregation_channel: Some(String::from("topic")),
+            ..Default::default()
+        });
+
+        assert_canonical(&Attendees {
+            attendees: HashSet::from([link(b"identity")]),
+            answered: HashMap::from([
+                (link(b"a").link, 1_700_000_000),
+                (link(b"bb").link, i64::MIN),
+            ]),
+        });
+    }
+
+    #[test]
+    fn non_canonical_blocks() {
+        // {"bb": 1, "a": 2}
+        let unsorted = [0xa2, 0x62, 0x62, 0x62, 0x01, 0x61, 0x61, 0x02];
+        let cid = Cid::new_v1(0x71, Code::Sha2_256.digest(&unsorted));
+
+        let mismatch = check_block(cid, &unsorted).unwrap().unwrap();
+
+        let sorted = [0xa2, 0x61, 0x61, 0x02, 0x62, 0x62, 0x62, 0x01];
+
+        assert_eq!(
+            mismatch.canonical,
+            Cid::new_v1(0x71, Code::Sha2_256.digest(&sorted))
+        );
+        assert_eq!(check_block(mismatch.canonical, &sorted).unwrap(), None);
+
+        assert!(matches!(
+            encode(&Video {
+                duration: Some(f64::NAN),
+                ..Default::default()
+            }),
+            Err(Error::NonCanonical(_))
+        ));
+
+        let raw = Cid::new_v1(0x55, Code::Sha2_256.digest(&unsorted));
+
+        assert_eq!(check_block(raw, &unsorted).unwrap(), None);
+    }
+
+    #[test]
+    fn heads_and_links() {
+        let mut bytes = Vec::new();
+        write_head(0, 23, &mut bytes);
+        write_head(0, 24, &mut bytes);
+        write_head(1, 0x100, &mut bytes);
+        write_head(2, 0x1_0000, &mut bytes);
+
+        assert_eq!(
+            bytes,
+            [0x17, 0x18, 0x18, 0x39, 0x01, 0x00, 0x5a, 0x00, 0x01, 0x00, 0x00]
+        );
+
+        let cid = link(b"node").link;
+        let ipld = Ipld::List(vec![
+            Ipld::Link(cid),
+            Ipld::Integer(-1),
+            Ipld::Map([(String::from("/"), Ipld::String(cid.to_string()))].into()),
+        ]);
+
+        assert_eq!(links(&ipld), vec![cid, cid]);
+
+        let bytes = encode_ipld(&ipld).unwrap();
+        let decoded: Ipld = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+
+        assert_eq!(decoded, ipld);
+    }
+}
@@ -1,27 +1,69 @@
 pub mod local;
+pub mod succession;
+pub mod threshold;
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use crate::{
+    comments::{load_media_comments, media_comments, signer_address},
+    crypto::signed_link::SignedLink,
     errors::Error,
-    indexing::{datetime, hamt},
-    utils::add_image,
+    events::verify_rsvp,
+    indexing::{bloom, datetime, hamt, tags},
+    rate_limit::RateLimiter,
+    timestamp::{CheckedTimestamp, TimestampPolicy},
+    user::{validate_identity, IdentityPatch},
+    Defluencer,
+};
+
+#[cfg(feature = "ordered-trees")]
+use crate::indexing::{
+    hub,
+    ordered_trees::{prolly::ProllyTree, staging::Staging},
+    registry, slug,
 };
 
-use chrono::{LocalResult, TimeZone, Utc};
+use chrono::{DateTime, Months, Utc};
 
 use cid::Cid;
 
+use futures::{future, pin_mut, Future, StreamExt, TryStreamExt};
+
 use ipfs_api::{responses::Codec, IpfsService};
 
+use ipns_records::IPNSRecord;
+
+use libipld_core::ipld::Ipld;
+
+#[cfg(feature = "ordered-trees")]
+use linked_data::channel::registry::Listing;
+
 use linked_data::{
     channel::{
-        follows::Follows,
-        live::LiveSettings,
+        archive::{ArchiveRecord, Tier},
+        follows::{FollowLists, Follows},
+        live::{HubAnnouncement, LiveSettings},
         moderation::{Bans, Moderators},
+        owners::Owners,
+        playlists::{Playlist, Playlists},
+        snapshot::{Snapshot, SnapshotIdentity, SnapshotMedia},
+        succession::Succession,
+        theme::{FocalPoint, Layout, LinkButton, Theme},
         ChannelMetadata,
     },
     identity::Identity,
-    indexes::hamt::HAMTRoot,
-    media::{comments::Comment, Media},
+    indexes::{bloom::BloomFilter, hamt::HAMTRoot},
+    media::{
+        comments::{
+            ArchivedComments, Comment, CommentChunk, CommentCount, CommentPermission,
+            CommentSettings, MediaComments, Upvote, Voters,
+        },
+        event::Attendees,
+        Media, Variant, Variants,
+    },
     types::{Address, IPLDLink, IPNSAddress, PeerId},
 };
 
@@ -29,6 +71,30 @@ use async_trait::async_trait;
 
 use self::local::LocalUpdater;
 
+/// Maximum number of pinned comments per media.
+pub const MAX_PINNED_COMMENTS: usize = 3;
+
+/// Comments per media kept in the comment index, older ones are archived in chunks.
+pub const HOT_COMMENTS: usize = 1024;
+
+/// How long updates wait for the latest IPNS record before comparing roots only.
+const SEQUENCE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long submitted comments are searched for before being skipped.
+const COMMENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+const DAG_CBOR: u64 = 0x71;
+
+/// What to do with comments on media no longer in the content index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanAction {
+    /// Remove the comments, their upvotes and pins.
+    Remove,
+
+    /// Move the comments to the orphan index, restored if the media is added back.
+    Move,
+}
+
 #[async_trait(?Send)]
 pub trait IpnsUpdater {
     /// Update IPNS with new Cid
@@ -116,91 +182,18 @@ where
         }
     }
 
-    /// Update your identity data.
-    #[cfg(not(target_arch = "wasm32"))]
-    pub async fn update_identity(
-        &self,
-        name: Option<String>,
-        bio: Option<String>,
-        banner: Option<std::path::PathBuf>,
-        avatar: Option<std::path::PathBuf>,
-        ipns_addr: Option<IPNSAddress>,
-        eth_addr: Option<String>,
-        btc_addr: Option<String>,
-    ) -> Result<Cid, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+    /// Update your identity data, see [IdentityPatch].
+    pub async fn update_identity(&self, patch: IdentityPatch) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
         let mut identity = self
             .ipfs
             .dag_get::<&str, Identity>(channel.identity.link, None, Codec::default())
             .await?;
 
-        if let Some(name) = name {
-            identity.name = name;
-        }
-
-        if let Some(bio) = bio {
-            identity.bio = Some(bio);
-        }
-
-        if let Some(banner) = banner {
-            identity.banner = Some(add_image(&self.ipfs, banner).await?.into());
-        }
-
-        if let Some(avatar) = avatar {
-            identity.avatar = Some(add_image(&self.ipfs, avatar).await?.into());
-        }
-
-        if let Some(ipns) = ipns_addr {
-            identity.ipns_addr = Some(ipns);
-        }
-
-        if let Some(eth_addr) = eth_addr {
-            identity.eth_addr = Some(eth_addr);
-        }
-
-        if let Some(btc_addr) = btc_addr {
-            identity.btc_addr = Some(btc_addr);
-        }
-
-        let cid = self
-            .ipfs
-            .dag_put(&identity, Codec::default(), Codec::default())
-            .await?;
-
-        channel.identity = cid.into();
-
-        self.update_metadata(root_cid, &channel).await?;
-
-        Ok(cid)
-    }
-
-    /// Update your identity data.
-    #[cfg(target_arch = "wasm32")]
-    pub async fn update_identity(
-        &self,
-        name: Option<String>,
-        avatar: Option<web_sys::File>,
-        ipns_addr: Option<IPNSAddress>,
-    ) -> Result<Cid, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
-
-        let mut identity = self
-            .ipfs
-            .dag_get::<&str, Identity>(channel.identity.link, None)
-            .await?;
-
-        if let Some(name) = name {
-            identity.name = name;
-        }
-
-        if let Some(avatar) = avatar {
-            identity.avatar = Some(add_image(&self.ipfs, avatar).await?.into());
-        }
+        patch.apply(&mut identity);
 
-        if let Some(ipns) = ipns_addr {
-            identity.ipns_addr = Some(ipns);
-        }
+        validate_identity(&identity)?;
 
         let cid = self
             .ipfs
@@ -209,25 +202,25 @@ where
 
         channel.identity = cid.into();
 
-        self.update_metadata(root_cid, &channel).await?;
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
         Ok(cid)
     }
 
     /// Replace your current Identity.
     pub async fn replace_identity(&self, identity: IPLDLink) -> Result<Cid, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
         channel.identity = identity;
 
-        self.update_metadata(root_cid, &channel).await?;
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
         Ok(identity.link)
     }
 
     /// Follow a channel.
     pub async fn follow(&self, addr: IPNSAddress) -> Result<Cid, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
         let mut follows = match channel.follows {
             Some(ipld) => {
@@ -249,14 +242,14 @@ where
 
         channel.follows = Some(cid.into());
 
-        self.update_metadata(root_cid, &channel).await?;
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
         Ok(cid)
     }
 
     /// Unfollow a channel.
     pub async fn unfollow(&self, addr: IPNSAddress) -> Result<Cid, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
         let mut follows = match channel.follows {
             Some(ipld) => {
@@ -278,468 +271,5572 @@ where
 
         channel.follows = Some(cid.into());
 
-        self.update_metadata(root_cid, &channel).await?;
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
         Ok(cid)
     }
 
-    /// Replace your follow list.
-    pub async fn replace_follow_list(&self, follows: IPLDLink) -> Result<Cid, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
-
-        channel.follows = Some(follows);
-
-        self.update_metadata(root_cid, &channel).await?;
-
-        Ok(follows.link)
-    }
-
-    /// Update live chat & streaming settings.
-    pub async fn update_live_settings(
+    /// Create a new named list of followees, empty or from an existing one.
+    pub async fn create_follow_list(
         &self,
-        peer_id: Option<PeerId>,
-        video_topic: Option<String>,
-        chat_topic: Option<String>,
-        archiving: Option<bool>,
+        name: String,
+        follows: Option<IPLDLink>,
     ) -> Result<Cid, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
-
-        let mut live = match channel.live {
-            Some(ipld) => {
-                self.ipfs
-                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
-                    .await?
-            }
-            None => LiveSettings::default(),
-        };
-
-        if let Some(peer_id) = peer_id {
-            live.peer_id = peer_id;
-        }
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
-        if let Some(video_topic) = video_topic {
-            live.video_topic = video_topic;
-        }
+        let mut lists = self.get_follow_lists(&channel).await?;
 
-        if let Some(chat_topic) = chat_topic {
-            live.chat_topic = Some(chat_topic);
+        if lists.lists.contains_key(&name) {
+            return Err(Error::AlreadyAdded);
         }
 
-        if let Some(archive) = archiving {
-            live.archiving = archive;
-        }
+        let follows = match follows {
+            Some(ipld) => ipld,
+            None => self
+                .ipfs
+                .dag_put(&Follows::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
 
-        let cid = self
-            .ipfs
-            .dag_put(&live, Codec::default(), Codec::default())
-            .await?;
+        lists.lists.insert(name, follows);
 
-        channel.live = Some(cid.into());
+        let cid = self.update_follow_lists(&mut channel, &lists).await?;
 
-        self.update_metadata(root_cid, &channel).await?;
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
         Ok(cid)
     }
 
-    /// Replace your live chat & streaming settings.
-    pub async fn replace_live_settings(&self, settings: IPLDLink) -> Result<Cid, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+    /// Remove a named list of followees.
+    pub async fn remove_follow_list(&self, name: String) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
-        channel.live = Some(settings);
+        let mut lists = self.get_follow_lists(&channel).await?;
 
-        self.update_metadata(root_cid, &channel).await?;
+        if lists.lists.remove(&name).is_none() {
+            return Err(Error::NotFound);
+        }
 
-        Ok(settings.link)
+        let cid = self.update_follow_lists(&mut channel, &lists).await?;
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(cid)
     }
 
-    /// Add a user to your ban list.
-    pub async fn ban_user(&self, user: Address) -> Result<Option<Cid>, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+    /// Follow a channel in the named list, the list is created if needed.
+    pub async fn follow_in_list(&self, name: String, addr: IPNSAddress) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
-        let mut live = match channel.live {
-            Some(ipld) => {
-                self.ipfs
-                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
-                    .await?
-            }
-            None => LiveSettings::default(),
-        };
+        let mut lists = self.get_follow_lists(&channel).await?;
 
-        let mut bans: Bans = match live.bans {
-            Some(link) => {
+        let mut follows = match lists.lists.get(&name) {
+            Some(ipld) => {
                 self.ipfs
-                    .dag_get(link.link, Option::<&str>::None, Codec::default())
+                    .dag_get::<&str, Follows>(ipld.link, None, Codec::default())
                     .await?
             }
-            None => Bans::default(),
+            None => Follows::default(),
         };
 
-        if !bans.banned_addrs.insert(user) {
-            return Ok(None);
+        if !follows.followees.insert(addr) {
+            return Err(Error::AlreadyAdded);
         }
 
-        let bans_cid = self
+        let follows = self
             .ipfs
-            .dag_put(&bans, Codec::default(), Codec::default())
+            .dag_put(&follows, Codec::default(), Codec::default())
             .await?;
-        live.bans = Some(bans_cid.into());
 
-        let live_cid = self
-            .ipfs
-            .dag_put(&live, Codec::default(), Codec::default())
-            .await?;
-        channel.live = Some(live_cid.into());
+        lists.lists.insert(name, follows.into());
 
-        self.update_metadata(root_cid, &channel).await?;
+        let cid = self.update_follow_lists(&mut channel, &lists).await?;
 
-        Ok(Some(bans_cid))
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(cid)
     }
 
-    /// Remove a user from your ban list.
-    pub async fn unban_user(&self, user: &Address) -> Result<Option<Cid>, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+    /// Unfollow a channel in the named list.
+    pub async fn unfollow_in_list(&self, name: String, addr: IPNSAddress) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
-        let mut live = match channel.live {
-            Some(ipld) => {
-                self.ipfs
-                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
-                    .await?
-            }
-            None => LiveSettings::default(),
-        };
+        let mut lists = self.get_follow_lists(&channel).await?;
 
-        let mut bans: Bans = match live.bans {
-            Some(link) => {
+        let mut follows = match lists.lists.get(&name) {
+            Some(ipld) => {
                 self.ipfs
-                    .dag_get(link.link, Option::<&str>::None, Codec::default())
+                    .dag_get::<&str, Follows>(ipld.link, None, Codec::default())
                     .await?
             }
-            None => return Ok(None),
+            None => return Err(Error::NotFound),
         };
 
-        if !bans.banned_addrs.remove(user) {
-            return Ok(None);
+        if !follows.followees.remove(&addr) {
+            return Err(Error::NotFound);
         }
 
-        let bans_cid = self
+        let follows = self
             .ipfs
-            .dag_put(&bans, Codec::default(), Codec::default())
+            .dag_put(&follows, Codec::default(), Codec::default())
             .await?;
-        live.bans = Some(bans_cid.into());
 
-        let live_cid = self
-            .ipfs
-            .dag_put(&live, Codec::default(), Codec::default())
-            .await?;
-        channel.live = Some(live_cid.into());
+        lists.lists.insert(name, follows.into());
 
-        self.update_metadata(root_cid, &channel).await?;
+        let cid = self.update_follow_lists(&mut channel, &lists).await?;
 
-        Ok(Some(bans_cid))
-    }
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
-    /// Replace your ban list.
-    pub async fn replace_ban_list(&self, bans: IPLDLink) -> Result<Cid, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+        Ok(cid)
+    }
 
-        let mut live = match channel.live {
+    async fn get_follow_lists(&self, channel: &ChannelMetadata) -> Result<FollowLists, Error> {
+        let lists = match channel.follow_lists {
             Some(ipld) => {
                 self.ipfs
-                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                    .dag_get::<&str, FollowLists>(ipld.link, None, Codec::default())
                     .await?
             }
-            None => LiveSettings::default(),
+            None => FollowLists::default(),
         };
 
-        live.bans = Some(bans);
+        Ok(lists)
+    }
 
-        let live_cid = self
+    async fn update_follow_lists(
+        &self,
+        channel: &mut ChannelMetadata,
+        lists: &FollowLists,
+    ) -> Result<Cid, Error> {
+        let cid = self
             .ipfs
-            .dag_put(&live, Codec::default(), Codec::default())
+            .dag_put(lists, Codec::default(), Codec::default())
             .await?;
-        channel.live = Some(live_cid.into());
 
-        self.update_metadata(root_cid, &channel).await?;
+        channel.follow_lists = Some(cid.into());
 
-        Ok(bans.link)
+        Ok(cid)
     }
 
-    /// Add a moderator to your list.
-    pub async fn add_moderator(&self, user: Address) -> Result<Option<Cid>, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+    /// Replace your follow list.
+    pub async fn replace_follow_list(&self, follows: IPLDLink) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
-        let mut live = match channel.live {
-            Some(ipld) => {
-                self.ipfs
-                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
-                    .await?
-            }
-            None => LiveSettings::default(),
-        };
+        channel.follows = Some(follows);
 
-        let mut mods: Moderators = match live.mods {
-            Some(link) => {
-                self.ipfs
-                    .dag_get(link.link, Option::<&str>::None, Codec::default())
-                    .await?
-            }
-            None => Moderators::default(),
-        };
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
-        if !mods.moderator_addrs.insert(user) {
-            return Ok(None);
+        Ok(follows.link)
+    }
+
+    /// Create a new empty playlist.
+    pub async fn create_playlist(
+        &self,
+        name: String,
+        description: Option<String>,
+        image: Option<IPLDLink>,
+    ) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut playlists = self.get_playlists(&channel).await?;
+
+        if playlists.playlists.contains_key(&name) {
+            return Err(Error::AlreadyAdded);
         }
 
-        let mods_cid = self
-            .ipfs
-            .dag_put(&mods, Codec::default(), Codec::default())
-            .await?;
-        live.mods = Some(mods_cid.into());
+        let playlist = Playlist {
+            description,
+            image,
+            content: Vec::new(),
+        };
 
-        let live_cid = self
+        let playlist = self
             .ipfs
-            .dag_put(&live, Codec::default(), Codec::default())
+            .dag_put(&playlist, Codec::default(), Codec::default())
             .await?;
-        channel.live = Some(live_cid.into());
 
-        self.update_metadata(root_cid, &channel).await?;
+        playlists.playlists.insert(name, playlist.into());
 
-        Ok(Some(mods_cid))
+        self.update_playlists(&mut channel, &playlists).await?;
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(playlist)
     }
 
-    /// Remove a moderator from your list.
-    pub async fn remove_moderator(&self, user: &Address) -> Result<Option<Cid>, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+    /// Remove a playlist, its content stays on your channel.
+    pub async fn remove_playlist(&self, name: String) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
-        let mut live = match channel.live {
-            Some(ipld) => {
-                self.ipfs
-                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
-                    .await?
-            }
-            None => LiveSettings::default(),
-        };
+        let mut playlists = self.get_playlists(&channel).await?;
 
-        let mut mods: Moderators = match live.mods {
-            Some(link) => {
-                self.ipfs
-                    .dag_get(link.link, Option::<&str>::None, Codec::default())
-                    .await?
-            }
-            None => return Ok(None),
-        };
+        if playlists.playlists.remove(&name).is_none() {
+            return Err(Error::NotFound);
+        }
 
-        if !mods.moderator_addrs.remove(user) {
-            return Ok(None);
-        }
+        let cid = self.update_playlists(&mut channel, &playlists).await?;
 
-        let mods_cid = self
-            .ipfs
-            .dag_put(&mods, Codec::default(), Codec::default())
-            .await?;
-        live.mods = Some(mods_cid.into());
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
-        let live_cid = self
-            .ipfs
-            .dag_put(&live, Codec::default(), Codec::default())
-            .await?;
-        channel.live = Some(live_cid.into());
+        Ok(cid)
+    }
+
+    /// Add content at the end of a playlist.
+    pub async fn add_to_playlist(&self, name: String, content_cid: Cid) -> Result<Cid, Error> {
+        self.edit_playlist(name, |content| {
+            if content.contains(&content_cid.into()) {
+                return Err(Error::AlreadyAdded);
+            }
 
-        self.update_metadata(root_cid, &channel).await?;
+            content.push(content_cid.into());
 
-        Ok(Some(mods_cid))
+            Ok(())
+        })
+        .await
     }
 
-    /// Replace your moderator list.
-    pub async fn replace_moderator_list(&self, moderators: IPLDLink) -> Result<Cid, Error> {
-        let (root_cid, mut channel) = self.get_metadata().await?;
+    /// Remove content from a playlist.
+    pub async fn remove_from_playlist(&self, name: String, content_cid: Cid) -> Result<Cid, Error> {
+        self.edit_playlist(name, |content| {
+            let count = content.len();
+            content.retain(|ipld| ipld.link != content_cid);
 
-        let mut live = match channel.live {
+            if content.len() == count {
+                return Err(Error::NotFound);
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Move content of a playlist to this position, the last one if out of bounds.
+    pub async fn reorder_playlist(
+        &self,
+        name: String,
+        content_cid: Cid,
+        position: usize,
+    ) -> Result<Cid, Error> {
+        self.edit_playlist(name, |content| {
+            let Some(index) = content.iter().position(|ipld| ipld.link == content_cid) else {
+                return Err(Error::NotFound);
+            };
+
+            let ipld = content.remove(index);
+            content.insert(position.min(content.len()), ipld);
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Apply a change to the content of a playlist then update your channel.
+    ///
+    /// Returns the new playlist CID.
+    async fn edit_playlist<F>(&self, name: String, edit: F) -> Result<Cid, Error>
+    where
+        F: FnOnce(&mut Vec<IPLDLink>) -> Result<(), Error>,
+    {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut playlists = self.get_playlists(&channel).await?;
+
+        let mut playlist = match playlists.playlists.get(&name) {
             Some(ipld) => {
                 self.ipfs
-                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                    .dag_get::<&str, Playlist>(ipld.link, None, Codec::default())
                     .await?
             }
-            None => LiveSettings::default(),
+            None => return Err(Error::NotFound),
         };
 
-        live.mods = Some(moderators);
+        edit(&mut playlist.content)?;
 
-        let live_cid = self
+        let playlist = self
             .ipfs
-            .dag_put(&live, Codec::default(), Codec::default())
+            .dag_put(&playlist, Codec::default(), Codec::default())
             .await?;
-        channel.live = Some(live_cid.into());
 
-        self.update_metadata(root_cid, &channel).await?;
+        playlists.playlists.insert(name, playlist.into());
 
-        Ok(moderators.link)
+        self.update_playlists(&mut channel, &playlists).await?;
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(playlist)
     }
 
-    /// Add new content.
-    pub async fn add_content(&self, content_cid: Cid) -> Result<Cid, Error> {
-        // path "/link" to skip signature block
-        let media: Media = self
-            .ipfs
-            .dag_get(content_cid, Some("/link"), Codec::default())
-            .await?;
-        let datetime = match Utc.timestamp_opt(media.user_timestamp(), 0) {
-            LocalResult::Single(datetime) => datetime,
-            LocalResult::None => return Err(Error::Timestamp),
-            LocalResult::Ambiguous(_, _) => return Err(Error::Timestamp),
+    async fn get_playlists(&self, channel: &ChannelMetadata) -> Result<Playlists, Error> {
+        let playlists = match channel.playlists {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, Playlists>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => Playlists::default(),
         };
 
-        let (root_cid, mut channel) = self.get_metadata().await?;
+        Ok(playlists)
+    }
 
-        datetime::insert(
-            &self.ipfs,
-            datetime,
-            &mut channel.content_index,
-            content_cid,
-        )
-        .await?;
+    async fn update_playlists(
+        &self,
+        channel: &mut ChannelMetadata,
+        playlists: &Playlists,
+    ) -> Result<Cid, Error> {
+        let cid = self
+            .ipfs
+            .dag_put(playlists, Codec::default(), Codec::default())
+            .await?;
 
-        self.update_metadata(root_cid, &channel).await?;
+        channel.playlists = Some(cid.into());
 
-        Ok(content_cid)
+        Ok(cid)
     }
 
-    /// Remove a specific media.
-    /// Also remove associated comments.
-    pub async fn remove_content(&self, content_cid: Cid) -> Result<Option<Cid>, Error> {
-        let media: Media = self
-            .ipfs
-            .dag_get(content_cid, Some("/link"), Codec::default())
-            .await?;
+    /// Replace content in every playlist, or remove it if there is no replacement.
+    async fn relink_playlists(
+        &self,
+        channel: &mut ChannelMetadata,
+        old_cid: Cid,
+        new_cid: Option<Cid>,
+    ) -> Result<(), Error> {
+        if channel.playlists.is_none() {
+            return Ok(());
+        }
 
-        let datetime = match Utc.timestamp_opt(media.user_timestamp(), 0) {
-            LocalResult::Single(datetime) => datetime,
-            LocalResult::None => return Err(Error::Timestamp),
-            LocalResult::Ambiguous(_, _) => return Err(Error::Timestamp),
-        };
+        let mut playlists = self.get_playlists(channel).await?;
+        let mut changed = false;
+
+        for ipld in playlists.playlists.values_mut() {
+            let mut playlist = self
+                .ipfs
+                .dag_get::<&str, Playlist>(ipld.link, None, Codec::default())
+                .await?;
+
+            let Some(index) = playlist
+                .content
+                .iter()
+                .position(|ipld| ipld.link == old_cid)
+            else {
+                continue;
+            };
+
+            match new_cid {
+                Some(cid) if !playlist.content.contains(&cid.into()) => {
+                    playlist.content[index] = cid.into();
+                }
+                _ => {
+                    playlist.content.remove(index);
+                }
+            }
 
-        let (root_cid, mut channel) = self.get_metadata().await?;
+            let cid = self
+                .ipfs
+                .dag_put(&playlist, Codec::default(), Codec::default())
+                .await?;
 
-        if channel.content_index.is_none() {
-            return Ok(None);
+            *ipld = cid.into();
+            changed = true;
+        }
+
+        if changed {
+            self.update_playlists(channel, &playlists).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Update live chat & streaming settings.
+    pub async fn update_live_settings(
+        &self,
+        peer_id: Option<PeerId>,
+        video_topic: Option<String>,
+        chat_topic: Option<String>,
+        archiving: Option<bool>,
+    ) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut live = match channel.live {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => LiveSettings::default(),
         };
 
-        if !datetime::remove(
-            &self.ipfs,
-            datetime,
-            &mut channel.content_index,
-            content_cid,
-        )
-        .await?
-        {
-            return Ok(None);
+        if let Some(peer_id) = peer_id {
+            live.peer_id = peer_id;
         }
 
-        // Remove comments too!
-        if let Some(index) = channel.comment_index.as_mut() {
-            hamt::remove(&self.ipfs, index, content_cid).await?;
+        if let Some(video_topic) = video_topic {
+            live.video_topic = video_topic;
         }
 
-        self.update_metadata(root_cid, &channel).await?;
+        if let Some(chat_topic) = chat_topic {
+            live.chat_topic = Some(chat_topic);
+        }
 
-        Ok(Some(content_cid))
-    }
+        if let Some(archive) = archiving {
+            live.archiving = archive;
+        }
 
-    /// Add a new comment on the specified media.
-    pub async fn add_comment(&self, comment_cid: Cid) -> Result<Option<Cid>, Error> {
-        let comment: Comment = self
+        let cid = self
             .ipfs
-            .dag_get(comment_cid, Some("/link"), Codec::default())
+            .dag_put(&live, Codec::default(), Codec::default())
             .await?;
-        let media_cid = comment.origin.expect("Comment Origin");
 
-        let (root_cid, mut channel) = self.get_metadata().await?;
+        channel.live = Some(cid.into());
 
-        let mut index = match channel.comment_index {
-            Some(index) => index,
-            None => self
-                .ipfs
-                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
-                .await?
-                .into(),
-        };
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
-        let mut comments = match hamt::get(&self.ipfs, index, media_cid).await? {
-            Some(comments) => comments.into(),
-            None => self
-                .ipfs
-                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
-                .await?
-                .into(),
+        Ok(cid)
+    }
+
+    /// Set or remove the public key signing your live segments.
+    pub async fn set_segment_key(&self, segment_key: Option<Vec<u8>>) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut live = match channel.live {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => LiveSettings::default(),
         };
 
-        hamt::insert(&self.ipfs, &mut comments, comment_cid, comment_cid).await?;
+        live.segment_key = segment_key;
 
-        hamt::insert(&self.ipfs, &mut index, media_cid, comments.link).await?;
+        let cid = self
+            .ipfs
+            .dag_put(&live, Codec::default(), Codec::default())
+            .await?;
 
-        channel.comment_index = Some(index);
+        channel.live = Some(cid.into());
 
-        self.update_metadata(root_cid, &channel).await?;
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
-        Ok(Some(comment_cid))
+        Ok(cid)
     }
 
-    /// Remove a specific comment.
-    pub async fn remove_comment(&self, comment_cid: Cid) -> Result<Option<Cid>, Error> {
-        let comment: Comment = self
+    /// Enable or disable the live beacon sent to each hub when a stream starts.
+    ///
+    /// Unknown hubs are added. Returns NotFound if the channel has no live settings yet.
+    pub async fn set_hub_announcements(&self, hubs: &[(IPNSAddress, bool)]) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let Some(ipld) = channel.live else {
+            return Err(Error::NotFound);
+        };
+
+        let mut live = self
             .ipfs
-            .dag_get(comment_cid, Some("/link"), Codec::default())
+            .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
             .await?;
-        let media_cid = comment.origin.expect("Comment Origin");
 
-        let (root_cid, mut channel) = self.get_metadata().await?;
+        for (hub, enabled) in hubs.iter().copied() {
+            match live
+                .hubs
+                .iter_mut()
+                .find(|announcement| announcement.hub == hub)
+            {
+                Some(announcement) => announcement.enabled = enabled,
+                None => live.hubs.push(HubAnnouncement { hub, enabled }),
+            }
+        }
 
-        let mut index = match channel.comment_index {
-            Some(it) => it,
-            _ => return Ok(None),
-        };
+        let cid = self
+            .ipfs
+            .dag_put(&live, Codec::default(), Codec::default())
+            .await?;
 
-        let mut comments = match hamt::get(&self.ipfs, index, media_cid).await? {
-            Some(comments) => comments.into(),
-            None => return Ok(None),
-        };
+        channel.live = Some(cid.into());
 
-        hamt::remove(&self.ipfs, &mut comments, comment_cid).await?;
+        self.update_metadata(root_cid, sequence, &channel).await?;
 
-        hamt::insert(&self.ipfs, &mut index, media_cid, comments.link).await?;
+        Ok(cid)
+    }
 
-        channel.comment_index = Some(index);
+    /// Replace your live chat & streaming settings.
+    pub async fn replace_live_settings(&self, settings: IPLDLink) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
-        self.update_metadata(root_cid, &channel).await?;
+        channel.live = Some(settings);
 
-        Ok(Some(comment_cid))
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(settings.link)
     }
 
-    pub async fn get_metadata(&self) -> Result<(Cid, ChannelMetadata), Error> {
-        let cid = self.ipfs.name_resolve(self.addr.into()).await?;
+    /// Update the branding of your channel page.
+    pub async fn update_theme(
+        &self,
+        accent_colors: Option<Vec<String>>,
+        layout: Option<Layout>,
+        banner_focal_point: Option<FocalPoint>,
+        links: Option<Vec<LinkButton>>,
+    ) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
-        let meta = self
+        let mut theme = match channel.theme {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, Theme>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => Theme::default(),
+        };
+
+        if let Some(accent_colors) = accent_colors {
+            theme.accent_colors = accent_colors;
+        }
+
+        if let Some(layout) = layout {
+            theme.layout = layout;
+        }
+
+        if let Some(focal_point) = banner_focal_point {
+            theme.banner_focal_point = Some(focal_point);
+        }
+
+        if let Some(links) = links {
+            theme.links = links;
+        }
+
+        validate_theme(&theme)?;
+
+        let cid = self
             .ipfs
-            .dag_get(cid, Option::<&str>::None, Codec::default())
+            .dag_put(&theme, Codec::default(), Codec::default())
             .await?;
 
-        Ok((cid, meta))
+        channel.theme = Some(cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(cid)
     }
 
-    async fn update_metadata(&self, old_cid: Cid, channel: &ChannelMetadata) -> Result<Cid, Error> {
-        let root = self
-            .ipfs
-            .dag_put(channel, Codec::default(), Codec::default())
-            .await?;
+    /// Remove the branding of your channel page.
+    pub async fn remove_theme(&self) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
 
-        self.ipfs.pin_update(old_cid, root).await?;
+        if channel.theme.take().is_none() {
+            return Ok(None);
+        }
 
-        self.updater.update(root).await?;
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
 
-        Ok(root)
+        Ok(Some(root))
     }
 
-    pub fn get_address(&self) -> IPNSAddress {
-        self.addr
+    /// Add a user to your ban list.
+    pub async fn ban_user(&self, user: Address) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut live = match channel.live {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => LiveSettings::default(),
+        };
+
+        let mut bans: Bans = match live.bans {
+            Some(link) => {
+                self.ipfs
+                    .dag_get(link.link, Option::<&str>::None, Codec::default())
+                    .await?
+            }
+            None => Bans::default(),
+        };
+
+        if !bans.banned_addrs.insert(user) {
+            return Ok(None);
+        }
+
+        let bans_cid = self
+            .ipfs
+            .dag_put(&bans, Codec::default(), Codec::default())
+            .await?;
+        live.bans = Some(bans_cid.into());
+
+        let live_cid = self
+            .ipfs
+            .dag_put(&live, Codec::default(), Codec::default())
+            .await?;
+        channel.live = Some(live_cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(bans_cid))
+    }
+
+    /// Remove a user from your ban list.
+    pub async fn unban_user(&self, user: &Address) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut live = match channel.live {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => LiveSettings::default(),
+        };
+
+        let mut bans: Bans = match live.bans {
+            Some(link) => {
+                self.ipfs
+                    .dag_get(link.link, Option::<&str>::None, Codec::default())
+                    .await?
+            }
+            None => return Ok(None),
+        };
+
+        if !bans.banned_addrs.remove(user) {
+            return Ok(None);
+        }
+
+        let bans_cid = self
+            .ipfs
+            .dag_put(&bans, Codec::default(), Codec::default())
+            .await?;
+        live.bans = Some(bans_cid.into());
+
+        let live_cid = self
+            .ipfs
+            .dag_put(&live, Codec::default(), Codec::default())
+            .await?;
+        channel.live = Some(live_cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(bans_cid))
+    }
+
+    /// Replace your ban list.
+    pub async fn replace_ban_list(&self, bans: IPLDLink) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut live = match channel.live {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => LiveSettings::default(),
+        };
+
+        live.bans = Some(bans);
+
+        let live_cid = self
+            .ipfs
+            .dag_put(&live, Codec::default(), Codec::default())
+            .await?;
+        channel.live = Some(live_cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(bans.link)
+    }
+
+    /// Add a moderator to your list.
+    pub async fn add_moderator(&self, user: Address) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut live = match channel.live {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => LiveSettings::default(),
+        };
+
+        let mut mods: Moderators = match live.mods {
+            Some(link) => {
+                self.ipfs
+                    .dag_get(link.link, Option::<&str>::None, Codec::default())
+                    .await?
+            }
+            None => Moderators::default(),
+        };
+
+        if !mods.moderator_addrs.insert(user) {
+            return Ok(None);
+        }
+
+        let mods_cid = self
+            .ipfs
+            .dag_put(&mods, Codec::default(), Codec::default())
+            .await?;
+        live.mods = Some(mods_cid.into());
+
+        let live_cid = self
+            .ipfs
+            .dag_put(&live, Codec::default(), Codec::default())
+            .await?;
+        channel.live = Some(live_cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(mods_cid))
+    }
+
+    /// Remove a moderator from your list.
+    pub async fn remove_moderator(&self, user: &Address) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut live = match channel.live {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => LiveSettings::default(),
+        };
+
+        let mut mods: Moderators = match live.mods {
+            Some(link) => {
+                self.ipfs
+                    .dag_get(link.link, Option::<&str>::None, Codec::default())
+                    .await?
+            }
+            None => return Ok(None),
+        };
+
+        if !mods.moderator_addrs.remove(user) {
+            return Ok(None);
+        }
+
+        let mods_cid = self
+            .ipfs
+            .dag_put(&mods, Codec::default(), Codec::default())
+            .await?;
+        live.mods = Some(mods_cid.into());
+
+        let live_cid = self
+            .ipfs
+            .dag_put(&live, Codec::default(), Codec::default())
+            .await?;
+        channel.live = Some(live_cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(mods_cid))
+    }
+
+    /// Replace your moderator list.
+    pub async fn replace_moderator_list(&self, moderators: IPLDLink) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut live = match channel.live {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => LiveSettings::default(),
+        };
+
+        live.mods = Some(moderators);
+
+        let live_cid = self
+            .ipfs
+            .dag_put(&live, Codec::default(), Codec::default())
+            .await?;
+        channel.live = Some(live_cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(moderators.link)
+    }
+
+    /// Add new content.
+    ///
+    /// Content timestamped too far in the future is rejected.
+    pub async fn add_content(&self, content_cid: Cid) -> Result<Cid, Error> {
+        self.add_content_checked(content_cid, &TimestampPolicy::default())
+            .await?;
+
+        Ok(content_cid)
+    }
+
+    /// Add new content, indexed at the date the policy allows.
+    pub async fn add_content_checked(
+        &self,
+        content_cid: Cid,
+        policy: &TimestampPolicy,
+    ) -> Result<CheckedTimestamp, Error> {
+        // path "/link" to skip signature block
+        let media: Media = self
+            .ipfs
+            .dag_get(content_cid, Some("/link"), Codec::default())
+            .await?;
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let checked = self
+            .index_content(&mut channel, content_cid, media, policy)
+            .await?;
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(checked)
+    }
+
+    /// Index new content, restoring the comments it had when removed.
+    async fn index_content(
+        &self,
+        channel: &mut ChannelMetadata,
+        content_cid: Cid,
+        media: Media,
+        policy: &TimestampPolicy,
+    ) -> Result<CheckedTimestamp, Error> {
+        let checked = policy.check(media.user_timestamp(), Utc::now().timestamp())?;
+
+        datetime::insert(
+            &self.ipfs,
+            checked.date_time,
+            &mut channel.content_index,
+            content_cid,
+        )
+        .await?;
+
+        let medias = [(content_cid, media)];
+
+        #[cfg(feature = "ordered-trees")]
+        self.index_slugs(channel, &medias).await?;
+
+        self.index_tags(channel, &medias).await?;
+
+        self.restore_orphans(channel, content_cid).await?;
+
+        Ok(checked)
+    }
+
+    /// Put back the comments a media had when it was removed, if any.
+    async fn restore_orphans(
+        &self,
+        channel: &mut ChannelMetadata,
+        content_cid: Cid,
+    ) -> Result<(), Error> {
+        if let Some(mut orphans) = channel.orphan_index {
+            if let Some(comments) = hamt::remove(&self.ipfs, &mut orphans, content_cid).await? {
+                self.restore_comments(channel, content_cid, comments)
+                    .await?;
+
+                channel.orphan_index = Some(orphans);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add many contents in a single metadata update, like an imported backlog.
+    ///
+    /// Returns the contents that were not already in the index.
+    pub async fn add_contents(&self, content_cids: &[Cid]) -> Result<Vec<Cid>, Error> {
+        let policy = TimestampPolicy::default();
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut added = Vec::with_capacity(content_cids.len());
+        let mut titled = Vec::with_capacity(content_cids.len());
+
+        for content_cid in content_cids.iter().copied() {
+            let media: Media = self
+                .ipfs
+                .dag_get(content_cid, Some("/link"), Codec::default())
+                .await?;
+
+            let checked = policy.check(media.user_timestamp(), Utc::now().timestamp())?;
+
+            if datetime::insert(
+                &self.ipfs,
+                checked.date_time,
+                &mut channel.content_index,
+                content_cid,
+            )
+            .await?
+            {
+                added.push(content_cid);
+                titled.push((content_cid, media));
+            }
+        }
+
+        if added.is_empty() {
+            return Ok(added);
+        }
+
+        #[cfg(feature = "ordered-trees")]
+        self.index_slugs(&mut channel, &titled).await?;
+
+        self.index_tags(&mut channel, &titled).await?;
+
+        for content_cid in added.iter().copied() {
+            self.restore_orphans(&mut channel, content_cid).await?;
+        }
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(added)
+    }
+
+    /// Put back the comments of a media in the comment index.
+    async fn restore_comments(
+        &self,
+        channel: &mut ChannelMetadata,
+        content_cid: Cid,
+        comments: Cid,
+    ) -> Result<(), Error> {
+        let mut index = match channel.comment_index {
+            Some(index) => index,
+            None => self
+                .ipfs
+                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
+
+        hamt::insert(&self.ipfs, &mut index, content_cid, comments).await?;
+
+        channel.comment_index = Some(index);
+
+        if let Some(ipld) = channel.comment_filter {
+            let mut filter = self
+                .ipfs
+                .dag_get::<&str, BloomFilter>(ipld.link, None, Codec::default())
+                .await?;
+
+            let count = self.hot_count(comments).await?;
+
+            for _ in 0..count {
+                bloom::insert(&mut filter, content_cid);
+            }
+
+            let cid = self
+                .ipfs
+                .dag_put(&filter, Codec::default(), Codec::default())
+                .await?;
+
+            channel.comment_filter = Some(cid.into());
+        }
+
+        Ok(())
+    }
+
+    /// Give permalink slugs to all the titled content of the channel.
+    ///
+    /// Channels with content added before permalinks need it once.
+    /// Older content is indexed first and keeps the plain slugs.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn backfill_slugs(&self) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let Some(content_index) = channel.content_index else {
+            return Ok(None);
+        };
+
+        let defluencer = Defluencer::from(self.ipfs.clone());
+
+        let mut medias: Vec<(Cid, Media)> = defluencer
+            .stream_content_rev_chrono(content_index)
+            .and_then(|content_cid| async move {
+                let media = self
+                    .ipfs
+                    .dag_get(content_cid, Some("/link"), Codec::default())
+                    .await?;
+
+                Result::<_, Error>::Ok((content_cid, media))
+            })
+            .try_collect()
+            .await?;
+
+        medias.reverse();
+
+        let permalinks = channel.permalink_index;
+
+        self.index_slugs(&mut channel, &medias).await?;
+
+        if channel.permalink_index == permalinks {
+            return Ok(None);
+        }
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(root))
+    }
+
+    /// Give each titled media a permalink slug not already taken by other content.
+    ///
+    /// Slugs of removed content are taken too, only that content gets them back.
+    #[cfg(feature = "ordered-trees")]
+    async fn index_slugs(
+        &self,
+        channel: &mut ChannelMetadata,
+        medias: &[(Cid, Media)],
+    ) -> Result<(), Error> {
+        let medias = medias
+            .iter()
+            .filter_map(|(cid, media)| media.title().map(|title| (*cid, slug::slugify(title))))
+            .collect::<Vec<_>>();
+
+        if medias.is_empty() {
+            return Ok(());
+        }
+
+        let mut tree = match channel.slug_index {
+            Some(ipld) => ProllyTree::load(self.ipfs.clone(), ipld.link).await?,
+            None => ProllyTree::new::<Cid>(self.ipfs.clone(), None).await?,
+        };
+
+        let mut permalinks = match channel.permalink_index {
+            Some(ipld) => ProllyTree::load(self.ipfs.clone(), ipld.link).await?,
+            None => ProllyTree::new::<String>(self.ipfs.clone(), None).await?,
+        };
+
+        let mut staging = Staging::default();
+        let mut reverse = Staging::default();
+
+        for (content_cid, base) in medias {
+            let content_key = content_cid.to_bytes();
+
+            // Content added again keeps its permalink.
+            if reverse.get(&content_key).is_some()
+                || permalinks
+                    .get::<String>(content_key.clone())
+                    .await?
+                    .is_some()
+            {
+                continue;
+            }
+
+            for n in 1.. {
+                let candidate = slug::candidate(&base, n);
+                let key = candidate.clone().into_bytes();
+
+                let taken = match staging.get(&key) {
+                    Some(staged) => staged.copied(),
+                    None => tree.get::<Cid>(key.clone()).await?.map(|(_, cid)| cid),
+                };
+
+                match taken {
+                    Some(cid) if cid == content_cid => {}
+                    Some(_) => continue,
+                    None => staging.insert(key, content_cid),
+                }
+
+                reverse.insert(content_key, candidate);
+                break;
+            }
+        }
+
+        if reverse.is_empty() {
+            return Ok(());
+        }
+
+        if !staging.is_empty() {
+            tree.commit(&mut staging).await?;
+
+            let cid = tree.save().await?;
+
+            channel.slug_index = Some(cid.into());
+        }
+
+        permalinks.commit(&mut reverse).await?;
+
+        let cid = permalinks.save().await?;
+
+        channel.permalink_index = Some(cid.into());
+
+        Ok(())
+    }
+
+    /// Remove the permalink of a media.
+    ///
+    /// Its slug is left in the slug index as a tombstone, so old links never point to other content.
+    #[cfg(feature = "ordered-trees")]
+    async fn unindex_slug(
+        &self,
+        channel: &mut ChannelMetadata,
+        content_cid: Cid,
+    ) -> Result<(), Error> {
+        let Some(permalinks) = channel.permalink_index else {
+            return Ok(());
+        };
+
+        let mut permalinks = ProllyTree::load(self.ipfs.clone(), permalinks.link).await?;
+
+        let content_key = content_cid.to_bytes();
+
+        if permalinks
+            .get::<String>(content_key.clone())
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        permalinks.remove::<String>(content_key).await?;
+
+        let cid = permalinks.save().await?;
+
+        channel.permalink_index = Some(cid.into());
+
+        Ok(())
+    }
+
+    /// Move the permalink of a media to its new version.
+    #[cfg(feature = "ordered-trees")]
+    async fn move_slug(
+        &self,
+        channel: &mut ChannelMetadata,
+        old_cid: Cid,
+        new_cid: Cid,
+    ) -> Result<(), Error> {
+        let (Some(slugs), Some(permalinks)) = (channel.slug_index, channel.permalink_index) else {
+            return Ok(());
+        };
+
+        let mut permalinks = ProllyTree::load(self.ipfs.clone(), permalinks.link).await?;
+
+        let old_key = old_cid.to_bytes();
+
+        let Some((_, slug)) = permalinks.get::<String>(old_key.clone()).await? else {
+            return Ok(());
+        };
+
+        permalinks.remove::<String>(old_key).await?;
+
+        let mut tree = ProllyTree::load(self.ipfs.clone(), slugs.link).await?;
+
+        tree.insert(slug.clone().into_bytes(), new_cid).await?;
+
+        permalinks.insert(new_cid.to_bytes(), slug).await?;
+
+        let cid = tree.save().await?;
+
+        channel.slug_index = Some(cid.into());
+
+        let cid = permalinks.save().await?;
+
+        channel.permalink_index = Some(cid.into());
+
+        Ok(())
+    }
+
+    /// Add content to the index of each of its tags.
+    async fn index_tags(
+        &self,
+        channel: &mut ChannelMetadata,
+        medias: &[(Cid, Media)],
+    ) -> Result<(), Error> {
+        let tagged = medias
+            .iter()
+            .flat_map(|(cid, media)| media.tags().iter().map(|tag| (*cid, tags::tag_key(tag))))
+            .collect::<Vec<_>>();
+
+        if tagged.is_empty() {
+            return Ok(());
+        }
+
+        let mut index = match channel.tag_index {
+            Some(index) => index,
+            None => self
+                .ipfs
+                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
+
+        for (content_cid, key) in tagged {
+            let mut content = match hamt::get(&self.ipfs, index, key).await? {
+                Some(content) => content.into(),
+                None => self
+                    .ipfs
+                    .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                    .await?
+                    .into(),
+            };
+
+            hamt::insert(&self.ipfs, &mut content, content_cid, content_cid).await?;
+
+            hamt::insert(&self.ipfs, &mut index, key, content.link).await?;
+        }
+
+        channel.tag_index = Some(index);
+
+        Ok(())
+    }
+
+    /// Remove content from the index of its tags, removing tags left empty.
+    async fn unindex_tags(
+        &self,
+        channel: &mut ChannelMetadata,
+        content_cid: Cid,
+        media_tags: &[String],
+    ) -> Result<(), Error> {
+        let Some(mut index) = channel.tag_index else {
+            return Ok(());
+        };
+
+        for tag in media_tags {
+            let key = tags::tag_key(tag);
+
+            let Some(content) = hamt::get(&self.ipfs, index, key).await? else {
+                continue;
+            };
+
+            let mut content = content.into();
+
+            if hamt::remove(&self.ipfs, &mut content, content_cid)
+                .await?
+                .is_none()
+            {
+                continue;
+            }
+
+            let empty = hamt::values(&self.ipfs, content)
+                .boxed_local()
+                .try_next()
+                .await?
+                .is_none();
+
+            if empty {
+                hamt::remove(&self.ipfs, &mut index, key).await?;
+            } else {
+                hamt::insert(&self.ipfs, &mut index, key, content.link).await?;
+            }
+        }
+
+        channel.tag_index = Some(index);
+
+        Ok(())
+    }
+
+    /// Remove a specific media.
+    /// Also remove associated comments and drop it from playlists.
+    pub async fn remove_content(&self, content_cid: Cid) -> Result<Option<Cid>, Error> {
+        let media: Media = self
+            .ipfs
+            .dag_get(content_cid, Some("/link"), Codec::default())
+            .await?;
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        if !self
+            .unindex_content(&mut channel, content_cid, &media)
+            .await?
+        {
+            return Ok(None);
+        }
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(content_cid))
+    }
+
+    /// Returns the date this content is indexed at, if it is.
+    async fn content_date(
+        &self,
+        index: IPLDLink,
+        content_cid: Cid,
+        media: &Media,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let now = Utc::now();
+
+        let to = match datetime::date_time(media.user_timestamp()) {
+            Ok(date_time) => {
+                let found =
+                    datetime::find(&self.ipfs, index, content_cid, date_time, date_time).await?;
+
+                if found.is_some() {
+                    return Ok(found);
+                }
+
+                date_time.min(now)
+            }
+            Err(_) => now,
+        };
+
+        // Clamped timestamps are indexed at the time the content was added,
+        // before the timestamp itself and not after now.
+        datetime::find(&self.ipfs, index, content_cid, DateTime::<Utc>::MIN_UTC, to).await
+    }
+
+    /// Remove content and everything indexed with it.
+    ///
+    /// Returns false if the content was not indexed.
+    async fn unindex_content(
+        &self,
+        channel: &mut ChannelMetadata,
+        content_cid: Cid,
+        media: &Media,
+    ) -> Result<bool, Error> {
+        let Some(index) = channel.content_index else {
+            return Ok(false);
+        };
+
+        let Some(date_time) = self.content_date(index, content_cid, media).await? else {
+            return Ok(false);
+        };
+
+        if !datetime::remove(
+            &self.ipfs,
+            date_time,
+            &mut channel.content_index,
+            content_cid,
+        )
+        .await?
+        {
+            return Ok(false);
+        }
+
+        // Replies to its comments, comments moved to a new version are kept.
+        if let Some(mut replies) = channel.reply_index {
+            let comments = self
+                .comment_cids(channel.comment_index, channel.comment_archive, content_cid)
+                .await?;
+
+            for comment_cid in comments {
+                hamt::remove(&self.ipfs, &mut replies, comment_cid).await?;
+            }
+
+            channel.reply_index = Some(replies);
+        }
+
+        // Remove comments too!
+        if let Some(index) = channel.comment_index.as_mut() {
+            if let Some(ipld) = channel.comment_filter {
+                let mut filter = self
+                    .ipfs
+                    .dag_get::<&str, BloomFilter>(ipld.link, None, Codec::default())
+                    .await?;
+
+                let mut count = 0;
+
+                if let Some(comments) = hamt::get(&self.ipfs, *index, content_cid).await? {
+                    count += self.hot_count(comments).await?;
+                }
+
+                if let Some(archive) = channel.comment_archive {
+                    count += self.archived_count(archive, content_cid).await?;
+                }
+
+                for _ in 0..count {
+                    bloom::remove(&mut filter, content_cid);
+                }
+
+                let cid = self
+                    .ipfs
+                    .dag_put(&filter, Codec::default(), Codec::default())
+                    .await?;
+
+                channel.comment_filter = Some(cid.into());
+            }
+
+            hamt::remove(&self.ipfs, index, content_cid).await?;
+        }
+
+        for index in [
+            channel.comment_archive.as_mut(),
+            channel.comment_counts.as_mut(),
+            channel.upvote_index.as_mut(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            hamt::remove(&self.ipfs, index, content_cid).await?;
+        }
+
+        #[cfg(feature = "ordered-trees")]
+        self.unindex_slug(channel, content_cid).await?;
+
+        let tags = media.tags();
+
+        self.unindex_tags(channel, content_cid, tags).await?;
+
+        if let Some(index) = channel.variant_index.as_mut() {
+            if let Some(cid) = hamt::remove(&self.ipfs, index, content_cid).await? {
+                let variants = self
+                    .ipfs
+                    .dag_get::<&str, Variants>(cid, None, Codec::default())
+                    .await?;
+
+                for variant in variants.variants {
+                    self.edit_variants(index, variant.link.link, |variants| {
+                        variants.retain(|variant| variant.link.link != content_cid)
+                    })
+                    .await?;
+                }
+            }
+        }
+
+        self.relink_playlists(channel, content_cid, None).await?;
+
+        Ok(true)
+    }
+
+    /// Replace a media with a new version of itself, keeping its comments, upvotes, playlist spots and permalink.
+    ///
+    /// Comments are moved to the new media but still name the old one as origin.
+    pub async fn replace_content(&self, old_cid: Cid, new_cid: Cid) -> Result<Option<Cid>, Error> {
+        let old_media: Media = self
+            .ipfs
+            .dag_get(old_cid, Some("/link"), Codec::default())
+            .await?;
+
+        let new_media: Media = self
+            .ipfs
+            .dag_get(new_cid, Some("/link"), Codec::default())
+            .await?;
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let Some(index) = channel.content_index else {
+            return Ok(None);
+        };
+
+        if self
+            .content_date(index, old_cid, &old_media)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        if let Some(ipld) = channel.comment_filter {
+            let mut filter = self
+                .ipfs
+                .dag_get::<&str, BloomFilter>(ipld.link, None, Codec::default())
+                .await?;
+
+            let mut count = 0;
+
+            if let Some(index) = channel.comment_index {
+                if let Some(comments) = hamt::get(&self.ipfs, index, old_cid).await? {
+                    count += self.hot_count(comments).await?;
+                }
+            }
+
+            if let Some(archive) = channel.comment_archive {
+                count += self.archived_count(archive, old_cid).await?;
+            }
+
+            for _ in 0..count {
+                bloom::remove(&mut filter, old_cid);
+                bloom::insert(&mut filter, new_cid);
+            }
+
+            let cid = self
+                .ipfs
+                .dag_put(&filter, Codec::default(), Codec::default())
+                .await?;
+
+            channel.comment_filter = Some(cid.into());
+        }
+
+        for index in [
+            channel.comment_index.as_mut(),
+            channel.comment_archive.as_mut(),
+            channel.comment_counts.as_mut(),
+            channel.upvote_index.as_mut(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Some(value) = hamt::remove(&self.ipfs, index, old_cid).await? {
+                hamt::insert(&self.ipfs, index, new_cid, value).await?;
+            }
+        }
+
+        self.relink_playlists(&mut channel, old_cid, Some(new_cid))
+            .await?;
+
+        #[cfg(feature = "ordered-trees")]
+        self.move_slug(&mut channel, old_cid, new_cid).await?;
+
+        // Variants are moved before unindexing, their counterparts now link to the new version.
+        if let Some(index) = channel.variant_index.as_mut() {
+            if let Some(cid) = hamt::remove(&self.ipfs, index, old_cid).await? {
+                let mut moved = self
+                    .ipfs
+                    .dag_get::<&str, Variants>(cid, None, Codec::default())
+                    .await?
+                    .variants;
+
+                moved.retain(|variant| variant.link.link != new_cid);
+
+                for variant in moved.iter() {
+                    self.edit_variants(index, variant.link.link, |variants| {
+                        variants.retain(|variant| variant.link.link != new_cid);
+
+                        for variant in variants.iter_mut() {
+                            if variant.link.link == old_cid {
+                                variant.link = new_cid.into();
+                            }
+                        }
+                    })
+                    .await?;
+                }
+
+                self.edit_variants(index, new_cid, move |variants| {
+                    variants.retain(|variant| variant.link.link != old_cid);
+
+                    for variant in moved {
+                        variants.retain(|old| old.link != variant.link);
+                        variants.push(variant);
+                    }
+                })
+                .await?;
+            }
+        }
+
+        self.unindex_content(&mut channel, old_cid, &old_media)
+            .await?;
+
+        self.index_content(
+            &mut channel,
+            new_cid,
+            new_media,
+            &TimestampPolicy::default(),
+        )
+        .await?;
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(new_cid))
+    }
+
+    /// Link two versions of the same content, in both directions.
+    ///
+    /// Each variant describes one of the two media; its kind and language.
+    pub async fn link_variants(&self, a: Variant, b: Variant) -> Result<Cid, Error> {
+        if a.link == b.link {
+            return Err(Error::AlreadyAdded);
+        }
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut index = match channel.variant_index {
+            Some(index) => index,
+            None => self
+                .ipfs
+                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
+
+        for (content, variant) in [(a.link.link, b.clone()), (b.link.link, a)] {
+            self.edit_variants(&mut index, content, move |variants| {
+                variants.retain(|old| old.link != variant.link);
+                variants.push(variant);
+            })
+            .await?;
+        }
+
+        channel.variant_index = Some(index);
+
+        self.update_metadata(root_cid, sequence, &channel).await
+    }
+
+    /// Remove the link between two versions of the same content, in both directions.
+    pub async fn unlink_variants(&self, a: Cid, b: Cid) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let Some(mut index) = channel.variant_index else {
+            return Ok(None);
+        };
+
+        for (content, other) in [(a, b), (b, a)] {
+            self.edit_variants(&mut index, content, |variants| {
+                variants.retain(|variant| variant.link.link != other)
+            })
+            .await?;
+        }
+
+        channel.variant_index = Some(index);
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(root))
+    }
+
+    /// Apply changes to the variants of some content, removing the entry if none are left.
+    async fn edit_variants(
+        &self,
+        index: &mut IPLDLink,
+        content_cid: Cid,
+        edit: impl FnOnce(&mut Vec<Variant>),
+    ) -> Result<(), Error> {
+        let mut variants = match hamt::get(&self.ipfs, *index, content_cid).await? {
+            Some(cid) => {
+                self.ipfs
+                    .dag_get::<&str, Variants>(cid, None, Codec::default())
+                    .await?
+            }
+            None => Variants::default(),
+        };
+
+        edit(&mut variants.variants);
+
+        if variants.variants.is_empty() {
+            hamt::remove(&self.ipfs, index, content_cid).await?;
+
+            return Ok(());
+        }
+
+        let cid = self
+            .ipfs
+            .dag_put(&variants, Codec::default(), Codec::default())
+            .await?;
+
+        hamt::insert(&self.ipfs, index, content_cid, cid).await?;
+
+        Ok(())
+    }
+
+    /// Add a new comment on the specified media.
+    pub async fn add_comment(&self, comment_cid: Cid) -> Result<Option<Cid>, Error> {
+        let mut added = self.add_comments(&[comment_cid]).await?;
+
+        Ok(added.pop())
+    }
+
+    /// Add many comments in a single metadata update.
+    ///
+    /// Replies to comments missing from this content's comments are skipped.
+    ///
+    /// Returns the comments that were not already in the index.
+    pub async fn add_comments(&self, comment_cids: &[Cid]) -> Result<Vec<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        // Counts too, see `rebuild_comment_counts`.
+        let counting = channel.comment_index.is_none() || channel.comment_counts.is_some();
+
+        // A filter can only be started alongside a new index.
+        let mut filter = match (channel.comment_index, channel.comment_filter) {
+            (None, _) => Some(BloomFilter::default()),
+            (Some(_), Some(ipld)) => Some(
+                self.ipfs
+                    .dag_get::<&str, BloomFilter>(ipld.link, None, Codec::default())
+                    .await?,
+            ),
+            (Some(_), None) => None,
+        };
+
+        let mut index = match channel.comment_index {
+            Some(index) => index,
+            None => self
+                .ipfs
+                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
+
+        let mut added = Vec::with_capacity(comment_cids.len());
+        let mut touched: HashMap<Cid, CommentCount> = HashMap::new();
+        let mut replies: Vec<(Cid, Cid)> = Vec::new();
+
+        for comment_cid in comment_cids.iter().copied() {
+            // Submissions are untrusted, one bad comment must not fail the others.
+            let comment = match self.submitted_comment(comment_cid).await {
+                Ok(comment) => comment,
+                Err(ipfs_api::errors::Error::Ipfs(_)) => continue,
+                Err(e) if e.is_decode() || e.is_timeout() => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            let Some(media_cid) = comment.origin else {
+                continue;
+            };
+
+            let mut comments = match media_comments(&self.ipfs, index, media_cid).await? {
+                Some(comments) => {
+                    if hamt::get(&self.ipfs, comments.comments, comment_cid)
+                        .await?
+                        .is_some()
+                    {
+                        continue;
+                    }
+
+                    comments
+                }
+                None => MediaComments {
+                    comments: self
+                        .ipfs
+                        .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                        .await?
+                        .into(),
+                    count: 0,
+                    pinned: Vec::new(),
+                },
+            };
+
+            // Older comments are moved to the archive, they are not new either.
+            if self
+                .is_archived(channel.comment_archive, media_cid, comment_cid)
+                .await?
+            {
+                continue;
+            }
+
+            // Replies must be to comments on the same content.
+            if let Some(parent) = comment.reply_to {
+                let hot = hamt::get(&self.ipfs, comments.comments, parent.link)
+                    .await?
+                    .is_some();
+
+                if !hot
+                    && !self
+                        .is_archived(channel.comment_archive, media_cid, parent.link)
+                        .await?
+                {
+                    continue;
+                }
+            }
+
+            hamt::insert(&self.ipfs, &mut comments.comments, comment_cid, comment_cid).await?;
+            comments.count += 1;
+
+            self.put_media_comments(&mut index, media_cid, &comments)
+                .await?;
+
+            if let Some(filter) = filter.as_mut() {
+                bloom::insert(filter, media_cid);
+            }
+
+            let count = touched.entry(media_cid).or_default();
+            count.count += 1;
+            count.last_comment = count.last_comment.max(Some(comment.user_timestamp));
+
+            if let Some(parent) = comment.reply_to {
+                replies.push((parent.link, comment_cid));
+            }
+
+            added.push(comment_cid);
+        }
+
+        if added.is_empty() {
+            return Ok(added);
+        }
+
+        if !replies.is_empty() {
+            let mut reply_index = match channel.reply_index {
+                Some(index) => index,
+                None => self
+                    .ipfs
+                    .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                    .await?
+                    .into(),
+            };
+
+            for (parent_cid, comment_cid) in replies {
+                let mut replies = match hamt::get(&self.ipfs, reply_index, parent_cid).await? {
+                    Some(replies) => replies.into(),
+                    None => self
+                        .ipfs
+                        .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                        .await?
+                        .into(),
+                };
+
+                hamt::insert(&self.ipfs, &mut replies, comment_cid, comment_cid).await?;
+
+                hamt::insert(&self.ipfs, &mut reply_index, parent_cid, replies.link).await?;
+            }
+
+            channel.reply_index = Some(reply_index);
+        }
+
+        for (media_cid, new) in touched {
+            self.archive_hot_comments(&mut channel.comment_archive, &mut index, media_cid)
+                .await?;
+
+            if counting {
+                self.update_comment_count(&mut channel.comment_counts, media_cid, |count| {
+                    count.count += new.count;
+                    count.last_comment = count.last_comment.max(new.last_comment);
+                })
+                .await?;
+            }
+        }
+
+        channel.comment_index = Some(index);
+
+        if let Some(filter) = filter.take() {
+            let cid = self
+                .ipfs
+                .dag_put(&filter, Codec::default(), Codec::default())
+                .await?;
+
+            channel.comment_filter = Some(cid.into());
+        }
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(added)
+    }
+
+    /// Remove a specific comment.
+    pub async fn remove_comment(&self, comment_cid: Cid) -> Result<Option<Cid>, Error> {
+        let comment: Comment = self
+            .ipfs
+            .dag_get(comment_cid, Some("/link"), Codec::default())
+            .await?;
+        let media_cid = comment.origin.ok_or(Error::NotFound)?;
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut index = match channel.comment_index {
+            Some(it) => it,
+            _ => return Ok(None),
+        };
+
+        let mut hot = false;
+
+        if let Some(mut comments) = media_comments(&self.ipfs, index, media_cid).await? {
+            hot = hamt::get(&self.ipfs, comments.comments, comment_cid)
+                .await?
+                .is_some();
+
+            if hot {
+                hamt::remove(&self.ipfs, &mut comments.comments, comment_cid).await?;
+                comments.count = comments.count.saturating_sub(1);
+            }
+
+            // Archived comments can be pinned too.
+            let pinned = comments.pinned.len();
+            comments.pinned.retain(|ipld| ipld.link != comment_cid);
+
+            if hot || comments.pinned.len() != pinned {
+                self.put_media_comments(&mut index, media_cid, &comments)
+                    .await?;
+
+                channel.comment_index = Some(index);
+            }
+        }
+
+        if !hot
+            && !self
+                .remove_archived_comment(&mut channel.comment_archive, media_cid, comment_cid)
+                .await?
+        {
+            return Ok(None);
+        }
+
+        if let Some(counts) = channel.comment_counts {
+            let mut last_comment = None;
+
+            if let Some(cid) = hamt::get(&self.ipfs, counts, media_cid).await? {
+                let count = self
+                    .ipfs
+                    .dag_get::<&str, CommentCount>(cid, None, Codec::default())
+                    .await?;
+
+                if count.last_comment == Some(comment.user_timestamp) {
+                    let latest = self
+                        .latest_comment(channel.comment_index, channel.comment_archive, media_cid)
+                        .await?;
+
+                    last_comment = Some(latest);
+                }
+            }
+
+            self.update_comment_count(&mut channel.comment_counts, media_cid, |count| {
+                count.count = count.count.saturating_sub(1);
+
+                if let Some(latest) = last_comment {
+                    count.last_comment = latest;
+                }
+            })
+            .await?;
+        }
+
+        if let Some(mut index) = channel.reply_index {
+            // Replies to a removed comment cannot be reached anymore.
+            let mut changed = hamt::remove(&self.ipfs, &mut index, comment_cid)
+                .await?
+                .is_some();
+
+            if let Some(parent) = comment.reply_to {
+                if let Some(replies) = hamt::get(&self.ipfs, index, parent.link).await? {
+                    let mut replies: IPLDLink = replies.into();
+
+                    if hamt::remove(&self.ipfs, &mut replies, comment_cid)
+                        .await?
+                        .is_some()
+                    {
+                        let remaining = hamt::values(&self.ipfs, replies);
+                        pin_mut!(remaining);
+
+                        if remaining.try_next().await?.is_none() {
+                            hamt::remove(&self.ipfs, &mut index, parent.link).await?;
+                        } else {
+                            hamt::insert(&self.ipfs, &mut index, parent.link, replies.link).await?;
+                        }
+
+                        changed = true;
+                    }
+                }
+            }
+
+            if changed {
+                channel.reply_index = Some(index);
+            }
+        }
+
+        if let Some(ipld) = channel.comment_filter {
+            let mut filter = self
+                .ipfs
+                .dag_get::<&str, BloomFilter>(ipld.link, None, Codec::default())
+                .await?;
+
+            bloom::remove(&mut filter, media_cid);
+
+            let cid = self
+                .ipfs
+                .dag_put(&filter, Codec::default(), Codec::default())
+                .await?;
+
+            channel.comment_filter = Some(cid.into());
+        }
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(comment_cid))
+    }
+
+    /// Find the comments on media no longer in the content index,
+    /// like content removed by editing the index directly.
+    ///
+    /// Returns the CIDs of the missing media.
+    pub async fn collect_orphan_comments(&self, action: OrphanAction) -> Result<Vec<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let Some(mut index) = channel.comment_index else {
+            return Ok(vec![]);
+        };
+
+        let mut content = HashSet::new();
+
+        if let Some(content_index) = channel.content_index {
+            let defluencer = Defluencer::from(self.ipfs.clone());
+            let stream = defluencer.stream_content_rev_chrono(content_index);
+            pin_mut!(stream);
+
+            while let Some(content_cid) = stream.try_next().await? {
+                content.insert(content_cid);
+            }
+        }
+
+        let orphans: Vec<(Cid, Cid)> = hamt::values(&self.ipfs, index)
+            .try_filter(|(media_cid, _)| future::ready(!content.contains(media_cid)))
+            .try_collect()
+            .await?;
+
+        if orphans.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut filter = match channel.comment_filter {
+            Some(ipld) => Some(
+                self.ipfs
+                    .dag_get::<&str, BloomFilter>(ipld.link, None, Codec::default())
+                    .await?,
+            ),
+            None => None,
+        };
+
+        let mut orphan_index = match (action, channel.orphan_index) {
+            (OrphanAction::Remove, _) => None,
+            (OrphanAction::Move, Some(index)) => Some(index),
+            (OrphanAction::Move, None) => Some(
+                self.ipfs
+                    .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                    .await?
+                    .into(),
+            ),
+        };
+
+        for (media_cid, comments) in orphans.iter().copied() {
+            if let Some(filter) = filter.as_mut() {
+                let mut count = self.hot_count(comments).await?;
+
+                // Archived comments stay with their media when moved.
+                if let (OrphanAction::Remove, Some(archive)) = (action, channel.comment_archive) {
+                    count += self.archived_count(archive, media_cid).await?;
+                }
+
+                for _ in 0..count {
+                    bloom::remove(filter, media_cid);
+                }
+            }
+
+            hamt::remove(&self.ipfs, &mut index, media_cid).await?;
+
+            match orphan_index.as_mut() {
+                Some(orphans) => hamt::insert(&self.ipfs, orphans, media_cid, comments).await?,
+                None => {
+                    for index in [
+                        channel.comment_archive.as_mut(),
+                        channel.comment_counts.as_mut(),
+                        channel.upvote_index.as_mut(),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    {
+                        hamt::remove(&self.ipfs, index, media_cid).await?;
+                    }
+                }
+            }
+        }
+
+        channel.comment_index = Some(index);
+
+        if orphan_index.is_some() {
+            channel.orphan_index = orphan_index;
+        }
+
+        if let Some(filter) = filter {
+            let cid = self
+                .ipfs
+                .dag_put(&filter, Codec::default(), Codec::default())
+                .await?;
+
+            channel.comment_filter = Some(cid.into());
+        }
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(orphans
+            .into_iter()
+            .map(|(media_cid, _)| media_cid)
+            .collect())
+    }
+
+    /// Archive the comments of a media once the comment index holds `HOT_COMMENTS` of them.
+    ///
+    /// The comments are moved to a new chunk, the next ones start a new HAMT.
+    async fn archive_hot_comments(
+        &self,
+        archive: &mut Option<IPLDLink>,
+        index: &mut IPLDLink,
+        media_cid: Cid,
+    ) -> Result<(), Error> {
+        let Some(mut comments) = media_comments(&self.ipfs, *index, media_cid).await? else {
+            return Ok(());
+        };
+
+        if comments.count < HOT_COMMENTS as u64 {
+            return Ok(());
+        }
+
+        let mut hot: Vec<Cid> = hamt::values(&self.ipfs, comments.comments)
+            .map_ok(|(_, cid)| cid)
+            .try_collect()
+            .await?;
+
+        hot.sort_unstable();
+
+        let chunk = CommentChunk {
+            comments: hot.into_iter().map(IPLDLink::from).collect(),
+        };
+
+        let chunk_cid = self
+            .ipfs
+            .dag_put(&chunk, Codec::default(), Codec::default())
+            .await?;
+
+        let mut archive_index = match *archive {
+            Some(index) => index,
+            None => self
+                .ipfs
+                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
+
+        let mut archived = match hamt::get(&self.ipfs, archive_index, media_cid).await? {
+            Some(cid) => {
+                self.ipfs
+                    .dag_get::<&str, ArchivedComments>(cid, None, Codec::default())
+                    .await?
+            }
+            None => ArchivedComments::default(),
+        };
+
+        archived.chunks.push(chunk_cid.into());
+
+        let archived_cid = self
+            .ipfs
+            .dag_put(&archived, Codec::default(), Codec::default())
+            .await?;
+
+        hamt::insert(&self.ipfs, &mut archive_index, media_cid, archived_cid).await?;
+
+        *archive = Some(archive_index);
+
+        // Pinned comments stay pinned once archived.
+        comments.comments = self
+            .ipfs
+            .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+            .await?
+            .into();
+        comments.count = 0;
+
+        self.put_media_comments(index, media_cid, &comments).await?;
+
+        Ok(())
+    }
+
+    /// Store the comments of a media in the comment index.
+    ///
+    /// Returns the CID of the media comments.
+    async fn put_media_comments(
+        &self,
+        index: &mut IPLDLink,
+        media_cid: Cid,
+        comments: &MediaComments,
+    ) -> Result<Cid, Error> {
+        let cid = self
+            .ipfs
+            .dag_put(comments, Codec::default(), Codec::default())
+            .await?;
+
+        hamt::insert(&self.ipfs, index, media_cid, cid).await?;
+
+        Ok(cid)
+    }
+
+    /// Count the comments of a media not archived yet, from its comment index value.
+    async fn hot_count(&self, comments: Cid) -> Result<usize, Error> {
+        let comments = load_media_comments(&self.ipfs, comments).await?;
+
+        Ok(comments.count as usize)
+    }
+
+    /// Update the comment count of a media, media without comments are removed from the index.
+    async fn update_comment_count(
+        &self,
+        counts: &mut Option<IPLDLink>,
+        media_cid: Cid,
+        update: impl FnOnce(&mut CommentCount),
+    ) -> Result<(), Error> {
+        let mut index = match *counts {
+            Some(index) => index,
+            None => self
+                .ipfs
+                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
+
+        let mut count = match hamt::get(&self.ipfs, index, media_cid).await? {
+            Some(cid) => {
+                self.ipfs
+                    .dag_get::<&str, CommentCount>(cid, None, Codec::default())
+                    .await?
+            }
+            None => CommentCount::default(),
+        };
+
+        update(&mut count);
+
+        if count.count == 0 {
+            hamt::remove(&self.ipfs, &mut index, media_cid).await?;
+        } else {
+            let cid = self
+                .ipfs
+                .dag_put(&count, Codec::default(), Codec::default())
+                .await?;
+
+            hamt::insert(&self.ipfs, &mut index, media_cid, cid).await?;
+        }
+
+        *counts = Some(index);
+
+        Ok(())
+    }
+
+    /// Number of archived comments on this media.
+    async fn archived_count(&self, archive: IPLDLink, media_cid: Cid) -> Result<usize, Error> {
+        match hamt::get(&self.ipfs, archive, media_cid).await? {
+            Some(cid) => self.chunks_count(cid).await,
+            None => Ok(0),
+        }
+    }
+
+    /// Number of comments in all the chunks of these archived comments.
+    async fn chunks_count(&self, cid: Cid) -> Result<usize, Error> {
+        let archived = self
+            .ipfs
+            .dag_get::<&str, ArchivedComments>(cid, None, Codec::default())
+            .await?;
+
+        let mut count = 0;
+
+        for ipld in archived.chunks {
+            let chunk = self
+                .ipfs
+                .dag_get::<&str, CommentChunk>(ipld.link, None, Codec::default())
+                .await?;
+
+            count += chunk.comments.len();
+        }
+
+        Ok(count)
+    }
+
+    /// Timestamp of the latest comment on this media, hot and archived.
+    async fn latest_comment(
+        &self,
+        index: Option<IPLDLink>,
+        archive: Option<IPLDLink>,
+        media_cid: Cid,
+    ) -> Result<Option<i64>, Error> {
+        let comments = self.comment_cids(index, archive, media_cid).await?;
+
+        let mut latest = None;
+
+        for comment_cid in comments {
+            let comment: Comment = self
+                .ipfs
+                .dag_get(comment_cid, Some("/link"), Codec::default())
+                .await?;
+
+            latest = latest.max(Some(comment.user_timestamp));
+        }
+
+        Ok(latest)
+    }
+
+    /// CIDs of the comments on this media, hot and archived.
+    async fn comment_cids(
+        &self,
+        index: Option<IPLDLink>,
+        archive: Option<IPLDLink>,
+        media_cid: Cid,
+    ) -> Result<Vec<Cid>, Error> {
+        let mut comments = Vec::new();
+
+        if let Some(index) = index {
+            if let Some(hot) = media_comments(&self.ipfs, index, media_cid).await? {
+                let hot: Vec<Cid> = hamt::values(&self.ipfs, hot.comments)
+                    .map_ok(|(_, cid)| cid)
+                    .try_collect()
+                    .await?;
+
+                comments.extend(hot);
+            }
+        }
+
+        if let Some(archive) = archive {
+            if let Some(cid) = hamt::get(&self.ipfs, archive, media_cid).await? {
+                let archived = self
+                    .ipfs
+                    .dag_get::<&str, ArchivedComments>(cid, None, Codec::default())
+                    .await?;
+
+                for ipld in archived.chunks {
+                    let chunk = self
+                        .ipfs
+                        .dag_get::<&str, CommentChunk>(ipld.link, None, Codec::default())
+                        .await?;
+
+                    comments.extend(chunk.comments.into_iter().map(|ipld| ipld.link));
+                }
+            }
+        }
+
+        Ok(comments)
+    }
+
+    /// Returns true if the comment is in the archived chunks of this media.
+    async fn is_archived(
+        &self,
+        archive: Option<IPLDLink>,
+        media_cid: Cid,
+        comment_cid: Cid,
+    ) -> Result<bool, Error> {
+        let Some(index) = archive else {
+            return Ok(false);
+        };
+
+        let Some(cid) = hamt::get(&self.ipfs, index, media_cid).await? else {
+            return Ok(false);
+        };
+
+        let archived = self
+            .ipfs
+            .dag_get::<&str, ArchivedComments>(cid, None, Codec::default())
+            .await?;
+
+        let comment: IPLDLink = comment_cid.into();
+
+        for ipld in archived.chunks {
+            let chunk = self
+                .ipfs
+                .dag_get::<&str, CommentChunk>(ipld.link, None, Codec::default())
+                .await?;
+
+            if chunk.comments.contains(&comment) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Remove a comment from the archived chunks of its media.
+    ///
+    /// Returns false if the comment was not archived.
+    async fn remove_archived_comment(
+        &self,
+        archive: &mut Option<IPLDLink>,
+        media_cid: Cid,
+        comment_cid: Cid,
+    ) -> Result<bool, Error> {
+        let Some(mut index) = *archive else {
+            return Ok(false);
+        };
+
+        let Some(cid) = hamt::get(&self.ipfs, index, media_cid).await? else {
+            return Ok(false);
+        };
+
+        let mut archived = self
+            .ipfs
+            .dag_get::<&str, ArchivedComments>(cid, None, Codec::default())
+            .await?;
+
+        let comment: IPLDLink = comment_cid.into();
+        let mut found = None;
+
+        for (i, ipld) in archived.chunks.iter().enumerate() {
+            let chunk = self
+                .ipfs
+                .dag_get::<&str, CommentChunk>(ipld.link, None, Codec::default())
+                .await?;
+
+            if chunk.comments.contains(&comment) {
+                found = Some((i, chunk));
+                break;
+            }
+        }
+
+        let Some((i, mut chunk)) = found else {
+            return Ok(false);
+        };
+
+        chunk.comments.retain(|ipld| *ipld != comment);
+
+        if chunk.comments.is_empty() {
+            archived.chunks.remove(i);
+        } else {
+            let chunk_cid = self
+                .ipfs
+                .dag_put(&chunk, Codec::default(), Codec::default())
+                .await?;
+
+            archived.chunks[i] = chunk_cid.into();
+        }
+
+        let archived_cid = self
+            .ipfs
+            .dag_put(&archived, Codec::default(), Codec::default())
+            .await?;
+
+        hamt::insert(&self.ipfs, &mut index, media_cid, archived_cid).await?;
+
+        *archive = Some(index);
+
+        Ok(true)
+    }
+
+    /// Build the comment filter from the whole comment index.
+    ///
+    /// Channels created before comment filters need it once.
+    pub async fn rebuild_comment_filter(&self) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let Some(index) = channel.comment_index else {
+            return Ok(None);
+        };
+
+        let mut filter = BloomFilter::default();
+
+        let stream = hamt::values(&self.ipfs, index);
+        pin_mut!(stream);
+
+        while let Some((media_cid, comments)) = stream.try_next().await? {
+            for _ in 0..self.hot_count(comments).await? {
+                bloom::insert(&mut filter, media_cid);
+            }
+        }
+
+        if let Some(archive) = channel.comment_archive {
+            let stream = hamt::values(&self.ipfs, archive);
+            pin_mut!(stream);
+
+            while let Some((media_cid, archived)) = stream.try_next().await? {
+                for _ in 0..self.chunks_count(archived).await? {
+                    bloom::insert(&mut filter, media_cid);
+                }
+            }
+        }
+
+        let cid = self
+            .ipfs
+            .dag_put(&filter, Codec::default(), Codec::default())
+            .await?;
+
+        channel.comment_filter = Some(cid.into());
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(root))
+    }
+
+    /// Build the comment counts from the whole comment index, reading every comment.
+    ///
+    /// Channels created before comment counts need it once.
+    pub async fn rebuild_comment_counts(&self) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let Some(index) = channel.comment_index else {
+            return Ok(None);
+        };
+
+        let mut comments: HashMap<Cid, Vec<Cid>> = HashMap::new();
+
+        let stream = hamt::values(&self.ipfs, index);
+        pin_mut!(stream);
+
+        while let Some((media_cid, hot)) = stream.try_next().await? {
+            let hot = load_media_comments(&self.ipfs, hot).await?;
+
+            let hot: Vec<Cid> = hamt::values(&self.ipfs, hot.comments)
+                .map_ok(|(_, cid)| cid)
+                .try_collect()
+                .await?;
+
+            comments.entry(media_cid).or_default().extend(hot);
+        }
+
+        if let Some(archive) = channel.comment_archive {
+            let stream = hamt::values(&self.ipfs, archive);
+            pin_mut!(stream);
+
+            while let Some((media_cid, archived)) = stream.try_next().await? {
+                let archived = self
+                    .ipfs
+                    .dag_get::<&str, ArchivedComments>(archived, None, Codec::default())
+                    .await?;
+
+                for ipld in archived.chunks {
+                    let chunk = self
+                        .ipfs
+                        .dag_get::<&str, CommentChunk>(ipld.link, None, Codec::default())
+                        .await?;
+
+                    comments
+                        .entry(media_cid)
+                        .or_default()
+                        .extend(chunk.comments.into_iter().map(|ipld| ipld.link));
+                }
+            }
+        }
+
+        let mut counts = self
+            .ipfs
+            .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+            .await?
+            .into();
+
+        for (media_cid, comments) in comments {
+            if comments.is_empty() {
+                continue;
+            }
+
+            let mut count = CommentCount {
+                count: comments.len() as u64,
+                last_comment: None,
+            };
+
+            for comment_cid in comments {
+                let comment: Comment = self
+                    .ipfs
+                    .dag_get(comment_cid, Some("/link"), Codec::default())
+                    .await?;
+
+                count.last_comment = count.last_comment.max(Some(comment.user_timestamp));
+            }
+
+            let cid = self
+                .ipfs
+                .dag_put(&count, Codec::default(), Codec::default())
+                .await?;
+
+            hamt::insert(&self.ipfs, &mut counts, media_cid, cid).await?;
+        }
+
+        channel.comment_counts = Some(counts);
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(root))
+    }
+
+    /// Pin one of your channel comments on top of its media.
+    ///
+    /// At most `MAX_PINNED_COMMENTS` per media, unpin one to make room.
+    pub async fn pin_comment(&self, comment_cid: Cid) -> Result<Cid, Error> {
+        let comment: Comment = self
+            .ipfs
+            .dag_get(comment_cid, Some("/link"), Codec::default())
+            .await?;
+        let media_cid = comment.origin.ok_or(Error::NotFound)?;
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let Some(mut index) = channel.comment_index else {
+            return Err(Error::NotFound);
+        };
+
+        let Some(mut comments) = media_comments(&self.ipfs, index, media_cid).await? else {
+            return Err(Error::NotFound);
+        };
+
+        if hamt::get(&self.ipfs, comments.comments, comment_cid)
+            .await?
+            .is_none()
+        {
+            return Err(Error::NotFound);
+        }
+
+        if comments.pinned.contains(&comment_cid.into()) {
+            return Err(Error::AlreadyAdded);
+        }
+
+        if comments.pinned.len() >= MAX_PINNED_COMMENTS {
+            return Err(Error::PinLimit);
+        }
+
+        comments.pinned.push(comment_cid.into());
+
+        let comments_cid = self
+            .put_media_comments(&mut index, media_cid, &comments)
+            .await?;
+
+        channel.comment_index = Some(index);
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(comments_cid)
+    }
+
+    /// Unpin a comment, it stays in the comment index.
+    pub async fn unpin_comment(&self, comment_cid: Cid) -> Result<Option<Cid>, Error> {
+        let comment: Comment = self
+            .ipfs
+            .dag_get(comment_cid, Some("/link"), Codec::default())
+            .await?;
+        let media_cid = comment.origin.ok_or(Error::NotFound)?;
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut index = match channel.comment_index {
+            Some(it) => it,
+            _ => return Ok(None),
+        };
+
+        let Some(mut comments) = media_comments(&self.ipfs, index, media_cid).await? else {
+            return Ok(None);
+        };
+
+        let count = comments.pinned.len();
+        comments.pinned.retain(|ipld| ipld.link != comment_cid);
+
+        if comments.pinned.len() == count {
+            return Ok(None);
+        }
+
+        let comments_cid = self
+            .put_media_comments(&mut index, media_cid, &comments)
+            .await?;
+
+        channel.comment_index = Some(index);
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(comments_cid))
+    }
+
+    /// Set who can comment on one media, or on all media without their own permission.
+    pub async fn set_comment_permission(
+        &self,
+        media_cid: Option<Cid>,
+        permission: CommentPermission,
+    ) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut settings = match channel.comment_settings {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, CommentSettings>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => CommentSettings::default(),
+        };
+
+        settings.set(media_cid, permission);
+
+        let cid = self
+            .ipfs
+            .dag_put(&settings, Codec::default(), Codec::default())
+            .await?;
+
+        channel.comment_settings = Some(cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(cid)
+    }
+
+    /// Add a signed upvote on one of your channel comments.
+    ///
+    /// Each identity can upvote a comment once, the optional limiter restrict how often.
+    /// The upvote must be signed by the voting identity.
+    pub async fn add_upvote(
+        &self,
+        upvote_cid: Cid,
+        limiter: Option<&RateLimiter>,
+    ) -> Result<Cid, Error> {
+        let signed_link: SignedLink = self
+            .ipfs
+            .dag_get(upvote_cid, Option::<&str>::None, Codec::default())
+            .await?;
+
+        if !signed_link.verify() {
+            return Err(Error::Signature);
+        }
+
+        let upvote: Upvote = self
+            .ipfs
+            .dag_get(
+                signed_link.link.link,
+                Option::<&str>::None,
+                Codec::default(),
+            )
+            .await?;
+
+        let identity: Identity = self
+            .ipfs
+            .dag_get(upvote.identity.link, Option::<&str>::None, Codec::default())
+            .await?;
+
+        if signer_address(&signed_link, &identity).is_err() {
+            return Err(Error::Identity);
+        }
+
+        let comment: Comment = self
+            .ipfs
+            .dag_get(upvote.comment, Some("/link"), Codec::default())
+            .await?;
+        let media_cid = comment.origin.ok_or(Error::NotFound)?;
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let Some(comment_index) = channel.comment_index else {
+            return Err(Error::NotFound);
+        };
+
+        let Some(comments) = media_comments(&self.ipfs, comment_index, media_cid).await? else {
+            return Err(Error::NotFound);
+        };
+
+        if hamt::get(&self.ipfs, comments.comments, upvote.comment)
+            .await?
+            .is_none()
+        {
+            return Err(Error::NotFound);
+        }
+
+        let mut index = match channel.upvote_index {
+            Some(index) => index,
+            None => self
+                .ipfs
+                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
+
+        let mut upvotes = match hamt::get(&self.ipfs, index, media_cid).await? {
+            Some(upvotes) => upvotes.into(),
+            None => self
+                .ipfs
+                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
+
+        let mut voters = match hamt::get(&self.ipfs, upvotes, upvote.comment).await? {
+            Some(cid) => {
+                self.ipfs
+                    .dag_get::<&str, Voters>(cid, None, Codec::default())
+                    .await?
+            }
+            None => Voters::default(),
+        };
+
+        if !voters.voters.insert(upvote.identity) {
+            return Err(Error::AlreadyAdded);
+        }
+
+        if let Some(limiter) = limiter {
+            if !limiter.allow(upvote.identity.link, Utc::now().timestamp()) {
+                return Err(Error::RateLimited);
+            }
+        }
+
+        let voters_cid = self
+            .ipfs
+            .dag_put(&voters, Codec::default(), Codec::default())
+            .await?;
+
+        hamt::insert(&self.ipfs, &mut upvotes, upvote.comment, voters_cid).await?;
+
+        hamt::insert(&self.ipfs, &mut index, media_cid, upvotes.link).await?;
+
+        channel.upvote_index = Some(index);
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(voters_cid)
+    }
+
+    /// Remove an upvote from one of your channel comments.
+    pub async fn remove_upvote(&self, upvote_cid: Cid) -> Result<Option<Cid>, Error> {
+        let signed_link: SignedLink = self
+            .ipfs
+            .dag_get(upvote_cid, Option::<&str>::None, Codec::default())
+            .await?;
+
+        if !signed_link.verify() {
+            return Err(Error::Signature);
+        }
+
+        let upvote: Upvote = self
+            .ipfs
+            .dag_get(
+                signed_link.link.link,
+                Option::<&str>::None,
+                Codec::default(),
+            )
+            .await?;
+
+        let identity: Identity = self
+            .ipfs
+            .dag_get(upvote.identity.link, Option::<&str>::None, Codec::default())
+            .await?;
+
+        if signer_address(&signed_link, &identity).is_err() {
+            return Err(Error::Identity);
+        }
+
+        let comment: Comment = self
+            .ipfs
+            .dag_get(upvote.comment, Some("/link"), Codec::default())
+            .await?;
+        let media_cid = comment.origin.ok_or(Error::NotFound)?;
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut index = match channel.upvote_index {
+            Some(it) => it,
+            _ => return Ok(None),
+        };
+
+        let mut upvotes: IPLDLink = match hamt::get(&self.ipfs, index, media_cid).await? {
+            Some(upvotes) => upvotes.into(),
+            None => return Ok(None),
+        };
+
+        let mut voters = match hamt::get(&self.ipfs, upvotes, upvote.comment).await? {
+            Some(cid) => {
+                self.ipfs
+                    .dag_get::<&str, Voters>(cid, None, Codec::default())
+                    .await?
+            }
+            None => return Ok(None),
+        };
+
+        if !voters.voters.remove(&upvote.identity) {
+            return Ok(None);
+        }
+
+        let voters_cid = self
+            .ipfs
+            .dag_put(&voters, Codec::default(), Codec::default())
+            .await?;
+
+        hamt::insert(&self.ipfs, &mut upvotes, upvote.comment, voters_cid).await?;
+
+        hamt::insert(&self.ipfs, &mut index, media_cid, upvotes.link).await?;
+
+        channel.upvote_index = Some(index);
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(voters_cid))
+    }
+
+    /// Count signed RSVPs toward the attendees of your channel events.
+    ///
+    /// Only the latest answer of each identity counts, invalid RSVPs are skipped.
+    /// Returns the RSVPs that changed an attendance, the others are ignored.
+    pub async fn add_rsvps(&self, rsvp_cids: &[Cid]) -> Result<Vec<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut index = match channel.rsvp_index {
+            Some(index) => index,
+            None => self
+                .ipfs
+                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
+
+        let mut added = Vec::with_capacity(rsvp_cids.len());
+        let mut updated = false;
+
+        for rsvp_cid in rsvp_cids.iter().copied() {
+            let rsvp = match verify_rsvp(&self.ipfs, rsvp_cid).await {
+                Ok(Some(rsvp)) => rsvp,
+                Ok(None) => continue,
+                Err(Error::IpfsApi(e)) if e.is_decode() => continue,
+                Err(e) => return Err(e),
+            };
+
+            let media: Media = match self
+                .ipfs
+                .dag_get(rsvp.event, Some("/link"), Codec::default())
+                .await
+            {
+                Ok(media) => media,
+                Err(e) if e.is_decode() => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            match media {
+                Media::Event(event) if event.identity == channel.identity => {}
+                _ => continue,
+            }
+
+            let mut attendees = match hamt::get(&self.ipfs, index, rsvp.event).await? {
+                Some(cid) => {
+                    self.ipfs
+                        .dag_get::<&str, Attendees>(cid, None, Codec::default())
+                        .await?
+                }
+                None => Attendees::default(),
+            };
+
+            // Only the latest answer of each identity counts.
+            match attendees.answered.get(&rsvp.identity.link) {
+                Some(latest) if *latest >= rsvp.user_timestamp => continue,
+                _ => {
+                    attendees
+                        .answered
+                        .insert(rsvp.identity.link, rsvp.user_timestamp);
+                }
+            }
+
+            let changed = match rsvp.attending {
+                true => attendees.attendees.insert(rsvp.identity),
+                false => attendees.attendees.remove(&rsvp.identity),
+            };
+
+            // Newer answers are saved even if unchanged so older ones stay ignored.
+            let attendees_cid = self
+                .ipfs
+                .dag_put(&attendees, Codec::default(), Codec::default())
+                .await?;
+
+            hamt::insert(&self.ipfs, &mut index, rsvp.event, attendees_cid).await?;
+
+            updated = true;
+
+            if changed {
+                added.push(rsvp_cid);
+            }
+        }
+
+        if !updated {
+            return Ok(added);
+        }
+
+        channel.rsvp_index = Some(index);
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(added)
+    }
+
+    /// Move media older than this many months to a remote pinning service.
+    ///
+    /// Media are pinned remotely first, recorded in the archive index then unpinned locally.
+    /// From then on the channel is pinned without them, see [repin_primary].
+    ///
+    /// Returns the CIDs of the newly archived media.
+    pub async fn archive_media(&self, months: u32, service: String) -> Result<Vec<Cid>, Error> {
+        let cutoff = Utc::now()
+            .checked_sub_months(Months::new(months))
+            .ok_or(Error::Timestamp)?
+            .timestamp();
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let content_index = match channel.content_index {
+            Some(index) => index,
+            None => return Ok(vec![]),
+        };
+
+        let mut index = match channel.archive_index {
+            Some(index) => index,
+            None => self
+                .ipfs
+                .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+                .await?
+                .into(),
+        };
+
+        let defluencer = Defluencer::from(self.ipfs.clone());
+        let stream = defluencer.stream_content_rev_chrono(content_index);
+        pin_mut!(stream);
+
+        let mut archived = vec![];
+
+        while let Some(content_cid) = stream.try_next().await? {
+            let media: Media = self
+                .ipfs
+                .dag_get(content_cid, Some("/link"), Codec::default())
+                .await?;
+
+            if media.user_timestamp() >= cutoff {
+                continue;
+            }
+
+            if hamt::get(&self.ipfs, index, content_cid).await?.is_some() {
+                continue;
+            }
+
+            self.ipfs
+                .pin_remote_add(content_cid, service.clone())
+                .await?;
+
+            let record = ArchiveRecord {
+                tier: Tier::Remote,
+                service: service.clone(),
+                timestamp: Utc::now().timestamp(),
+            };
+
+            let record_cid = self
+                .ipfs
+                .dag_put(&record, Codec::default(), Codec::default())
+                .await?;
+
+            hamt::insert(&self.ipfs, &mut index, content_cid, record_cid).await?;
+
+            archived.push(content_cid);
+        }
+
+        if archived.is_empty() {
+            return Ok(archived);
+        }
+
+        channel.archive_index = Some(index);
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        // Unpinned once the new root is pinned, the update would pin them again otherwise.
+        for content_cid in archived.iter().copied() {
+            match self.ipfs.pin_rm(content_cid, true).await {
+                Ok(_) => {}
+                // Not every media is pinned on its own.
+                Err(ipfs_api::errors::Error::Ipfs(e)) if e.message.contains("not pinned") => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(archived)
+    }
+
+    /// Snapshot the channel identity and latest content into one block linked from the metadata.
+    ///
+    /// Web clients can render it with a single request then traverse the indexes.
+    pub async fn update_snapshot(&self, count: usize) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let identity = self
+            .ipfs
+            .dag_get::<&str, Identity>(channel.identity.link, None, Codec::default())
+            .await?;
+
+        let mut content = Vec::with_capacity(count);
+        let mut identities: Vec<SnapshotIdentity> = Vec::new();
+
+        if let Some(index) = channel.content_index {
+            let defluencer = Defluencer::from(self.ipfs.clone());
+            let stream = defluencer.stream_content_rev_chrono(index).take(count);
+            pin_mut!(stream);
+
+            while let Some(content_cid) = stream.try_next().await? {
+                let media: Media = self
+                    .ipfs
+                    .dag_get(content_cid, Some("/link"), Codec::default())
+                    .await?;
+
+                let link = media.identity();
+
+                if link != channel.identity && !identities.iter().any(|id| id.link == link) {
+                    let identity = self
+                        .ipfs
+                        .dag_get::<&str, Identity>(link.link, None, Codec::default())
+                        .await?;
+
+                    identities.push(SnapshotIdentity { link, identity });
+                }
+
+                content.push(SnapshotMedia {
+                    link: content_cid.into(),
+                    media,
+                });
+            }
+        }
+
+        let snapshot = Snapshot {
+            timestamp: Utc::now().timestamp(),
+            identity,
+            content_index: channel.content_index,
+            content,
+            identities,
+        };
+
+        let cid = self
+            .ipfs
+            .dag_put(&snapshot, Codec::default(), Codec::default())
+            .await?;
+
+        channel.snapshot = Some(cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(cid)
+    }
+
+    /// Add a signed listing to your registry.
+    ///
+    /// The listing identity must be the owner of the listed channel.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn add_listing(&self, listing_cid: Cid) -> Result<Cid, Error> {
+        let signed_link: SignedLink = self
+            .ipfs
+            .dag_get(listing_cid, Option::<&str>::None, Codec::default())
+            .await?;
+
+        if !signed_link.verify() {
+            return Err(Error::Signature);
+        }
+
+        let listing: Listing = self
+            .ipfs
+            .dag_get(listing_cid, Some("/link"), Codec::default())
+            .await?;
+
+        let identity: Identity = self
+            .ipfs
+            .dag_get(
+                listing.identity.link,
+                Option::<&str>::None,
+                Codec::default(),
+            )
+            .await?;
+
+        if identity.ipns_addr != Some(listing.channel) {
+            return Err(Error::IPNSMismatch);
+        }
+
+        if signer_address(&signed_link, &identity).is_err() {
+            return Err(Error::Identity);
+        }
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut tree = match channel.registry {
+            Some(ipld) => ProllyTree::load(self.ipfs.clone(), ipld.link).await?,
+            None => ProllyTree::new::<Cid>(self.ipfs.clone(), None).await?,
+        };
+
+        let key_values = listing
+            .topics
+            .iter()
+            .map(|topic| (registry::listing_key(topic, listing.channel), listing_cid));
+
+        tree.batch_insert(key_values).await?;
+
+        let cid = tree.save().await?;
+
+        channel.registry = Some(cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(cid)
+    }
+
+    /// Remove a channel from your registry under the specified topics.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn remove_listing(
+        &self,
+        addr: IPNSAddress,
+        topics: Vec<String>,
+    ) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut tree = match channel.registry {
+            Some(ipld) => ProllyTree::load(self.ipfs.clone(), ipld.link).await?,
+            None => return Ok(None),
+        };
+
+        let keys = topics
+            .iter()
+            .map(|topic| registry::listing_key(topic, addr));
+
+        tree.batch_remove::<Cid>(keys).await?;
+
+        let cid = tree.save().await?;
+
+        channel.registry = Some(cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(cid))
+    }
+
+    /// Remove every channel listed under this topic from your registry.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn remove_topic(&self, topic: &str) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut tree = match channel.registry {
+            Some(ipld) => ProllyTree::load(self.ipfs.clone(), ipld.link).await?,
+            None => return Ok(None),
+        };
+
+        tree.batch_remove_range::<Cid>(registry::topic_range(topic))
+            .await?;
+
+        let cid = tree.save().await?;
+
+        channel.registry = Some(cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(cid))
+    }
+
+    /// Set the pubsub topic where content is submitted for aggregation, none to stop aggregating.
+    pub async fn update_aggregation_channel(&self, topic: Option<String>) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        channel.agregation_channel = topic;
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(root)
+    }
+
+    /// Add content approved by moderators to your hub index.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn add_hub_content(&self, content_cid: Cid) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut tree = match channel.hub_index {
+            Some(ipld) => ProllyTree::load(self.ipfs.clone(), ipld.link).await?,
+            None => ProllyTree::new::<Cid>(self.ipfs.clone(), None).await?,
+        };
+
+        let key = hub::approval_key(Utc::now().timestamp(), content_cid);
+
+        tree.insert(key, content_cid).await?;
+
+        let cid = tree.save().await?;
+
+        channel.hub_index = Some(cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(cid)
+    }
+
+    /// Remove content from your hub index, whenever it was approved.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn remove_hub_content(&self, content_cid: Cid) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let mut tree = match channel.hub_index {
+            Some(ipld) => ProllyTree::load(self.ipfs.clone(), ipld.link).await?,
+            None => return Ok(None),
+        };
+
+        let keys: Vec<Vec<u8>> = tree
+            .stream::<Cid>()
+            .try_filter_map(|(key, cid)| future::ready(Ok((cid == content_cid).then_some(key))))
+            .try_collect()
+            .await?;
+
+        if keys.is_empty() {
+            return Ok(None);
+        }
+
+        tree.batch_remove::<Cid>(keys).await?;
+
+        let cid = tree.save().await?;
+
+        channel.hub_index = Some(cid.into());
+
+        self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(cid))
+    }
+
+    /// Copy the content, comments, variants, follows, playlists, registry and hub of another channel into this one.
+    ///
+    /// Identity, live settings, theme, comment settings and aggregation topic of this channel are kept.
+    /// Indexes are immutable, linking them is the same as rebuilding them from the original content.
+    ///
+    /// WARNING! The current indexes of this channel are replaced.
+    pub async fn fork(&self, from: IPNSAddress) -> Result<Cid, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let cid = self.ipfs.name_resolve(from).await?;
+
+        let other = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+            .await?;
+
+        let ChannelMetadata {
+            identity: _,
+            content_index,
+            comment_index,
+            reply_index,
+            comment_archive,
+            comment_filter,
+            comment_counts,
+            orphan_index,
+            upvote_index,
+            comment_settings: _,
+            live: _,
+            follows,
+            follow_lists,
+            playlists,
+            agregation_channel: _,
+            registry,
+            hub_index,
+            variant_index,
+            slug_index,
+            permalink_index,
+            tag_index,
+            rsvp_index,
+            archive_index: _,
+            theme: _,
+            owners: _,
+            approvals: _,
+            snapshot: _,
+            succession: _,
+        } = other;
+
+        channel.content_index = content_index;
+        channel.comment_index = comment_index;
+        channel.reply_index = reply_index;
+        channel.comment_archive = comment_archive;
+        channel.comment_filter = comment_filter;
+        channel.comment_counts = comment_counts;
+        channel.orphan_index = orphan_index;
+        channel.upvote_index = upvote_index;
+        channel.follows = follows;
+        channel.follow_lists = follow_lists;
+        channel.playlists = playlists;
+        channel.registry = registry;
+        channel.hub_index = hub_index;
+        channel.variant_index = variant_index;
+        channel.slug_index = slug_index;
+        channel.permalink_index = permalink_index;
+        channel.tag_index = tag_index;
+        channel.rsvp_index = rsvp_index;
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(root)
+    }
+
+    /// Share ownership of this channel, updates will require approval from `threshold` owners.
+    ///
+    /// Use a `ThresholdUpdater` to collect approvals.
+    pub async fn set_owners(&self, threshold: usize, keys: Vec<Vec<u8>>) -> Result<Cid, Error> {
+        if threshold == 0 || threshold > keys.len() {
+            return Err(Error::Approvals);
+        }
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let owners = Owners { threshold, keys };
+
+        let cid = self
+            .ipfs
+            .dag_put(&owners, Codec::default(), Codec::default())
+            .await?;
+
+        channel.owners = Some(cid.into());
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(root)
+    }
+
+    /// Return the channel to a single owner. Must be approved by the current owners.
+    pub async fn remove_owners(&self) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        if channel.owners.take().is_none() {
+            return Ok(None);
+        }
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(root))
+    }
+
+    /// Let successor keys continue the channel after this many seconds without updates.
+    ///
+    /// Every channel update delays the takeover, see `heartbeat`.
+    pub async fn set_succession(&self, keys: Vec<Vec<u8>>, inactivity: i64) -> Result<Cid, Error> {
+        if keys.is_empty() || inactivity <= 0 {
+            return Err(Error::Succession);
+        }
+
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        let succession = Succession {
+            keys,
+            inactivity,
+            heartbeat: Utc::now().timestamp(),
+        };
+
+        let cid = self
+            .ipfs
+            .dag_put(&succession, Codec::default(), Codec::default())
+            .await?;
+
+        channel.succession = Some(cid.into());
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(root)
+    }
+
+    pub async fn remove_succession(&self) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, mut channel) = self.read_metadata().await?;
+
+        if channel.succession.take().is_none() {
+            return Ok(None);
+        }
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(root))
+    }
+
+    /// Update the channel without changes to delay the takeover of successors.
+    pub async fn heartbeat(&self) -> Result<Option<Cid>, Error> {
+        let (root_cid, sequence, channel) = self.read_metadata().await?;
+
+        if channel.succession.is_none() {
+            return Ok(None);
+        }
+
+        let root = self.update_metadata(root_cid, sequence, &channel).await?;
+
+        Ok(Some(root))
+    }
+
+    pub async fn get_metadata(&self) -> Result<(Cid, ChannelMetadata), Error> {
+        let cid = self.ipfs.name_resolve(self.addr.into()).await?;
+
+        let meta = self
+            .ipfs
+            .dag_get(cid, Option::<&str>::None, Codec::default())
+            .await?;
+
+        Ok((cid, meta))
+    }
+
+    /// Get the channel metadata along with the IPNS record pointing to it.
+    ///
+    /// The record is fetched from the routing system instead of the node cache
+    /// and its signature is verified against the channel address.
+    /// Use the record sequence and validity to judge freshness.
+    pub async fn get_metadata_verified(&self) -> Result<(IPNSRecord, Cid, ChannelMetadata), Error> {
+        let record = self.get_record().await?;
+
+        let cid = record.get_value();
+
+        let meta = self
+            .ipfs
+            .dag_get(cid, Option::<&str>::None, Codec::default())
+            .await?;
+
+        Ok((record, cid, meta))
+    }
+
+    /// Fetch the channel IPNS record from the routing system and verify it.
+    async fn get_record(&self) -> Result<IPNSRecord, Error> {
+        let bytes = self.ipfs.routing_get(self.addr).await?;
+
+        let record = IPNSRecord::from_bytes(&bytes)?;
+
+        record.verify(self.addr.into())?;
+
+        if record.is_expired()? {
+            return Err(ipns_records::Error::Expired.into());
+        }
+
+        Ok(record)
+    }
+
+    /// Get the channel metadata to update, with the sequence of the IPNS record pointing to it.
+    ///
+    /// The sequence is none if the record could not be fetched in time.
+    async fn read_metadata(&self) -> Result<(Cid, Option<u64>, ChannelMetadata), Error> {
+        let (metadata, sequence) = future::join(self.get_metadata(), self.latest_sequence()).await;
+
+        let (cid, metadata) = metadata?;
+
+        Ok((cid, sequence, metadata))
+    }
+
+    /// Publish the new metadata only if the channel root is still the one it was read from,
+    /// and the IPNS record sequence did not change since.
+    ///
+    /// Returns a conflict error if another process updated the channel in the meantime.
+    async fn update_metadata(
+        &self,
+        old_cid: Cid,
+        sequence: Option<u64>,
+        channel: &ChannelMetadata,
+    ) -> Result<Cid, Error> {
+        // Every update proves the creator is still active.
+        let mut channel = channel.clone();
+
+        if let Some(ipld) = channel.succession {
+            let mut succession = self
+                .ipfs
+                .dag_get::<&str, Succession>(ipld.link, None, Codec::default())
+                .await?;
+
+            succession.heartbeat = Utc::now().timestamp();
+
+            let cid = self
+                .ipfs
+                .dag_put(&succession, Codec::default(), Codec::default())
+                .await?;
+
+            channel.succession = Some(cid.into());
+        }
+
+        let root = self
+            .ipfs
+            .dag_put(&channel, Codec::default(), Codec::default())
+            .await?;
+
+        // The node cache could hide a concurrent update.
+        let (current, latest) = future::join(
+            self.ipfs.name_resolve_nocache(self.addr),
+            self.latest_sequence(),
+        )
+        .await;
+
+        let current = current?;
+
+        // The same root published again is still a concurrent update.
+        let republished = match (sequence, latest) {
+            (Some(before), Some(after)) => before != after,
+            _ => false,
+        };
+
+        if current != old_cid || republished {
+            return Err(Error::Conflict {
+                expected: old_cid,
+                found: current,
+            });
+        }
+
+        if channel.archive_index.is_some() {
+            repin_primary(&self.ipfs, old_cid, root).await?;
+        } else {
+            self.ipfs.pin_update(old_cid, root).await?;
+        }
+
+        self.updater.update(root).await?;
+
+        Ok(root)
+    }
+
+    pub fn get_address(&self) -> IPNSAddress {
+        self.addr
+    }
+
+    /// Sequence of the latest IPNS record.
+    ///
+    /// None if the record is missing, expired, unsupported by the backend or not fetched in time.
+    async fn latest_sequence(&self) -> Option<u64> {
+        let record = self.get_record();
+        pin_mut!(record);
+
+        match future::select(record, futures_timer::Delay::new(SEQUENCE_TIMEOUT)).await {
+            future::Either::Left((Ok(record), _)) => Some(record.get_sequence()),
+            _ => None,
+        }
+    }
+
+    /// Fetch a submitted comment, a timeout error if not found in time.
+    async fn submitted_comment(&self, cid: Cid) -> Result<Comment, ipfs_api::errors::Error> {
+        let comment = self
+            .ipfs
+            .dag_get::<&str, Comment>(cid, Some("/link"), Codec::default());
+        pin_mut!(comment);
+
+        match future::select(comment, futures_timer::Delay::new(COMMENT_TIMEOUT)).await {
+            future::Either::Left((result, _)) => result,
+            future::Either::Right(_) => Err(ipfs_api::errors::Error::Timeout {
+                endpoint: String::from("dag/get"),
+                attempts: 1,
+            }),
+        }
+    }
+
+    /// Run a channel update again when it conflicts with a concurrent one.
+    ///
+    /// Updates read the latest metadata each time, retrying applies the change on top.
+    ///
+    /// Example: `channel.retry_on_conflict(3, || channel.add_content(cid)).await`
+    pub async fn retry_on_conflict<F, Fut, R>(
+        &self,
+        attempts: usize,
+        mut update: F,
+    ) -> Result<R, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<R, Error>>,
+    {
+        let mut attempt = 1;
+
+        loop {
+            match update().await {
+                Err(Error::Conflict { .. }) if attempt < attempts => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Pin the new root without the media archived remotely then unpin what only the old root kept.
+///
+/// A recursive pin of the root would keep the archived media,
+/// instead DAG-CBOR nodes are pinned one by one and other blocks recursively.
+/// Every node of both roots is visited, only channels with an archive index are pinned this way.
+pub(crate) async fn repin_primary(ipfs: &IpfsService, old: Cid, new: Cid) -> Result<(), Error> {
+    let (nodes, blocks) = primary_pins(ipfs, new).await?;
+
+    for (cid, recursive) in nodes
+        .iter()
+        .map(|cid| (*cid, false))
+        .chain(blocks.iter().map(|cid| (*cid, true)))
+    {
+        match ipfs.pin_add(cid, recursive).await {
+            Ok(_) => {}
+            // Files are pinned recursively when added.
+            Err(ipfs_api::errors::Error::Ipfs(e)) if e.message.contains("pinned recursively") => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let (old_nodes, old_blocks) = primary_pins(ipfs, old).await?;
+
+    let stale = old_nodes
+        .difference(&nodes)
+        .chain(old_blocks.difference(&blocks))
+        .copied();
+
+    // Both pin kinds are removed, the old root was pinned recursively before the first archival.
+    for cid in stale {
+        match ipfs.pin_rm(cid, true).await {
+            Ok(_) => {}
+            Err(ipfs_api::errors::Error::Ipfs(e)) if e.message.contains("not pinned") => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the DAG-CBOR nodes and the other blocks linked from this root, archived media excluded.
+async fn primary_pins(
+    ipfs: &IpfsService,
+    root: Cid,
+) -> Result<(HashSet<Cid>, HashSet<Cid>), Error> {
+    let channel = ipfs
+        .dag_get::<&str, ChannelMetadata>(root, None, Codec::default())
+        .await?;
+
+    let mut archived = HashSet::new();
+
+    if let Some(index) = channel.archive_index {
+        let stream = hamt::values(ipfs, index);
+        pin_mut!(stream);
+
+        while let Some((content_cid, record_cid)) = stream.try_next().await? {
+            let record = ipfs
+                .dag_get::<&str, ArchiveRecord>(record_cid, None, Codec::default())
+                .await?;
+
+            if record.tier == Tier::Remote {
+                archived.insert(content_cid);
+            }
+        }
+    }
+
+    let mut nodes = HashSet::new();
+    let mut blocks = HashSet::new();
+    let mut stack = vec![root];
+
+    while let Some(cid) = stack.pop() {
+        // Index keys are DAG-CBOR CIDs, like content.
+        if archived.contains(&Cid::new_v1(DAG_CBOR, *cid.hash())) {
+            continue;
+        }
+
+        if cid.codec() != DAG_CBOR {
+            blocks.insert(cid);
+            continue;
+        }
+
+        if !nodes.insert(cid) {
+            continue;
+        }
+
+        let ipld = ipfs
+            .dag_get::<&str, Ipld>(cid, None, Codec::default())
+            .await?;
+
+        ipld.references(&mut stack);
+    }
+
+    Ok((nodes, blocks))
+}
+
+/// Frontends render themes as is, only allow plain colors and web links.
+fn validate_theme(theme: &Theme) -> Result<(), Error> {
+    let valid_color = |color: &String| match color.strip_prefix('#') {
+        Some(hex) => {
+            (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    };
+
+    if !theme.accent_colors.iter().all(valid_color) {
+        return Err(Error::Theme);
+    }
+
+    if let Some(FocalPoint { x, y }) = theme.banner_focal_point {
+        if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+            return Err(Error::Theme);
+        }
+    }
+
+    let valid_link = |link: &LinkButton| {
+        ["https://", "http://", "ipfs://", "ipns://"]
+            .iter()
+            .any(|scheme| link.url.starts_with(scheme))
+    };
+
+    if !theme.links.iter().all(valid_link) {
+        return Err(Error::Theme);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #![cfg(not(target_arch = "wasm32"))]
+
+    use super::*;
+
+    use crate::{crypto::signed_link::HashAlgorithm, watch::ChannelEvent};
+
+    use ipfs_api::{mock::MockIpfs, responses::Hash};
+
+    use k256::ecdsa::{signature::DigestSigner, Signature, SigningKey};
+
+    use linked_data::media::{
+        blog::BlogPost,
+        event::{Event, Rsvp},
+        VariantKind,
+    };
+
+    use multihash::{Code, MultihashDigest};
+
+    use sha3::{Digest, Keccak256};
+
+    async fn signed(ipfs: &IpfsService, media: &impl serde::Serialize) -> Cid {
+        let link = ipfs
+            .dag_put(media, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let signed_link = SignedLink {
+            link: link.into(),
+            public_key: vec![],
+            hash_algo: HashAlgorithm::EthereumLedgerApp,
+            signature: vec![],
+        };
+
+        ipfs.dag_put(&signed_link, Codec::default(), Codec::default())
+            .await
+            .unwrap()
+    }
+
+    /// Sign like the Ethereum Ledger app would.
+    async fn eth_signed(ipfs: &IpfsService, key: &SigningKey, data: &impl serde::Serialize) -> Cid {
+        let link = ipfs
+            .dag_put(data, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let signing_input = link.hash().digest();
+        let mut eth_message =
+            format!("\x19Ethereum Signed Message:\n{}", signing_input.len()).into_bytes();
+        eth_message.extend_from_slice(signing_input);
+
+        let signature: Signature = key.sign_digest(Keccak256::new_with_prefix(eth_message));
+
+        let signed_link = SignedLink {
+            link: link.into(),
+            public_key: key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+            hash_algo: HashAlgorithm::EthereumLedgerApp,
+            signature: signature.to_der().as_bytes().to_vec(),
+        };
+
+        ipfs.dag_put(&signed_link, Codec::default(), Codec::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn blake3_channel() {
+        let ipfs = MockIpfs::new().service().with_hash(Hash::Blake3);
+
+        let identity = Identity {
+            name: String::from("Blake3 Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        assert_eq!(id_cid.hash().code(), Hash::Blake3 as u64);
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let post = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("Hello Blake3"),
+        };
+
+        let post_cid = signed(&ipfs, &post).await;
+
+        channel.add_content(post_cid).await.unwrap();
+
+        let comment = Comment {
+            origin: Some(post_cid),
+            text: String::from("Nice hash"),
+            ..post
+        };
+
+        let comment_cid = signed(&ipfs, &comment).await;
+
+        channel.add_comment(comment_cid).await.unwrap();
+
+        let (root_cid, metadata) = channel.get_metadata().await.unwrap();
+
+        assert_eq!(root_cid.hash().code(), Hash::Blake3 as u64);
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        let content: Vec<Cid> = defluencer
+            .stream_content_rev_chrono(metadata.content_index.unwrap())
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(content, vec![post_cid]);
+
+        let comments: Vec<Cid> = defluencer
+            .stream_content_comments(metadata.comment_index.unwrap(), post_cid)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(comments, vec![comment_cid]);
+
+        let keys: Vec<Cid> = hamt::values(&ipfs, metadata.comment_index.unwrap())
+            .map_ok(|(key, _)| key)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(keys, vec![post_cid]);
+
+        ipfs.key_rm("blake3_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn update_conflict() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Conflict Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let first = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("First"),
+        };
+
+        let second = Comment {
+            user_timestamp: first.user_timestamp + 1,
+            text: String::from("Second"),
+            ..first.clone()
+        };
+
+        let first_cid = signed(&ipfs, &first).await;
+        let second_cid = signed(&ipfs, &second).await;
+
+        let (root_cid, sequence, mut metadata) = channel.read_metadata().await.unwrap();
+
+        // Another writer publishes in between.
+        channel.add_content(first_cid).await.unwrap();
+
+        let (competing, _) = channel.get_metadata().await.unwrap();
+
+        metadata.agregation_channel = Some(String::from("aggregation"));
+
+        let Err(Error::Conflict { expected, found }) =
+            channel.update_metadata(root_cid, sequence, &metadata).await
+        else {
+            panic!("the update should conflict");
+        };
+
+        assert_eq!(expected, root_cid);
+        assert_eq!(found, competing);
+
+        // Only the first attempt races another writer.
+        let attempts = std::cell::Cell::new(0);
+        let (channel, attempts) = (&channel, &attempts);
+
+        let root = channel
+            .retry_on_conflict(3, || async move {
+                attempts.set(attempts.get() + 1);
+
+                let (root_cid, sequence, mut metadata) = channel.read_metadata().await?;
+
+                if attempts.get() == 1 {
+                    channel.add_content(second_cid).await?;
+                }
+
+                metadata.agregation_channel = Some(String::from("aggregation"));
+
+                channel.update_metadata(root_cid, sequence, &metadata).await
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(attempts.get(), 2);
+
+        let (root_cid, metadata) = channel.get_metadata().await.unwrap();
+
+        assert_eq!(root_cid, root);
+        assert_eq!(
+            metadata.agregation_channel,
+            Some(String::from("aggregation"))
+        );
+
+        let content: Vec<Cid> = Defluencer::from(ipfs.clone())
+            .stream_content_rev_chrono(metadata.content_index.unwrap())
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(content, vec![second_cid, first_cid]);
+
+        ipfs.key_rm("conflict_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn content_variants() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Variants Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let original = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("Hello"),
+        };
+
+        let translated = Comment {
+            user_timestamp: original.user_timestamp + 1,
+            text: String::from("Bonjour"),
+            ..original.clone()
+        };
+
+        let original_cid = signed(&ipfs, &original).await;
+        let translated_cid = signed(&ipfs, &translated).await;
+
+        channel.add_content(original_cid).await.unwrap();
+        channel.add_content(translated_cid).await.unwrap();
+
+        let a = Variant {
+            link: original_cid.into(),
+            kind: VariantKind::Original,
+            language: Some(String::from("en")),
+        };
+
+        let b = Variant {
+            link: translated_cid.into(),
+            kind: VariantKind::Translated,
+            language: Some(String::from("fr")),
+        };
+
+        channel.link_variants(a.clone(), b.clone()).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+        let variant_index = metadata.variant_index.unwrap();
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        assert_eq!(
+            defluencer
+                .content_variants(variant_index, original_cid)
+                .await
+                .unwrap(),
+            vec![b.clone()]
+        );
+        assert_eq!(
+            defluencer
+                .content_variants(variant_index, translated_cid)
+                .await
+                .unwrap(),
+            vec![a.clone()]
+        );
+
+        let groups: Vec<_> = defluencer
+            .stream_content_grouped(metadata.content_index.unwrap(), Some(variant_index))
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(groups, vec![(translated_cid, vec![a.clone()])]);
+
+        // A new version keeps the links, in both directions.
+        let edited = Comment {
+            text: String::from("Hello!"),
+            ..original.clone()
+        };
+
+        let edited_cid = signed(&ipfs, &edited).await;
+
+        channel
+            .replace_content(original_cid, edited_cid)
+            .await
+            .unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+        let variant_index = metadata.variant_index.unwrap();
+
+        assert_eq!(
+            defluencer
+                .content_variants(variant_index, edited_cid)
+                .await
+                .unwrap(),
+            vec![b]
+        );
+        assert_eq!(
+            defluencer
+                .content_variants(variant_index, translated_cid)
+                .await
+                .unwrap(),
+            vec![Variant {
+                link: edited_cid.into(),
+                ..a
+            }]
+        );
+        assert!(defluencer
+            .content_variants(variant_index, original_cid)
+            .await
+            .unwrap()
+            .is_empty());
+
+        channel.remove_content(translated_cid).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        assert!(defluencer
+            .content_variants(metadata.variant_index.unwrap(), edited_cid)
+            .await
+            .unwrap()
+            .is_empty());
+
+        ipfs.key_rm("variants_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn orphan_comments() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Orphans Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let post = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("Hello"),
+        };
+
+        let post_cid = signed(&ipfs, &post).await;
+
+        channel.add_content(post_cid).await.unwrap();
+
+        let comment = Comment {
+            origin: Some(post_cid),
+            text: String::from("Hi"),
+            ..post.clone()
+        };
+
+        let comment_cid = signed(&ipfs, &comment).await;
+
+        channel.add_comment(comment_cid).await.unwrap();
+
+        assert!(channel
+            .collect_orphan_comments(OrphanAction::Move)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Remove the post without touching the comments.
+        let (root_cid, sequence, mut metadata) = channel.read_metadata().await.unwrap();
+
+        let date_time = datetime::date_time(post.user_timestamp).unwrap();
+        datetime::remove(&ipfs, date_time, &mut metadata.content_index, post_cid)
+            .await
+            .unwrap();
+
+        channel
+            .update_metadata(root_cid, sequence, &metadata)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            channel
+                .collect_orphan_comments(OrphanAction::Move)
+                .await
+                .unwrap(),
+            vec![post_cid]
+        );
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        assert!(hamt::get(&ipfs, metadata.comment_index.unwrap(), post_cid)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(hamt::get(&ipfs, metadata.orphan_index.unwrap(), post_cid)
+            .await
+            .unwrap()
+            .is_some());
+
+        channel.add_content(post_cid).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        let comments: Vec<Cid> = defluencer
+            .stream_content_comments(metadata.comment_index.unwrap(), post_cid)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(comments, vec![comment_cid]);
+        assert!(hamt::get(&ipfs, metadata.orphan_index.unwrap(), post_cid)
+            .await
+            .unwrap()
+            .is_none());
+
+        ipfs.key_rm("orphans_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn pinned_comments() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Pinned Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let post = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("Hello"),
+        };
+
+        let post_cid = signed(&ipfs, &post).await;
+
+        channel.add_content(post_cid).await.unwrap();
+
+        let mut comment_cids = Vec::new();
+
+        for text in ["First", "Second"] {
+            let comment = Comment {
+                origin: Some(post_cid),
+                text: String::from(text),
+                ..post.clone()
+            };
+
+            comment_cids.push(signed(&ipfs, &comment).await);
+        }
+
+        channel.add_comments(&comment_cids).await.unwrap();
+
+        channel.pin_comment(comment_cids[1]).await.unwrap();
+
+        assert!(matches!(
+            channel.pin_comment(comment_cids[1]).await,
+            Err(Error::AlreadyAdded)
+        ));
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+        let index = metadata.comment_index.unwrap();
+
+        let comments = media_comments(&ipfs, index, post_cid)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(comments.pinned, vec![IPLDLink::from(comment_cids[1])]);
+        assert_eq!(comments.count, 2);
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        let streamed: Vec<Cid> = defluencer
+            .stream_content_comments_pinned_first(index, post_cid)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(streamed.len(), 2);
+        assert_eq!(streamed[0], comment_cids[1]);
+
+        channel.remove_comment(comment_cids[1]).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+        let index = metadata.comment_index.unwrap();
+
+        let comments = media_comments(&ipfs, index, post_cid)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(comments.pinned.is_empty());
+        assert_eq!(comments.count, 1);
+
+        ipfs.key_rm("pinned_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn baseline_comment_index() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Baseline Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let post = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("Hello"),
+        };
+
+        let post_cid = signed(&ipfs, &post).await;
+
+        channel.add_content(post_cid).await.unwrap();
+
+        let old = Comment {
+            origin: Some(post_cid),
+            text: String::from("Old"),
+            ..post.clone()
+        };
+
+        let old_cid = signed(&ipfs, &old).await;
+
+        // Comment index values used to be the comments HAMT itself.
+        let mut comments: IPLDLink = ipfs
+            .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+            .await
+            .unwrap()
+            .into();
+
+        hamt::insert(&ipfs, &mut comments, old_cid, old_cid)
+            .await
+            .unwrap();
+
+        let mut index: IPLDLink = ipfs
+            .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+            .await
+            .unwrap()
+            .into();
+
+        hamt::insert(&ipfs, &mut index, post_cid, comments.link)
+            .await
+            .unwrap();
+
+        let (root_cid, sequence, mut metadata) = channel.read_metadata().await.unwrap();
+        metadata.comment_index = Some(index);
+        channel
+            .update_metadata(root_cid, sequence, &metadata)
+            .await
+            .unwrap();
+
+        let found = media_comments(&ipfs, index, post_cid)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(found.comments, comments);
+        assert_eq!(found.count, 1);
+
+        let new = Comment {
+            text: String::from("New"),
+            ..old.clone()
+        };
+
+        let new_cid = signed(&ipfs, &new).await;
+
+        channel.add_comments(&[new_cid]).await.unwrap();
+        channel.remove_comment(old_cid).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+        let index = metadata.comment_index.unwrap();
+
+        let found = media_comments(&ipfs, index, post_cid)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(found.count, 1);
+        assert_eq!(
+            hamt::get(&ipfs, found.comments, new_cid).await.unwrap(),
+            Some(new_cid)
+        );
+
+        ipfs.key_rm("baseline_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn commenter_channel() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Commenter Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let identity = ipfs
+            .dag_get::<&str, Identity>(id_cid, None, Codec::default())
+            .await
+            .unwrap();
+
+        // Anyone can claim a channel address in their identity.
+        let impostor = Identity {
+            name: String::from("Impostor"),
+            ..identity.clone()
+        };
+
+        let impostor_cid = ipfs
+            .dag_put(&impostor, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let comment = Comment {
+            identity: id_cid.into(),
+            ..Default::default()
+        };
+
+        let forged = Comment {
+            identity: impostor_cid.into(),
+            ..Default::default()
+        };
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        let owner = defluencer
+            .commenter_channel(&comment, &identity)
+            .await
+            .unwrap();
+
+        assert_eq!(owner.map(|(addr, _)| addr), Some(channel.get_address()));
+
+        assert!(defluencer
+            .commenter_channel(&forged, &impostor)
+            .await
+            .unwrap()
+            .is_none());
+
+        ipfs.key_rm("commenter_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn comment_counts() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Counts Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let post = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("Hello"),
+        };
+
+        let post_cid = signed(&ipfs, &post).await;
+
+        channel.add_content(post_cid).await.unwrap();
+
+        let mut comment_cids = Vec::new();
+
+        for i in 0..3 {
+            let comment = Comment {
+                user_timestamp: post.user_timestamp + i,
+                origin: Some(post_cid),
+                text: format!("Hi {}", i),
+                ..post.clone()
+            };
+
+            comment_cids.push(signed(&ipfs, &comment).await);
+        }
+
+        channel.add_comments(&comment_cids).await.unwrap();
+        channel.remove_comment(comment_cids[0]).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        let counts = defluencer
+            .comment_counts(metadata.comment_counts.unwrap(), &[post_cid, id_cid])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            counts[&post_cid],
+            CommentCount {
+                count: 2,
+                last_comment: Some(post.user_timestamp + 2),
+            }
+        );
+        assert_eq!(counts[&id_cid].count, 0);
+
+        // The latest comment is found again when the newest one is removed.
+        channel.remove_comment(comment_cids[2]).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let counts = defluencer
+            .comment_counts(metadata.comment_counts.unwrap(), &[post_cid])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            counts[&post_cid],
+            CommentCount {
+                count: 1,
+                last_comment: Some(post.user_timestamp + 1),
+            }
+        );
+
+        channel.remove_content(post_cid).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        assert!(hamt::get(&ipfs, metadata.comment_counts.unwrap(), post_cid)
+            .await
+            .unwrap()
+            .is_none());
+
+        ipfs.key_rm("counts_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn archived_comments() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Archive Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let post = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp() - 3600,
+            origin: None,
+            reply_to: None,
+            text: String::from("Hello"),
+        };
+
+        let post_cid = signed(&ipfs, &post).await;
+
+        channel.add_content(post_cid).await.unwrap();
+
+        let mut comment_cids = Vec::with_capacity(HOT_COMMENTS + 2);
+
+        for i in 0..HOT_COMMENTS + 2 {
+            let comment = Comment {
+                user_timestamp: post.user_timestamp + i as i64,
+                origin: Some(post_cid),
+                text: format!("Hi {}", i),
+                ..post.clone()
+            };
+
+            comment_cids.push(signed(&ipfs, &comment).await);
+        }
+
+        // A full index is archived, the last two stay hot.
+        channel
+            .add_comments(&comment_cids[..HOT_COMMENTS])
+            .await
+            .unwrap();
+        channel
+            .add_comments(&comment_cids[HOT_COMMENTS..])
+            .await
+            .unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let hot = media_comments(&ipfs, metadata.comment_index.unwrap(), post_cid)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(hot.count, 2);
+        assert_eq!(
+            channel
+                .archived_count(metadata.comment_archive.unwrap(), post_cid)
+                .await
+                .unwrap(),
+            HOT_COMMENTS
+        );
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        let all: Vec<Cid> = defluencer
+            .stream_content_comments_tiered(
+                metadata.comment_index.unwrap(),
+                metadata.comment_archive,
+                post_cid,
+            )
+            .try_collect()
+            .await
+            .unwrap();
+
+        // Hot comments first.
+        assert_eq!(all.len(), HOT_COMMENTS + 2);
+        assert!(all[..2]
+            .iter()
+            .all(|cid| comment_cids[HOT_COMMENTS..].contains(cid)));
+
+        let counts = defluencer
+            .comment_counts(metadata.comment_counts.unwrap(), &[post_cid])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            counts[&post_cid],
+            CommentCount {
+                count: HOT_COMMENTS as u64 + 2,
+                last_comment: Some(post.user_timestamp + HOT_COMMENTS as i64 + 1),
+            }
+        );
+
+        // Archived comments are not added again.
+        assert!(channel
+            .add_comments(&comment_cids[1..2])
+            .await
+            .unwrap()
+            .is_empty());
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let hot = media_comments(&ipfs, metadata.comment_index.unwrap(), post_cid)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(hot.count, 2);
+
+        let counts = defluencer
+            .comment_counts(metadata.comment_counts.unwrap(), &[post_cid])
+            .await
+            .unwrap();
+
+        assert_eq!(counts[&post_cid].count, HOT_COMMENTS as u64 + 2);
+
+        // Removal works in both tiers.
+        channel.remove_comment(comment_cids[0]).await.unwrap();
+        channel
+            .remove_comment(comment_cids[HOT_COMMENTS + 1])
+            .await
+            .unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let hot = media_comments(&ipfs, metadata.comment_index.unwrap(), post_cid)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(hot.count, 1);
+        assert_eq!(
+            channel
+                .archived_count(metadata.comment_archive.unwrap(), post_cid)
+                .await
+                .unwrap(),
+            HOT_COMMENTS - 1
+        );
+        assert!(!channel
+            .is_archived(metadata.comment_archive, post_cid, comment_cids[0])
+            .await
+            .unwrap());
+
+        let counts = defluencer
+            .comment_counts(metadata.comment_counts.unwrap(), &[post_cid])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            counts[&post_cid],
+            CommentCount {
+                count: HOT_COMMENTS as u64,
+                last_comment: Some(post.user_timestamp + HOT_COMMENTS as i64),
+            }
+        );
+
+        // Archived comments go with their content.
+        channel.remove_content(post_cid).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        assert!(
+            hamt::get(&ipfs, metadata.comment_archive.unwrap(), post_cid)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        ipfs.key_rm("archive_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn hub_announcements() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Hubs Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, _) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let hub = |name: &[u8]| {
+            IPNSAddress::try_from(Cid::new_v1(0x72, Code::Sha2_256.digest(name))).unwrap()
+        };
+
+        let (first, second) = (hub(b"first"), hub(b"second"));
+
+        // Beacons need a video topic.
+        assert!(matches!(
+            channel.set_hub_announcements(&[(first, true)]).await,
+            Err(Error::NotFound)
+        ));
+
+        channel
+            .update_live_settings(None, Some(String::from("video")), None, None)
+            .await
+            .unwrap();
+
+        channel
+            .set_hub_announcements(&[(first, true), (second, true)])
+            .await
+            .unwrap();
+
+        let cid = channel
+            .set_hub_announcements(&[(first, false)])
+            .await
+            .unwrap();
+
+        let live = ipfs
+            .dag_get::<&str, LiveSettings>(cid, None, Codec::default())
+            .await
+            .unwrap();
+
+        assert_eq!(live.video_topic, "video");
+        assert_eq!(live.hubs.len(), 2);
+        assert_eq!(live.announced_hubs().collect::<Vec<_>>(), vec![second]);
+
+        ipfs.key_rm("hubs_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalid_submissions() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Submissions Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let post = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("Hello"),
+        };
+
+        let post_cid = signed(&ipfs, &post).await;
+
+        channel.add_content(post_cid).await.unwrap();
+
+        let comment = Comment {
+            origin: Some(post_cid),
+            text: String::from("Hi"),
+            ..post.clone()
+        };
+
+        let comment_cid = signed(&ipfs, &comment).await;
+
+        // Not a signed link, a comment without origin and a missing block.
+        let unsigned = ipfs
+            .dag_put(&comment, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+        let no_origin = signed(&ipfs, &post).await;
+        let missing = MockIpfs::new()
+            .service()
+            .dag_put(&"missing", Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let added = channel
+            .add_comments(&[unsigned, no_origin, missing, comment_cid])
+            .await
+            .unwrap();
+
+        assert_eq!(added, vec![comment_cid]);
+
+        ipfs.key_rm("submissions_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn archived_media() {
+        let mock = MockIpfs::new();
+        let ipfs = mock.service();
+
+        let identity = Identity {
+            name: String::from("Archive Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let old = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp() - 60 * 60 * 24 * 365,
+            origin: None,
+            reply_to: None,
+            text: String::from("Old"),
+        };
+
+        let old_cid = signed(&ipfs, &old).await;
+        let old_media = ipfs
+            .dag_get::<&str, SignedLink>(old_cid, None, Codec::default())
+            .await
+            .unwrap()
+            .link
+            .link;
+
+        let recent = Comment {
+            user_timestamp: Utc::now().timestamp(),
+            text: String::from("Recent"),
+            ..old.clone()
+        };
+
+        let recent_cid = signed(&ipfs, &recent).await;
+
+        channel.add_content(old_cid).await.unwrap();
+        channel.add_content(recent_cid).await.unwrap();
+
+        // Comments link to the media they are on.
+        let comment = Comment {
+            origin: Some(old_cid),
+            text: String::from("Hi"),
+            ..recent.clone()
+        };
+
+        let comment_cid = signed(&ipfs, &comment).await;
+
+        channel.add_comment(comment_cid).await.unwrap();
+
+        assert!(mock.is_kept(old_media));
+
+        let archived = channel
+            .archive_media(1, String::from("service"))
+            .await
+            .unwrap();
+
+        assert_eq!(archived, vec![old_cid]);
+        assert!(mock.is_pinned_remotely(old_cid));
+
+        assert!(!mock.is_kept(old_cid));
+        assert!(!mock.is_kept(old_media));
+        assert!(mock.is_kept(recent_cid));
+        assert!(mock.is_kept(comment_cid));
+
+        // Later updates keep the archived media unpinned.
+        let new = Comment {
+            text: String::from("New"),
+            ..recent.clone()
+        };
+
+        let new_cid = signed(&ipfs, &new).await;
+
+        channel.add_content(new_cid).await.unwrap();
+
+        let (root_cid, _) = channel.get_metadata().await.unwrap();
+
+        assert!(mock.is_kept(root_cid));
+        assert!(mock.is_kept(new_cid));
+        assert!(mock.is_kept(recent_cid));
+        assert!(!mock.is_kept(old_media));
+
+        ipfs.key_rm("archive_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn comment_replies() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Replies Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let post = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("Hello"),
+        };
+
+        let post_cid = signed(&ipfs, &post).await;
+
+        channel.add_content(post_cid).await.unwrap();
+
+        let comment = Comment {
+            origin: Some(post_cid),
+            text: String::from("Hi"),
+            ..post.clone()
+        };
+
+        let comment_cid = signed(&ipfs, &comment).await;
+
+        let reply = Comment {
+            reply_to: Some(comment_cid.into()),
+            text: String::from("Hi back"),
+            ..comment.clone()
+        };
+
+        let reply_cid = signed(&ipfs, &reply).await;
+
+        let nested = Comment {
+            reply_to: Some(reply_cid.into()),
+            text: String::from("Hi again"),
+            ..comment.clone()
+        };
+
+        let nested_cid = signed(&ipfs, &nested).await;
+
+        channel
+            .add_comments(&[comment_cid, reply_cid, nested_cid])
+            .await
+            .unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        let conversation: Vec<(Cid, Cid)> = defluencer
+            .stream_comment_replies(metadata.reply_index.unwrap(), comment_cid)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            conversation,
+            vec![(comment_cid, reply_cid), (reply_cid, nested_cid)]
+        );
+
+        // Replies are comments on the content too.
+        let counts = defluencer
+            .comment_counts(metadata.comment_counts.unwrap(), &[post_cid])
+            .await
+            .unwrap();
+
+        assert_eq!(counts[&post_cid].count, 3);
+
+        // The post is not a comment on itself.
+        let stray = Comment {
+            reply_to: Some(post_cid.into()),
+            text: String::from("Hi there"),
+            ..comment.clone()
+        };
+
+        let stray_cid = signed(&ipfs, &stray).await;
+
+        assert_eq!(channel.add_comment(stray_cid).await.unwrap(), None);
+
+        channel.remove_comment(reply_cid).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let conversation: Vec<(Cid, Cid)> = defluencer
+            .stream_comment_replies(metadata.reply_index.unwrap(), comment_cid)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert!(conversation.is_empty());
+        assert!(hamt::get(&ipfs, metadata.reply_index.unwrap(), comment_cid)
+            .await
+            .unwrap()
+            .is_none());
+
+        // Replies are kept with the comments of a new version.
+        let another = Comment {
+            reply_to: Some(comment_cid.into()),
+            text: String::from("Hi anyway"),
+            ..comment.clone()
+        };
+
+        let another_cid = signed(&ipfs, &another).await;
+
+        channel.add_comment(another_cid).await.unwrap();
+
+        let new_post = Comment {
+            text: String::from("Hello again"),
+            ..post.clone()
+        };
+
+        let new_post_cid = signed(&ipfs, &new_post).await;
+
+        channel
+            .replace_content(post_cid, new_post_cid)
+            .await
+            .unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        assert!(hamt::get(&ipfs, metadata.reply_index.unwrap(), comment_cid)
+            .await
+            .unwrap()
+            .is_some());
+
+        channel.remove_content(new_post_cid).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        assert!(hamt::get(&ipfs, metadata.reply_index.unwrap(), comment_cid)
+            .await
+            .unwrap()
+            .is_none());
+
+        // Foreign indexes may loop.
+        let mut replies: IPLDLink = ipfs
+            .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+            .await
+            .unwrap()
+            .into();
+
+        hamt::insert(&ipfs, &mut replies, reply_cid, reply_cid)
+            .await
+            .unwrap();
+
+        let mut loops: IPLDLink = ipfs
+            .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+            .await
+            .unwrap()
+            .into();
+
+        hamt::insert(&ipfs, &mut loops, comment_cid, comment_cid)
+            .await
+            .unwrap();
+
+        let mut index: IPLDLink = ipfs
+            .dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+            .await
+            .unwrap()
+            .into();
+
+        hamt::insert(&ipfs, &mut index, comment_cid, replies.link)
+            .await
+            .unwrap();
+        hamt::insert(&ipfs, &mut index, reply_cid, loops.link)
+            .await
+            .unwrap();
+
+        let conversation: Vec<(Cid, Cid)> = defluencer
+            .stream_comment_replies(index, comment_cid)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(conversation, vec![(comment_cid, reply_cid)]);
+    }
+
+    #[tokio::test]
+    async fn playlists() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Playlists Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let mut posts = Vec::new();
+
+        for text in ["First", "Second", "Third"] {
+            let post = Comment {
+                identity: id_cid.into(),
+                user_timestamp: Utc::now().timestamp(),
+                origin: None,
+                reply_to: None,
+                text: String::from(text),
+            };
+
+            let post_cid = signed(&ipfs, &post).await;
+
+            channel.add_content(post_cid).await.unwrap();
+
+            posts.push(post_cid);
+        }
+
+        let name = String::from("Best Of");
+
+        channel
+            .create_playlist(name.clone(), None, None)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            channel.create_playlist(name.clone(), None, None).await,
+            Err(Error::AlreadyAdded)
+        ));
+
+        for post_cid in posts.iter() {
+            channel
+                .add_to_playlist(name.clone(), *post_cid)
+                .await
+                .unwrap();
+        }
+
+        channel
+            .reorder_playlist(name.clone(), posts[2], 0)
+            .await
+            .unwrap();
+        channel
+            .remove_from_playlist(name.clone(), posts[1])
+            .await
+            .unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        let content: Vec<Cid> = defluencer
+            .stream_playlist(metadata.playlists.unwrap(), name.clone())
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(content, vec![posts[2], posts[0]]);
+
+        let edit = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("First, edited"),
+        };
+
+        let edit_cid = signed(&ipfs, &edit).await;
+
+        channel.replace_content(posts[0], edit_cid).await.unwrap();
+        channel.remove_content(posts[2]).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let content: Vec<Cid> = defluencer
+            .stream_playlist(metadata.playlists.unwrap(), name.clone())
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(content, vec![edit_cid]);
+
+        channel.remove_playlist(name).await.unwrap();
+
+        ipfs.key_rm("playlists_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn tag_index() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Tags Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let mut posts = Vec::new();
+
+        for (title, tags) in [("First", vec!["Rust"]), ("Second", vec!["rust", "IPFS"])] {
+            let post = BlogPost {
+                identity: id_cid.into(),
+                user_timestamp: Utc::now().timestamp(),
+                title: String::from(title),
+                tags: tags.into_iter().map(String::from).collect(),
+                ..Default::default()
+            };
+
+            let post_cid = signed(&ipfs, &post).await;
+
+            channel.add_content(post_cid).await.unwrap();
+
+            posts.push(post_cid);
+        }
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+        let index = metadata.tag_index.unwrap();
+
+        let mut content: Vec<Cid> = defluencer
+            .stream_content_by_tag(index, " RUST ")
+            .try_collect()
+            .await
+            .unwrap();
+        content.sort();
+
+        let mut expected = posts.clone();
+        expected.sort();
+
+        assert_eq!(content, expected);
+
+        channel.remove_content(posts[1]).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+        let index = metadata.tag_index.unwrap();
+
+        let content: Vec<Cid> = defluencer
+            .stream_content_by_tag(index, "ipfs")
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert!(content.is_empty());
+
+        ipfs.key_rm("tags_test").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn watch_updates() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Watch Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        // The mock has no routing API, names are resolved instead.
+        let defluencer = Defluencer::from(ipfs.clone());
+        let stream =
+            defluencer.watch_channel_every(channel.get_address(), Duration::from_millis(10));
+        pin_mut!(stream);
+
+        // An update that cannot be fetched does not end the watch.
+        let missing = MockIpfs::new()
+            .service()
+            .dag_put(&"missing", Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (event, _) = futures::join!(stream.next(), async {
+            futures_timer::Delay::new(Duration::from_millis(50)).await;
+
+            ipfs.name_publish(missing, "watch_test").await.unwrap();
+        });
+
+        assert!(event.unwrap().is_err());
+
+        let post = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: None,
+            reply_to: None,
+            text: String::from("Still watching?"),
+        };
+
+        let post_cid = signed(&ipfs, &post).await;
+
+        channel.add_content(post_cid).await.unwrap();
+
+        assert_eq!(
+            stream.next().await.unwrap().unwrap(),
+            ChannelEvent::NewContent(post_cid)
+        );
+
+        ipfs.key_rm("watch_test").await.unwrap();
+    }
+
+    #[cfg(feature = "ordered-trees")]
+    #[tokio::test]
+    async fn permalinks() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Permalinks Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let post = BlogPost {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            title: String::from("Hello, World!"),
+            ..Default::default()
+        };
+
+        let first = signed(&ipfs, &post).await;
+
+        let second = signed(
+            &ipfs,
+            &BlogPost {
+                user_timestamp: post.user_timestamp - 1,
+                ..post.clone()
+            },
+        )
+        .await;
+
+        channel.add_content(first).await.unwrap();
+        channel.add_content(second).await.unwrap();
+
+        let defluencer = Defluencer::from(ipfs.clone());
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        for (content_cid, slug) in [(first, "hello-world"), (second, "hello-world-2")] {
+            assert_eq!(
+                defluencer
+                    .content_slug(&metadata, content_cid)
+                    .await
+                    .unwrap(),
+                Some(String::from(slug))
+            );
+            assert_eq!(
+                defluencer
+                    .slug_content(&metadata, String::from(slug))
+                    .await
+                    .unwrap(),
+                content_cid
+            );
+        }
+
+        // Comments follow their content to its new version.
+        let comment = Comment {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            origin: Some(first),
+            reply_to: None,
+            text: String::from("First!"),
+        };
+
+        let comment_cid = signed(&ipfs, &comment).await;
+
+        channel.add_comment(comment_cid).await.unwrap();
+
+        let edited = signed(
+            &ipfs,
+            &BlogPost {
+                tags: vec![String::from("edited")],
+                ..post.clone()
+            },
+        )
+        .await;
+
+        channel.replace_content(first, edited).await.unwrap();
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        assert_eq!(
+            defluencer
+                .slug_content(&metadata, String::from("hello-world"))
+                .await
+                .unwrap(),
+            edited
+        );
+        assert_eq!(
+            defluencer
+                .comment_content(&metadata, comment.origin, comment_cid)
+                .await
+                .unwrap(),
+            Some(edited)
+        );
+
+        // Slugs of removed content are not given to new content.
+        channel.remove_content(second).await.unwrap();
+
+        let third = signed(
+            &ipfs,
+            &BlogPost {
+                user_timestamp: post.user_timestamp - 2,
+                ..post.clone()
+            },
+        )
+        .await;
+
+        channel.add_content(third).await.unwrap();
+
+        let (root_cid, sequence, mut metadata) = channel.read_metadata().await.unwrap();
+
+        assert!(defluencer
+            .slug_content(&metadata, String::from("hello-world-2"))
+            .await
+            .is_err());
+        assert_eq!(
+            defluencer.content_slug(&metadata, third).await.unwrap(),
+            Some(String::from("hello-world-3"))
+        );
+
+        // Content added before permalinks gets them, oldest first.
+        metadata.slug_index = None;
+        metadata.permalink_index = None;
+
+        channel
+            .update_metadata(root_cid, sequence, &metadata)
+            .await
+            .unwrap();
+
+        assert!(channel.backfill_slugs().await.unwrap().is_some());
+        assert!(channel.backfill_slugs().await.unwrap().is_none());
+
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        for (content_cid, slug) in [(third, "hello-world"), (edited, "hello-world-2")] {
+            assert_eq!(
+                defluencer
+                    .content_slug(&metadata, content_cid)
+                    .await
+                    .unwrap(),
+                Some(String::from(slug))
+            );
+        }
+
+        ipfs.key_rm("permalinks_test").await.unwrap();
+    }
+
+    async fn attendees(
+        ipfs: &IpfsService,
+        channel: &Channel<LocalUpdater>,
+        event: Cid,
+    ) -> Attendees {
+        let (_, metadata) = channel.get_metadata().await.unwrap();
+
+        let cid = hamt::get(ipfs, metadata.rsvp_index.unwrap(), event)
+            .await
+            .unwrap()
+            .unwrap();
+
+        ipfs.dag_get::<&str, Attendees>(cid, None, Codec::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rsvp_answers() {
+        let ipfs = MockIpfs::new().service();
+
+        let identity = Identity {
+            name: String::from("Rsvp Test"),
+            ..Default::default()
+        };
+
+        let id_cid = ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let (channel, id_cid) = Channel::create_local(ipfs.clone(), id_cid).await.unwrap();
+
+        let event = Event {
+            identity: id_cid.into(),
+            user_timestamp: Utc::now().timestamp(),
+            title: String::from("Premiere"),
+            start: Utc::now().timestamp() + 3600,
+            ..Default::default()
+        };
+
+        let event_cid = signed(&ipfs, &event).await;
+
+        channel.add_content(event_cid).await.unwrap();
+
+        let key = SigningKey::from_slice(&[1u8; 32]).unwrap();
+
+        let probe = eth_signed(&ipfs, &key, &"probe").await;
+        let probe: SignedLink = ipfs
+            .dag_get(probe, Option::<&str>::None, Codec::default())
+            .await
+            .unwrap();
+
+        let guest = Identity {
+            name: String::from("Guest"),
+            eth_addr: Some(probe.get_address()),
+            ..Default::default()
+        };
+
+        let guest_cid = ipfs
+            .dag_put(&guest, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let answer = |user_timestamp, attending| Rsvp {
+            identity: guest_cid.into(),
+            user_timestamp,
+            event: event_cid,
+            attending,
+        };
+
+        let yes = eth_signed(&ipfs, &key, &answer(20, true)).await;
+        let stale_no = eth_signed(&ipfs, &key, &answer(10, false)).await;
+        let no = eth_signed(&ipfs, &key, &answer(30, false)).await;
+
+        let not_identity = ipfs
+            .dag_put(&"not an identity", Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let bad = Rsvp {
+            identity: not_identity.into(),
+            ..answer(40, true)
+        };
+
+        let bad = eth_signed(&ipfs, &key, &bad).await;
+
+        let added = channel.add_rsvps(&[bad, yes, stale_no]).await.unwrap();
+
+        assert_eq!(added, vec![yes]);
+
+        let list = attendees(&ipfs, &channel, event_cid).await;
+
+        assert!(list.attendees.contains(&guest_cid.into()));
+        assert_eq!(list.answered.get(&guest_cid), Some(&20));
+
+        let added = channel.add_rsvps(&[no, stale_no]).await.unwrap();
+
+        assert_eq!(added, vec![no]);
+
+        let list = attendees(&ipfs, &channel, event_cid).await;
+
+        assert!(list.attendees.is_empty());
+        assert_eq!(list.answered.get(&guest_cid), Some(&30));
+
+        ipfs.key_rm("rsvp_test").await.unwrap();
     }
 }
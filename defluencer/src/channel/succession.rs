@@ -0,0 +1,88 @@
+use cid::Cid;
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{
+    channel::{
+        succession::{Succession, SuccessionClaim},
+        ChannelMetadata,
+    },
+    types::IPNSAddress,
+};
+
+use crate::{crypto::signed_link::SignedLink, errors::Error};
+
+/// Pubsub topic where successors announce their claims.
+pub fn succession_topic(addr: IPNSAddress) -> String {
+    format!("{}_succession", addr)
+}
+
+/// Returns true if the successors can take over at this time.
+pub fn is_inactive(succession: &Succession, timestamp: i64) -> bool {
+    timestamp.saturating_sub(succession.heartbeat) >= succession.inactivity
+}
+
+/// Verify a signed succession claim against the latest metadata followers observed.
+///
+/// On success, followers should follow the successor address instead.
+/// Claims made before the inactivity period ended or on older metadata are rejected.
+pub async fn verify_claim(
+    ipfs: &IpfsService,
+    addr: IPNSAddress,
+    latest: Cid,
+    claim: Cid,
+    timestamp: i64,
+) -> Result<SuccessionClaim, Error> {
+    let metadata = ipfs
+        .dag_get::<&str, ChannelMetadata>(latest, None, Codec::default())
+        .await?;
+
+    let succession = match metadata.succession {
+        Some(ipld) => {
+            ipfs.dag_get::<&str, Succession>(ipld.link, None, Codec::default())
+                .await?
+        }
+        None => return Err(Error::Succession),
+    };
+
+    let signed_link = ipfs
+        .dag_get::<&str, SignedLink>(claim, None, Codec::default())
+        .await?;
+
+    if !succession.keys.contains(&signed_link.public_key) || !signed_link.verify() {
+        return Err(Error::Succession);
+    }
+
+    let claim = ipfs
+        .dag_get::<&str, SuccessionClaim>(signed_link.link.link, None, Codec::default())
+        .await?;
+
+    if claim.channel != addr || claim.metadata.link != latest {
+        return Err(Error::Succession);
+    }
+
+    if !is_inactive(&succession, claim.timestamp) || claim.timestamp > timestamp {
+        return Err(Error::Succession);
+    }
+
+    Ok(claim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inactivity_period() {
+        let succession = Succession {
+            keys: vec![],
+            inactivity: 100,
+            heartbeat: 1_000,
+        };
+
+        assert!(!is_inactive(&succession, 1_000));
+        assert!(!is_inactive(&succession, 1_099));
+        assert!(is_inactive(&succession, 1_100));
+        assert!(!is_inactive(&succession, i64::MIN));
+    }
+}
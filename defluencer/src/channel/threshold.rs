@@ -0,0 +1,299 @@
+use std::{collections::HashSet, time::Duration};
+
+use async_trait::async_trait;
+
+use cid::Cid;
+
+use futures::{
+    future::{self, Either},
+    pin_mut, TryStreamExt,
+};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{
+    channel::{
+        owners::{Approvals, Owners},
+        ChannelMetadata,
+    },
+    types::IPNSAddress,
+};
+
+use crate::{crypto::signed_link::SignedLink, errors::Error};
+
+use super::IpnsUpdater;
+
+/// How long owners have to approve an update by default.
+pub const APPROVALS_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Pubsub topic where updates awaiting approval are announced.
+pub fn proposal_topic(addr: IPNSAddress) -> String {
+    format!("{}_proposals", addr)
+}
+
+/// Pubsub topic where owners send their approvals.
+pub fn approval_topic(addr: IPNSAddress) -> String {
+    format!("{}_approvals", addr)
+}
+
+/// IPNS updater for co-owned channels.
+///
+/// Each update is announced as a proposal then published only once enough owners approved it.
+/// Channels without owners are updated right away.
+///
+/// Other owners approve proposals with `User::approve_channel_update`.
+#[derive(Clone)]
+pub struct ThresholdUpdater<T>
+where
+    T: IpnsUpdater + Clone,
+{
+    ipfs: IpfsService,
+    addr: IPNSAddress,
+    inner: T,
+    timeout: Duration,
+}
+
+impl<T> ThresholdUpdater<T>
+where
+    T: IpnsUpdater + Clone,
+{
+    pub fn new(ipfs: IpfsService, addr: IPNSAddress, inner: T) -> Self {
+        Self {
+            ipfs,
+            addr,
+            inner,
+            timeout: APPROVALS_TIMEOUT,
+        }
+    }
+
+    /// Give up on proposals not approved within this duration.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// Wait for owners approvals of this proposal.
+    ///
+    /// Returns the CIDs of the signed links once the threshold is reached,
+    /// or an error if it is not reached before the timeout.
+    pub async fn collect_approvals(
+        &self,
+        proposal: Cid,
+        owners: &Owners,
+    ) -> Result<Vec<Cid>, Error> {
+        let topic = approval_topic(self.addr).into_bytes();
+        let stream = self.ipfs.pubsub_sub(topic);
+        pin_mut!(stream);
+
+        self.ipfs
+            .pubsub_pub(proposal_topic(self.addr), proposal.to_bytes())
+            .await?;
+
+        let collect = async {
+            let mut signers = HashSet::with_capacity(owners.threshold);
+            let mut approvals = Vec::with_capacity(owners.threshold);
+
+            while let Some(msg) = stream.try_next().await? {
+                let cid = match Cid::try_from(msg.data.as_slice()) {
+                    Ok(cid) => cid,
+                    Err(_) => continue,
+                };
+
+                let signed_link = match self
+                    .ipfs
+                    .dag_get::<&str, SignedLink>(cid, None, Codec::default())
+                    .await
+                {
+                    Ok(link) => link,
+                    Err(_) => continue,
+                };
+
+                if signed_link.link.link != proposal || !is_owner(&signed_link, owners) {
+                    continue;
+                }
+
+                if signers.insert(signed_link.public_key) {
+                    approvals.push(cid);
+                }
+
+                if approvals.len() >= owners.threshold {
+                    return Ok(approvals);
+                }
+            }
+
+            Err(Error::Approvals)
+        };
+
+        let delay = futures_timer::Delay::new(self.timeout);
+
+        pin_mut!(collect);
+
+        match future::select(collect, delay).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(Error::Approvals),
+        }
+    }
+
+    /// Add the approvals to the proposed metadata. Returns the final metadata CID.
+    pub async fn assemble(&self, proposal: Cid, signatures: Vec<Cid>) -> Result<Cid, Error> {
+        let mut metadata = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(proposal, None, Codec::default())
+            .await?;
+
+        let approvals = Approvals {
+            proposal: proposal.into(),
+            signatures: signatures.into_iter().map(|cid| cid.into()).collect(),
+        };
+
+        let approvals = self
+            .ipfs
+            .dag_put(&approvals, Codec::default(), Codec::default())
+            .await?;
+
+        metadata.approvals = Some(approvals.into());
+
+        let root = self
+            .ipfs
+            .dag_put(&metadata, Codec::default(), Codec::default())
+            .await?;
+
+        Ok(root)
+    }
+}
+
+#[async_trait(?Send)]
+impl<T> IpnsUpdater for ThresholdUpdater<T>
+where
+    T: IpnsUpdater + Clone,
+{
+    async fn update(&self, cid: Cid) -> Result<(), Error> {
+        let mut metadata = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+            .await?;
+
+        let previous = self.ipfs.name_resolve(self.addr).await?;
+        let previous = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(previous, None, Codec::default())
+            .await?;
+
+        // Current owners must approve changes to ownership.
+        let owners = match previous.owners.or(metadata.owners) {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, Owners>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => return self.inner.update(cid).await,
+        };
+
+        // Approvals of the previous update are stale.
+        let proposal = match metadata.approvals.take() {
+            Some(_) => {
+                self.ipfs
+                    .dag_put(&metadata, Codec::default(), Codec::default())
+                    .await?
+            }
+            None => cid,
+        };
+
+        let signatures = self.collect_approvals(proposal, &owners).await?;
+
+        let root = self.assemble(proposal, signatures).await?;
+
+        if metadata.archive_index.is_some() {
+            super::repin_primary(&self.ipfs, cid, root).await?;
+        } else {
+            self.ipfs.pin_update(cid, root).await?;
+        }
+
+        self.inner.update(root).await
+    }
+}
+
+/// Verify that this metadata was approved by enough owners.
+///
+/// Owners should be the last trusted set, otherwise a new set could approve itself.
+pub async fn verify_approvals(
+    ipfs: &IpfsService,
+    metadata: &ChannelMetadata,
+    owners: &Owners,
+) -> Result<(), Error> {
+    let approvals = match metadata.approvals {
+        Some(ipld) => {
+            ipfs.dag_get::<&str, Approvals>(ipld.link, None, Codec::default())
+                .await?
+        }
+        None => return Err(Error::Approvals),
+    };
+
+    let proposal = ipfs
+        .dag_get::<&str, ChannelMetadata>(approvals.proposal.link, None, Codec::default())
+        .await?;
+
+    let mut stripped = metadata.clone();
+    stripped.approvals = None;
+
+    if stripped != proposal {
+        return Err(Error::Approvals);
+    }
+
+    let mut signers = HashSet::with_capacity(approvals.signatures.len());
+
+    for ipld in approvals.signatures {
+        let signed_link = ipfs
+            .dag_get::<&str, SignedLink>(ipld.link, None, Codec::default())
+            .await?;
+
+        if signed_link.link != approvals.proposal || !is_owner(&signed_link, owners) {
+            continue;
+        }
+
+        signers.insert(signed_link.public_key);
+    }
+
+    if signers.len() < owners.threshold {
+        return Err(Error::Approvals);
+    }
+
+    Ok(())
+}
+
+fn is_owner(signed_link: &SignedLink, owners: &Owners) -> bool {
+    owners.keys.contains(&signed_link.public_key) && signed_link.verify()
+}
+
+/// Verify a channel update against the last trusted owners, if any.
+///
+/// On success, the owners declared by this update become trusted.
+pub async fn verify_update(
+    ipfs: &IpfsService,
+    cid: Cid,
+    trusted: &mut Option<Owners>,
+) -> Result<(), Error> {
+    let metadata = ipfs
+        .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+        .await?;
+
+    let declared = match metadata.owners {
+        Some(ipld) => Some(
+            ipfs.dag_get::<&str, Owners>(ipld.link, None, Codec::default())
+                .await?,
+        ),
+        None => None,
+    };
+
+    let owners = match trusted.as_ref().or(declared.as_ref()) {
+        Some(owners) => owners,
+        None => return Ok(()),
+    };
+
+    verify_approvals(ipfs, &metadata, owners).await?;
+
+    *trusted = declared;
+
+    Ok(())
+}
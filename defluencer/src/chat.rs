@@ -0,0 +1,483 @@
+use std::collections::{HashMap, HashSet};
+
+use cid::Cid;
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{
+    channel::moderation::{Bans, Moderators},
+    identity::Identity,
+    media::chat::{ChatInfo, ChatMessage, ChatSettings, MessageType},
+    types::{Address, PeerId},
+};
+
+use crate::{
+    crypto::signed_link::{HashAlgorithm, SignedLink},
+    errors::Error,
+};
+
+#[cfg(feature = "daemons")]
+use crate::reputation::Reputation;
+
+/// Encode a chat message for pubsub, in DAG-CBOR.
+pub fn encode_message(message: &ChatMessage) -> Result<Vec<u8>, Error> {
+    let data = serde_ipld_dagcbor::to_vec(message).map_err(ipfs_api::errors::Error::from)?;
+
+    Ok(data)
+}
+
+/// Decode a chat message from pubsub, none if invalid.
+///
+/// JSON messages of older clients are still accepted, for now.
+pub fn decode_message(data: &[u8]) -> Option<ChatMessage> {
+    if let Ok(message) = serde_ipld_dagcbor::from_slice(data) {
+        return Some(message);
+    }
+
+    serde_json::from_slice(data).ok()
+}
+
+/// What to do with a chat message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// Show the message.
+    Allow,
+
+    /// Drop the message.
+    Reject(ChatRejection),
+
+    /// A moderator changed the chat settings, broadcast them.
+    Settings(ChatSettings),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatRejection {
+    /// The chat signature is invalid or was not made for this peer.
+    Unverified,
+
+    Banned,
+
+    /// Only moderators can ban, add moderators or change settings.
+    NotModerator,
+
+    /// Settings older than the active ones.
+    StaleSettings,
+
+    SlowMode,
+
+    FollowersOnly,
+
+    EmoteOnly,
+}
+
+/// Enforce bans, moderation and chat settings on a live chat.
+pub struct ChatRoom {
+    ipfs: IpfsService,
+
+    bans: Bans,
+    mods: Moderators,
+    followers: HashSet<Address>,
+
+    settings: ChatSettings,
+
+    /// Verified signer of each peer, keyed by chat signature.
+    signers: HashMap<(PeerId, Cid), Address>,
+
+    last_message: HashMap<Address, i64>,
+
+    #[cfg(feature = "daemons")]
+    reputation: Reputation,
+}
+
+impl ChatRoom {
+    pub fn new(ipfs: IpfsService, bans: Bans, mods: Moderators) -> Self {
+        Self {
+            ipfs,
+            bans,
+            mods,
+            followers: Default::default(),
+            settings: Default::default(),
+            signers: Default::default(),
+            last_message: Default::default(),
+            #[cfg(feature = "daemons")]
+            reputation: Default::default(),
+        }
+    }
+
+    /// Addresses allowed to chat in followers only mode.
+    pub fn set_followers(&mut self, followers: HashSet<Address>) {
+        self.followers = followers;
+    }
+
+    /// Sender statistics of previous streams.
+    #[cfg(feature = "daemons")]
+    pub fn set_reputation(&mut self, reputation: Reputation) {
+        self.reputation = reputation;
+    }
+
+    /// Sender statistics, for moderators deciding bans.
+    #[cfg(feature = "daemons")]
+    pub fn reputation(&self) -> &Reputation {
+        &self.reputation
+    }
+
+    /// The active settings.
+    pub fn settings(&self) -> &ChatSettings {
+        &self.settings
+    }
+
+    /// The ban list, including bans made by moderators in chat.
+    pub fn bans(&self) -> &Bans {
+        &self.bans
+    }
+
+    /// Verify the sender then apply bans, moderation and settings at this time.
+    pub async fn process(
+        &mut self,
+        peer: PeerId,
+        message: &ChatMessage,
+        timestamp: i64,
+    ) -> Result<Verdict, Error> {
+        let Some(address) = self.signer(peer, message.signature.link).await? else {
+            #[cfg(feature = "daemons")]
+            self.reputation.record_unverified(peer);
+
+            return Ok(Verdict::Reject(ChatRejection::Unverified));
+        };
+
+        if self.bans.banned_addrs.contains(&address) {
+            return Ok(Verdict::Reject(ChatRejection::Banned));
+        }
+
+        let is_mod = self.mods.moderator_addrs.contains(&address);
+
+        match &message.message {
+            MessageType::Text(text) => match self.check_rules(address, text, timestamp) {
+                Ok(()) => {
+                    #[cfg(feature = "daemons")]
+                    self.reputation.record_message(address, timestamp);
+
+                    Ok(Verdict::Allow)
+                }
+                Err(reason) => {
+                    #[cfg(feature = "daemons")]
+                    self.reputation.record_rejection(address, timestamp);
+
+                    Ok(Verdict::Reject(reason))
+                }
+            },
+            MessageType::Ban(ban) if is_mod => {
+                if self.bans.banned_addrs.insert(ban.ban_addrs) {
+                    #[cfg(feature = "daemons")]
+                    self.reputation.record_ban(ban.ban_addrs);
+                }
+
+                Ok(Verdict::Allow)
+            }
+            MessageType::Mod(_) if is_mod => Ok(Verdict::Allow),
+            MessageType::Settings(ipld) if is_mod => {
+                let signed_link = self
+                    .ipfs
+                    .dag_get::<&str, SignedLink>(ipld.link, None, Codec::default())
+                    .await?;
+
+                if !signed_link.verify() || signer_address(&signed_link) != Some(address) {
+                    return Ok(Verdict::Reject(ChatRejection::Unverified));
+                }
+
+                let settings = self
+                    .ipfs
+                    .dag_get::<&str, ChatSettings>(signed_link.link.link, None, Codec::default())
+                    .await?;
+
+                match self.update_settings(settings) {
+                    Ok(()) => Ok(Verdict::Settings(self.settings.clone())),
+                    Err(reason) => Ok(Verdict::Reject(reason)),
+                }
+            }
+            _ => Ok(Verdict::Reject(ChatRejection::NotModerator)),
+        }
+    }
+
+    /// Returns the address that signed the chat info of this peer.
+    async fn signer(&mut self, peer: PeerId, signature: Cid) -> Result<Option<Address>, Error> {
+        if let Some(address) = self.signers.get(&(peer, signature)) {
+            return Ok(Some(*address));
+        }
+
+        let signed_link = match self
+            .ipfs
+            .dag_get::<&str, SignedLink>(signature, None, Codec::default())
+            .await
+        {
+            Ok(link) => link,
+            Err(e) if e.is_decode() => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        if !signed_link.verify() {
+            return Ok(None);
+        }
+
+        let info = self
+            .ipfs
+            .dag_get::<&str, ChatInfo>(signed_link.link.link, None, Codec::default())
+            .await?;
+
+        if info.node != peer {
+            return Ok(None);
+        }
+
+        let address = signer_address(&signed_link);
+
+        if let Some(address) = address {
+            self.signers.insert((peer, signature), address);
+        }
+
+        Ok(address)
+    }
+
+    fn check_rules(
+        &mut self,
+        address: Address,
+        text: &str,
+        timestamp: i64,
+    ) -> Result<(), ChatRejection> {
+        if self.mods.moderator_addrs.contains(&address) {
+            return Ok(());
+        }
+
+        if self.settings.followers_only && !self.followers.contains(&address) {
+            return Err(ChatRejection::FollowersOnly);
+        }
+
+        if self.settings.emote_only && !is_emote_only(text) {
+            return Err(ChatRejection::EmoteOnly);
+        }
+
+        if let Some(interval) = self.settings.slow_mode {
+            if let Some(last) = self.last_message.get(&address) {
+                if timestamp - last < interval as i64 {
+                    return Err(ChatRejection::SlowMode);
+                }
+            }
+        }
+
+        self.last_message.insert(address, timestamp);
+
+        Ok(())
+    }
+
+    fn update_settings(&mut self, settings: ChatSettings) -> Result<(), ChatRejection> {
+        if settings.timestamp < self.settings.timestamp {
+            return Err(ChatRejection::StaleSettings);
+        }
+
+        self.settings = settings;
+
+        Ok(())
+    }
+}
+
+/// Ethereum signers are known by their address, Bitcoin ones by their public key hash.
+fn signer_address(signed_link: &SignedLink) -> Option<Address> {
+    if signed_link.hash_algo == HashAlgorithm::BitcoinLedgerApp {
+        return Some(signed_link.get_pubkey_hash());
+    }
+
+    let address = signed_link.get_address();
+    let bytes = hex::decode(address.trim_start_matches("0x")).ok()?;
+
+    bytes.try_into().ok()
+}
+
+/// Returns the chat addresses of an identity.
+///
+/// Ethereum addresses are decoded from hex and legacy Bitcoin ones to their public key hash.
+pub fn identity_addresses(identity: &Identity) -> Vec<Address> {
+    let mut addresses = Vec::with_capacity(2);
+
+    if let Some(addr) = identity.eth_addr.as_ref() {
+        if let Some(address) = hex::decode(addr.trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+        {
+            addresses.push(address);
+        }
+    }
+
+    if let Some(addr) = identity.btc_addr.as_ref() {
+        if let Some(address) = p2pkh_hash(addr) {
+            addresses.push(address);
+        }
+    }
+
+    addresses
+}
+
+/// Returns the public key hash of a P2PKH address, none if invalid.
+fn p2pkh_hash(addr: &str) -> Option<Address> {
+    use sha2::{Digest, Sha256};
+
+    let bytes = multibase::Base::Base58Btc.decode(addr).ok()?;
+
+    if bytes.len() != 25 || bytes[0] != 0x00 {
+        return None;
+    }
+
+    let checksum = Sha256::digest(Sha256::digest(&bytes[..21]));
+
+    if checksum[..4] != bytes[21..] {
+        return None;
+    }
+
+    bytes[1..21].try_into().ok()
+}
+
+/// Returns true if the text only contains `:emote:` codes or emojis.
+pub fn is_emote_only(text: &str) -> bool {
+    let mut words = text.split_whitespace().peekable();
+
+    if words.peek().is_none() {
+        return false;
+    }
+
+    words.all(|word| {
+        let code = word.len() > 2
+            && word.starts_with(':')
+            && word.ends_with(':')
+            && word[1..word.len() - 1]
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        code || word.chars().all(|c| !c.is_alphanumeric() && !c.is_ascii())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_encoding() {
+        let signature =
+            Cid::try_from("bafyreih223c6mqauz5ouolokqrofaekpuu45eblm33fm3g2rlwdkqfabo4").unwrap();
+
+        let message = ChatMessage {
+            message: MessageType::Text(String::from("Hello chat")),
+            signature: signature.into(),
+        };
+
+        let cbor = encode_message(&message).unwrap();
+        let json = serde_json::to_vec(&message).unwrap();
+
+        assert!(cbor.len() < json.len());
+        assert_eq!(decode_message(&cbor), Some(message));
+
+        let message = ChatMessage {
+            message: MessageType::Settings(signature.into()),
+            signature: signature.into(),
+        };
+
+        let json = serde_json::to_vec(&message).unwrap();
+
+        assert_eq!(decode_message(&json), Some(message));
+        assert_eq!(decode_message(b"garbage"), None);
+    }
+
+    #[test]
+    fn emote_only() {
+        assert!(is_emote_only(":wave: :pog_champ:"));
+        assert!(is_emote_only("🎉🎉 👍"));
+        assert!(!is_emote_only("hello :wave:"));
+        assert!(!is_emote_only("::"));
+        assert!(!is_emote_only(":)"));
+        assert!(!is_emote_only(""));
+    }
+
+    #[test]
+    fn chat_rules() {
+        let user = [1u8; 20];
+        let moderator = [2u8; 20];
+
+        let mods = Moderators {
+            moderator_addrs: HashSet::from([moderator]),
+        };
+
+        let mut room = ChatRoom::new(IpfsService::default(), Bans::default(), mods);
+
+        assert_eq!(room.check_rules(user, "hello", 0), Ok(()));
+        assert_eq!(room.check_rules(user, "hello", 1), Ok(()));
+
+        let settings = ChatSettings {
+            slow_mode: Some(30),
+            followers_only: false,
+            emote_only: true,
+            timestamp: 10,
+        };
+
+        assert_eq!(room.update_settings(settings.clone()), Ok(()));
+
+        assert_eq!(
+            room.check_rules(user, "hello", 40),
+            Err(ChatRejection::EmoteOnly)
+        );
+        assert_eq!(room.check_rules(user, ":wave:", 40), Ok(()));
+        assert_eq!(
+            room.check_rules(user, ":wave:", 50),
+            Err(ChatRejection::SlowMode)
+        );
+        assert_eq!(room.check_rules(user, ":wave:", 70), Ok(()));
+
+        assert_eq!(room.check_rules(moderator, "hello", 70), Ok(()));
+        assert_eq!(room.check_rules(moderator, "hello", 71), Ok(()));
+
+        let stale = ChatSettings {
+            followers_only: true,
+            timestamp: 5,
+            ..settings
+        };
+
+        assert_eq!(
+            room.update_settings(stale.clone()),
+            Err(ChatRejection::StaleSettings)
+        );
+
+        let followers_only = ChatSettings {
+            timestamp: 20,
+            ..stale
+        };
+
+        assert_eq!(room.update_settings(followers_only), Ok(()));
+        assert_eq!(
+            room.check_rules(user, ":wave:", 200),
+            Err(ChatRejection::FollowersOnly)
+        );
+
+        room.set_followers(HashSet::from([user]));
+        assert_eq!(room.check_rules(user, ":wave:", 200), Ok(()));
+    }
+
+    #[test]
+    fn identity_chat_addresses() {
+        let identity = Identity {
+            eth_addr: Some(String::from("0x0101010101010101010101010101010101010101")),
+            btc_addr: Some(String::from("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH")),
+            ..Default::default()
+        };
+
+        let btc: Address = hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6")
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert_eq!(identity_addresses(&identity), vec![[1u8; 20], btc]);
+
+        let identity = Identity {
+            btc_addr: Some(String::from("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMh")),
+            ..Default::default()
+        };
+
+        assert!(identity_addresses(&identity).is_empty());
+    }
+}
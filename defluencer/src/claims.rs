@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use cid::Cid;
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::identity::{Claim, Identity, Platform};
+
+use reqwest::{Client, Url};
+
+use serde::Deserialize;
+
+use crate::{comments::signer_address, crypto::signed_link::SignedLink, errors::Error};
+
+/// Returns the url where the proof can be fetched.
+///
+/// None if the proof is not a post of the claimed account.
+pub fn proof_source(claim: &Claim) -> Option<String> {
+    let url = Url::parse(&claim.proof).ok()?;
+
+    if url.scheme() != "https" {
+        return None;
+    }
+
+    let host = url.host_str()?;
+
+    let segments: Vec<&str> = url
+        .path_segments()?
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    match (claim.platform, host, segments.as_slice()) {
+        (Platform::GitHub, "gist.github.com", [account, id])
+            if account.eq_ignore_ascii_case(&claim.account) =>
+        {
+            // The API returns the owner along with the files.
+            Some(format!("https://api.github.com/gists/{}", id))
+        }
+        (Platform::Twitter, "twitter.com" | "x.com", [account, "status", id])
+            if account.eq_ignore_ascii_case(&claim.account)
+                && id.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            // Posts cannot be read without an account, the embed contains the text.
+            Some(format!(
+                "https://publish.twitter.com/oembed?url=https://twitter.com/{}/status/{}",
+                account, id
+            ))
+        }
+        (Platform::Website, host, _) if host.eq_ignore_ascii_case(&claim.account) => {
+            Some(url.to_string())
+        }
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct Gist {
+    owner: Option<GistOwner>,
+
+    #[serde(default)]
+    files: HashMap<String, GistFile>,
+}
+
+#[derive(Deserialize)]
+struct GistOwner {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct GistFile {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OEmbed {
+    author_url: String,
+    html: String,
+}
+
+/// Returns the text of the proof fetched from its source.
+///
+/// None if the proof was not authored by the claimed account.
+pub fn proof_text(claim: &Claim, body: &str) -> Option<String> {
+    match claim.platform {
+        Platform::GitHub => {
+            let gist: Gist = serde_json::from_str(body).ok()?;
+
+            if !gist.owner?.login.eq_ignore_ascii_case(&claim.account) {
+                return None;
+            }
+
+            let text = gist
+                .files
+                .into_values()
+                .filter_map(|file| file.content)
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            Some(text)
+        }
+        Platform::Twitter => {
+            let embed: OEmbed = serde_json::from_str(body).ok()?;
+
+            let url = Url::parse(&embed.author_url).ok()?;
+            let author = url.path_segments()?.find(|segment| !segment.is_empty())?;
+
+            if !author.eq_ignore_ascii_case(&claim.account) {
+                return None;
+            }
+
+            Some(embed.html)
+        }
+        Platform::Website => Some(body.to_owned()),
+    }
+}
+
+/// Returns true if the proof contains the signer address.
+pub fn proof_matches(proof: &str, address: &str) -> bool {
+    proof.to_lowercase().contains(&address.to_lowercase())
+}
+
+/// Verify a claim of this identity, fetching the proof from the platform.
+///
+/// Returns none if the claim is not signed by the identity or the proof is missing.
+pub async fn verify_claim(
+    ipfs: &IpfsService,
+    claim_cid: Cid,
+    identity: &Identity,
+) -> Result<Option<Claim>, Error> {
+    let signed_link = ipfs
+        .dag_get::<&str, SignedLink>(claim_cid, None, Codec::default())
+        .await?;
+
+    if !signed_link.verify() {
+        return Ok(None);
+    }
+
+    let address = match signer_address(&signed_link, identity) {
+        Ok(address) => address,
+        Err(_) => return Ok(None),
+    };
+
+    let claim = ipfs
+        .dag_get::<&str, Claim>(signed_link.link.link, None, Codec::default())
+        .await?;
+
+    let source = match proof_source(&claim) {
+        Some(source) => source,
+        None => return Ok(None),
+    };
+
+    // GitHub's API refuses requests without a user agent.
+    let response = Client::new()
+        .get(source)
+        .header("User-Agent", "defluencer")
+        .send()
+        .await
+        .map_err(ipfs_api::errors::Error::from)?;
+
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(ipfs_api::errors::Error::from)?;
+
+    let proof = match proof_text(&claim, &body) {
+        Some(proof) => proof,
+        None => return Ok(None),
+    };
+
+    match proof_matches(&proof, &address) {
+        true => Ok(Some(claim)),
+        false => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(platform: Platform, account: &str, proof: &str) -> Claim {
+        Claim {
+            user_timestamp: 0,
+            platform,
+            account: account.to_owned(),
+            proof: proof.to_owned(),
+        }
+    }
+
+    #[test]
+    fn proof_sources() {
+        let gist = claim(
+            Platform::GitHub,
+            "SionoiS",
+            "https://gist.github.com/sionois/0123abcd",
+        );
+
+        assert_eq!(
+            proof_source(&gist).as_deref(),
+            Some("https://api.github.com/gists/0123abcd")
+        );
+
+        let stolen = claim(
+            Platform::GitHub,
+            "SionoiS",
+            "https://gist.github.com/impostor/0123abcd",
+        );
+
+        assert_eq!(proof_source(&stolen), None);
+
+        let post = claim(
+            Platform::Twitter,
+            "defluencer",
+            "https://x.com/defluencer/status/1234567890",
+        );
+
+        assert!(proof_source(&post).is_some());
+
+        let website = claim(
+            Platform::Website,
+            "defluencer.eth.limo",
+            "https://defluencer.eth.limo/claim.txt",
+        );
+
+        assert!(proof_source(&website).is_some());
+
+        let insecure = claim(
+            Platform::Website,
+            "defluencer.eth.limo",
+            "http://defluencer.eth.limo/claim.txt",
+        );
+
+        assert_eq!(proof_source(&insecure), None);
+    }
+
+    #[test]
+    fn proof_authors() {
+        let gist = claim(
+            Platform::GitHub,
+            "SionoiS",
+            "https://gist.github.com/sionois/0123abcd",
+        );
+
+        let body = r#"{"owner":{"login":"sionois"},"files":{"proof.txt":{"content":"0xabc"}}}"#;
+        assert_eq!(proof_text(&gist, body).as_deref(), Some("0xabc"));
+
+        let body = r#"{"owner":{"login":"impostor"},"files":{"proof.txt":{"content":"0xabc"}}}"#;
+        assert_eq!(proof_text(&gist, body), None);
+
+        let post = claim(
+            Platform::Twitter,
+            "defluencer",
+            "https://x.com/defluencer/status/1234567890",
+        );
+
+        let body = r#"{"author_name":"Defluencer","author_url":"https://twitter.com/defluencer","html":"0xabc"}"#;
+        assert_eq!(proof_text(&post, body).as_deref(), Some("0xabc"));
+
+        // Quoting someone else's post.
+        let body = r#"{"author_name":"defluencer","author_url":"https://twitter.com/impostor","html":"0xabc"}"#;
+        assert_eq!(proof_text(&post, body), None);
+    }
+
+    #[test]
+    fn proof_contains_address() {
+        let address = "0xAbC0000000000000000000000000000000000001";
+
+        assert!(proof_matches(
+            "Verifying my defluencer identity: 0xabc0000000000000000000000000000000000001",
+            address
+        ));
+        assert!(!proof_matches("Nothing to see here", address));
+    }
+}
@@ -0,0 +1,557 @@
+use std::{collections::HashSet, sync::RwLock};
+
+use cid::Cid;
+
+use futures::{future, TryStreamExt};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{
+    channel::{follows::Follows, moderation::Blocklist, ChannelMetadata},
+    identity::Identity,
+    indexes::hamt::HAMTRoot,
+    media::comments::{Comment, CommentPermission, CommentSettings, MediaComments},
+    types::{IPLDLink, IPNSAddress},
+};
+
+use crate::{
+    crypto::signed_link::{HashAlgorithm, SignedLink},
+    errors::Error,
+    filter::Filter,
+    indexing::hamt,
+    limits::MAX_NODE_SIZE,
+    rate_limit::RateLimiter,
+    recovery::Recoveries,
+    timestamp::TimestampPolicy,
+};
+
+/// Pubsub topic where comments are submitted to a channel.
+pub fn comments_topic(addr: IPNSAddress) -> String {
+    format!("{}_comments", addr)
+}
+
+/// Identity of a commenter whose signature was checked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedIdentity {
+    pub identity: Identity,
+
+    /// Blockchain address that signed the comment.
+    pub address: String,
+}
+
+/// Why a comment could not be verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// The comment is not wrapped in a signed link.
+    Unsigned,
+
+    /// The signature does not match the comment.
+    Signature,
+
+    /// The signer is not the address declared by the commenter identity.
+    IdentityMismatch,
+
+    /// Signatures of this blockchain cannot be attributed yet.
+    Unsupported,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejection {
+    pub cid: Cid,
+    pub reason: RejectionReason,
+}
+
+pub type VerifiedComment = Result<(Cid, Comment, VerifiedIdentity), Rejection>;
+
+/// Fetch a comment's signed link, check the signature then attribute it to the claimed identity.
+///
+/// Unverifiable comments are rejected, network errors are returned as is.
+pub async fn verify_comment(ipfs: &IpfsService, cid: Cid) -> Result<VerifiedComment, Error> {
+    verify_comment_recovered(ipfs, cid, &Recoveries::default()).await
+}
+
+/// Same as `verify_comment` but also accept the addresses of recovered identities.
+pub async fn verify_comment_recovered(
+    ipfs: &IpfsService,
+    cid: Cid,
+    recoveries: &Recoveries,
+) -> Result<VerifiedComment, Error> {
+    let reject = |reason| Ok(Err(Rejection { cid, reason }));
+
+    let signed_link = match ipfs
+        .dag_get::<&str, SignedLink>(cid, None, Codec::default())
+        .await
+    {
+        Ok(link) => link,
+        Err(e) if e.is_decode() => return reject(RejectionReason::Unsigned),
+        Err(e) => return Err(e.into()),
+    };
+
+    if !signed_link.verify() {
+        return reject(RejectionReason::Signature);
+    }
+
+    let comment = ipfs
+        .dag_get::<&str, Comment>(signed_link.link.link, None, Codec::default())
+        .await?;
+
+    let identity = ipfs
+        .dag_get::<&str, Identity>(comment.identity.link, None, Codec::default())
+        .await?;
+
+    let address = match signer_address(&signed_link, &identity) {
+        Ok(address) => address,
+        Err(RejectionReason::IdentityMismatch)
+            if recoveries.is_recovered(comment.identity.link, &signed_link.get_address()) =>
+        {
+            signed_link.get_address()
+        }
+        Err(reason) => return reject(reason),
+    };
+
+    let verified = VerifiedIdentity { identity, address };
+
+    Ok(Ok((cid, comment, verified)))
+}
+
+/// Why a verified comment was refused by a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// The comment is not on any media.
+    NoOrigin,
+
+    TooLong,
+
+    /// The comment or its signed link node is larger than the maximum size.
+    TooLarge,
+
+    /// The comment is dated too far in the future or before Unix time.
+    Timestamp,
+
+    Blocked,
+
+    RateLimited,
+
+    /// Comments are closed on this media.
+    Closed,
+
+    /// The commenter is not a follower or vouched user as required by the media.
+    NotPermitted,
+}
+
+/// Rules a channel applies to comments submitted by others.
+#[derive(Debug)]
+pub struct CommentPolicy {
+    /// Maximum comment length in bytes.
+    pub max_length: usize,
+
+    /// Maximum comment and signed link node size in bytes.
+    pub max_size: u64,
+
+    pub blocklist: Blocklist,
+
+    pub limiter: RateLimiter,
+
+    pub timestamps: TimestampPolicy,
+
+    /// Comment permissions of the channel's media.
+    settings: RwLock<CommentSettings>,
+
+    /// Channels followed by this channel, their users are vouched for.
+    vouched: RwLock<HashSet<IPNSAddress>>,
+}
+
+impl CommentPolicy {
+    pub fn new(max_length: usize, limiter: RateLimiter) -> Self {
+        Self {
+            max_length,
+            max_size: MAX_NODE_SIZE,
+            blocklist: Blocklist::default(),
+            limiter,
+            timestamps: TimestampPolicy::default(),
+            settings: Default::default(),
+            vouched: Default::default(),
+        }
+    }
+
+    /// Replace the comment permissions, eg. after the channel changed them.
+    pub fn set_settings(&self, settings: CommentSettings) {
+        *self.settings.write().expect("Lock Poisoned") = settings;
+    }
+
+    /// Replace the channels whose users are vouched for.
+    pub fn set_vouched(&self, vouched: HashSet<IPNSAddress>) {
+        *self.vouched.write().expect("Lock Poisoned") = vouched;
+    }
+
+    /// Use the comment permissions and followed channels of this channel metadata.
+    pub async fn load_channel(
+        &self,
+        ipfs: &IpfsService,
+        metadata: &ChannelMetadata,
+    ) -> Result<(), Error> {
+        let settings = match metadata.comment_settings {
+            Some(ipld) => {
+                ipfs.dag_get::<&str, CommentSettings>(ipld.link, None, Codec::default())
+                    .await?
+            }
+            None => CommentSettings::default(),
+        };
+
+        let vouched = match metadata.follows {
+            Some(ipld) => {
+                ipfs.dag_get::<&str, Follows>(ipld.link, None, Codec::default())
+                    .await?
+                    .followees
+            }
+            None => HashSet::new(),
+        };
+
+        self.set_settings(settings);
+        self.set_vouched(vouched);
+
+        Ok(())
+    }
+
+    /// Check the size of a node before downloading it.
+    pub fn check_size(&self, size: u64) -> Result<(), PolicyViolation> {
+        if size > self.max_size {
+            return Err(PolicyViolation::TooLarge);
+        }
+
+        Ok(())
+    }
+
+    /// Check the rules then count the comment against the commenter's rate limit.
+    pub fn check(&self, comment: &Comment, timestamp: i64) -> Result<(), PolicyViolation> {
+        self.check_rules(comment, timestamp)?;
+
+        self.count(comment, timestamp)
+    }
+
+    /// Check the rules without counting the comment, rate limited commenters are rejected.
+    pub fn check_rules(&self, comment: &Comment, timestamp: i64) -> Result<(), PolicyViolation> {
+        if comment.origin.is_none() {
+            return Err(PolicyViolation::NoOrigin);
+        }
+
+        if comment.text.len() > self.max_length {
+            return Err(PolicyViolation::TooLong);
+        }
+
+        if self
+            .timestamps
+            .check(comment.user_timestamp, timestamp)
+            .is_err()
+        {
+            return Err(PolicyViolation::Timestamp);
+        }
+
+        if !self.blocklist.allow_comment(comment) {
+            return Err(PolicyViolation::Blocked);
+        }
+
+        if self.limiter.is_limited(comment.identity.link, timestamp) {
+            return Err(PolicyViolation::RateLimited);
+        }
+
+        Ok(())
+    }
+
+    /// Count the comment against the commenter's rate limit.
+    pub fn count(&self, comment: &Comment, timestamp: i64) -> Result<(), PolicyViolation> {
+        if !self.limiter.allow(comment.identity.link, timestamp) {
+            return Err(PolicyViolation::RateLimited);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the permission of the media commented on.
+    pub fn permission(&self, comment: &Comment) -> CommentPermission {
+        let settings = self.settings.read().expect("Lock Poisoned");
+
+        match comment.origin {
+            Some(origin) => settings.get(origin),
+            None => settings.permission,
+        }
+    }
+
+    /// Check that the commenter can comment on this media.
+    ///
+    /// The commenter channel must be one whose metadata names the commenter identity,
+    /// see `Defluencer::commenter_channel`.
+    /// Whether it follows this channel only matters for the followers permission.
+    pub fn check_permission(
+        &self,
+        comment: &Comment,
+        channel: Option<IPNSAddress>,
+        follower: bool,
+    ) -> Result<(), PolicyViolation> {
+        let vouched =
+            channel.is_some_and(|addr| self.vouched.read().expect("Lock Poisoned").contains(&addr));
+
+        match self.permission(comment) {
+            CommentPermission::Everyone => Ok(()),
+            CommentPermission::Followers if follower || vouched => Ok(()),
+            CommentPermission::Vouched if vouched => Ok(()),
+            CommentPermission::Closed => Err(PolicyViolation::Closed),
+            _ => Err(PolicyViolation::NotPermitted),
+        }
+    }
+}
+
+/// Returns the comments of this media in a channel comment index.
+pub async fn media_comments(
+    ipfs: &IpfsService,
+    comment_index: IPLDLink,
+    media_cid: Cid,
+) -> Result<Option<MediaComments>, Error> {
+    let Some(cid) = hamt::get(ipfs, comment_index, media_cid).await? else {
+        return Ok(None);
+    };
+
+    let comments = load_media_comments(ipfs, cid).await?;
+
+    Ok(Some(comments))
+}
+
+/// Decode a comment index value.
+///
+/// Channels created before media comments store the comments HAMT directly, those are recounted.
+pub(crate) async fn load_media_comments(
+    ipfs: &IpfsService,
+    cid: Cid,
+) -> Result<MediaComments, Error> {
+    match ipfs
+        .dag_get::<&str, MediaComments>(cid, None, Codec::default())
+        .await
+    {
+        Ok(comments) => return Ok(comments),
+        Err(e) if e.is_decode() => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    ipfs.dag_get::<&str, HAMTRoot>(cid, None, Codec::default())
+        .await?;
+
+    let count = hamt::values(ipfs, cid.into())
+        .try_fold(0, |count, _| future::ready(Ok(count + 1)))
+        .await?;
+
+    Ok(MediaComments {
+        comments: cid.into(),
+        count,
+        pinned: Vec::new(),
+    })
+}
+
+pub(crate) fn signer_address(
+    signed_link: &SignedLink,
+    identity: &Identity,
+) -> Result<String, RejectionReason> {
+    let (address, claimed) = match signed_link.hash_algo {
+        HashAlgorithm::EthereumLedgerApp => (signed_link.get_address(), identity.eth_addr.as_ref()),
+        HashAlgorithm::BitcoinLedgerApp => return Err(RejectionReason::Unsupported),
+    };
+
+    match claimed {
+        Some(claimed) if claimed.eq_ignore_ascii_case(&address) => Ok(address),
+        _ => Err(RejectionReason::IdentityMismatch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use k256::ecdsa::SigningKey;
+
+    use multihash::{Code, MultihashDigest};
+
+    fn signed_link(hash_algo: HashAlgorithm) -> SignedLink {
+        let key = SigningKey::from_slice(&[1u8; 32]).unwrap();
+        let public_key = key
+            .verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec();
+
+        SignedLink {
+            link: Cid::new_v1(0x71, Code::Sha2_256.digest(b"comment")).into(),
+            public_key,
+            hash_algo,
+            signature: vec![],
+        }
+    }
+
+    #[test]
+    fn attribute_signer() {
+        let link = signed_link(HashAlgorithm::EthereumLedgerApp);
+        let address = link.get_address();
+
+        let identity = Identity {
+            eth_addr: Some(address.to_uppercase().replace("0X", "0x")),
+            ..Default::default()
+        };
+
+        assert_eq!(signer_address(&link, &identity), Ok(address));
+
+        let impostor = Identity {
+            eth_addr: Some(format!("0x{}", "00".repeat(20))),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            signer_address(&link, &impostor),
+            Err(RejectionReason::IdentityMismatch)
+        );
+
+        assert_eq!(
+            signer_address(&link, &Identity::default()),
+            Err(RejectionReason::IdentityMismatch)
+        );
+
+        let link = signed_link(HashAlgorithm::BitcoinLedgerApp);
+
+        assert_eq!(
+            signer_address(&link, &identity),
+            Err(RejectionReason::Unsupported)
+        );
+    }
+
+    #[test]
+    fn comment_policy() {
+        let identity = Cid::new_v1(0x71, Code::Sha2_256.digest(b"identity"));
+        let origin = Cid::new_v1(0x71, Code::Sha2_256.digest(b"media"));
+
+        let mut policy = CommentPolicy::new(10, RateLimiter::new(1, 60));
+
+        let comment = Comment {
+            identity: identity.into(),
+            user_timestamp: 0,
+            origin: Some(origin),
+            reply_to: None,
+            text: String::from("Nice!"),
+        };
+
+        assert_eq!(policy.check(&comment, 0), Ok(()));
+        assert_eq!(
+            policy.check(&comment, 10),
+            Err(PolicyViolation::RateLimited)
+        );
+        assert_eq!(policy.check(&comment, 60), Ok(()));
+
+        // Rejected comments cost nothing.
+        assert_eq!(policy.check_rules(&comment, 120), Ok(()));
+        assert_eq!(policy.check_rules(&comment, 120), Ok(()));
+        assert_eq!(policy.count(&comment, 120), Ok(()));
+        assert_eq!(
+            policy.check_rules(&comment, 130),
+            Err(PolicyViolation::RateLimited)
+        );
+
+        let long = Comment {
+            text: String::from("Way too long to be accepted"),
+            ..comment.clone()
+        };
+
+        assert_eq!(policy.check(&long, 200), Err(PolicyViolation::TooLong));
+
+        assert_eq!(policy.check_size(MAX_NODE_SIZE), Ok(()));
+        assert_eq!(
+            policy.check_size(MAX_NODE_SIZE + 1),
+            Err(PolicyViolation::TooLarge)
+        );
+
+        let orphan = Comment {
+            origin: None,
+            ..comment.clone()
+        };
+
+        assert_eq!(policy.check(&orphan, 200), Err(PolicyViolation::NoOrigin));
+
+        let future = Comment {
+            user_timestamp: 1000,
+            ..comment.clone()
+        };
+
+        assert_eq!(policy.check(&future, 200), Err(PolicyViolation::Timestamp));
+
+        policy.blocklist.identities.insert(identity.into());
+
+        assert_eq!(policy.check(&comment, 200), Err(PolicyViolation::Blocked));
+    }
+
+    #[test]
+    fn comment_permissions() {
+        let origin = Cid::new_v1(0x71, Code::Sha2_256.digest(b"media"));
+        let other = Cid::new_v1(0x71, Code::Sha2_256.digest(b"other"));
+        let friend =
+            IPNSAddress::try_from("bafzbeiegbnjh5uopd5vc22tgkz6chf7a6ala3x5e47vnhv5sq5bzo46tri")
+                .unwrap();
+
+        let policy = CommentPolicy::new(10, RateLimiter::new(1, 60));
+        policy.set_vouched(HashSet::from([friend]));
+
+        let comment = Comment {
+            origin: Some(origin),
+            ..Default::default()
+        };
+
+        // Commenter channels, verified to name the commenter identity.
+        let stranger = None;
+        let vouched = Some(friend);
+
+        assert_eq!(policy.check_permission(&comment, stranger, false), Ok(()));
+
+        let mut settings = CommentSettings::default();
+        settings.set(Some(origin), CommentPermission::Followers);
+        policy.set_settings(settings.clone());
+
+        assert_eq!(
+            policy.check_permission(&comment, stranger, false),
+            Err(PolicyViolation::NotPermitted)
+        );
+        assert_eq!(policy.check_permission(&comment, stranger, true), Ok(()));
+        assert_eq!(policy.check_permission(&comment, vouched, false), Ok(()));
+
+        settings.set(Some(origin), CommentPermission::Vouched);
+        policy.set_settings(settings.clone());
+
+        assert_eq!(
+            policy.check_permission(&comment, stranger, true),
+            Err(PolicyViolation::NotPermitted)
+        );
+        assert_eq!(policy.check_permission(&comment, vouched, false), Ok(()));
+
+        settings.set(None, CommentPermission::Closed);
+        policy.set_settings(settings.clone());
+
+        assert_eq!(policy.permission(&comment), CommentPermission::Vouched);
+        assert_eq!(
+            policy.check_permission(
+                &Comment {
+                    origin: Some(other),
+                    ..Default::default()
+                },
+                vouched,
+                true
+            ),
+            Err(PolicyViolation::Closed)
+        );
+
+        // Explicit permissions stay, even when equal to the channel one.
+        settings.set(Some(origin), CommentPermission::Closed);
+        settings.set(None, CommentPermission::Everyone);
+        policy.set_settings(settings.clone());
+
+        assert_eq!(policy.permission(&comment), CommentPermission::Closed);
+
+        settings.unset(origin);
+        policy.set_settings(settings.clone());
+
+        assert!(settings.media.is_empty());
+        assert_eq!(policy.permission(&comment), CommentPermission::Everyone);
+    }
+}
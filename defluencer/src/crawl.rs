@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use cid::Cid;
+
+use linked_data::{
+    channel::ChannelMetadata,
+    types::{IPLDLink, IPNSAddress},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+
+/// State of a web crawl, serialize it to resume later.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct CrawlCheckpoint {
+    /// Channel metadata fully crawled, followees included.
+    pub visited: HashSet<IPLDLink>,
+
+    /// Channels not yet fully crawled.
+    pub frontier: HashSet<IPNSAddress>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrawlEvent {
+    /// A channel metadata CID and content.
+    Channel(Cid, ChannelMetadata),
+
+    /// Crawl state at this point.
+    ///
+    /// Channels in the frontier may be returned again after resuming.
+    Checkpoint(CrawlCheckpoint),
+}
+
+/// Bookkeeping of an ongoing crawl.
+#[derive(Debug, Default)]
+pub(crate) struct CrawlState {
+    /// Channel metadata already seen.
+    pub seen: HashSet<Cid>,
+
+    /// Channel metadata fully crawled.
+    pub visited: HashSet<Cid>,
+
+    /// Channels being crawled.
+    pub pending: HashSet<IPNSAddress>,
+
+    /// Channels returned since the last checkpoint.
+    pub count: usize,
+}
+
+impl CrawlState {
+    /// Mark this channel as crawled, followees included.
+    pub fn done(&mut self, addr: IPNSAddress, cid: Cid) {
+        self.pending.remove(&addr);
+        self.visited.insert(cid);
+    }
+
+    pub fn checkpoint(&mut self) -> CrawlCheckpoint {
+        self.count = 0;
+
+        CrawlCheckpoint {
+            visited: self.visited.iter().map(|cid| (*cid).into()).collect(),
+            frontier: self.pending.clone(),
+        }
+    }
+}
+
+impl From<CrawlCheckpoint> for CrawlState {
+    fn from(checkpoint: CrawlCheckpoint) -> Self {
+        let visited: HashSet<Cid> = checkpoint
+            .visited
+            .into_iter()
+            .map(|ipld| ipld.link)
+            .collect();
+
+        Self {
+            seen: visited.clone(),
+            visited,
+            pending: HashSet::new(),
+            count: 0,
+        }
+    }
+}
+
+/// Returns None if the request timed out.
+///
+/// Unresponsive channels are skipped and stay in the frontier, resumed crawls try them again.
+pub(crate) fn skip_timeout<T, E: Into<Error>>(result: Result<T, E>) -> Result<Option<T>, Error> {
+    match result.map_err(Into::into) {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::IpfsApi(e)) if e.is_timeout() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    #[test]
+    fn resume_from_checkpoint() {
+        let cid =
+            Cid::try_from("bafyreih223c6mqauz5ouolokqrofaekpuu45eblm33fm3g2rlwdkqfabo4").unwrap();
+        let addr =
+            IPNSAddress::try_from("bafzbeiegbnjh5uopd5vc22tgkz6chf7a6ala3x5e47vnhv5sq5bzo46tri")
+                .unwrap();
+        let other =
+            IPNSAddress::try_from(Cid::new_v1(0x72, Code::Sha2_256.digest(b"other"))).unwrap();
+
+        let mut state = CrawlState::default();
+        state.seen.insert(cid);
+        state.pending.insert(addr);
+        state.pending.insert(other);
+        state.count = 10;
+
+        state.done(addr, cid);
+
+        let checkpoint = state.checkpoint();
+
+        assert_eq!(state.count, 0);
+        assert_eq!(checkpoint.frontier, HashSet::from([other]));
+
+        let json = serde_json::to_string(&checkpoint).unwrap();
+        let checkpoint: CrawlCheckpoint = serde_json::from_str(&json).unwrap();
+
+        let resumed = CrawlState::from(checkpoint);
+
+        assert!(resumed.seen.contains(&cid));
+        assert!(resumed.visited.contains(&cid));
+        assert!(resumed.pending.is_empty());
+    }
+}
@@ -0,0 +1,509 @@
+use std::{collections::HashSet, io::Cursor, ops::Bound};
+
+use cid::Cid;
+
+use futures::{pin_mut, TryStreamExt};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use libipld_core::ipld::Ipld;
+
+use linked_data::{
+    channel::{follows::Follows, ChannelMetadata},
+    types::IPNSAddress,
+};
+
+use crate::{
+    channel::{local::LocalUpdater, threshold::verify_update, IpnsUpdater},
+    errors::Error,
+    indexing::ordered_trees::{errors::Error as TreeError, prolly::ProllyTree, staging::Staging},
+};
+
+/// Prefix of channel keys, sorted before all follow keys.
+const CHANNEL_PREFIX: u8 = 0x00;
+
+/// Prefix of follow keys, sorted by follower then followee.
+const FOLLOW_PREFIX: u8 = 0x01;
+
+/// Value of a crawl snapshot tree pair.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum GraphValue {
+    /// A follow edge, both channels are in the key.
+    #[default]
+    Follow,
+
+    /// A crawled channel metadata and whether its owners approved it.
+    ///
+    /// Channels without owners have nothing to verify.
+    Channel {
+        metadata: Cid,
+        verified: Option<bool>,
+    },
+}
+
+impl From<GraphValue> for Ipld {
+    fn from(value: GraphValue) -> Self {
+        match value {
+            GraphValue::Follow => Ipld::Null,
+            GraphValue::Channel { metadata, verified } => {
+                let verified = match verified {
+                    Some(verified) => Ipld::Bool(verified),
+                    None => Ipld::Null,
+                };
+
+                Ipld::List(vec![Ipld::Link(metadata), verified])
+            }
+        }
+    }
+}
+
+impl TryFrom<Ipld> for GraphValue {
+    type Error = TreeError;
+
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        let list = match ipld {
+            Ipld::Null => return Ok(Self::Follow),
+            Ipld::List(list) => list,
+            _ => return Err(TreeError::UnknownValueType),
+        };
+
+        let verified = match list.as_slice() {
+            [Ipld::Link(_), Ipld::Bool(verified)] => Some(*verified),
+            [Ipld::Link(_), Ipld::Null] => None,
+            _ => return Err(TreeError::UnknownValueType),
+        };
+
+        let Some(Ipld::Link(metadata)) = list.first() else {
+            return Err(TreeError::UnknownValueType);
+        };
+
+        Ok(Self::Channel {
+            metadata: *metadata,
+            verified,
+        })
+    }
+}
+
+/// One decoded pair of a crawl snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrawlRecord {
+    Channel {
+        addr: IPNSAddress,
+        metadata: Cid,
+        verified: Option<bool>,
+    },
+    Follow {
+        follower: IPNSAddress,
+        followee: IPNSAddress,
+    },
+}
+
+impl CrawlRecord {
+    /// Returns the record of a snapshot pair, none if the key is unknown.
+    pub fn decode(key: &[u8], value: GraphValue) -> Option<Self> {
+        let (prefix, rest) = key.split_first()?;
+
+        let mut reader = Cursor::new(rest);
+
+        let first = Cid::read_bytes(&mut reader).ok()?;
+        let first = IPNSAddress::try_from(first).ok()?;
+
+        let record = match (*prefix, value) {
+            (CHANNEL_PREFIX, GraphValue::Channel { metadata, verified }) => Self::Channel {
+                addr: first,
+                metadata,
+                verified,
+            },
+            (FOLLOW_PREFIX, GraphValue::Follow) => {
+                let second = Cid::read_bytes(&mut reader).ok()?;
+
+                Self::Follow {
+                    follower: first,
+                    followee: IPNSAddress::try_from(second).ok()?,
+                }
+            }
+            _ => return None,
+        };
+
+        if reader.position() as usize != rest.len() {
+            return None;
+        }
+
+        Some(record)
+    }
+}
+
+/// Return the snapshot tree key of this channel.
+pub fn channel_key(addr: IPNSAddress) -> Vec<u8> {
+    let cid: Cid = addr.into();
+
+    let mut key = vec![CHANNEL_PREFIX];
+    key.extend(cid.to_bytes());
+
+    key
+}
+
+/// Return the snapshot tree key of this follow.
+pub fn follow_key(follower: IPNSAddress, followee: IPNSAddress) -> Vec<u8> {
+    let followee: Cid = followee.into();
+
+    let mut key = follows_prefix(follower);
+    key.extend(followee.to_bytes());
+
+    key
+}
+
+/// Return the prefix of the snapshot tree keys of this channel follows.
+fn follows_prefix(follower: IPNSAddress) -> Vec<u8> {
+    let follower: Cid = follower.into();
+
+    let mut key = vec![FOLLOW_PREFIX];
+    key.extend(follower.to_bytes());
+
+    key
+}
+
+/// Crawl results saved in a prolly tree published under an IPNS address.
+///
+/// Others can stream a snapshot instead of crawling again,
+/// or diff two snapshots since unchanged parts of the web share tree nodes.
+pub struct CrawlSnapshot<T>
+where
+    T: IpnsUpdater + Clone,
+{
+    ipfs: IpfsService,
+    addr: IPNSAddress,
+    updater: T,
+
+    /// Last root published by this snapshot.
+    root: Option<Cid>,
+
+    /// Root published at this address before the snapshot started over, unpinned once replaced.
+    previous: Option<Cid>,
+
+    tree: ProllyTree,
+    staging: Staging<Vec<u8>, GraphValue>,
+}
+
+impl CrawlSnapshot<LocalUpdater> {
+    /// Create a new snapshot with your IPFS node local keys.
+    ///
+    /// The key is generated if it does not exist.
+    pub async fn create_local(ipfs: IpfsService, key: String) -> Result<Self, Error> {
+        let addr = match ipfs.key_list().await?.get(&key) {
+            Some(addr) => *addr,
+            None => IPNSAddress::try_from(ipfs.key_gen(key.clone()).await?.id)?,
+        };
+
+        let updater = LocalUpdater::new(ipfs.clone(), key);
+
+        Self::new(ipfs, addr, updater).await
+    }
+
+    /// Continue the snapshot last published with your IPFS node local key.
+    pub async fn resume_local(ipfs: IpfsService, key: String) -> Result<Self, Error> {
+        let addr = match ipfs.key_list().await?.get(&key) {
+            Some(addr) => *addr,
+            None => return Err(Error::NotFound),
+        };
+
+        let updater = LocalUpdater::new(ipfs.clone(), key);
+
+        Self::resume(ipfs, addr, updater).await
+    }
+}
+
+impl<T> CrawlSnapshot<T>
+where
+    T: IpnsUpdater + Clone,
+{
+    /// Start an empty snapshot.
+    ///
+    /// The snapshot previously published at this address is unpinned when this one is published.
+    pub async fn new(ipfs: IpfsService, addr: IPNSAddress, updater: T) -> Result<Self, Error> {
+        let tree = ProllyTree::new::<GraphValue>(ipfs.clone(), None).await?;
+
+        let previous = ipfs.name_resolve(addr).await.ok();
+
+        Ok(Self {
+            ipfs,
+            addr,
+            updater,
+            root: None,
+            previous,
+            tree,
+            staging: Staging::default(),
+        })
+    }
+
+    /// Continue the snapshot published at this address, for resumed crawls.
+    pub async fn resume(ipfs: IpfsService, addr: IPNSAddress, updater: T) -> Result<Self, Error> {
+        let root = ipfs.name_resolve(addr).await?;
+
+        let tree = ProllyTree::load(ipfs.clone(), root).await?;
+
+        Ok(Self {
+            ipfs,
+            addr,
+            updater,
+            root: Some(root),
+            previous: None,
+            tree,
+            staging: Staging::default(),
+        })
+    }
+
+    /// Stage a crawled channel, its verification status and follows.
+    ///
+    /// Follows recorded before but no longer in the channel are removed.
+    /// Channels whose approvals could not be fetched are not staged, the error is returned.
+    ///
+    /// Nothing is written until published.
+    pub async fn record(
+        &mut self,
+        addr: IPNSAddress,
+        cid: Cid,
+        metadata: &ChannelMetadata,
+    ) -> Result<(), Error> {
+        let verified = match metadata.owners {
+            Some(_) => match verify_update(&self.ipfs, cid, &mut None).await {
+                Ok(()) => Some(true),
+                Err(Error::Approvals) => Some(false),
+                Err(Error::IpfsApi(e)) if e.is_decode() => Some(false),
+                Err(e) => return Err(e),
+            },
+            None => None,
+        };
+
+        let followees = match metadata.follows {
+            Some(ipld) => {
+                self.ipfs
+                    .dag_get::<&str, Follows>(ipld.link, None, Codec::default())
+                    .await?
+                    .followees
+            }
+            None => HashSet::new(),
+        };
+
+        let followees: HashSet<Vec<u8>> = followees
+            .into_iter()
+            .map(|followee| follow_key(addr, followee))
+            .collect();
+
+        let prefix = follows_prefix(addr);
+
+        let stream = self
+            .tree
+            .stream_range::<GraphValue>((Bound::Included(prefix.clone()), Bound::Unbounded))
+            .try_take_while(|(key, _)| futures::future::ready(Ok(key.starts_with(&prefix))));
+        pin_mut!(stream);
+
+        let mut removed = Vec::new();
+
+        while let Some((key, _)) = stream.try_next().await? {
+            if !followees.contains(&key) {
+                removed.push(key);
+            }
+        }
+
+        self.staging.insert(
+            channel_key(addr),
+            GraphValue::Channel {
+                metadata: cid,
+                verified,
+            },
+        );
+
+        self.staging.batch_remove(removed);
+
+        self.staging
+            .batch_insert(followees.into_iter().map(|key| (key, GraphValue::Follow)));
+
+        Ok(())
+    }
+
+    /// Number of changes not yet published.
+    pub fn staged(&self) -> usize {
+        self.staging.len()
+    }
+
+    /// Write the staged changes then publish the new tree.
+    ///
+    /// Returns the tree CID.
+    pub async fn publish(&mut self) -> Result<Cid, Error> {
+        self.tree.commit(&mut self.staging).await?;
+
+        let root = self.tree.save().await?;
+
+        match self.root {
+            Some(old_root) if old_root == root => return Ok(root),
+            Some(old_root) => self.ipfs.pin_update(old_root, root).await?,
+            None => self.ipfs.pin_add(root, true).await?,
+        }
+
+        self.updater.update(root).await?;
+
+        self.root = Some(root);
+
+        if let Some(previous) = self.previous.take().filter(|previous| *previous != root) {
+            match self.ipfs.pin_rm(previous, true).await {
+                Ok(_) => {}
+                // Published by another node or unpinned by hand.
+                Err(ipfs_api::errors::Error::Ipfs(e)) if e.message.contains("not pinned") => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(root)
+    }
+
+    pub fn get_address(&self) -> IPNSAddress {
+        self.addr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    #[test]
+    fn key_roundtrip() {
+        let addr =
+            IPNSAddress::try_from("bafzbeiegbnjh5uopd5vc22tgkz6chf7a6ala3x5e47vnhv5sq5bzo46tri")
+                .unwrap();
+        let other =
+            IPNSAddress::try_from(Cid::new_v1(0x72, Code::Sha2_256.digest(b"other"))).unwrap();
+        let metadata = Cid::new_v1(0x71, Code::Sha2_256.digest(b"metadata"));
+
+        let value = GraphValue::Channel {
+            metadata,
+            verified: Some(true),
+        };
+
+        let ipld: Ipld = value.clone().into();
+        assert_eq!(GraphValue::try_from(ipld).unwrap(), value);
+
+        let unowned = GraphValue::Channel {
+            metadata,
+            verified: None,
+        };
+
+        let ipld: Ipld = unowned.clone().into();
+        assert_eq!(GraphValue::try_from(ipld).unwrap(), unowned);
+
+        let ipld: Ipld = GraphValue::Follow.into();
+        assert_eq!(GraphValue::try_from(ipld).unwrap(), GraphValue::Follow);
+
+        assert_eq!(
+            CrawlRecord::decode(&channel_key(addr), value.clone()),
+            Some(CrawlRecord::Channel {
+                addr,
+                metadata,
+                verified: Some(true)
+            })
+        );
+
+        let key = follow_key(addr, other);
+
+        assert!(channel_key(other) < key);
+        assert_eq!(
+            CrawlRecord::decode(&key, GraphValue::Follow),
+            Some(CrawlRecord::Follow {
+                follower: addr,
+                followee: other
+            })
+        );
+
+        assert_eq!(CrawlRecord::decode(&key, value), None);
+        assert_eq!(
+            CrawlRecord::decode(&key[..key.len() - 1], GraphValue::Follow),
+            None
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn resumed_snapshot() {
+        use ipfs_api::mock::MockIpfs;
+
+        let mock = MockIpfs::new();
+        let ipfs = mock.service();
+
+        let addr = |name: &[u8]| {
+            IPNSAddress::try_from(Cid::new_v1(0x72, Code::Sha2_256.digest(name))).unwrap()
+        };
+
+        let (channel, a, b) = (addr(b"channel"), addr(b"a"), addr(b"b"));
+
+        let mut roots = Vec::new();
+
+        for (resume, followees) in [(false, vec![a, b]), (true, vec![a]), (false, vec![b])] {
+            let key = String::from("crawl_test");
+
+            let mut snapshot = if resume {
+                CrawlSnapshot::resume_local(ipfs.clone(), key).await
+            } else {
+                CrawlSnapshot::create_local(ipfs.clone(), key).await
+            }
+            .unwrap();
+
+            let follows = Follows {
+                followees: followees.into_iter().collect(),
+            };
+
+            let follows = ipfs
+                .dag_put(&follows, Codec::default(), Codec::default())
+                .await
+                .unwrap();
+
+            let metadata = ChannelMetadata {
+                follows: Some(follows.into()),
+                ..Default::default()
+            };
+
+            let cid = ipfs
+                .dag_put(&metadata, Codec::default(), Codec::default())
+                .await
+                .unwrap();
+
+            snapshot.record(channel, cid, &metadata).await.unwrap();
+
+            roots.push((cid, snapshot.publish().await.unwrap()));
+        }
+
+        // Follows removed by the channel are dropped when resuming.
+        let (cid, root) = roots[1];
+
+        let records: Vec<_> = ProllyTree::load(ipfs.clone(), root)
+            .await
+            .unwrap()
+            .stream::<GraphValue>()
+            .map_ok(|(key, value)| CrawlRecord::decode(&key, value).unwrap())
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            records,
+            vec![
+                CrawlRecord::Channel {
+                    addr: channel,
+                    metadata: cid,
+                    verified: None,
+                },
+                CrawlRecord::Follow {
+                    follower: channel,
+                    followee: a,
+                },
+            ]
+        );
+
+        // Starting over unpins the snapshot published before.
+        assert!(!mock.is_pinned(root));
+        assert!(mock.is_pinned(roots[2].1));
+
+        ipfs.key_rm("crawl_test").await.unwrap();
+    }
+}
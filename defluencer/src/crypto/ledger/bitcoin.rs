@@ -40,8 +40,11 @@ impl BitcoinLedgerApp {
     /// The public key is shown on the screen. After confirmation by the user.
     ///
     /// Returns the public key & full serialized extended version as per BIP-32.
-    pub fn get_extended_pubkey(&self, index: u32) -> Result<(VerifyingKey, String), Error> {
-        let response = self.addr(index)?;
+    pub fn get_extended_pubkey(
+        &self,
+        path: &DerivationPath,
+    ) -> Result<(VerifyingKey, String), Error> {
+        let response = self.addr(path)?;
 
         let addr = std::str::from_utf8(response.data())?;
 
@@ -63,20 +66,16 @@ impl BitcoinLedgerApp {
         Ok((public_key, addr.to_owned()))
     }
 
-    fn addr(&self, index: u32) -> Result<APDUAnswer<Vec<u8>>, LedgerAppError<LedgerHIDError>> {
+    fn addr(
+        &self,
+        path: &DerivationPath,
+    ) -> Result<APDUAnswer<Vec<u8>>, LedgerAppError<LedgerHIDError>> {
         // https://github.com/LedgerHQ/app-bitcoin-new/blob/develop/doc/bitcoin.md#get_extended_pubkey
 
         let mut data = Vec::with_capacity(22);
 
         data.push(1_u8); // Show on screen
-        data.push(5_u8); // Number of BIP 32 derivations to perform
-
-        // Derivation Path, hardend key start with 0x8xxxxxxx
-        data.extend(0x8000002C_u32.to_be_bytes()); // Purpose 4 bytes
-        data.extend(0x80000000_u32.to_be_bytes()); // Coin type 4 bytes
-        data.extend(0x80000000_u32.to_be_bytes()); // Account 4 bytes
-        data.extend(0x00000000_u32.to_be_bytes()); // Change 4 bytes
-        data.extend(index.to_be_bytes()); // Index 4 bytes
+        data.extend(path.encode()?); // Derivation Path
 
         let command = APDUCommand {
             cla: BitcoinLedgerApp::CLA,
@@ -134,8 +133,12 @@ impl BitcoinLedgerApp {
     /// The display hash is the Sha2-256 of the message.
     ///
     /// Returns EcDSA signature.
-    pub fn sign_message(&self, message: &[u8], index: u32) -> Result<(Signature, RecoveryId), Error> {
-        let response = self.sign(message, index)?;
+    pub fn sign_message(
+        &self,
+        message: &[u8],
+        path: &DerivationPath,
+    ) -> Result<(Signature, RecoveryId), Error> {
+        let response = self.sign(message, path)?;
 
         /* #[cfg(debug_assertions)]
         println!("Response Data: {:?}", response.data()); */
@@ -153,7 +156,7 @@ impl BitcoinLedgerApp {
     fn sign(
         &self,
         message: &[u8],
-        index: u32,
+        path: &DerivationPath,
     ) -> Result<APDUAnswer<Vec<u8>>, LedgerAppError<LedgerHIDError>> {
         // https://github.com/LedgerHQ/app-bitcoin-new/blob/develop/doc/bitcoin.md#sign_message
         // https://docs.rs/bitcoin/0.28.1/bitcoin/consensus/encode/struct.VarInt.html
@@ -164,14 +167,7 @@ impl BitcoinLedgerApp {
 
         let mut data = Vec::with_capacity(255);
 
-        data.push(5_u8); // Number of BIP 32 derivations to perform
-
-        // Derivation Path, hardend key start with 0x8xxxxxxx
-        data.extend(0x8000002C_u32.to_be_bytes()); // Purpose 4 bytes
-        data.extend(0x80000000_u32.to_be_bytes()); // Coin type 4 bytes
-        data.extend(0x80000000_u32.to_be_bytes()); // Account 4 bytes
-        data.extend(0x00000000_u32.to_be_bytes()); // Change 4 bytes
-        data.extend(index.to_be_bytes()); // Index 4 bytes
+        data.extend(path.encode()?); // Derivation Path
 
         let msg_length = VarInt(message.len() as u64).consensus_encode();
 
@@ -407,6 +403,75 @@ impl BitcoinLedgerApp {
     }
 }
 
+/// BIP-44 derivation path, ```m/44'/<coin type>'/<account>'/<change>/<index>```.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DerivationPath {
+    /// 0 for mainnet, 1 for testnet.
+    pub coin_type: u32,
+
+    pub account: u32,
+
+    /// 0 for receiving addresses, 1 for change addresses.
+    pub change: u32,
+
+    pub index: u32,
+}
+
+impl DerivationPath {
+    pub const MAINNET: u32 = 0;
+    pub const TESTNET: u32 = 1;
+
+    /// Mainnet path of this account.
+    pub fn account(account: u32) -> Self {
+        Self {
+            account,
+            ..Default::default()
+        }
+    }
+
+    pub fn testnet(mut self) -> Self {
+        self.coin_type = Self::TESTNET;
+        self
+    }
+
+    /// Returns the number of derivations followed by each of them, big endian.
+    ///
+    /// Hardened derivations start with 0x8xxxxxxx, every index must be lower
+    /// or the path signed with would not be the one displayed.
+    fn encode(&self) -> Result<Vec<u8>, LedgerAppError<LedgerHIDError>> {
+        const HARDENED: u32 = 0x80000000;
+
+        if [self.coin_type, self.account, self.change, self.index]
+            .into_iter()
+            .any(|index| index >= HARDENED)
+        {
+            return Err(LedgerAppError::InvalidDerivePath);
+        }
+
+        let mut data = Vec::with_capacity(21);
+
+        data.push(5_u8); // Number of BIP 32 derivations to perform
+
+        data.extend((HARDENED | 44).to_be_bytes()); // Purpose 4 bytes
+        data.extend((HARDENED | self.coin_type).to_be_bytes()); // Coin type 4 bytes
+        data.extend((HARDENED | self.account).to_be_bytes()); // Account 4 bytes
+        data.extend(self.change.to_be_bytes()); // Change 4 bytes
+        data.extend(self.index.to_be_bytes()); // Index 4 bytes
+
+        Ok(data)
+    }
+}
+
+impl std::fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "m/44'/{}'/{}'/{}/{}",
+            self.coin_type, self.account, self.change, self.index
+        )
+    }
+}
+
 #[derive(Clone)]
 pub struct BitcoinMerkle {}
 
@@ -439,3 +504,47 @@ impl Hasher for BitcoinMerkle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derivation_path() {
+        let path = DerivationPath::default();
+
+        assert_eq!(path.to_string(), "m/44'/0'/0'/0/0");
+        assert_eq!(
+            path.encode().unwrap(),
+            vec![5, 0x80, 0, 0, 44, 0x80, 0, 0, 0, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        let path = DerivationPath {
+            change: 1,
+            index: 7,
+            ..DerivationPath::account(2).testnet()
+        };
+
+        assert_eq!(path.to_string(), "m/44'/1'/2'/1/7");
+        assert_eq!(
+            &path.encode().unwrap()[5..],
+            &[0x80, 0, 0, 1, 0x80, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 7]
+        );
+
+        assert!(DerivationPath::account(0x80000000).encode().is_err());
+
+        let path = DerivationPath {
+            change: 0x80000000,
+            ..Default::default()
+        };
+
+        assert!(path.encode().is_err());
+
+        let path = DerivationPath {
+            index: u32::MAX,
+            ..Default::default()
+        };
+
+        assert!(path.encode().is_err());
+    }
+}
@@ -3,7 +3,7 @@
 mod bitcoin;
 mod ethereum;
 
-pub use self::bitcoin::BitcoinLedgerApp;
+pub use self::bitcoin::{BitcoinLedgerApp, DerivationPath};
 pub use ethereum::EthereumLedgerApp;
 
 use std::sync::Arc;
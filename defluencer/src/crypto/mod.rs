@@ -2,5 +2,5 @@ pub mod signers;
 
 pub mod signed_link;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "signers"))]
 pub mod ledger;
@@ -17,12 +17,14 @@ pub struct SignedLink {
     pub link: IPLDLink,
 
     /// SEC1 encoded public key.
+    #[serde(with = "serde_bytes")]
     pub public_key: Vec<u8>,
 
     /// What algo to apply before signing
     pub hash_algo: HashAlgorithm,
 
-    /// ASN.1 DER encoded signature.
+    /// ASN.1 DER encoded signature or 64 bytes compact signature.
+    #[serde(with = "serde_bytes")]
     pub signature: Vec<u8>,
 }
 
@@ -40,8 +42,29 @@ impl SignedLink {
         }
     }
 
+    /// Returns the P2PKH address of the compressed public key.
     fn get_btc_address(&self) -> String {
-        unimplemented!()
+        use sha2::Sha256;
+
+        let mut payload = vec![0x00]; // mainnet version byte
+        payload.extend(self.get_pubkey_hash());
+
+        let checksum = Sha256::digest(Sha256::digest(&payload));
+        payload.extend(&checksum[..4]);
+
+        multibase::Base::Base58Btc.encode(payload)
+    }
+
+    /// Returns RIPEMD160(SHA256(public key)), keys are compressed first when valid.
+    pub fn get_pubkey_hash(&self) -> [u8; 20] {
+        use ripemd::Ripemd160;
+        use sha2::Sha256;
+
+        let compressed = k256::ecdsa::VerifyingKey::from_sec1_bytes(&self.public_key)
+            .map(|key| key.to_encoded_point(true).as_bytes().to_vec())
+            .unwrap_or_else(|_| self.public_key.clone());
+
+        Ripemd160::digest(Sha256::digest(compressed)).into()
     }
 
     fn get_eth_address(&self) -> String {
@@ -79,9 +102,9 @@ impl SignedLink {
             Err(_) => return false,
         };
 
-        let signature = match k256::ecdsa::Signature::from_der(&self.signature) {
-            Ok(sig) => sig,
-            Err(_) => return false,
+        let signature = match parse_signature(&self.signature) {
+            Some(sig) => sig,
+            None => return false,
         };
 
         let msg_length = VarInt(signing_input.len() as u64).consensus_encode();
@@ -107,9 +130,9 @@ impl SignedLink {
             Err(_) => return false,
         };
 
-        let signature = match k256::ecdsa::Signature::from_der(&self.signature) {
-            Ok(sig) => sig,
-            Err(_) => return false,
+        let signature = match parse_signature(&self.signature) {
+            Some(sig) => sig,
+            None => return false,
         };
 
         let mut eth_message =
@@ -121,3 +144,80 @@ impl SignedLink {
         verif_key.verify_digest(digest, &signature).is_ok()
     }
 }
+
+/// Compact signatures are exactly 64 bytes, DER ones are always longer.
+fn parse_signature(bytes: &[u8]) -> Option<k256::ecdsa::Signature> {
+    if bytes.len() == 64 {
+        return k256::ecdsa::Signature::from_slice(bytes).ok();
+    }
+
+    k256::ecdsa::Signature::from_der(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cid::Cid;
+
+    use k256::ecdsa::{signature::DigestSigner, Signature, SigningKey};
+
+    use multihash::{Code, MultihashDigest};
+
+    #[test]
+    fn compact_and_der_signatures() {
+        let key = SigningKey::from_slice(&[1u8; 32]).unwrap();
+        let cid = Cid::new_v1(0x71, Code::Sha2_256.digest(b"chat"));
+
+        let signing_input = cid.hash().digest();
+        let mut eth_message =
+            format!("\x19Ethereum Signed Message:\n{}", signing_input.len()).into_bytes();
+        eth_message.extend_from_slice(signing_input);
+
+        let signature: Signature = key.sign_digest(Keccak256::new_with_prefix(eth_message));
+
+        let mut link = SignedLink {
+            link: cid.into(),
+            public_key: key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+            hash_algo: HashAlgorithm::EthereumLedgerApp,
+            signature: signature.to_der().as_bytes().to_vec(),
+        };
+
+        assert!(link.verify());
+
+        link.signature = signature.to_bytes().to_vec();
+        assert_eq!(link.signature.len(), 64);
+        assert!(link.verify());
+
+        link.signature[0] ^= 1;
+        assert!(!link.verify());
+    }
+
+    #[test]
+    fn btc_address() {
+        let mut scalar = [0u8; 32];
+        scalar[31] = 1;
+        let key = SigningKey::from_slice(&scalar).unwrap();
+
+        let link = SignedLink {
+            link: Cid::new_v1(0x71, Code::Sha2_256.digest(b"chat")).into(),
+            public_key: key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+            hash_algo: HashAlgorithm::BitcoinLedgerApp,
+            signature: vec![],
+        };
+
+        assert_eq!(
+            hex::encode(link.get_pubkey_hash()),
+            "751e76e8199196d454941c45d1b3a323f1433bd6"
+        );
+        assert_eq!(link.get_address(), "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH");
+    }
+}
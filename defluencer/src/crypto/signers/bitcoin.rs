@@ -7,7 +7,10 @@ use sha2::{Digest, Sha256};
 use k256::ecdsa::{Signature, VerifyingKey};
 
 use crate::{
-    crypto::{ledger::BitcoinLedgerApp, signed_link::HashAlgorithm},
+    crypto::{
+        ledger::{BitcoinLedgerApp, DerivationPath},
+        signed_link::HashAlgorithm,
+    },
     errors::Error,
     utils::VarInt,
 };
@@ -17,16 +20,30 @@ use super::Signer;
 #[derive(Clone)]
 pub struct BitcoinSigner {
     app: BitcoinLedgerApp,
-    account_index: u32,
+    path: DerivationPath,
 }
 
 impl BitcoinSigner {
+    /// Sign with this address of the first mainnet account, m/44'/0'/0'/0/<index>.
     pub fn new(app: BitcoinLedgerApp, account_index: u32) -> Self {
-        Self { app, account_index }
+        let path = DerivationPath {
+            index: account_index,
+            ..Default::default()
+        };
+
+        Self::with_path(app, path)
+    }
+
+    pub fn with_path(app: BitcoinLedgerApp, path: DerivationPath) -> Self {
+        Self { app, path }
+    }
+
+    pub fn path(&self) -> DerivationPath {
+        self.path
     }
 
     pub fn get_public_address(&self) -> Result<String, Error> {
-        let (_, addr) = self.app.get_extended_pubkey(self.account_index)?;
+        let (_, addr) = self.app.get_extended_pubkey(&self.path)?;
 
         Ok(addr)
     }
@@ -38,7 +55,7 @@ impl Signer for BitcoinSigner {
         &self,
         signing_input: &[u8],
     ) -> Result<(VerifyingKey, Signature, HashAlgorithm), Error> {
-        let (signature, rec_id) = self.app.sign_message(signing_input, self.account_index)?;
+        let (signature, rec_id) = self.app.sign_message(signing_input, &self.path)?;
 
         let btc_message = {
             let mut temp = Vec::from("\x18Bitcoin Signed Message:\n");
@@ -71,7 +88,7 @@ mod tests {
     fn addr() {
         let app = BitcoinLedgerApp::default();
 
-        let (_, addr) = app.get_extended_pubkey(0).unwrap();
+        let (_, addr) = app.get_extended_pubkey(&DerivationPath::default()).unwrap();
 
         println!("Address: {}", addr);
     }
@@ -80,9 +97,9 @@ mod tests {
     #[ignore]
     fn sign_test() {
         let app = BitcoinLedgerApp::default();
-        let account_index = 0;
+        let path = DerivationPath::default();
 
-        let (pub_key, _addr) = app.get_extended_pubkey(account_index).unwrap();
+        let (pub_key, _addr) = app.get_extended_pubkey(&path).unwrap();
         let verif_key = VerifyingKey::from(pub_key);
 
         //let signing_input = b"Hello World!";
@@ -92,7 +109,7 @@ mod tests {
         println!("Message Display Hash: 0x{}", hex::encode(display_hash));
 
         let (signature, rec_id) = app
-            .sign_message(signing_input, account_index)
+            .sign_message(signing_input, &path)
             .expect("Msg signature");
 
         let msg_length = VarInt(signing_input.len() as u64).consensus_encode();
@@ -2,9 +2,17 @@
 
 use async_trait::async_trait;
 
+use chrono::Duration;
+
+use cid::Cid;
+
+use ipns_records::{CryptoKey, UnsignedRecord};
+
+use sha2::Sha256;
+
 use sha3::{Digest, Keccak256};
 
-use k256::ecdsa::{Signature, VerifyingKey};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
 
 use crate::{crypto::signed_link::HashAlgorithm, errors::Error};
 
@@ -24,6 +32,64 @@ impl MetamaskSigner {
     pub fn new(addr: Address, web3: Web3<Eip1193>) -> Self {
         Self { addr, web3 }
     }
+
+    /// Create an IPNS record signed by this account.
+    ///
+    /// Metamask is asked to sign the hash of both signing inputs,
+    /// eth_sign must be enabled in its advanced settings.
+    ///
+    /// Returns the record address and the protobuf encoded record, ready for the DHT or pubsub.
+    pub async fn sign_ipns_record(
+        &self,
+        cid: Cid,
+        valid_for: Duration,
+        sequence: u64,
+        ttl: u64,
+    ) -> Result<(Cid, Vec<u8>), Error> {
+        let unsigned = UnsignedRecord::new(cid, valid_for, sequence, ttl);
+
+        let (verif_key, signature_v1) = self.sign_hash(&unsigned.signing_input_v1()).await?;
+        let (_, signature_v2) = self.sign_hash(&unsigned.signing_input_v2()).await?;
+
+        let crypto_key = CryptoKey::new_k256(&verif_key);
+        let addr = crypto_key.to_address();
+
+        let record = unsigned.into_record(crypto_key, signature_v1, signature_v2)?;
+
+        record.verify(addr)?;
+
+        Ok((addr, record.to_bytes()))
+    }
+
+    /// Sign the SHA2-256 hash of the input, without the Ethereum message prefix.
+    ///
+    /// Returns the recovered public key and the DER encoded signature.
+    async fn sign_hash(&self, signing_input: &[u8]) -> Result<(VerifyingKey, Vec<u8>), Error> {
+        let hash = Sha256::digest(signing_input);
+
+        let sig = self
+            .web3
+            .eth()
+            .sign(self.addr.into(), hash.to_vec().into())
+            .await?;
+
+        // The k256 crate expect 0 OR 1 as recovery ID, instead Metamask return 27 OR 28
+        let mut bytes = sig.to_fixed_bytes();
+        if bytes[64] == 27 || bytes[64] == 28 {
+            bytes[64] -= 27;
+        }
+
+        let rec_id = RecoveryId::try_from(bytes[64])?;
+
+        let signature = Signature::try_from(&bytes[0..64])?;
+
+        let verif_key = VerifyingKey::recover_from_prehash(&hash, &signature, rec_id)?;
+
+        // IPNS verifiers reject high S signatures.
+        let signature = signature.normalize_s().unwrap_or(signature);
+
+        Ok((verif_key, signature.to_der().as_bytes().to_vec()))
+    }
 }
 
 #[async_trait(?Send)]
@@ -1,19 +1,19 @@
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "signers"))]
 mod bitcoin;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "signers"))]
 pub use self::bitcoin::BitcoinSigner;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "signers"))]
 mod ethereum;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "signers"))]
 pub use ethereum::EthereumSigner;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", feature = "signers"))]
 mod metamask;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", feature = "signers"))]
 pub use metamask::MetamaskSigner;
 
 use crate::errors::Error;
@@ -0,0 +1,207 @@
+use std::fmt::Write;
+
+use linked_data::media::video::{Segment, Setup, Track};
+
+use crate::gateway::Gateway;
+
+/// DASH manifest of an archived video, for players without HLS support.
+///
+/// Segments are listed as gateway URLs, one adaptation set per mime type.
+/// Without a known duration, segments are assumed to last 1 second.
+pub fn dash_mpd(
+    setup: &Setup,
+    segments: &[Segment],
+    duration: Option<f64>,
+    gateway: &Gateway,
+) -> String {
+    let duration = duration.unwrap_or(segments.len() as f64);
+
+    let segment_millis = match segments.len() {
+        0 => 1000,
+        count => ((duration * 1000.0) / count as f64).round().max(1.0) as u64,
+    };
+
+    let mut mpd = String::new();
+
+    let _ = writeln!(mpd, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        mpd,
+        r#"<MPD xmlns="urn:mpeg:dash:schema:mpd:2011" profiles="urn:mpeg:dash:profile:isoff-main:2011" type="static" mediaPresentationDuration="PT{:.3}S" minBufferTime="PT2S">"#,
+        duration
+    );
+    let _ = writeln!(mpd, r#"  <Period start="PT0S">"#);
+
+    let mut mime_types: Vec<&str> = Vec::new();
+
+    for track in setup.tracks.iter() {
+        let (mime_type, _) = parse_codec(&track.codec);
+
+        if !mime_types.contains(&mime_type) {
+            mime_types.push(mime_type);
+        }
+    }
+
+    for mime_type in mime_types {
+        let content_type = mime_type.split('/').next().unwrap_or_default();
+
+        let _ = writeln!(
+            mpd,
+            r#"    <AdaptationSet contentType="{}" mimeType="{}" segmentAlignment="true">"#,
+            escape(content_type),
+            escape(mime_type)
+        );
+
+        for track in setup
+            .tracks
+            .iter()
+            .filter(|track| parse_codec(&track.codec).0 == mime_type)
+        {
+            representation(&mut mpd, track, segments, segment_millis, gateway);
+        }
+
+        let _ = writeln!(mpd, "    </AdaptationSet>");
+    }
+
+    let _ = writeln!(mpd, "  </Period>");
+    let _ = writeln!(mpd, "</MPD>");
+
+    mpd
+}
+
+fn representation(
+    mpd: &mut String,
+    track: &Track,
+    segments: &[Segment],
+    segment_millis: u64,
+    gateway: &Gateway,
+) {
+    let (_, codecs) = parse_codec(&track.codec);
+
+    let _ = writeln!(
+        mpd,
+        r#"      <Representation id="{}" codecs="{}" bandwidth="{}">"#,
+        escape(&track.name),
+        escape(codecs),
+        track.bandwidth
+    );
+    let _ = writeln!(
+        mpd,
+        r#"        <SegmentList timescale="1000" duration="{}">"#,
+        segment_millis
+    );
+    let _ = writeln!(
+        mpd,
+        r#"          <Initialization sourceURL="{}"/>"#,
+        escape(&gateway.ipfs(track.initialization_segment.link))
+    );
+
+    for segment in segments {
+        if let Some(ipld) = segment.tracks.get(&track.name) {
+            let _ = writeln!(
+                mpd,
+                r#"          <SegmentURL media="{}"/>"#,
+                escape(&gateway.ipfs(ipld.link))
+            );
+        }
+    }
+
+    let _ = writeln!(mpd, "        </SegmentList>");
+    let _ = writeln!(mpd, "      </Representation>");
+}
+
+/// Split an HTML media type into mime type and codecs.
+///
+/// Eg. ```video/mp4; codecs="avc1.64002a"``` becomes ```video/mp4``` and ```avc1.64002a```
+fn parse_codec(codec: &str) -> (&str, &str) {
+    let (mime_type, params) = codec.split_once(';').unwrap_or((codec, ""));
+
+    let codecs = params
+        .trim()
+        .strip_prefix("codecs=")
+        .unwrap_or_default()
+        .trim_matches('"');
+
+    (mime_type.trim(), codecs)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use cid::Cid;
+
+    use multihash::{Code, MultihashDigest};
+
+    fn cid(data: &[u8]) -> Cid {
+        Cid::new_v1(0x55, Code::Sha2_256.digest(data))
+    }
+
+    #[test]
+    fn manifest() {
+        let setup = Setup {
+            tracks: vec![
+                Track {
+                    name: "audio".into(),
+                    codec: r#"audio/mp4; codecs="mp4a.40.2""#.into(),
+                    initialization_segment: cid(b"audio init").into(),
+                    bandwidth: 128_000,
+                },
+                Track {
+                    name: "720p30".into(),
+                    codec: r#"video/mp4; codecs="avc1.64001f""#.into(),
+                    initialization_segment: cid(b"720 init").into(),
+                    bandwidth: 3_000_000,
+                },
+                Track {
+                    name: "1080p60".into(),
+                    codec: r#"video/mp4; codecs="avc1.64002a""#.into(),
+                    initialization_segment: cid(b"1080 init").into(),
+                    bandwidth: 6_000_000,
+                },
+            ],
+        };
+
+        let segments: Vec<_> = (0..3u8)
+            .map(|i| {
+                let tracks = setup
+                    .tracks
+                    .iter()
+                    .map(|track| {
+                        (
+                            track.name.clone(),
+                            cid(&[track.name.as_bytes(), &[i]].concat()).into(),
+                        )
+                    })
+                    .collect::<HashMap<_, _>>();
+
+                Segment {
+                    tracks,
+                    setup: None,
+                    previous: None,
+                }
+            })
+            .collect();
+
+        let mpd = dash_mpd(&setup, &segments, Some(3.0), &Gateway::default());
+
+        assert!(mpd.contains(r#"mediaPresentationDuration="PT3.000S""#));
+        assert_eq!(mpd.matches("<AdaptationSet").count(), 2);
+        assert_eq!(mpd.matches("<Representation").count(), 3);
+        assert_eq!(mpd.matches("<SegmentURL").count(), 9);
+        assert!(mpd.contains(r#"codecs="avc1.64002a""#));
+        assert!(mpd.contains(&format!(
+            r#"<Initialization sourceURL="https://ipfs.io/ipfs/{}"/>"#,
+            cid(b"audio init")
+        )));
+        assert!(mpd.contains(r#"duration="1000""#));
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use cid::Cid;
+
+use futures::{stream, StreamExt, TryStreamExt};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::media::Media;
+
+use crate::errors::Error;
+
+/// Number of block stat requests in flight at once.
+const CONCURRENCY: usize = 16;
+
+/// Blocks & bytes shared between two DAGs or unique to each one.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DedupReport {
+    pub shared_blocks: usize,
+    pub shared_bytes: u64,
+
+    pub unique_blocks_a: usize,
+    pub unique_bytes_a: u64,
+
+    pub unique_blocks_b: usize,
+    pub unique_bytes_b: u64,
+}
+
+impl DedupReport {
+    /// Compare two contents block by block.
+    ///
+    /// Videos are followed to their timecode DAG, any other media is compared as a whole.
+    pub async fn compare(ipfs: &IpfsService, a: Cid, b: Cid) -> Result<Self, Error> {
+        let (a, b) = futures::try_join!(media_root(ipfs, a), media_root(ipfs, b))?;
+
+        let (blocks_a, blocks_b) = futures::try_join!(block_sizes(ipfs, a), block_sizes(ipfs, b))?;
+
+        Ok(Self::from_blocks(&blocks_a, &blocks_b))
+    }
+
+    pub fn from_blocks(a: &HashMap<Cid, u64>, b: &HashMap<Cid, u64>) -> Self {
+        let mut report = Self::default();
+
+        for (cid, size) in a.iter() {
+            if b.contains_key(cid) {
+                report.shared_blocks += 1;
+                report.shared_bytes += size;
+            } else {
+                report.unique_blocks_a += 1;
+                report.unique_bytes_a += size;
+            }
+        }
+
+        for (cid, size) in b.iter() {
+            if !a.contains_key(cid) {
+                report.unique_blocks_b += 1;
+                report.unique_bytes_b += size;
+            }
+        }
+
+        report
+    }
+
+    /// Ratio of shared bytes over all bytes of both DAGs.
+    pub fn shared_ratio(&self) -> f64 {
+        let total = self.shared_bytes + self.unique_bytes_a + self.unique_bytes_b;
+
+        if total == 0 {
+            return 0.0;
+        }
+
+        self.shared_bytes as f64 / total as f64
+    }
+}
+
+async fn media_root(ipfs: &IpfsService, content_cid: Cid) -> Result<Cid, Error> {
+    // path "/link" to skip signature block
+    let media: Media = ipfs
+        .dag_get(content_cid, Some("/link"), Codec::default())
+        .await?;
+
+    match media {
+        Media::Video(video) => Ok(video.video.link),
+        _ => Ok(content_cid),
+    }
+}
+
+async fn block_sizes(ipfs: &IpfsService, root: Cid) -> Result<HashMap<Cid, u64>, Error> {
+    let mut cids = ipfs.refs(root).await?;
+    cids.push(root);
+
+    let sizes = stream::iter(cids)
+        .map(|cid| async move {
+            let size = ipfs.block_stat(cid).await?;
+
+            Result::<_, Error>::Ok((cid, size))
+        })
+        .buffer_unordered(CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    Ok(sizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    fn block(data: &[u8]) -> Cid {
+        Cid::new_v1(0x55, Code::Sha2_256.digest(data))
+    }
+
+    #[test]
+    fn shared_and_unique() {
+        let a: HashMap<_, _> = [(block(b"1"), 10), (block(b"2"), 20), (block(b"3"), 30)].into();
+        let b: HashMap<_, _> = [(block(b"2"), 20), (block(b"3"), 30), (block(b"4"), 40)].into();
+
+        let report = DedupReport::from_blocks(&a, &b);
+
+        assert_eq!(report.shared_blocks, 2);
+        assert_eq!(report.shared_bytes, 50);
+        assert_eq!(report.unique_blocks_a, 1);
+        assert_eq!(report.unique_bytes_a, 10);
+        assert_eq!(report.unique_blocks_b, 1);
+        assert_eq!(report.unique_bytes_b, 40);
+        assert_eq!(report.shared_ratio(), 0.5);
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+
+use cid::{multihash::Multihash, Cid};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use libipld_core::ipld::Ipld;
+
+use sha2::{Digest, Sha256};
+
+use crate::errors::Error;
+
+const DAG_CBOR: u64 = 0x71;
+
+/// CIDs an operator refuses to serve, pin or aggregate.
+///
+/// Parsed from bad-bits style lists, one entry per line.
+/// - ```//<HEX>``` the SHA-256 of ```<CIDv1 base32>/```, the CIDs themselves stay hidden.
+/// - ```/ipfs/<CID>``` or ```<CID>```, matching every CID with the same multihash.
+///
+/// Comments, headers and entries with paths are ignored, only whole CIDs are denied.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Denylist {
+    multihashes: HashSet<Multihash>,
+    hashes: HashSet<[u8; 32]>,
+}
+
+impl Denylist {
+    /// Parse a list, skipping the lines that are not entries.
+    pub fn parse(text: &str) -> Self {
+        let mut list = Self::default();
+
+        for line in text.lines().map(str::trim) {
+            if let Some(hex) = line.strip_prefix("//") {
+                let mut hash = [0; 32];
+
+                if hex::decode_to_slice(hex, &mut hash).is_ok() {
+                    list.hashes.insert(hash);
+                }
+
+                continue;
+            }
+
+            let entry = line.strip_prefix("/ipfs/").unwrap_or(line);
+
+            if let Ok(cid) = Cid::try_from(entry) {
+                list.multihashes.insert(*cid.hash());
+            }
+        }
+
+        list
+    }
+
+    /// Read a list from a file or download it from a HTTP(S) URL.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "daemons"))]
+    pub async fn load(source: &str) -> Result<Self, Error> {
+        let text = if source.starts_with("http://") || source.starts_with("https://") {
+            reqwest::get(source)
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(ipfs_api::errors::Error::from)?
+                .text()
+                .await
+                .map_err(ipfs_api::errors::Error::from)?
+        } else {
+            tokio::fs::read_to_string(source).await?
+        };
+
+        Ok(Self::parse(&text))
+    }
+
+    /// Returns true if this CID is denied.
+    pub fn contains(&self, cid: Cid) -> bool {
+        if self.multihashes.contains(cid.hash()) {
+            return true;
+        }
+
+        if self.hashes.is_empty() {
+            return false;
+        }
+
+        // Lists hash CIDv1 in base32, CIDv0 must be converted first.
+        let cid = Cid::new_v1(cid.codec(), *cid.hash());
+
+        let hash = Sha256::new_with_prefix(format!("{}/", cid)).finalize();
+
+        self.hashes.contains(hash.as_slice())
+    }
+
+    /// Returns the first denied CID among this node and the nodes it links to, down to this depth.
+    ///
+    /// Only DAG-CBOR nodes are followed, nodes that cannot be decoded have no links.
+    pub async fn first_denied(
+        &self,
+        ipfs: &IpfsService,
+        cid: Cid,
+        depth: usize,
+    ) -> Result<Option<Cid>, Error> {
+        let mut level = vec![cid];
+
+        for i in 0..=depth {
+            if let Some(cid) = level.iter().find(|cid| self.contains(**cid)) {
+                return Ok(Some(*cid));
+            }
+
+            if i == depth {
+                break;
+            }
+
+            let mut next = Vec::new();
+
+            for cid in level.into_iter().filter(|cid| cid.codec() == DAG_CBOR) {
+                match ipfs
+                    .dag_get::<&str, Ipld>(cid, None, Codec::default())
+                    .await
+                {
+                    Ok(ipld) => collect_links(&ipld, &mut next),
+                    Err(e) if e.is_decode() => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            level = next;
+        }
+
+        Ok(None)
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.multihashes.len() + self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Returns the CIDs this node links to.
+///
+/// IPLDLink maps and CIDs serialized as text are links too, as are texts that happen to be CIDs.
+pub fn links(ipld: &Ipld) -> Vec<Cid> {
+    let mut links = Vec::new();
+
+    collect_links(ipld, &mut links);
+
+    links
+}
+
+fn collect_links(ipld: &Ipld, links: &mut Vec<Cid>) {
+    match ipld {
+        Ipld::Link(cid) => links.push(*cid),
+        Ipld::String(text) => {
+            if let Ok(cid) = Cid::try_from(text.as_str()) {
+                links.push(cid);
+            }
+        }
+        Ipld::Map(map) => map.values().for_each(|ipld| collect_links(ipld, links)),
+        Ipld::List(list) => list.iter().for_each(|ipld| collect_links(ipld, links)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    const CID_V0: &str = "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn";
+    const CID_V1: &str = "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354";
+
+    #[test]
+    fn double_hashed() {
+        let cid = Cid::from_str(CID_V0).unwrap();
+        let v1 = Cid::new_v1(cid.codec(), *cid.hash());
+
+        let hash = Sha256::new_with_prefix(format!("{}/", v1)).finalize();
+
+        let text = format!("# Bad Bits\n\n//{}\n//not-hex\n", hex::encode(hash));
+        let list = Denylist::parse(&text);
+
+        assert_eq!(list.len(), 1);
+        assert!(list.contains(cid));
+        assert!(list.contains(v1));
+        assert!(!list.contains(Cid::from_str(CID_V1).unwrap()));
+    }
+
+    #[test]
+    fn plain_cids() {
+        let text = format!("version: 1\n---\n/ipfs/{}\n{}/path\n", CID_V0, CID_V1);
+        let list = Denylist::parse(&text);
+
+        assert_eq!(list.len(), 1);
+
+        // Same multihash, any CID version.
+        let cid = Cid::from_str(CID_V0).unwrap();
+        assert!(list.contains(Cid::new_v1(0x55, *cid.hash())));
+
+        assert!(!list.contains(Cid::from_str(CID_V1).unwrap()));
+        assert!(Denylist::default().is_empty());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn denied_links() {
+        use crate::crypto::signed_link::{HashAlgorithm, SignedLink};
+
+        use ipfs_api::mock::MockIpfs;
+
+        use linked_data::media::comments::Comment;
+
+        let ipfs = MockIpfs::new().service();
+
+        let media = Cid::from_str(CID_V1).unwrap();
+
+        let comment = Comment {
+            origin: Some(media),
+            text: String::from("Denied by association"),
+            ..Default::default()
+        };
+
+        let comment = ipfs
+            .dag_put(&comment, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let signed_link = SignedLink {
+            link: comment.into(),
+            public_key: vec![],
+            hash_algo: HashAlgorithm::EthereumLedgerApp,
+            signature: vec![],
+        };
+
+        let submission = ipfs
+            .dag_put(&signed_link, Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let list = Denylist::parse(CID_V1);
+
+        assert_eq!(
+            list.first_denied(&ipfs, submission, 2).await.unwrap(),
+            Some(media)
+        );
+        assert_eq!(list.first_denied(&ipfs, submission, 1).await.unwrap(), None);
+
+        let list = Denylist::parse(&comment.to_string());
+
+        assert_eq!(
+            list.first_denied(&ipfs, submission, 2).await.unwrap(),
+            Some(comment)
+        );
+    }
+}
@@ -0,0 +1,405 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::{self, Display},
+};
+
+use cid::Cid;
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{
+    channel::{follows::Follows, ChannelMetadata},
+    indexes::date_time::*,
+    types::{IPLDLink, IPNSAddress},
+};
+
+use crate::errors::Error;
+
+/// Channel settings, replaced as a whole when changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Identity,
+    Live,
+    AggregationChannel,
+    Theme,
+    Owners,
+    Approvals,
+    Snapshot,
+    Succession,
+    CommentSettings,
+}
+
+/// Channel indexes, only their roots are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Index {
+    Comments,
+    Replies,
+    CommentArchive,
+    CommentFilter,
+    CommentCounts,
+    Orphans,
+    Upvotes,
+    FollowLists,
+    Playlists,
+    Registry,
+    Hub,
+    Variants,
+    Slugs,
+    Permalinks,
+    Tags,
+    Rsvps,
+    Archive,
+}
+
+/// One difference between two versions of a channel.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelChange {
+    ContentAdded(Cid),
+    ContentRemoved(Cid),
+    Followed(IPNSAddress),
+    Unfollowed(IPNSAddress),
+    SettingChanged(Setting),
+    IndexChanged {
+        index: Index,
+        old: Option<IPLDLink>,
+        new: Option<IPLDLink>,
+    },
+}
+
+impl Display for ChannelChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChannelChange::ContentAdded(cid) => write!(f, "Content Added {}", cid),
+            ChannelChange::ContentRemoved(cid) => write!(f, "Content Removed {}", cid),
+            ChannelChange::Followed(addr) => write!(f, "Followed {}", addr),
+            ChannelChange::Unfollowed(addr) => write!(f, "Unfollowed {}", addr),
+            ChannelChange::SettingChanged(setting) => write!(f, "{:?} Changed", setting),
+            ChannelChange::IndexChanged { index, new, .. } => match new {
+                Some(new) => write!(f, "{:?} Index Changed {}", index, new.link),
+                None => write!(f, "{:?} Index Removed", index),
+            },
+        }
+    }
+}
+
+/// Compare the settings and index roots of two channel metadata.
+///
+/// Content and follows are not compared, see `Defluencer::diff_channel`.
+pub fn metadata_changes(old: &ChannelMetadata, new: &ChannelMetadata) -> Vec<ChannelChange> {
+    // Destructured so that new fields are not forgotten.
+    let ChannelMetadata {
+        identity,
+        content_index: _,
+        comment_index,
+        reply_index,
+        comment_archive,
+        comment_filter,
+        comment_counts,
+        orphan_index,
+        upvote_index,
+        comment_settings,
+        live,
+        follows: _,
+        follow_lists,
+        playlists,
+        agregation_channel,
+        registry,
+        hub_index,
+        variant_index,
+        slug_index,
+        permalink_index,
+        tag_index,
+        rsvp_index,
+        archive_index,
+        theme,
+        owners,
+        approvals,
+        snapshot,
+        succession,
+    } = new;
+
+    let mut changes = Vec::new();
+
+    let settings = [
+        (Setting::Identity, old.identity != *identity),
+        (Setting::Live, old.live != *live),
+        (
+            Setting::AggregationChannel,
+            old.agregation_channel != *agregation_channel,
+        ),
+        (Setting::Theme, old.theme != *theme),
+        (Setting::Owners, old.owners != *owners),
+        (Setting::Approvals, old.approvals != *approvals),
+        (Setting::Snapshot, old.snapshot != *snapshot),
+        (Setting::Succession, old.succession != *succession),
+        (
+            Setting::CommentSettings,
+            old.comment_settings != *comment_settings,
+        ),
+    ];
+
+    changes.extend(
+        settings
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(setting, _)| ChannelChange::SettingChanged(setting)),
+    );
+
+    let indexes = [
+        (Index::Comments, old.comment_index, *comment_index),
+        (Index::Replies, old.reply_index, *reply_index),
+        (Index::CommentArchive, old.comment_archive, *comment_archive),
+        (Index::CommentFilter, old.comment_filter, *comment_filter),
+        (Index::CommentCounts, old.comment_counts, *comment_counts),
+        (Index::Orphans, old.orphan_index, *orphan_index),
+        (Index::Upvotes, old.upvote_index, *upvote_index),
+        (Index::FollowLists, old.follow_lists, *follow_lists),
+        (Index::Playlists, old.playlists, *playlists),
+        (Index::Registry, old.registry, *registry),
+        (Index::Hub, old.hub_index, *hub_index),
+        (Index::Variants, old.variant_index, *variant_index),
+        (Index::Slugs, old.slug_index, *slug_index),
+        (Index::Permalinks, old.permalink_index, *permalink_index),
+        (Index::Tags, old.tag_index, *tag_index),
+        (Index::Rsvps, old.rsvp_index, *rsvp_index),
+        (Index::Archive, old.archive_index, *archive_index),
+    ];
+
+    changes.extend(
+        indexes
+            .into_iter()
+            .filter(|(_, old, new)| old != new)
+            .map(|(index, old, new)| ChannelChange::IndexChanged { index, old, new }),
+    );
+
+    changes
+}
+
+/// Compare two lists of followees.
+pub(crate) async fn follows_changes(
+    ipfs: &IpfsService,
+    old: Option<IPLDLink>,
+    new: Option<IPLDLink>,
+) -> Result<Vec<ChannelChange>, Error> {
+    if old == new {
+        return Ok(Vec::new());
+    }
+
+    let (old, new) = futures::try_join!(followees(ipfs, old), followees(ipfs, new))?;
+
+    let mut changes: Vec<_> = new
+        .difference(&old)
+        .copied()
+        .map(ChannelChange::Followed)
+        .collect();
+
+    changes.extend(old.difference(&new).copied().map(ChannelChange::Unfollowed));
+
+    Ok(changes)
+}
+
+async fn followees(
+    ipfs: &IpfsService,
+    follows: Option<IPLDLink>,
+) -> Result<HashSet<IPNSAddress>, Error> {
+    let Some(ipld) = follows else {
+        return Ok(HashSet::new());
+    };
+
+    let follows = ipfs
+        .dag_get::<&str, Follows>(ipld.link, None, Codec::default())
+        .await?;
+
+    Ok(follows.followees)
+}
+
+/// Depth of the seconds nodes in the content index.
+const SECONDS_DEPTH: usize = 5;
+
+/// Compare two content indexes, in chronological order.
+///
+/// Identical sub-trees are skipped without being fetched.
+pub(crate) async fn content_changes(
+    ipfs: &IpfsService,
+    old: Option<IPLDLink>,
+    new: Option<IPLDLink>,
+) -> Result<Vec<ChannelChange>, Error> {
+    let mut changes = Vec::new();
+
+    // Depth first, latest pushed is diffed first.
+    let mut stack = vec![(0, old.map(|ipld| ipld.link), new.map(|ipld| ipld.link))];
+
+    while let Some((depth, old, new)) = stack.pop() {
+        if old == new {
+            continue;
+        }
+
+        if depth == SECONDS_DEPTH {
+            let (old, new) = futures::try_join!(seconds(ipfs, old), seconds(ipfs, new))?;
+
+            changes.extend(leaf_changes(&old, &new));
+
+            continue;
+        }
+
+        let (old, new) = futures::try_join!(links(ipfs, depth, old), links(ipfs, depth, new))?;
+
+        let keys: BTreeSet<i64> = old.keys().chain(new.keys()).copied().collect();
+
+        stack.extend(
+            keys.into_iter()
+                .rev()
+                .map(|key| (depth + 1, old.get(&key).copied(), new.get(&key).copied())),
+        );
+    }
+
+    Ok(changes)
+}
+
+fn leaf_changes(old: &BTreeSet<Cid>, new: &BTreeSet<Cid>) -> Vec<ChannelChange> {
+    let mut changes: Vec<_> = new
+        .difference(old)
+        .copied()
+        .map(ChannelChange::ContentAdded)
+        .collect();
+
+    changes.extend(
+        old.difference(new)
+            .copied()
+            .map(ChannelChange::ContentRemoved),
+    );
+
+    changes
+}
+
+/// Links to the children of a content index node, by year, month, day, hour or minute.
+async fn links(
+    ipfs: &IpfsService,
+    depth: usize,
+    node: Option<Cid>,
+) -> Result<BTreeMap<i64, Cid>, Error> {
+    let Some(cid) = node else {
+        return Ok(BTreeMap::new());
+    };
+
+    let links = match depth {
+        0 => to_links(
+            ipfs.dag_get::<&str, Yearly>(cid, None, Codec::default())
+                .await?
+                .year,
+        ),
+        1 => to_links(
+            ipfs.dag_get::<&str, Monthly>(cid, None, Codec::default())
+                .await?
+                .month,
+        ),
+        2 => to_links(
+            ipfs.dag_get::<&str, Daily>(cid, None, Codec::default())
+                .await?
+                .day,
+        ),
+        3 => to_links(
+            ipfs.dag_get::<&str, Hourly>(cid, None, Codec::default())
+                .await?
+                .hour,
+        ),
+        _ => to_links(
+            ipfs.dag_get::<&str, Minutes>(cid, None, Codec::default())
+                .await?
+                .minute,
+        ),
+    };
+
+    Ok(links)
+}
+
+fn to_links<K: Into<i64>>(map: BTreeMap<K, IPLDLink>) -> BTreeMap<i64, Cid> {
+    map.into_iter()
+        .map(|(key, ipld)| (key.into(), ipld.link))
+        .collect()
+}
+
+/// Content CIDs of all the seconds of a minute.
+async fn seconds(ipfs: &IpfsService, node: Option<Cid>) -> Result<BTreeSet<Cid>, Error> {
+    let Some(cid) = node else {
+        return Ok(BTreeSet::new());
+    };
+
+    let seconds = ipfs
+        .dag_get::<&str, Seconds>(cid, None, Codec::default())
+        .await?;
+
+    Ok(seconds
+        .second
+        .into_values()
+        .flatten()
+        .map(|ipld| ipld.link)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    fn link(data: &[u8]) -> IPLDLink {
+        Cid::new_v1(0x71, Code::Sha2_256.digest(data)).into()
+    }
+
+    #[test]
+    fn metadata_diff() {
+        let old = ChannelMetadata {
+            identity: link(b"identity"),
+            comment_index: Some(link(b"comments")),
+            theme: Some(link(b"theme")),
+            ..Default::default()
+        };
+
+        assert!(metadata_changes(&old, &old).is_empty());
+
+        let new = ChannelMetadata {
+            comment_index: Some(link(b"more comments")),
+            upvote_index: Some(link(b"upvotes")),
+            theme: None,
+            agregation_channel: Some(String::from("topic")),
+            ..old.clone()
+        };
+
+        assert_eq!(
+            metadata_changes(&old, &new),
+            vec![
+                ChannelChange::SettingChanged(Setting::AggregationChannel),
+                ChannelChange::SettingChanged(Setting::Theme),
+                ChannelChange::IndexChanged {
+                    index: Index::Comments,
+                    old: Some(link(b"comments")),
+                    new: Some(link(b"more comments")),
+                },
+                ChannelChange::IndexChanged {
+                    index: Index::Upvotes,
+                    old: None,
+                    new: Some(link(b"upvotes")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn leaf_diff() {
+        let kept = link(b"kept").link;
+        let added = link(b"added").link;
+        let removed = link(b"removed").link;
+
+        let old = BTreeSet::from([kept, removed]);
+        let new = BTreeSet::from([kept, added]);
+
+        assert_eq!(
+            leaf_changes(&old, &new),
+            vec![
+                ChannelChange::ContentAdded(added),
+                ChannelChange::ContentRemoved(removed),
+            ]
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use std::path::PathBuf;
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+
+use chrono::Utc;
+
+use cid::Cid;
+
+use futures::{Stream, TryStreamExt};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{
+    identity::Identity,
+    messaging::{DirectMessage, EncryptedMessage},
+    types::IPLDLink,
+};
+
+use rand_core::{OsRng, RngCore};
+
+use tokio::io::AsyncWriteExt;
+
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::errors::Error;
+
+pub use x25519_dalek;
+
+const TOPIC_CONTEXT: &str = "defluencer 2023-06 direct messages topic";
+const KEY_CONTEXT: &str = "defluencer 2023-06 direct messages key";
+
+/// Encrypted conversation between two identities.
+///
+/// Both sides derive the same pubsub topic and key from their X25519 keys,
+/// the topic does not reveal who is talking.
+pub struct DirectMessages {
+    ipfs: IpfsService,
+    identity: IPLDLink,
+
+    public_key: PublicKey,
+    peer_key: PublicKey,
+
+    cipher: XChaCha20Poly1305,
+    topic: String,
+}
+
+impl DirectMessages {
+    /// Start a conversation with the owner of the peer identity.
+    ///
+    /// The peer identity must include a DM key.
+    pub async fn new(
+        ipfs: IpfsService,
+        secret: &StaticSecret,
+        identity: Cid,
+        peer: Cid,
+    ) -> Result<Self, Error> {
+        let peer = ipfs
+            .dag_get::<&str, Identity>(peer, None, Codec::default())
+            .await?;
+
+        let peer_key = match peer.dm_key.map(<[u8; 32]>::try_from) {
+            Some(Ok(key)) => PublicKey::from(key),
+            _ => return Err(Error::NotFound),
+        };
+
+        Ok(Self::from_keys(ipfs, identity, secret, peer_key))
+    }
+
+    pub fn from_keys(
+        ipfs: IpfsService,
+        identity: Cid,
+        secret: &StaticSecret,
+        peer_key: PublicKey,
+    ) -> Self {
+        let public_key = PublicKey::from(secret);
+        let shared = secret.diffie_hellman(&peer_key);
+
+        let topic = hex::encode(blake3::derive_key(TOPIC_CONTEXT, shared.as_bytes()));
+
+        let key = blake3::derive_key(KEY_CONTEXT, shared.as_bytes());
+        let cipher = XChaCha20Poly1305::new(&key.into());
+
+        Self {
+            ipfs,
+            identity: identity.into(),
+            public_key,
+            peer_key,
+            cipher,
+            topic,
+        }
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Encrypt then publish a message. Peers must be listening to receive it.
+    pub async fn send(&self, text: String) -> Result<DirectMessage, Error> {
+        let message = DirectMessage {
+            identity: self.identity,
+            user_timestamp: Utc::now().timestamp(),
+            text,
+        };
+
+        let envelope = self.encrypt(&message)?;
+
+        let data = serde_ipld_dagcbor::to_vec(&envelope).map_err(ipfs_api::errors::Error::from)?;
+
+        self.ipfs.pubsub_pub(&self.topic, data).await?;
+
+        Ok(message)
+    }
+
+    /// Stream the messages of both sides of the conversation.
+    ///
+    /// Anyone can publish on the topic, messages that cannot be decrypted are dropped.
+    pub fn stream(&self) -> impl Stream<Item = Result<DirectMessage, Error>> + '_ {
+        self.ipfs
+            .pubsub_sub(self.topic.clone().into_bytes())
+            .err_into()
+            .try_filter_map(move |msg| async move {
+                let envelope = match serde_ipld_dagcbor::from_slice::<EncryptedMessage>(&msg.data) {
+                    Ok(envelope) => envelope,
+                    Err(_) => return Ok(None),
+                };
+
+                Ok(self.decrypt(&envelope).ok())
+            })
+    }
+
+    pub fn encrypt(&self, message: &DirectMessage) -> Result<EncryptedMessage, Error> {
+        let plaintext =
+            serde_ipld_dagcbor::to_vec(message).map_err(ipfs_api::errors::Error::from)?;
+
+        let mut nonce = [0u8; 24];
+        OsRng.fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| Error::Cipher)?;
+
+        let envelope = EncryptedMessage {
+            sender: self.public_key.as_bytes().to_vec(),
+            nonce: nonce.to_vec(),
+            ciphertext,
+        };
+
+        Ok(envelope)
+    }
+
+    pub fn decrypt(&self, envelope: &EncryptedMessage) -> Result<DirectMessage, Error> {
+        if envelope.sender != self.peer_key.as_bytes()
+            && envelope.sender != self.public_key.as_bytes()
+        {
+            return Err(Error::Cipher);
+        }
+
+        if envelope.nonce.len() != 24 {
+            return Err(Error::Cipher);
+        }
+
+        let plaintext = self
+            .cipher
+            .decrypt(
+                XNonce::from_slice(&envelope.nonce),
+                envelope.ciphertext.as_slice(),
+            )
+            .map_err(|_| Error::Cipher)?;
+
+        let message = serde_ipld_dagcbor::from_slice(&plaintext).map_err(|_| Error::Cipher)?;
+
+        Ok(message)
+    }
+}
+
+/// Conversation history stored locally, one JSON message per line.
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Returns all the messages in order, none if the file does not exist.
+    pub async fn load(&self) -> Result<Vec<DirectMessage>, Error> {
+        let text = match tokio::fs::read_to_string(&self.path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let messages = text
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(messages)
+    }
+
+    pub async fn append(&self, message: &DirectMessage) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+
+        file.write_all(&line).await?;
+
+        Ok(())
+    }
+}
+
+/// Generate a new DM key pair.
+pub fn generate_secret() -> StaticSecret {
+    StaticSecret::random_from_rng(OsRng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    fn identity(name: &[u8]) -> Cid {
+        Cid::new_v1(0x71, Code::Sha2_256.digest(name))
+    }
+
+    #[test]
+    fn pairwise_conversation() {
+        let alice = generate_secret();
+        let bob = generate_secret();
+        let eve = generate_secret();
+
+        let alice_dm = DirectMessages::from_keys(
+            IpfsService::default(),
+            identity(b"alice"),
+            &alice,
+            PublicKey::from(&bob),
+        );
+        let bob_dm = DirectMessages::from_keys(
+            IpfsService::default(),
+            identity(b"bob"),
+            &bob,
+            PublicKey::from(&alice),
+        );
+        let eve_dm = DirectMessages::from_keys(
+            IpfsService::default(),
+            identity(b"eve"),
+            &eve,
+            PublicKey::from(&bob),
+        );
+
+        assert_eq!(alice_dm.topic(), bob_dm.topic());
+        assert_ne!(alice_dm.topic(), eve_dm.topic());
+
+        let message = DirectMessage {
+            identity: identity(b"alice").into(),
+            user_timestamp: 0,
+            text: String::from("Hello Bob"),
+        };
+
+        let envelope = alice_dm.encrypt(&message).unwrap();
+
+        assert_eq!(bob_dm.decrypt(&envelope).unwrap(), message);
+        assert_eq!(alice_dm.decrypt(&envelope).unwrap(), message);
+        assert!(eve_dm.decrypt(&envelope).is_err());
+
+        let mut tampered = envelope.clone();
+        tampered.ciphertext[0] ^= 1;
+
+        assert!(bob_dm.decrypt(&tampered).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use cid::Cid;
+
+use futures::future::{self, Either};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::media::video::{Parity, Segment, Timecode};
+
+use reed_solomon_erasure::galois_8::ReedSolomon;
+
+use crate::errors::Error;
+
+/// Reed-Solomon coding of archived segments.
+///
+/// Each track of `segments` consecutive segments gets `parity` shards,
+/// any `parity` track data of a group can be reconstructed from the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErasureCoding {
+    pub segments: usize,
+    pub parity: usize,
+}
+
+impl Default for ErasureCoding {
+    fn default() -> Self {
+        Self {
+            segments: 8,
+            parity: 2,
+        }
+    }
+}
+
+/// Outcome of an archive repair.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Repair {
+    /// Track data found missing and reconstructed.
+    pub repaired: Vec<Cid>,
+
+    /// Track data missing that could not be reconstructed.
+    pub lost: Vec<Cid>,
+}
+
+/// Returns the parity shards of the data, zero padded to the longest.
+pub fn encode(data: &[Vec<u8>], parity: usize) -> Result<Vec<Vec<u8>>, Error> {
+    let len = data.iter().map(Vec::len).max().unwrap_or_default();
+
+    let mut shards: Vec<Vec<u8>> = data
+        .iter()
+        .map(|shard| {
+            let mut shard = shard.clone();
+            shard.resize(len, 0);
+            shard
+        })
+        .collect();
+
+    shards.extend((0..parity).map(|_| vec![0; len]));
+
+    let coder = ReedSolomon::new(data.len(), parity)?;
+    coder.encode(&mut shards)?;
+
+    Ok(shards.split_off(data.len()))
+}
+
+/// Returns all the data, reconstructing the missing shards.
+///
+/// Sizes are the original length of each data shard.
+pub fn reconstruct(
+    data: Vec<Option<Vec<u8>>>,
+    sizes: &[u64],
+    parity: Vec<Option<Vec<u8>>>,
+) -> Result<Vec<Vec<u8>>, Error> {
+    let len = sizes.iter().copied().max().unwrap_or_default() as usize;
+    let data_count = data.len();
+
+    let coder = ReedSolomon::new(data_count, parity.len())?;
+
+    let mut shards: Vec<Option<Vec<u8>>> = data
+        .into_iter()
+        .chain(parity)
+        .map(|shard| {
+            shard.map(|mut shard| {
+                shard.resize(len, 0);
+                shard
+            })
+        })
+        .collect();
+
+    coder.reconstruct_data(&mut shards)?;
+
+    let data = shards
+        .into_iter()
+        .take(data_count)
+        .zip(sizes)
+        .map(|(shard, size)| {
+            let mut shard = shard.unwrap_or_default();
+            shard.truncate(*size as usize);
+            shard
+        })
+        .collect();
+
+    Ok(data)
+}
+
+/// Add parity for each track of these segments.
+///
+/// Returns the CIDs of the parity nodes.
+pub async fn add_parity(
+    ipfs: &IpfsService,
+    segments: &[Cid],
+    parity: usize,
+) -> Result<Vec<Cid>, Error> {
+    let mut tracks: BTreeMap<String, Vec<Cid>> = BTreeMap::new();
+
+    for cid in segments.iter().copied() {
+        let segment = ipfs
+            .dag_get::<&str, Segment>(cid, None, Codec::default())
+            .await?;
+
+        for (name, ipld) in segment.tracks {
+            tracks.entry(name).or_default().push(ipld.link);
+        }
+    }
+
+    let mut cids = Vec::with_capacity(tracks.len());
+
+    for links in tracks.into_values() {
+        let mut data = Vec::with_capacity(links.len());
+
+        for link in links.iter().copied() {
+            let bytes = ipfs.cat(link, Option::<&str>::None).await?;
+
+            data.push(bytes.to_vec());
+        }
+
+        let mut shards = Vec::with_capacity(parity);
+
+        for shard in encode(&data, parity)? {
+            shards.push(add_shard(ipfs, shard).await?.into());
+        }
+
+        let node = Parity {
+            data: links.into_iter().map(Into::into).collect(),
+            sizes: data.iter().map(|data| data.len() as u64).collect(),
+            parity: shards,
+        };
+
+        let cid = ipfs
+            .dag_put(&node, Codec::default(), Codec::default())
+            .await?;
+
+        cids.push(cid);
+    }
+
+    Ok(cids)
+}
+
+/// Reconstruct the track data that cannot be retrieved within the timeout.
+///
+/// Reconstructed data is added back to this node.
+pub async fn repair(ipfs: &IpfsService, parity: Cid, timeout: Duration) -> Result<Repair, Error> {
+    let node = ipfs
+        .dag_get::<&str, Parity>(parity, None, Codec::default())
+        .await?;
+
+    let mut data = Vec::with_capacity(node.data.len());
+    let mut missing = Vec::new();
+
+    for ipld in node.data.iter() {
+        let bytes = fetch(ipfs, ipld.link, timeout).await;
+
+        if bytes.is_none() {
+            missing.push(ipld.link);
+        }
+
+        data.push(bytes);
+    }
+
+    if missing.is_empty() {
+        return Ok(Repair::default());
+    }
+
+    let mut shards = Vec::with_capacity(node.parity.len());
+
+    for ipld in node.parity.iter() {
+        shards.push(fetch(ipfs, ipld.link, timeout).await);
+    }
+
+    let data = match reconstruct(data, &node.sizes, shards) {
+        Ok(data) => data,
+        Err(Error::ErasureCoding(_)) => {
+            return Ok(Repair {
+                repaired: Vec::new(),
+                lost: missing,
+            })
+        }
+        Err(e) => return Err(e),
+    };
+
+    let mut repair = Repair::default();
+
+    for (ipld, bytes) in node.data.iter().zip(data) {
+        if !missing.contains(&ipld.link) {
+            continue;
+        }
+
+        // Same chunking and hash, same CID.
+        match add_shard(ipfs, bytes).await? == ipld.link {
+            true => repair.repaired.push(ipld.link),
+            false => repair.lost.push(ipld.link),
+        }
+    }
+
+    Ok(repair)
+}
+
+/// Repair all the segment groups of an archive.
+pub async fn repair_archive(
+    ipfs: &IpfsService,
+    timecode: Cid,
+    timeout: Duration,
+) -> Result<Repair, Error> {
+    let timecode = ipfs
+        .dag_get::<&str, Timecode>(timecode, None, Codec::default())
+        .await?;
+
+    let mut repair = Repair::default();
+
+    for ipld in timecode.parity {
+        let Repair { repaired, lost } = self::repair(ipfs, ipld.link, timeout).await?;
+
+        repair.repaired.extend(repaired);
+        repair.lost.extend(lost);
+    }
+
+    Ok(repair)
+}
+
+/// Returns the data or none if it cannot be found in time.
+async fn fetch(ipfs: &IpfsService, cid: Cid, timeout: Duration) -> Option<Vec<u8>> {
+    let cat = ipfs.cat(cid, Option::<&str>::None);
+    let delay = futures_timer::Delay::new(timeout);
+
+    futures::pin_mut!(cat);
+
+    match future::select(cat, delay).await {
+        Either::Left((Ok(bytes), _)) => Some(bytes.to_vec()),
+        Either::Left((Err(_), _)) | Either::Right(_) => None,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn add_shard(ipfs: &IpfsService, shard: Vec<u8>) -> Result<Cid, Error> {
+    let stream = futures::stream::once(future::ready(Ok::<_, std::io::Error>(shard)));
+
+    let cid = ipfs.add(stream).await?;
+
+    Ok(cid)
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn add_shard(ipfs: &IpfsService, shard: Vec<u8>) -> Result<Cid, Error> {
+    let cid = ipfs.add(shard.into()).await?;
+
+    Ok(cid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let data = vec![b"first track".to_vec(), b"2nd".to_vec(), b"third!".to_vec()];
+        let sizes: Vec<u64> = data.iter().map(|data| data.len() as u64).collect();
+
+        let parity = encode(&data, 2).unwrap();
+
+        assert_eq!(parity.len(), 2);
+        assert!(parity.iter().all(|shard| shard.len() == data[0].len()));
+
+        let damaged = vec![None, Some(data[1].clone()), None];
+        let shards = parity.into_iter().map(Some).collect();
+
+        assert_eq!(reconstruct(damaged, &sizes, shards).unwrap(), data);
+
+        let lost = vec![None, None, None];
+        let shards = vec![None, None];
+
+        assert!(reconstruct(lost, &sizes, shards).is_err());
+    }
+}
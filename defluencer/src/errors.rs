@@ -2,26 +2,33 @@ use thiserror::Error;
 
 use crate::indexing::hamt;
 
+#[cfg(feature = "ordered-trees")]
+use crate::indexing::ordered_trees;
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[cfg(target_arch = "wasm32")]
     #[error("JS: {0}")]
     JsError(js_sys::JsString),
 
-    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg(all(not(target_arch = "wasm32"), feature = "signers"))]
     #[error("Ledger: {0}")]
     Ledger(#[from] ledger_zondax_generic::LedgerAppError<ledger_transport_hid::LedgerHIDError>),
 
     #[error("HAMT: {0}")]
     HAMT(#[from] hamt::HAMTError),
 
+    #[cfg(feature = "ordered-trees")]
+    #[error("Ordered Tree: {0}")]
+    OrderedTree(#[from] ordered_trees::errors::Error),
+
     #[error("Elliptic Curve: {0}")]
     EllipticCurve(#[from] k256::elliptic_curve::Error),
 
     #[error("Signature: {0}")]
     Signatue(#[from] k256::ecdsa::signature::Error),
 
-    #[cfg(target_arch = "wasm32")]
+    #[cfg(all(target_arch = "wasm32", feature = "signers"))]
     #[error("Web3: {0}")]
     Web3(#[from] web3::Error),
 
@@ -43,6 +50,10 @@ pub enum Error {
     #[error("Multihash: {0}")]
     Multihash(#[from] multihash::Error),
 
+    #[cfg(feature = "daemons")]
+    #[error("Erasure Coding: {0}")]
+    ErasureCoding(#[from] reed_solomon_erasure::Error),
+
     #[error("Ipfs: {0}")]
     IpfsApi(#[from] ipfs_api::errors::Error),
 
@@ -67,9 +78,60 @@ pub enum Error {
     #[error("Defluencer: Cannot process file, please use a markdown file")]
     Markdown,
 
+    #[error("Defluencer: Operation not supported for this media type")]
+    Unsupported,
+
+    #[error("Defluencer: Unsupported media format, {0}")]
+    MediaFormat(String),
+
     #[error("IPNS Address Mismatch")]
     IPNSMismatch,
 
+    #[error("Invalid Signature")]
+    Signature,
+
     #[error("Invalid Timestamp")]
     Timestamp,
+
+    #[error("Timestamp Too Far In The Future")]
+    FutureTimestamp,
+
+    #[error("Rate Limited")]
+    RateLimited,
+
+    #[error("Not Enough Owners Approvals")]
+    Approvals,
+
+    #[error("Invalid Theme")]
+    Theme,
+
+    #[error("Invalid Identity")]
+    Identity,
+
+    #[error("Invalid Succession")]
+    Succession,
+
+    #[error("Not Enough Vouches For Recovery")]
+    Recovery,
+
+    #[error("Cannot Encrypt Or Decrypt Message")]
+    Cipher,
+
+    #[error("Too Large: {size} bytes, limit is {limit}")]
+    TooLarge { size: u64, limit: u64 },
+
+    #[error("Too Many Pinned Comments")]
+    PinLimit,
+
+    #[error("Channel Update Conflict: expected {expected} found {found}")]
+    Conflict { expected: cid::Cid, found: cid::Cid },
+
+    #[error("Cannot Encode Canonically: {0}")]
+    NonCanonical(String),
+
+    #[error("Invalid DAG-CBOR Block {0}")]
+    InvalidBlock(cid::Cid),
+
+    #[error("Denied CID {0}")]
+    Denied(cid::Cid),
 }
@@ -0,0 +1,206 @@
+use std::fmt::Write;
+
+use chrono::{TimeZone, Utc};
+
+use cid::Cid;
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{
+    identity::Identity,
+    media::event::{Event, Rsvp},
+    types::IPNSAddress,
+};
+
+use crate::{comments::signer_address, crypto::signed_link::SignedLink, errors::Error};
+
+/// Pubsub topic where RSVPs to a channel's events are submitted.
+pub fn rsvp_topic(addr: IPNSAddress) -> String {
+    format!("{}_rsvp", addr)
+}
+
+/// Fetch an RSVP's signed link, check the signature then attribute it to the claimed identity.
+///
+/// Returns None if the RSVP is unverifiable, network errors are returned as is.
+pub async fn verify_rsvp(ipfs: &IpfsService, cid: Cid) -> Result<Option<Rsvp>, Error> {
+    let signed_link = match ipfs
+        .dag_get::<&str, SignedLink>(cid, None, Codec::default())
+        .await
+    {
+        Ok(link) => link,
+        Err(e) if e.is_decode() => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    if !signed_link.verify() {
+        return Ok(None);
+    }
+
+    let rsvp = match ipfs
+        .dag_get::<&str, Rsvp>(signed_link.link.link, None, Codec::default())
+        .await
+    {
+        Ok(rsvp) => rsvp,
+        Err(e) if e.is_decode() => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let identity = ipfs
+        .dag_get::<&str, Identity>(rsvp.identity.link, None, Codec::default())
+        .await?;
+
+    if signer_address(&signed_link, &identity).is_err() {
+        return Ok(None);
+    }
+
+    Ok(Some(rsvp))
+}
+
+/// Event with the number of attendees known to the channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEntry {
+    pub cid: Cid,
+    pub event: Event,
+    pub attendees: usize,
+}
+
+/// Returns an iCalendar (RFC 5545) document of these events.
+pub fn calendar(name: &str, entries: &[CalendarEntry]) -> String {
+    let mut ics = String::new();
+
+    line(&mut ics, "BEGIN:VCALENDAR");
+    line(&mut ics, "VERSION:2.0");
+    line(&mut ics, "PRODID:-//Defluencer//Events//EN");
+    line(&mut ics, &format!("X-WR-CALNAME:{}", escape(name)));
+
+    for entry in entries {
+        let event = &entry.event;
+
+        line(&mut ics, "BEGIN:VEVENT");
+        line(&mut ics, &format!("UID:{}@defluencer", entry.cid));
+        line(
+            &mut ics,
+            &format!("DTSTAMP:{}", date_time(event.user_timestamp)),
+        );
+        line(&mut ics, &format!("DTSTART:{}", date_time(event.start)));
+
+        if let Some(duration) = event.duration {
+            let end = event.start.saturating_add(duration as i64);
+            line(&mut ics, &format!("DTEND:{}", date_time(end)));
+        }
+
+        line(&mut ics, &format!("SUMMARY:{}", escape(&event.title)));
+
+        let mut description = event.summary.clone().unwrap_or_default();
+
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+
+        let _ = write!(description, "{} attending", entry.attendees);
+
+        line(&mut ics, &format!("DESCRIPTION:{}", escape(&description)));
+
+        if let Some(location) = event.location.as_deref() {
+            line(&mut ics, &format!("LOCATION:{}", escape(location)));
+        }
+
+        if let Some(stream) = event.stream {
+            line(&mut ics, &format!("URL:ipns://{}", stream));
+        }
+
+        line(&mut ics, "END:VEVENT");
+    }
+
+    line(&mut ics, "END:VCALENDAR");
+
+    ics
+}
+
+fn date_time(timestamp: i64) -> String {
+    match Utc.timestamp_opt(timestamp, 0).single() {
+        Some(date) => date.format("%Y%m%dT%H%M%SZ").to_string(),
+        None => String::from("19700101T000000Z"),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for char in text.chars() {
+        match char {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(char),
+        }
+    }
+
+    escaped
+}
+
+/// Write a content line, folded at 75 octets without splitting characters.
+fn line(ics: &mut String, content: &str) {
+    let mut length = 0;
+
+    for char in content.chars() {
+        if length + char.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            length = 1;
+        }
+
+        ics.push(char);
+        length += char.len_utf8();
+    }
+
+    ics.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    #[test]
+    fn calendar_export() {
+        let cid = Cid::new_v1(0x71, Code::Sha2_256.digest(b"event"));
+
+        let event = Event {
+            user_timestamp: 0,
+            title: String::from("Premiere; Part 1, Live"),
+            start: 1_700_000_000,
+            duration: Some(3600),
+            location: Some(String::from("Montréal")),
+            summary: Some("First line\nSecond line ".repeat(4)),
+            ..Default::default()
+        };
+
+        let entries = [CalendarEntry {
+            cid,
+            event,
+            attendees: 42,
+        }];
+
+        let ics = calendar("My Channel", &entries);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains(&format!("UID:{}@defluencer\r\n", cid)));
+        assert!(ics.contains("DTSTART:20231114T221320Z\r\n"));
+        assert!(ics.contains("DTEND:20231114T231320Z\r\n"));
+        assert!(ics.contains("SUMMARY:Premiere\\; Part 1\\, Live\r\n"));
+        assert!(ics.contains("LOCATION:Montréal\r\n"));
+        assert!(!ics.contains("URL:"));
+
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= 75);
+        }
+
+        let unfolded = ics.replace("\r\n ", "");
+
+        assert!(unfolded.contains("Second line \\n\\n42 attending\r\n"));
+    }
+}
@@ -0,0 +1,112 @@
+use linked_data::{
+    channel::moderation::Blocklist,
+    media::{chat::ChatMessage, comments::Comment},
+    types::PeerId,
+};
+
+/// Decide what is shown when streaming comments and chat.
+///
+/// Filters allow everything by default and can be chained with `and`.
+pub trait Filter {
+    /// Returns true if the comment should be shown.
+    fn allow_comment(&self, _comment: &Comment) -> bool {
+        true
+    }
+
+    /// Returns true if the chat message should be shown.
+    fn allow_chat(&self, _peer: PeerId, _message: &ChatMessage) -> bool {
+        true
+    }
+
+    /// Only allow what both filters allow.
+    fn and<F: Filter>(self, other: F) -> And<Self, F>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+}
+
+/// Allow everything.
+impl Filter for () {}
+
+impl<F: Filter + ?Sized> Filter for &F {
+    fn allow_comment(&self, comment: &Comment) -> bool {
+        (**self).allow_comment(comment)
+    }
+
+    fn allow_chat(&self, peer: PeerId, message: &ChatMessage) -> bool {
+        (**self).allow_chat(peer, message)
+    }
+}
+
+impl Filter for Blocklist {
+    fn allow_comment(&self, comment: &Comment) -> bool {
+        !self.identities.contains(&comment.identity)
+    }
+
+    fn allow_chat(&self, peer: PeerId, _message: &ChatMessage) -> bool {
+        !self.peers.contains(&peer)
+    }
+}
+
+pub struct And<A, B>(pub A, pub B);
+
+impl<A: Filter, B: Filter> Filter for And<A, B> {
+    fn allow_comment(&self, comment: &Comment) -> bool {
+        self.0.allow_comment(comment) && self.1.allow_comment(comment)
+    }
+
+    fn allow_chat(&self, peer: PeerId, message: &ChatMessage) -> bool {
+        self.0.allow_chat(peer, message) && self.1.allow_chat(peer, message)
+    }
+}
+
+/// Filter comments with a closure.
+pub struct CommentFilter<F>(pub F);
+
+impl<F: Fn(&Comment) -> bool> Filter for CommentFilter<F> {
+    fn allow_comment(&self, comment: &Comment) -> bool {
+        (self.0)(comment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cid::Cid;
+
+    use multihash::{Code, MultihashDigest};
+
+    fn comment(identity: &[u8], text: &str) -> Comment {
+        Comment {
+            identity: Cid::new_v1(0x71, Code::Sha2_256.digest(identity)).into(),
+            text: text.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn chained_filters() {
+        let troll = comment(b"troll", "hello");
+        let friend = comment(b"friend", "hello");
+        let spam = comment(b"friend", "buy now");
+
+        let mut blocklist = Blocklist::default();
+        blocklist.identities.insert(troll.identity);
+
+        assert!(!blocklist.allow_comment(&troll));
+        assert!(blocklist.allow_comment(&spam));
+
+        let filter = (&blocklist).and(CommentFilter(|comment: &Comment| {
+            !comment.text.contains("buy")
+        }));
+
+        assert!(!filter.allow_comment(&troll));
+        assert!(filter.allow_comment(&friend));
+        assert!(!filter.allow_comment(&spam));
+
+        assert!(().allow_comment(&troll));
+    }
+}
@@ -0,0 +1,183 @@
+use cid::Cid;
+
+use linked_data::types::IPNSAddress;
+
+use multibase::Base;
+
+/// How a gateway expects content to be addressed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GatewayStyle {
+    /// Eg. ```https://ipfs.io/ipfs/<CID>```
+    #[default]
+    Path,
+
+    /// Eg. ```https://<CID>.ipfs.dweb.link/```
+    ///
+    /// Each CID has its own origin, isolating web pages from one another.
+    Subdomain,
+}
+
+/// Public or self-hosted HTTP gateway used to render links to content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gateway {
+    scheme: String,
+    host: String,
+    style: GatewayStyle,
+}
+
+impl Default for Gateway {
+    fn default() -> Self {
+        Self::path("https://ipfs.io")
+    }
+}
+
+impl Gateway {
+    /// Gateway serving content at ```<URL>/ipfs/<CID>```.
+    ///
+    /// URLs without scheme default to https.
+    pub fn path(url: &str) -> Self {
+        Self::new(url, GatewayStyle::Path)
+    }
+
+    /// Gateway serving content at ```<CID>.ipfs.<HOST>```.
+    pub fn subdomain(url: &str) -> Self {
+        Self::new(url, GatewayStyle::Subdomain)
+    }
+
+    pub fn new(url: &str, style: GatewayStyle) -> Self {
+        let url = url.trim().trim_end_matches('/');
+
+        let (scheme, host) = url.split_once("://").unwrap_or(("https", url));
+
+        Self {
+            scheme: scheme.to_owned(),
+            host: host.to_owned(),
+            style,
+        }
+    }
+
+    pub fn style(&self) -> GatewayStyle {
+        self.style
+    }
+
+    /// Returns the gateway root URL.
+    pub fn url(&self) -> String {
+        format!("{}://{}", self.scheme, self.host)
+    }
+
+    /// Returns the URL of this CID.
+    pub fn ipfs(&self, cid: Cid) -> String {
+        match self.style {
+            GatewayStyle::Path => format!("{}/ipfs/{}", self.url(), cid),
+            GatewayStyle::Subdomain => {
+                // Subdomains are case insensitive, CIDv0 would not survive.
+                let cid = Cid::new_v1(cid.codec(), *cid.hash());
+
+                format!("{}://{}.ipfs.{}/", self.scheme, cid, self.host)
+            }
+        }
+    }
+
+    /// Returns the URL of the content this IPNS address points to.
+    pub fn ipns(&self, addr: IPNSAddress) -> String {
+        match self.style {
+            GatewayStyle::Path => format!("{}/ipns/{}", self.url(), addr),
+            GatewayStyle::Subdomain => {
+                let cid: Cid = addr.into();
+
+                // Base 36 keeps ed25519 keys under the 63 characters DNS label limit.
+                let label = cid
+                    .to_string_of_base(Base::Base36Lower)
+                    .unwrap_or_else(|_| cid.to_string());
+
+                format!("{}://{}.ipns.{}/", self.scheme, label, self.host)
+            }
+        }
+    }
+
+    /// Returns the URL of this file, browsers will use the file name when saving.
+    pub fn file(&self, cid: Cid, filename: &str) -> String {
+        format!("{}?filename={}", self.ipfs(cid), percent_encode(filename))
+    }
+
+    /// Returns the URL of this file, browsers will download it instead of displaying it.
+    pub fn download(&self, cid: Cid, filename: &str) -> String {
+        format!("{}&download=true", self.file(cid, filename))
+    }
+}
+
+/// Percent encode everything but unreserved characters.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            byte => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    const CID_V0: &str = "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn";
+    const CID_V1: &str = "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354";
+    const IPNS: &str = "bafzbeiegbnjh5uopd5vc22tgkz6chf7a6ala3x5e47vnhv5sq5bzo46tri";
+
+    #[test]
+    fn path_urls() {
+        let gateway = Gateway::path("http://127.0.0.1:8080/");
+        let cid = Cid::from_str(CID_V0).unwrap();
+
+        assert_eq!(
+            gateway.ipfs(cid),
+            format!("http://127.0.0.1:8080/ipfs/{}", CID_V0)
+        );
+
+        let addr = IPNSAddress::from_str(IPNS).unwrap();
+
+        assert_eq!(
+            gateway.ipns(addr),
+            format!("http://127.0.0.1:8080/ipns/{}", IPNS)
+        );
+
+        assert_eq!(
+            gateway.download(cid, "My Video #1.mp4"),
+            format!(
+                "http://127.0.0.1:8080/ipfs/{}?filename=My%20Video%20%231.mp4&download=true",
+                CID_V0
+            )
+        );
+    }
+
+    #[test]
+    fn subdomain_urls() {
+        let gateway = Gateway::subdomain("dweb.link");
+
+        let cid = Cid::from_str(CID_V0).unwrap();
+
+        assert_eq!(
+            gateway.ipfs(cid),
+            format!("https://{}.ipfs.dweb.link/", CID_V1)
+        );
+
+        let addr = IPNSAddress::from_str(IPNS).unwrap();
+        let url = gateway.ipns(addr);
+
+        assert!(url.starts_with("https://k"));
+        assert!(url.ends_with(".ipns.dweb.link/"));
+
+        assert_eq!(
+            gateway.file(cid, "post.md"),
+            format!("https://{}.ipfs.dweb.link/?filename=post.md", CID_V1)
+        );
+    }
+}
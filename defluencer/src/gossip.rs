@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use cid::Cid;
+
+use ipns_records::IPNSRecord;
+
+use linked_data::{
+    channel::gossip::{Gossip, GossipEntry},
+    types::IPNSAddress,
+};
+
+use crate::errors::Error;
+
+/// Pubsub topic where a group of friends gossip the channel roots they know.
+pub fn gossip_topic(group: &str) -> String {
+    format!("{}_gossip", group)
+}
+
+/// Latest verified IPNS record of each channel, shared between tasks.
+///
+/// Records are only kept if their signature match the channel address
+/// and their sequence is higher than the one already known.
+#[derive(Clone, Default)]
+pub struct GossipCache {
+    records: Arc<RwLock<HashMap<IPNSAddress, IPNSRecord>>>,
+}
+
+impl GossipCache {
+    /// Verify a record then keep it if it is the freshest known for this channel.
+    ///
+    /// Returns the new channel root, none if the record was not newer.
+    pub fn insert(&self, channel: IPNSAddress, record: &[u8]) -> Result<Option<Cid>, Error> {
+        let record = IPNSRecord::from_bytes(record)?;
+
+        record.verify(channel.into())?;
+
+        if record.is_expired()? {
+            return Err(ipns_records::Error::Expired.into());
+        }
+
+        let mut records = self.records.write().expect("Lock Poisoned");
+
+        if let Some(known) = records.get(&channel) {
+            if known.get_sequence() >= record.get_sequence() {
+                return Ok(None);
+            }
+        }
+
+        let root = record.get_value();
+
+        records.insert(channel, record);
+
+        Ok(Some(root))
+    }
+
+    /// Latest known root and sequence of this channel, if its record has not expired.
+    pub fn latest(&self, channel: IPNSAddress) -> Option<(Cid, u64)> {
+        let records = self.records.read().expect("Lock Poisoned");
+
+        let record = records.get(&channel)?;
+
+        match record.is_expired() {
+            Ok(false) => Some((record.get_value(), record.get_sequence())),
+            _ => None,
+        }
+    }
+
+    /// Every unexpired record, ready to be gossiped.
+    pub fn to_gossip(&self) -> Gossip {
+        let records = self.records.read().expect("Lock Poisoned");
+
+        let entries = records
+            .iter()
+            .filter(|(_, record)| matches!(record.is_expired(), Ok(false)))
+            .map(|(channel, record)| GossipEntry {
+                channel: *channel,
+                record: record.to_bytes(),
+            })
+            .collect();
+
+        Gossip { entries }
+    }
+}
+
+/// Encode gossip for pubsub, in DAG-CBOR.
+pub fn encode_gossip(gossip: &Gossip) -> Result<Vec<u8>, Error> {
+    let data = serde_ipld_dagcbor::to_vec(gossip).map_err(ipfs_api::errors::Error::from)?;
+
+    Ok(data)
+}
+
+/// Decode gossip received over pubsub, entries are NOT verified.
+pub fn decode_gossip(data: &[u8]) -> Option<Gossip> {
+    serde_ipld_dagcbor::from_slice(data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    #![cfg(not(target_arch = "wasm32"))]
+
+    use super::*;
+
+    use chrono::Duration;
+
+    use ipns_records::{CryptoKey, UnsignedRecord};
+
+    use k256::ecdsa::{signature::DigestSigner, Signature, SigningKey};
+
+    use multihash::{Code, MultihashDigest};
+
+    use sha2::{Digest, Sha256};
+
+    fn sign(key: &SigningKey, input: &[u8]) -> Vec<u8> {
+        let signature: Signature = key.sign_digest(Sha256::new_with_prefix(input));
+
+        signature.to_der().as_bytes().to_vec()
+    }
+
+    fn record(key: &SigningKey, root: Cid, sequence: u64, valid_for: Duration) -> Vec<u8> {
+        let unsigned = UnsignedRecord::new(root, valid_for, sequence, 0);
+
+        let signature_v1 = sign(key, &unsigned.signing_input_v1());
+        let signature_v2 = sign(key, &unsigned.signing_input_v2());
+
+        let crypto_key = CryptoKey::new_k256(key.verifying_key());
+
+        unsigned
+            .into_record(crypto_key, signature_v1, signature_v2)
+            .unwrap()
+            .to_bytes()
+    }
+
+    fn address(key: &SigningKey) -> IPNSAddress {
+        CryptoKey::new_k256(key.verifying_key())
+            .to_address()
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn gossip_cache() {
+        let key = SigningKey::random(&mut rand_core::OsRng);
+        let squatter = SigningKey::random(&mut rand_core::OsRng);
+
+        let channel = address(&key);
+
+        let old_root = Cid::new_v1(0x71, Code::Sha2_256.digest(b"old"));
+        let new_root = Cid::new_v1(0x71, Code::Sha2_256.digest(b"new"));
+
+        let cache = GossipCache::default();
+
+        let old = record(&key, old_root, 1, Duration::days(1));
+        let new = record(&key, new_root, 2, Duration::days(1));
+
+        assert_eq!(cache.insert(channel, &new).unwrap(), Some(new_root));
+        assert_eq!(cache.insert(channel, &old).unwrap(), None);
+        assert_eq!(cache.latest(channel), Some((new_root, 2)));
+
+        let forged = record(&squatter, old_root, 3, Duration::days(1));
+
+        assert!(cache.insert(channel, &forged).is_err());
+
+        let expired = record(&key, old_root, 4, Duration::days(-1));
+
+        assert!(cache.insert(channel, &expired).is_err());
+        assert_eq!(cache.latest(channel), Some((new_root, 2)));
+
+        let data = encode_gossip(&cache.to_gossip()).unwrap();
+        let gossip = decode_gossip(&data).unwrap();
+
+        let other = GossipCache::default();
+
+        for entry in gossip.entries {
+            other.insert(entry.channel, &entry.record).unwrap();
+        }
+
+        assert_eq!(other.latest(channel), Some((new_root, 2)));
+    }
+}
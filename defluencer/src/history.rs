@@ -0,0 +1,219 @@
+use chrono::Utc;
+
+use cid::Cid;
+
+use futures::{future, stream, Stream, TryStreamExt};
+
+use ipfs_api::IpfsService;
+
+use libipld_core::ipld::Ipld;
+
+use linked_data::types::IPNSAddress;
+
+use crate::{
+    channel::{local::LocalUpdater, IpnsUpdater},
+    errors::Error,
+    indexing::ordered_trees::{errors::Error as TreeError, prolly::ProllyTree},
+};
+
+/// Playback state of one media.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Progress {
+    /// Playback position in seconds.
+    pub position: f64,
+
+    /// Media was watched or read until the end.
+    pub completed: bool,
+
+    /// Timestamp of the last update in Unix time.
+    pub timestamp: i64,
+}
+
+impl From<Progress> for Ipld {
+    fn from(progress: Progress) -> Self {
+        Ipld::List(vec![
+            Ipld::Float(progress.position),
+            Ipld::Bool(progress.completed),
+            Ipld::Integer(progress.timestamp as i128),
+        ])
+    }
+}
+
+impl TryFrom<Ipld> for Progress {
+    type Error = TreeError;
+
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        let Ipld::List(list) = ipld else {
+            return Err(TreeError::UnknownValueType);
+        };
+
+        let [Ipld::Float(position), Ipld::Bool(completed), Ipld::Integer(timestamp)] =
+            list.as_slice()
+        else {
+            return Err(TreeError::UnknownValueType);
+        };
+
+        let progress = Self {
+            position: *position,
+            completed: *completed,
+            timestamp: *timestamp as i64,
+        };
+
+        Ok(progress)
+    }
+}
+
+/// Per media playback positions, stored in a prolly tree under an IPNS address.
+///
+/// Resolve the same address on any device to continue watching.
+#[derive(Clone)]
+pub struct WatchHistory<T>
+where
+    T: IpnsUpdater + Clone,
+{
+    ipfs: IpfsService,
+    addr: IPNSAddress,
+    updater: T,
+}
+
+impl WatchHistory<LocalUpdater> {
+    /// Create a new watch history with your IPFS node local keys.
+    pub async fn create_local(ipfs: IpfsService, key: String) -> Result<Self, Error> {
+        let key_pair = ipfs.key_gen(key.clone()).await?;
+        let addr = IPNSAddress::try_from(key_pair.id)?;
+
+        let tree = ProllyTree::new::<Progress>(ipfs.clone(), None).await?;
+        let root = tree.save().await?;
+
+        ipfs.pin_add(root, true).await?;
+
+        let updater = LocalUpdater::new(ipfs.clone(), key);
+
+        updater.update(root).await?;
+
+        let history = Self::new(ipfs, addr, updater);
+
+        Ok(history)
+    }
+}
+
+impl<T> WatchHistory<T>
+where
+    T: IpnsUpdater + Clone,
+{
+    pub fn new(ipfs: IpfsService, addr: IPNSAddress, updater: T) -> Self {
+        Self {
+            ipfs,
+            addr,
+            updater,
+        }
+    }
+
+    /// Returns the playback state of this media, if any.
+    pub async fn get(&self, media: Cid) -> Result<Option<Progress>, Error> {
+        let (_, tree) = self.get_tree().await?;
+
+        let progress = tree
+            .get::<Progress>(media.to_bytes())
+            .await?
+            .map(|(_, progress)| progress);
+
+        Ok(progress)
+    }
+
+    /// Save the playback state of this media.
+    pub async fn set(&self, media: Cid, position: f64, completed: bool) -> Result<Cid, Error> {
+        let (old_root, mut tree) = self.get_tree().await?;
+
+        let progress = Progress {
+            position,
+            completed,
+            timestamp: Utc::now().timestamp(),
+        };
+
+        tree.insert(media.to_bytes(), progress).await?;
+
+        self.update_tree(old_root, &tree).await
+    }
+
+    /// Forget the playback state of this media.
+    pub async fn remove(&self, media: Cid) -> Result<Cid, Error> {
+        let (old_root, mut tree) = self.get_tree().await?;
+
+        tree.remove::<Progress>(media.to_bytes()).await?;
+
+        self.update_tree(old_root, &tree).await
+    }
+
+    /// Stream the playback state of all media.
+    pub fn stream(&self) -> impl Stream<Item = Result<(Cid, Progress), Error>> + '_ {
+        stream::once(async move {
+            let (_, tree) = self.get_tree().await?;
+
+            Result::<_, Error>::Ok(tree.stream::<Progress>().err_into())
+        })
+        .try_flatten()
+        .and_then(|(key, progress)| async move {
+            let cid = Cid::try_from(key)?;
+
+            Result::<_, Error>::Ok((cid, progress))
+        })
+    }
+
+    /// Returns all media not yet completed, most recently watched first.
+    pub async fn continue_watching(&self) -> Result<Vec<(Cid, Progress)>, Error> {
+        let mut media: Vec<_> = self
+            .stream()
+            .try_filter(|(_, progress)| future::ready(!progress.completed))
+            .try_collect()
+            .await?;
+
+        media.sort_unstable_by(|(_, a), (_, b)| b.timestamp.cmp(&a.timestamp));
+
+        Ok(media)
+    }
+
+    async fn get_tree(&self) -> Result<(Cid, ProllyTree), Error> {
+        let root = self.ipfs.name_resolve(self.addr).await?;
+
+        let tree = ProllyTree::load(self.ipfs.clone(), root).await?;
+
+        Ok((root, tree))
+    }
+
+    async fn update_tree(&self, old_root: Cid, tree: &ProllyTree) -> Result<Cid, Error> {
+        let root = tree.save().await?;
+
+        self.ipfs.pin_update(old_root, root).await?;
+
+        self.updater.update(root).await?;
+
+        Ok(root)
+    }
+
+    pub fn get_address(&self) -> IPNSAddress {
+        self.addr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_roundtrip() {
+        let progress = Progress {
+            position: 754.5,
+            completed: false,
+            timestamp: 1_700_000_000,
+        };
+
+        let ipld: Ipld = progress.clone().into();
+
+        let decoded = Progress::try_from(ipld).unwrap();
+
+        assert_eq!(progress, decoded);
+
+        assert!(Progress::try_from(Ipld::Bool(true)).is_err());
+    }
+}
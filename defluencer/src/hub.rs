@@ -0,0 +1,229 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Mutex,
+};
+
+use cid::Cid;
+
+use futures::{Stream, TryStreamExt};
+
+use linked_data::types::IPLDLink;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aggregation::{AggregationStats, Aggregator},
+    channel::{Channel, IpnsUpdater},
+    errors::Error,
+    Defluencer,
+};
+
+/// Moderation queue of a hub, serialize it to resume later.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct HubQueue {
+    /// Content waiting for moderation, oldest first.
+    pub pending: VecDeque<IPLDLink>,
+
+    /// Content approved or rejected, never queued again.
+    pub moderated: HashSet<IPLDLink>,
+}
+
+impl HubQueue {
+    /// Queue content not seen before, returns false otherwise.
+    pub fn push(&mut self, content_cid: Cid) -> bool {
+        let ipld = content_cid.into();
+
+        if self.moderated.contains(&ipld) || self.pending.contains(&ipld) {
+            return false;
+        }
+
+        self.pending.push_back(ipld);
+
+        true
+    }
+
+    pub fn contains(&self, content_cid: Cid) -> bool {
+        self.pending.contains(&content_cid.into())
+    }
+
+    /// Take queued content out of the queue, returns false if it was not queued.
+    pub fn moderate(&mut self, content_cid: Cid) -> bool {
+        let ipld = content_cid.into();
+
+        let Some(idx) = self.pending.iter().position(|pending| *pending == ipld) else {
+            return false;
+        };
+
+        self.pending.remove(idx);
+        self.moderated.insert(ipld);
+
+        true
+    }
+}
+
+/// Moderated aggregation hub, for communities gathering content on a topic.
+///
+/// Content submitted on the hub channel aggregation topic waits in a moderation queue.
+/// Approved content is added to the hub index, published under the hub channel address.
+///
+/// Subscribers use `Defluencer::stream_hub` and `Defluencer::subscribe_hub`.
+pub struct Hub<T>
+where
+    T: IpnsUpdater + Clone,
+{
+    defluencer: Defluencer,
+    channel: Channel<T>,
+    topic: String,
+    aggregator: Aggregator,
+    queue: Mutex<HubQueue>,
+}
+
+impl<T> Hub<T>
+where
+    T: IpnsUpdater + Clone,
+{
+    /// Open the hub of this channel, it must have an aggregation topic.
+    ///
+    /// Submissions are filtered by the aggregator before being queued.
+    pub async fn open(
+        defluencer: Defluencer,
+        channel: Channel<T>,
+        aggregator: Aggregator,
+    ) -> Result<Self, Error> {
+        let (_, metadata) = channel.get_metadata().await?;
+
+        let topic = match metadata.agregation_channel {
+            Some(topic) => topic,
+            None => return Err(Error::NotFound),
+        };
+
+        Ok(Self {
+            defluencer,
+            channel,
+            topic,
+            aggregator,
+            queue: Default::default(),
+        })
+    }
+
+    /// Resume moderation with a queue saved earlier.
+    pub fn with_queue(mut self, queue: HubQueue) -> Self {
+        self.queue = Mutex::new(queue);
+        self
+    }
+
+    /// Returns the moderation queue, save it to resume after a restart.
+    pub fn queue(&self) -> HubQueue {
+        self.queue.lock().expect("Lock Poisoned").clone()
+    }
+
+    /// Topic where content is submitted to this hub.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub fn channel(&self) -> &Channel<T> {
+        &self.channel
+    }
+
+    /// Receive submissions and queue them for moderation.
+    ///
+    /// Content already queued or moderated is ignored.
+    ///
+    /// Returns the CID of each newly queued content.
+    pub fn submissions(&self) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        self.defluencer
+            .subscribe_aggregator(self.topic.clone(), &self.aggregator)
+            .try_filter_map(move |cid| async move {
+                let mut queue = self.queue.lock().expect("Lock Poisoned");
+
+                Ok(queue.push(cid).then_some(cid))
+            })
+    }
+
+    /// Content waiting for moderation, oldest first.
+    pub fn pending(&self) -> Vec<Cid> {
+        self.queue
+            .lock()
+            .expect("Lock Poisoned")
+            .pending
+            .iter()
+            .map(|ipld| ipld.link)
+            .collect()
+    }
+
+    /// Add queued content to the hub index.
+    ///
+    /// Content stays queued if it could not be added.
+    ///
+    /// Returns the new hub index CID.
+    pub async fn approve(&self, content_cid: Cid) -> Result<Cid, Error> {
+        if !self
+            .queue
+            .lock()
+            .expect("Lock Poisoned")
+            .contains(content_cid)
+        {
+            return Err(Error::NotFound);
+        }
+
+        let cid = self.channel.add_hub_content(content_cid).await?;
+
+        self.queue
+            .lock()
+            .expect("Lock Poisoned")
+            .moderate(content_cid);
+
+        Ok(cid)
+    }
+
+    /// Drop queued content, returns false if it was not queued.
+    pub fn reject(&self, content_cid: Cid) -> bool {
+        self.queue
+            .lock()
+            .expect("Lock Poisoned")
+            .moderate(content_cid)
+    }
+
+    /// Remove previously approved content from the hub index.
+    pub async fn remove(&self, content_cid: Cid) -> Result<Option<Cid>, Error> {
+        self.channel.remove_hub_content(content_cid).await
+    }
+
+    /// Submissions dropped before moderation, by reason.
+    pub fn stats(&self) -> AggregationStats {
+        self.aggregator.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    fn content(data: &[u8]) -> Cid {
+        Cid::new_v1(0x71, Code::Sha2_256.digest(data))
+    }
+
+    #[test]
+    fn queue_dedupe() {
+        let mut queue = HubQueue::default();
+
+        assert!(queue.push(content(b"a")));
+        assert!(queue.push(content(b"b")));
+        assert!(!queue.push(content(b"a")));
+
+        assert!(queue.moderate(content(b"a")));
+        assert!(!queue.moderate(content(b"a")));
+
+        // Moderated content is not queued again.
+        assert!(!queue.push(content(b"a")));
+        assert!(queue.contains(content(b"b")));
+
+        let json = serde_json::to_vec(&queue).unwrap();
+        let restored: HubQueue = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(restored, queue);
+    }
+}
@@ -0,0 +1,389 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+
+use serde::Deserialize;
+
+use crate::errors::Error;
+
+/// Media found in the archive of another platform.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportEntry {
+    pub title: String,
+
+    /// Time of the original publication in Unix time.
+    pub user_timestamp: i64,
+
+    pub summary: Option<String>,
+
+    /// Duration in seconds.
+    pub duration: Option<f64>,
+
+    /// File path or URL of the media.
+    pub media: String,
+
+    /// Media type, e.g. video/mp4 or audio/mpeg.
+    pub mime_type: Option<String>,
+
+    /// File path or URL of the thumbnail.
+    pub thumbnail: Option<String>,
+}
+
+impl ImportEntry {
+    pub fn is_audio(&self) -> bool {
+        self.mime_type
+            .as_deref()
+            .is_some_and(|mime| mime.starts_with("audio/"))
+    }
+}
+
+#[derive(Deserialize)]
+struct InfoJson {
+    title: String,
+
+    #[serde(default)]
+    description: Option<String>,
+
+    #[serde(default)]
+    timestamp: Option<i64>,
+
+    /// YYYYMMDD
+    #[serde(default)]
+    upload_date: Option<String>,
+
+    #[serde(default)]
+    duration: Option<f64>,
+
+    #[serde(default, rename = "_filename")]
+    filename: Option<String>,
+
+    #[serde(default)]
+    ext: Option<String>,
+
+    #[serde(default)]
+    vcodec: Option<String>,
+}
+
+/// Parse the metadata written by yt-dlp or youtube-dl with ```--write-info-json```.
+///
+/// Media and thumbnail paths are as written by the downloader.
+pub fn parse_info_json(json: &str) -> Option<ImportEntry> {
+    let info: InfoJson = serde_json::from_str(json).ok()?;
+
+    let user_timestamp = match (info.timestamp, info.upload_date.as_deref()) {
+        (Some(timestamp), _) => timestamp,
+        (None, Some(date)) => {
+            let date = NaiveDate::parse_from_str(date, "%Y%m%d").ok()?;
+
+            Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?)
+                .timestamp()
+        }
+        (None, None) => return None,
+    };
+
+    let media = info.filename?;
+
+    let mut mime_type = info
+        .ext
+        .as_deref()
+        .and_then(|ext| mime_guess::from_ext(ext).first_raw())
+        .map(str::to_owned);
+
+    // Audio only downloads keep video containers like webm.
+    if info.vcodec.as_deref() == Some("none") {
+        mime_type = mime_type.map(|mime| mime.replacen("video/", "audio/", 1));
+    }
+
+    let thumbnail = media
+        .rsplit_once('.')
+        .map(|(stem, _)| format!("{}.jpg", stem));
+
+    Some(ImportEntry {
+        title: info.title,
+        user_timestamp,
+        summary: non_empty(info.description),
+        duration: info.duration,
+        media,
+        mime_type,
+        thumbnail,
+    })
+}
+
+/// Parse the items of a podcast RSS feed that have an enclosure.
+///
+/// Items without an enclosure or a valid publication date are skipped.
+pub fn parse_rss(xml: &str) -> Vec<ImportEntry> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    let mut items = Vec::new();
+    let mut item: Option<RssItem> = None;
+
+    let mut itunes_cover = None;
+    let mut image_cover = None;
+    let mut in_image = false;
+
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(tag)) => {
+                match tag.name().as_ref() {
+                    b"item" => item = Some(RssItem::default()),
+                    b"image" if item.is_none() => in_image = true,
+                    _ => {}
+                }
+
+                read_attributes(&tag, item.as_mut(), &mut itunes_cover);
+
+                text.clear();
+            }
+            Ok(Event::Empty(tag)) => read_attributes(&tag, item.as_mut(), &mut itunes_cover),
+            Ok(Event::Text(content)) => {
+                if let Ok(content) = content.unescape() {
+                    text.push_str(&content);
+                }
+            }
+            Ok(Event::CData(content)) => text.push_str(&String::from_utf8_lossy(&content)),
+            Ok(Event::End(tag)) => {
+                let value = std::mem::take(&mut text);
+
+                let Some(current) = item.as_mut() else {
+                    match tag.name().as_ref() {
+                        b"url" if in_image => {
+                            image_cover.get_or_insert(value);
+                        }
+                        b"image" => in_image = false,
+                        _ => {}
+                    }
+
+                    continue;
+                };
+
+                let field = match tag.name().as_ref() {
+                    b"item" => {
+                        items.extend(item.take());
+                        continue;
+                    }
+                    b"title" => &mut current.title,
+                    b"pubDate" => &mut current.pub_date,
+                    b"description" => &mut current.description,
+                    b"itunes:summary" => &mut current.summary,
+                    b"itunes:duration" => &mut current.duration,
+                    _ => continue,
+                };
+
+                field.get_or_insert(value);
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    let cover = itunes_cover.or(image_cover);
+
+    items
+        .into_iter()
+        .filter_map(|item| item.into_entry(cover.as_ref()))
+        .collect()
+}
+
+/// Elements of a feed item, as found.
+#[derive(Default)]
+struct RssItem {
+    title: Option<String>,
+    pub_date: Option<String>,
+    description: Option<String>,
+    summary: Option<String>,
+    duration: Option<String>,
+    media: Option<String>,
+    mime_type: Option<String>,
+    image: Option<String>,
+}
+
+impl RssItem {
+    fn into_entry(self, cover: Option<&String>) -> Option<ImportEntry> {
+        let media = self.media?;
+
+        let user_timestamp = DateTime::parse_from_rfc2822(self.pub_date?.trim())
+            .ok()?
+            .timestamp();
+
+        let summary = self
+            .summary
+            .or(self.description)
+            .map(|text| strip_tags(&text));
+
+        Some(ImportEntry {
+            title: self.title.unwrap_or_default(),
+            user_timestamp,
+            summary: non_empty(summary),
+            duration: self.duration.and_then(|text| parse_duration(&text)),
+            media,
+            mime_type: self.mime_type,
+            thumbnail: self.image.or_else(|| cover.cloned()),
+        })
+    }
+}
+
+/// Enclosures and images are attributes, of the item or else of the feed.
+fn read_attributes(tag: &BytesStart, item: Option<&mut RssItem>, cover: &mut Option<String>) {
+    let attribute = |name: &str| {
+        tag.try_get_attribute(name)
+            .ok()
+            .flatten()
+            .and_then(|attribute| attribute.unescape_value().ok())
+            .map(|value| value.into_owned())
+    };
+
+    match (tag.name().as_ref(), item) {
+        (b"enclosure", Some(item)) if item.media.is_none() => {
+            item.media = attribute("url");
+            item.mime_type = attribute("type");
+        }
+        (b"itunes:image", Some(item)) if item.image.is_none() => item.image = attribute("href"),
+        (b"itunes:image", None) if cover.is_none() => *cover = attribute("href"),
+        _ => {}
+    }
+}
+
+/// Download a podcast RSS feed and parse its items.
+pub async fn fetch_rss(url: &str) -> Result<Vec<ImportEntry>, Error> {
+    let xml = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(ipfs_api::errors::Error::from)?
+        .text()
+        .await
+        .map_err(ipfs_api::errors::Error::from)?;
+
+    Ok(parse_rss(&xml))
+}
+
+/// Parse itunes durations, in seconds or as HH:MM:SS.
+fn parse_duration(text: &str) -> Option<f64> {
+    text.trim().split(':').try_fold(0.0, |total, part| {
+        Some(total * 60.0 + part.parse::<f64>().ok()?)
+    })
+}
+
+/// Remove the HTML markup of show notes.
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    text.trim().to_owned()
+}
+
+fn non_empty(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_owned())
+        .filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_json() {
+        let json = r#"{
+            "id": "dQw4w9WgXcQ",
+            "title": "Never Gonna Give You Up",
+            "description": "The official video.",
+            "upload_date": "20091025",
+            "duration": 212,
+            "ext": "mp4",
+            "vcodec": "avc1.640028",
+            "_filename": "archive/Never Gonna Give You Up [dQw4w9WgXcQ].mp4"
+        }"#;
+
+        let entry = parse_info_json(json).unwrap();
+
+        assert_eq!(entry.title, "Never Gonna Give You Up");
+        assert_eq!(entry.user_timestamp, 1256428800);
+        assert_eq!(entry.duration, Some(212.0));
+        assert_eq!(entry.mime_type.as_deref(), Some("video/mp4"));
+        assert_eq!(
+            entry.thumbnail.as_deref(),
+            Some("archive/Never Gonna Give You Up [dQw4w9WgXcQ].jpg")
+        );
+        assert!(!entry.is_audio());
+
+        let audio = r#"{
+            "title": "Episode",
+            "timestamp": 1700000000,
+            "ext": "webm",
+            "vcodec": "none",
+            "_filename": "Episode.webm"
+        }"#;
+
+        let entry = parse_info_json(audio).unwrap();
+
+        assert_eq!(entry.user_timestamp, 1700000000);
+        assert_eq!(entry.summary, None);
+        assert!(entry.is_audio());
+    }
+
+    #[test]
+    fn rss() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+<channel>
+<title>Show</title>
+<itunes:image href="https://example.com/cover.jpg"/>
+<item>
+<title>Episode 2 &amp; More</title>
+<itunes:title>Ignored</itunes:title>
+<pubDate>Tue, 14 Nov 2023 22:13:20 +0000</pubDate>
+<description><![CDATA[<p>Show notes</p>]]></description>
+<enclosure url="https://example.com/2.mp3" length="1000" type="audio/mpeg"/>
+<itunes:duration>1:02:03</itunes:duration>
+</item>
+<item>
+<title>No Enclosure</title>
+<pubDate>Mon, 13 Nov 2023 22:13:20 +0000</pubDate>
+</item>
+<item>
+<title>Episode 1</title>
+<pubDate>Sun, 12 Nov 2023 22:13:20 +0000</pubDate>
+<enclosure url='https://example.com/1.mp3' type='audio/mpeg'/>
+<itunes:image href="https://example.com/1.jpg"/>
+<itunes:duration>95</itunes:duration>
+</item>
+</channel>
+</rss>"#;
+
+        let entries = parse_rss(xml);
+
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].title, "Episode 2 & More");
+        assert_eq!(entries[0].user_timestamp, 1700000000);
+        assert_eq!(entries[0].summary.as_deref(), Some("Show notes"));
+        assert_eq!(entries[0].duration, Some(3723.0));
+        assert_eq!(entries[0].media, "https://example.com/2.mp3");
+        assert_eq!(
+            entries[0].thumbnail.as_deref(),
+            Some("https://example.com/cover.jpg")
+        );
+        assert!(entries[0].is_audio());
+
+        assert_eq!(entries[1].duration, Some(95.0));
+        assert_eq!(
+            entries[1].thumbnail.as_deref(),
+            Some("https://example.com/1.jpg")
+        );
+    }
+}
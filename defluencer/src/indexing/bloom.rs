@@ -0,0 +1,92 @@
+use cid::Cid;
+
+use linked_data::indexes::bloom::BloomFilter;
+
+use sha2::{Digest, Sha256};
+
+/// Counter positions of a key, derived from its multihash digest
+/// so that CID version and codec do not matter.
+fn positions(filter: &BloomFilter, key: Cid) -> impl Iterator<Item = usize> {
+    let bytes = Sha256::digest(key.hash().digest());
+
+    let h1 = u64::from_le_bytes(bytes[0..8].try_into().expect("8 bytes"));
+    let h2 = u64::from_le_bytes(bytes[8..16].try_into().expect("8 bytes")) | 1;
+
+    let len = filter.counters.len() as u64;
+
+    (0..filter.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+}
+
+pub(crate) fn insert(filter: &mut BloomFilter, key: Cid) {
+    if filter.counters.is_empty() {
+        return;
+    }
+
+    for i in positions(filter, key) {
+        filter.counters[i] = filter.counters[i].saturating_add(1);
+    }
+}
+
+/// Only remove keys that were inserted, others would cause false negatives.
+pub(crate) fn remove(filter: &mut BloomFilter, key: Cid) {
+    if filter.counters.is_empty() {
+        return;
+    }
+
+    for i in positions(filter, key) {
+        if filter.counters[i] != u8::MAX {
+            filter.counters[i] = filter.counters[i].saturating_sub(1);
+        }
+    }
+}
+
+/// Returns false if the key is absent, true if it may be present.
+pub(crate) fn contains(filter: &BloomFilter, key: Cid) -> bool {
+    if filter.counters.is_empty() {
+        return true;
+    }
+
+    positions(filter, key).all(|i| filter.counters[i] > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    fn key(i: u32) -> Cid {
+        Cid::new_v1(0x71, Code::Sha2_256.digest(&i.to_le_bytes()))
+    }
+
+    #[test]
+    fn counting_filter() {
+        let mut filter = BloomFilter::default();
+
+        for i in 0..100 {
+            insert(&mut filter, key(i));
+        }
+
+        for i in 0..100 {
+            assert!(contains(&filter, key(i)));
+        }
+
+        let false_positives = (100..10_100).filter(|i| contains(&filter, key(*i))).count();
+        assert!(false_positives < 100);
+
+        // Same digest, other codec
+        let raw = Cid::new_v1(0x55, *key(0).hash());
+        assert!(contains(&filter, raw));
+
+        insert(&mut filter, key(0));
+        remove(&mut filter, key(0));
+        assert!(contains(&filter, key(0)));
+
+        for i in 0..100 {
+            remove(&mut filter, key(i));
+        }
+
+        assert!(filter.counters.iter().all(|count| *count == 0));
+        assert!(!contains(&filter, key(0)));
+    }
+}
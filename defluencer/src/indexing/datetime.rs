@@ -2,7 +2,7 @@ use std::collections::HashSet;
 
 use crate::errors::Error;
 
-use chrono::{DateTime, Datelike, Timelike, Utc};
+use chrono::{DateTime, Datelike, LocalResult, TimeZone, Timelike, Utc};
 
 use cid::Cid;
 
@@ -234,6 +234,199 @@ pub(crate) async fn remove(
     Ok(result)
 }
 
+/// Walk the index, keeping only the values between these dates, inclusive.
+///
+/// Returns the CIDs of the index nodes visited and of the values in range.
+pub(crate) async fn range(
+    ipfs: &IpfsService,
+    index: IPLDLink,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<(Vec<Cid>, Vec<Cid>), Error> {
+    let from = date_key(from);
+    let to = date_key(to);
+
+    let mut nodes = vec![];
+    let mut values = vec![];
+
+    let mut stack = vec![(Vec::<i64>::new(), index.link)];
+
+    while let Some((prefix, cid)) = stack.pop() {
+        nodes.push(cid);
+
+        if prefix.len() == SECONDS_DEPTH {
+            let seconds = ipfs
+                .dag_get::<&str, Seconds>(cid, None, Codec::default())
+                .await?;
+
+            for (second, set) in seconds.second {
+                let mut key = prefix.clone();
+                key.push(second as i64);
+
+                if within(&from, &to, &key) {
+                    values.extend(set.into_iter().map(|ipld| ipld.link));
+                }
+            }
+
+            continue;
+        }
+
+        let children = children(ipfs, prefix.len(), cid).await?;
+
+        for (key, cid) in children {
+            let mut child = prefix.clone();
+            child.push(key);
+
+            if within(&from, &to, &child) {
+                stack.push((child, cid));
+            }
+        }
+    }
+
+    Ok((nodes, values))
+}
+
+/// Find where a value is in the index between these dates, inclusive, newest dates first.
+pub(crate) async fn find(
+    ipfs: &IpfsService,
+    index: IPLDLink,
+    value: Cid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, Error> {
+    let value: IPLDLink = value.into();
+
+    let from = date_key(from);
+    let to = date_key(to);
+
+    let mut stack = vec![(Vec::<i64>::new(), index.link)];
+
+    while let Some((prefix, cid)) = stack.pop() {
+        if prefix.len() < SECONDS_DEPTH {
+            for (key, cid) in children(ipfs, prefix.len(), cid).await? {
+                let mut child = prefix.clone();
+                child.push(key);
+
+                if within(&from, &to, &child) {
+                    stack.push((child, cid));
+                }
+            }
+
+            continue;
+        }
+
+        let seconds = ipfs
+            .dag_get::<&str, Seconds>(cid, None, Codec::default())
+            .await?;
+
+        let second = seconds
+            .second
+            .into_iter()
+            .rev()
+            .filter(|(second, _)| {
+                let mut key = prefix.clone();
+                key.push(*second as i64);
+
+                within(&from, &to, &key)
+            })
+            .find_map(|(second, set)| set.contains(&value).then_some(second));
+
+        if let Some(second) = second {
+            let date_time = Utc
+                .with_ymd_and_hms(
+                    prefix[0] as i32,
+                    prefix[1] as u32,
+                    prefix[2] as u32,
+                    prefix[3] as u32,
+                    prefix[4] as u32,
+                    second,
+                )
+                .single();
+
+            return Ok(date_time);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the date of this Unix time if the index can store it.
+pub(crate) fn date_time(timestamp: i64) -> Result<DateTime<Utc>, Error> {
+    match Utc.timestamp_opt(timestamp, 0) {
+        LocalResult::Single(date_time) if (0..=MAX_YEAR).contains(&date_time.year()) => {
+            Ok(date_time)
+        }
+        _ => Err(Error::Timestamp),
+    }
+}
+
+/// Depth of the seconds nodes, under years, months, days, hours and minutes.
+const SECONDS_DEPTH: usize = 5;
+
+const MAX_YEAR: i32 = 9999;
+
+/// Keys and links of the children of a node above the seconds.
+async fn children(ipfs: &IpfsService, depth: usize, cid: Cid) -> Result<Vec<(i64, Cid)>, Error> {
+    let children = match depth {
+        0 => ipfs
+            .dag_get::<&str, Yearly>(cid, None, Codec::default())
+            .await?
+            .year
+            .into_iter()
+            .map(|(key, ipld)| (key as i64, ipld.link))
+            .collect(),
+        1 => ipfs
+            .dag_get::<&str, Monthly>(cid, None, Codec::default())
+            .await?
+            .month
+            .into_iter()
+            .map(|(key, ipld)| (key as i64, ipld.link))
+            .collect(),
+        2 => ipfs
+            .dag_get::<&str, Daily>(cid, None, Codec::default())
+            .await?
+            .day
+            .into_iter()
+            .map(|(key, ipld)| (key as i64, ipld.link))
+            .collect(),
+        3 => ipfs
+            .dag_get::<&str, Hourly>(cid, None, Codec::default())
+            .await?
+            .hour
+            .into_iter()
+            .map(|(key, ipld)| (key as i64, ipld.link))
+            .collect(),
+        _ => ipfs
+            .dag_get::<&str, Minutes>(cid, None, Codec::default())
+            .await?
+            .minute
+            .into_iter()
+            .map(|(key, ipld)| (key as i64, ipld.link))
+            .collect(),
+    };
+
+    Ok(children)
+}
+
+/// Returns the date as year, month, day, hour, minute and second.
+fn date_key(date_time: DateTime<Utc>) -> [i64; 6] {
+    [
+        date_time.year() as i64,
+        date_time.month() as i64,
+        date_time.day() as i64,
+        date_time.hour() as i64,
+        date_time.minute() as i64,
+        date_time.second() as i64,
+    ]
+}
+
+/// Returns true if some dates starting with this key prefix are in range.
+fn within(from: &[i64; 6], to: &[i64; 6], prefix: &[i64]) -> bool {
+    let len = prefix.len().min(6);
+
+    from[..len] <= prefix[..len] && prefix[..len] <= to[..len]
+}
+
 #[cfg(test)]
 mod tests {
     #![cfg(not(target_arch = "wasm32"))]
@@ -246,7 +439,7 @@ mod tests {
 
     use futures::StreamExt;
 
-    use ipfs_api::IpfsService;
+    use ipfs_api::{mock::MockIpfs, IpfsService};
 
     use multihash::MultihashGeneric;
     type Multihash = MultihashGeneric<64>;
@@ -266,6 +459,19 @@ mod tests {
         Cid::new_v1(0x71, multihash)
     }
 
+    #[test]
+    fn out_of_range_timestamps() {
+        assert_eq!(
+            date_time(0).unwrap(),
+            Utc.with_ymd_and_hms(1970, 1, 1, 0, 0, 0).unwrap()
+        );
+
+        // First second of year 10000
+        assert!(date_time(253_402_300_800).is_err());
+        assert!(date_time(i64::MAX).is_err());
+        assert!(date_time(i64::MIN).is_err());
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
     #[ignore]
     async fn empty_index_get_remove() {
@@ -290,9 +496,8 @@ mod tests {
     }
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
-    #[ignore]
     async fn index_duplicate_insert() {
-        let ipfs = IpfsService::default();
+        let ipfs = MockIpfs::new().service();
 
         let mut index = None;
 
@@ -315,6 +520,38 @@ mod tests {
         assert!(!result);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn bounded_find() {
+        let ipfs = MockIpfs::new().service();
+
+        let mut index = None;
+
+        let mut rng = Xoshiro256StarStar::seed_from_u64(2347867832489023);
+
+        let date_time = Utc.with_ymd_and_hms(2020, 7, 8, 9, 10, 11).unwrap();
+
+        let cid = random_cid(&mut rng);
+
+        insert(&ipfs, date_time, &mut index, cid).await.unwrap();
+
+        let index = index.unwrap();
+
+        let found = find(&ipfs, index, cid, date_time, date_time).await.unwrap();
+        assert_eq!(found, Some(date_time));
+
+        let found = find(&ipfs, index, cid, DateTime::<Utc>::MIN_UTC, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(found, Some(date_time));
+
+        let before = date_time - Duration::seconds(1);
+
+        let found = find(&ipfs, index, cid, DateTime::<Utc>::MIN_UTC, before)
+            .await
+            .unwrap();
+        assert_eq!(found, None);
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
     #[ignore]
     async fn index_sequential_insert() {
@@ -441,4 +678,20 @@ mod tests {
 
         println!("Final Count {}\nRoot {}", sum, index.unwrap().link);
     }
+
+    #[test]
+    fn range_prefix() {
+        let from = date_key(Utc.with_ymd_and_hms(2022, 11, 15, 12, 0, 0).unwrap());
+        let to = date_key(Utc.with_ymd_and_hms(2023, 2, 1, 0, 0, 0).unwrap());
+
+        assert!(within(&from, &to, &[2022]));
+        assert!(within(&from, &to, &[2023, 1]));
+        assert!(!within(&from, &to, &[2021]));
+        assert!(!within(&from, &to, &[2022, 10]));
+        assert!(!within(&from, &to, &[2023, 3]));
+        assert!(within(&from, &to, &[2022, 11, 15]));
+        assert!(!within(&from, &to, &[2022, 11, 15, 11]));
+        assert!(within(&from, &to, &[2023, 2, 1, 0, 0, 0]));
+        assert!(!within(&from, &to, &[2023, 2, 1, 0, 0, 1]));
+    }
 }
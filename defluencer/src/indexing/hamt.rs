@@ -9,7 +9,6 @@ use ipfs_api::{responses::Codec, IpfsService};
 use linked_data::{
     indexes::hamt::{
         BitField, BucketEntry, Element, HAMTNode, HAMTRoot, BUCKET_SIZE, DIGEST_LENGTH_BYTES,
-        HASH_ALGORITHM,
     },
     types::IPLDLink,
 };
@@ -32,6 +31,19 @@ pub(crate) async fn get(
     root: IPLDLink,
     key: Cid,
 ) -> Result<Option<Cid>, Error> {
+    let (_, value) = path(ipfs, root, key).await?;
+
+    Ok(value)
+}
+
+/// Returns the CIDs of the nodes leading to this key, root first, and its value if any.
+pub(crate) async fn path(
+    ipfs: &IpfsService,
+    root: IPLDLink,
+    key: Cid,
+) -> Result<(Vec<Cid>, Option<Cid>), Error> {
+    let mut nodes = vec![root.link];
+
     let hash: MultihashGeneric<DIGEST_LENGTH_BYTES> = key.hash().resize()?;
     let (_, digest, _) = hash.into_inner();
 
@@ -49,7 +61,7 @@ pub(crate) async fn get(
 
         if !map[index] {
             // CASE: index bit is not set
-            return Ok(None);
+            return Ok((nodes, None));
         }
 
         // CASE: index bit is set
@@ -62,6 +74,7 @@ pub(crate) async fn get(
                 node = ipfs
                     .dag_get::<&str, HAMTNode>(ipld.link, None, Codec::default())
                     .await?;
+                nodes.push(ipld.link);
                 depth += 1;
 
                 continue;
@@ -70,12 +83,12 @@ pub(crate) async fn get(
                 let entry = BucketEntry {
                     key: digest,
                     value: Default::default(),
+                    hash: None,
                 };
 
-                match btree.get(&entry) {
-                    Some(entry) => return Ok(Some(entry.value.link)),
-                    None => return Ok(None),
-                }
+                let value = btree.get(&entry).map(|entry| entry.value.link);
+
+                return Ok((nodes, value));
             }
         }
     }
@@ -88,13 +101,19 @@ pub(crate) async fn insert(
     value: Cid,
 ) -> Result<(), Error> {
     let hash: MultihashGeneric<DIGEST_LENGTH_BYTES> = key.hash().resize()?;
-    let (_, digest, _) = hash.into_inner();
+    let (code, digest, _) = hash.into_inner();
 
     let mut root = ipfs
         .dag_get::<&str, HAMTRoot>(index.link, None, Codec::default())
         .await?;
 
-    set(ipfs, digest, value.into(), 0, &mut root.hamt).await?;
+    let entry = BucketEntry {
+        key: digest,
+        value: value.into(),
+        hash: (code != root.hash_algorithm as u64).then_some(code),
+    };
+
+    set(ipfs, entry, 0, &mut root.hamt).await?;
 
     let cid = ipfs
         .dag_put(&root, Codec::default(), Codec::default())
@@ -108,19 +127,17 @@ pub(crate) async fn insert(
 #[async_recursion(?Send)]
 async fn set(
     ipfs: &IpfsService,
-    key: [u8; DIGEST_LENGTH_BYTES],
-    value: IPLDLink,
+    entry: BucketEntry,
     depth: usize,
     node: &mut HAMTNode,
 ) -> Result<Cid, Error> {
-    let index = key[depth] as usize;
+    let index = entry.key[depth] as usize;
     let mut map = BitField::from(node.map);
     let data_index = map[0..index].count_ones();
 
     if !map[index] {
         // CASE: index bit is not set
 
-        let entry = BucketEntry { key, value };
         let bucket = Element::Bucket(BTreeSet::from([entry]));
 
         node.data.insert(data_index, bucket);
@@ -147,7 +164,7 @@ async fn set(
                 .dag_get::<&str, HAMTNode>(ipld.link, None, Codec::default())
                 .await?;
 
-            let cid = set(ipfs, key, value, depth + 1, &mut new_node).await?;
+            let cid = set(ipfs, entry, depth + 1, &mut new_node).await?;
 
             *ipld = cid.into();
 
@@ -159,8 +176,6 @@ async fn set(
         }
         Element::Bucket(btree) => {
             if btree.len() < BUCKET_SIZE {
-                let entry = BucketEntry { key, value };
-
                 btree.insert(entry);
 
                 let cid = ipfs
@@ -173,10 +188,10 @@ async fn set(
             let mut new_node = HAMTNode::default();
 
             for item in btree.iter() {
-                set(ipfs, item.key, item.value, depth + 1, &mut new_node).await?;
+                set(ipfs, *item, depth + 1, &mut new_node).await?;
             }
 
-            let cid = set(ipfs, key, value, depth + 1, &mut new_node).await?;
+            let cid = set(ipfs, entry, depth + 1, &mut new_node).await?;
 
             node.data[data_index] = Element::Link(cid.into());
 
@@ -285,6 +300,7 @@ async fn delete(
                 let entry = BucketEntry {
                     key,
                     value: Default::default(),
+                    hash: None,
                 };
 
                 if btree.remove(&entry) {
@@ -326,6 +342,7 @@ async fn delete(
     let entry = BucketEntry {
         key,
         value: Default::default(),
+        hash: None,
     };
 
     if btree.remove(&entry) {
@@ -349,7 +366,7 @@ pub(crate) fn values(
             .dag_get::<&str, HAMTRoot>(ipld.link, None, Codec::default())
             .await?;
 
-        let stream = stream_data(ipfs, root_node.hamt);
+        let stream = stream_data(ipfs, root_node.hash_algorithm as u64, root_node.hamt);
 
         Ok(Some((stream, root)))
     })
@@ -358,6 +375,7 @@ pub(crate) fn values(
 
 fn stream_data(
     ipfs: &IpfsService,
+    hash_algorithm: u64,
     node: HAMTNode,
 ) -> impl Stream<Item = Result<(Cid, Cid), Error>> + '_ {
     stream::try_unfold(node.data.into_iter(), move |mut iter| async move {
@@ -372,14 +390,14 @@ fn stream_data(
                     .dag_get::<&str, HAMTNode>(ipld.link, None, Codec::default())
                     .await?;
 
-                let stream = stream_data(ipfs, node).boxed_local();
+                let stream = stream_data(ipfs, hash_algorithm, node).boxed_local();
 
                 Ok(Some((stream, iter)))
             }
             Element::Bucket(vec) => {
                 let stream = stream::iter(vec.into_iter().map(|entry| {
-                    let hash = Multihash::wrap(HASH_ALGORITHM as u64, &entry.key)
-                        .expect("Valid Multihash");
+                    let code = entry.hash.unwrap_or(hash_algorithm);
+                    let hash = Multihash::wrap(code, &entry.key).expect("Valid Multihash");
                     let key = Cid::new_v1(/* DAG-CBOR */ 0x71, hash);
 
                     let value = entry.value.link;
@@ -401,7 +419,7 @@ mod tests {
 
     use super::*;
 
-    use ipfs_api::IpfsService;
+    use ipfs_api::{mock::MockIpfs, IpfsService};
 
     use rand::Rng;
 
@@ -418,15 +436,36 @@ mod tests {
         Cid::new_v1(/* DAG-CBOR */ 0x71, multihash)
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
-    #[ignore]
+    async fn empty_root(ipfs: &IpfsService) -> IPLDLink {
+        ipfs.dag_put(&HAMTRoot::default(), Codec::default(), Codec::default())
+            .await
+            .unwrap()
+            .into()
+    }
+
+    /// Same as hamt_sequential_insert.
+    async fn sequential_root(ipfs: &IpfsService) -> IPLDLink {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(2347867832489023);
+
+        let mut root = empty_root(ipfs).await;
+
+        let value =
+            Cid::try_from("bafyreih62zarvnosx5aktyzkhk6ufn5b33eqmm5te5ozor25r3rfigznje").unwrap();
+
+        for _ in 0..256 {
+            insert(ipfs, &mut root, random_cid(&mut rng), value)
+                .await
+                .unwrap();
+        }
+
+        root
+    }
+
+    #[tokio::test]
     async fn empty_hamt_get_remove() {
-        let ipfs = IpfsService::default();
+        let ipfs = MockIpfs::new().service();
 
-        // Pre-generated with ipfs.dag_put(&HAMTRoot::default(), Codec::default(), Codec::default()).await;
-        let mut root = Cid::try_from("bafyreif5btv4rgnd443jetidp5iotdh6fdtndhm7c7qtvw32bujcbyk7re")
-            .unwrap()
-            .into();
+        let mut root = empty_root(&ipfs).await;
 
         // Random key
         let key =
@@ -442,15 +481,11 @@ mod tests {
         assert!(result.unwrap().is_none());
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
-    #[ignore]
+    #[tokio::test]
     async fn hamt_duplicate_insert() {
-        let ipfs = IpfsService::default();
+        let ipfs = MockIpfs::new().service();
 
-        // Pre-generated with ipfs.dag_put(&HAMTRoot::default(), Codec::default(), Codec::default()).await;
-        let mut root = Cid::try_from("bafyreif5btv4rgnd443jetidp5iotdh6fdtndhm7c7qtvw32bujcbyk7re")
-            .unwrap()
-            .into();
+        let mut root = empty_root(&ipfs).await;
 
         // Random key
         let key =
@@ -482,17 +517,13 @@ mod tests {
         println!("Root {}", root.link);
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
-    #[ignore]
+    #[tokio::test]
     async fn hamt_sequential_insert() {
-        let ipfs = IpfsService::default();
+        let ipfs = MockIpfs::new().service();
 
         let mut rng = Xoshiro256StarStar::seed_from_u64(2347867832489023);
 
-        // Pre-generated with ipfs.dag_put(&HAMTRoot::default(), Codec::default(), Codec::default()).await;
-        let mut root = Cid::try_from("bafyreif5btv4rgnd443jetidp5iotdh6fdtndhm7c7qtvw32bujcbyk7re")
-            .unwrap()
-            .into();
+        let mut root = empty_root(&ipfs).await;
 
         let value =
             Cid::try_from("bafyreih62zarvnosx5aktyzkhk6ufn5b33eqmm5te5ozor25r3rfigznje").unwrap();
@@ -516,15 +547,36 @@ mod tests {
         println!("Root {}", root.link);
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
-    #[ignore]
+    #[tokio::test]
+    async fn hamt_path() {
+        let ipfs = MockIpfs::new().service();
+
+        let mut rng = Xoshiro256StarStar::seed_from_u64(2347867832489023);
+
+        let root = sequential_root(&ipfs).await;
+
+        let value =
+            Cid::try_from("bafyreih62zarvnosx5aktyzkhk6ufn5b33eqmm5te5ozor25r3rfigznje").unwrap();
+
+        let (nodes, result) = path(&ipfs, root, random_cid(&mut rng)).await.unwrap();
+
+        assert_eq!(nodes[0], root.link);
+        assert_eq!(result, Some(value));
+
+        let key =
+            Cid::try_from("bafyreiebxcyrgbybcebsk7dwlkidiyi7y6shpvsmneufdouto3pgumvefe").unwrap();
+
+        let (nodes, result) = path(&ipfs, root, key).await.unwrap();
+
+        assert_eq!(nodes[0], root.link);
+        assert!(result.is_none());
+    }
+
+    #[tokio::test]
     async fn hamt_remove_collapse() {
-        let ipfs = IpfsService::default();
+        let ipfs = MockIpfs::new().service();
 
-        // Pre-generated with hamt_sequential_insert;
-        let mut root = Cid::try_from("bafyreibk3jg65ukzj5i3lolkmm6cl6yzz7mzrqesrja4msro7lfo3s6exy")
-            .unwrap()
-            .into();
+        let mut root = sequential_root(&ipfs).await;
 
         let key =
             Cid::try_from("bafyreiarw4llrjyv6ctuhyupx65tzbgr37kkiyjwyxj6blnmekpfx32ysu").unwrap();
@@ -546,17 +598,13 @@ mod tests {
         }
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
-    #[ignore]
+    #[tokio::test]
     async fn hamt_sequential_remove() {
-        let ipfs = IpfsService::default();
+        let ipfs = MockIpfs::new().service();
 
         let mut rng = Xoshiro256StarStar::seed_from_u64(2347867832489023);
 
-        // Pre-generated with hamt_sequential_insert;
-        let mut root = Cid::try_from("bafyreibk3jg65ukzj5i3lolkmm6cl6yzz7mzrqesrja4msro7lfo3s6exy")
-            .unwrap()
-            .into();
+        let mut root = sequential_root(&ipfs).await;
 
         for _ in 0..256 {
             let key = random_cid(&mut rng);
@@ -574,17 +622,13 @@ mod tests {
         assert_eq!(0, sum);
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
-    #[ignore]
+    #[tokio::test]
     async fn hamt_fuzzy() {
-        let ipfs = IpfsService::default();
+        let ipfs = MockIpfs::new().service();
 
         let mut rng = Xoshiro256StarStar::seed_from_u64(2347867832489023);
 
-        // Pre-generated with ipfs.dag_put(&HAMTRoot::default(), Codec::default(), Codec::default()).await;
-        let mut root = Cid::try_from("bafyreif5btv4rgnd443jetidp5iotdh6fdtndhm7c7qtvw32bujcbyk7re")
-            .unwrap()
-            .into();
+        let mut root = empty_root(&ipfs).await;
 
         let value =
             Cid::try_from("bafyreih62zarvnosx5aktyzkhk6ufn5b33eqmm5te5ozor25r3rfigznje").unwrap();
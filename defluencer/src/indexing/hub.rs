@@ -0,0 +1,51 @@
+use cid::Cid;
+
+/// Return the hub tree key for content approved at this time.
+///
+/// Keys are ordered by approval time first then by content CID.
+pub fn approval_key(timestamp: i64, content_cid: Cid) -> Vec<u8> {
+    let mut key = timestamp_prefix(timestamp);
+    key.extend(content_cid.to_bytes());
+
+    key
+}
+
+/// Return the prefix shared by all the keys of content approved at this time.
+///
+/// Any key approved at or after this time is greater than the prefix.
+pub fn timestamp_prefix(timestamp: i64) -> Vec<u8> {
+    (timestamp.max(0) as u64).to_be_bytes().to_vec()
+}
+
+/// Split a hub tree key into approval timestamp and content CID.
+pub fn split_approval_key(key: &[u8]) -> Option<(i64, Cid)> {
+    if key.len() < 8 {
+        return None;
+    }
+
+    let (timestamp, cid) = key.split_at(8);
+
+    let timestamp = u64::from_be_bytes(timestamp.try_into().ok()?) as i64;
+    let cid = Cid::try_from(cid).ok()?;
+
+    Some((timestamp, cid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    #[test]
+    fn key_roundtrip() {
+        let cid = Cid::new_v1(0x71, Code::Sha2_256.digest(b"media"));
+
+        let key = approval_key(1_700_000_000, cid);
+
+        assert!(key > timestamp_prefix(1_700_000_000));
+        assert!(key < timestamp_prefix(1_700_000_001));
+
+        assert_eq!(split_approval_key(&key), Some((1_700_000_000, cid)));
+    }
+}
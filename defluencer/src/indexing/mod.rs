@@ -1,4 +1,13 @@
+pub mod bloom;
 pub mod datetime;
 pub mod hamt;
+#[cfg(feature = "ordered-trees")]
+pub mod hub;
 //pub mod log;
+#[cfg(feature = "ordered-trees")]
 pub mod ordered_trees;
+#[cfg(feature = "ordered-trees")]
+pub mod registry;
+#[cfg(feature = "ordered-trees")]
+pub mod slug;
+pub mod tags;
@@ -0,0 +1,63 @@
+use std::ops::Bound;
+
+/// Position in an ordered tree, recorded by key instead of node path.
+///
+/// Reads resumed with a cursor continue after the last key returned,
+/// even if the tree root changed in the mean time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor<K> {
+    last: Option<K>,
+}
+
+impl<K> Default for Cursor<K> {
+    fn default() -> Self {
+        Self { last: None }
+    }
+}
+
+impl<K: Clone> Cursor<K> {
+    /// Resume after this key.
+    pub fn after(key: K) -> Self {
+        Self { last: Some(key) }
+    }
+
+    /// The last key returned, if any.
+    pub fn last_key(&self) -> Option<&K> {
+        self.last.as_ref()
+    }
+
+    /// Record a key as returned.
+    pub fn advance(&mut self, key: &K) {
+        self.last = Some(key.clone());
+    }
+
+    /// Range of the keys not yet returned.
+    pub fn remaining(&self) -> (Bound<K>, Bound<K>) {
+        match self.last.as_ref() {
+            Some(key) => (Bound::Excluded(key.clone()), Bound::Unbounded),
+            None => (Bound::Unbounded, Bound::Unbounded),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::ops::RangeBounds;
+
+    #[test]
+    fn cursor_remaining() {
+        let mut cursor = Cursor::default();
+
+        assert!(cursor.remaining().contains(&0u8));
+
+        cursor.advance(&5u8);
+
+        assert_eq!(cursor.last_key(), Some(&5));
+        assert!(!cursor.remaining().contains(&5));
+        assert!(cursor.remaining().contains(&6));
+
+        assert_eq!(Cursor::after(5u8), cursor);
+    }
+}
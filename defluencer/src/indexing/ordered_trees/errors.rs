@@ -16,6 +16,21 @@ pub enum Error {
     #[error("Unknown Codec")]
     UnknownCodec,
 
+    #[error("Unknown Hash Function {0}")]
+    UnknownHashFunction(u64),
+
+    #[error("Unknown Node Version {0}, newer software or forced decoding required")]
+    UnknownNodeVersion(u64),
+
+    #[error("Unknown Node Layout")]
+    UnknownNodeLayout,
+
+    #[error("Malformed Node, keys and values count must match")]
+    MalformedNode,
+
+    #[error("Invalid Proof")]
+    InvalidProof,
+
     #[error("Ipld Error: {0}")]
     IpldError(#[from] libipld_core::error::Error),
 
@@ -1,4 +1,7 @@
-mod errors;
+pub mod cursor;
+pub mod errors;
 //pub mod merkle_search; Disabled until fixed
 pub mod prolly;
+mod range;
+pub mod staging;
 mod traits;
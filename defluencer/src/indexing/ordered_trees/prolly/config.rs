@@ -30,23 +30,76 @@ impl Default for Strategies {
     }
 }
 
+/// Hash functions used to find chunk boundaries, identified by their multicodec.
+///
+/// Non-cryptographic hashes are faster but a malicious writer could shape the tree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BoundaryHash {
+    #[default]
+    Sha2_256,
+    Blake3,
+    Xxh3_64,
+}
+
+impl BoundaryHash {
+    pub fn digest(&self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha2_256 => Code::Sha2_256.digest(bytes).digest().to_vec(),
+            Self::Blake3 => blake3::hash(bytes).as_bytes().to_vec(),
+            Self::Xxh3_64 => xxhash_rust::xxh3::xxh3_64(bytes).to_be_bytes().to_vec(),
+        }
+    }
+}
+
+impl From<BoundaryHash> for u64 {
+    fn from(hash: BoundaryHash) -> Self {
+        match hash {
+            BoundaryHash::Sha2_256 => 0x12,
+            BoundaryHash::Blake3 => 0x1e,
+            BoundaryHash::Xxh3_64 => 0xb3e3,
+        }
+    }
+}
+
+impl TryFrom<u64> for BoundaryHash {
+    type Error = Error;
+
+    fn try_from(code: u64) -> Result<Self, Self::Error> {
+        match code {
+            0x12 => Ok(Self::Sha2_256),
+            0x1e => Ok(Self::Blake3),
+            0xb3e3 => Ok(Self::Xxh3_64),
+            code => Err(Error::UnknownHashFunction(code)),
+        }
+    }
+}
+
 /// Chunking strategy that count 0 bits in the last 4 bytes of a key's hash then
 /// compare it with the chunking factor.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HashThreshold {
     pub chunking_factor: usize,
-    pub multihash_code: Code,
+    pub hash_function: BoundaryHash,
 }
 
 impl Default for HashThreshold {
     fn default() -> Self {
         Self {
             chunking_factor: 1 << 26,
-            multihash_code: Code::Sha2_256,
+            hash_function: BoundaryHash::default(),
         }
     }
 }
 
+impl HashThreshold {
+    /// Returns true if these encoded bytes end a chunk.
+    pub fn is_boundary(&self, bytes: &[u8]) -> bool {
+        let hash = self.hash_function.digest(bytes);
+
+        chunking(self.chunking_factor as u32, &hash)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(try_from = "Ipld", into = "Ipld")]
 pub struct Config {
@@ -104,11 +157,7 @@ impl Config {
 
                 bytes.append(&mut value_bytes);
 
-                let hash = threshold.multihash_code.digest(&bytes);
-
-                let bound = chunking(threshold.chunking_factor as u32, hash.digest());
-
-                Ok(bound)
+                Ok(threshold.is_boundary(&bytes))
             }
         }
     }
@@ -169,4 +218,26 @@ mod tests {
             assert_eq!(bound, expected_bound);
         }
     }
+
+    #[test]
+    fn boundary_hash_roundtrip() {
+        for hash_function in [
+            BoundaryHash::Sha2_256,
+            BoundaryHash::Blake3,
+            BoundaryHash::Xxh3_64,
+        ] {
+            let mut config = Config::default();
+            config.chunking_strategy = Strategies::Threshold(HashThreshold {
+                chunking_factor: 1 << 10,
+                hash_function,
+            });
+
+            let ipld: Ipld = config.clone().into();
+            let decoded = Config::try_from(ipld).unwrap();
+
+            assert_eq!(config, decoded);
+        }
+
+        assert!(BoundaryHash::try_from(0x13).is_err());
+    }
 }
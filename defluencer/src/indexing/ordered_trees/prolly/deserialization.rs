@@ -4,6 +4,8 @@ use std::{
     str::FromStr,
 };
 
+use cid::Cid;
+
 use ipfs_api::responses::Codec;
 
 use multihash::Code;
@@ -13,7 +15,7 @@ use serde::{Deserialize, Serialize};
 use serde_ipld_dagcbor::DecodeError;
 
 use super::{
-    config::{Config, HashThreshold, Strategies, Tree},
+    config::{BoundaryHash, Config, HashThreshold, Strategies, Tree},
     node::{Branch, Leaf, TreeNode},
 };
 
@@ -26,6 +28,11 @@ use crate::indexing::ordered_trees::{
     traits::{Key, Value},
 };
 
+/// Version of the tree node encoding.
+///
+/// Nodes without version predate versioning and are treated as version 0.
+pub const NODE_VERSION: u64 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(bound = "K: Key, V: Value", try_from = "Ipld", into = "Ipld")]
 pub enum TreeNodes<K, V> {
@@ -35,6 +42,8 @@ pub enum TreeNodes<K, V> {
 
 impl<K: Key, V: Value> From<TreeNodes<K, V>> for Ipld {
     fn from(node: TreeNodes<K, V>) -> Self {
+        let version = Ipld::Integer(NODE_VERSION as i128);
+
         match node {
             TreeNodes::Branch(branch) => {
                 let keys = branch
@@ -52,7 +61,7 @@ impl<K: Key, V: Value> From<TreeNodes<K, V>> for Ipld {
                     .collect::<Vec<_>>();
                 let links = Ipld::List(links);
 
-                Ipld::List(vec![Ipld::Bool(false), keys, links])
+                Ipld::List(vec![version, Ipld::Bool(false), keys, links])
             }
             TreeNodes::Leaf(leaf) => {
                 let keys = leaf
@@ -70,7 +79,7 @@ impl<K: Key, V: Value> From<TreeNodes<K, V>> for Ipld {
                     .collect::<Vec<_>>();
                 let values = Ipld::List(values);
 
-                Ipld::List(vec![Ipld::Bool(true), keys, values])
+                Ipld::List(vec![version, Ipld::Bool(true), keys, values])
             }
         }
     }
@@ -80,71 +89,90 @@ impl<K: Key, V: Value> TryFrom<Ipld> for TreeNodes<K, V> {
     type Error = Error;
 
     fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
-        let mut list: Vec<Ipld> = ipld.try_into()?;
-
-        if list.len() != 3 {
-            return Err(DecodeError::RequireLength {
-                name: "tuple",
-                expect: 3,
-                value: list.len(),
-            }
-            .into());
-        };
+        decode_node(ipld, false)
+    }
+}
 
-        let values: Vec<Ipld> = list.pop().unwrap().try_into()?;
-        let keys: Vec<Ipld> = list.pop().unwrap().try_into()?;
-        let leaf: bool = list.pop().unwrap().try_into()?;
+impl<K: Key, V: Value> TreeNodes<K, V> {
+    /// Decode a node ignoring unknown versions, extra fields and undecodable key value pairs.
+    ///
+    /// Only meant for recovery tooling, data WILL be lost.
+    pub fn try_from_forced(ipld: Ipld) -> Result<Self, Error> {
+        decode_node(ipld, true)
+    }
+}
 
-        let keys = {
-            //TODO Find how to fix trait bounds
-            /* let result: Result<Vec<K>, _> = keys.into_iter().map(|ipld| ipld.try_into()).collect();
+fn decode_node<K: Key, V: Value>(ipld: Ipld, force: bool) -> Result<TreeNodes<K, V>, Error> {
+    let mut list: Vec<Ipld> = ipld.try_into()?;
 
-            result? */
+    if let Some(Ipld::Bool(_)) = list.first() {
+        list.insert(0, Ipld::Integer(0));
+    }
 
-            let mut new_keys = VecDeque::with_capacity(keys.len());
-            for ipld in keys {
-                let Ok(key) = ipld.try_into() else {
-                    return Err(Error::UnknownKeyType);
-                };
+    if list.len() < 4 || (list.len() > 4 && !force) {
+        return Err(Error::UnknownNodeLayout);
+    };
 
-                new_keys.push_back(key)
-            }
+    list.truncate(4);
 
-            new_keys
-        };
+    let values: Vec<Ipld> = list.pop().unwrap().try_into()?;
+    let keys: Vec<Ipld> = list.pop().unwrap().try_into()?;
+    let leaf: bool = list.pop().unwrap().try_into()?;
+    let version: u64 = list.pop().unwrap().try_into()?;
 
-        let tree = if leaf {
-            //TODO Find how to fix trait bounds
-            /* let result: Result<Vec<V>, _> =
-                values.into_iter().map(|ipld| ipld.try_into()).collect();
+    if version > NODE_VERSION && !force {
+        return Err(Error::UnknownNodeVersion(version));
+    }
 
-            let elements = result?; */
+    if keys.len() != values.len() && !force {
+        return Err(Error::MalformedNode);
+    }
 
-            let mut elements = Vec::with_capacity(values.len());
-            for ipld in values {
-                let Ok(value) = ipld.try_into() else {
-                    return Err(Error::UnknownValueType);
-                };
+    let tree = if leaf {
+        let mut node = TreeNode {
+            keys: VecDeque::with_capacity(keys.len()),
+            values: Leaf {
+                elements: Vec::with_capacity(values.len()),
+            },
+        };
 
-                elements.push(value);
+        for (key, value) in keys.into_iter().zip(values) {
+            match (K::try_from(key), V::try_from(value)) {
+                (Ok(key), Ok(value)) => {
+                    node.keys.push_back(key);
+                    node.values.elements.push(value);
+                }
+                (Err(_), _) if !force => return Err(Error::UnknownKeyType),
+                (_, Err(_)) if !force => return Err(Error::UnknownValueType),
+                _ => continue,
             }
+        }
 
-            let values = Leaf { elements };
-
-            TreeNodes::Leaf(TreeNode { keys, values })
-        } else {
-            let result: Result<VecDeque<_>, _> =
-                values.into_iter().map(|ipld| ipld.try_into()).collect();
-
-            let links = result?;
+        TreeNodes::Leaf(node)
+    } else {
+        let mut node = TreeNode {
+            keys: VecDeque::with_capacity(keys.len()),
+            values: Branch {
+                links: VecDeque::with_capacity(values.len()),
+            },
+        };
 
-            let values = Branch { links };
+        for (key, link) in keys.into_iter().zip(values) {
+            match (K::try_from(key), Cid::try_from(link)) {
+                (Ok(key), Ok(link)) => {
+                    node.keys.push_back(key);
+                    node.values.links.push_back(link);
+                }
+                (Err(_), _) if !force => return Err(Error::UnknownKeyType),
+                (_, Err(e)) if !force => return Err(e.into()),
+                _ => continue,
+            }
+        }
 
-            TreeNodes::Branch(TreeNode { keys, values })
-        };
+        TreeNodes::Branch(node)
+    };
 
-        Ok(tree)
-    }
+    Ok(tree)
 }
 
 impl TryFrom<Ipld> for Config {
@@ -187,13 +215,13 @@ impl TryFrom<Ipld> for Config {
                     };
 
                     let hf: u64 = list.pop().unwrap().try_into()?;
-                    let multihash_code = Code::try_from(hf)?;
+                    let hash_function = BoundaryHash::try_from(hf)?;
 
                     let chunking_factor = list.pop().unwrap().try_into()?;
 
                     *threshold = HashThreshold {
                         chunking_factor,
-                        multihash_code,
+                        hash_function,
                     };
                 }
             }
@@ -260,7 +288,7 @@ impl From<Config> for Ipld {
             Strategies::Threshold(ref threshold) => {
                 let HashThreshold {
                     chunking_factor,
-                    multihash_code: hash_function,
+                    hash_function,
                 } = threshold;
 
                 let map = BTreeMap::from([(
@@ -321,8 +349,6 @@ impl TryFrom<Ipld> for Tree {
 
 #[cfg(test)]
 mod tests {
-    use cid::Cid;
-
     use super::*;
 
     #[test]
@@ -364,4 +390,50 @@ mod tests {
 
         assert_eq!(branch_node, decoded_branch);
     }
+
+    #[test]
+    fn strict_versions() {
+        let key = Ipld::Bytes(vec![255u8, 0u8]);
+        let value = Ipld::String(String::from("This is value number one"));
+
+        let legacy = Ipld::List(vec![
+            Ipld::Bool(true),
+            Ipld::List(vec![key.clone()]),
+            Ipld::List(vec![value.clone()]),
+        ]);
+        let node = TreeNodes::<Vec<u8>, String>::try_from(legacy).unwrap();
+        assert!(matches!(node, TreeNodes::Leaf(leaf) if leaf.keys.len() == 1));
+
+        let future = Ipld::List(vec![
+            Ipld::Integer(NODE_VERSION as i128 + 1),
+            Ipld::Bool(true),
+            Ipld::List(vec![key.clone()]),
+            Ipld::List(vec![value.clone()]),
+            Ipld::Null,
+        ]);
+        let result = TreeNodes::<Vec<u8>, String>::try_from(future.clone());
+        assert!(matches!(result, Err(Error::UnknownNodeLayout)));
+        let node = TreeNodes::<Vec<u8>, String>::try_from_forced(future).unwrap();
+        assert!(matches!(node, TreeNodes::Leaf(leaf) if leaf.keys.len() == 1));
+
+        let newer = Ipld::List(vec![
+            Ipld::Integer(NODE_VERSION as i128 + 1),
+            Ipld::Bool(true),
+            Ipld::List(vec![key.clone()]),
+            Ipld::List(vec![value]),
+        ]);
+        let result = TreeNodes::<Vec<u8>, String>::try_from(newer);
+        assert!(matches!(result, Err(Error::UnknownNodeVersion(_))));
+
+        let malformed = Ipld::List(vec![
+            Ipld::Integer(NODE_VERSION as i128),
+            Ipld::Bool(true),
+            Ipld::List(vec![key.clone(), key]),
+            Ipld::List(vec![Ipld::Null, Ipld::String(String::from("Two"))]),
+        ]);
+        let result = TreeNodes::<Vec<u8>, String>::try_from(malformed.clone());
+        assert!(matches!(result, Err(Error::MalformedNode)));
+        let node = TreeNodes::<Vec<u8>, String>::try_from_forced(malformed).unwrap();
+        assert!(matches!(node, TreeNodes::Leaf(leaf) if leaf.values.elements.len() == 1));
+    }
 }
@@ -0,0 +1,314 @@
+use std::cmp::Ordering;
+
+use cid::Cid;
+
+use futures::{stream, Stream};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use libipld_core::ipld::Ipld;
+
+use super::deserialization::TreeNodes;
+
+use crate::indexing::ordered_trees::{
+    errors::Error,
+    traits::{Key, Value},
+};
+
+/// Difference between two versions of a tree for one key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<K, V> {
+    /// Only in the other tree.
+    Added(K, V),
+
+    /// Only in this tree.
+    Removed(K, V),
+
+    /// In both trees with different values.
+    Changed { key: K, old: V, new: V },
+}
+
+impl<K, V> Change<K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Change::Added(key, _) | Change::Removed(key, _) | Change::Changed { key, .. } => key,
+        }
+    }
+
+    /// Returns the key with the values before and after the change.
+    pub fn into_parts(self) -> (K, Option<V>, Option<V>) {
+        match self {
+            Change::Added(key, value) => (key, None, Some(value)),
+            Change::Removed(key, value) => (key, Some(value), None),
+            Change::Changed { key, old, new } => (key, Some(old), Some(new)),
+        }
+    }
+}
+
+/// Next unvisited subtree or pair of one tree.
+#[derive(Debug, Clone, PartialEq)]
+enum Item<K, V> {
+    /// Height zero links point to leaves.
+    Link {
+        cid: Cid,
+        height: usize,
+    },
+    Pair(K, V),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    /// Identical subtrees.
+    SkipBoth,
+    ExpandOld,
+    ExpandNew,
+    ExpandBoth,
+    /// Pair only in the old tree.
+    TakeOld,
+    /// Pair only in the new tree.
+    TakeNew,
+    /// Same key in both trees.
+    TakeBoth,
+    Done,
+}
+
+/// What to do with the next items of both trees.
+///
+/// Subtrees are only skipped when identical, higher subtrees are expanded first
+/// so that identical subtrees of different versions line up.
+fn step<K: Ord, V>(old: Option<&Item<K, V>>, new: Option<&Item<K, V>>) -> Step {
+    match (old, new) {
+        (None, None) => Step::Done,
+        (Some(Item::Link { .. }), None) => Step::ExpandOld,
+        (Some(Item::Pair(..)), None) => Step::TakeOld,
+        (None, Some(Item::Link { .. })) => Step::ExpandNew,
+        (None, Some(Item::Pair(..))) => Step::TakeNew,
+        (
+            Some(Item::Link {
+                cid: old_cid,
+                height: old_height,
+            }),
+            Some(Item::Link {
+                cid: new_cid,
+                height: new_height,
+            }),
+        ) => {
+            if old_cid == new_cid {
+                Step::SkipBoth
+            } else if old_height > new_height {
+                Step::ExpandOld
+            } else if new_height > old_height {
+                Step::ExpandNew
+            } else {
+                Step::ExpandBoth
+            }
+        }
+        (Some(Item::Link { .. }), Some(Item::Pair(..))) => Step::ExpandOld,
+        (Some(Item::Pair(..)), Some(Item::Link { .. })) => Step::ExpandNew,
+        (Some(Item::Pair(old_key, _)), Some(Item::Pair(new_key, _))) => {
+            match old_key.cmp(new_key) {
+                Ordering::Less => Step::TakeOld,
+                Ordering::Greater => Step::TakeNew,
+                Ordering::Equal => Step::TakeBoth,
+            }
+        }
+    }
+}
+
+/// Stream the changes from the old to the new tree, in key order.
+///
+/// Each tree is decoded with its own codec.
+/// Subtrees with the same CID in both trees are never fetched.
+pub fn diff<K: Key, V: Value>(
+    ipfs: IpfsService,
+    (old_root, old_codec): (Cid, Codec),
+    (new_root, new_codec): (Cid, Codec),
+) -> impl Stream<Item = Result<Change<K, V>, Error>> {
+    stream::try_unfold(None, move |state| {
+        let ipfs = ipfs.clone();
+
+        async move {
+            // Stacks of items, the next item is last.
+            let (mut old, mut new): (Vec<Item<K, V>>, Vec<Item<K, V>>) = match state {
+                Some(stacks) => stacks,
+                None => {
+                    let (old_height, new_height) = futures::try_join!(
+                        height::<K, V>(&ipfs, old_root, old_codec),
+                        height::<K, V>(&ipfs, new_root, new_codec),
+                    )?;
+
+                    (
+                        vec![Item::Link {
+                            cid: old_root,
+                            height: old_height,
+                        }],
+                        vec![Item::Link {
+                            cid: new_root,
+                            height: new_height,
+                        }],
+                    )
+                }
+            };
+
+            loop {
+                let change = match step(old.last(), new.last()) {
+                    Step::Done => return Ok(None),
+                    Step::SkipBoth => {
+                        old.pop();
+                        new.pop();
+                        continue;
+                    }
+                    Step::ExpandOld => {
+                        expand(&ipfs, &mut old, old_codec).await?;
+                        continue;
+                    }
+                    Step::ExpandNew => {
+                        expand(&ipfs, &mut new, new_codec).await?;
+                        continue;
+                    }
+                    Step::ExpandBoth => {
+                        futures::try_join!(
+                            expand(&ipfs, &mut old, old_codec),
+                            expand(&ipfs, &mut new, new_codec)
+                        )?;
+                        continue;
+                    }
+                    Step::TakeOld => {
+                        let Some(Item::Pair(key, value)) = old.pop() else {
+                            unreachable!();
+                        };
+
+                        Change::Removed(key, value)
+                    }
+                    Step::TakeNew => {
+                        let Some(Item::Pair(key, value)) = new.pop() else {
+                            unreachable!();
+                        };
+
+                        Change::Added(key, value)
+                    }
+                    Step::TakeBoth => {
+                        let (Some(Item::Pair(key, old_value)), Some(Item::Pair(_, new_value))) =
+                            (old.pop(), new.pop())
+                        else {
+                            unreachable!();
+                        };
+
+                        let old_ipld: Ipld = old_value.clone().into();
+                        let new_ipld: Ipld = new_value.clone().into();
+
+                        if old_ipld == new_ipld {
+                            continue;
+                        }
+
+                        Change::Changed {
+                            key,
+                            old: old_value,
+                            new: new_value,
+                        }
+                    }
+                };
+
+                return Result::<_, Error>::Ok(Some((change, Some((old, new)))));
+            }
+        }
+    })
+}
+
+/// Number of branch levels above the leaves.
+async fn height<K: Key, V: Value>(
+    ipfs: &IpfsService,
+    root: Cid,
+    codec: Codec,
+) -> Result<usize, Error> {
+    let mut height = 0;
+    let mut link = root;
+
+    // Trees are balanced, the first path is as long as any other.
+    loop {
+        match ipfs
+            .dag_get::<&str, TreeNodes<K, V>>(link, None, codec)
+            .await?
+        {
+            TreeNodes::Branch(branch) => {
+                let Some(first) = branch.values.links.front() else {
+                    return Ok(height);
+                };
+
+                link = *first;
+                height += 1;
+            }
+            TreeNodes::Leaf(_) => return Ok(height),
+        }
+    }
+}
+
+/// Replace the next link of the stack by its children.
+async fn expand<K: Key, V: Value>(
+    ipfs: &IpfsService,
+    stack: &mut Vec<Item<K, V>>,
+    codec: Codec,
+) -> Result<(), Error> {
+    let Some(Item::Link { cid, height }) = stack.pop() else {
+        return Ok(());
+    };
+
+    let node = ipfs
+        .dag_get::<&str, TreeNodes<K, V>>(cid, None, codec)
+        .await?;
+
+    match node {
+        TreeNodes::Branch(branch) => {
+            stack.extend(branch.values.links.into_iter().rev().map(|cid| Item::Link {
+                cid,
+                height: height.saturating_sub(1),
+            }))
+        }
+        TreeNodes::Leaf(leaf) => stack.extend(
+            leaf.keys
+                .into_iter()
+                .zip(leaf.values.elements)
+                .rev()
+                .map(|(key, value)| Item::Pair(key, value)),
+        ),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    fn link(data: &[u8], height: usize) -> Item<u8, u8> {
+        Item::Link {
+            cid: Cid::new_v1(0x71, Code::Sha2_256.digest(data)),
+            height,
+        }
+    }
+
+    #[test]
+    fn steps() {
+        let a = link(b"a", 1);
+        let b = link(b"b", 1);
+        let high = link(b"c", 2);
+
+        assert_eq!(step::<u8, u8>(None, None), Step::Done);
+        assert_eq!(step(Some(&a), Some(&a)), Step::SkipBoth);
+        assert_eq!(step(Some(&a), Some(&b)), Step::ExpandBoth);
+        assert_eq!(step(Some(&high), Some(&a)), Step::ExpandOld);
+        assert_eq!(step(Some(&a), Some(&high)), Step::ExpandNew);
+
+        let pair = Item::Pair(5, 0);
+
+        assert_eq!(step(Some(&a), Some(&pair)), Step::ExpandOld);
+        assert_eq!(step(Some(&pair), None), Step::TakeOld);
+        assert_eq!(step(None, Some(&pair)), Step::TakeNew);
+
+        assert_eq!(step(Some(&pair), Some(&Item::Pair(6, 0))), Step::TakeOld);
+        assert_eq!(step(Some(&pair), Some(&Item::Pair(4, 0))), Step::TakeNew);
+        assert_eq!(step(Some(&pair), Some(&Item::Pair(5, 1))), Step::TakeBoth);
+    }
+}
@@ -0,0 +1,110 @@
+use libipld_core::ipld::Ipld;
+
+/// Values of a key changed differently on both sides of a merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict<V> {
+    /// Value before both changes, none if added.
+    pub base: Option<V>,
+
+    /// Value in this tree, none if removed.
+    pub ours: Option<V>,
+
+    /// Value in the other tree, none if removed.
+    pub theirs: Option<V>,
+}
+
+/// Returns the value to write for a key changed in their tree, none to keep ours.
+///
+/// Keys changed only by them take their value,
+/// keys changed to different values on both sides are resolved by the callback.
+pub(crate) fn merge_value<K, V, F>(
+    key: &K,
+    ours: Option<&Option<V>>,
+    base: Option<V>,
+    theirs: Option<V>,
+    resolve: &mut F,
+) -> Option<Option<V>>
+where
+    V: Clone + Into<Ipld>,
+    F: FnMut(&K, Conflict<V>) -> Option<V>,
+{
+    let Some(ours) = ours else {
+        return Some(theirs);
+    };
+
+    if same(ours.as_ref(), theirs.as_ref()) {
+        return None;
+    }
+
+    let conflict = Conflict {
+        base,
+        ours: ours.clone(),
+        theirs,
+    };
+
+    let resolved = resolve(key, conflict);
+
+    if same(resolved.as_ref(), ours.as_ref()) {
+        return None;
+    }
+
+    Some(resolved)
+}
+
+fn same<V: Clone + Into<Ipld>>(a: Option<&V>, b: Option<&V>) -> bool {
+    let a: Option<Ipld> = a.cloned().map(Into::into);
+    let b: Option<Ipld> = b.cloned().map(Into::into);
+
+    a == b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_values() {
+        let mut theirs_wins = |_: &u8, conflict: Conflict<u8>| conflict.theirs;
+        let mut ours_wins = |_: &u8, conflict: Conflict<u8>| conflict.ours;
+
+        // Only changed by them.
+        assert_eq!(
+            merge_value(&1, None, Some(1), Some(2), &mut ours_wins),
+            Some(Some(2))
+        );
+        assert_eq!(
+            merge_value(&1, None, Some(1), None, &mut ours_wins),
+            Some(None)
+        );
+
+        // Same change on both sides.
+        assert_eq!(
+            merge_value(&1, Some(&Some(2)), Some(1), Some(2), &mut theirs_wins),
+            None
+        );
+
+        // Conflicts.
+        assert_eq!(
+            merge_value(&1, Some(&Some(3)), Some(1), Some(2), &mut theirs_wins),
+            Some(Some(2))
+        );
+        assert_eq!(
+            merge_value(&1, Some(&Some(3)), Some(1), Some(2), &mut ours_wins),
+            None
+        );
+        assert_eq!(
+            merge_value(&1, Some(&None), None, Some(2), &mut theirs_wins),
+            Some(Some(2))
+        );
+
+        let mut remove = |_: &u8, conflict: Conflict<u8>| {
+            assert_eq!(conflict.base, Some(1));
+            None
+        };
+
+        assert_eq!(
+            merge_value(&1, Some(&Some(3)), Some(1), Some(2), &mut remove),
+            Some(None)
+        );
+    }
+}
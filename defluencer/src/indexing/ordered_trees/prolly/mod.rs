@@ -1,16 +1,24 @@
 mod config;
 mod deserialization;
+mod diff;
 mod iterators;
+mod merge;
 mod node;
+mod proof;
+mod snapshot;
 mod tree;
 
-use std::iter;
+use std::{collections::BTreeMap, iter, ops::RangeBounds};
 
-pub use config::{Config, HashThreshold, Strategies};
+pub use config::{BoundaryHash, Config, HashThreshold, Strategies};
+pub use diff::Change;
+pub use merge::Conflict;
+pub use proof::{verify_proof, Proof};
+pub use snapshot::Snapshot;
 
 use cid::Cid;
 
-use futures::{Stream, StreamExt};
+use futures::{pin_mut, Stream, TryStreamExt};
 
 use ipfs_api::{responses::Codec, IpfsService};
 
@@ -21,10 +29,14 @@ use self::{
     node::{Leaf, TreeNode},
 };
 
-use super::{errors::Error, traits::Value};
+use super::{cursor::Cursor, errors::Error, staging::Staging, traits::Value};
 
 type Key = Vec<u8>;
 
+/// Prolly tree with a single writer.
+///
+/// Updates build a new root, the tree only points to it once the whole update succeeded.
+/// Reads use the root they started from, share a [Snapshot] with concurrent readers.
 #[derive(Clone)]
 pub struct ProllyTree {
     config: Config,
@@ -63,52 +75,33 @@ impl ProllyTree {
         Ok(tree)
     }
 
-    pub async fn save(&self) -> Result<Cid, Error> {
-        let config = self
-            .ipfs
-            .dag_put(&self.config, self.config.codec, self.config.codec)
-            .await?;
+    /// Returns the root CID of the last committed update.
+    pub fn current(&self) -> Cid {
+        self.root
+    }
 
-        let tree = Tree {
-            config,
+    /// Pin the current root, later updates of this tree are not visible to the snapshot.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            config: self.config.clone(),
+            ipfs: self.ipfs.clone(),
             root: self.root,
-        };
-
-        let cid = self
-            .ipfs
-            .dag_put(&tree, self.config.codec, self.config.codec)
-            .await?;
+        }
+    }
 
-        Ok(cid)
+    pub async fn save(&self) -> Result<Cid, Error> {
+        self.snapshot().save().await
     }
 
     pub async fn get<V: Value>(&self, key: Key) -> Result<Option<(Key, V)>, Error> {
-        let results = tree::batch_get(
-            self.ipfs.clone(),
-            self.root,
-            self.config.codec,
-            iter::once(key),
-        )
-        .collect::<Vec<_>>()
-        .await;
-
-        let results: Result<Vec<_>, _> = results.into_iter().collect();
-        let mut results = results?;
-
-        if results.len() != 1 {
-            return Ok(None);
-        }
-
-        let kv = results.pop().unwrap();
-
-        Ok(Some(kv))
+        self.snapshot().get(key).await
     }
 
     pub fn batch_get<V: Value>(
         &self,
         keys: impl IntoIterator<Item = Key>,
     ) -> impl Stream<Item = Result<(Key, V), Error>> {
-        tree::batch_get(self.ipfs.clone(), self.root, self.config.codec, keys)
+        self.snapshot().batch_get(keys)
     }
 
     pub async fn insert<V: Value>(&mut self, key: Key, value: V) -> Result<(), Error> {
@@ -169,7 +162,376 @@ impl ProllyTree {
         Ok(())
     }
 
+    /// Remove all the pairs with keys in range.
+    pub async fn batch_remove_range<V: Value>(
+        &mut self,
+        range: impl RangeBounds<Key>,
+    ) -> Result<(), Error> {
+        let root = tree::batch_remove_range::<Key, V>(
+            self.ipfs.clone(),
+            self.root,
+            self.config.clone(),
+            range,
+        )
+        .await?;
+
+        self.root = root;
+
+        Ok(())
+    }
+
+    /// Write all the staged changes to the tree then clear them.
+    ///
+    /// Each node on the path of the changes is written once per pass,
+    /// one for removals and one for insertions, instead of once per batch.
+    /// Changes are kept if the commit fails.
+    pub async fn commit<V: Value>(&mut self, staging: &mut Staging<Key, V>) -> Result<(), Error> {
+        let (inserts, removes) = staging.batches();
+
+        let mut root = self.root;
+
+        if !removes.is_empty() {
+            root =
+                tree::batch_remove::<Key, V>(self.ipfs.clone(), root, self.config.clone(), removes)
+                    .await?;
+        }
+
+        if !inserts.is_empty() {
+            root =
+                tree::batch_insert(self.ipfs.clone(), root, self.config.clone(), inserts).await?;
+        }
+
+        self.root = root;
+        staging.clear();
+
+        Ok(())
+    }
+
     pub fn stream<V: Value>(&self) -> impl Stream<Item = Result<(Key, V), Error>> {
-        tree::stream_pairs(self.ipfs.clone(), self.root, self.config.codec)
+        self.snapshot().stream()
+    }
+
+    /// Stream the pairs with keys in range.
+    pub fn stream_range<V: Value>(
+        &self,
+        range: impl RangeBounds<Key>,
+    ) -> impl Stream<Item = Result<(Key, V), Error>> {
+        self.snapshot().stream_range(range)
+    }
+
+    /// Stream the pairs after the cursor, advancing it as pairs are returned.
+    ///
+    /// Streaming again from the same cursor on a newer version of this tree
+    /// continues after the last key returned.
+    pub fn stream_cursor<'a, V: Value>(
+        &self,
+        cursor: &'a mut Cursor<Key>,
+    ) -> impl Stream<Item = Result<(Key, V), Error>> + 'a {
+        self.snapshot().stream_cursor(cursor)
+    }
+
+    /// Returns at most `limit` pairs after the cursor, advancing it.
+    pub async fn next_page<V: Value>(
+        &self,
+        cursor: &mut Cursor<Key>,
+        limit: usize,
+    ) -> Result<Vec<(Key, V)>, Error> {
+        self.snapshot().next_page(cursor, limit).await
+    }
+
+    /// Stream the changes from this tree to another version of it, in key order.
+    ///
+    /// The other tree is identified by the CID returned when it was saved.
+    /// Subtrees shared by both versions are skipped without being fetched.
+    pub fn diff<V: Value>(
+        &self,
+        other_root: Cid,
+    ) -> impl Stream<Item = Result<Change<Key, V>, Error>> {
+        self.snapshot().diff(other_root)
+    }
+
+    /// Merge the changes made to another version of this tree since their common base.
+    ///
+    /// Base and other tree are identified by the CIDs returned when they were saved.
+    /// Keys changed to different values in both trees are resolved by the callback,
+    /// returning none removes the key. The result does not depend on the order of the changes.
+    pub async fn merge<V: Value, F>(
+        &mut self,
+        base: Cid,
+        theirs: Cid,
+        mut resolve: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&Key, Conflict<V>) -> Option<V>,
+    {
+        let (base, theirs) = futures::try_join!(
+            Self::load(self.ipfs.clone(), base),
+            Self::load(self.ipfs.clone(), theirs),
+        )?;
+
+        let base = (base.root, base.config.codec);
+
+        let ours: BTreeMap<Key, Option<V>> =
+            diff::diff(self.ipfs.clone(), base, (self.root, self.config.codec))
+                .map_ok(|change| {
+                    let (key, _, value) = change.into_parts();
+                    (key, value)
+                })
+                .try_collect()
+                .await?;
+
+        let changes = diff::diff(self.ipfs.clone(), base, (theirs.root, theirs.config.codec));
+        pin_mut!(changes);
+
+        let mut staging = Staging::default();
+
+        while let Some(change) = changes.try_next().await? {
+            let (key, base, theirs) = change.into_parts();
+
+            match merge::merge_value(&key, ours.get(&key), base, theirs, &mut resolve) {
+                Some(Some(value)) => staging.insert(key, value),
+                Some(None) => staging.remove(key),
+                None => {}
+            }
+        }
+
+        self.commit(&mut staging).await
+    }
+
+    /// Returns the blocks proving that a key is or is not in this tree.
+    ///
+    /// The tree is saved first, proofs are verified against the saved tree CID.
+    pub async fn prove<V: Value>(&self, key: Key) -> Result<Proof, Error> {
+        self.snapshot().prove::<V>(key).await
+    }
+
+    /// Stream all the pairs that can still be decoded, for recovery purposes.
+    ///
+    /// Nodes of unknown versions are decoded as best as possible, data may be lost.
+    pub fn stream_forced<V: Value>(&self) -> impl Stream<Item = Result<(Key, V), Error>> {
+        self.snapshot().stream_forced()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ipfs_api::mock::MockIpfs;
+
+    /// Small chunks, trees of a few thousand keys have many levels.
+    fn config() -> Config {
+        let mut config = Config::default();
+        let mut strat = HashThreshold::default();
+        strat.chunking_factor = 1 << 19;
+        config.chunking_strategy = Strategies::Threshold(strat);
+
+        config
+    }
+
+    fn key(i: u16) -> Key {
+        i.to_be_bytes().to_vec()
+    }
+
+    #[tokio::test]
+    async fn commit_staging() {
+        let ipfs = MockIpfs::new().service();
+
+        let mut tree = ProllyTree::new::<u64>(ipfs.clone(), Some(config()))
+            .await
+            .unwrap();
+
+        tree.batch_insert((0..1000).map(|i| (key(i), i as u64)))
+            .await
+            .unwrap();
+
+        let mut staging = Staging::default();
+
+        staging.batch_insert((0..100).map(|i| (key(i), 1000 + i as u64)));
+        staging.batch_remove((900..1000).map(key));
+        staging.insert(key(2000), 2000);
+        staging.remove(key(3000));
+
+        let before = tree.snapshot();
+
+        tree.commit(&mut staging).await.unwrap();
+
+        assert!(staging.is_empty());
+
+        let mut expected: BTreeMap<Key, u64> = (0..900).map(|i| (key(i), i as u64)).collect();
+        expected.extend((0..100).map(|i| (key(i), 1000 + i as u64)));
+        expected.insert(key(2000), 2000);
+
+        let pairs: Vec<(Key, u64)> = tree.stream().try_collect().await.unwrap();
+
+        assert_eq!(pairs, expected.clone().into_iter().collect::<Vec<_>>());
+
+        // Same tree as if built in one go.
+        let mut rebuilt = ProllyTree::new::<u64>(ipfs.clone(), Some(config()))
+            .await
+            .unwrap();
+
+        rebuilt.batch_insert(expected).await.unwrap();
+
+        assert_eq!(tree.current(), rebuilt.current());
+
+        // Earlier readers are unaffected.
+        assert_eq!(
+            before.get::<u64>(key(950)).await.unwrap(),
+            Some((key(950), 950))
+        );
+    }
+
+    #[tokio::test]
+    async fn remove_range() {
+        let ipfs = MockIpfs::new().service();
+
+        let mut tree = ProllyTree::new::<u64>(ipfs.clone(), Some(config()))
+            .await
+            .unwrap();
+
+        tree.batch_insert((0..2000).map(|i| (key(i), i as u64)))
+            .await
+            .unwrap();
+
+        tree.batch_remove_range::<u64>(key(100)..key(1500))
+            .await
+            .unwrap();
+
+        let expected: Vec<(Key, u64)> = (0..100)
+            .chain(1500..2000)
+            .map(|i| (key(i), i as u64))
+            .collect();
+
+        let pairs: Vec<(Key, u64)> = tree.stream().try_collect().await.unwrap();
+
+        assert_eq!(pairs, expected);
+
+        // Same tree as if the range was never added.
+        let mut rebuilt = ProllyTree::new::<u64>(ipfs.clone(), Some(config()))
+            .await
+            .unwrap();
+
+        rebuilt.batch_insert(expected).await.unwrap();
+
+        assert_eq!(tree.current(), rebuilt.current());
+    }
+
+    #[tokio::test]
+    async fn diff_skips_shared_subtrees() {
+        let mock = MockIpfs::new();
+        let ipfs = mock.service();
+
+        let mut tree = ProllyTree::new::<u64>(ipfs.clone(), Some(config()))
+            .await
+            .unwrap();
+
+        tree.batch_insert((0..2000).map(|i| (key(i), i as u64)))
+            .await
+            .unwrap();
+
+        let old = tree.snapshot();
+
+        tree.insert(key(1000), 0u64).await.unwrap();
+
+        let new = tree.save().await.unwrap();
+
+        let reads = mock.reads();
+
+        let pairs: Vec<(Key, u64)> = tree.stream().try_collect().await.unwrap();
+
+        let stream_reads = mock.reads() - reads;
+
+        assert_eq!(pairs.len(), 2000);
+
+        let reads = mock.reads();
+
+        let changes: Vec<Change<Key, u64>> = old.diff(new).try_collect().await.unwrap();
+
+        let diff_reads = mock.reads() - reads;
+
+        assert_eq!(
+            changes,
+            vec![Change::Changed {
+                key: key(1000),
+                old: 1000,
+                new: 0,
+            }]
+        );
+
+        // Only the paths to the changed leaf are fetched.
+        assert!(
+            diff_reads * 4 < stream_reads,
+            "{} reads to diff, {} to stream",
+            diff_reads,
+            stream_reads
+        );
+    }
+
+    #[tokio::test]
+    async fn three_way_merge() {
+        let ipfs = MockIpfs::new().service();
+
+        let mut ours = ProllyTree::new::<u64>(ipfs.clone(), Some(config()))
+            .await
+            .unwrap();
+
+        ours.batch_insert((0..500).map(|i| (key(i), i as u64)))
+            .await
+            .unwrap();
+
+        let base = ours.save().await.unwrap();
+
+        let mut theirs = ours.clone();
+
+        ours.batch_insert([(key(1), 100u64), (key(3), 300)])
+            .await
+            .unwrap();
+        ours.remove::<u64>(key(2)).await.unwrap();
+
+        theirs
+            .batch_insert([
+                (key(1), 101u64),
+                (key(3), 300),
+                (key(4), 400),
+                (key(600), 600),
+            ])
+            .await
+            .unwrap();
+        theirs.remove::<u64>(key(5)).await.unwrap();
+
+        let theirs = theirs.save().await.unwrap();
+
+        let mut conflicts = Vec::new();
+
+        ours.merge(base, theirs, |key, conflict: Conflict<u64>| {
+            conflicts.push((key.clone(), conflict.clone()));
+
+            conflict.ours.max(conflict.theirs)
+        })
+        .await
+        .unwrap();
+
+        // Only key 1 changed to different values on both sides.
+        assert_eq!(
+            conflicts,
+            vec![(
+                key(1),
+                Conflict {
+                    base: Some(1),
+                    ours: Some(100),
+                    theirs: Some(101),
+                }
+            )]
+        );
+
+        let mut expected: BTreeMap<Key, u64> = (0..500).map(|i| (key(i), i as u64)).collect();
+        expected.extend([(key(1), 101), (key(3), 300), (key(4), 400), (key(600), 600)]);
+        expected.remove(&key(2));
+        expected.remove(&key(5));
+
+        let pairs: Vec<(Key, u64)> = ours.stream().try_collect().await.unwrap();
+
+        assert_eq!(pairs, expected.into_iter().collect::<Vec<_>>());
     }
 }
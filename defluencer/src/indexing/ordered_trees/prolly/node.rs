@@ -1,4 +1,8 @@
-use std::{collections::VecDeque, fmt::Debug};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    ops::{Bound, RangeBounds},
+};
 
 use super::{
     deserialization::TreeNodes,
@@ -10,6 +14,7 @@ use cid::Cid;
 
 use crate::indexing::ordered_trees::{
     errors::Error,
+    range::{covers, disjoint},
     traits::{Key, Value},
 };
 
@@ -98,6 +103,51 @@ impl<K: Key> TreeNode<K, Branch> {
         }
     }
 
+    /// Remove the links of children overlapping the range.
+    ///
+    /// Returns the links of children partially covered and the child preceding the range,
+    /// in order, so they can be rewritten and merged.
+    pub fn remove_range(&mut self, range: &(Bound<K>, Bound<K>)) -> Vec<Cid> {
+        let mut links = Vec::new();
+        let mut first = None;
+        let mut i = 0;
+
+        while i < self.keys.len() {
+            let child_range = (
+                Bound::Included(self.keys[i].clone()),
+                match self.keys.get(i + 1) {
+                    Some(key) => Bound::Excluded(key.clone()),
+                    None => Bound::Unbounded,
+                },
+            );
+
+            if disjoint(range, &child_range) {
+                i += 1;
+                continue;
+            }
+
+            first.get_or_insert(i);
+
+            self.keys.remove(i);
+            let link = self.values.links.remove(i).unwrap();
+
+            if !covers(range, &child_range) {
+                links.push(link);
+            }
+        }
+
+        if let Some(idx) = first {
+            //Include link to previous node for merging purpose
+            if idx > 0 {
+                self.keys.remove(idx - 1);
+                let link = self.values.links.remove(idx - 1).unwrap();
+                links.insert(0, link);
+            }
+        }
+
+        links
+    }
+
     /// Run the chunking algorithm on this node. Return splitted nodes in order if any.
     pub fn split<V: Value>(self, mut config: Config) -> Result<Vec<Self>, Error> {
         let (bytes, mut node) = {
@@ -339,6 +389,26 @@ impl<K: Key, V: Value> TreeNode<K, Leaf<V>> {
         }
     }
 
+    /// Remove keys and values in range.
+    ///
+    /// Idempotent.
+    pub fn remove_range(&mut self, range: &(Bound<K>, Bound<K>)) {
+        let start = match self.keys.iter().position(|key| range.contains(key)) {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        let count = self
+            .keys
+            .iter()
+            .skip(start)
+            .take_while(|key| range.contains(*key))
+            .count();
+
+        self.keys.drain(start..start + count);
+        self.values.elements.drain(start..start + count);
+    }
+
     pub fn iter(
         &self,
     ) -> impl IntoIterator<Item = (&K, &V)> + Iterator<Item = (&K, &V)> + DoubleEndedIterator {
@@ -371,7 +441,7 @@ impl<K: Key, V: Value> TreeNode<K, Leaf<V>> {
 
 #[cfg(test)]
 mod tests {
-    use crate::indexing::ordered_trees::prolly::{HashThreshold, Strategies};
+    use crate::indexing::ordered_trees::prolly::{BoundaryHash, HashThreshold, Strategies};
 
     use super::*;
 
@@ -384,7 +454,7 @@ mod tests {
 
     use cid::Cid;
 
-    use multihash::{Code, Multihash};
+    use multihash::Multihash;
 
     #[test]
     fn into_search_batch() {
@@ -427,7 +497,7 @@ mod tests {
         let mut config = Config::default();
         config.chunking_strategy = Strategies::Threshold(HashThreshold {
             chunking_factor: 16,
-            multihash_code: Code::Sha2_256,
+            hash_function: BoundaryHash::Sha2_256,
         });
         config.codec = Codec::DagCbor;
 
@@ -483,7 +553,7 @@ mod tests {
         let mut config = Config::default();
         config.chunking_strategy = Strategies::Threshold(HashThreshold {
             chunking_factor: 16,
-            multihash_code: Code::Sha2_256,
+            hash_function: BoundaryHash::Sha2_256,
         });
         config.codec = Codec::DagCbor;
         config.min_size = 0;
@@ -566,7 +636,7 @@ mod tests {
         let mut config = Config::default();
         config.chunking_strategy = Strategies::Threshold(HashThreshold {
             chunking_factor: 16,
-            multihash_code: Code::Sha2_256,
+            hash_function: BoundaryHash::Sha2_256,
         });
         config.codec = Codec::DagCbor;
 
@@ -651,6 +721,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn leaf_remove_range() {
+        let mut rng = Xoshiro256StarStar::from_entropy();
+
+        let keys = VecDeque::from(vec![0, 3, 5, 7, 9, 10]);
+
+        let elements: Vec<_> = (0..keys.len()).map(|_| random_cid(&mut rng)).collect();
+
+        let mut node = TreeNode::<u8, Leaf<Cid>> {
+            keys,
+            values: Leaf {
+                elements: elements.clone(),
+            },
+        };
+
+        node.remove_range(&(Bound::Included(4), Bound::Excluded(9)));
+
+        assert_eq!(node.keys, VecDeque::from(vec![0, 3, 9, 10]));
+        assert_eq!(
+            node.values.elements,
+            vec![elements[0], elements[1], elements[4], elements[5]]
+        );
+    }
+
+    #[test]
+    fn branch_remove_range() {
+        let mut rng = Xoshiro256StarStar::from_entropy();
+
+        let keys = VecDeque::from(vec![0, 10, 20, 30, 40]);
+
+        let links: VecDeque<_> = (0..keys.len()).map(|_| random_cid(&mut rng)).collect();
+
+        let mut node = TreeNode::<u8, Branch> {
+            keys,
+            values: Branch {
+                links: links.clone(),
+            },
+        };
+
+        // Child 20 is fully covered, children 10 & 30 are partially covered.
+        let rewrite = node.remove_range(&(Bound::Included(15), Bound::Excluded(35)));
+
+        assert_eq!(rewrite, vec![links[0], links[1], links[3]]);
+        assert_eq!(node.keys, VecDeque::from(vec![40]));
+        assert_eq!(node.values.links, VecDeque::from(vec![links[4]]));
+
+        let mut node = TreeNode::<u8, Branch> {
+            keys: VecDeque::from(vec![0, 10, 20, 30, 40]),
+            values: Branch {
+                links: links.clone(),
+            },
+        };
+
+        // Only covered children, the previous one is still rewritten.
+        let rewrite = node.remove_range(&(Bound::Included(10), Bound::Excluded(30)));
+
+        assert_eq!(rewrite, vec![links[0]]);
+        assert_eq!(node.keys, VecDeque::from(vec![30, 40]));
+    }
+
     fn random_cid(rng: &mut Xoshiro256StarStar) -> Cid {
         let mut input = [0u8; 64];
         rng.fill_bytes(&mut input);
@@ -0,0 +1,181 @@
+use cid::Cid;
+
+use ipfs_api::responses::Codec;
+
+use multihash::{Code, MultihashDigest};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+    config::Tree,
+    deserialization::TreeNodes,
+    node::{Branch, TreeNode},
+};
+
+use crate::indexing::ordered_trees::{
+    errors::Error,
+    traits::{Key, Value},
+};
+
+/// Encoded blocks on the path from a saved tree to a key.
+///
+/// The saved tree block is first, the leaf or the branch excluding the key is last.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proof {
+    pub blocks: Vec<Vec<u8>>,
+}
+
+/// Verify a proof against a saved tree CID, without IPFS.
+///
+/// Returns the value if the key is in the tree, none if the proof shows it is not.
+pub fn verify_proof<K: Key, V: Value>(
+    root: Cid,
+    key: &K,
+    proof: &Proof,
+) -> Result<Option<V>, Error> {
+    let Some((tree, nodes)) = proof.blocks.split_first() else {
+        return Err(Error::InvalidProof);
+    };
+
+    let tree: Tree = decode(root, tree)?;
+
+    let mut link = tree.root;
+
+    for (idx, bytes) in nodes.iter().enumerate() {
+        let last = idx + 1 == nodes.len();
+
+        match decode::<TreeNodes<K, V>>(link, bytes)? {
+            TreeNodes::Branch(branch) => match child(&branch, key) {
+                Some(cid) if !last => link = cid,
+                None if last => return Ok(None),
+                _ => return Err(Error::InvalidProof),
+            },
+            TreeNodes::Leaf(leaf) if last => {
+                let value = leaf
+                    .keys
+                    .binary_search(key)
+                    .ok()
+                    .map(|idx| leaf.values.elements[idx].clone());
+
+                return Ok(value);
+            }
+            TreeNodes::Leaf(_) => return Err(Error::InvalidProof),
+        }
+    }
+
+    Err(Error::InvalidProof)
+}
+
+/// Returns the link to the child whose range includes the key,
+/// none if the key is before the first child.
+pub(crate) fn child<K: Key>(branch: &TreeNode<K, Branch>, key: &K) -> Option<Cid> {
+    let idx = match branch.keys.binary_search(key) {
+        Ok(idx) => idx,
+        Err(0) => return None,
+        Err(idx) => idx - 1,
+    };
+
+    branch.values.links.get(idx).copied()
+}
+
+/// Decode a block after checking that it hashes to this CID.
+///
+/// Only DAG-CBOR blocks are supported.
+pub(crate) fn decode<T: DeserializeOwned>(cid: Cid, bytes: &[u8]) -> Result<T, Error> {
+    if cid.codec() != Codec::DagCbor as u64 {
+        return Err(Error::UnknownCodec);
+    }
+
+    let code = Code::try_from(cid.hash().code())?;
+
+    if code.digest(bytes) != *cid.hash() {
+        return Err(Error::InvalidProof);
+    }
+
+    serde_ipld_dagcbor::from_slice(bytes).map_err(|_| Error::InvalidProof)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    use crate::indexing::ordered_trees::prolly::node::Leaf;
+
+    fn block<T: Serialize>(node: &T) -> (Cid, Vec<u8>) {
+        let bytes = serde_ipld_dagcbor::to_vec(node).unwrap();
+        let cid = Cid::new_v1(Codec::DagCbor as u64, Code::Sha2_256.digest(&bytes));
+
+        (cid, bytes)
+    }
+
+    fn key(byte: u8) -> Vec<u8> {
+        vec![byte]
+    }
+
+    fn leaf(keys: &[u8]) -> (Cid, Vec<u8>) {
+        let node: TreeNodes<Vec<u8>, String> = TreeNodes::Leaf(TreeNode {
+            keys: keys.iter().copied().map(key).collect(),
+            values: Leaf {
+                elements: keys.iter().map(|key| key.to_string()).collect(),
+            },
+        });
+
+        block(&node)
+    }
+
+    #[test]
+    fn inclusion_and_exclusion() {
+        let (first, first_bytes) = leaf(&[2, 3]);
+        let (second, second_bytes) = leaf(&[5, 8]);
+
+        let branch: TreeNodes<Vec<u8>, String> = TreeNodes::Branch(TreeNode {
+            keys: VecDeque::from([key(2), key(5)]),
+            values: Branch {
+                links: VecDeque::from([first, second]),
+            },
+        });
+        let (branch, branch_bytes) = block(&branch);
+
+        let config = Cid::new_v1(0x71, Code::Sha2_256.digest(b"config"));
+        let (root, tree_bytes) = block(&Tree {
+            config,
+            root: branch,
+        });
+
+        let proof = Proof {
+            blocks: vec![tree_bytes.clone(), branch_bytes.clone(), second_bytes],
+        };
+
+        assert_eq!(
+            verify_proof(root, &key(8), &proof).unwrap(),
+            Some(String::from("8"))
+        );
+        assert_eq!(
+            verify_proof::<_, String>(root, &key(6), &proof).unwrap(),
+            None
+        );
+
+        // Proves the key of another leaf.
+        assert!(verify_proof::<_, String>(root, &key(3), &proof).is_err());
+
+        let before = Proof {
+            blocks: vec![tree_bytes, branch_bytes],
+        };
+
+        assert_eq!(
+            verify_proof::<_, String>(root, &key(1), &before).unwrap(),
+            None
+        );
+
+        let mut tampered = proof.clone();
+        tampered.blocks[2] = first_bytes;
+
+        assert!(verify_proof::<_, String>(root, &key(8), &tampered).is_err());
+
+        let other = Cid::new_v1(0x71, Code::Sha2_256.digest(b"other"));
+
+        assert!(verify_proof::<_, String>(other, &key(8), &proof).is_err());
+    }
+}
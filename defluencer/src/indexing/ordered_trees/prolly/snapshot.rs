@@ -0,0 +1,217 @@
+use std::{iter, ops::RangeBounds};
+
+use cid::Cid;
+
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use super::{
+    config::{Config, Tree},
+    deserialization::TreeNodes,
+    diff::{self, Change},
+    proof::{self, Proof},
+    tree, Key, ProllyTree,
+};
+
+use crate::indexing::ordered_trees::{cursor::Cursor, errors::Error, traits::Value};
+
+/// Read only view of a prolly tree at one root.
+///
+/// Nodes are immutable, a snapshot keeps reading the same pairs
+/// while the tree it came from is updated.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub(super) config: Config,
+
+    pub(super) ipfs: IpfsService,
+
+    pub(super) root: Cid,
+}
+
+impl Snapshot {
+    /// Returns the root CID this snapshot reads from.
+    pub fn root(&self) -> Cid {
+        self.root
+    }
+
+    pub async fn save(&self) -> Result<Cid, Error> {
+        let config = self
+            .ipfs
+            .dag_put(&self.config, self.config.codec, self.config.codec)
+            .await?;
+
+        let tree = Tree {
+            config,
+            root: self.root,
+        };
+
+        let cid = self
+            .ipfs
+            .dag_put(&tree, self.config.codec, self.config.codec)
+            .await?;
+
+        Ok(cid)
+    }
+
+    pub async fn get<V: Value>(&self, key: Key) -> Result<Option<(Key, V)>, Error> {
+        let results = tree::batch_get(
+            self.ipfs.clone(),
+            self.root,
+            self.config.codec,
+            iter::once(key),
+        )
+        .collect::<Vec<_>>()
+        .await;
+
+        let results: Result<Vec<_>, _> = results.into_iter().collect();
+        let mut results = results?;
+
+        if results.len() != 1 {
+            return Ok(None);
+        }
+
+        let kv = results.pop().unwrap();
+
+        Ok(Some(kv))
+    }
+
+    pub fn batch_get<V: Value>(
+        &self,
+        keys: impl IntoIterator<Item = Key>,
+    ) -> impl Stream<Item = Result<(Key, V), Error>> {
+        tree::batch_get(self.ipfs.clone(), self.root, self.config.codec, keys)
+    }
+
+    pub fn stream<V: Value>(&self) -> impl Stream<Item = Result<(Key, V), Error>> {
+        tree::stream_pairs(self.ipfs.clone(), self.root, self.config.codec)
+    }
+
+    /// Stream the pairs with keys in range.
+    pub fn stream_range<V: Value>(
+        &self,
+        range: impl RangeBounds<Key>,
+    ) -> impl Stream<Item = Result<(Key, V), Error>> {
+        tree::stream_range(self.ipfs.clone(), self.root, self.config.codec, range)
+    }
+
+    /// Stream the pairs after the cursor, advancing it as pairs are returned.
+    ///
+    /// Streaming again from the same cursor on a newer version of this tree
+    /// continues after the last key returned.
+    pub fn stream_cursor<'a, V: Value>(
+        &self,
+        cursor: &'a mut Cursor<Key>,
+    ) -> impl Stream<Item = Result<(Key, V), Error>> + 'a {
+        self.stream_range(cursor.remaining())
+            .inspect_ok(move |(key, _)| cursor.advance(key))
+    }
+
+    /// Returns at most `limit` pairs after the cursor, advancing it.
+    pub async fn next_page<V: Value>(
+        &self,
+        cursor: &mut Cursor<Key>,
+        limit: usize,
+    ) -> Result<Vec<(Key, V)>, Error> {
+        self.stream_cursor(cursor).take(limit).try_collect().await
+    }
+
+    /// Stream the changes from this tree to another version of it, in key order.
+    ///
+    /// The other tree is identified by the CID returned when it was saved.
+    /// Subtrees shared by both versions are skipped without being fetched.
+    pub fn diff<V: Value>(
+        &self,
+        other_root: Cid,
+    ) -> impl Stream<Item = Result<Change<Key, V>, Error>> {
+        let ipfs = self.ipfs.clone();
+        let root = self.root;
+        let codec = self.config.codec;
+
+        stream::once(async move {
+            let other = ProllyTree::load(ipfs.clone(), other_root).await?;
+
+            Result::<_, Error>::Ok(diff::diff(
+                ipfs,
+                (root, codec),
+                (other.root, other.config.codec),
+            ))
+        })
+        .try_flatten()
+    }
+
+    /// Returns the blocks proving that a key is or is not in this tree.
+    ///
+    /// The tree is saved first, proofs are verified against the saved tree CID.
+    pub async fn prove<V: Value>(&self, key: Key) -> Result<Proof, Error> {
+        if self.config.codec != Codec::DagCbor {
+            return Err(Error::UnknownCodec);
+        }
+
+        let tree = self.save().await?;
+
+        let mut blocks = vec![self.ipfs.block_get(tree).await?.to_vec()];
+        let mut link = self.root;
+
+        loop {
+            let bytes = self.ipfs.block_get(link).await?.to_vec();
+
+            let node: TreeNodes<Key, V> = proof::decode(link, &bytes)?;
+
+            blocks.push(bytes);
+
+            match node {
+                TreeNodes::Branch(branch) => match proof::child(&branch, &key) {
+                    Some(cid) => link = cid,
+                    None => break,
+                },
+                TreeNodes::Leaf(_) => break,
+            }
+        }
+
+        Ok(Proof { blocks })
+    }
+
+    /// Stream all the pairs that can still be decoded, for recovery purposes.
+    ///
+    /// Nodes of unknown versions are decoded as best as possible, data may be lost.
+    pub fn stream_forced<V: Value>(&self) -> impl Stream<Item = Result<(Key, V), Error>> {
+        tree::stream_pairs_forced(self.ipfs.clone(), self.root, self.config.codec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::indexing::ordered_trees::prolly::ProllyTree;
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn snapshot_isolation() {
+        let ipfs = IpfsService::default();
+
+        let mut tree = ProllyTree::new::<u64>(ipfs, None).await.unwrap();
+
+        tree.batch_insert((0u8..10).map(|i| (vec![i], i as u64)))
+            .await
+            .unwrap();
+
+        let snapshot = tree.snapshot();
+
+        assert_eq!(snapshot.root(), tree.current());
+
+        tree.batch_insert((10u8..20).map(|i| (vec![i], i as u64)))
+            .await
+            .unwrap();
+
+        assert_ne!(snapshot.root(), tree.current());
+
+        let old: Vec<(Key, u64)> = snapshot.stream().try_collect().await.unwrap();
+        let new: Vec<(Key, u64)> = tree.stream().try_collect().await.unwrap();
+
+        assert_eq!(old.len(), 10);
+        assert_eq!(new.len(), 20);
+        assert!(snapshot.get::<u64>(vec![15]).await.unwrap().is_none());
+    }
+}
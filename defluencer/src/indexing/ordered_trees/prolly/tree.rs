@@ -1,3 +1,5 @@
+use std::ops::{Bound, RangeBounds};
+
 use async_recursion::async_recursion;
 
 use futures::{future::try_join_all, stream, Stream, StreamExt, TryStreamExt};
@@ -12,8 +14,11 @@ use super::{
 
 use cid::Cid;
 
+use libipld_core::ipld::Ipld;
+
 use crate::indexing::ordered_trees::{
     errors::Error,
+    range::disjoint,
     traits::{Key, Value},
 };
 
@@ -324,6 +329,127 @@ async fn execute_batch_remove<K: Key, V: Value>(
     Ok(key_links)
 }
 
+/// Remove all values in the tree with keys in range.
+///
+/// Subtrees fully covered by the range are dropped without being fetched,
+/// only the nodes at the range boundaries are rewritten.
+pub async fn batch_remove_range<K: Key, V: Value>(
+    ipfs: IpfsService,
+    root: Cid,
+    config: Config,
+    range: impl RangeBounds<K>,
+) -> Result<Cid, Error> {
+    let range = (range.start_bound().cloned(), range.end_bound().cloned());
+
+    let key_links =
+        execute_batch_remove_range::<K, V>(ipfs.clone(), vec![root], config.clone(), range).await?;
+
+    if key_links.len() > 1 {
+        let mut node = TreeNode::<K, Branch>::default();
+        node.insert(key_links.into_iter());
+        let node = TreeNodes::<K, V>::Branch(node);
+        let cid = ipfs.dag_put(&node, config.codec, config.codec).await?;
+        return Ok(cid);
+    }
+
+    if key_links.is_empty() {
+        let node = TreeNode::<K, Leaf<V>>::default();
+        let node = TreeNodes::Leaf(node);
+        let root = ipfs.dag_put(&node, config.codec, config.codec).await?;
+        return Ok(root);
+    }
+
+    Ok(key_links[0].1)
+}
+
+#[async_recursion]
+async fn execute_batch_remove_range<K: Key, V: Value>(
+    ipfs: IpfsService,
+    links: Vec<Cid>,
+    config: Config,
+    range: (Bound<K>, Bound<K>),
+) -> Result<Vec<(K, Cid)>, Error> {
+    let futures = links
+        .into_iter()
+        .map(|link| ipfs.dag_get::<&str, TreeNodes<K, V>>(link, None, config.codec))
+        .collect::<Vec<_>>();
+
+    let nodes = try_join_all(futures).await?;
+
+    // Merge all the nodes
+    // Works only because we know the nodes will be either leafs or branches.
+    let node = nodes
+        .into_iter()
+        .reduce(|acc, x| match (acc, x) {
+            (TreeNodes::Branch(mut node), TreeNodes::Branch(other)) => {
+                node.merge(other);
+                TreeNodes::Branch(node)
+            }
+            (TreeNodes::Leaf(mut node), TreeNodes::Leaf(other)) => {
+                node.merge(other);
+                TreeNodes::Leaf(node)
+            }
+            _ => panic!("The tree should always be symmetrical"),
+        })
+        .expect("at least one node");
+
+    let nodes: Vec<_> = match node {
+        TreeNodes::Leaf(mut node) => {
+            node.remove_range(&range);
+
+            let nodes = node.split(config.clone())?;
+
+            nodes
+                .into_iter()
+                .filter(|leaf| !leaf.keys.is_empty())
+                .map(|leaf| TreeNodes::Leaf(leaf))
+                .collect()
+        }
+        TreeNodes::Branch(mut node) => {
+            let links = node.remove_range(&range);
+
+            if !links.is_empty() {
+                let key_links =
+                    execute_batch_remove_range::<K, V>(ipfs.clone(), links, config.clone(), range)
+                        .await?;
+
+                node.insert(key_links.into_iter());
+            }
+
+            let nodes = node.split::<V>(config.clone())?;
+
+            nodes
+                .into_iter()
+                .filter(|branch| !branch.keys.is_empty())
+                .map(|branch| TreeNodes::Branch(branch))
+                .collect()
+        }
+    };
+
+    let keys = nodes
+        .iter()
+        .map(|node| match node {
+            TreeNodes::Branch(node) => node.keys[0].clone(),
+            TreeNodes::Leaf(node) => node.keys[0].clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let futures: Vec<_> = nodes
+        .into_iter()
+        .map(|node| {
+            let ipfs = ipfs.clone();
+
+            async move { ipfs.dag_put(&node, config.codec, config.codec).await }
+        })
+        .collect();
+
+    let links = try_join_all(futures).await?;
+
+    let key_links = keys.into_iter().zip(links.into_iter()).collect();
+
+    Ok(key_links)
+}
+
 /// Stream all KVs in the tree in order.
 pub fn stream_pairs<K: Key, V: Value>(
     ipfs: IpfsService,
@@ -375,6 +501,107 @@ fn stream_branch<K: Key, V: Value>(
         .try_flatten()
 }
 
+/// Stream the KVs with keys in range, in order.
+///
+/// Subtrees disjoint from the range are not fetched.
+pub fn stream_range<K: Key, V: Value>(
+    ipfs: IpfsService,
+    root: Cid,
+    codec: Codec,
+    range: impl RangeBounds<K>,
+) -> impl Stream<Item = Result<(K, V), Error>> {
+    let range = (range.start_bound().cloned(), range.end_bound().cloned());
+
+    stream_range_node(ipfs, root, codec, range)
+}
+
+fn stream_range_node<K: Key, V: Value>(
+    ipfs: IpfsService,
+    link: Cid,
+    codec: Codec,
+    range: (Bound<K>, Bound<K>),
+) -> impl Stream<Item = Result<(K, V), Error>> {
+    stream::once(async move {
+        match ipfs
+            .dag_get::<&str, TreeNodes<K, V>>(link, None, codec)
+            .await
+        {
+            Ok(node) => Ok((ipfs, node, range)),
+            Err(e) => Err(e),
+        }
+    })
+    .map_ok(move |(ipfs, node, range)| match node {
+        TreeNodes::Branch(branch) => {
+            let links: Vec<_> = branch
+                .into_iter()
+                .filter(|(child_range, _)| !disjoint(&range, child_range))
+                .map(|(_, link)| link)
+                .collect();
+
+            stream::iter(links)
+                .map(move |link| stream_range_node(ipfs.clone(), link, codec, range.clone()))
+                .flatten()
+                .boxed_local()
+        }
+        TreeNodes::Leaf(leaf) => stream::iter(
+            leaf.into_iter()
+                .filter(move |(key, _)| range.contains(key))
+                .map(|item| Ok(item)),
+        )
+        .boxed_local(),
+    })
+    .try_flatten()
+}
+
+/// Stream all KVs in the tree in order, decoding nodes leniently.
+///
+/// Unknown versions and undecodable pairs are skipped instead of returning errors.
+pub fn stream_pairs_forced<K: Key, V: Value>(
+    ipfs: IpfsService,
+    root: Cid,
+    codec: Codec,
+) -> impl Stream<Item = Result<(K, V), Error>> {
+    stream::once(async move {
+        let ipld = ipfs.dag_get::<&str, Ipld>(root, None, codec).await?;
+
+        let node = TreeNodes::<K, V>::try_from_forced(ipld)?;
+
+        Result::<_, Error>::Ok((ipfs, node))
+    })
+    .map_ok(move |(ipfs, node)| match node {
+        TreeNodes::Branch(branch) => stream_branch_forced(ipfs, branch, codec).boxed_local(),
+        TreeNodes::Leaf(leaf) => stream::iter(leaf.into_iter().map(|item| Ok(item))).boxed_local(),
+    })
+    .try_flatten()
+}
+
+fn stream_branch_forced<K: Key, V: Value>(
+    ipfs: IpfsService,
+    branch: TreeNode<K, Branch>,
+    codec: Codec,
+) -> impl Stream<Item = Result<(K, V), Error>> {
+    stream::iter(branch.into_iter())
+        .map(|(_, link)| Ok(link))
+        .and_then(move |link| {
+            let ipfs = ipfs.clone();
+
+            async move {
+                let ipld = ipfs.dag_get::<&str, Ipld>(link, None, codec).await?;
+
+                let node = TreeNodes::<K, V>::try_from_forced(ipld)?;
+
+                Result::<_, Error>::Ok((ipfs, node))
+            }
+        })
+        .map_ok(move |(ipfs, node)| match node {
+            TreeNodes::Branch(branch) => stream_branch_forced(ipfs, branch, codec).boxed_local(),
+            TreeNodes::Leaf(leaf) => stream::iter(leaf.into_iter())
+                .map(|item| Ok(item))
+                .boxed_local(),
+        })
+        .try_flatten()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -382,19 +609,20 @@ mod tests {
 
     use super::*;
 
+    use crate::indexing::ordered_trees::cursor::Cursor;
+
     use futures::StreamExt;
 
-    use ipfs_api::IpfsService;
+    use ipfs_api::{mock::MockIpfs, IpfsService};
 
     use rand::prelude::*;
 
     use rand_xoshiro::Xoshiro256StarStar;
 
     #[tokio::test(flavor = "multi_thread")]
-    #[ignore]
     async fn tree_stream_all() {
         let mut rng = Xoshiro256StarStar::seed_from_u64(6784236783546783546u64);
-        let ipfs = IpfsService::default();
+        let ipfs = MockIpfs::new().service();
 
         let mut config = Config::default();
         let mut strat = HashThreshold::default();
@@ -429,21 +657,11 @@ mod tests {
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    #[ignore]
     async fn tree_batch_insert() {
-        let mut rng = Xoshiro256StarStar::seed_from_u64(6784236783546783546u64);
-        let ipfs = IpfsService::default();
-
-        let mut config = Config::default();
-        let mut strat = HashThreshold::default();
-        strat.chunking_factor = 1 << 19;
-        config.chunking_strategy = Strategies::Threshold(strat);
+        let ipfs = MockIpfs::new().service();
+        let config = config();
 
-        let original_batch = unique_random_sorted_pairs::<32>(10_000, &mut rng);
-
-        //Run first test to generate the prolly tree
-        let tree_cid =
-            Cid::try_from("bafyreiacttehgexdhblgzfcco2chzf64s6x3e6asyzhyr4qhh2vmwkaiwu").unwrap();
+        let (tree_cid, original_batch) = seeded_tree(&ipfs, &config).await;
 
         let mut rng = Xoshiro256StarStar::from_entropy();
 
@@ -493,21 +711,11 @@ mod tests {
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    #[ignore]
     async fn tree_batch_remove() {
-        let mut rng = Xoshiro256StarStar::seed_from_u64(6784236783546783546u64);
-        let ipfs = IpfsService::default();
-
-        let mut config = Config::default();
-        let mut strat = HashThreshold::default();
-        strat.chunking_factor = 1 << 19;
-        config.chunking_strategy = Strategies::Threshold(strat);
+        let ipfs = MockIpfs::new().service();
+        let config = config();
 
-        let mut batch = unique_random_sorted_pairs::<32>(10_000, &mut rng);
-
-        //Run first test to generate the prolly tree
-        let tree_cid =
-            Cid::try_from("bafyreiacttehgexdhblgzfcco2chzf64s6x3e6asyzhyr4qhh2vmwkaiwu").unwrap();
+        let (tree_cid, mut batch) = seeded_tree(&ipfs, &config).await;
 
         let mut rng = Xoshiro256StarStar::from_entropy();
 
@@ -552,24 +760,147 @@ mod tests {
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    #[ignore]
-    async fn tree_remove_all() {
-        let mut rng = Xoshiro256StarStar::seed_from_u64(6784236783546783546u64);
-        let ipfs = IpfsService::default();
+    async fn tree_batch_remove_range() {
+        let ipfs = MockIpfs::new().service();
+        let config = config();
 
-        let mut config = Config::default();
-        let mut strat = HashThreshold::default();
-        strat.chunking_factor = 1 << 19;
-        config.chunking_strategy = Strategies::Threshold(strat);
+        let (tree_cid, batch) = seeded_tree(&ipfs, &config).await;
 
-        let batch = unique_random_sorted_pairs::<32>(10_000, &mut rng);
-        let keys: Vec<_> = batch.into_iter().map(|(key, _)| key).collect();
+        // Start included, end excluded.
+        let range = batch[1_000].0..batch[3_000].0;
+
+        let tree_cid = batch_remove_range::<u16, DataBlob>(
+            ipfs.clone(),
+            tree_cid,
+            config.clone(),
+            range.clone(),
+        )
+        .await
+        .expect("Range remove");
+
+        // Start excluded, end included.
+        let bounds = (
+            Bound::Excluded(batch[5_000].0),
+            Bound::Included(batch[7_000].0),
+        );
 
-        //Run first test to generate the prolly tree
-        let empty_tree_cid =
-            Cid::try_from("bafyreiekfsw2g3fbdebzwf4equw2kygz6blz7uxtyvyd36xokibnd6hvgi").unwrap();
         let tree_cid =
-            Cid::try_from("bafyreiacttehgexdhblgzfcco2chzf64s6x3e6asyzhyr4qhh2vmwkaiwu").unwrap();
+            batch_remove_range::<u16, DataBlob>(ipfs.clone(), tree_cid, config.clone(), bounds)
+                .await
+                .expect("Range remove");
+
+        let result: Vec<_> = stream_pairs::<u16, DataBlob>(ipfs, tree_cid, config.codec)
+            .try_collect()
+            .await
+            .expect("Tree Streaming");
+
+        let expected: Vec<_> = batch
+            .iter()
+            .filter(|(key, _)| !range.contains(key) && !bounds.contains(key))
+            .cloned()
+            .collect();
+
+        assert_eq!(result, expected);
+
+        let result_keys: Vec<_> = result.into_iter().map(|(key, _)| key).collect();
+
+        assert!(!result_keys.contains(&batch[1_000].0));
+        assert!(result_keys.contains(&batch[3_000].0));
+        assert!(result_keys.contains(&batch[5_000].0));
+        assert!(!result_keys.contains(&batch[7_000].0));
+        assert_eq!(result_keys.len(), batch.len() - 2_000 - 2_000);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn tree_cursor_resume() {
+        let ipfs = MockIpfs::new().service();
+        let config = config();
+
+        let (tree_cid, batch) = seeded_tree(&ipfs, &config).await;
+
+        let full: Vec<_> = stream_range::<u16, DataBlob>(ipfs.clone(), tree_cid, config.codec, ..)
+            .try_collect()
+            .await
+            .expect("Full range");
+
+        assert_eq!(full, batch);
+
+        // Pages end mid node, without duplicates or gaps.
+        let mut cursor = Cursor::default();
+        let mut pages = Vec::with_capacity(full.len());
+
+        loop {
+            let page: Vec<_> = stream_range::<u16, DataBlob>(
+                ipfs.clone(),
+                tree_cid,
+                config.codec,
+                cursor.remaining(),
+            )
+            .take(777)
+            .try_collect()
+            .await
+            .expect("Page");
+
+            let Some((last, _)) = page.last() else {
+                break;
+            };
+
+            cursor.advance(last);
+            pages.extend(page);
+        }
+
+        assert_eq!(pages, full);
+
+        let mut cursor = Cursor::default();
+
+        let first: Vec<_> =
+            stream_range::<u16, DataBlob>(ipfs.clone(), tree_cid, config.codec, cursor.remaining())
+                .take(1_000)
+                .try_collect()
+                .await
+                .expect("First page");
+
+        assert_eq!(&first[..], &batch[..1_000]);
+
+        cursor.advance(&first.last().unwrap().0);
+
+        // The root changes between pages
+        let removed = batch[500].0..batch[2_000].0;
+        let tree_cid = batch_remove_range::<u16, DataBlob>(
+            ipfs.clone(),
+            tree_cid,
+            config.clone(),
+            removed.clone(),
+        )
+        .await
+        .expect("Range remove");
+
+        let rest: Vec<_> =
+            stream_range::<u16, DataBlob>(ipfs, tree_cid, config.codec, cursor.remaining())
+                .try_collect()
+                .await
+                .expect("Next pages");
+
+        let expected: Vec<_> = batch
+            .into_iter()
+            .skip(1_000)
+            .filter(|(key, _)| !removed.contains(key))
+            .collect();
+
+        assert_eq!(rest, expected);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn tree_remove_all() {
+        let ipfs = MockIpfs::new().service();
+        let config = config();
+
+        let empty_tree_cid = empty_root(&ipfs, &config).await;
+        let (tree_cid, batch) = seeded_tree(&ipfs, &config).await;
+
+        assert_ne!(tree_cid, empty_tree_cid);
+
+        let keys: Vec<_> = batch.into_iter().map(|(key, _)| key).collect();
 
         let result = batch_remove::<u16, DataBlob>(ipfs, tree_cid, config, keys)
             .await
@@ -646,6 +977,40 @@ mod tests {
 
     type DataBlob = Vec<u8>;
 
+    fn config() -> Config {
+        let mut config = Config::default();
+        let mut strat = HashThreshold::default();
+        strat.chunking_factor = 1 << 19;
+        config.chunking_strategy = Strategies::Threshold(strat);
+
+        config
+    }
+
+    async fn empty_root(ipfs: &IpfsService, config: &Config) -> Cid {
+        let node = TreeNode::<u16, Leaf<DataBlob>>::default();
+        let node = TreeNodes::Leaf(node);
+
+        ipfs.dag_put(&node, config.codec, config.codec)
+            .await
+            .expect("Root node")
+    }
+
+    /// The tree of `tree_stream_all`, built in place of a fixture root.
+    async fn seeded_tree(ipfs: &IpfsService, config: &Config) -> (Cid, Vec<(u16, DataBlob)>) {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(6784236783546783546u64);
+
+        let batch = unique_random_sorted_pairs::<32>(10_000, &mut rng);
+
+        let root = empty_root(ipfs, config).await;
+
+        let tree_cid =
+            batch_insert::<u16, DataBlob>(ipfs.clone(), root, config.clone(), batch.clone())
+                .await
+                .expect("Batch insert");
+
+        (tree_cid, batch)
+    }
+
     fn unique_random_sorted_pairs<const T: usize>(
         numb: usize,
         rng: &mut Xoshiro256StarStar,
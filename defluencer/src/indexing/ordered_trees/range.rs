@@ -0,0 +1,92 @@
+use std::ops::Bound;
+
+/// Returns true if every key of the node range is also in the range.
+///
+/// Conservative, node ranges whose keys are unknown are never covered.
+pub fn covers<K: Ord>(range: &(Bound<K>, Bound<K>), node_range: &(Bound<K>, Bound<K>)) -> bool {
+    let start = match (&range.0, &node_range.0) {
+        (Bound::Unbounded, _) => true,
+        (_, Bound::Unbounded) => false,
+        (Bound::Excluded(start), Bound::Included(node_start)) => start < node_start,
+        (Bound::Included(start), Bound::Included(node_start))
+        | (Bound::Included(start), Bound::Excluded(node_start))
+        | (Bound::Excluded(start), Bound::Excluded(node_start)) => start <= node_start,
+    };
+
+    let end = match (&range.1, &node_range.1) {
+        (Bound::Unbounded, _) => true,
+        (_, Bound::Unbounded) => false,
+        (Bound::Excluded(end), Bound::Included(node_end)) => end > node_end,
+        (Bound::Included(end), Bound::Included(node_end))
+        | (Bound::Included(end), Bound::Excluded(node_end))
+        | (Bound::Excluded(end), Bound::Excluded(node_end)) => end >= node_end,
+    };
+
+    start && end
+}
+
+/// Returns true if no key of the node range can be in the range.
+///
+/// Conservative, overlapping ranges may contain no keys at all.
+pub fn disjoint<K: Ord>(range: &(Bound<K>, Bound<K>), node_range: &(Bound<K>, Bound<K>)) -> bool {
+    let below = match (&range.1, &node_range.0) {
+        (Bound::Unbounded, _) | (_, Bound::Unbounded) => false,
+        (Bound::Included(end), Bound::Included(node_start)) => end < node_start,
+        (Bound::Included(end), Bound::Excluded(node_start))
+        | (Bound::Excluded(end), Bound::Included(node_start))
+        | (Bound::Excluded(end), Bound::Excluded(node_start)) => end <= node_start,
+    };
+
+    let above = match (&range.0, &node_range.1) {
+        (Bound::Unbounded, _) | (_, Bound::Unbounded) => false,
+        (Bound::Included(start), Bound::Included(node_end)) => start > node_end,
+        (Bound::Included(start), Bound::Excluded(node_end))
+        | (Bound::Excluded(start), Bound::Included(node_end))
+        | (Bound::Excluded(start), Bound::Excluded(node_end)) => start >= node_end,
+    };
+
+    below || above
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::ops::RangeBounds;
+
+    fn bounds(range: impl RangeBounds<u8>) -> (Bound<u8>, Bound<u8>) {
+        (range.start_bound().cloned(), range.end_bound().cloned())
+    }
+
+    #[test]
+    fn range_cover() {
+        let range = bounds(10..20);
+
+        assert!(covers(&range, &bounds(10..20)));
+        assert!(covers(&range, &bounds(12..=19)));
+        assert!(!covers(&range, &bounds(12..=20)));
+        assert!(!covers(&range, &bounds(9..15)));
+        assert!(!covers(&range, &bounds(15..)));
+        assert!(covers(&bounds(..), &bounds(15..)));
+
+        let node = (Bound::Excluded(10), Bound::Excluded(20));
+        assert!(covers(&range, &node));
+        assert!(!covers(&bounds(11..20), &node));
+    }
+
+    #[test]
+    fn range_disjoint() {
+        let range = bounds(10..20);
+
+        assert!(disjoint(&range, &bounds(20..30)));
+        assert!(disjoint(&range, &bounds(..10)));
+        assert!(disjoint(&range, &bounds(0..=9)));
+        assert!(!disjoint(&range, &bounds(0..=10)));
+        assert!(!disjoint(&range, &bounds(19..)));
+        assert!(!disjoint(&range, &bounds(..)));
+
+        let node = (Bound::Excluded(19), Bound::Unbounded);
+        assert!(disjoint(&bounds(10..=19), &node));
+        assert!(!disjoint(&bounds(10..=20), &node));
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+/// Changes to an ordered tree accumulated in memory.
+///
+/// Later changes to a key replace earlier ones, committing writes the final
+/// state of each key only once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Staging<K, V> {
+    changes: BTreeMap<K, Option<V>>,
+}
+
+impl<K, V> Default for Staging<K, V> {
+    fn default() -> Self {
+        Self {
+            changes: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Staging<K, V> {
+    pub fn insert(&mut self, key: K, value: V) {
+        self.changes.insert(key, Some(value));
+    }
+
+    pub fn batch_insert(&mut self, key_values: impl IntoIterator<Item = (K, V)>) {
+        for (key, value) in key_values {
+            self.insert(key, value);
+        }
+    }
+
+    pub fn remove(&mut self, key: K) {
+        self.changes.insert(key, None);
+    }
+
+    pub fn batch_remove(&mut self, keys: impl IntoIterator<Item = K>) {
+        for key in keys {
+            self.remove(key);
+        }
+    }
+
+    /// Staged change of this key.
+    ///
+    /// Returns ```None``` if unchanged, ```Some(None)``` if removed.
+    pub fn get(&self, key: &K) -> Option<Option<&V>> {
+        self.changes.get(key).map(Option::as_ref)
+    }
+
+    /// Number of keys changed.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Drop all staged changes.
+    pub fn clear(&mut self) {
+        self.changes.clear()
+    }
+
+    /// Sorted pairs to insert and keys to remove.
+    pub(crate) fn batches(&self) -> (Vec<(K, V)>, Vec<K>) {
+        let mut inserts = Vec::new();
+        let mut removes = Vec::new();
+
+        for (key, change) in self.changes.iter() {
+            match change {
+                Some(value) => inserts.push((key.clone(), value.clone())),
+                None => removes.push(key.clone()),
+            }
+        }
+
+        (inserts, removes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_change_wins() {
+        let mut staging = Staging::default();
+
+        staging.batch_insert([(3u8, 30u8), (1, 10), (2, 20)]);
+        staging.remove(2);
+        staging.insert(1, 11);
+        staging.batch_remove([4, 5]);
+        staging.insert(5, 50);
+
+        assert_eq!(staging.len(), 5);
+        assert_eq!(staging.get(&1), Some(Some(&11)));
+        assert_eq!(staging.get(&2), Some(None));
+        assert_eq!(staging.get(&6), None);
+
+        let (inserts, removes) = staging.batches();
+
+        assert_eq!(inserts, vec![(1, 11), (3, 30), (5, 50)]);
+        assert_eq!(removes, vec![2, 4]);
+
+        staging.clear();
+
+        assert!(staging.is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+use std::ops::Range;
+
+use cid::Cid;
+
+use linked_data::types::IPNSAddress;
+
+/// Separate the topic from the channel address in registry keys.
+const SEPARATOR: u8 = 0x00;
+
+/// Return the registry tree key for this topic and channel.
+///
+/// Keys are ordered by topic first then by channel address.
+pub fn listing_key(topic: &str, channel: IPNSAddress) -> Vec<u8> {
+    let topic = normalize_topic(topic);
+    let cid: Cid = channel.into();
+
+    let mut key = topic.into_bytes();
+    key.push(SEPARATOR);
+    key.extend(cid.to_bytes());
+
+    key
+}
+
+/// Return the prefix shared by all the keys of this topic.
+pub fn topic_prefix(topic: &str) -> Vec<u8> {
+    let mut prefix = normalize_topic(topic).into_bytes();
+    prefix.push(SEPARATOR);
+
+    prefix
+}
+
+/// Return the range of all the keys of this topic.
+pub fn topic_range(topic: &str) -> Range<Vec<u8>> {
+    let start = topic_prefix(topic);
+
+    let mut end = start.clone();
+    if let Some(last) = end.last_mut() {
+        *last += 1;
+    }
+
+    start..end
+}
+
+/// Split a registry tree key into topic and channel address.
+pub fn split_listing_key(key: &[u8]) -> Option<(String, IPNSAddress)> {
+    let idx = key.iter().position(|byte| *byte == SEPARATOR)?;
+
+    let topic = std::str::from_utf8(&key[..idx]).ok()?.to_owned();
+    let cid = Cid::try_from(&key[idx + 1..]).ok()?;
+    let addr = IPNSAddress::try_from(cid).ok()?;
+
+    Some((topic, addr))
+}
+
+/// Topics are case insensitive and trimmed.
+pub fn normalize_topic(topic: &str) -> String {
+    topic.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_roundtrip() {
+        let addr =
+            IPNSAddress::try_from("bafzbeiegbnjh5uopd5vc22tgkz6chf7a6ala3x5e47vnhv5sq5bzo46tri")
+                .unwrap();
+
+        let key = listing_key(" Music ", addr);
+
+        assert!(key.starts_with(&topic_prefix("music")));
+
+        let (topic, channel) = split_listing_key(&key).unwrap();
+
+        assert_eq!(topic, "music");
+        assert_eq!(channel, addr);
+    }
+
+    #[test]
+    fn topic_keys_in_range() {
+        let addr =
+            IPNSAddress::try_from("bafzbeiegbnjh5uopd5vc22tgkz6chf7a6ala3x5e47vnhv5sq5bzo46tri")
+                .unwrap();
+
+        let range = topic_range("music");
+
+        assert!(range.contains(&listing_key("Music", addr)));
+        assert!(!range.contains(&listing_key("music videos", addr)));
+        assert!(!range.contains(&listing_key("musi", addr)));
+    }
+}
@@ -0,0 +1,148 @@
+use cid::Cid;
+
+use linked_data::types::IPNSAddress;
+
+/// Maximum slug length in bytes, before collision suffixes.
+pub const MAX_SLUG_LENGTH: usize = 64;
+
+/// Slug of untitled content or titles without any letters or digits.
+const UNTITLED: &str = "content";
+
+/// Return the slug of a title, lowercase words separated by dashes.
+///
+/// Only ASCII letters and digits are kept, other characters separate words.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len().min(MAX_SLUG_LENGTH));
+
+    for word in title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let sep = usize::from(!slug.is_empty());
+
+        if slug.len() + sep + word.len() > MAX_SLUG_LENGTH {
+            // Long first words are cut, others are dropped.
+            if slug.is_empty() {
+                slug.push_str(&word[..MAX_SLUG_LENGTH]);
+            }
+
+            break;
+        }
+
+        if sep == 1 {
+            slug.push('-');
+        }
+
+        slug.push_str(word);
+    }
+
+    if slug.is_empty() {
+        return UNTITLED.to_owned();
+    }
+
+    slug.make_ascii_lowercase();
+
+    slug
+}
+
+/// Return the nth candidate for a slug, the slug itself first.
+///
+/// Titles colliding with existing slugs get numbered, starting at 2.
+pub fn candidate(slug: &str, n: usize) -> String {
+    match n {
+        0 | 1 => slug.to_owned(),
+        n => format!("{}-{}", slug, n),
+    }
+}
+
+/// Return the permalink path of content, e.g. /ipns/<channel>/content/<slug>
+pub fn permalink_path(addr: IPNSAddress, slug: &str) -> String {
+    format!("/ipns/{}/content/{}", addr, slug)
+}
+
+/// Split a permalink path into channel address and slug.
+///
+/// The ipns prefix is optional.
+pub fn parse_permalink(path: &str) -> Option<(IPNSAddress, String)> {
+    let path = path.trim_matches('/');
+    let path = path.strip_prefix("ipns/").unwrap_or(path);
+
+    let (addr, rest) = path.split_once('/')?;
+    let slug = rest.strip_prefix("content/")?;
+
+    if slug.is_empty() || slug.contains('/') {
+        return None;
+    }
+
+    let addr = IPNSAddress::try_from(addr).ok()?;
+
+    Some((addr, slug.to_owned()))
+}
+
+/// Return the permalink path of a comment, e.g. /ipns/<channel>/content/<slug>/comments/<CID>
+pub fn comment_permalink_path(addr: IPNSAddress, slug: &str, comment_cid: Cid) -> String {
+    format!("{}/comments/{}", permalink_path(addr, slug), comment_cid)
+}
+
+/// Split a comment permalink path into channel address, content slug and comment CID.
+pub fn parse_comment_permalink(path: &str) -> Option<(IPNSAddress, String, Cid)> {
+    let (content, comment) = path.trim_end_matches('/').rsplit_once("/comments/")?;
+
+    let (addr, slug) = parse_permalink(content)?;
+
+    let comment_cid = Cid::try_from(comment).ok()?;
+
+    Some((addr, slug, comment_cid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Rust 2021 -- Édition "), "rust-2021-dition");
+        assert_eq!(slugify("???"), "content");
+        assert_eq!(slugify(&"a".repeat(100)).len(), MAX_SLUG_LENGTH);
+
+        let long = format!("{} {}", "a".repeat(60), "word");
+        assert_eq!(slugify(&long), "a".repeat(60));
+
+        assert_eq!(candidate("hello", 0), "hello");
+        assert_eq!(candidate("hello", 1), "hello");
+        assert_eq!(candidate("hello", 3), "hello-3");
+    }
+
+    #[test]
+    fn permalinks() {
+        let addr =
+            IPNSAddress::try_from("bafzbeiegbnjh5uopd5vc22tgkz6chf7a6ala3x5e47vnhv5sq5bzo46tri")
+                .unwrap();
+
+        let path = permalink_path(addr, "hello-world");
+
+        assert_eq!(parse_permalink(&path), Some((addr, "hello-world".into())));
+
+        let short = format!("{}/content/hello-world/", addr);
+        assert_eq!(parse_permalink(&short), Some((addr, "hello-world".into())));
+
+        assert_eq!(parse_permalink(&format!("/ipns/{}/hello", addr)), None);
+        assert_eq!(parse_permalink(&format!("/ipns/{}/content/", addr)), None);
+
+        let comment_cid = Cid::new_v1(0x71, Code::Sha2_256.digest(b"comment"));
+
+        let path = comment_permalink_path(addr, "hello-world", comment_cid);
+
+        assert_eq!(parse_permalink(&path), None);
+        assert_eq!(
+            parse_comment_permalink(&path),
+            Some((addr, "hello-world".into(), comment_cid))
+        );
+
+        let uncommented = format!("/ipns/{}/content/hello-world/comments/", addr);
+        assert_eq!(parse_comment_permalink(&uncommented), None);
+    }
+}
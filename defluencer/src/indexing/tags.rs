@@ -0,0 +1,28 @@
+use cid::Cid;
+
+use multihash::{Code, MultihashDigest};
+
+/// Return a tag as indexed, trimmed and lowercase.
+pub fn normalize(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Return the key of a tag in the tag index.
+///
+/// Tags differing only by case or surrounding spaces share a key.
+pub fn tag_key(tag: &str) -> Cid {
+    Cid::new_v1(0x55, Code::Sha2_256.digest(normalize(tag).as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_keys() {
+        assert_eq!(normalize(" Live Music "), "live music");
+
+        assert_eq!(tag_key("Rust"), tag_key(" rust"));
+        assert_ne!(tag_key("rust"), tag_key("rustacean"));
+    }
+}
@@ -1,40 +1,180 @@
+#[cfg(feature = "daemons")]
+pub mod aggregation;
+
+pub mod analytics;
+pub mod canonical;
 pub mod channel;
+pub mod chat;
+
+#[cfg(feature = "claims")]
+pub mod claims;
+
+pub mod comments;
+pub mod crawl;
+
+#[cfg(feature = "ordered-trees")]
+pub mod crawl_snapshot;
+
 pub mod crypto;
+pub mod dash;
+pub mod dedup;
+
+pub mod denylist;
+
+pub mod diff;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "dm"))]
+pub mod dm;
+
+#[cfg(feature = "daemons")]
+pub mod erasure;
+
 pub mod errors;
+pub mod events;
+pub mod filter;
+pub mod gateway;
+pub mod gossip;
+
+#[cfg(feature = "ordered-trees")]
+pub mod history;
+
+#[cfg(all(feature = "daemons", feature = "ordered-trees"))]
+pub mod hub;
+
+#[cfg(feature = "import")]
+pub mod import;
+
 pub mod indexing;
+pub mod limits;
+pub mod live;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod probe;
+
+pub mod rate_limit;
+pub mod recovery;
+
+#[cfg(feature = "daemons")]
+pub mod reputation;
+
+pub mod resolver;
+pub mod timestamp;
 pub mod user;
 pub mod utils;
+pub mod watch;
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    iter,
+    time::Duration,
+};
+
+#[cfg(feature = "daemons")]
+use aggregation::Aggregator;
+
+use analytics::Analytics;
+
+use channel::{
+    succession::{succession_topic, verify_claim},
+    threshold::{proposal_topic, verify_update},
+};
 
-use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Utc};
 
 use cid::Cid;
 
+use comments::{
+    comments_topic, load_media_comments, media_comments, verify_comment, CommentPolicy,
+    VerifiedComment,
+};
+
+use crawl::{skip_timeout, CrawlCheckpoint, CrawlEvent, CrawlState};
+
+#[cfg(feature = "ordered-trees")]
+use crawl_snapshot::{CrawlRecord, GraphValue};
+
+use denylist::Denylist;
+
+use diff::ChannelChange;
+
 use errors::Error;
 
+use events::{rsvp_topic, verify_rsvp};
+
+use filter::Filter;
+
+use gateway::Gateway;
+
+use gossip::{decode_gossip, encode_gossip, gossip_topic, GossipCache};
+
 use futures::{
-    stream::{self, FuturesUnordered},
+    future::{self, Either},
+    pin_mut,
+    stream::{self, FuturesUnordered, LocalBoxStream},
     Stream, StreamExt, TryStreamExt,
 };
 
-use indexing::hamt;
+use indexing::{bloom, datetime, hamt, tags};
+
+#[cfg(feature = "ordered-trees")]
+use indexing::{
+    hub as hub_keys,
+    ordered_trees::{cursor::Cursor, prolly::ProllyTree},
+    registry, slug,
+};
 
 use ipns_records::IPNSRecord;
+
+#[cfg(feature = "daemons")]
+use libipld_core::ipld::Ipld;
+
 use linked_data::{
-    channel::{follows::Follows, ChannelMetadata},
+    channel::{
+        archive::ArchiveRecord,
+        follows::{FollowLists, Follows},
+        live::LiveSettings,
+        owners::Owners,
+        playlists::{Playlist, Playlists},
+        snapshot::Snapshot,
+        succession::SuccessionClaim,
+        ChannelMetadata,
+    },
     identity::Identity,
-    indexes::date_time::*,
-    media::Media,
-    types::{IPLDLink, IPNSAddress},
+    indexes::{bloom::BloomFilter, date_time::*},
+    media::{
+        chat::{ChatMessage, ChatSettings},
+        comments::{
+            ArchivedComments, Comment, CommentChunk, CommentCount, CommentPermission,
+            CommentSettings, Voters,
+        },
+        event::Attendees,
+        video::{Day, Hour, Minute, Segment, Setup, Timecode, Video},
+        Media, Variant, Variants,
+    },
+    types::{IPLDLink, IPNSAddress, PeerId},
 };
 
+#[cfg(feature = "claims")]
+use linked_data::identity::Claim;
+
 use ipfs_api::{
+    pubsub::{Backoff, PubSubEvent},
     responses::{Codec, PubSubMessage},
     IpfsService,
 };
 
+use resolver::NameResolver;
+
+use watch::{ChannelEvent, LiveState};
+
+/// Delay allowed to find a submitted block, submitters choose which CIDs are looked up.
+const SUBMISSION_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Default, Clone)]
 pub struct Defluencer {
     ipfs: IpfsService,
+
+    resolver: Option<NameResolver>,
 }
 
 impl Into<IpfsService> for Defluencer {
@@ -45,29 +185,153 @@ impl Into<IpfsService> for Defluencer {
 
 impl From<IpfsService> for Defluencer {
     fn from(ipfs: IpfsService) -> Self {
-        Self { ipfs }
+        Self {
+            ipfs,
+            resolver: None,
+        }
     }
 }
 
 impl Defluencer {
+    /// Resolve IPNS names with retries and fallback endpoints instead of only the local node.
+    pub fn with_resolver(mut self, resolver: NameResolver) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    async fn name_resolve(&self, addr: IPNSAddress) -> Result<Cid, Error> {
+        match self.resolver.as_ref() {
+            Some(resolver) => resolver.resolve(addr).await,
+            None => Ok(self.ipfs.name_resolve(addr).await?),
+        }
+    }
+
     /// Pin a channel to this local node.
     ///
+    /// Nothing is pinned if any block of the channel is denied, the first one found is returned.
+    ///
     /// WARNING!
     /// This function pin ALL content from the channel.
     /// The amout of data downloaded could be massive.
-    pub async fn pin_channel(&self, ipns: IPNSAddress) -> Result<(), Error> {
-        let cid = self.ipfs.name_resolve(ipns.into()).await?;
+    pub async fn pin_channel(&self, ipns: IPNSAddress, denylist: &Denylist) -> Result<(), Error> {
+        let cid = self.name_resolve(ipns.into()).await?;
+
+        if !denylist.is_empty() {
+            let refs = self.ipfs.refs(cid).await?;
+
+            if let Some(denied) = iter::once(cid)
+                .chain(refs)
+                .find(|cid| denylist.contains(*cid))
+            {
+                return Err(Error::Denied(denied));
+            }
+        }
 
         self.ipfs.pin_add(cid, true).await?;
 
         Ok(())
     }
 
+    /// Pin a channel's content created between these dates to this local node.
+    ///
+    /// The metadata, identity and the index nodes leading to this content are also pinned,
+    /// with its comments, upvotes and records. The rest of the channel is not.
+    ///
+    /// Content linking to denied CIDs is skipped, a denied channel or identity is an error.
+    ///
+    /// Returns the CIDs of the content pinned then those skipped.
+    pub async fn pin_channel_range(
+        &self,
+        ipns: IPNSAddress,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        denylist: &Denylist,
+    ) -> Result<(Vec<Cid>, Vec<Cid>), Error> {
+        let cid = self.name_resolve(ipns.into()).await?;
+
+        if denylist.contains(cid) {
+            return Err(Error::Denied(cid));
+        }
+
+        let metadata = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+            .await?;
+
+        if let Some(denied) = denylist
+            .first_denied(&self.ipfs, metadata.identity.link, 1)
+            .await?
+        {
+            return Err(Error::Denied(denied));
+        }
+
+        self.ipfs.pin_add(cid, false).await?;
+        self.ipfs.pin_add(metadata.identity.link, true).await?;
+
+        let index = match metadata.content_index {
+            Some(index) => index,
+            None => return Ok((vec![], vec![])),
+        };
+
+        let (nodes, range) = datetime::range(&self.ipfs, index, from, to).await?;
+
+        for node in nodes {
+            self.ipfs.pin_add(node, false).await?;
+        }
+
+        let mut content = Vec::with_capacity(range.len());
+        let mut denied = Vec::new();
+
+        for cid in range {
+            // The signed link, the media and what it links to.
+            if denylist.first_denied(&self.ipfs, cid, 2).await?.is_some() {
+                denied.push(cid);
+                continue;
+            }
+
+            self.ipfs.pin_add(cid, true).await?;
+
+            content.push(cid);
+        }
+
+        let mut nodes = HashSet::new();
+
+        for (index, recursive) in [
+            (metadata.comment_index, true),
+            (metadata.comment_archive, true),
+            (metadata.comment_counts, true),
+            (metadata.upvote_index, true),
+            (metadata.archive_index, true),
+            // Variants link to other content, maybe out of range.
+            (metadata.variant_index, false),
+        ] {
+            let Some(index) = index else {
+                continue;
+            };
+
+            for cid in content.iter() {
+                let (path, value) = hamt::path(&self.ipfs, index, *cid).await?;
+
+                nodes.extend(path);
+
+                if let Some(value) = value {
+                    self.ipfs.pin_add(value, recursive).await?;
+                }
+            }
+        }
+
+        for node in nodes {
+            self.ipfs.pin_add(node, false).await?;
+        }
+
+        Ok((content, denied))
+    }
+
     /// Unpin a channel from this local node.
     ///
     /// This function unpin everyting; metadata, content, comment, etc...
     pub async fn unpin_channel(&self, ipns: IPNSAddress) -> Result<(), Error> {
-        let cid = self.ipfs.name_resolve(ipns.into()).await?;
+        let cid = self.name_resolve(ipns.into()).await?;
 
         self.ipfs.pin_rm(cid, true).await?;
 
@@ -98,172 +362,1097 @@ impl Defluencer {
             })
     }
 
-    /// Subscribe to a channel.
+    /// Receive updates from the agregation channel, without duplicates, denied or stale content.
     ///
-    /// Return CID of the latest channel metadata.
-    pub fn subscribe_channel_updates(
-        &self,
-        channel_addr: IPNSAddress,
-    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
-        let topic = channel_addr.to_pubsub_topic();
+    /// Dropped submissions are counted by the aggregator.
+    /// Submissions not found within ```SUBMISSION_TIMEOUT``` are skipped.
+    #[cfg(feature = "daemons")]
+    pub fn subscribe_aggregator<'a>(
+        &'a self,
+        topic: String,
+        aggregator: &'a Aggregator,
+    ) -> impl Stream<Item = Result<Cid, Error>> + 'a {
+        self.ipfs
+            .pubsub_sub(topic.into_bytes())
+            .err_into()
+            .try_filter_map(move |msg| async move {
+                let PubSubMessage { from: _, data } = msg;
 
-        let latest_channel_cid = Cid::default();
-        let sequence = 0;
+                let cid = match Cid::try_from(data) {
+                    Ok(cid) => cid,
+                    Err(_) => {
+                        aggregator.invalid();
+                        return Ok(None);
+                    }
+                };
 
-        let stream = self.ipfs.pubsub_sub(topic.into_bytes()).boxed_local();
+                if !aggregator.first_seen(cid) {
+                    return Ok(None);
+                }
 
-        stream::try_unfold(
-            (sequence, latest_channel_cid, stream),
-            move |(mut sequence, mut latest_channel_cid, mut stream)| async move {
-                loop {
-                    let msg = match stream.try_next().await? {
-                        Some(msg) => msg,
-                        None => return Result::<_, Error>::Ok(None),
-                    };
+                if !aggregator.is_allowed(cid) {
+                    return Ok(None);
+                }
 
-                    let PubSubMessage { from: _, data } = msg;
+                // Check the size before downloading the whole node.
+                match self.submission_size(cid).await {
+                    Some(size) if aggregator.is_small(size) => {}
+                    _ => return Ok(None),
+                }
 
-                    let record = IPNSRecord::from_bytes(&data)?;
+                let ipld = match self
+                    .ipfs
+                    .dag_get::<&str, Ipld>(cid, None, Codec::default())
+                    .await
+                {
+                    Ok(ipld) => ipld,
+                    Err(e) if e.is_decode() => {
+                        aggregator.invalid();
+                        return Ok(None);
+                    }
+                    Err(e) => return Err(e.into()),
+                };
 
-                    let seq = record.get_sequence();
+                // Media linking to denied content is denied too.
+                if !denylist::links(&ipld)
+                    .into_iter()
+                    .all(|link| aggregator.is_allowed(link))
+                {
+                    return Ok(None);
+                }
 
-                    if sequence >= seq {
-                        continue;
+                let media: Media = match libipld_core::serde::from_ipld(ipld) {
+                    Ok(media) => media,
+                    Err(_) => {
+                        aggregator.invalid();
+                        return Ok(None);
                     }
+                };
 
-                    let cid = record.get_value();
+                match aggregator.is_fresh(media.user_timestamp(), Utc::now().timestamp()) {
+                    true => Ok(Some(cid)),
+                    false => Ok(None),
+                }
+            })
+    }
 
-                    if latest_channel_cid == cid {
-                        continue;
-                    }
+    /// Receive the valid succession claims of a channel, given the latest metadata observed.
+    ///
+    /// Invalid claims are dropped.
+    pub fn subscribe_succession_claims(
+        &self,
+        addr: IPNSAddress,
+        latest: Cid,
+    ) -> impl Stream<Item = Result<SuccessionClaim, Error>> + '_ {
+        self.ipfs
+            .pubsub_sub(succession_topic(addr).into_bytes())
+            .err_into()
+            .try_filter_map(move |msg| async move {
+                let PubSubMessage { from: _, data } = msg;
 
-                    if record.verify(channel_addr.into()).is_err() {
-                        continue;
-                    }
+                let cid = match Cid::try_from(data) {
+                    Ok(cid) => cid,
+                    Err(_) => return Ok(None),
+                };
 
-                    sequence = seq;
-                    latest_channel_cid = cid;
+                let timestamp = Utc::now().timestamp();
 
-                    return Ok(Some((
-                        latest_channel_cid,
-                        (sequence, latest_channel_cid, stream),
-                    )));
+                match verify_claim(&self.ipfs, addr, latest, cid, timestamp).await {
+                    Ok(claim) => Ok(Some(claim)),
+                    Err(Error::Succession) => Ok(None),
+                    Err(e) => Err(e),
                 }
-            },
-        )
+            })
     }
 
-    /// Returns all followees channels on the social web without duplicates.
+    /// Submit a signed listing to a registry's aggregation topic.
     ///
-    /// WARNING! This search will crawl the entire web. Limiting the number of result is best.
-    pub fn streaming_web_crawl(
+    /// The registry operator decide if it should be added.
+    pub async fn submit_listing(&self, topic: String, listing_cid: Cid) -> Result<(), Error> {
+        self.ipfs
+            .pubsub_pub(topic.into_bytes(), listing_cid.to_bytes())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Submit content to a hub's aggregation topic.
+    ///
+    /// The hub moderators decide if it should be added.
+    pub async fn submit_to_hub(&self, topic: String, content_cid: Cid) -> Result<(), Error> {
+        self.ipfs
+            .pubsub_pub(topic.into_bytes(), content_cid.to_bytes())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Submit content to the aggregation topic of the hub at this address.
+    ///
+    /// Used by streaming nodes to announce a live beacon, see `live::live_beacon`.
+    pub async fn announce_to_hub(&self, hub: IPNSAddress, content_cid: Cid) -> Result<(), Error> {
+        let cid = self.ipfs.name_resolve(hub).await?;
+
+        let metadata = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+            .await?;
+
+        let Some(topic) = metadata.agregation_channel else {
+            return Err(Error::NotFound);
+        };
+
+        self.submit_to_hub(topic, content_cid).await
+    }
+
+    /// Submit a signed comment to a channel's comments topic.
+    ///
+    /// Channels running the ingestion daemon add it if it follows their policy.
+    pub async fn submit_comment(
         &self,
-        addresses: impl Iterator<Item = IPNSAddress>,
-    ) -> impl Stream<Item = Result<(Cid, ChannelMetadata), Error>> + '_ {
-        let set = HashSet::new();
+        channel: IPNSAddress,
+        comment_cid: Cid,
+    ) -> Result<(), Error> {
+        self.ipfs
+            .pubsub_pub(comments_topic(channel), comment_cid.to_bytes())
+            .await?;
 
-        let resolve_pool: FuturesUnordered<_> = addresses
-            .into_iter()
-            .map(|addr| self.ipfs.name_resolve(addr.into()))
-            .collect();
+        Ok(())
+    }
 
-        let metadata_pool = FuturesUnordered::<_>::new();
+    /// Receive the comments submitted to a channel that are signed by their commenter and follow the policy.
+    ///
+    /// Other submissions are dropped, as are those not found within ```SUBMISSION_TIMEOUT```.
+    pub fn subscribe_comment_submissions<'a>(
+        &'a self,
+        channel: IPNSAddress,
+        policy: &'a CommentPolicy,
+    ) -> impl Stream<Item = Result<Cid, Error>> + 'a {
+        self.ipfs
+            .pubsub_sub(comments_topic(channel).into_bytes())
+            .err_into()
+            .try_filter_map(move |msg| async move {
+                let PubSubMessage { from: _, data } = msg;
 
-        let follows_pool = FuturesUnordered::<_>::new();
+                let cid = match Cid::try_from(data) {
+                    Ok(cid) => cid,
+                    Err(_) => return Ok(None),
+                };
 
-        stream::try_unfold(
-            (set, resolve_pool, metadata_pool, follows_pool),
-            move |(mut set, mut resolve_pool, mut metadata_pool, mut follows_pool)| async move {
-                loop {
-                    futures_util::select! {
-                        result = resolve_pool.try_next() => {
-                            let cid = match result? {
-                                Some(cid) => cid,
-                                None => continue,
-                            };
+                match self.submission_size(cid).await {
+                    Some(size) if policy.check_size(size).is_ok() => {}
+                    _ => return Ok(None),
+                }
 
-                            if !set.insert(cid) {
-                                continue;
-                            }
+                let link = match self
+                    .ipfs
+                    .dag_get::<&str, IPLDLink>(cid, Some("/link"), Codec::default())
+                    .await
+                {
+                    Ok(link) => link,
+                    Err(e) if e.is_decode() => return Ok(None),
+                    Err(e) => return Err(e.into()),
+                };
 
-                            metadata_pool.push(async move { (cid, self.ipfs.dag_get::<&str, ChannelMetadata>(cid, None, Codec::default()).await) });
-                        },
-                        option = metadata_pool.next() => {
-                             let (cid, metadata) = match option {
-                                Some(mt) => mt,
-                                None => continue,
-                            };
+                match self.submission_size(link.link).await {
+                    Some(size) if policy.check_size(size).is_ok() => {}
+                    _ => return Ok(None),
+                }
 
-                            let metadata = metadata?;
+                let (cid, comment, verified) = match verify_comment(&self.ipfs, cid).await? {
+                    Ok(verified) => verified,
+                    Err(_) => return Ok(None),
+                };
 
-                            if let Some(ipld) = metadata.follows {
-                                follows_pool.push(self.ipfs.dag_get::<&str, Follows>(ipld.link, None, Codec::default()));
-                            }
+                let timestamp = Utc::now().timestamp();
 
-                            let next_item = (cid, metadata.clone());
+                // Rate limited commenters are dropped before resolving their channel.
+                if policy.check_rules(&comment, timestamp).is_err() {
+                    return Ok(None);
+                }
 
-                            return Ok(Some((next_item,
-                                (set, resolve_pool, metadata_pool, follows_pool),
-                            )));
-                        },
-                        result = follows_pool.try_next() => {
-                             let follows = match result? {
-                                Some(fl) => fl,
-                                None => continue,
-                            };
+                let permission = policy.permission(&comment);
+
+                let (commenter, follower) = match permission {
+                    CommentPermission::Followers | CommentPermission::Vouched => {
+                        match self.commenter_channel(&comment, &verified.identity).await? {
+                            Some((addr, metadata)) => {
+                                let follower = permission == CommentPermission::Followers
+                                    && self.metadata_follows(&metadata, channel).await?;
 
-                            for addr in follows.followees {
-                                resolve_pool.push(self.ipfs.name_resolve(addr.into()));
+                                (Some(addr), follower)
                             }
-                        },
-                        complete => return Ok(None),
+                            None => (None, false),
+                        }
                     }
+                    _ => (None, false),
+                };
+
+                if policy
+                    .check_permission(&comment, commenter, follower)
+                    .is_err()
+                {
+                    return Ok(None);
                 }
-            },
-        )
+
+                // Only permitted comments count against the rate limit.
+                match policy.count(&comment, timestamp) {
+                    Ok(()) => Ok(Some(cid)),
+                    Err(_) => Ok(None),
+                }
+            })
     }
 
-    /// Return all the cids and channels of all the identities provided.
-    pub async fn channels_metadata(
+    /// Returns the size of a submitted block, none if it could not be found in time.
+    async fn submission_size(&self, cid: Cid) -> Option<u64> {
+        let stat = self.ipfs.block_stat(cid);
+        pin_mut!(stat);
+
+        let delay = futures_timer::Delay::new(SUBMISSION_TIMEOUT);
+
+        match future::select(stat, delay).await {
+            Either::Left((Ok(size), _)) => Some(size),
+            Either::Left((Err(_), _)) | Either::Right(_) => None,
+        }
+    }
+
+    /// Submit a signed RSVP to a channel's RSVP topic.
+    ///
+    /// Channels running the RSVP daemon count it toward the event attendees.
+    pub async fn submit_rsvp(&self, channel: IPNSAddress, rsvp_cid: Cid) -> Result<(), Error> {
+        self.ipfs
+            .pubsub_pub(rsvp_topic(channel), rsvp_cid.to_bytes())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Receive the RSVPs submitted to a channel that are signed by their attendee.
+    ///
+    /// Other submissions are dropped.
+    pub fn subscribe_rsvp_submissions(
         &self,
-        identities: impl Iterator<Item = &Identity>,
-    ) -> HashMap<Cid, ChannelMetadata> {
-        let stream: FuturesUnordered<_> = identities
-            .filter_map(|identity| {
-                identity
-                    .ipns_addr
-                    .map(|ipns| self.ipfs.name_resolve(ipns.into()))
-            })
-            .collect();
+        channel: IPNSAddress,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        self.ipfs
+            .pubsub_sub(rsvp_topic(channel).into_bytes())
+            .err_into()
+            .try_filter_map(move |msg| async move {
+                let PubSubMessage { from: _, data } = msg;
 
-        stream
-            .filter_map(|result| async move {
-                match result {
-                    Ok(cid) => match self
-                        .ipfs
-                        .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
-                        .await
-                    {
-                        Ok(channel) => Some((cid, channel)),
-                        Err(_) => None,
-                    },
-                    Err(_) => None,
+                let cid = match Cid::try_from(data) {
+                    Ok(cid) => cid,
+                    Err(_) => return Ok(None),
+                };
+
+                match verify_rsvp(&self.ipfs, cid).await? {
+                    Some(_) => Ok(Some(cid)),
+                    None => Ok(None),
                 }
             })
-            .collect()
-            .await
     }
 
-    /// Returns all the cids and identities of all the followees of all the channels provided.
-    pub async fn followees_identity(
+    /// Fetch the records of these channels from the routing system into the gossip cache.
+    ///
+    /// Returns the channels whose root changed, those that cannot be fetched are skipped.
+    pub async fn refresh_gossip(
         &self,
-        channels: impl Iterator<Item = &ChannelMetadata>,
-    ) -> HashMap<Cid, Identity> {
-        let stream: FuturesUnordered<_> = channels
-            .filter_map(|channel| {
-                channel.follows.map(|ipld| {
-                    self.ipfs
-                        .dag_get::<&str, Follows>(ipld.link, None, Codec::default())
+        cache: &GossipCache,
+        channels: &[IPNSAddress],
+    ) -> Vec<(IPNSAddress, Cid)> {
+        let mut updated = Vec::new();
+
+        for channel in channels {
+            let Ok(bytes) = self.ipfs.routing_get(*channel).await else {
+                continue;
+            };
+
+            if let Ok(Some(root)) = cache.insert(*channel, &bytes) {
+                updated.push((*channel, root));
+            }
+        }
+
+        updated
+    }
+
+    /// Share every record of the cache with a group of friends.
+    pub async fn publish_gossip(&self, group: &str, cache: &GossipCache) -> Result<(), Error> {
+        let gossip = cache.to_gossip();
+
+        if gossip.entries.is_empty() {
+            return Ok(());
+        }
+
+        let data = encode_gossip(&gossip)?;
+
+        self.ipfs.pubsub_pub(gossip_topic(group), data).await?;
+
+        Ok(())
+    }
+
+    /// Receive gossip from a group of friends.
+    ///
+    /// Returns channel roots fresher than those in the cache,
+    /// records with invalid signatures or older sequences are dropped.
+    pub fn subscribe_gossip<'a>(
+        &'a self,
+        group: &str,
+        cache: &'a GossipCache,
+    ) -> impl Stream<Item = Result<(IPNSAddress, Cid), Error>> + 'a {
+        self.ipfs
+            .pubsub_sub(gossip_topic(group).into_bytes())
+            .err_into()
+            .map_ok(move |msg| {
+                let PubSubMessage { from: _, data } = msg;
+
+                let entries = match decode_gossip(&data) {
+                    Some(gossip) => gossip.entries,
+                    None => Vec::new(),
+                };
+
+                let updates: Vec<_> = entries
+                    .into_iter()
+                    .filter_map(|entry| match cache.insert(entry.channel, &entry.record) {
+                        Ok(Some(root)) => Some(Ok((entry.channel, root))),
+                        _ => None,
+                    })
+                    .collect();
+
+                stream::iter(updates)
+            })
+            .try_flatten()
+    }
+
+    /// Returns the identities attending this event.
+    pub async fn event_attendees(
+        &self,
+        rsvp_index: IPLDLink,
+        event_cid: Cid,
+    ) -> Result<Attendees, Error> {
+        let attendees = match hamt::get(&self.ipfs, rsvp_index, event_cid).await? {
+            Some(cid) => {
+                self.ipfs
+                    .dag_get::<&str, Attendees>(cid, None, Codec::default())
+                    .await?
+            }
+            None => Attendees::default(),
+        };
+
+        Ok(attendees)
+    }
+
+    /// Stream all the listings of a registry, optionally only those of one topic.
+    ///
+    /// Force decoding of unknown tree nodes, only for recovery.
+    ///
+    /// Returns (Topic, Channel Address, Signed Listing CID)
+    #[cfg(feature = "ordered-trees")]
+    pub fn stream_registry(
+        &self,
+        registry: IPLDLink,
+        topic: Option<String>,
+        force: bool,
+    ) -> impl Stream<Item = Result<(String, IPNSAddress, Cid), Error>> + '_ {
+        let range = topic.map(|topic| registry::topic_range(&topic));
+        let filter = range.clone();
+
+        stream::once(async move {
+            let tree = ProllyTree::load(self.ipfs.clone(), registry.link).await?;
+
+            // Forced decoding streams every key, those of other topics are filtered out.
+            let stream = match (force, range) {
+                (true, _) => tree.stream_forced::<Cid>().boxed_local(),
+                (false, Some(range)) => tree.stream_range::<Cid>(range).boxed_local(),
+                (false, None) => tree.stream::<Cid>().boxed_local(),
+            };
+
+            Result::<_, Error>::Ok(stream.err_into())
+        })
+        .try_flatten()
+        .try_filter_map(move |(key, cid)| {
+            let keep = match filter.as_ref() {
+                Some(range) => range.contains(&key),
+                None => true,
+            };
+
+            let item = match keep {
+                true => registry::split_listing_key(&key).map(|(topic, addr)| (topic, addr, cid)),
+                false => None,
+            };
+
+            future::ready(Ok(item))
+        })
+    }
+
+    /// Stream the content approved by a hub, oldest approval first.
+    ///
+    /// Returns (Approval Timestamp, Content CID)
+    #[cfg(feature = "ordered-trees")]
+    pub fn stream_hub(
+        &self,
+        hub_index: IPLDLink,
+    ) -> impl Stream<Item = Result<(i64, Cid), Error>> + '_ {
+        stream::once(async move {
+            let tree = ProllyTree::load(self.ipfs.clone(), hub_index.link).await?;
+
+            Result::<_, Error>::Ok(tree.stream::<Cid>().err_into())
+        })
+        .try_flatten()
+        .try_filter_map(|(key, _)| future::ready(Ok(hub_keys::split_approval_key(&key))))
+    }
+
+    /// Subscribe to a hub, receiving the content approved from now on.
+    ///
+    /// Return CID of newly approved content.
+    #[cfg(feature = "ordered-trees")]
+    pub fn subscribe_hub(
+        &self,
+        hub_addr: IPNSAddress,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        let cursor = Cursor::after(hub_keys::timestamp_prefix(Utc::now().timestamp()));
+
+        let updates = self.subscribe_channel_updates(hub_addr).boxed_local();
+
+        stream::try_unfold(
+            (cursor, updates),
+            move |(mut cursor, mut updates)| async move {
+                let cid = match updates.try_next().await? {
+                    Some(cid) => cid,
+                    None => return Result::<_, Error>::Ok(None),
+                };
+
+                let metadata = self
+                    .ipfs
+                    .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+                    .await?;
+
+                let approved: Vec<Cid> = match metadata.hub_index {
+                    Some(ipld) => {
+                        let tree = ProllyTree::load(self.ipfs.clone(), ipld.link).await?;
+
+                        tree.stream_cursor::<Cid>(&mut cursor)
+                            .map_ok(|(_, cid)| cid)
+                            .try_collect()
+                            .await?
+                    }
+                    None => Vec::new(),
+                };
+
+                Ok(Some((stream::iter(approved).map(Ok), (cursor, updates))))
+            },
+        )
+        .try_flatten()
+    }
+
+    /// Subscribe to a channel.
+    ///
+    /// Updates of co-owned channels are only returned when approved by enough owners.
+    ///
+    /// Return CID of the latest channel metadata.
+    pub fn subscribe_channel_updates(
+        &self,
+        channel_addr: IPNSAddress,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        self.subscribe_channel_records(channel_addr)
+            .map_ok(|(_, cid)| cid)
+    }
+
+    /// Subscribe to a channel, returning the sequence and value of each newer record.
+    fn subscribe_channel_records(
+        &self,
+        channel_addr: IPNSAddress,
+    ) -> impl Stream<Item = Result<(u64, Cid), Error>> + '_ {
+        let latest_channel_cid = Cid::default();
+        let sequence = 0;
+        let owners = None;
+
+        // Only the latest record matters, gaps are ignored.
+        let stream = self
+            .ipfs
+            .ipns_sub(channel_addr, Backoff::default())
+            .boxed_local();
+
+        stream::try_unfold(
+            (sequence, latest_channel_cid, owners, stream),
+            move |(mut sequence, mut latest_channel_cid, mut owners, mut stream)| async move {
+                loop {
+                    let msg = match stream.next().await {
+                        Some(PubSubEvent::Message(msg)) => msg,
+                        Some(PubSubEvent::Gap) => continue,
+                        None => return Result::<_, Error>::Ok(None),
+                    };
+
+                    let PubSubMessage { from: _, data } = msg;
+
+                    let record = IPNSRecord::from_bytes(&data)?;
+
+                    let seq = record.get_sequence();
+
+                    if sequence >= seq {
+                        continue;
+                    }
+
+                    let cid = record.get_value();
+
+                    if latest_channel_cid == cid {
+                        continue;
+                    }
+
+                    if record.verify(channel_addr.into()).is_err() {
+                        continue;
+                    }
+
+                    if verify_update(&self.ipfs, cid, &mut owners).await.is_err() {
+                        continue;
+                    }
+
+                    sequence = seq;
+                    latest_channel_cid = cid;
+
+                    return Ok(Some((
+                        (sequence, latest_channel_cid),
+                        (sequence, latest_channel_cid, owners, stream),
+                    )));
+                }
+            },
+        )
+    }
+
+    /// Subscribe to a channel, each update is compared to the previous one.
+    ///
+    /// The first update is compared to the channel as currently resolved.
+    pub fn subscribe_channel_changes(
+        &self,
+        channel_addr: IPNSAddress,
+    ) -> impl Stream<Item = Result<ChannelChange, Error>> + '_ {
+        stream::once(async move {
+            let root = self.ipfs.name_resolve(channel_addr).await?;
+
+            let updates = self.subscribe_channel_updates(channel_addr).boxed_local();
+
+            let changes =
+                stream::try_unfold((root, updates), move |(previous, mut updates)| async move {
+                    let Some(root) = updates.try_next().await? else {
+                        return Ok(None);
+                    };
+
+                    let changes = self.diff_channel(previous, root).await?;
+
+                    let changes = stream::iter(changes.into_iter().map(Ok));
+
+                    Result::<_, Error>::Ok(Some((changes, (root, updates))))
+                })
+                .try_flatten();
+
+            Result::<_, Error>::Ok(changes)
+        })
+        .try_flatten()
+    }
+
+    /// Watch a channel, combining pubsub updates with periodic IPNS resolution.
+    ///
+    /// Updates missed over pubsub are found at the next resolution, see `watch::POLL_INTERVAL`.
+    pub fn watch_channel(
+        &self,
+        channel_addr: IPNSAddress,
+    ) -> impl Stream<Item = Result<ChannelEvent, Error>> + '_ {
+        self.watch_channel_every(channel_addr, watch::POLL_INTERVAL)
+    }
+
+    /// Watch a channel, fetching its IPNS record every interval.
+    ///
+    /// Records are ordered by sequence, late resolutions never replay older versions.
+    /// Nodes without a routing API resolve names instead, only new roots are kept.
+    ///
+    /// Failed resolutions, invalid records and updates that could not be fetched are returned
+    /// as errors and watching goes on, those updates are tried again at the next resolution.
+    pub fn watch_channel_every(
+        &self,
+        channel_addr: IPNSAddress,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<ChannelEvent, Error>> + '_ {
+        stream::once(async move {
+            // Without a record, the first one received sets the sequence.
+            let (sequence, root) = match self.resolve_record(channel_addr).await {
+                Ok(record) => record,
+                Err(_) => (0, self.name_resolve(channel_addr).await?),
+            };
+
+            let metadata = self
+                .ipfs
+                .dag_get::<&str, ChannelMetadata>(root, None, Codec::default())
+                .await?;
+
+            let live = self.live_watch(metadata.live).await?;
+
+            let updates = self
+                .subscribe_channel_records(channel_addr)
+                .filter_map(|result| future::ready(result.ok()))
+                .map(|(sequence, root)| Result::<_, Error>::Ok((Some(sequence), root)));
+
+            let polls = stream::unfold(None, move |mut owners| async move {
+                futures_timer::Delay::new(interval).await;
+
+                let record = self.poll_record(channel_addr, &mut owners).await;
+
+                Some((record, owners))
+            });
+
+            let records = stream::select(updates.boxed_local(), polls.boxed_local());
+
+            let events = stream::unfold(
+                (root, sequence, records, metadata.live, live),
+                move |state| async move {
+                    let (mut previous, mut sequence, mut records, mut live_link, mut watching) =
+                        state;
+
+                    loop {
+                        let next = match watching.as_mut() {
+                            Some((_, segments)) => {
+                                match future::select(records.next(), segments.next()).await {
+                                    Either::Left((record, _)) => Either::Left(record),
+                                    Either::Right((event, _)) => Either::Right(event),
+                                }
+                            }
+                            None => Either::Left(records.next().await),
+                        };
+
+                        let record = match (next, watching.as_mut()) {
+                            (Either::Left(record), _) => record,
+                            (Either::Right(Some(PubSubEvent::Message(msg))), Some((live, _))) => {
+                                if !live.on_segment(&msg.data, Utc::now().timestamp()) {
+                                    continue;
+                                }
+
+                                let event = ChannelEvent::LiveStarted(live.settings.clone());
+
+                                let state = (previous, sequence, records, live_link, watching);
+
+                                return Some((vec![Ok(event)], state));
+                            }
+                            (Either::Right(_), _) => continue,
+                        };
+
+                        let (seq, root) = match record {
+                            Some(Ok(record)) => record,
+                            Some(Err(e)) => {
+                                let state = (previous, sequence, records, live_link, watching);
+
+                                return Some((vec![Err(e)], state));
+                            }
+                            None => return None,
+                        };
+
+                        match seq {
+                            Some(seq) if seq <= sequence => continue,
+                            None if root == previous => continue,
+                            _ => {}
+                        }
+
+                        let events = match self.channel_events(previous, root).await {
+                            Ok((events, link)) => {
+                                if link != live_link {
+                                    match self.live_watch(link).await {
+                                        Ok(watch) => {
+                                            watching = watch;
+                                            live_link = link;
+                                        }
+                                        Err(e) => {
+                                            let state =
+                                                (previous, sequence, records, live_link, watching);
+
+                                            return Some((vec![Err(e)], state));
+                                        }
+                                    }
+                                }
+
+                                previous = root;
+                                sequence = seq.unwrap_or(sequence);
+
+                                events.into_iter().map(Ok).collect()
+                            }
+                            Err(e) => vec![Err(e)],
+                        };
+
+                        let state = (previous, sequence, records, live_link, watching);
+
+                        return Some((events, state));
+                    }
+                },
+            )
+            .flat_map(stream::iter);
+
+            Result::<_, Error>::Ok(events)
+        })
+        .try_flatten()
+    }
+
+    /// Fetch and verify the latest update of a channel.
+    ///
+    /// Returns the sequence of the record, none when resolved without one.
+    async fn poll_record(
+        &self,
+        addr: IPNSAddress,
+        owners: &mut Option<Owners>,
+    ) -> Result<(Option<u64>, Cid), Error> {
+        let (sequence, cid) = match self.resolve_record(addr).await {
+            Ok((sequence, cid)) => (Some(sequence), cid),
+            Err(Error::IpfsApi(ipfs_api::errors::Error::Unsupported(_))) => {
+                (None, self.name_resolve(addr).await?)
+            }
+            Err(e) => return Err(e),
+        };
+
+        verify_update(&self.ipfs, cid, owners).await?;
+
+        Ok((sequence, cid))
+    }
+
+    /// Follow the video topic of these live settings, if any.
+    async fn live_watch(
+        &self,
+        live: Option<IPLDLink>,
+    ) -> Result<Option<(LiveState, LocalBoxStream<'_, PubSubEvent>)>, Error> {
+        let Some(ipld) = live else {
+            return Ok(None);
+        };
+
+        let settings = self
+            .ipfs
+            .dag_get::<&str, LiveSettings>(ipld.link, None, Codec::default())
+            .await?;
+
+        let segments = self
+            .ipfs
+            .pubsub_sub_reconnect(
+                settings.video_topic.clone().into_bytes(),
+                Backoff::default(),
+            )
+            .boxed_local();
+
+        Ok(Some((LiveState::new(settings), segments)))
+    }
+
+    /// Fetch the IPNS record of this address, returning its sequence and value.
+    ///
+    /// Invalid and expired records are rejected.
+    async fn resolve_record(&self, addr: IPNSAddress) -> Result<(u64, Cid), Error> {
+        let bytes = self.ipfs.routing_get(addr).await?;
+
+        let record = IPNSRecord::from_bytes(&bytes)?;
+
+        record.verify(addr.into())?;
+
+        if record.is_expired()? {
+            return Err(ipns_records::Error::Expired.into());
+        }
+
+        Ok((record.get_sequence(), record.get_value()))
+    }
+
+    /// Returns the events between two versions of a channel and the live settings of the new one.
+    async fn channel_events(
+        &self,
+        old_root: Cid,
+        new_root: Cid,
+    ) -> Result<(Vec<ChannelEvent>, Option<IPLDLink>), Error> {
+        let (old, new) = futures::try_join!(
+            self.ipfs
+                .dag_get::<&str, ChannelMetadata>(old_root, None, Codec::default()),
+            self.ipfs
+                .dag_get::<&str, ChannelMetadata>(new_root, None, Codec::default()),
+        )?;
+
+        let changes =
+            diff::content_changes(&self.ipfs, old.content_index, new.content_index).await?;
+
+        let mut events: Vec<_> = changes
+            .into_iter()
+            .filter_map(ChannelEvent::from_change)
+            .collect();
+
+        if old.identity != new.identity {
+            events.push(ChannelEvent::IdentityUpdated(new.identity.link));
+        }
+
+        Ok((events, new.live))
+    }
+
+    /// Compare two versions of a channel metadata.
+    ///
+    /// Content and followees are compared one by one, other indexes only by root.
+    pub async fn diff_channel(
+        &self,
+        old_root: Cid,
+        new_root: Cid,
+    ) -> Result<Vec<ChannelChange>, Error> {
+        if old_root == new_root {
+            return Ok(Vec::new());
+        }
+
+        let (old, new) = futures::try_join!(
+            self.ipfs
+                .dag_get::<&str, ChannelMetadata>(old_root, None, Codec::default()),
+            self.ipfs
+                .dag_get::<&str, ChannelMetadata>(new_root, None, Codec::default()),
+        )?;
+
+        let (mut changes, follows) = futures::try_join!(
+            diff::content_changes(&self.ipfs, old.content_index, new.content_index),
+            diff::follows_changes(&self.ipfs, old.follows, new.follows),
+        )?;
+
+        changes.extend(follows);
+        changes.extend(diff::metadata_changes(&old, &new));
+
+        Ok(changes)
+    }
+
+    /// Subscribe to the update proposals of a co-owned channel.
+    ///
+    /// Return CIDs of the proposed channel metadata.
+    pub fn subscribe_channel_proposals(
+        &self,
+        channel_addr: IPNSAddress,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        self.ipfs
+            .pubsub_sub(proposal_topic(channel_addr).into_bytes())
+            .err_into()
+            .try_filter_map(|msg| async move {
+                let PubSubMessage { from: _, data } = msg;
+
+                Ok(Cid::try_from(data.as_slice()).ok())
+            })
+    }
+
+    /// Returns all followees channels on the social web without duplicates.
+    ///
+    /// WARNING! This search will crawl the entire web. Limiting the number of result is best.
+    pub fn streaming_web_crawl(
+        &self,
+        addresses: impl Iterator<Item = IPNSAddress>,
+    ) -> impl Stream<Item = Result<(Cid, ChannelMetadata), Error>> + '_ {
+        self.streaming_web_crawl_checkpointed(addresses, None, 0)
+            .try_filter_map(|event| async move {
+                match event {
+                    CrawlEvent::Channel(cid, metadata) => Ok(Some((cid, metadata))),
+                    CrawlEvent::Checkpoint(_) => Ok(None),
+                }
+            })
+    }
+
+    /// Returns all followees channels on the social web without duplicates,
+    /// with a checkpoint every `interval` channels. Zero means no checkpoints.
+    ///
+    /// Resume an interrupted crawl by passing its last checkpoint.
+    ///
+    /// Channels timing out, as per the retry policy of the IPFS service, are skipped.
+    ///
+    /// WARNING! This search will crawl the entire web. Limiting the number of result is best.
+    pub fn streaming_web_crawl_checkpointed(
+        &self,
+        addresses: impl Iterator<Item = IPNSAddress>,
+        checkpoint: Option<CrawlCheckpoint>,
+        interval: usize,
+    ) -> impl Stream<Item = Result<CrawlEvent, Error>> + '_ {
+        self.streaming_web_crawl_addressed(addresses, checkpoint, interval)
+            .map_ok(|(_, event)| event)
+    }
+
+    /// Same as ```streaming_web_crawl_checkpointed``` with the address of each channel,
+    /// none for checkpoints.
+    ///
+    /// Record channels in a ```CrawlSnapshot``` to publish the crawled web for others.
+    pub fn streaming_web_crawl_addressed(
+        &self,
+        addresses: impl Iterator<Item = IPNSAddress>,
+        checkpoint: Option<CrawlCheckpoint>,
+        interval: usize,
+    ) -> impl Stream<Item = Result<(Option<IPNSAddress>, CrawlEvent), Error>> + '_ {
+        let checkpoint = checkpoint.unwrap_or_default();
+        let frontier = checkpoint.frontier.clone();
+
+        let mut state = CrawlState::from(checkpoint);
+
+        let resolve_pool: FuturesUnordered<_> = frontier
+            .into_iter()
+            .chain(addresses)
+            .filter(|addr| state.pending.insert(*addr))
+            .map(|addr| self.resolve_crawl(addr))
+            .collect();
+
+        let metadata_pool = FuturesUnordered::<_>::new();
+
+        let follows_pool = FuturesUnordered::<_>::new();
+
+        stream::try_unfold(
+            (state, resolve_pool, metadata_pool, follows_pool),
+            move |(mut state, mut resolve_pool, mut metadata_pool, mut follows_pool)| async move {
+                loop {
+                    if interval > 0 && state.count >= interval {
+                        let checkpoint = state.checkpoint();
+
+                        return Ok(Some((
+                            (None, CrawlEvent::Checkpoint(checkpoint)),
+                            (state, resolve_pool, metadata_pool, follows_pool),
+                        )));
+                    }
+
+                    futures_util::select! {
+                        option = resolve_pool.next() => {
+                            let (addr, result) = match option {
+                                Some(item) => item,
+                                None => continue,
+                            };
+
+                            let Some(cid) = skip_timeout(result)? else {
+                                continue;
+                            };
+
+                            if !state.seen.insert(cid) {
+                                state.pending.remove(&addr);
+                                continue;
+                            }
+
+                            metadata_pool.push(async move { (addr, cid, self.ipfs.dag_get::<&str, ChannelMetadata>(cid, None, Codec::default()).await) });
+                        },
+                        option = metadata_pool.next() => {
+                            let (addr, cid, metadata) = match option {
+                                Some(mt) => mt,
+                                None => continue,
+                            };
+
+                            let Some(metadata) = skip_timeout(metadata)? else {
+                                continue;
+                            };
+
+                            match metadata.follows {
+                                Some(ipld) => follows_pool.push(async move { (addr, cid, self.ipfs.dag_get::<&str, Follows>(ipld.link, None, Codec::default()).await) }),
+                                None => state.done(addr, cid),
+                            }
+
+                            state.count += 1;
+
+                            let next_item = (Some(addr), CrawlEvent::Channel(cid, metadata));
+
+                            return Ok(Some((next_item,
+                                (state, resolve_pool, metadata_pool, follows_pool),
+                            )));
+                        },
+                        option = follows_pool.next() => {
+                            let (addr, cid, follows) = match option {
+                                Some(fl) => fl,
+                                None => continue,
+                            };
+
+                            let Some(follows) = skip_timeout(follows)? else {
+                                continue;
+                            };
+
+                            for followee in follows.followees {
+                                if state.pending.insert(followee) {
+                                    resolve_pool.push(self.resolve_crawl(followee));
+                                }
+                            }
+
+                            state.done(addr, cid);
+                        },
+                        complete => return Ok(None),
+                    }
+                }
+            },
+        )
+    }
+
+    /// Stream the channels and follows of a published crawl snapshot.
+    #[cfg(feature = "ordered-trees")]
+    pub fn stream_crawl_snapshot(
+        &self,
+        snapshot: IPNSAddress,
+    ) -> impl Stream<Item = Result<CrawlRecord, Error>> + '_ {
+        stream::once(async move {
+            let root = self.name_resolve(snapshot).await?;
+
+            let tree = ProllyTree::load(self.ipfs.clone(), root).await?;
+
+            Result::<_, Error>::Ok(tree.stream::<GraphValue>().err_into())
+        })
+        .try_flatten()
+        .try_filter_map(|(key, value)| future::ready(Ok(CrawlRecord::decode(&key, value))))
+    }
+
+    /// Stream the differences between two crawl snapshot trees.
+    ///
+    /// Returns (Old Record, New Record), either is none if added or removed.
+    #[cfg(feature = "ordered-trees")]
+    pub fn diff_crawl_snapshots(
+        &self,
+        old_root: Cid,
+        new_root: Cid,
+    ) -> impl Stream<Item = Result<(Option<CrawlRecord>, Option<CrawlRecord>), Error>> + '_ {
+        stream::once(async move {
+            let tree = ProllyTree::load(self.ipfs.clone(), old_root).await?;
+
+            Result::<_, Error>::Ok(tree.diff::<GraphValue>(new_root).err_into())
+        })
+        .try_flatten()
+        .map_ok(|change| {
+            let (key, old, new) = change.into_parts();
+
+            let old = old.and_then(|value| CrawlRecord::decode(&key, value));
+            let new = new.and_then(|value| CrawlRecord::decode(&key, value));
+
+            (old, new)
+        })
+    }
+
+    async fn resolve_crawl(&self, addr: IPNSAddress) -> (IPNSAddress, Result<Cid, Error>) {
+        let result = self.name_resolve(addr).await;
+
+        (addr, result)
+    }
+
+    /// Return all the cids and channels of all the identities provided.
+    pub async fn channels_metadata(
+        &self,
+        identities: impl Iterator<Item = &Identity>,
+    ) -> HashMap<Cid, ChannelMetadata> {
+        let stream: FuturesUnordered<_> = identities
+            .filter_map(|identity| {
+                identity
+                    .ipns_addr
+                    .map(|ipns| self.name_resolve(ipns.into()))
+            })
+            .collect();
+
+        stream
+            .filter_map(|result| async move {
+                match result {
+                    Ok(cid) => match self
+                        .ipfs
+                        .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+                        .await
+                    {
+                        Ok(channel) => Some((cid, channel)),
+                        Err(_) => None,
+                    },
+                    Err(_) => None,
+                }
+            })
+            .collect()
+            .await
+    }
+
+    /// Returns all the cids and identities of all the followees of all the channels provided.
+    pub async fn followees_identity(
+        &self,
+        channels: impl Iterator<Item = &ChannelMetadata>,
+    ) -> HashMap<Cid, Identity> {
+        let stream: FuturesUnordered<_> = channels
+            .filter_map(|channel| {
+                channel.follows.map(|ipld| {
+                    self.ipfs
+                        .dag_get::<&str, Follows>(ipld.link, None, Codec::default())
                 })
             })
             .collect();
@@ -277,7 +1466,7 @@ impl Defluencer {
             })
             .flatten()
             .filter_map(|addr| async move {
-                match self.ipfs.name_resolve(addr.into()).await {
+                match self.name_resolve(addr.into()).await {
                     Ok(cid) => Some(cid),
                     Err(_) => None,
                 }
@@ -301,9 +1490,641 @@ impl Defluencer {
                     Ok(identity) => Some((channel.identity.link, identity)),
                     Err(_) => None,
                 }
-            })
-            .collect()
-            .await
+            })
+            .collect()
+            .await
+    }
+
+    /// Returns the followees of a named list.
+    pub async fn follow_list(&self, lists: IPLDLink, name: &str) -> Result<Follows, Error> {
+        let lists = self
+            .ipfs
+            .dag_get::<&str, FollowLists>(lists.link, None, Codec::default())
+            .await?;
+
+        let ipld = lists.lists.get(name).ok_or(Error::NotFound)?;
+
+        let follows = self
+            .ipfs
+            .dag_get::<&str, Follows>(ipld.link, None, Codec::default())
+            .await?;
+
+        Ok(follows)
+    }
+
+    /// Returns the daily publications, comments and live stream durations of a channel.
+    ///
+    /// Every media and comment is fetched, archived comments included.
+    pub async fn channel_analytics(&self, channel: &ChannelMetadata) -> Result<Analytics, Error> {
+        let mut analytics = Analytics::default();
+
+        if let Some(index) = channel.content_index {
+            let stream = self
+                .stream_content_rev_chrono(index)
+                .map_ok(|cid| async move {
+                    self.ipfs
+                        .dag_get::<&str, Media>(cid, Some("/link"), Codec::default())
+                        .await
+                        .map_err(Error::from)
+                })
+                .try_buffer_unordered(16);
+            pin_mut!(stream);
+
+            while let Some(media) = stream.try_next().await? {
+                analytics.add_media(&media);
+            }
+        }
+
+        let archived = stream::iter(channel.comment_archive)
+            .map(|index| hamt::values(&self.ipfs, index))
+            .flatten()
+            .and_then(|(_, cid)| async move {
+                let archived = self
+                    .ipfs
+                    .dag_get::<&str, ArchivedComments>(cid, None, Codec::default())
+                    .await?;
+
+                Result::<_, Error>::Ok(stream::iter(archived.chunks).map(Result::<_, Error>::Ok))
+            })
+            .try_flatten()
+            .and_then(|ipld| async move {
+                let chunk = self
+                    .ipfs
+                    .dag_get::<&str, CommentChunk>(ipld.link, None, Codec::default())
+                    .await?;
+
+                let comments = chunk
+                    .comments
+                    .into_iter()
+                    .map(|ipld| Result::<_, Error>::Ok(ipld.link));
+
+                Result::<_, Error>::Ok(stream::iter(comments))
+            })
+            .try_flatten();
+
+        let stream = stream::iter(channel.comment_index)
+            .map(|index| self.stream_all_comments(index).map_ok(|(_, cid)| cid))
+            .flatten()
+            .chain(archived)
+            .map_ok(|cid| async move {
+                self.ipfs
+                    .dag_get::<&str, Comment>(cid, Some("/link"), Codec::default())
+                    .await
+                    .map_err(Error::from)
+            })
+            .try_buffer_unordered(16);
+        pin_mut!(stream);
+
+        while let Some(comment) = stream.try_next().await? {
+            analytics.add_comment(&comment);
+        }
+
+        Ok(analytics)
+    }
+
+    /// Returns the number of channels following this one in a crawl snapshot.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn crawled_followers(
+        &self,
+        snapshot: IPNSAddress,
+        channel: IPNSAddress,
+    ) -> Result<u64, Error> {
+        let stream = self.stream_crawl_snapshot(snapshot);
+        pin_mut!(stream);
+
+        let mut followers = 0;
+
+        while let Some(record) = stream.try_next().await? {
+            if let CrawlRecord::Follow { followee, .. } = record {
+                if followee == channel {
+                    followers += 1;
+                }
+            }
+        }
+
+        Ok(followers)
+    }
+
+    /// Returns the chat addresses of the channels following this one in a crawl snapshot.
+    ///
+    /// Followers whose channel cannot be fetched or whose identity has no address are skipped.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn crawled_follower_addresses(
+        &self,
+        snapshot: IPNSAddress,
+        channel: IPNSAddress,
+    ) -> Result<HashSet<linked_data::types::Address>, Error> {
+        let stream = self.stream_crawl_snapshot(snapshot);
+        pin_mut!(stream);
+
+        let mut addresses = HashSet::new();
+
+        while let Some(record) = stream.try_next().await? {
+            let CrawlRecord::Follow { follower, followee } = record else {
+                continue;
+            };
+
+            if followee != channel {
+                continue;
+            }
+
+            let Ok(cid) = self.name_resolve(follower).await else {
+                continue;
+            };
+
+            let Ok(metadata) = self
+                .ipfs
+                .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+                .await
+            else {
+                continue;
+            };
+
+            let Ok(identity) = self
+                .ipfs
+                .dag_get::<&str, Identity>(metadata.identity.link, None, Codec::default())
+                .await
+            else {
+                continue;
+            };
+
+            addresses.extend(chat::identity_addresses(&identity));
+        }
+
+        Ok(addresses)
+    }
+
+    /// Returns a named playlist.
+    pub async fn playlist(&self, playlists: IPLDLink, name: &str) -> Result<Playlist, Error> {
+        let playlists = self
+            .ipfs
+            .dag_get::<&str, Playlists>(playlists.link, None, Codec::default())
+            .await?;
+
+        let ipld = playlists.playlists.get(name).ok_or(Error::NotFound)?;
+
+        let playlist = self
+            .ipfs
+            .dag_get::<&str, Playlist>(ipld.link, None, Codec::default())
+            .await?;
+
+        Ok(playlist)
+    }
+
+    /// Lazily stream the content CIDs of a named playlist, in playing order.
+    pub fn stream_playlist(
+        &self,
+        playlists: IPLDLink,
+        name: String,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        stream::once(async move {
+            let playlist = self.playlist(playlists, &name).await?;
+
+            let content = playlist
+                .content
+                .into_iter()
+                .map(|ipld| Result::<_, Error>::Ok(ipld.link));
+
+            Result::<_, Error>::Ok(stream::iter(content))
+        })
+        .try_flatten()
+    }
+
+    /// Stream all content CIDs of a channel with this tag, in no particular order.
+    ///
+    /// Tags are matched regardless of case.
+    pub fn stream_content_by_tag(
+        &self,
+        tag_index: IPLDLink,
+        tag: &str,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        let key = tags::tag_key(tag);
+
+        stream::once(async move {
+            let content = hamt::get(&self.ipfs, tag_index, key).await?;
+
+            Result::<_, Error>::Ok(content)
+        })
+        .try_filter_map(move |option| async move {
+            match option {
+                Some(content) => Ok(Some(hamt::values(&self.ipfs, content.into()))),
+                None => Ok(None),
+            }
+        })
+        .try_flatten()
+        .map_ok(|(_, cid)| cid)
+    }
+
+    /// Returns the latest snapshot of a channel, if any.
+    ///
+    /// Two requests after name resolution, enough to render the top of a channel page.
+    pub async fn channel_snapshot(&self, addr: IPNSAddress) -> Result<Option<Snapshot>, Error> {
+        let cid = self.name_resolve(addr).await?;
+
+        let channel = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+            .await?;
+
+        let Some(ipld) = channel.snapshot else {
+            return Ok(None);
+        };
+
+        let snapshot = self
+            .ipfs
+            .dag_get::<&str, Snapshot>(ipld.link, None, Codec::default())
+            .await?;
+
+        Ok(Some(snapshot))
+    }
+
+    /// Returns the content CID a permalink path like /ipns/<channel>/content/<slug> points to.
+    ///
+    /// The path stays valid as the channel is updated, until the content is removed.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn resolve_permalink(&self, path: &str) -> Result<Cid, Error> {
+        let (addr, slug) = slug::parse_permalink(path).ok_or(Error::NotFound)?;
+
+        let cid = self.name_resolve(addr).await?;
+
+        let channel = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+            .await?;
+
+        self.slug_content(&channel, slug).await
+    }
+
+    /// Returns the content and comment CIDs a permalink path like
+    /// /ipns/<channel>/content/<slug>/comments/<CID> points to.
+    ///
+    /// The comment must be on this content, recent or archived.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn resolve_comment_permalink(&self, path: &str) -> Result<(Cid, Cid), Error> {
+        let (addr, slug, comment_cid) =
+            slug::parse_comment_permalink(path).ok_or(Error::NotFound)?;
+
+        let cid = self.name_resolve(addr).await?;
+
+        let channel = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+            .await?;
+
+        let content_cid = self.slug_content(&channel, slug).await?;
+
+        if !self.has_comment(&channel, content_cid, comment_cid).await? {
+            return Err(Error::NotFound);
+        }
+
+        Ok((content_cid, comment_cid))
+    }
+
+    /// Returns true if the comment is on this content, recent or archived.
+    #[cfg(feature = "ordered-trees")]
+    async fn has_comment(
+        &self,
+        channel: &ChannelMetadata,
+        content_cid: Cid,
+        comment_cid: Cid,
+    ) -> Result<bool, Error> {
+        let Some(comment_index) = channel.comment_index else {
+            return Ok(false);
+        };
+
+        if let Some(comments) = media_comments(&self.ipfs, comment_index, content_cid).await? {
+            if hamt::get(&self.ipfs, comments.comments, comment_cid)
+                .await?
+                .is_some()
+            {
+                return Ok(true);
+            }
+        }
+
+        let stream = self.stream_content_comments_tiered(
+            comment_index,
+            channel.comment_archive,
+            content_cid,
+        );
+        pin_mut!(stream);
+
+        while let Some(cid) = stream.try_next().await? {
+            if cid == comment_cid {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Returns the content a comment is on, recent or archived.
+    ///
+    /// Comments keep their origin when content is replaced,
+    /// the whole comment index is searched when the origin does not have it.
+    #[cfg(feature = "ordered-trees")]
+    async fn comment_content(
+        &self,
+        channel: &ChannelMetadata,
+        origin: Option<Cid>,
+        comment_cid: Cid,
+    ) -> Result<Option<Cid>, Error> {
+        let Some(comment_index) = channel.comment_index else {
+            return Ok(None);
+        };
+
+        let mut visited = HashSet::new();
+
+        if let Some(origin) = origin {
+            if self.has_comment(channel, origin, comment_cid).await? {
+                return Ok(Some(origin));
+            }
+
+            visited.insert(origin);
+        }
+
+        for index in iter::once(comment_index).chain(channel.comment_archive) {
+            let stream = hamt::values(&self.ipfs, index);
+            pin_mut!(stream);
+
+            while let Some((content_cid, _)) = stream.try_next().await? {
+                if !visited.insert(content_cid) {
+                    continue;
+                }
+
+                if self.has_comment(channel, content_cid, comment_cid).await? {
+                    return Ok(Some(content_cid));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the content a slug points to.
+    ///
+    /// Slugs of removed content are kept as tombstones and not found.
+    #[cfg(feature = "ordered-trees")]
+    async fn slug_content(&self, channel: &ChannelMetadata, slug: String) -> Result<Cid, Error> {
+        let ipld = channel.slug_index.ok_or(Error::NotFound)?;
+
+        let tree = ProllyTree::load(self.ipfs.clone(), ipld.link).await?;
+
+        let Some((_, content_cid)) = tree.get::<Cid>(slug.clone().into_bytes()).await? else {
+            return Err(Error::NotFound);
+        };
+
+        match self.content_slug(channel, content_cid).await? {
+            Some(current) if current == slug => Ok(content_cid),
+            _ => Err(Error::NotFound),
+        }
+    }
+
+    /// Returns the permalink path of some content of a channel, if it has one.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn content_permalink(
+        &self,
+        addr: IPNSAddress,
+        content_cid: Cid,
+    ) -> Result<Option<String>, Error> {
+        let cid = self.name_resolve(addr).await?;
+
+        let channel = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+            .await?;
+
+        let slug = self.content_slug(&channel, content_cid).await?;
+
+        Ok(slug.map(|slug| slug::permalink_path(addr, &slug)))
+    }
+
+    /// Returns the permalink path of a comment, if the content it is on has one.
+    ///
+    /// Comments on replaced content link to the new version.
+    #[cfg(feature = "ordered-trees")]
+    pub async fn comment_permalink(
+        &self,
+        addr: IPNSAddress,
+        comment_cid: Cid,
+    ) -> Result<Option<String>, Error> {
+        let comment = self
+            .ipfs
+            .dag_get::<&str, Comment>(comment_cid, Some("/link"), Codec::default())
+            .await?;
+
+        let cid = self.name_resolve(addr).await?;
+
+        let channel = self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+            .await?;
+
+        let Some(content_cid) = self
+            .comment_content(&channel, comment.origin, comment_cid)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let slug = self.content_slug(&channel, content_cid).await?;
+
+        Ok(slug.map(|slug| slug::comment_permalink_path(addr, &slug, comment_cid)))
+    }
+
+    #[cfg(feature = "ordered-trees")]
+    async fn content_slug(
+        &self,
+        channel: &ChannelMetadata,
+        content_cid: Cid,
+    ) -> Result<Option<String>, Error> {
+        let Some(ipld) = channel.permalink_index else {
+            return Ok(None);
+        };
+
+        let tree = ProllyTree::load(self.ipfs.clone(), ipld.link).await?;
+
+        let slug = tree.get::<String>(content_cid.to_bytes()).await?;
+
+        Ok(slug.map(|(_, slug)| slug))
+    }
+
+    /// Returns all the channels of a named list.
+    ///
+    /// Unlike the web crawl, the followees of those channels are not visited.
+    pub fn streaming_list_crawl(
+        &self,
+        lists: IPLDLink,
+        name: String,
+    ) -> impl Stream<Item = Result<(Cid, ChannelMetadata), Error>> + '_ {
+        stream::once(async move {
+            let follows = self.follow_list(lists, &name).await?;
+
+            let pool: FuturesUnordered<_> = follows
+                .followees
+                .into_iter()
+                .map(|addr| async move {
+                    let cid = self.name_resolve(addr.into()).await?;
+
+                    let metadata = self
+                        .ipfs
+                        .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+                        .await?;
+
+                    Result::<_, Error>::Ok((cid, metadata))
+                })
+                .collect();
+
+            Result::<_, Error>::Ok(pool)
+        })
+        .try_flatten()
+    }
+
+    /// Lazily stream the content CIDs of all the channels of a named list.
+    ///
+    /// Each channel content is in reverse chronological order, one channel after the other.
+    pub fn stream_list_content(
+        &self,
+        lists: IPLDLink,
+        name: String,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        self.streaming_list_crawl(lists, name)
+            .try_filter_map(|(_, metadata)| future::ready(Ok(metadata.content_index)))
+            .map_ok(|index| self.stream_content_rev_chrono(index))
+            .try_flatten()
+    }
+
+    /// Lazily stream media of the same kind as this one from a channel, nearest in time first.
+    ///
+    /// Newer and older media alternate, starting with the next newer one.
+    /// Nothing is suggested if the media is not in the content index.
+    pub fn related(
+        &self,
+        content_index: IPLDLink,
+        media_cid: Cid,
+        limit: usize,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        stream::once(async move {
+            let media = self
+                .ipfs
+                .dag_get::<&str, Media>(media_cid, Some("/link"), Codec::default())
+                .await?;
+            let kind = std::mem::discriminant(&media);
+
+            let mut content = self
+                .stream_content_rev_chrono(content_index)
+                .boxed_local()
+                .fuse();
+
+            // Only the nearest newer media are kept.
+            let mut newer = VecDeque::with_capacity(limit);
+
+            loop {
+                match content.try_next().await? {
+                    Some(cid) if cid == media_cid => break,
+                    Some(cid) => {
+                        if newer.len() == limit {
+                            newer.pop_front();
+                        }
+
+                        newer.push_back(cid);
+                    }
+                    None => {
+                        newer.clear();
+                        break;
+                    }
+                }
+            }
+
+            let alternate = stream::try_unfold(
+                (newer, content, true),
+                |(mut newer, mut older, mut newer_turn)| async move {
+                    loop {
+                        if newer_turn {
+                            newer_turn = false;
+
+                            if let Some(cid) = newer.pop_back() {
+                                return Ok(Some((cid, (newer, older, newer_turn))));
+                            }
+                        }
+
+                        newer_turn = true;
+
+                        if let Some(cid) = older.try_next().await? {
+                            return Ok(Some((cid, (newer, older, newer_turn))));
+                        }
+
+                        if newer.is_empty() {
+                            return Result::<_, Error>::Ok(None);
+                        }
+                    }
+                },
+            );
+
+            Result::<_, Error>::Ok(alternate.map_ok(move |cid| (kind, cid)))
+        })
+        .try_flatten()
+        .try_filter_map(move |(kind, cid)| async move {
+            let media = self
+                .ipfs
+                .dag_get::<&str, Media>(cid, Some("/link"), Codec::default())
+                .await?;
+
+            Ok((std::mem::discriminant(&media) == kind).then_some(cid))
+        })
+        .take(limit)
+    }
+
+    /// Returns the known variants of this content.
+    pub async fn content_variants(
+        &self,
+        variant_index: IPLDLink,
+        content_cid: Cid,
+    ) -> Result<Vec<Variant>, Error> {
+        let variants = match hamt::get(&self.ipfs, variant_index, content_cid).await? {
+            Some(cid) => {
+                self.ipfs
+                    .dag_get::<&str, Variants>(cid, None, Codec::default())
+                    .await?
+            }
+            None => return Ok(Vec::new()),
+        };
+
+        Ok(variants.variants)
+    }
+
+    /// Lazily stream a channel content CIDs with their variants.
+    ///
+    /// Variants are grouped with the newest version of the content, they are not streamed on their own.
+    pub fn stream_content_grouped(
+        &self,
+        content_index: IPLDLink,
+        variant_index: Option<IPLDLink>,
+    ) -> impl Stream<Item = Result<(Cid, Vec<Variant>), Error>> + '_ {
+        let content = self.stream_content_rev_chrono(content_index).boxed_local();
+
+        stream::try_unfold(
+            (content, HashSet::new()),
+            move |(mut content, mut grouped)| async move {
+                loop {
+                    let Some(cid) = content.try_next().await? else {
+                        return Ok(None);
+                    };
+
+                    if grouped.remove(&cid) {
+                        continue;
+                    }
+
+                    let variants = match variant_index {
+                        Some(index) => self.content_variants(index, cid).await?,
+                        None => Vec::new(),
+                    };
+
+                    grouped.extend(variants.iter().map(|variant| variant.link.link));
+
+                    return Ok(Some(((cid, variants), (content, grouped))));
+                }
+            },
+        )
     }
 
     /// Lazily stream a channel content CIDs.
@@ -429,25 +2250,527 @@ impl Defluencer {
         .map_ok(|ipld| ipld.link)
     }
 
+    /// Returns true if some content on a channel has comments.
+    ///
+    /// Content without comments is usually ruled out by the comment filter alone.
+    pub async fn has_comments(
+        &self,
+        channel: &ChannelMetadata,
+        content_cid: Cid,
+    ) -> Result<bool, Error> {
+        let Some(comment_index) = channel.comment_index else {
+            return Ok(false);
+        };
+
+        if let Some(ipld) = channel.comment_filter {
+            let filter = self
+                .ipfs
+                .dag_get::<&str, BloomFilter>(ipld.link, None, Codec::default())
+                .await?;
+
+            if !bloom::contains(&filter, content_cid) {
+                return Ok(false);
+            }
+        }
+
+        let stream = self.stream_content_comments_tiered(
+            comment_index,
+            channel.comment_archive,
+            content_cid,
+        );
+        pin_mut!(stream);
+
+        let first = stream.try_next().await?;
+
+        Ok(first.is_some())
+    }
+
     /// Stream all comment CIDs for some content on a channel.
     pub fn stream_content_comments(
         &self,
         comment_index: IPLDLink,
         content_cid: Cid,
     ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
-        stream::once(async move {
-            let comments = hamt::get(&self.ipfs, comment_index, content_cid).await?;
+        stream::once(media_comments(&self.ipfs, comment_index, content_cid))
+            .try_filter_map(move |option| async move {
+                match option {
+                    Some(comments) => Ok(Some(hamt::values(&self.ipfs, comments.comments))),
+                    None => Ok(None),
+                }
+            })
+            .try_flatten()
+            .map_ok(|(_, cid)| cid)
+    }
 
-            Result::<_, Error>::Ok(comments)
-        })
-        .try_filter_map(move |option| async move {
-            match option {
-                Some(comments) => Ok(Some(hamt::values(&self.ipfs, comments.into()))),
-                None => Ok(None),
-            }
+    /// Stream the conversation under a comment, replies to replies included.
+    ///
+    /// The replies of a comment come before their own replies.
+    /// Each comment is returned once, even if a foreign index loops back.
+    ///
+    /// Returns (Parent CID, Reply CID)
+    pub fn stream_comment_replies(
+        &self,
+        reply_index: IPLDLink,
+        comment_cid: Cid,
+    ) -> impl Stream<Item = Result<(Cid, Cid), Error>> + '_ {
+        let visited = HashSet::from([comment_cid]);
+
+        stream::try_unfold(
+            (vec![comment_cid], visited),
+            move |(mut parents, mut visited)| async move {
+                let Some(parent_cid) = parents.pop() else {
+                    return Result::<_, Error>::Ok(None);
+                };
+
+                let mut replies: Vec<Cid> =
+                    match hamt::get(&self.ipfs, reply_index, parent_cid).await? {
+                        Some(replies) => {
+                            hamt::values(&self.ipfs, replies.into())
+                                .map_ok(|(_, cid)| cid)
+                                .try_collect()
+                                .await?
+                        }
+                        None => Vec::new(),
+                    };
+
+                replies.retain(|cid| visited.insert(*cid));
+
+                parents.extend(replies.iter().rev());
+
+                let replies = replies
+                    .into_iter()
+                    .map(move |cid| Result::<_, Error>::Ok((parent_cid, cid)));
+
+                Ok(Some((stream::iter(replies), (parents, visited))))
+            },
+        )
+        .try_flatten()
+    }
+
+    /// Stream all comment CIDs for some content on a channel, archived comments included.
+    ///
+    /// Recent comments first then archived chunks, newest first.
+    pub fn stream_content_comments_tiered(
+        &self,
+        comment_index: IPLDLink,
+        comment_archive: Option<IPLDLink>,
+        content_cid: Cid,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        let archived = stream::once(async move {
+            let chunks = match comment_archive {
+                Some(index) => match hamt::get(&self.ipfs, index, content_cid).await? {
+                    Some(cid) => {
+                        self.ipfs
+                            .dag_get::<&str, ArchivedComments>(cid, None, Codec::default())
+                            .await?
+                            .chunks
+                    }
+                    None => Vec::new(),
+                },
+                None => Vec::new(),
+            };
+
+            let chunks = chunks.into_iter().rev().map(Result::<_, Error>::Ok);
+
+            Result::<_, Error>::Ok(stream::iter(chunks))
         })
         .try_flatten()
-        .map_ok(|(_, cid)| cid)
+        .and_then(move |ipld: IPLDLink| async move {
+            let chunk = self
+                .ipfs
+                .dag_get::<&str, CommentChunk>(ipld.link, None, Codec::default())
+                .await?;
+
+            let comments = chunk
+                .comments
+                .into_iter()
+                .map(|ipld| Result::<_, Error>::Ok(ipld.link));
+
+            Result::<_, Error>::Ok(stream::iter(comments))
+        })
+        .try_flatten();
+
+        self.stream_content_comments(comment_index, content_cid)
+            .chain(archived)
+    }
+
+    /// Returns who can comment on this content.
+    ///
+    /// Clients should hide the comment box of users without permission.
+    pub async fn comment_permission(
+        &self,
+        channel: &ChannelMetadata,
+        content_cid: Cid,
+    ) -> Result<CommentPermission, Error> {
+        let Some(ipld) = channel.comment_settings else {
+            return Ok(CommentPermission::Everyone);
+        };
+
+        let settings = self
+            .ipfs
+            .dag_get::<&str, CommentSettings>(ipld.link, None, Codec::default())
+            .await?;
+
+        Ok(settings.get(content_cid))
+    }
+
+    /// Returns the channel of a commenter, if its metadata names the commenter identity.
+    ///
+    /// Identities declare any channel address, only the channel metadata proves ownership.
+    /// Channels that cannot be resolved or decoded are ignored.
+    pub async fn commenter_channel(
+        &self,
+        comment: &Comment,
+        identity: &Identity,
+    ) -> Result<Option<(IPNSAddress, ChannelMetadata)>, Error> {
+        let Some(addr) = identity.ipns_addr else {
+            return Ok(None);
+        };
+
+        let Ok(cid) = self.name_resolve(addr).await else {
+            return Ok(None);
+        };
+
+        let metadata = match self
+            .ipfs
+            .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+            .await
+        {
+            Ok(metadata) => metadata,
+            Err(e) if e.is_decode() || e.is_timeout() => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        if metadata.identity != comment.identity {
+            return Ok(None);
+        }
+
+        Ok(Some((addr, metadata)))
+    }
+
+    /// Returns true if the channel of this commenter follows the other channel.
+    ///
+    /// Commenters without a channel or whose channel does not name their identity follow no one.
+    pub async fn follows_channel(
+        &self,
+        comment: &Comment,
+        identity: &Identity,
+        channel: IPNSAddress,
+    ) -> Result<bool, Error> {
+        match self.commenter_channel(comment, identity).await? {
+            Some((_, metadata)) => self.metadata_follows(&metadata, channel).await,
+            None => Ok(false),
+        }
+    }
+
+    async fn metadata_follows(
+        &self,
+        metadata: &ChannelMetadata,
+        channel: IPNSAddress,
+    ) -> Result<bool, Error> {
+        let Some(ipld) = metadata.follows else {
+            return Ok(false);
+        };
+
+        let follows = match self
+            .ipfs
+            .dag_get::<&str, Follows>(ipld.link, None, Codec::default())
+            .await
+        {
+            Ok(follows) => follows,
+            Err(e) if e.is_decode() || e.is_timeout() => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(follows.followees.contains(&channel))
+    }
+
+    /// Returns the pinned comment CIDs of this content, in pinning order.
+    pub async fn pinned_comments(
+        &self,
+        comment_index: IPLDLink,
+        content_cid: Cid,
+    ) -> Result<Vec<Cid>, Error> {
+        let pinned = match media_comments(&self.ipfs, comment_index, content_cid).await? {
+            Some(comments) => comments.pinned,
+            None => return Ok(Vec::new()),
+        };
+
+        Ok(pinned.into_iter().map(|ipld| ipld.link).collect())
+    }
+
+    /// Returns the comment count of each media, without listing their comments.
+    ///
+    /// Media without comments have a count of zero.
+    pub async fn comment_counts(
+        &self,
+        comment_counts: IPLDLink,
+        media_cids: &[Cid],
+    ) -> Result<HashMap<Cid, CommentCount>, Error> {
+        let counts = media_cids.iter().map(|media_cid| async move {
+            let count = match hamt::get(&self.ipfs, comment_counts, *media_cid).await? {
+                Some(cid) => {
+                    self.ipfs
+                        .dag_get::<&str, CommentCount>(cid, None, Codec::default())
+                        .await?
+                }
+                None => CommentCount::default(),
+            };
+
+            Result::<_, Error>::Ok((*media_cid, count))
+        });
+
+        let counts = future::try_join_all(counts).await?;
+
+        Ok(counts.into_iter().collect())
+    }
+
+    /// Stream all comment CIDs for some content on a channel, pinned comments first.
+    pub fn stream_content_comments_pinned_first(
+        &self,
+        comment_index: IPLDLink,
+        content_cid: Cid,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        stream::once(self.pinned_comments(comment_index, content_cid))
+            .map_ok(move |pinned| {
+                let set: HashSet<Cid> = pinned.iter().copied().collect();
+
+                let others = self
+                    .stream_content_comments(comment_index, content_cid)
+                    .try_filter(move |cid| future::ready(!set.contains(cid)));
+
+                stream::iter(pinned.into_iter().map(Ok)).chain(others)
+            })
+            .try_flatten()
+    }
+
+    /// Stream comment CIDs for some content on a channel that the filter allows.
+    pub fn stream_content_comments_filtered<'a, F: Filter>(
+        &'a self,
+        comment_index: IPLDLink,
+        content_cid: Cid,
+        filter: &'a F,
+    ) -> impl Stream<Item = Result<Cid, Error>> + 'a {
+        self.stream_content_comments(comment_index, content_cid)
+            .try_filter_map(move |cid| async move {
+                let comment: Comment = self
+                    .ipfs
+                    .dag_get(cid, Some("/link"), Codec::default())
+                    .await?;
+
+                Ok(filter.allow_comment(&comment).then_some(cid))
+            })
+    }
+
+    /// Stream the comments on this content, each one verified against its commenter identity.
+    ///
+    /// Unverifiable comments are yielded as rejections.
+    pub fn stream_content_comments_verified(
+        &self,
+        comment_index: IPLDLink,
+        content_cid: Cid,
+    ) -> impl Stream<Item = Result<VerifiedComment, Error>> + '_ {
+        self.stream_content_comments(comment_index, content_cid)
+            .and_then(move |cid| async move { verify_comment(&self.ipfs, cid).await })
+    }
+
+    /// Returns where this content is stored.
+    ///
+    /// Frontends should warn that archived media are slower to load.
+    pub async fn media_tier(
+        &self,
+        archive_index: IPLDLink,
+        content_cid: Cid,
+    ) -> Result<ArchiveRecord, Error> {
+        let record = match hamt::get(&self.ipfs, archive_index, content_cid).await? {
+            Some(cid) => {
+                self.ipfs
+                    .dag_get::<&str, ArchiveRecord>(cid, None, Codec::default())
+                    .await?
+            }
+            None => ArchiveRecord::default(),
+        };
+
+        Ok(record)
+    }
+
+    /// Returns true if the video archive holds fewer segments than recorded at creation.
+    ///
+    /// Videos created without a segment count cannot be audited and are never reported.
+    pub async fn is_video_truncated(&self, video: &Video) -> Result<bool, Error> {
+        let expected = match video.segment_count {
+            Some(count) => count,
+            None => return Ok(false),
+        };
+
+        let days: Day = self
+            .ipfs
+            .dag_get(video.video.link, Some("/time"), Codec::default())
+            .await?;
+
+        let mut count = 0;
+
+        for ipld in days.links_to_hours {
+            let hours: Hour = self
+                .ipfs
+                .dag_get(ipld.link, Option::<&str>::None, Codec::default())
+                .await?;
+
+            for ipld in hours.links_to_minutes {
+                let minutes: Minute = self
+                    .ipfs
+                    .dag_get(ipld.link, Option::<&str>::None, Codec::default())
+                    .await?;
+
+                count += minutes.links_to_seconds.len() as u64;
+            }
+        }
+
+        Ok(count < expected)
+    }
+
+    /// Returns the claims of external accounts of this identity whose proof could be verified.
+    ///
+    /// Proofs are fetched from each platform, claims that cannot be verified are skipped.
+    #[cfg(feature = "claims")]
+    pub async fn verified_claims(&self, identity_cid: Cid) -> Result<Vec<(Cid, Claim)>, Error> {
+        let identity: Identity = self
+            .ipfs
+            .dag_get(identity_cid, Option::<&str>::None, Codec::default())
+            .await?;
+
+        let identity = &identity;
+
+        let claims: Vec<Option<Claim>> = stream::iter(identity.claims.iter())
+            .map(|ipld| async move {
+                claims::verify_claim(&self.ipfs, ipld.link, identity)
+                    .await
+                    .ok()
+                    .flatten()
+            })
+            .buffered(4)
+            .collect()
+            .await;
+
+        let verified = identity
+            .claims
+            .iter()
+            .zip(claims)
+            .filter_map(|(ipld, claim)| claim.map(|claim| (ipld.link, claim)))
+            .collect();
+
+        Ok(verified)
+    }
+
+    /// Returns a DASH manifest of this video archive, segments are served by the gateway.
+    ///
+    /// Widen playback to players without HLS support.
+    pub async fn dash_manifest(&self, video_cid: Cid, gateway: &Gateway) -> Result<String, Error> {
+        let video: Video = self
+            .ipfs
+            .dag_get(video_cid, Some("/link"), Codec::default())
+            .await?;
+
+        let timecode: Timecode = self
+            .ipfs
+            .dag_get(video.video.link, Option::<&str>::None, Codec::default())
+            .await?;
+
+        let days = self
+            .ipfs
+            .dag_get::<&str, Day>(timecode.timecode.link, None, Codec::default())
+            .await?;
+
+        let mut seconds = Vec::new();
+
+        for ipld in days.links_to_hours {
+            let hours: Hour = self
+                .ipfs
+                .dag_get(ipld.link, Option::<&str>::None, Codec::default())
+                .await?;
+
+            for ipld in hours.links_to_minutes {
+                let minutes: Minute = self
+                    .ipfs
+                    .dag_get(ipld.link, Option::<&str>::None, Codec::default())
+                    .await?;
+
+                seconds.extend(minutes.links_to_seconds);
+            }
+        }
+
+        let segments: Vec<Segment> = stream::iter(seconds)
+            .map(|ipld| async move {
+                self.ipfs
+                    .dag_get::<&str, Segment>(ipld.link, Some("/video"), Codec::default())
+                    .await
+            })
+            .buffered(16)
+            .try_collect()
+            .await?;
+
+        let setup = match segments.first().and_then(|segment| segment.setup) {
+            Some(ipld) => ipld,
+            None => return Err(Error::NotFound),
+        };
+
+        let setup: Setup = self
+            .ipfs
+            .dag_get(setup.link, Option::<&str>::None, Codec::default())
+            .await?;
+
+        let duration = timecode.duration.or(video.duration);
+
+        Ok(dash::dash_mpd(&setup, &segments, duration, gateway))
+    }
+
+    /// Returns the number of upvotes of every upvoted comment on this content.
+    pub async fn comment_scores(
+        &self,
+        upvote_index: IPLDLink,
+        content_cid: Cid,
+    ) -> Result<HashMap<Cid, usize>, Error> {
+        let upvotes = match hamt::get(&self.ipfs, upvote_index, content_cid).await? {
+            Some(upvotes) => upvotes,
+            None => return Ok(HashMap::default()),
+        };
+
+        hamt::values(&self.ipfs, upvotes.into())
+            .and_then(|(comment, voters)| async move {
+                let voters = self
+                    .ipfs
+                    .dag_get::<&str, Voters>(voters, None, Codec::default())
+                    .await?;
+
+                Result::<_, Error>::Ok((comment, voters.voters.len()))
+            })
+            .try_collect()
+            .await
+    }
+
+    /// Returns all the comments on this content, highest score first.
+    ///
+    /// Comments with the same score are ordered by CID for consistency.
+    pub async fn content_comments_by_score(
+        &self,
+        comment_index: IPLDLink,
+        upvote_index: Option<IPLDLink>,
+        content_cid: Cid,
+    ) -> Result<Vec<(Cid, usize)>, Error> {
+        let scores = match upvote_index {
+            Some(index) => self.comment_scores(index, content_cid).await?,
+            None => HashMap::default(),
+        };
+
+        let mut comments: Vec<(Cid, usize)> = self
+            .stream_content_comments(comment_index, content_cid)
+            .map_ok(|cid| (cid, scores.get(&cid).copied().unwrap_or_default()))
+            .try_collect()
+            .await?;
+
+        comments.sort_unstable_by(|(a_cid, a), (b_cid, b)| b.cmp(a).then(a_cid.cmp(b_cid)));
+
+        Ok(comments)
     }
 
     /// Stream all the comments on a channel.
@@ -458,7 +2781,108 @@ impl Defluencer {
         comment_index: IPLDLink,
     ) -> impl Stream<Item = Result<(Cid, Cid), Error>> + '_ {
         hamt::values(&self.ipfs, comment_index)
-            .map_ok(|(_, cid)| hamt::values(&self.ipfs, cid.into()))
+            .and_then(move |(media_cid, cid)| async move {
+                let comments = load_media_comments(&self.ipfs, cid).await?;
+
+                let stream = hamt::values(&self.ipfs, comments.comments)
+                    .map_ok(move |(_, comment_cid)| (media_cid, comment_cid));
+
+                Result::<_, Error>::Ok(stream)
+            })
             .try_flatten()
     }
+
+    /// Stream all the comments on a channel that the filter allows.
+    ///
+    /// Returns (Media CID, Comment CID)
+    pub fn stream_all_comments_filtered<'a, F: Filter>(
+        &'a self,
+        comment_index: IPLDLink,
+        filter: &'a F,
+    ) -> impl Stream<Item = Result<(Cid, Cid), Error>> + 'a {
+        self.stream_all_comments(comment_index)
+            .try_filter_map(move |(media_cid, cid)| async move {
+                let comment: Comment = self
+                    .ipfs
+                    .dag_get(cid, Some("/link"), Codec::default())
+                    .await?;
+
+                Ok(filter.allow_comment(&comment).then_some((media_cid, cid)))
+            })
+    }
+
+    /// Stream live chat messages that the filter allows.
+    ///
+    /// Messages that cannot be decoded are dropped.
+    pub fn stream_chat<'a, F: Filter>(
+        &'a self,
+        topic: String,
+        filter: &'a F,
+    ) -> impl Stream<Item = Result<(PeerId, ChatMessage), Error>> + 'a {
+        self.ipfs
+            .pubsub_sub(topic.into_bytes())
+            .err_into()
+            .try_filter_map(move |msg| async move {
+                let PubSubMessage { from, data } = msg;
+
+                let message = match chat::decode_message(&data) {
+                    Some(message) => message,
+                    None => return Ok(None),
+                };
+
+                Ok(filter.allow_chat(from, &message).then_some((from, message)))
+            })
+    }
+
+    /// Stream the live video segments of a channel.
+    ///
+    /// Segments not signed by the segment key of the live settings are dropped.
+    pub fn stream_live_segments(
+        &self,
+        settings: LiveSettings,
+    ) -> impl Stream<Item = Result<Cid, Error>> + '_ {
+        self.ipfs
+            .pubsub_sub(settings.video_topic.clone().into_bytes())
+            .err_into()
+            .try_filter_map(move |msg| {
+                let PubSubMessage { from: _, data } = msg;
+
+                future::ready(Ok(live::decode_segment(&settings, &data)))
+            })
+    }
+
+    /// Stream the active chat settings broadcast by the streaming node.
+    pub fn stream_chat_settings(
+        &self,
+        topic: String,
+        peer_id: PeerId,
+    ) -> impl Stream<Item = Result<ChatSettings, Error>> + '_ {
+        self.ipfs
+            .pubsub_sub(topic.into_bytes())
+            .err_into()
+            .try_filter_map(move |msg| async move {
+                let PubSubMessage { from, data } = msg;
+
+                if from != peer_id {
+                    return Ok(None);
+                }
+
+                Ok(serde_json::from_slice(&data).ok())
+            })
+    }
+
+    /// Stream all the comments on a channel, each one verified against its commenter identity.
+    ///
+    /// Returns (Media CID, Comment or Rejection)
+    pub fn stream_all_comments_verified(
+        &self,
+        comment_index: IPLDLink,
+    ) -> impl Stream<Item = Result<(Cid, VerifiedComment), Error>> + '_ {
+        self.stream_all_comments(comment_index)
+            .and_then(move |(media_cid, cid)| async move {
+                let verified = verify_comment(&self.ipfs, cid).await?;
+
+                Ok((media_cid, verified))
+            })
+    }
 }
@@ -0,0 +1,96 @@
+use cid::Cid;
+
+use ipfs_api::IpfsService;
+
+use serde::Serialize;
+
+use crate::errors::Error;
+
+/// Maximum comment and micro blog post text length in bytes.
+pub const MAX_TEXT_SIZE: usize = 2000;
+
+/// Maximum blog post markdown file size in bytes.
+pub const MAX_MARKDOWN_SIZE: u64 = 1024 * 1024;
+
+/// Maximum encoded size of a content node in bytes.
+pub const MAX_NODE_SIZE: u64 = 16 * 1024;
+
+/// Size limits of the content created by users.
+///
+/// Payloads over the limits are rejected before being added to IPFS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeLimits {
+    pub text: usize,
+    pub markdown: u64,
+    pub node: u64,
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        Self {
+            text: MAX_TEXT_SIZE,
+            markdown: MAX_MARKDOWN_SIZE,
+            node: MAX_NODE_SIZE,
+        }
+    }
+}
+
+impl SizeLimits {
+    pub fn check_text(&self, text: &str) -> Result<(), Error> {
+        check(text.len() as u64, self.text as u64)
+    }
+
+    pub fn check_markdown(&self, size: u64) -> Result<(), Error> {
+        check(size, self.markdown)
+    }
+
+    /// Check the size of a node once encoded as DAG-CBOR.
+    pub fn check_node<T>(&self, node: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let data = serde_ipld_dagcbor::to_vec(node).map_err(ipfs_api::errors::Error::from)?;
+
+        check(data.len() as u64, self.node)
+    }
+
+    /// Check the size of a block without fetching it.
+    pub async fn check_block(&self, ipfs: &IpfsService, cid: Cid) -> Result<(), Error> {
+        let size = ipfs.block_stat(cid).await?;
+
+        check(size, self.node)
+    }
+}
+
+fn check(size: u64, limit: u64) -> Result<(), Error> {
+    if size > limit {
+        return Err(Error::TooLarge { size, limit });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits() {
+        let limits = SizeLimits {
+            text: 5,
+            markdown: 10,
+            node: 64,
+        };
+
+        assert!(limits.check_text("hello").is_ok());
+        assert!(matches!(
+            limits.check_text("hello!"),
+            Err(Error::TooLarge { size: 6, limit: 5 })
+        ));
+
+        assert!(limits.check_markdown(11).is_err());
+
+        assert!(limits.check_node("small").is_ok());
+        assert!(limits.check_node(&"large".repeat(20)).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use cid::Cid;
+
+use k256::ecdsa::{
+    signature::{DigestSigner, DigestVerifier},
+    Signature, VerifyingKey,
+};
+
+use linked_data::{
+    channel::live::{LiveSettings, SignedSegment},
+    media::event::Event,
+    types::IPNSAddress,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::errors::Error;
+
+pub use k256::ecdsa::SigningKey;
+
+const SEGMENT_CONTEXT: &str = "defluencer 2023-06 live segment";
+
+/// Generate a new key to sign live segments with.
+///
+/// Hardware wallets are too slow to sign every segment,
+/// publish the public key in your live settings instead.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn generate_segment_key() -> SigningKey {
+    SigningKey::random(&mut rand_core::OsRng)
+}
+
+/// SEC1 encoded public key, as stored in the live settings.
+pub fn segment_public_key(key: &SigningKey) -> Vec<u8> {
+    key.verifying_key().to_sec1_bytes().into_vec()
+}
+
+fn segment_digest(segment: Cid) -> Sha256 {
+    let mut digest = Sha256::new_with_prefix(SEGMENT_CONTEXT);
+    digest.update(segment.to_bytes());
+    digest
+}
+
+/// Sign a segment CID.
+///
+/// Each segment links to the previous one, signing the latest also vouches for the chain.
+pub fn sign_segment(key: &SigningKey, segment: Cid) -> SignedSegment {
+    let signature: Signature = key.sign_digest(segment_digest(segment));
+
+    SignedSegment {
+        segment: segment.into(),
+        signature: signature.to_vec(),
+    }
+}
+
+/// Check a segment signature against a SEC1 encoded public key.
+pub fn verify_segment(public_key: &[u8], signed: &SignedSegment) -> bool {
+    let Ok(verif_key) = VerifyingKey::from_sec1_bytes(public_key) else {
+        return false;
+    };
+
+    let Ok(signature) = Signature::from_slice(&signed.signature) else {
+        return false;
+    };
+
+    verif_key
+        .verify_digest(segment_digest(signed.segment.link), &signature)
+        .is_ok()
+}
+
+/// Encode a signed segment for pubsub, in DAG-CBOR.
+pub fn encode_segment(signed: &SignedSegment) -> Result<Vec<u8>, Error> {
+    let data = serde_ipld_dagcbor::to_vec(signed).map_err(ipfs_api::errors::Error::from)?;
+
+    Ok(data)
+}
+
+/// Decode a segment announcement, none if invalid.
+///
+/// When the live settings include a segment key only segments signed by it are returned,
+/// otherwise bare CIDs of older streaming nodes are accepted too.
+pub fn decode_segment(settings: &LiveSettings, data: &[u8]) -> Option<Cid> {
+    let signed = serde_ipld_dagcbor::from_slice::<SignedSegment>(data).ok();
+
+    match (settings.segment_key.as_deref(), signed) {
+        (Some(public_key), Some(signed)) => {
+            verify_segment(public_key, &signed).then_some(signed.segment.link)
+        }
+        (Some(_), None) => None,
+        (None, Some(signed)) => Some(signed.segment.link),
+        (None, None) => Cid::try_from(data).ok(),
+    }
+}
+
+/// Event announcing that a channel is streaming now, as submitted to hubs.
+///
+/// Hubs accept any media, the event links the stream so discovery sites can list it.
+pub fn live_beacon(identity: Cid, channel: IPNSAddress, title: String, now: i64) -> Event {
+    Event {
+        identity: identity.into(),
+        user_timestamp: now,
+        title,
+        start: now,
+        stream: Some(channel),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![cfg(not(target_arch = "wasm32"))]
+
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    #[test]
+    fn segment_signatures() {
+        let key = generate_segment_key();
+        let squatter = generate_segment_key();
+
+        let segment = Cid::new_v1(0x71, Code::Sha2_256.digest(b"segment"));
+        let other = Cid::new_v1(0x71, Code::Sha2_256.digest(b"other"));
+
+        let signed = sign_segment(&key, segment);
+
+        assert!(verify_segment(&segment_public_key(&key), &signed));
+        assert!(!verify_segment(&segment_public_key(&squatter), &signed));
+
+        let forged = SignedSegment {
+            segment: other.into(),
+            ..signed.clone()
+        };
+
+        assert!(!verify_segment(&segment_public_key(&key), &forged));
+
+        let data = encode_segment(&signed).unwrap();
+
+        let mut settings = LiveSettings::default();
+
+        assert_eq!(decode_segment(&settings, &data), Some(segment));
+        assert_eq!(
+            decode_segment(&settings, &segment.to_bytes()),
+            Some(segment)
+        );
+
+        settings.segment_key = Some(segment_public_key(&key));
+
+        assert_eq!(decode_segment(&settings, &data), Some(segment));
+        assert_eq!(decode_segment(&settings, &segment.to_bytes()), None);
+
+        let squatted = encode_segment(&sign_segment(&squatter, other)).unwrap();
+
+        assert_eq!(decode_segment(&settings, &squatted), None);
+    }
+
+    #[test]
+    fn beacons() {
+        let identity = Cid::new_v1(0x71, Code::Sha2_256.digest(b"identity"));
+        let channel =
+            IPNSAddress::try_from(Cid::new_v1(0x72, Code::Sha2_256.digest(b"channel"))).unwrap();
+
+        let beacon = live_beacon(identity, channel, String::from("Live"), 1_700_000_000);
+
+        assert_eq!(beacon.identity.link, identity);
+        assert_eq!(beacon.title, "Live");
+        assert_eq!(beacon.user_timestamp, 1_700_000_000);
+        assert_eq!(beacon.start, 1_700_000_000);
+        assert_eq!(beacon.stream, Some(channel));
+        assert_eq!(beacon.duration, None);
+    }
+}
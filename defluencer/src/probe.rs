@@ -0,0 +1,220 @@
+use std::{ffi::OsStr, process::Stdio};
+
+use linked_data::media::MediaFormat;
+
+use serde::Deserialize;
+
+use tokio::process::Command;
+
+use crate::errors::Error;
+
+/// Audio codecs playable by browsers.
+const AUDIO_CODECS: [&str; 5] = ["mp3", "aac", "opus", "vorbis", "flac"];
+
+/// Output of ffprobe.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Probe {
+    format: Format,
+
+    #[serde(default)]
+    streams: Vec<Stream>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct Format {
+    format_name: String,
+
+    /// Seconds, as a decimal string.
+    #[serde(default)]
+    duration: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct Stream {
+    #[serde(default)]
+    codec_type: String,
+
+    #[serde(default)]
+    codec_name: String,
+
+    #[serde(default)]
+    disposition: Disposition,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+struct Disposition {
+    /// Cover art is stored as a single frame video stream.
+    #[serde(default)]
+    attached_pic: u8,
+}
+
+impl Probe {
+    /// Duration in seconds.
+    pub fn duration(&self) -> Option<f64> {
+        self.format.duration.as_deref()?.parse().ok()
+    }
+
+    /// Check that browsers can play this file as is.
+    ///
+    /// Returns the media type and format of the file.
+    pub fn audio(&self) -> Result<(String, MediaFormat), Error> {
+        let container = &self.format.format_name;
+
+        let mime_type = match container.split(',').next().unwrap_or_default() {
+            "mp3" => "audio/mpeg",
+            "mov" | "mp4" => "audio/mp4",
+            "ogg" => "audio/ogg",
+            "flac" => "audio/flac",
+            "wav" => "audio/wav",
+            "matroska" => "audio/webm",
+            _ => return Err(Error::MediaFormat(format!("container {}", container))),
+        };
+
+        let codecs = self.codecs("audio");
+
+        if codecs.is_empty() {
+            return Err(Error::MediaFormat(format!(
+                "no audio track in {}",
+                container
+            )));
+        }
+
+        for codec in codecs.iter() {
+            if !(AUDIO_CODECS.contains(&codec.as_str()) || codec.starts_with("pcm_")) {
+                return Err(Error::MediaFormat(format!("audio codec {}", codec)));
+            }
+        }
+
+        let format = MediaFormat {
+            container: container.clone(),
+            codecs,
+        };
+
+        Ok((mime_type.to_owned(), format))
+    }
+
+    /// Check that this file can be encoded to HLS, any codec ffmpeg decodes will do.
+    ///
+    /// Returns the format of the file.
+    pub fn video(&self) -> Result<MediaFormat, Error> {
+        let container = &self.format.format_name;
+
+        let video = self.codecs("video");
+
+        if video.is_empty() {
+            return Err(Error::MediaFormat(format!(
+                "no video track in {}",
+                container
+            )));
+        }
+
+        let audio = self.codecs("audio");
+
+        if audio.is_empty() {
+            return Err(Error::MediaFormat(format!(
+                "no audio track in {}",
+                container
+            )));
+        }
+
+        Ok(MediaFormat {
+            container: container.clone(),
+            codecs: video.into_iter().chain(audio).collect(),
+        })
+    }
+
+    /// Codecs of the tracks of this type, cover art excluded.
+    fn codecs(&self, codec_type: &str) -> Vec<String> {
+        self.streams
+            .iter()
+            .filter(|stream| {
+                stream.codec_type == codec_type && stream.disposition.attached_pic == 0
+            })
+            .map(|stream| stream.codec_name.clone())
+            .collect()
+    }
+}
+
+/// Probe a file path or URL with ffprobe.
+pub async fn probe(input: impl AsRef<OsStr>) -> Result<Probe, Error> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_format",
+            "-show_streams",
+            "-of",
+            "json",
+        ])
+        .arg(input)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+
+        return Err(Error::MediaFormat(error.trim().to_owned()));
+    }
+
+    parse_probe(&output.stdout)
+}
+
+/// Parse the JSON output of ffprobe.
+pub fn parse_probe(json: &[u8]) -> Result<Probe, Error> {
+    let probe = serde_json::from_slice(json)?;
+
+    Ok(probe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MP3: &str = r#"{
+        "streams": [
+            { "index": 0, "codec_name": "mp3", "codec_type": "audio", "disposition": { "attached_pic": 0 } },
+            { "index": 1, "codec_name": "mjpeg", "codec_type": "video", "disposition": { "attached_pic": 1 } }
+        ],
+        "format": { "format_name": "mp3", "duration": "1534.223673" }
+    }"#;
+
+    const MKV: &str = r#"{
+        "streams": [
+            { "index": 0, "codec_name": "vp9", "codec_type": "video" },
+            { "index": 1, "codec_name": "opus", "codec_type": "audio" }
+        ],
+        "format": { "format_name": "matroska,webm" }
+    }"#;
+
+    #[test]
+    fn audio_and_video() {
+        let mp3 = parse_probe(MP3.as_bytes()).unwrap();
+
+        let (mime_type, format) = mp3.audio().unwrap();
+
+        assert_eq!(mime_type, "audio/mpeg");
+        assert_eq!(format.codecs, vec!["mp3"]);
+        assert_eq!(mp3.duration(), Some(1534.223673));
+
+        // Cover art is not a video.
+        assert!(matches!(mp3.video(), Err(Error::MediaFormat(_))));
+
+        let mkv = parse_probe(MKV.as_bytes()).unwrap();
+
+        let format = mkv.video().unwrap();
+
+        assert_eq!(format.container, "matroska,webm");
+        assert_eq!(format.codecs, vec!["vp9", "opus"]);
+        assert_eq!(mkv.duration(), None);
+
+        let wma = r#"{
+            "streams": [{ "codec_name": "wmav2", "codec_type": "audio" }],
+            "format": { "format_name": "asf" }
+        }"#;
+        let wma = parse_probe(wma.as_bytes()).unwrap();
+
+        assert!(matches!(wma.audio(), Err(Error::MediaFormat(e)) if e == "container asf"));
+    }
+}
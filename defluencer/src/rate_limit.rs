@@ -0,0 +1,88 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use cid::Cid;
+
+/// Sliding window rate limiter keyed by identity CID.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Maximum number of actions per window.
+    max: usize,
+
+    /// Window length in seconds.
+    window: i64,
+
+    history: Mutex<HashMap<Cid, VecDeque<i64>>>,
+}
+
+impl RateLimiter {
+    pub fn new(max: usize, window: i64) -> Self {
+        Self {
+            max,
+            window,
+            history: Default::default(),
+        }
+    }
+
+    /// Returns true and record the action if this identity is under the limit at this time.
+    pub fn allow(&self, identity: Cid, timestamp: i64) -> bool {
+        let mut history = self.history.lock().expect("Lock Poisoned");
+
+        let actions = history.entry(identity).or_default();
+
+        if self.recent(actions, timestamp) >= self.max {
+            return false;
+        }
+
+        actions.push_back(timestamp);
+
+        true
+    }
+
+    /// Returns true if this identity is over the limit at this time, without recording anything.
+    pub fn is_limited(&self, identity: Cid, timestamp: i64) -> bool {
+        let mut history = self.history.lock().expect("Lock Poisoned");
+
+        match history.get_mut(&identity) {
+            Some(actions) => self.recent(actions, timestamp) >= self.max,
+            None => false,
+        }
+    }
+
+    /// Drop the actions outside the window then count the rest.
+    fn recent(&self, actions: &mut VecDeque<i64>, timestamp: i64) -> usize {
+        while let Some(oldest) = actions.front() {
+            if *oldest > timestamp - self.window {
+                break;
+            }
+
+            actions.pop_front();
+        }
+
+        actions.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sliding_window() {
+        let identity =
+            Cid::try_from("bafyreih223c6mqauz5ouolokqrofaekpuu45eblm33fm3g2rlwdkqfabo4").unwrap();
+
+        let limiter = RateLimiter::new(2, 60);
+
+        assert!(limiter.allow(identity, 0));
+        assert!(limiter.allow(identity, 10));
+        assert!(!limiter.allow(identity, 20));
+        assert!(limiter.allow(identity, 61));
+        assert!(!limiter.allow(identity, 62));
+        assert!(limiter.allow(identity, 71));
+        assert!(limiter.is_limited(identity, 72));
+        assert!(!limiter.is_limited(identity, 200));
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+
+use cid::Cid;
+
+use futures::{pin_mut, TryStreamExt};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{
+    identity::{Identity, RecoveryNode, Vouches},
+    types::IPNSAddress,
+};
+
+use crate::{
+    crypto::signed_link::{HashAlgorithm, SignedLink},
+    errors::Error,
+};
+
+/// Pubsub topic where trusted channels send their vouches for an identity.
+pub fn recovery_topic(identity: Cid) -> String {
+    format!("{}_recovery", identity)
+}
+
+/// Request binding this address to an identity whose keys were lost.
+///
+/// Share the returned recovery node CID with the trusted channels, see [crate::user::User::vouch_recovery].
+pub async fn create_recovery(
+    ipfs: &IpfsService,
+    identity: Cid,
+    eth_addr: String,
+) -> Result<Cid, Error> {
+    let node = RecoveryNode {
+        identity: identity.into(),
+        eth_addr,
+        timestamp: Utc::now().timestamp(),
+    };
+
+    let cid = ipfs
+        .dag_put(&node, Codec::default(), Codec::default())
+        .await?;
+
+    Ok(cid)
+}
+
+/// Wait for trusted channels to vouch for this recovery on the recovery topic.
+///
+/// Returns the CID of the vouches once enough were verified, see [verify_recovery].
+pub async fn collect_vouches(ipfs: &IpfsService, recovery: Cid) -> Result<Cid, Error> {
+    let node = ipfs
+        .dag_get::<&str, RecoveryNode>(recovery, None, Codec::default())
+        .await?;
+
+    let identity = ipfs
+        .dag_get::<&str, Identity>(node.identity.link, None, Codec::default())
+        .await?;
+
+    // Vouches could never be enough.
+    match identity.recovery {
+        Some(recovery) if valid_threshold(recovery.threshold, recovery.channels.len()) => {}
+        _ => return Err(Error::Recovery),
+    }
+
+    let topic = recovery_topic(node.identity.link).into_bytes();
+    let stream = ipfs.pubsub_sub(topic);
+    pin_mut!(stream);
+
+    let mut signatures = Vec::new();
+
+    while let Some(msg) = stream.try_next().await? {
+        let cid = match Cid::try_from(msg.data.as_slice()) {
+            Ok(cid) => cid,
+            Err(_) => continue,
+        };
+
+        let signed_link = match ipfs
+            .dag_get::<&str, SignedLink>(cid, None, Codec::default())
+            .await
+        {
+            Ok(link) => link,
+            Err(_) => continue,
+        };
+
+        if signed_link.link.link != recovery || signatures.contains(&cid.into()) {
+            continue;
+        }
+
+        signatures.push(cid.into());
+
+        let vouches = Vouches {
+            recovery: recovery.into(),
+            signatures: signatures.clone(),
+        };
+
+        let cid = ipfs
+            .dag_put(&vouches, Codec::default(), Codec::default())
+            .await?;
+
+        match verify_recovery(ipfs, cid).await {
+            Ok(_) => return Ok(cid),
+            Err(Error::Recovery) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(Error::Recovery)
+}
+
+/// Verify that enough trusted channels vouched for this recovery.
+///
+/// Channels are trusted if listed by the recovered identity,
+/// their vouches must be signed by the address of their current identity.
+/// Channels that can't be resolved are skipped.
+///
+/// Returns the recovery node.
+pub async fn verify_recovery(ipfs: &IpfsService, vouches: Cid) -> Result<RecoveryNode, Error> {
+    let vouches = ipfs
+        .dag_get::<&str, Vouches>(vouches, None, Codec::default())
+        .await?;
+
+    let node = ipfs
+        .dag_get::<&str, RecoveryNode>(vouches.recovery.link, None, Codec::default())
+        .await?;
+
+    let identity = ipfs
+        .dag_get::<&str, Identity>(node.identity.link, None, Codec::default())
+        .await?;
+
+    let recovery = match identity.recovery {
+        Some(recovery) => recovery,
+        None => return Err(Error::Recovery),
+    };
+
+    if !valid_threshold(recovery.threshold, recovery.channels.len()) {
+        return Err(Error::Recovery);
+    }
+
+    let mut addresses = HashMap::with_capacity(recovery.channels.len());
+
+    for channel in recovery.channels {
+        let identity = match channel_identity(ipfs, channel).await {
+            Ok(identity) => identity,
+            Err(_) => continue,
+        };
+
+        if let Some(addr) = identity.eth_addr {
+            addresses.insert(channel, addr);
+        }
+    }
+
+    let mut signed_links = Vec::with_capacity(vouches.signatures.len());
+
+    for ipld in vouches.signatures {
+        let signed_link = ipfs
+            .dag_get::<&str, SignedLink>(ipld.link, None, Codec::default())
+            .await?;
+
+        signed_links.push(signed_link);
+    }
+
+    if vouchers(vouches.recovery.link, &signed_links, &addresses).len() < recovery.threshold {
+        return Err(Error::Recovery);
+    }
+
+    Ok(node)
+}
+
+/// At least one vouch is required and no more than there are trusted channels.
+pub(crate) fn valid_threshold(threshold: usize, channels: usize) -> bool {
+    threshold > 0 && threshold <= channels
+}
+
+async fn channel_identity(ipfs: &IpfsService, channel: IPNSAddress) -> Result<Identity, Error> {
+    let cid = ipfs.name_resolve(channel).await?;
+
+    let identity = ipfs
+        .dag_get(cid, Some("/identity"), Codec::default())
+        .await?;
+
+    Ok(identity)
+}
+
+/// Returns the trusted channels that signed this recovery node.
+fn vouchers(
+    recovery: Cid,
+    signed_links: &[SignedLink],
+    addresses: &HashMap<IPNSAddress, String>,
+) -> HashSet<IPNSAddress> {
+    let mut vouchers = HashSet::with_capacity(signed_links.len());
+
+    for signed_link in signed_links {
+        if signed_link.link.link != recovery
+            || signed_link.hash_algo != HashAlgorithm::EthereumLedgerApp
+            || !signed_link.verify()
+        {
+            continue;
+        }
+
+        let signer = signed_link.get_address();
+
+        for (channel, addr) in addresses.iter() {
+            if addr.eq_ignore_ascii_case(&signer) {
+                vouchers.insert(*channel);
+            }
+        }
+    }
+
+    vouchers
+}
+
+/// New addresses accepted for identities that lost their keys.
+#[derive(Debug, Default, Clone)]
+pub struct Recoveries {
+    addresses: HashMap<Cid, HashSet<String>>,
+}
+
+impl Recoveries {
+    /// Accept the new address of a verified recovery.
+    pub fn insert(&mut self, node: &RecoveryNode) {
+        self.addresses
+            .entry(node.identity.link)
+            .or_default()
+            .insert(node.eth_addr.to_lowercase());
+    }
+
+    /// Returns true if this address was recovered for this identity.
+    pub fn is_recovered(&self, identity: Cid, address: &str) -> bool {
+        match self.addresses.get(&identity) {
+            Some(addresses) => addresses.contains(&address.to_lowercase()),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use k256::ecdsa::{signature::DigestSigner, Signature, SigningKey};
+
+    use multihash::{Code, MultihashDigest};
+
+    use sha3::{Digest, Keccak256};
+
+    fn vouch(key: &SigningKey, recovery: Cid) -> SignedLink {
+        let signing_input = recovery.hash().digest();
+        let mut eth_message =
+            format!("\x19Ethereum Signed Message:\n{}", signing_input.len()).into_bytes();
+        eth_message.extend_from_slice(signing_input);
+
+        let signature: Signature = key.sign_digest(Keccak256::new_with_prefix(eth_message));
+
+        SignedLink {
+            link: recovery.into(),
+            public_key: key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+            hash_algo: HashAlgorithm::EthereumLedgerApp,
+            signature: signature.to_der().as_bytes().to_vec(),
+        }
+    }
+
+    fn channel(name: &[u8]) -> IPNSAddress {
+        IPNSAddress::try_from(Cid::new_v1(0x72, Code::Sha2_256.digest(name))).unwrap()
+    }
+
+    #[test]
+    fn count_vouchers() {
+        let recovery = Cid::new_v1(0x71, Code::Sha2_256.digest(b"recovery"));
+        let other = Cid::new_v1(0x71, Code::Sha2_256.digest(b"other"));
+
+        let alice = SigningKey::from_slice(&[1u8; 32]).unwrap();
+        let bob = SigningKey::from_slice(&[2u8; 32]).unwrap();
+        let eve = SigningKey::from_slice(&[3u8; 32]).unwrap();
+
+        let addresses = HashMap::from([
+            (channel(b"alice"), vouch(&alice, recovery).get_address()),
+            (
+                channel(b"bob"),
+                vouch(&bob, recovery).get_address().to_uppercase(),
+            ),
+        ]);
+
+        let mut tampered = vouch(&bob, recovery);
+        tampered.signature[8] ^= 1;
+
+        let signed_links = [
+            vouch(&alice, recovery),
+            vouch(&alice, recovery),
+            vouch(&eve, recovery),
+            vouch(&bob, other),
+            tampered,
+        ];
+
+        assert_eq!(
+            vouchers(recovery, &signed_links, &addresses),
+            HashSet::from([channel(b"alice")])
+        );
+
+        let signed_links = [vouch(&alice, recovery), vouch(&bob, recovery)];
+
+        assert_eq!(vouchers(recovery, &signed_links, &addresses).len(), 2);
+    }
+
+    #[test]
+    fn thresholds() {
+        assert!(!valid_threshold(0, 3));
+        assert!(valid_threshold(1, 3));
+        assert!(valid_threshold(3, 3));
+        assert!(!valid_threshold(4, 3));
+        assert!(!valid_threshold(1, 0));
+    }
+
+    #[test]
+    fn recovered_addresses() {
+        let identity = Cid::new_v1(0x71, Code::Sha2_256.digest(b"identity"));
+
+        let mut recoveries = Recoveries::default();
+
+        assert!(!recoveries.is_recovered(identity, "0xAbC"));
+
+        recoveries.insert(&RecoveryNode {
+            identity: identity.into(),
+            eth_addr: String::from("0xabc"),
+            timestamp: 0,
+        });
+
+        assert!(recoveries.is_recovered(identity, "0xAbC"));
+        assert!(!recoveries.is_recovered(identity, "0xdef"));
+    }
+}
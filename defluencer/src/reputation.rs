@@ -0,0 +1,236 @@
+use std::collections::{HashMap, VecDeque};
+
+use linked_data::types::{Address, PeerId};
+
+use serde::{Deserialize, Serialize};
+
+/// Rate is measured over this many seconds.
+const RATE_WINDOW: i64 = 60;
+
+/// Weight of a rejected message against an accepted one.
+const REJECTED_WEIGHT: f64 = 3.0;
+
+/// Weight of a moderator ban against an accepted message.
+const BANNED_WEIGHT: f64 = 50.0;
+
+/// Peers sending unverifiable messages are kept up to this many.
+const MAX_UNVERIFIED_PEERS: usize = 1_000;
+
+/// Chat history of one sender address.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SenderStats {
+    /// Messages shown in chat.
+    pub messages: u64,
+
+    /// Messages dropped for breaking the chat rules.
+    pub rejected: u64,
+
+    /// Times banned by a moderator.
+    pub banned: u64,
+
+    /// Timestamp in Unix time.
+    pub first_seen: i64,
+
+    /// Timestamp in Unix time.
+    pub last_seen: i64,
+
+    /// Recent message timestamps, not persisted.
+    #[serde(skip)]
+    recent: VecDeque<i64>,
+}
+
+impl SenderStats {
+    /// Number of messages sent in the last minute.
+    pub fn rate(&self, now: i64) -> usize {
+        self.recent
+            .iter()
+            .filter(|timestamp| now - **timestamp < RATE_WINDOW)
+            .count()
+    }
+
+    /// Trust score between 0 and 1, unknown senders start at 1.
+    ///
+    /// Rejected messages lower the score, moderator bans lower it much more.
+    pub fn trust(&self) -> f64 {
+        let good = self.messages as f64 + 1.0;
+        let bad = self.rejected as f64 * REJECTED_WEIGHT + self.banned as f64 * BANNED_WEIGHT;
+
+        good / (good + bad)
+    }
+
+    fn seen(&mut self, timestamp: i64) {
+        if self.first_seen == 0 {
+            self.first_seen = timestamp;
+        }
+
+        self.last_seen = self.last_seen.max(timestamp);
+
+        while let Some(oldest) = self.recent.front() {
+            if timestamp - oldest < RATE_WINDOW {
+                break;
+            }
+
+            self.recent.pop_front();
+        }
+
+        self.recent.push_back(timestamp);
+    }
+}
+
+/// Chat statistics per sender address and per peer, kept across streams.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(from = "Entries", into = "Entries")]
+pub struct Reputation {
+    senders: HashMap<Address, SenderStats>,
+
+    /// Messages from peers that could not be verified.
+    unverified: HashMap<PeerId, u64>,
+}
+
+impl Reputation {
+    pub fn sender(&self, address: &Address) -> Option<&SenderStats> {
+        self.senders.get(address)
+    }
+
+    pub fn senders(&self) -> impl Iterator<Item = (&Address, &SenderStats)> {
+        self.senders.iter()
+    }
+
+    /// Trust score of this address, see `SenderStats::trust`.
+    pub fn trust(&self, address: &Address) -> f64 {
+        match self.senders.get(address) {
+            Some(stats) => stats.trust(),
+            None => 1.0,
+        }
+    }
+
+    /// Number of unverifiable messages sent by this peer.
+    pub fn unverified(&self, peer: &PeerId) -> u64 {
+        self.unverified.get(peer).copied().unwrap_or_default()
+    }
+
+    pub fn record_message(&mut self, address: Address, timestamp: i64) {
+        let stats = self.senders.entry(address).or_default();
+
+        stats.seen(timestamp);
+        stats.messages += 1;
+    }
+
+    pub fn record_rejection(&mut self, address: Address, timestamp: i64) {
+        let stats = self.senders.entry(address).or_default();
+
+        stats.seen(timestamp);
+        stats.rejected += 1;
+    }
+
+    pub fn record_ban(&mut self, address: Address) {
+        self.senders.entry(address).or_default().banned += 1;
+    }
+
+    /// Once full, the peer with the fewest messages is forgotten for a new one.
+    pub fn record_unverified(&mut self, peer: PeerId) {
+        if !self.unverified.contains_key(&peer) && self.unverified.len() >= MAX_UNVERIFIED_PEERS {
+            let quietest = self
+                .unverified
+                .iter()
+                .min_by_key(|(_, count)| **count)
+                .map(|(peer, _)| *peer);
+
+            if let Some(quietest) = quietest {
+                self.unverified.remove(&quietest);
+            }
+        }
+
+        *self.unverified.entry(peer).or_default() += 1;
+    }
+}
+
+/// Serialized form, addresses cannot be map keys.
+#[derive(Serialize, Deserialize)]
+struct Entries {
+    senders: Vec<(Address, SenderStats)>,
+    unverified: Vec<(PeerId, u64)>,
+}
+
+impl From<Entries> for Reputation {
+    fn from(entries: Entries) -> Self {
+        Self {
+            senders: entries.senders.into_iter().collect(),
+            unverified: entries.unverified.into_iter().collect(),
+        }
+    }
+}
+
+impl From<Reputation> for Entries {
+    fn from(reputation: Reputation) -> Self {
+        Self {
+            senders: reputation.senders.into_iter().collect(),
+            unverified: reputation.unverified.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cid::Cid;
+
+    use multihash::{Code, MultihashDigest};
+
+    #[test]
+    fn trust_score() {
+        let user = [1u8; 20];
+        let spammer = [2u8; 20];
+
+        let mut reputation = Reputation::default();
+
+        assert_eq!(reputation.trust(&user), 1.0);
+
+        for timestamp in 0..10 {
+            reputation.record_message(user, timestamp * 10);
+            reputation.record_message(spammer, timestamp);
+        }
+
+        reputation.record_rejection(spammer, 10);
+        reputation.record_ban(spammer);
+
+        assert_eq!(reputation.trust(&user), 1.0);
+        assert!(reputation.trust(&spammer) < 0.2);
+
+        let stats = reputation.sender(&user).unwrap();
+
+        assert_eq!(stats.rate(90), 6);
+        assert_eq!(stats.rate(200), 0);
+        assert_eq!((stats.first_seen, stats.last_seen), (0, 90));
+
+        let json = serde_json::to_vec(&reputation).unwrap();
+        let restored: Reputation = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(restored.trust(&spammer), reputation.trust(&spammer));
+        assert_eq!(restored.sender(&user).unwrap().rate(90), 0);
+    }
+
+    #[test]
+    fn unverified_cap() {
+        let peer = |i: usize| {
+            PeerId::try_from(Cid::new_v1(0x72, Code::Sha2_256.digest(&i.to_be_bytes()))).unwrap()
+        };
+
+        let mut reputation = Reputation::default();
+
+        reputation.record_unverified(peer(0));
+        reputation.record_unverified(peer(0));
+
+        for i in 1..MAX_UNVERIFIED_PEERS * 2 {
+            reputation.record_unverified(peer(i));
+        }
+
+        assert_eq!(reputation.unverified.len(), MAX_UNVERIFIED_PEERS);
+        assert_eq!(reputation.unverified(&peer(0)), 2);
+        assert_eq!(
+            reputation.unverified(&peer(MAX_UNVERIFIED_PEERS * 2 - 1)),
+            1
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::{future::Future, time::Duration};
+
+use cid::Cid;
+
+use futures::{
+    future::{self, Either},
+    pin_mut,
+};
+
+use ipfs_api::{routing::DelegatedRouting, IpfsService};
+
+use linked_data::types::IPNSAddress;
+
+use crate::{errors::Error, gossip::GossipCache};
+
+/// Where to resolve IPNS names when the local node cannot.
+#[derive(Clone)]
+pub enum Endpoint {
+    /// Another Kubo RPC API.
+    Kubo(IpfsService),
+
+    /// Delegated routing HTTP API, records are verified locally.
+    Delegated(DelegatedRouting),
+}
+
+/// IPNS resolution with retries and fallback endpoints.
+///
+/// The local node then each endpoint are tried in order, every attempt is bounded by the timeout.
+/// Verified gossip is only used when they all fail, it may lag behind the routing system.
+#[derive(Clone)]
+pub struct NameResolver {
+    ipfs: IpfsService,
+
+    fallbacks: Vec<Endpoint>,
+
+    gossip: Option<GossipCache>,
+
+    timeout: Duration,
+
+    /// Number of passes through all the endpoints.
+    attempts: usize,
+}
+
+impl NameResolver {
+    pub fn new(ipfs: IpfsService, timeout: Duration, attempts: usize) -> Self {
+        Self {
+            ipfs,
+            fallbacks: Vec::new(),
+            gossip: None,
+            timeout,
+            attempts: attempts.max(1),
+        }
+    }
+
+    /// Add an endpoint to try after the ones already added.
+    pub fn with_fallback(mut self, endpoint: Endpoint) -> Self {
+        self.fallbacks.push(endpoint);
+        self
+    }
+
+    /// Use roots gossiped by friends when no endpoint answers,
+    /// see [`crate::Defluencer::subscribe_gossip`].
+    ///
+    /// Gossiped records are only trusted after their signature is verified.
+    pub fn with_gossip(mut self, cache: GossipCache) -> Self {
+        self.gossip = Some(cache);
+        self
+    }
+
+    /// Returns the CID this address points to, or the last error if all endpoints failed.
+    pub async fn resolve(&self, addr: IPNSAddress) -> Result<Cid, Error> {
+        let mut error = None;
+
+        for _ in 0..self.attempts {
+            let resolve = self.ipfs.name_resolve_timeout(addr, self.timeout);
+
+            match bounded(resolve, self.timeout, "name/resolve").await {
+                Ok(cid) => return Ok(cid),
+                Err(e) => error = Some(e),
+            }
+
+            for endpoint in self.fallbacks.iter() {
+                match self.resolve_with(endpoint, addr).await {
+                    Ok(cid) => return Ok(cid),
+                    Err(e) => error = Some(e),
+                }
+            }
+        }
+
+        if let Some((cid, _)) = self.gossip.as_ref().and_then(|cache| cache.latest(addr)) {
+            return Ok(cid);
+        }
+
+        Err(error.unwrap_or(Error::NotFound))
+    }
+
+    async fn resolve_with(&self, endpoint: &Endpoint, addr: IPNSAddress) -> Result<Cid, Error> {
+        match endpoint {
+            Endpoint::Kubo(ipfs) => {
+                let resolve = ipfs.name_resolve_timeout(addr, self.timeout);
+
+                bounded(resolve, self.timeout, "name/resolve").await
+            }
+            Endpoint::Delegated(routing) => {
+                let resolve = routing.name_resolve(addr, Some(self.timeout));
+
+                bounded(resolve, self.timeout, "routing/v1/ipns").await
+            }
+        }
+    }
+}
+
+/// Bound an attempt on the client side.
+///
+/// Backends and browsers ignore request timeouts and the DHT timeout only applies to the node.
+async fn bounded<F>(resolve: F, timeout: Duration, endpoint: &str) -> Result<Cid, Error>
+where
+    F: Future<Output = Result<Cid, ipfs_api::errors::Error>>,
+{
+    pin_mut!(resolve);
+
+    match future::select(resolve, futures_timer::Delay::new(timeout)).await {
+        Either::Left((result, _)) => Ok(result?),
+        Either::Right(_) => Err(ipfs_api::errors::Error::Timeout {
+            endpoint: endpoint.to_owned(),
+            attempts: 1,
+        }
+        .into()),
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::{DateTime, Utc};
+
+use crate::{errors::Error, indexing::datetime};
+
+/// Default tolerance for clocks running ahead, in seconds.
+pub const DEFAULT_MAX_SKEW: i64 = 300;
+
+/// Unix time before which timestamps are suspicious, 2000-01-01.
+pub const DEFAULT_ANCIENT: i64 = 946_684_800;
+
+/// What to do with timestamps too far in the future.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FutureTimestamps {
+    #[default]
+    Reject,
+
+    /// Use the current time instead.
+    Clamp,
+}
+
+/// Rules applied to the user timestamps of content added to a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampPolicy {
+    /// Seconds a timestamp can be ahead of the local clock.
+    pub max_skew: i64,
+
+    pub future: FutureTimestamps,
+
+    /// Timestamps before this Unix time are flagged as ancient.
+    pub ancient: i64,
+}
+
+impl Default for TimestampPolicy {
+    fn default() -> Self {
+        Self {
+            max_skew: DEFAULT_MAX_SKEW,
+            future: FutureTimestamps::default(),
+            ancient: DEFAULT_ANCIENT,
+        }
+    }
+}
+
+/// A timestamp accepted by a policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckedTimestamp {
+    /// Date used for indexing.
+    pub date_time: DateTime<Utc>,
+
+    /// The timestamp was in the future and replaced by the current time.
+    pub clamped: bool,
+
+    /// The timestamp is older than the policy allows without warning.
+    pub ancient: bool,
+}
+
+impl TimestampPolicy {
+    /// Check a user timestamp against the local clock, both in Unix time.
+    pub fn check(&self, timestamp: i64, now: i64) -> Result<CheckedTimestamp, Error> {
+        let mut clamped = false;
+
+        let timestamp = if timestamp > now.saturating_add(self.max_skew) {
+            match self.future {
+                FutureTimestamps::Reject => return Err(Error::FutureTimestamp),
+                FutureTimestamps::Clamp => {
+                    clamped = true;
+                    now
+                }
+            }
+        } else {
+            timestamp
+        };
+
+        let date_time = datetime::date_time(timestamp)?;
+
+        Ok(CheckedTimestamp {
+            date_time,
+            clamped,
+            ancient: timestamp < self.ancient,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamp_policy() {
+        let now = 1_700_000_000;
+
+        let mut policy = TimestampPolicy::default();
+
+        let checked = policy.check(now + 60, now).unwrap();
+        assert!(!checked.clamped && !checked.ancient);
+        assert_eq!(checked.date_time.timestamp(), now + 60);
+
+        assert!(matches!(
+            policy.check(now + 3600, now),
+            Err(Error::FutureTimestamp)
+        ));
+
+        assert!(policy.check(0, now).unwrap().ancient);
+        assert!(matches!(policy.check(i64::MIN, now), Err(Error::Timestamp)));
+
+        policy.future = FutureTimestamps::Clamp;
+
+        let checked = policy.check(i64::MAX, now).unwrap();
+        assert!(checked.clamped);
+        assert_eq!(checked.date_time.timestamp(), now);
+    }
+}
@@ -1,8 +1,12 @@
 use std::borrow::Cow;
 
 use crate::{
+    canonical,
+    channel::{succession::succession_topic, threshold::approval_topic},
     crypto::{signed_link::SignedLink, signers::Signer},
     errors::Error,
+    limits::SizeLimits,
+    recovery::{recovery_topic, valid_threshold},
     utils::{add_image, add_markdown},
 };
 
@@ -13,12 +17,15 @@ use cid::Cid;
 use ipfs_api::{responses::Codec, IpfsService};
 
 use linked_data::{
-    identity::Identity,
+    channel::{registry::Listing, succession::SuccessionClaim},
+    identity::{Claim, Identity, Platform, ProfileField, ProfileLink, Recovery, RecoveryNode},
     media::{
         blog::BlogPost,
-        chat::ChatInfo,
-        comments::Comment,
-        video::{Day, Hour, Minute, Video},
+        chat::{ChatInfo, ChatSettings},
+        comments::{Comment, Upvote},
+        event::{Event, Rsvp},
+        video::{Chapter, Day, Hour, Minute, Timecode, Video},
+        Media,
     },
     types::{IPLDLink, IPNSAddress},
 };
@@ -36,6 +43,7 @@ where
     ipfs: IpfsService,
     identity: IPLDLink,
     signer: T,
+    limits: SizeLimits,
 }
 
 impl<T> PartialEq for User<T>
@@ -56,9 +64,16 @@ where
             ipfs,
             signer,
             identity: identity.into(),
+            limits: SizeLimits::default(),
         }
     }
 
+    /// Replace the default content size limits.
+    pub fn with_limits(mut self, limits: SizeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     pub fn get_identity(&self) -> Cid {
         self.identity.link
     }
@@ -91,105 +106,19 @@ where
         Ok(user)
     }
 
-    /// Update your identity data.
-    #[cfg(not(target_arch = "wasm32"))]
+    /// Update your identity data, see [IdentityPatch].
     pub async fn update_identity(
-        mut self,
-        name: Option<String>,
-        bio: Option<String>,
-        banner: Option<std::path::PathBuf>,
-        avatar: Option<std::path::PathBuf>,
-        ipns_addr: Option<IPNSAddress>,
-        btc_addr: Option<String>,
-        eth_addr: Option<String>,
+        &mut self,
+        patch: IdentityPatch,
     ) -> Result<(Cid, Identity), Error> {
         let mut identity = self
             .ipfs
             .dag_get::<&str, Identity>(self.identity.link, None, Codec::default())
             .await?;
 
-        if let Some(name) = name {
-            identity.name = name;
-        }
-
-        if let Some(bio) = bio {
-            identity.bio = Some(bio);
-        }
+        patch.apply(&mut identity);
 
-        if let Some(banner) = banner {
-            identity.banner = Some(add_image(&self.ipfs, banner).await?.into());
-        }
-
-        if let Some(avatar) = avatar {
-            identity.avatar = Some(add_image(&self.ipfs, avatar).await?.into());
-        }
-
-        if let Some(ipns) = ipns_addr {
-            identity.ipns_addr = Some(ipns);
-        }
-
-        if let Some(btc_addr) = btc_addr {
-            identity.btc_addr = Some(btc_addr);
-        }
-
-        if let Some(eth_addr) = eth_addr {
-            identity.eth_addr = Some(eth_addr);
-        }
-
-        let cid = self
-            .ipfs
-            .dag_put(&identity, Codec::default(), Codec::default())
-            .await?;
-
-        self.identity = cid.into();
-
-        Ok((cid, identity))
-    }
-
-    /// Update your identity data.
-    #[cfg(target_arch = "wasm32")]
-    pub async fn update_identity(
-        &mut self,
-        name: Option<String>,
-        bio: Option<String>,
-        banner: Option<web_sys::File>,
-        avatar: Option<web_sys::File>,
-        ipns_addr: Option<IPNSAddress>,
-        btc_addr: Option<String>,
-        eth_addr: Option<String>,
-    ) -> Result<(Cid, Identity), Error> {
-        let mut identity = self
-            .ipfs
-            .dag_get::<&str, Identity>(self.identity.link, None)
-            .await?;
-
-        if let Some(name) = name {
-            identity.name = name;
-        }
-
-        if let Some(bio) = bio {
-            identity.bio = Some(bio);
-        }
-
-        if let Some(banner) = banner {
-            identity.banner = Some(add_image(&self.ipfs, banner).await?.into());
-        }
-
-        if let Some(avatar) = avatar {
-            identity.avatar = Some(add_image(&self.ipfs, avatar).await?.into());
-        }
-
-        if let Some(ipns) = ipns_addr {
-            identity.ipns_addr = Some(ipns);
-        }
-
-        if let Some(btc_addr) = btc_addr {
-            identity.btc_addr = Some(btc_addr);
-        }
-
-        if let Some(eth_addr) = eth_addr {
-            identity.eth_addr = Some(eth_addr);
-        }
+        validate_identity(&identity)?;
 
         let cid = self
             .ipfs
@@ -208,11 +137,14 @@ where
         origin: Option<Cid>,
         pin: bool,
     ) -> Result<(Cid, Comment), Error> {
+        self.limits.check_text(&text)?;
+
         let micro_post = Comment {
             identity: self.identity,
             text,
             user_timestamp: Utc::now().timestamp(),
             origin,
+            reply_to: None,
         };
 
         let cid = self.add_content(&micro_post, pin).await?;
@@ -222,14 +154,20 @@ where
 
     /// Create a new blog post.
     #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_blog_post(
         &self,
         title: String,
         image: Option<PathBuf>,
         markdown: PathBuf,
         word_count: Option<u64>,
+        summary: Option<String>,
+        tags: Vec<String>,
         pin: bool,
     ) -> Result<(Cid, BlogPost), Error> {
+        let size = tokio::fs::metadata(&markdown).await?.len();
+        self.limits.check_markdown(size)?;
+
         let (image, content) = match image {
             Some(image) => {
                 let (image, markdown) = tokio::try_join!(
@@ -253,6 +191,9 @@ where
             image,
             title,
             word_count,
+            summary,
+            variants: Vec::new(),
+            tags,
         };
 
         let cid = self.add_content(&post, pin).await?;
@@ -262,14 +203,19 @@ where
 
     /// Create a new blog post.
     #[cfg(target_arch = "wasm32")]
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_blog_post(
         &self,
         title: String,
         image: Option<web_sys::File>,
         markdown: web_sys::File,
         word_count: Option<u64>,
+        summary: Option<String>,
+        tags: Vec<String>,
         pin: bool,
     ) -> Result<(Cid, BlogPost), Error> {
+        self.limits.check_markdown(markdown.size() as u64)?;
+
         let (image, content) = match image {
             Some(image) => {
                 let (image, markdown) = futures::try_join!(
@@ -293,6 +239,9 @@ where
             image,
             title,
             word_count,
+            summary,
+            variants: Vec::new(),
+            tags,
         };
 
         let cid = self.add_content(&post, pin).await?;
@@ -307,19 +256,22 @@ where
         title: String,
         video: Cid,
         thumbnail: Option<PathBuf>,
+        summary: Option<String>,
+        tags: Vec<String>,
+        live: bool,
         pin: bool,
     ) -> Result<(Cid, Video), Error> {
-        let (image, duration) = match thumbnail {
+        let (image, (duration, size, segment_count)) = match thumbnail {
             Some(img) => {
-                let (img, dur) =
-                    tokio::try_join!(add_image(&self.ipfs, img), self.video_duration(video))?;
+                let (img, stats) =
+                    tokio::try_join!(add_image(&self.ipfs, img), self.video_stats(video))?;
 
-                (Some(img.into()), Some(dur))
+                (Some(img.into()), stats)
             }
             None => {
-                let duration = self.video_duration(video).await?;
+                let stats = self.video_stats(video).await?;
 
-                (None, Some(duration))
+                (None, stats)
             }
         };
 
@@ -328,8 +280,17 @@ where
             user_timestamp: Utc::now().timestamp(),
             image,
             title,
-            duration,
+            duration: Some(duration),
+            size,
+            segment_count,
             video: video.into(),
+            summary,
+            variants: Vec::new(),
+            chapters: Vec::new(),
+            thumbnails: Vec::new(),
+            format: None,
+            live,
+            tags,
         };
 
         let cid = self.add_content(&video_post, pin).await?;
@@ -344,19 +305,22 @@ where
         title: String,
         video: Cid,
         thumbnail: Option<web_sys::File>,
+        summary: Option<String>,
+        tags: Vec<String>,
+        live: bool,
         pin: bool,
     ) -> Result<(Cid, Video), Error> {
-        let (image, duration) = match thumbnail {
+        let (image, (duration, size, segment_count)) = match thumbnail {
             Some(img) => {
-                let (img, dur) =
-                    futures::try_join!(add_image(&self.ipfs, img), self.video_duration(video))?;
+                let (img, stats) =
+                    futures::try_join!(add_image(&self.ipfs, img), self.video_stats(video))?;
 
-                (Some(img.into()), Some(dur))
+                (Some(img.into()), stats)
             }
             None => {
-                let duration = self.video_duration(video).await?;
+                let stats = self.video_stats(video).await?;
 
-                (None, Some(duration))
+                (None, stats)
             }
         };
 
@@ -365,8 +329,17 @@ where
             user_timestamp: Utc::now().timestamp(),
             image,
             title,
-            duration,
+            duration: Some(duration),
+            size,
+            segment_count,
             video: video.into(),
+            summary,
+            variants: Vec::new(),
+            chapters: Vec::new(),
+            thumbnails: Vec::new(),
+            format: None,
+            live,
+            tags,
         };
 
         let cid = self.add_content(&video_post, pin).await?;
@@ -374,6 +347,222 @@ where
         Ok((cid, video_post))
     }
 
+    /// Replace the summary of a blog post or video.
+    ///
+    /// Summaries are generated externally, this only link them to the content.
+    /// Returns the CID of the new signed content.
+    pub async fn update_summary(
+        &self,
+        content_cid: Cid,
+        summary: Option<String>,
+        pin: bool,
+    ) -> Result<Cid, Error> {
+        let media: Media = self
+            .ipfs
+            .dag_get(content_cid, Some("/link"), Codec::default())
+            .await?;
+
+        match media {
+            Media::Blog(mut post) => {
+                post.summary = summary;
+                self.add_content(&post, pin).await
+            }
+            Media::Video(mut video) => {
+                video.summary = summary;
+                self.add_content(&video, pin).await
+            }
+            Media::Event(mut event) => {
+                event.summary = summary;
+                self.add_content(&event, pin).await
+            }
+            Media::Audio(mut audio) => {
+                audio.summary = summary;
+                self.add_content(&audio, pin).await
+            }
+            Media::Comment(_) => Err(Error::Unsupported),
+        }
+    }
+
+    /// Edit a blog post.
+    ///
+    /// Replacing the markdown without a new summary removes the old one.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn edit_blog_post(
+        &self,
+        post_cid: Cid,
+        title: Option<String>,
+        image: Option<PathBuf>,
+        markdown: Option<PathBuf>,
+        word_count: Option<u64>,
+        summary: Option<String>,
+        pin: bool,
+    ) -> Result<(Cid, BlogPost), Error> {
+        let mut post: BlogPost = self
+            .ipfs
+            .dag_get(post_cid, Some("/link"), Codec::default())
+            .await?;
+
+        if let Some(title) = title {
+            post.title = title;
+        }
+
+        if let Some(image) = image {
+            post.image = Some(add_image(&self.ipfs, image).await?.into());
+        }
+
+        let content_changed = match markdown {
+            Some(markdown) => {
+                let size = tokio::fs::metadata(&markdown).await?.len();
+                self.limits.check_markdown(size)?;
+
+                post.content = add_markdown(&self.ipfs, markdown).await?.into();
+                true
+            }
+            None => false,
+        };
+
+        if let Some(word_count) = word_count {
+            post.word_count = Some(word_count);
+        }
+
+        sync_summary(&mut post.summary, summary, content_changed);
+
+        let cid = self.add_content(&post, pin).await?;
+
+        Ok((cid, post))
+    }
+
+    /// Edit a blog post.
+    ///
+    /// Replacing the markdown without a new summary removes the old one.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn edit_blog_post(
+        &self,
+        post_cid: Cid,
+        title: Option<String>,
+        image: Option<web_sys::File>,
+        markdown: Option<web_sys::File>,
+        word_count: Option<u64>,
+        summary: Option<String>,
+        pin: bool,
+    ) -> Result<(Cid, BlogPost), Error> {
+        let mut post: BlogPost = self
+            .ipfs
+            .dag_get(post_cid, Some("/link"), Codec::default())
+            .await?;
+
+        if let Some(title) = title {
+            post.title = title;
+        }
+
+        if let Some(image) = image {
+            post.image = Some(add_image(&self.ipfs, image).await?.into());
+        }
+
+        let content_changed = match markdown {
+            Some(markdown) => {
+                self.limits.check_markdown(markdown.size() as u64)?;
+
+                post.content = add_markdown(&self.ipfs, markdown).await?.into();
+                true
+            }
+            None => false,
+        };
+
+        if let Some(word_count) = word_count {
+            post.word_count = Some(word_count);
+        }
+
+        sync_summary(&mut post.summary, summary, content_changed);
+
+        let cid = self.add_content(&post, pin).await?;
+
+        Ok((cid, post))
+    }
+
+    /// Edit a video post.
+    ///
+    /// Replacing the video without a new summary removes the old one.
+    pub async fn edit_video_post(
+        &self,
+        video_cid: Cid,
+        title: Option<String>,
+        video: Option<Cid>,
+        summary: Option<String>,
+        pin: bool,
+    ) -> Result<(Cid, Video), Error> {
+        let mut video_post: Video = self
+            .ipfs
+            .dag_get(video_cid, Some("/link"), Codec::default())
+            .await?;
+
+        if let Some(title) = title {
+            video_post.title = title;
+        }
+
+        let content_changed = match video {
+            Some(video) => {
+                video_post.duration = Some(self.video_duration(video).await?);
+                video_post.video = video.into();
+                true
+            }
+            None => false,
+        };
+
+        sync_summary(&mut video_post.summary, summary, content_changed);
+
+        let cid = self.add_content(&video_post, pin).await?;
+
+        Ok((cid, video_post))
+    }
+
+    /// Replace the raw archive of a streamed video with its post-processed version.
+    ///
+    /// Title and summary are kept, it is the same content.
+    /// The first thumbnail is used as image if the video had none.
+    pub async fn replace_video_archive(
+        &self,
+        video_cid: Cid,
+        processed: Cid,
+        chapters: Vec<Chapter>,
+        thumbnails: Vec<IPLDLink>,
+        pin: bool,
+    ) -> Result<(Cid, Video), Error> {
+        let mut video_post: Video = self
+            .ipfs
+            .dag_get(video_cid, Some("/link"), Codec::default())
+            .await?;
+
+        let (duration, size, segment_count) = self.video_stats(processed).await?;
+
+        video_post.video = processed.into();
+        video_post.duration = Some(duration);
+        video_post.size = size;
+        video_post.segment_count = segment_count;
+
+        if video_post.image.is_none() {
+            video_post.image = thumbnails.first().copied();
+        }
+
+        video_post.chapters = chapters;
+        video_post.thumbnails = thumbnails;
+
+        let cid = self.add_content(&video_post, pin).await?;
+
+        Ok((cid, video_post))
+    }
+
+    /// Sign media created elsewhere, like content imported from other platforms.
+    ///
+    /// The media must be attributed to this user.
+    pub async fn add_imported(&self, media: &Media, pin: bool) -> Result<Cid, Error> {
+        if media.identity() != self.identity {
+            return Err(Error::Identity);
+        }
+
+        self.add_content(media, pin).await
+    }
+
     /// Create a new comment on the specified media.
     pub async fn create_comment(
         &self,
@@ -381,10 +570,13 @@ where
         text: String,
         pin: bool,
     ) -> Result<(Cid, Comment), Error> {
+        self.limits.check_text(&text)?;
+
         let comment = Comment {
             identity: self.identity,
             user_timestamp: Utc::now().timestamp(),
             origin: Some(origin),
+            reply_to: None,
             text,
         };
 
@@ -393,11 +585,254 @@ where
         Ok((cid, comment))
     }
 
+    /// Create a new reply to the specified comment, on the same media.
+    pub async fn create_reply(
+        &self,
+        comment: Cid,
+        text: String,
+        pin: bool,
+    ) -> Result<(Cid, Comment), Error> {
+        self.limits.check_text(&text)?;
+
+        let parent: Comment = self
+            .ipfs
+            .dag_get(comment, Some("/link"), Codec::default())
+            .await?;
+
+        let reply = Comment {
+            identity: self.identity,
+            user_timestamp: Utc::now().timestamp(),
+            origin: parent.origin,
+            reply_to: Some(comment.into()),
+            text,
+        };
+
+        let cid = self.add_content(&reply, pin).await?;
+
+        Ok((cid, reply))
+    }
+
+    /// Create a new upvote on the specified comment.
+    pub async fn create_upvote(&self, comment: Cid, pin: bool) -> Result<(Cid, Upvote), Error> {
+        let upvote = Upvote {
+            identity: self.identity,
+            user_timestamp: Utc::now().timestamp(),
+            comment,
+        };
+
+        let cid = self.add_content(&upvote, pin).await?;
+
+        Ok((cid, upvote))
+    }
+
+    /// Create a new event, located in person, on a live stream or both.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_event(
+        &self,
+        title: String,
+        start: i64,
+        duration: Option<u64>,
+        location: Option<String>,
+        stream: Option<IPNSAddress>,
+        summary: Option<String>,
+        tags: Vec<String>,
+        pin: bool,
+    ) -> Result<(Cid, Event), Error> {
+        let event = Event {
+            identity: self.identity,
+            user_timestamp: Utc::now().timestamp(),
+            title,
+            start,
+            duration,
+            location,
+            stream,
+            image: None,
+            summary,
+            tags,
+        };
+
+        let cid = self.add_content(&event, pin).await?;
+
+        Ok((cid, event))
+    }
+
+    /// Answer an event invitation, a later answer replaces the previous one.
+    pub async fn create_rsvp(
+        &self,
+        event: Cid,
+        attending: bool,
+        pin: bool,
+    ) -> Result<(Cid, Rsvp), Error> {
+        let rsvp = Rsvp {
+            identity: self.identity,
+            user_timestamp: Utc::now().timestamp(),
+            event,
+            attending,
+        };
+
+        let cid = self.add_content(&rsvp, pin).await?;
+
+        Ok((cid, rsvp))
+    }
+
+    /// Create a new listing request for a channel registry.
+    pub async fn create_listing(
+        &self,
+        channel: IPNSAddress,
+        topics: Vec<String>,
+        pin: bool,
+    ) -> Result<(Cid, Listing), Error> {
+        let listing = Listing {
+            identity: self.identity,
+            user_timestamp: Utc::now().timestamp(),
+            channel,
+            topics,
+        };
+
+        let cid = self.add_content(&listing, pin).await?;
+
+        Ok((cid, listing))
+    }
+
+    /// Claim an account on another platform then add the claim to your identity.
+    ///
+    /// The proof must already be posted by the account and contain your signer address.
+    ///
+    /// Returns the new identity CID.
+    pub async fn add_claim(
+        &mut self,
+        platform: Platform,
+        account: String,
+        proof: String,
+    ) -> Result<(Cid, Identity), Error> {
+        let claim = Claim {
+            user_timestamp: Utc::now().timestamp(),
+            platform,
+            account,
+            proof,
+        };
+
+        let claim_cid = self.add_content(&claim, true).await?;
+
+        let mut identity = self
+            .ipfs
+            .dag_get::<&str, Identity>(self.identity.link, None, Codec::default())
+            .await?;
+
+        identity.claims.push(claim_cid.into());
+
+        let cid = self
+            .ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await?;
+
+        self.identity = cid.into();
+
+        Ok((cid, identity))
+    }
+
+    /// Remove a claim from your identity.
+    ///
+    /// Returns the new identity CID.
+    pub async fn remove_claim(&mut self, claim_cid: Cid) -> Result<(Cid, Identity), Error> {
+        let mut identity = self
+            .ipfs
+            .dag_get::<&str, Identity>(self.identity.link, None, Codec::default())
+            .await?;
+
+        let count = identity.claims.len();
+
+        identity.claims.retain(|ipld| ipld.link != claim_cid);
+
+        if identity.claims.len() == count {
+            return Err(Error::NotFound);
+        }
+
+        let cid = self
+            .ipfs
+            .dag_put(&identity, Codec::default(), Codec::default())
+            .await?;
+
+        self.identity = cid.into();
+
+        Ok((cid, identity))
+    }
+
+    /// Sign a co-owned channel update proposal then send the approval to the proposer.
+    ///
+    /// Returns the CID of the signed proposal.
+    pub async fn approve_channel_update(
+        &self,
+        channel: IPNSAddress,
+        proposal: Cid,
+    ) -> Result<Cid, Error> {
+        let cid = self.create_signed_link(proposal).await?;
+
+        self.ipfs
+            .pubsub_pub(approval_topic(channel), cid.to_bytes())
+            .await?;
+
+        Ok(cid)
+    }
+
+    /// Vouch for the recovery of an identity that trusts your channel.
+    ///
+    /// Only Ethereum signatures are accepted as vouches.
+    ///
+    /// Returns the CID of the signed recovery node.
+    pub async fn vouch_recovery(&self, recovery: Cid) -> Result<Cid, Error> {
+        let node = self
+            .ipfs
+            .dag_get::<&str, RecoveryNode>(recovery, None, Codec::default())
+            .await?;
+
+        let cid = self.create_signed_link(recovery).await?;
+
+        self.ipfs
+            .pubsub_pub(recovery_topic(node.identity.link), cid.to_bytes())
+            .await?;
+
+        Ok(cid)
+    }
+
+    /// Claim an inactive channel as one of its successors then announce it to followers.
+    ///
+    /// Followers verify the claim against the channel succession policy.
+    pub async fn claim_succession(
+        &self,
+        channel: IPNSAddress,
+        successor: IPNSAddress,
+    ) -> Result<Cid, Error> {
+        let metadata = self.ipfs.name_resolve(channel).await?;
+
+        let claim = SuccessionClaim {
+            channel,
+            metadata: metadata.into(),
+            successor,
+            timestamp: Utc::now().timestamp(),
+        };
+
+        let cid = self
+            .ipfs
+            .dag_put(&claim, Codec::default(), Codec::default())
+            .await?;
+
+        let cid = self.create_signed_link(cid).await?;
+
+        self.ipfs
+            .pubsub_pub(succession_topic(channel), cid.to_bytes())
+            .await?;
+
+        Ok(cid)
+    }
+
     /// Returns the CID of the signed block linking to the content
     async fn add_content<V>(&self, metadata: &V, pin: bool) -> Result<Cid, Error>
     where
         V: ?Sized + Serialize,
     {
+        self.limits.check_node(metadata)?;
+
         let content_cid = self
             .ipfs
             .dag_put(metadata, Codec::default(), Codec::default())
@@ -412,6 +847,23 @@ where
         Ok(signed_cid)
     }
 
+    /// Returns duration, size and segment count as recorded by the archiving daemon.
+    ///
+    /// Duration is estimated from the timecode structure for older videos.
+    async fn video_stats(&self, video: Cid) -> Result<(f64, Option<u64>, Option<u64>), Error> {
+        let timecode: Timecode = self
+            .ipfs
+            .dag_get(video, Option::<&str>::None, Codec::default())
+            .await?;
+
+        let duration = match timecode.duration {
+            Some(duration) => duration,
+            None => self.video_duration(video).await?,
+        };
+
+        Ok((duration, timecode.size, timecode.segment_count))
+    }
+
     async fn video_duration(&self, video: Cid) -> Result<f64, Error> {
         let days: Day = self
             .ipfs
@@ -452,26 +904,397 @@ where
             .dag_put(&chat_info, Codec::default(), Codec::default())
             .await?;
 
-        let cid = self.create_signed_link(cid).await?;
+        let cid = self.create_compact_signed_link(cid).await?;
+
+        Ok(cid)
+    }
+
+    /// Returns a signed link to chat settings, sent in chat messages to change the rules live.
+    ///
+    /// Settings are only applied when signed by a moderator.
+    pub async fn chat_settings(&self, settings: ChatSettings) -> Result<Cid, Error> {
+        let cid = self
+            .ipfs
+            .dag_put(&settings, Codec::default(), Codec::default())
+            .await?;
+
+        let cid = self.create_compact_signed_link(cid).await?;
 
         Ok(cid)
     }
 
     async fn create_signed_link(&self, cid: Cid) -> Result<Cid, Error> {
+        self.sign_link(cid, false).await
+    }
+
+    /// Signed links with 64 bytes signatures, for size sensitive uses like chat.
+    async fn create_compact_signed_link(&self, cid: Cid) -> Result<Cid, Error> {
+        self.sign_link(cid, true).await
+    }
+
+    async fn sign_link(&self, cid: Cid, compact: bool) -> Result<Cid, Error> {
         let (verif_key, signature, hash_algo) = self.signer.sign(cid.hash().digest()).await?;
 
+        let signature = match compact {
+            true => signature.to_bytes().to_vec(),
+            false => signature.to_der().as_bytes().to_vec(),
+        };
+
         let signed_link = SignedLink {
             link: cid.into(),
             public_key: verif_key.to_encoded_point(false).as_bytes().to_vec(),
             hash_algo,
-            signature: signature.to_der().as_bytes().to_vec(),
+            signature,
         };
 
+        // Signed links are hashed from canonical bytes, never re-encoded by the node.
+        let data = canonical::encode(&signed_link)?;
+
         let cid = self
             .ipfs
-            .dag_put(&signed_link, Codec::default(), Codec::default())
+            .block_put_encoded(data.into(), Codec::DagCbor)
             .await?;
 
         Ok(cid)
     }
 }
+
+/// A summary describe the content it was generated from, new content makes it stale.
+fn sync_summary(current: &mut Option<String>, new: Option<String>, content_changed: bool) {
+    match new {
+        Some(summary) => *current = Some(summary),
+        None if content_changed => *current = None,
+        None => {}
+    }
+}
+
+/// Change to an optional identity field.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Patch<T> {
+    /// Keep the current value.
+    #[default]
+    Keep,
+
+    /// Replace the current value.
+    Set(T),
+
+    /// Remove the current value.
+    Clear,
+}
+
+impl<T> Patch<T> {
+    pub fn apply(self, field: &mut Option<T>) {
+        match self {
+            Self::Keep => {}
+            Self::Set(value) => *field = Some(value),
+            Self::Clear => *field = None,
+        }
+    }
+}
+
+impl<T> From<Option<T>> for Patch<T> {
+    /// None keeps the current value.
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Self::Set(value),
+            None => Self::Keep,
+        }
+    }
+}
+
+/// Changes to an identity, fields left to default are kept.
+///
+/// Images must be added beforehand, see [add_image].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct IdentityPatch {
+    /// Names can be replaced but not cleared.
+    pub name: Option<String>,
+    pub bio: Patch<String>,
+    pub banner: Patch<IPLDLink>,
+    pub avatar: Patch<IPLDLink>,
+    pub ipns_addr: Patch<IPNSAddress>,
+    pub btc_addr: Patch<String>,
+    pub eth_addr: Patch<String>,
+    pub pronouns: Patch<String>,
+    pub location: Patch<String>,
+    pub recovery: Patch<Recovery>,
+
+    /// Replace all links, empty to clear.
+    pub links: Option<Vec<ProfileLink>>,
+
+    /// Replace the display order, empty to let frontends choose.
+    pub display: Option<Vec<ProfileField>>,
+}
+
+impl IdentityPatch {
+    pub fn apply(self, identity: &mut Identity) {
+        let Self {
+            name,
+            bio,
+            banner,
+            avatar,
+            ipns_addr,
+            btc_addr,
+            eth_addr,
+            pronouns,
+            location,
+            recovery,
+            links,
+            display,
+        } = self;
+
+        if let Some(name) = name {
+            identity.name = name;
+        }
+
+        bio.apply(&mut identity.bio);
+        banner.apply(&mut identity.banner);
+        avatar.apply(&mut identity.avatar);
+        ipns_addr.apply(&mut identity.ipns_addr);
+        btc_addr.apply(&mut identity.btc_addr);
+        eth_addr.apply(&mut identity.eth_addr);
+        pronouns.apply(&mut identity.pronouns);
+        location.apply(&mut identity.location);
+        recovery.apply(&mut identity.recovery);
+
+        if let Some(links) = links {
+            identity.links = links;
+        }
+
+        if let Some(display) = display {
+            identity.display = display;
+        }
+    }
+}
+
+pub const MAX_PRONOUNS_LENGTH: usize = 32;
+pub const MAX_LOCATION_LENGTH: usize = 64;
+pub const MAX_LINK_TITLE_LENGTH: usize = 64;
+pub const MAX_PROFILE_LINKS: usize = 10;
+
+/// Check the structured profile fields of an identity.
+pub fn validate_identity(identity: &Identity) -> Result<(), Error> {
+    let valid_text = |text: &Option<String>, max: usize| match text {
+        Some(text) => !text.trim().is_empty() && text.chars().count() <= max,
+        None => true,
+    };
+
+    if !valid_text(&identity.pronouns, MAX_PRONOUNS_LENGTH)
+        || !valid_text(&identity.location, MAX_LOCATION_LENGTH)
+    {
+        return Err(Error::Identity);
+    }
+
+    if identity.links.len() > MAX_PROFILE_LINKS || !identity.links.iter().all(valid_profile_link) {
+        return Err(Error::Identity);
+    }
+
+    if let Some(recovery) = identity.recovery.as_ref() {
+        if !valid_threshold(recovery.threshold, recovery.channels.len()) {
+            return Err(Error::Identity);
+        }
+    }
+
+    for (i, field) in identity.display.iter().enumerate() {
+        if identity.display[..i].contains(field) {
+            return Err(Error::Identity);
+        }
+    }
+
+    Ok(())
+}
+
+/// Links must have a title and a web or IPFS URL.
+pub fn valid_profile_link(link: &ProfileLink) -> bool {
+    let title = link.title.trim();
+
+    !title.is_empty()
+        && title.chars().count() <= MAX_LINK_TITLE_LENGTH
+        && ["https://", "http://", "ipfs://", "ipns://"]
+            .iter()
+            .any(|scheme| link.url.starts_with(scheme))
+}
+
+/// Fields in display order, all of them when no preference is set.
+pub fn display_order(identity: &Identity) -> Vec<ProfileField> {
+    if identity.display.is_empty() {
+        return vec![
+            ProfileField::Bio,
+            ProfileField::Pronouns,
+            ProfileField::Location,
+            ProfileField::Links,
+        ];
+    }
+
+    identity.display.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(title: &str, url: &str) -> ProfileLink {
+        ProfileLink {
+            title: title.to_owned(),
+            url: url.to_owned(),
+        }
+    }
+
+    #[test]
+    fn identity_patch() {
+        let mut identity = Identity {
+            name: String::from("Alice"),
+            bio: Some(String::from("Hello")),
+            btc_addr: Some(String::from("bc1q")),
+            links: vec![link("Blog", "https://example.com")],
+            ..Default::default()
+        };
+
+        IdentityPatch {
+            bio: Patch::Set(String::from("Bonjour")),
+            btc_addr: Patch::Clear,
+            location: Some(String::from("Montréal")).into(),
+            links: Some(Vec::new()),
+            ..Default::default()
+        }
+        .apply(&mut identity);
+
+        assert_eq!(identity.name, "Alice");
+        assert_eq!(identity.bio.as_deref(), Some("Bonjour"));
+        assert_eq!(identity.btc_addr, None);
+        assert_eq!(identity.location.as_deref(), Some("Montréal"));
+        assert!(identity.links.is_empty());
+
+        IdentityPatch::default().apply(&mut identity);
+
+        assert_eq!(identity.bio.as_deref(), Some("Bonjour"));
+    }
+
+    #[test]
+    fn structured_profile() {
+        let mut identity = Identity {
+            pronouns: Some(String::from("they/them")),
+            location: Some(String::from("Montréal")),
+            links: vec![link("Blog", "https://example.com")],
+            display: vec![ProfileField::Links, ProfileField::Bio],
+            ..Default::default()
+        };
+
+        assert!(validate_identity(&identity).is_ok());
+        assert_eq!(
+            display_order(&identity),
+            vec![ProfileField::Links, ProfileField::Bio]
+        );
+
+        identity.display.push(ProfileField::Links);
+        assert!(validate_identity(&identity).is_err());
+        identity.display.pop();
+
+        identity.links.push(link(" ", "https://example.com"));
+        assert!(validate_identity(&identity).is_err());
+        identity.links.pop();
+
+        identity.links.push(link("Script", "javascript:alert(1)"));
+        assert!(validate_identity(&identity).is_err());
+        identity.links.pop();
+
+        identity.pronouns = Some("x".repeat(MAX_PRONOUNS_LENGTH + 1));
+        assert!(validate_identity(&identity).is_err());
+        identity.pronouns = None;
+
+        let channel =
+            IPNSAddress::try_from("bafzbeiegbnjh5uopd5vc22tgkz6chf7a6ala3x5e47vnhv5sq5bzo46tri")
+                .unwrap();
+
+        IdentityPatch {
+            recovery: Patch::Set(Recovery {
+                threshold: 2,
+                channels: vec![channel],
+            }),
+            ..Default::default()
+        }
+        .apply(&mut identity);
+        assert!(validate_identity(&identity).is_err());
+
+        identity.recovery.as_mut().unwrap().threshold = 1;
+        assert!(validate_identity(&identity).is_ok());
+
+        assert_eq!(display_order(&Identity::default()).len(), 4);
+    }
+
+    /// Sign like the Ethereum Ledger app would.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[derive(Clone)]
+    struct KeySigner(k256::ecdsa::SigningKey);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[async_trait::async_trait(?Send)]
+    impl Signer for KeySigner {
+        async fn sign(
+            &self,
+            signing_input: &[u8],
+        ) -> Result<
+            (
+                k256::ecdsa::VerifyingKey,
+                k256::ecdsa::Signature,
+                crate::crypto::signed_link::HashAlgorithm,
+            ),
+            Error,
+        > {
+            use k256::ecdsa::signature::DigestSigner;
+            use sha3::{Digest, Keccak256};
+
+            let mut eth_message =
+                format!("\x19Ethereum Signed Message:\n{}", signing_input.len()).into_bytes();
+            eth_message.extend_from_slice(signing_input);
+
+            let signature = self.0.sign_digest(Keccak256::new_with_prefix(eth_message));
+
+            Ok((
+                *self.0.verifying_key(),
+                signature,
+                crate::crypto::signed_link::HashAlgorithm::EthereumLedgerApp,
+            ))
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn signed_link_bytes() {
+        use ipfs_api::mock::MockIpfs;
+        use libipld_core::ipld::Ipld;
+
+        let ipfs = MockIpfs::new().service();
+
+        let key = k256::ecdsa::SigningKey::from_slice(&[1; 32]).unwrap();
+        let user = User::new(ipfs.clone(), KeySigner(key), Cid::default());
+
+        let post = ipfs
+            .dag_put(&BlogPost::default(), Codec::default(), Codec::default())
+            .await
+            .unwrap();
+
+        let cid = user.create_signed_link(post).await.unwrap();
+
+        let signed_link: SignedLink = ipfs
+            .dag_get(cid, Option::<&str>::None, Codec::default())
+            .await
+            .unwrap();
+
+        assert_eq!(signed_link.link.link, post);
+        assert!(signed_link.verify());
+
+        let block = ipfs.block_get(cid).await.unwrap();
+
+        assert_eq!(block, canonical::encode(&signed_link).unwrap());
+        assert_eq!(cid, canonical::cid(&signed_link).unwrap());
+
+        // Binary fields are CBOR byte strings, not arrays of integers.
+        let Ipld::Map(map) = serde_ipld_dagcbor::from_slice(&block).unwrap() else {
+            panic!("signed link is not a map");
+        };
+
+        assert!(matches!(map.get("public_key"), Some(Ipld::Bytes(key)) if key.len() == 65));
+        assert!(matches!(map.get("signature"), Some(Ipld::Bytes(_))));
+    }
+}
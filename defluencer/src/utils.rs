@@ -61,6 +61,32 @@ pub async fn add_image(ipfs: &IpfsService, path: std::path::PathBuf) -> Result<C
     Ok(cid)
 }
 
+/// Download a file while adding it to IPFS. Returns the CID and size in bytes.
+#[cfg(all(not(target_arch = "wasm32"), feature = "import"))]
+pub async fn add_url(ipfs: &IpfsService, url: &str) -> Result<(Cid, u64), Error> {
+    use futures::TryStreamExt;
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    };
+
+    let response = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(ipfs_api::errors::Error::from)?;
+
+    let size = Arc::new(AtomicU64::new(0));
+    let counter = size.clone();
+
+    let stream = response.bytes_stream().inspect_ok(move |bytes| {
+        counter.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+    });
+
+    let cid = ipfs.add(stream).await?;
+
+    Ok((cid, size.load(Ordering::Relaxed)))
+}
+
 /// Add a markdown file to IPFS and return the CID
 #[cfg(not(target_arch = "wasm32"))]
 pub async fn add_markdown(ipfs: &IpfsService, path: std::path::PathBuf) -> Result<Cid, Error> {
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use cid::Cid;
+
+use linked_data::channel::live::LiveSettings;
+
+use crate::{diff::ChannelChange, live::decode_segment};
+
+/// Delay between IPNS resolutions when watching a channel.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Live segments after a pause this long start a new stream.
+pub const LIVE_GAP: Duration = Duration::from_secs(60);
+
+/// What happened to a watched channel, see `Defluencer::watch_channel`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelEvent {
+    /// CID of the signed media added.
+    NewContent(Cid),
+
+    /// CID of the signed media removed.
+    ContentRemoved(Cid),
+
+    /// CID of the new identity.
+    IdentityUpdated(Cid),
+
+    /// First live segment after a pause, the channel is streaming now.
+    LiveStarted(LiveSettings),
+}
+
+impl ChannelEvent {
+    /// Returns the event of a content change, none for other changes.
+    pub fn from_change(change: ChannelChange) -> Option<Self> {
+        match change {
+            ChannelChange::ContentAdded(cid) => Some(Self::NewContent(cid)),
+            ChannelChange::ContentRemoved(cid) => Some(Self::ContentRemoved(cid)),
+            _ => None,
+        }
+    }
+}
+
+/// Segments of the live stream of a watched channel.
+pub(crate) struct LiveState {
+    pub settings: LiveSettings,
+
+    /// Unix time of the last valid segment.
+    last_segment: Option<i64>,
+}
+
+impl LiveState {
+    pub fn new(settings: LiveSettings) -> Self {
+        Self {
+            settings,
+            last_segment: None,
+        }
+    }
+
+    /// Returns true if this message is the first valid segment after a pause.
+    pub fn on_segment(&mut self, data: &[u8], now: i64) -> bool {
+        if decode_segment(&self.settings, data).is_none() {
+            return false;
+        }
+
+        let started = match self.last_segment {
+            Some(last) => now - last > LIVE_GAP.as_secs() as i64,
+            None => true,
+        };
+
+        self.last_segment = Some(now);
+
+        started
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use multihash::{Code, MultihashDigest};
+
+    #[test]
+    fn events() {
+        let cid = Cid::new_v1(0x71, Code::Sha2_256.digest(b"media"));
+
+        assert_eq!(
+            ChannelEvent::from_change(ChannelChange::ContentAdded(cid)),
+            Some(ChannelEvent::NewContent(cid))
+        );
+        assert_eq!(
+            ChannelEvent::from_change(ChannelChange::ContentRemoved(cid)),
+            Some(ChannelEvent::ContentRemoved(cid))
+        );
+    }
+
+    #[test]
+    fn live_starts() {
+        let segment = Cid::new_v1(0x71, Code::Sha2_256.digest(b"segment"));
+
+        let mut live = LiveState::new(LiveSettings::default());
+
+        assert!(!live.on_segment(b"garbage", 0));
+        assert!(live.on_segment(&segment.to_bytes(), 0));
+        assert!(!live.on_segment(&segment.to_bytes(), 4));
+
+        let resumed = 4 + LIVE_GAP.as_secs() as i64 + 1;
+
+        assert!(live.on_segment(&segment.to_bytes(), resumed));
+    }
+}
@@ -0,0 +1,420 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+//! Ordered tree properties and canonical encoding checked against Kubo nodes running in docker.
+//!
+//! Run with ```cargo test -p defluencer --features kubo-tests --test kubo```.
+//! Versions are read from KUBO_VERSIONS as a comma separated list of image tags.
+//! Failures print the version and seed to reproduce them.
+
+use std::{collections::BTreeMap, net::TcpListener, process::Command, time::Duration};
+
+use cid::Cid;
+
+use defluencer::{
+    canonical,
+    indexing::ordered_trees::prolly::{Config, HashThreshold, ProllyTree, Strategies},
+};
+
+use futures::TryStreamExt;
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{
+    channel::ChannelMetadata,
+    media::{comments::Comment, video::Video, MediaFormat},
+    types::IPLDLink,
+};
+
+use multihash::{Code, MultihashDigest};
+
+use rand::prelude::*;
+
+use rand_xoshiro::Xoshiro256StarStar;
+
+const DEFAULT_VERSIONS: &str = "v0.20.0,v0.24.0,latest";
+
+const SEEDS: [u64; 3] = [6784236783546783546, 1298374619283746, 42];
+
+const KEY_COUNT: usize = 3_000;
+
+type Model = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Kubo container, stopped when dropped.
+struct Kubo {
+    container: String,
+    ipfs: IpfsService,
+}
+
+impl Kubo {
+    async fn start(version: &str) -> Self {
+        // Let the OS pick a free port.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Free Port")
+            .port();
+
+        let output = Command::new("docker")
+            .args(["run", "--detach", "--rm"])
+            .args(["--publish", &format!("127.0.0.1:{}:5001", port)])
+            .arg(format!("ipfs/kubo:{}", version))
+            .output()
+            .expect("Docker must be installed");
+
+        assert!(
+            output.status.success(),
+            "Kubo {} failed to start. {}",
+            version,
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let container = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+
+        let ipfs = IpfsService::new(&format!("http://127.0.0.1:{}/api/v0/", port)).unwrap();
+
+        let kubo = Self { container, ipfs };
+
+        for _ in 0..120 {
+            if kubo.ipfs.peer_id().await.is_ok() {
+                return kubo;
+            }
+
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+
+        panic!("Kubo {} API unreachable", version);
+    }
+}
+
+impl Drop for Kubo {
+    fn drop(&mut self) {
+        let _ = Command::new("docker")
+            .args(["stop", &self.container])
+            .output();
+    }
+}
+
+fn versions() -> Vec<String> {
+    std::env::var("KUBO_VERSIONS")
+        .unwrap_or_else(|_| DEFAULT_VERSIONS.to_owned())
+        .split(',')
+        .map(|version| version.trim().to_owned())
+        .filter(|version| !version.is_empty())
+        .collect()
+}
+
+/// Default shape and one with smaller chunks, for deeper trees.
+fn configs() -> Vec<Config> {
+    let deep = Config {
+        chunking_strategy: Strategies::Threshold(HashThreshold {
+            chunking_factor: 1 << 28,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    vec![Config::default(), deep]
+}
+
+fn random_pairs(count: usize, rng: &mut Xoshiro256StarStar) -> Model {
+    let mut model = Model::new();
+
+    while model.len() < count {
+        let mut key = vec![0u8; rng.gen_range(1..=16)];
+        rng.fill_bytes(&mut key);
+
+        let mut value = vec![0u8; rng.gen_range(0..=32)];
+        rng.fill_bytes(&mut value);
+
+        model.insert(key, value);
+    }
+
+    model
+}
+
+/// Split pairs in random order and random size batches.
+fn random_batches(
+    pairs: impl IntoIterator<Item = (Vec<u8>, Vec<u8>)>,
+    rng: &mut Xoshiro256StarStar,
+) -> Vec<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut pairs: Vec<_> = pairs.into_iter().collect();
+    pairs.shuffle(rng);
+
+    let mut batches = Vec::new();
+
+    while !pairs.is_empty() {
+        let size = rng.gen_range(1..=pairs.len().min(500));
+
+        batches.push(pairs.drain(..size).collect());
+    }
+
+    batches
+}
+
+async fn build(
+    ipfs: &IpfsService,
+    config: &Config,
+    batches: Vec<Vec<(Vec<u8>, Vec<u8>)>>,
+) -> ProllyTree {
+    let mut tree = ProllyTree::new::<Vec<u8>>(ipfs.clone(), Some(config.clone()))
+        .await
+        .unwrap();
+
+    for batch in batches {
+        tree.batch_insert(batch).await.unwrap();
+    }
+
+    tree
+}
+
+async fn assert_matches(tree: &ProllyTree, model: &Model, context: &str) {
+    let pairs: Vec<(Vec<u8>, Vec<u8>)> = tree.stream().try_collect().await.unwrap();
+
+    let expected: Vec<_> = model.clone().into_iter().collect();
+
+    assert!(
+        pairs == expected,
+        "{} streamed pairs differ from model",
+        context
+    );
+}
+
+/// Run a property for each Kubo version, config and seed.
+macro_rules! property {
+    ($name:ident, $check:ident) => {
+        #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+        async fn $name() {
+            for version in versions() {
+                let kubo = Kubo::start(&version).await;
+
+                for (i, config) in configs().iter().enumerate() {
+                    for seed in SEEDS {
+                        let context = format!(
+                            "{} on Kubo {} config {} seed {}",
+                            stringify!($name),
+                            version,
+                            i,
+                            seed
+                        );
+
+                        let rng = Xoshiro256StarStar::seed_from_u64(seed);
+
+                        $check(&kubo.ipfs, config, rng, &context).await;
+                    }
+                }
+            }
+        }
+    };
+}
+
+/// Every inserted pair can be streamed and fetched, absent keys are not found.
+async fn insert_get(
+    ipfs: &IpfsService,
+    config: &Config,
+    mut rng: Xoshiro256StarStar,
+    context: &str,
+) {
+    let model = random_pairs(KEY_COUNT, &mut rng);
+
+    let tree = build(ipfs, config, random_batches(model.clone(), &mut rng)).await;
+
+    assert_matches(&tree, &model, context).await;
+
+    let absent: Vec<_> = random_pairs(100, &mut rng)
+        .into_keys()
+        .filter(|key| !model.contains_key(key))
+        .collect();
+
+    let mut keys: Vec<_> = model
+        .keys()
+        .filter(|_| rng.gen_bool(0.1))
+        .cloned()
+        .collect();
+    let present = keys.len();
+    keys.extend(absent.iter().cloned());
+
+    let found: Vec<(Vec<u8>, Vec<u8>)> = tree.batch_get(keys).try_collect().await.unwrap();
+
+    assert_eq!(found.len(), present, "{} missing pairs", context);
+
+    for (key, value) in found.iter() {
+        assert!(model.get(key) == Some(value), "{} wrong value", context);
+    }
+
+    for key in absent {
+        let result = tree.get::<Vec<u8>>(key).await.unwrap();
+
+        assert!(result.is_none(), "{} found absent key", context);
+    }
+}
+
+/// Removed keys are gone, the others are untouched.
+async fn insert_remove(
+    ipfs: &IpfsService,
+    config: &Config,
+    mut rng: Xoshiro256StarStar,
+    context: &str,
+) {
+    let mut model = random_pairs(KEY_COUNT, &mut rng);
+
+    let mut tree = build(ipfs, config, random_batches(model.clone(), &mut rng)).await;
+
+    let removed: Vec<_> = model
+        .keys()
+        .filter(|_| rng.gen_bool(0.3))
+        .cloned()
+        .collect();
+
+    for batch in removed.chunks(rng.gen_range(1..=200)) {
+        tree.batch_remove::<Vec<u8>>(batch.to_vec()).await.unwrap();
+    }
+
+    for key in removed.iter() {
+        model.remove(key);
+    }
+
+    assert_matches(&tree, &model, context).await;
+
+    for key in removed.into_iter().take(20) {
+        let result = tree.get::<Vec<u8>>(key).await.unwrap();
+
+        assert!(result.is_none(), "{} found removed key", context);
+    }
+}
+
+/// Inserting present pairs or removing absent keys does not change the tree.
+async fn idempotence(
+    ipfs: &IpfsService,
+    config: &Config,
+    mut rng: Xoshiro256StarStar,
+    context: &str,
+) {
+    let model = random_pairs(KEY_COUNT, &mut rng);
+
+    let mut tree = build(ipfs, config, random_batches(model.clone(), &mut rng)).await;
+
+    let root = tree.save().await.unwrap();
+
+    for batch in random_batches(model.clone(), &mut rng) {
+        tree.batch_insert(batch).await.unwrap();
+    }
+
+    assert_eq!(tree.save().await.unwrap(), root, "{} re-insert", context);
+
+    let absent: Vec<_> = random_pairs(100, &mut rng)
+        .into_keys()
+        .filter(|key| !model.contains_key(key))
+        .collect();
+
+    tree.batch_remove::<Vec<u8>>(absent).await.unwrap();
+
+    assert_eq!(
+        tree.save().await.unwrap(),
+        root,
+        "{} absent removal",
+        context
+    );
+}
+
+/// The same pairs always produce the same tree, whatever the history.
+async fn convergence(
+    ipfs: &IpfsService,
+    config: &Config,
+    mut rng: Xoshiro256StarStar,
+    context: &str,
+) {
+    let model = random_pairs(KEY_COUNT, &mut rng);
+
+    let single = build(ipfs, config, vec![model.clone().into_iter().collect()]).await;
+    let root = single.save().await.unwrap();
+
+    let batched = build(ipfs, config, random_batches(model.clone(), &mut rng)).await;
+
+    assert_eq!(batched.save().await.unwrap(), root, "{} batches", context);
+
+    let extra = random_pairs(KEY_COUNT / 2, &mut rng);
+    let extra: Vec<_> = extra
+        .into_iter()
+        .filter(|(key, _)| !model.contains_key(key))
+        .collect();
+
+    let mut superset = model.clone();
+    superset.extend(extra.iter().cloned());
+
+    let mut pruned = build(ipfs, config, random_batches(superset, &mut rng)).await;
+
+    let keys: Vec<_> = extra.into_iter().map(|(key, _)| key).collect();
+    pruned.batch_remove::<Vec<u8>>(keys).await.unwrap();
+
+    assert_eq!(pruned.save().await.unwrap(), root, "{} removals", context);
+}
+
+property!(prolly_insert_get, insert_get);
+property!(prolly_insert_remove, insert_remove);
+property!(prolly_idempotence, idempotence);
+property!(prolly_convergence, convergence);
+
+fn link(data: &[u8]) -> IPLDLink {
+    Cid::new_v1(0x71, Code::Sha2_256.digest(data)).into()
+}
+
+/// Kubo decodes then re-encodes nodes, the block must stay the same.
+async fn assert_kubo_encoding<T: serde::Serialize>(ipfs: &IpfsService, node: &T, context: &str) {
+    let cid = ipfs
+        .dag_put(node, Codec::DagCbor, Codec::DagCbor)
+        .await
+        .unwrap();
+
+    let block = ipfs.block_get(cid).await.unwrap();
+
+    assert!(
+        block == canonical::encode(node).unwrap(),
+        "{} block differs from canonical encoding",
+        context
+    );
+    assert_eq!(cid, canonical::cid(node).unwrap(), "{}", context);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn canonical_encoding() {
+    let video = Video {
+        identity: link(b"identity"),
+        user_timestamp: 1_700_000_000,
+        video: link(b"timecode"),
+        title: String::from("Title"),
+        duration: Some(3723.5),
+        size: Some(u32::MAX as u64 + 1),
+        image: Some(link(b"image")),
+        format: Some(MediaFormat {
+            container: String::from("mp4"),
+            codecs: vec![String::from("h264"), String::from("aac")],
+        }),
+        tags: vec![String::from("music")],
+        ..Default::default()
+    };
+
+    let comment = Comment {
+        identity: link(b"identity"),
+        user_timestamp: -1,
+        origin: Some(link(b"media").link),
+        reply_to: Some(link(b"parent")),
+        text: String::from("Reply"),
+    };
+
+    let channel = ChannelMetadata {
+        identity: link(b"identity"),
+        content_index: Some(link(b"content")),
+        comment_index: Some(link(b"comments")),
+        agregation_channel: Some(String::from("topic")),
+        ..Default::default()
+    };
+
+    for version in versions() {
+        let kubo = Kubo::start(&version).await;
+
+        let context = format!("Kubo {}", version);
+
+        assert_kubo_encoding(&kubo.ipfs, &video, &context).await;
+        assert_kubo_encoding(&kubo.ipfs, &comment, &context).await;
+        assert_kubo_encoding(&kubo.ipfs, &channel, &context).await;
+    }
+}
@@ -0,0 +1,45 @@
+#ifndef DEFLUENCER_H
+#define DEFLUENCER_H
+
+#include <stdbool.h>
+
+/* Success. */
+#define DEFLUENCER_OK 0
+/* Failure, see defluencer_last_error. */
+#define DEFLUENCER_ERROR -1
+/* The callback stopped the stream early. */
+#define DEFLUENCER_STOPPED 1
+
+typedef struct Handle Handle;
+
+/* Called with each item of a stream as a JSON string, only valid during the call.
+ * Return false to stop the stream. A null callback is an error. */
+typedef bool (*Callback)(const char *item, void *user_data);
+
+/* Create a handle using this IPFS API URL, the default local node if null. */
+Handle *defluencer_new(const char *api_url);
+
+void defluencer_free(Handle *handle);
+
+/* Free a string returned by this library. */
+void defluencer_string_free(char *string);
+
+/* Message of the last error on this thread, null if none. Do not free. */
+const char *defluencer_last_error(void);
+
+/* {"cid": <METADATA_CID>, "metadata": <CHANNEL_METADATA>} or null on failure. */
+char *defluencer_resolve_channel(const Handle *handle, const char *address);
+
+/* Items are {"cid": <SIGNED_LINK_CID>, "media": <MEDIA>}, newest first. */
+int defluencer_stream_content(const Handle *handle, const char *address, Callback callback,
+                              void *user_data);
+
+/* Items are verified comments, see defluencer_verify_comment. */
+int defluencer_stream_comments(const Handle *handle, const char *address, const char *content_cid,
+                               Callback callback, void *user_data);
+
+/* {"cid", "verified": true, "address", "comment"} or {"cid", "verified": false, "reason"},
+ * null on failure. */
+char *defluencer_verify_comment(const Handle *handle, const char *comment_cid);
+
+#endif
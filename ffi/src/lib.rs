@@ -0,0 +1,393 @@
+//! C ABI over the Defluencer read APIs.
+//!
+//! Values cross the boundary as JSON strings.
+//! Strings returned by this library must be freed with `defluencer_string_free`.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    fmt::Display,
+    ptr,
+};
+
+use cid::Cid;
+
+use defluencer::{
+    comments::{verify_comment, VerifiedComment},
+    errors::Error,
+    Defluencer,
+};
+
+use futures_util::{
+    future, pin_mut,
+    stream::{self, Stream},
+    StreamExt, TryStreamExt,
+};
+
+use ipfs_api::{responses::Codec, IpfsService};
+
+use linked_data::{channel::ChannelMetadata, media::Media, types::IPNSAddress};
+
+use serde_json::{json, Value};
+
+use tokio::runtime::{Builder, Runtime};
+
+/// Success.
+pub const DEFLUENCER_OK: c_int = 0;
+
+/// Failure, see `defluencer_last_error`.
+pub const DEFLUENCER_ERROR: c_int = -1;
+
+/// The callback stopped the stream early.
+pub const DEFLUENCER_STOPPED: c_int = 1;
+
+/// Called with each item of a stream as a JSON string, only valid during the call.
+///
+/// Return false to stop the stream. A null callback is an error.
+pub type Callback = Option<extern "C" fn(item: *const c_char, user_data: *mut c_void) -> bool>;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Async runtime and IPFS client, calls using the same handle must not overlap.
+pub struct Handle {
+    runtime: Runtime,
+    ipfs: IpfsService,
+    defluencer: Defluencer,
+}
+
+/// Create a handle using this IPFS API URL, the default local node if null.
+///
+/// Returns null on failure.
+///
+/// # Safety
+/// The URL must be null or a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn defluencer_new(api_url: *const c_char) -> *mut Handle {
+    let ipfs = match api_url.is_null() {
+        true => IpfsService::default(),
+        false => {
+            let Some(url) = read_str(api_url) else {
+                return ptr::null_mut();
+            };
+
+            match IpfsService::new(url) {
+                Ok(ipfs) => ipfs,
+                Err(e) => return fail(e, ptr::null_mut()),
+            }
+        }
+    };
+
+    let runtime = match Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => return fail(e, ptr::null_mut()),
+    };
+
+    let handle = Handle {
+        runtime,
+        defluencer: Defluencer::from(ipfs.clone()),
+        ipfs,
+    };
+
+    Box::into_raw(Box::new(handle))
+}
+
+/// Free a handle.
+///
+/// # Safety
+/// The handle must come from `defluencer_new` and not be used afterward.
+#[no_mangle]
+pub unsafe extern "C" fn defluencer_free(handle: *mut Handle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Free a string returned by this library.
+///
+/// # Safety
+/// The string must come from this library and not be used afterward.
+#[no_mangle]
+pub unsafe extern "C" fn defluencer_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Returns the message of the last error on this thread, null if none.
+///
+/// Valid until the next call to this library on the same thread, do not free.
+#[no_mangle]
+pub extern "C" fn defluencer_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Resolve a channel address.
+///
+/// Returns ```{"cid": <METADATA_CID>, "metadata": <CHANNEL_METADATA>}``` or null on failure.
+///
+/// # Safety
+/// The handle must be valid and the address a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn defluencer_resolve_channel(
+    handle: *const Handle,
+    address: *const c_char,
+) -> *mut c_char {
+    let (Some(handle), Some(addr)) = (handle.as_ref(), read_address(address)) else {
+        return ptr::null_mut();
+    };
+
+    let result = handle.runtime.block_on(channel(&handle.ipfs, addr));
+
+    match result {
+        Ok((cid, metadata)) => to_c_string(json!({
+            "cid": cid.to_string(),
+            "metadata": metadata,
+        })),
+        Err(e) => fail(e, ptr::null_mut()),
+    }
+}
+
+/// Stream the content of a channel, newest first.
+///
+/// Each item is ```{"cid": <SIGNED_LINK_CID>, "media": <MEDIA>}```.
+///
+/// # Safety
+/// The handle must be valid and the address a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn defluencer_stream_content(
+    handle: *const Handle,
+    address: *const c_char,
+    callback: Callback,
+    user_data: *mut c_void,
+) -> c_int {
+    let (Some(handle), Some(addr)) = (handle.as_ref(), read_address(address)) else {
+        return DEFLUENCER_ERROR;
+    };
+
+    let ipfs = &handle.ipfs;
+
+    let stream = stream::once(channel(ipfs, addr))
+        .try_filter_map(|(_, metadata)| future::ready(Ok(metadata.content_index)))
+        .map_ok(|index| handle.defluencer.stream_content_rev_chrono(index))
+        .try_flatten()
+        .and_then(|cid| async move {
+            let media = ipfs
+                .dag_get::<&str, Media>(cid, Some("/link"), Codec::default())
+                .await?;
+
+            Result::<_, Error>::Ok(json!({
+                "cid": cid.to_string(),
+                "media": media,
+            }))
+        });
+
+    drive(&handle.runtime, stream, callback, user_data)
+}
+
+/// Stream the comments on some content of a channel, each one verified.
+///
+/// Each item is a verified comment, see `defluencer_verify_comment`.
+///
+/// # Safety
+/// The handle must be valid, the address and CID valid C strings.
+#[no_mangle]
+pub unsafe extern "C" fn defluencer_stream_comments(
+    handle: *const Handle,
+    address: *const c_char,
+    content_cid: *const c_char,
+    callback: Callback,
+    user_data: *mut c_void,
+) -> c_int {
+    let (Some(handle), Some(addr), Some(content_cid)) = (
+        handle.as_ref(),
+        read_address(address),
+        read_cid(content_cid),
+    ) else {
+        return DEFLUENCER_ERROR;
+    };
+
+    let stream = stream::once(channel(&handle.ipfs, addr))
+        .try_filter_map(|(_, metadata)| future::ready(Ok(metadata.comment_index)))
+        .map_ok(|index| {
+            handle
+                .defluencer
+                .stream_content_comments_verified(index, content_cid)
+        })
+        .try_flatten()
+        .map_ok(comment_json);
+
+    drive(&handle.runtime, stream, callback, user_data)
+}
+
+/// Verify a comment signature against its commenter identity.
+///
+/// Returns ```{"cid", "verified": true, "address", "comment"}```
+/// or ```{"cid", "verified": false, "reason"}```, null on failure.
+///
+/// # Safety
+/// The handle must be valid and the CID a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn defluencer_verify_comment(
+    handle: *const Handle,
+    comment_cid: *const c_char,
+) -> *mut c_char {
+    let (Some(handle), Some(cid)) = (handle.as_ref(), read_cid(comment_cid)) else {
+        return ptr::null_mut();
+    };
+
+    let result = handle.runtime.block_on(verify_comment(&handle.ipfs, cid));
+
+    match result {
+        Ok(verified) => to_c_string(comment_json(verified)),
+        Err(e) => fail(e, ptr::null_mut()),
+    }
+}
+
+async fn channel(ipfs: &IpfsService, addr: IPNSAddress) -> Result<(Cid, ChannelMetadata), Error> {
+    let cid = ipfs.name_resolve(addr).await?;
+
+    let metadata = ipfs
+        .dag_get::<&str, ChannelMetadata>(cid, None, Codec::default())
+        .await?;
+
+    Ok((cid, metadata))
+}
+
+fn comment_json(verified: VerifiedComment) -> Value {
+    match verified {
+        Ok((cid, comment, identity)) => json!({
+            "cid": cid.to_string(),
+            "verified": true,
+            "address": identity.address,
+            "comment": comment,
+        }),
+        Err(rejection) => json!({
+            "cid": rejection.cid.to_string(),
+            "verified": false,
+            "reason": format!("{:?}", rejection.reason),
+        }),
+    }
+}
+
+/// Pass each item of the stream to the callback until it ends, fails or the callback stops it.
+fn drive(
+    runtime: &Runtime,
+    stream: impl Stream<Item = Result<Value, Error>>,
+    callback: Callback,
+    user_data: *mut c_void,
+) -> c_int {
+    let Some(callback) = callback else {
+        return fail("null callback", DEFLUENCER_ERROR);
+    };
+
+    runtime.block_on(async move {
+        pin_mut!(stream);
+
+        while let Some(result) = stream.next().await {
+            let item = match result {
+                Ok(item) => item,
+                Err(e) => return fail(e, DEFLUENCER_ERROR),
+            };
+
+            // Serialized JSON escapes NUL characters.
+            let item = CString::new(item.to_string()).unwrap_or_default();
+
+            if !callback(item.as_ptr(), user_data) {
+                return DEFLUENCER_STOPPED;
+            }
+        }
+
+        DEFLUENCER_OK
+    })
+}
+
+/// Save the error message for `defluencer_last_error` then return the value.
+fn fail<T>(error: impl Display, value: T) -> T {
+    let message = CString::new(error.to_string().replace('\0', "")).unwrap_or_default();
+
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+
+    value
+}
+
+fn to_c_string(value: Value) -> *mut c_char {
+    CString::new(value.to_string())
+        .unwrap_or_default()
+        .into_raw()
+}
+
+unsafe fn read_str<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return fail("Null String", None);
+    }
+
+    match CStr::from_ptr(string).to_str() {
+        Ok(string) => Some(string),
+        Err(e) => fail(e, None),
+    }
+}
+
+unsafe fn read_address(string: *const c_char) -> Option<IPNSAddress> {
+    match IPNSAddress::try_from(read_str(string)?) {
+        Ok(addr) => Some(addr),
+        Err(e) => fail(e, None),
+    }
+}
+
+unsafe fn read_cid(string: *const c_char) -> Option<Cid> {
+    match Cid::try_from(read_str(string)?) {
+        Ok(cid) => Some(cid),
+        Err(e) => fail(e, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern "C" fn take_two(item: *const c_char, user_data: *mut c_void) -> bool {
+        let items = unsafe { &mut *(user_data as *mut Vec<String>) };
+        let item = unsafe { CStr::from_ptr(item) };
+
+        items.push(item.to_str().unwrap().to_owned());
+
+        items.len() < 2
+    }
+
+    #[test]
+    fn callbacks() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+
+        let mut items: Vec<String> = Vec::new();
+        let user_data = &mut items as *mut Vec<String> as *mut c_void;
+
+        let stream = stream::iter([json!(1), json!("two"), json!(3)].map(Ok));
+        let code = drive(&runtime, stream, Some(take_two), user_data);
+
+        assert_eq!(code, DEFLUENCER_STOPPED);
+        assert_eq!(items, vec!["1", "\"two\""]);
+
+        items.clear();
+
+        let stream = stream::iter([Ok(json!(1)), Err(Error::NotFound)]);
+        let code = drive(&runtime, stream, Some(take_two), user_data);
+
+        assert_eq!(code, DEFLUENCER_ERROR);
+        assert_eq!(items, vec!["1"]);
+        assert!(!defluencer_last_error().is_null());
+
+        items.clear();
+
+        let stream = stream::iter([Ok(json!(1))]);
+        let code = drive(&runtime, stream, None, user_data);
+
+        assert_eq!(code, DEFLUENCER_ERROR);
+        assert!(items.is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+use async_trait::async_trait;
+
+use bytes::Bytes;
+
+use cid::Cid;
+
+use futures_util::{stream, TryStreamExt};
+
+use linked_data::types::{IPNSAddress, PeerId};
+
+use crate::{
+    errors::Error,
+    responses::{
+        Codec, Hash, KeyList, KeyListResponse, KeyPair, NamePublishResponse, PinAddResponse,
+        PinRemoteAddResponse, PinRmResponse, PubSubMessage,
+    },
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub type BackendStream<'a, T> = futures_util::stream::BoxStream<'a, T>;
+
+#[cfg(target_arch = "wasm32")]
+pub type BackendStream<'a, T> = futures_util::stream::LocalBoxStream<'a, T>;
+
+/// Send and Sync natively, backends are shared between threads.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSendSync: Send + Sync {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync> MaybeSendSync for T {}
+
+/// Send and Sync natively, backends are shared between threads.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSendSync {}
+
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSendSync for T {}
+
+/// Primitives of an IPFS node.
+///
+/// Kubo's HTTP API is used unless another backend is set with [crate::IpfsService::with_backend],
+/// eg. an embedded node or a mock for tests. Nodes are exchanged encoded,
+/// ```IpfsService``` does the (de)serialization.
+///
+/// Pinning, IPNS, keys, identity and pubsub are optional, unsupported by default.
+/// Kubo specific commands, like the mutable file system, are unsupported by other backends.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait IpfsBackend: MaybeSendSync {
+    /// Returns the raw data of a block.
+    async fn block_get(&self, cid: Cid) -> Result<Bytes, Error>;
+
+    /// Store data as is in a block of this codec. Returns the block CID.
+    async fn block_put(&self, data: Bytes, codec: u64, hash: Hash) -> Result<Cid, Error>;
+
+    /// Returns the size in bytes of a block.
+    async fn block_stat(&self, cid: Cid) -> Result<u64, Error> {
+        let data = self.block_get(cid).await?;
+
+        Ok(data.len() as u64)
+    }
+
+    /// Returns the node at the path from this CID, encoded in the output codec.
+    ///
+    /// Paths are like ```/link/0```, links along the way are followed.
+    async fn dag_get(&self, cid: Cid, path: Option<&str>, output: Codec) -> Result<Bytes, Error>;
+
+    /// Decode a node in the input codec then store it in the store codec. Returns the node CID.
+    async fn dag_put(
+        &self,
+        data: Bytes,
+        input: Codec,
+        store: Codec,
+        hash: Hash,
+    ) -> Result<Cid, Error>;
+
+    /// Add a file chunked in blocks of [crate::CHUNK_SIZE] bytes. Returns the file CID.
+    async fn add(&self, data: Bytes, hash: Hash) -> Result<Cid, Error>;
+
+    /// Add a file as it is read. Returns the file CID.
+    ///
+    /// The file is collected then added by default, backends chunking as they read should not.
+    async fn add_stream(
+        &self,
+        stream: BackendStream<'static, Result<Bytes, Error>>,
+        hash: Hash,
+    ) -> Result<Cid, Error> {
+        let data = stream
+            .try_fold(Vec::new(), |mut data, chunk| async move {
+                data.extend_from_slice(&chunk);
+                Ok(data)
+            })
+            .await?;
+
+        self.add(data.into(), hash).await
+    }
+
+    /// Returns the content of the file at the path from this CID.
+    async fn cat(&self, cid: Cid, path: Option<&str>) -> Result<Bytes, Error>;
+
+    /// List the CIDs of all blocks linked from this one, recursively and without duplicates.
+    async fn refs(&self, _cid: Cid) -> Result<Vec<Cid>, Error> {
+        Err(Error::Unsupported("refs"))
+    }
+
+    async fn pin_add(&self, _cid: Cid, _recursive: bool) -> Result<PinAddResponse, Error> {
+        Err(Error::Unsupported("pin/add"))
+    }
+
+    async fn pin_rm(&self, _cid: Cid, _recursive: bool) -> Result<PinRmResponse, Error> {
+        Err(Error::Unsupported("pin/rm"))
+    }
+
+    /// Pin with a remote pinning service, waiting until it holds the data.
+    async fn pin_remote_add(
+        &self,
+        _cid: Cid,
+        _service: &str,
+    ) -> Result<PinRemoteAddResponse, Error> {
+        Err(Error::Unsupported("pin/remote/add"))
+    }
+
+    /// Pin the new CID recursively then unpin the old one.
+    async fn pin_update(&self, old: Cid, new: Cid) -> Result<PinRmResponse, Error> {
+        self.pin_add(new, true).await?;
+
+        self.pin_rm(old, true).await
+    }
+
+    async fn name_resolve(&self, _addr: IPNSAddress) -> Result<Cid, Error> {
+        Err(Error::Unsupported("name/resolve"))
+    }
+
+    /// Resolve from the routing system, skipping the node cache.
+    ///
+    /// Backends without a cache resolve as usual.
+    async fn name_resolve_nocache(&self, addr: IPNSAddress) -> Result<Cid, Error> {
+        self.name_resolve(addr).await
+    }
+
+    /// Publish a record pointing to this CID, signed by the key with this name.
+    async fn name_publish(&self, _cid: Cid, _key: &str) -> Result<NamePublishResponse, Error> {
+        Err(Error::Unsupported("name/publish"))
+    }
+
+    /// Create a key with this name. Returns the key and its IPNS address.
+    async fn key_gen(&self, _name: &str) -> Result<KeyPair, Error> {
+        Err(Error::Unsupported("key/gen"))
+    }
+
+    /// Returns the IPNS address of every key, by name.
+    async fn key_list(&self) -> Result<KeyList, Error> {
+        Err(Error::Unsupported("key/list"))
+    }
+
+    /// Remove the key with this name. Returns the keys removed.
+    async fn key_rm(&self, _name: &str) -> Result<KeyListResponse, Error> {
+        Err(Error::Unsupported("key/rm"))
+    }
+
+    /// Returns the raw IPNS record of this address from the routing system.
+    async fn routing_get(&self, _addr: IPNSAddress) -> Result<Bytes, Error> {
+        Err(Error::Unsupported("routing/get"))
+    }
+
+    async fn peer_id(&self) -> Result<PeerId, Error> {
+        Err(Error::Unsupported("id"))
+    }
+
+    async fn pubsub_pub(&self, _topic: &[u8], _data: Bytes) -> Result<(), Error> {
+        Err(Error::Unsupported("pubsub/pub"))
+    }
+
+    fn pubsub_sub(&self, _topic: Vec<u8>) -> BackendStream<'_, Result<PubSubMessage, Error>> {
+        Box::pin(stream::once(async {
+            Err(Error::Unsupported("pubsub/sub"))
+        }))
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::TryReserveError, fmt};
+use std::{collections::TryReserveError, convert::Infallible, fmt};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -11,6 +11,9 @@ pub enum Error {
     #[error("DAG CBOR Decode: {0}")]
     Decode(#[from] serde_ipld_dagcbor::DecodeError<TryReserveError>),
 
+    #[error("DAG CBOR Decode: {0}")]
+    DecodeSlice(#[from] serde_ipld_dagcbor::DecodeError<Infallible>),
+
     #[error("Serde: {0}")]
     Serde(#[from] serde_json::error::Error),
 
@@ -26,17 +29,62 @@ pub enum Error {
     #[error("Cid: {0}")]
     Cid(#[from] cid::Error),
 
+    #[error("Unknown multihash code: {0:#x}")]
+    UnknownHash(u64),
+
     #[error("Ipfs: {0}")]
     Ipfs(#[from] IPFSError),
 
     #[error("Ipns: Key not found")]
     Ipns,
 
+    #[error("Ipns Record: {0}")]
+    IpnsRecord(#[from] ipns_records::Error),
+
     #[error("Parse: {0}")]
     Parse(#[from] url::ParseError),
 
+    #[error("Header Name: {0}")]
+    HeaderName(#[from] reqwest::header::InvalidHeaderName),
+
+    #[error("Header Value: {0}")]
+    HeaderValue(#[from] reqwest::header::InvalidHeaderValue),
+
     #[error("IO: {0}")]
     IO(#[from] std::io::Error),
+
+    #[error("Timeout: no response from {endpoint} after {attempts} attempt(s)")]
+    Timeout { endpoint: String, attempts: u32 },
+
+    #[error("Unsupported by this backend: {0}")]
+    Unsupported(&'static str),
+}
+
+impl Error {
+    /// Returns true if the request timed out on every attempt.
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout { .. })
+    }
+
+    /// Returns true if the data could not be decoded into the type requested.
+    pub fn is_decode(&self) -> bool {
+        matches!(
+            self,
+            Self::Decode(_) | Self::DecodeSlice(_) | Self::Serde(_)
+        )
+    }
+
+    /// Returns true if no response was received, trying again may succeed.
+    pub(crate) fn is_transient(&self) -> bool {
+        match self {
+            Self::Timeout { .. } => true,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Reqwest(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+            #[cfg(target_arch = "wasm32")]
+            Self::Reqwest(e) => e.is_timeout() || e.is_request(),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -0,0 +1,120 @@
+use bytes::{Bytes, BytesMut};
+
+use futures_util::{future::LocalBoxFuture, stream::LocalBoxStream, StreamExt, TryStreamExt};
+
+use reqwest::{header::HeaderMap, Client, Method, Request, StatusCode, Url};
+
+use crate::errors::Error;
+
+/// Request to the IPFS API.
+pub struct FetchRequest {
+    pub method: Method,
+
+    pub url: Url,
+
+    /// Headers of this request and those sent with every request.
+    pub headers: HeaderMap,
+
+    /// Complete body, multipart forms included.
+    pub body: Option<Bytes>,
+}
+
+/// Response of the IPFS API, the body may be streamed as it arrives.
+pub struct FetchResponse {
+    pub status: StatusCode,
+
+    pub body: LocalBoxStream<'static, Result<Bytes, Error>>,
+}
+
+/// Send requests in place of the built-in client.
+///
+/// Route requests through a service worker cache or an authenticating proxy.
+/// Implemented for functions and closures returning a boxed future.
+pub trait Fetch {
+    fn fetch(&self, request: FetchRequest)
+        -> LocalBoxFuture<'static, Result<FetchResponse, Error>>;
+}
+
+impl<F> Fetch for F
+where
+    F: Fn(FetchRequest) -> LocalBoxFuture<'static, Result<FetchResponse, Error>>,
+{
+    fn fetch(
+        &self,
+        request: FetchRequest,
+    ) -> LocalBoxFuture<'static, Result<FetchResponse, Error>> {
+        self(request)
+    }
+}
+
+/// Response of the built-in client or of a custom fetch.
+pub enum Response {
+    Client(reqwest::Response),
+    Fetch(FetchResponse),
+}
+
+impl Response {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::Client(response) => response.status(),
+            Self::Fetch(response) => response.status,
+        }
+    }
+
+    pub async fn bytes(self) -> Result<Bytes, Error> {
+        match self {
+            Self::Client(response) => Ok(response.bytes().await?),
+            Self::Fetch(response) => {
+                let bytes = response
+                    .body
+                    .try_fold(BytesMut::new(), |mut bytes, chunk| async move {
+                        bytes.extend_from_slice(&chunk);
+                        Ok(bytes)
+                    })
+                    .await?;
+
+                Ok(bytes.freeze())
+            }
+        }
+    }
+
+    pub fn bytes_stream(self) -> LocalBoxStream<'static, Result<Bytes, Error>> {
+        match self {
+            Self::Client(response) => response.bytes_stream().err_into().boxed_local(),
+            Self::Fetch(response) => response.body,
+        }
+    }
+}
+
+/// Send a request with the custom fetch, if any, or the built-in client.
+///
+/// The client adds the default headers itself, they are added here for custom fetches.
+pub(crate) async fn send(
+    client: &Client,
+    fetch: Option<&dyn Fetch>,
+    headers: &HeaderMap,
+    request: Request,
+) -> Result<Response, Error> {
+    let Some(fetch) = fetch else {
+        return Ok(Response::Client(client.execute(request).await?));
+    };
+
+    let mut all_headers = headers.clone();
+    all_headers.extend(request.headers().clone());
+
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(Bytes::copy_from_slice);
+
+    let request = FetchRequest {
+        method: request.method().clone(),
+        url: request.url().clone(),
+        headers: all_headers,
+        body,
+    };
+
+    let response = fetch.fetch(request).await?;
+
+    Ok(Response::Fetch(response))
+}
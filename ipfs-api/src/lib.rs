@@ -1,15 +1,38 @@
+pub mod backend;
 pub mod errors;
+#[cfg(target_arch = "wasm32")]
+pub mod fetch;
+#[cfg(feature = "test-util")]
+pub mod mock;
+pub mod pubsub;
 pub mod responses;
+pub mod retry;
+pub mod routing;
 
-use std::{borrow::Cow, sync::Arc};
+use std::{borrow::Cow, future::Future, sync::Arc, time::Duration};
 
+use async_trait::async_trait;
+
+use backend::{BackendStream, IpfsBackend};
 use errors::{Error, IPFSError};
-use futures_util::{stream, AsyncBufReadExt, Stream, StreamExt, TryStreamExt};
+use futures_util::{
+    future::{self, Either},
+    pin_mut,
+    stream::{self, LocalBoxStream},
+    AsyncBufReadExt, Stream, StreamExt, TryStreamExt,
+};
+
+use ipns_records::IPNSRecord;
 
 use linked_data::types::{IPNSAddress, PeerId};
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::responses::*;
+use crate::{
+    pubsub::{Backoff, PubSubEvent, SequenceTracker},
+    responses::*,
+    retry::RetryPolicy,
+    routing::DelegatedRouting,
+};
 
 use cid::{
     multibase::{encode, Base},
@@ -17,18 +40,43 @@ use cid::{
 };
 
 use reqwest::{
-    multipart::{Form, Part},
-    Client, Url,
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+    Client, Request, RequestBuilder, Url,
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::multipart::{Form, Part};
+
+#[cfg(target_arch = "wasm32")]
+use fetch::Fetch;
+
 use bytes::Bytes;
 
 pub const DEFAULT_URI: &str = "http://127.0.0.1:5001/api/v0/";
 
+/// Size of the blocks files are chunked into when added.
+pub const CHUNK_SIZE: u64 = 1048576;
+
 #[derive(Clone)]
 pub struct IpfsService {
     client: Client,
     base_url: Arc<Url>,
+
+    routing: Option<DelegatedRouting>,
+
+    hash: Hash,
+
+    /// Sent with every request.
+    headers: HeaderMap,
+
+    retry: RetryPolicy,
+
+    /// Node used instead of the HTTP API, for the methods it provides.
+    backend: Option<Arc<dyn IpfsBackend>>,
+
+    /// Sends the requests instead of the built-in client.
+    #[cfg(target_arch = "wasm32")]
+    fetch: Option<Arc<dyn Fetch>>,
 }
 
 impl Default for IpfsService {
@@ -38,7 +86,17 @@ impl Default for IpfsService {
 
         let client = Client::new();
 
-        Self { client, base_url }
+        Self {
+            client,
+            base_url,
+            routing: None,
+            hash: Hash::default(),
+            headers: HeaderMap::new(),
+            retry: RetryPolicy::default(),
+            backend: None,
+            #[cfg(target_arch = "wasm32")]
+            fetch: None,
+        }
     }
 }
 
@@ -49,28 +107,220 @@ impl IpfsService {
 
         let client = Client::new();
 
-        Ok(Self { client, base_url })
+        Ok(Self {
+            client,
+            base_url,
+            routing: None,
+            hash: Hash::default(),
+            headers: HeaderMap::new(),
+            retry: RetryPolicy::default(),
+            backend: None,
+            #[cfg(target_arch = "wasm32")]
+            fetch: None,
+        })
+    }
+
+    /// Add a header to every request, e.g. the project id of a hosted provider.
+    pub fn with_header(self, name: &str, value: &str) -> Result<Self, Error> {
+        let name = HeaderName::from_bytes(name.as_bytes())?;
+        let value = HeaderValue::from_str(value)?;
+
+        self.with_header_value(name, value)
+    }
+
+    /// Authenticate every request with a username and password.
+    pub fn with_basic_auth(self, username: &str, password: &str) -> Result<Self, Error> {
+        let mut value = HeaderValue::from_str(&basic_auth(username, password))?;
+        value.set_sensitive(true);
+
+        self.with_header_value(AUTHORIZATION, value)
+    }
+
+    /// Authenticate every request with an API token.
+    pub fn with_bearer_token(self, token: &str) -> Result<Self, Error> {
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", token))?;
+        value.set_sensitive(true);
+
+        self.with_header_value(AUTHORIZATION, value)
+    }
+
+    fn with_header_value(mut self, name: HeaderName, value: HeaderValue) -> Result<Self, Error> {
+        self.headers.insert(name, value);
+
+        self.client = Client::builder()
+            .default_headers(self.headers.clone())
+            .build()?;
+
+        Ok(self)
+    }
+
+    /// Resolve IPNS and find providers through delegated routing instead of this node.
+    pub fn with_delegated_routing(mut self, routing: DelegatedRouting) -> Self {
+        self.routing = Some(routing);
+        self
+    }
+
+    /// Address the blocks added by this service with this hash function instead of SHA2-256.
+    pub fn with_hash(mut self, hash: Hash) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    /// Retry requests and bound their duration as per this policy.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Store, pin, name and exchange content with this node instead of kubo's HTTP API.
+    ///
+    /// Methods not part of [IpfsBackend] still use the HTTP API.
+    pub fn with_backend(mut self, backend: impl IpfsBackend + 'static) -> Self {
+        self.backend = Some(Arc::new(backend));
+        self
+    }
+
+    fn backend(&self) -> &dyn IpfsBackend {
+        match self.backend.as_deref() {
+            Some(backend) => backend,
+            None => self,
+        }
+    }
+
+    /// Kubo specific commands are unsupported when another backend is set.
+    fn kubo_only(&self, endpoint: &'static str) -> Result<(), Error> {
+        match self.backend {
+            Some(_) => Err(Error::Unsupported(endpoint)),
+            None => Ok(()),
+        }
+    }
+
+    /// Send requests with this fetch implementation instead of the built-in client.
+    #[cfg(target_arch = "wasm32")]
+    pub fn with_fetch(mut self, fetch: impl Fetch + 'static) -> Self {
+        self.fetch = Some(Arc::new(fetch));
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn send(&self, request: RequestBuilder) -> Result<reqwest::Response, Error> {
+        self.retry(request, |request| async move {
+            Ok(self.client.execute(request).await?)
+        })
+        .await
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn send(&self, request: RequestBuilder) -> Result<fetch::Response, Error> {
+        self.retry(request, |request| {
+            fetch::send(&self.client, self.fetch.as_deref(), &self.headers, request)
+        })
+        .await
+    }
+
+    /// Send the request, again when no response is received, as per the retry policy.
+    async fn retry<F, R>(
+        &self,
+        request: RequestBuilder,
+        send: impl Fn(Request) -> F,
+    ) -> Result<R, Error>
+    where
+        F: Future<Output = Result<R, Error>>,
+    {
+        let mut request = request.build()?;
+
+        let endpoint = request
+            .url()
+            .path()
+            .strip_prefix(self.base_url.path())
+            .unwrap_or_default()
+            .to_owned();
+
+        let timeout = self.retry.timeout(&endpoint);
+
+        let mut attempt = 1;
+        let mut delay = None;
+
+        loop {
+            // Requests with streamed bodies cannot be sent twice.
+            let next = self
+                .retry
+                .next_delay(attempt, delay)
+                .and_then(|delay| Some((delay, request.try_clone()?)));
+
+            let response = send(request);
+
+            let result = match timeout {
+                Some(timeout) => {
+                    pin_mut!(response);
+
+                    match future::select(response, futures_timer::Delay::new(timeout)).await {
+                        Either::Left((result, _)) => result,
+                        Either::Right(_) => Err(Error::Timeout {
+                            endpoint: endpoint.clone(),
+                            attempts: attempt,
+                        }),
+                    }
+                }
+                None => response.await,
+            };
+
+            match (result, next) {
+                (Err(e), Some((wait, clone))) if e.is_transient() => {
+                    futures_timer::Delay::new(wait).await;
+
+                    request = clone;
+                    delay = Some(wait);
+                    attempt += 1;
+                }
+                (result, _) => return result,
+            }
+        }
+    }
+
+    /// Hash function used to address new blocks.
+    pub fn hash(&self) -> Hash {
+        self.hash
     }
 
     #[cfg(target_arch = "wasm32")]
     pub async fn add(&self, bytes: Bytes) -> Result<Cid, Error> {
+        self.backend().add(bytes, self.hash).await
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn add<S>(&self, stream: S) -> Result<Cid, Error>
+    where
+        S: futures_util::stream::TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        if let Some(backend) = self.backend.as_ref() {
+            let stream = stream
+                .map_ok(Bytes::from)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error).into())
+                .boxed();
+
+            return backend.add_stream(stream, self.hash).await;
+        }
+
         let url = self.base_url.join("add")?;
 
-        let part = Part::stream(bytes);
+        let body = reqwest::Body::wrap_stream(stream);
+        let part = Part::stream(body);
 
         let form = Form::new().part("path", part);
 
-        let bytes = self
+        let request = self
             .client
             .post(url)
             .query(&[("pin", "false")])
             .query(&[("cid-version", "1")])
             .query(&[("chunker", "size-1048576")])
-            .multipart(form)
-            .send()
-            .await?
-            .bytes()
-            .await?;
+            .query(&[("hash", self.hash.to_string())])
+            .multipart(form);
+
+        let bytes = self.send(request).await?.bytes().await?;
 
         //println!("{}", std::str::from_utf8(&bytes).unwrap());
 
@@ -83,41 +333,132 @@ impl IpfsService {
         Err(error.into())
     }
 
+    /// Add a file, streaming progress as it is chunked.
+    ///
+    /// The last item has the CID of the file.
     #[cfg(not(target_arch = "wasm32"))]
-    pub async fn add<S>(&self, stream: S) -> Result<Cid, Error>
+    pub fn add_with_progress<S>(
+        &self,
+        stream: S,
+    ) -> impl Stream<Item = Result<AddProgress, Error>> + '_
     where
         S: futures_util::stream::TryStream + Send + Sync + 'static,
         S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
         Bytes: From<S::Ok>,
     {
+        stream::once(async move {
+            self.kubo_only("add")?;
+
+            let url = self.base_url.join("add")?;
+
+            let body = reqwest::Body::wrap_stream(stream);
+            let part = Part::stream(body);
+
+            let form = Form::new().part("path", part);
+
+            let request = self
+                .client
+                .post(url)
+                .query(&[("pin", "false")])
+                .query(&[("cid-version", "1")])
+                .query(&[("chunker", format!("size-{}", CHUNK_SIZE))])
+                .query(&[("hash", self.hash.to_string())])
+                .query(&[("progress", "true")])
+                .multipart(form);
+
+            let response = self.send(request).await?;
+
+            let stream = response.bytes_stream();
+
+            let line_stream = stream
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))
+                .into_async_read()
+                .lines()
+                .scan(0, |bytes, item| {
+                    let progress = match item {
+                        Ok(line) => add_progress(&line, bytes),
+                        Err(e) => Err(e.into()),
+                    };
+
+                    futures_util::future::ready(Some(progress))
+                });
+
+            Result::<_, Error>::Ok(line_stream)
+        })
+        .try_flatten()
+    }
+
+    /// Add a directory and all its files, preserving structure.
+    ///
+    /// Files are opened and streamed one at a time as the request is sent.
+    ///
+    /// Returns the CID of the directory.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn add_dir(&self, path: impl AsRef<std::path::Path>) -> Result<Cid, Error> {
+        self.kubo_only("add")?;
+
         let url = self.base_url.join("add")?;
 
-        let body = reqwest::Body::wrap_stream(stream);
-        let part = Part::stream(body);
+        let mut entries = Vec::new();
+        dir_entries(path.as_ref(), "", &mut entries)?;
 
-        let form = Form::new().part("path", part);
+        let mut form = Form::new();
+
+        for DirEntry { path, name, len } in entries {
+            let file_name: String = url::form_urlencoded::byte_serialize(name.as_bytes()).collect();
+
+            let part = match len {
+                None => Part::bytes(Vec::new()).mime_str("application/x-directory")?,
+                Some(len) => {
+                    let stream = stream::once(tokio::fs::File::open(path))
+                        .map_ok(tokio_util::io::ReaderStream::new)
+                        .try_flatten();
+
+                    let body = reqwest::Body::wrap_stream(stream);
+
+                    Part::stream_with_length(body, len).mime_str("application/octet-stream")?
+                }
+            };
+
+            form = form.part("file", part.file_name(file_name));
+        }
 
-        let bytes = self
+        let request = self
             .client
             .post(url)
             .query(&[("pin", "false")])
             .query(&[("cid-version", "1")])
             .query(&[("chunker", "size-1048576")])
-            .multipart(form)
-            .send()
-            .await?
-            .bytes()
-            .await?;
+            .query(&[("hash", self.hash.to_string())])
+            .query(&[("wrap-with-directory", "true")])
+            .multipart(form);
 
-        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+        let bytes = self.send(request).await?.bytes().await?;
 
-        if let Ok(res) = serde_json::from_slice::<AddResponse>(&bytes) {
-            return Ok(res.try_into()?);
-        }
+        // One line per file, the wrapping directory has no name.
+        for line in bytes.split(|byte| *byte == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
 
-        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+            if let Ok(res) = serde_json::from_slice::<AddResponse>(line) {
+                if res.name.is_empty() {
+                    return Ok(res.try_into()?);
+                }
 
-        Err(error.into())
+                continue;
+            }
+
+            let error = serde_json::from_slice::<IPFSError>(line)?;
+
+            return Err(error.into());
+        }
+
+        Err(Error::Ipfs(IPFSError {
+            message: String::from("Directory not added"),
+            code: 0,
+            error_type: "error".to_owned(),
+        }))
     }
 
     /// Download content from block with this CID.
@@ -125,44 +466,57 @@ impl IpfsService {
     where
         U: Into<Cow<'static, str>>,
     {
-        let url = self.base_url.join("cat")?;
+        let path = path.map(Into::into);
 
-        let mut origin = cid.to_string();
+        self.backend().cat(cid, path.as_deref()).await
+    }
 
-        if let Some(path) = path {
-            origin.push_str(&path.into());
-        }
+    /// Write data to a file of the mutable file system, replacing its content.
+    ///
+    /// Missing parent directories are created if parents is true.
+    pub async fn files_write<U>(&self, path: U, data: Bytes, parents: bool) -> Result<(), Error>
+    where
+        U: Into<Cow<'static, str>>,
+    {
+        self.kubo_only("files/write")?;
+
+        let url = self.base_url.join("files/write")?;
 
-        let bytes = self
+        let (content_type, body) = multipart("file", data);
+
+        let request = self
             .client
             .post(url)
-            .query(&[("arg", &origin)])
-            .send()
-            .await?
-            .bytes()
-            .await?;
+            .query(&[("arg", path.into())])
+            .query(&[("create", "true"), ("truncate", "true")])
+            .query(&[("parents", &parents.to_string())])
+            .query(&[("cid-version", "1")])
+            .query(&[("hash", self.hash.to_string())])
+            .header(CONTENT_TYPE, content_type)
+            .body(body);
 
-        Ok(bytes)
+        self.files_empty_response(request).await
     }
 
-    /// Pin a CID recursively or not.
-    pub async fn pin_add(&self, cid: Cid, recursive: bool) -> Result<PinAddResponse, Error> {
-        let url = self.base_url.join("pin/add")?;
+    /// Read a file of the mutable file system.
+    pub async fn files_read<U>(&self, path: U) -> Result<Bytes, Error>
+    where
+        U: Into<Cow<'static, str>>,
+    {
+        self.kubo_only("files/read")?;
 
-        let bytes = self
-            .client
-            .post(url)
-            .query(&[("arg", cid.to_string())])
-            .query(&[("recursive", &recursive.to_string())])
-            .send()
-            .await?
-            .bytes()
-            .await?;
+        let url = self.base_url.join("files/read")?;
 
-        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+        let request = self.client.post(url).query(&[("arg", path.into())]);
 
-        if let Ok(res) = serde_json::from_slice::<PinAddResponse>(&bytes) {
-            return Ok(res);
+        let response = self.send(request).await?;
+
+        let success = response.status().is_success();
+
+        let bytes = response.bytes().await?;
+
+        if success {
+            return Ok(bytes);
         }
 
         let error = serde_json::from_slice::<IPFSError>(&bytes)?;
@@ -170,23 +524,25 @@ impl IpfsService {
         Err(error.into())
     }
 
-    pub async fn pin_update(&self, old: Cid, new: Cid) -> Result<PinRmResponse, Error> {
-        let url = self.base_url.join("pin/update")?;
+    /// List a directory of the mutable file system.
+    pub async fn files_ls<U>(&self, path: U) -> Result<Vec<FilesEntry>, Error>
+    where
+        U: Into<Cow<'static, str>>,
+    {
+        self.kubo_only("files/ls")?;
 
-        let bytes = self
+        let url = self.base_url.join("files/ls")?;
+
+        let request = self
             .client
             .post(url)
-            .query(&[("arg", old.to_string())])
-            .query(&[("arg", new.to_string())])
-            .send()
-            .await?
-            .bytes()
-            .await?;
+            .query(&[("arg", path.into())])
+            .query(&[("long", "true")]);
 
-        //println!("pin_rm Raw => {}", std::str::from_utf8(&bytes).unwrap());
+        let bytes = self.send(request).await?.bytes().await?;
 
-        if let Ok(res) = serde_json::from_slice::<PinRmResponse>(&bytes) {
-            return Ok(res);
+        if let Ok(res) = serde_json::from_slice::<FilesLsResponse>(&bytes) {
+            return Ok(res.entries.unwrap_or_default());
         }
 
         let error = serde_json::from_slice::<IPFSError>(&bytes)?;
@@ -194,23 +550,49 @@ impl IpfsService {
         Err(error.into())
     }
 
-    /// Remove Pinned CID.
-    pub async fn pin_rm(&self, cid: Cid, recursive: bool) -> Result<PinRmResponse, Error> {
-        let url = self.base_url.join("pin/rm")?;
+    /// Copy a file or directory into the mutable file system.
+    ///
+    /// The source can be a path of the mutable file system or of IPFS, eg. ```/ipfs/<CID>```.
+    ///
+    /// Nothing is copied, only linked, the data stays where it is.
+    pub async fn files_cp<U, V>(
+        &self,
+        source: U,
+        destination: V,
+        parents: bool,
+    ) -> Result<(), Error>
+    where
+        U: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+    {
+        self.kubo_only("files/cp")?;
 
-        let bytes = self
+        let url = self.base_url.join("files/cp")?;
+
+        let request = self
             .client
             .post(url)
-            .query(&[("arg", cid.to_string())])
-            .query(&[("recursive", &recursive.to_string())])
-            .send()
-            .await?
-            .bytes()
-            .await?;
+            .query(&[("arg", source.into())])
+            .query(&[("arg", destination.into())])
+            .query(&[("parents", &parents.to_string())]);
 
-        //println!("pin_rm Raw => {}", std::str::from_utf8(&bytes).unwrap());
+        self.files_empty_response(request).await
+    }
 
-        if let Ok(res) = serde_json::from_slice::<PinRmResponse>(&bytes) {
+    /// Returns the CID, size and type of a file or directory of the mutable file system.
+    pub async fn files_stat<U>(&self, path: U) -> Result<FilesStatResponse, Error>
+    where
+        U: Into<Cow<'static, str>>,
+    {
+        self.kubo_only("files/stat")?;
+
+        let url = self.base_url.join("files/stat")?;
+
+        let request = self.client.post(url).query(&[("arg", path.into())]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        if let Ok(res) = serde_json::from_slice::<FilesStatResponse>(&bytes) {
             return Ok(res);
         }
 
@@ -219,17 +601,60 @@ impl IpfsService {
         Err(error.into())
     }
 
+    /// Files commands answer with an empty body unless they fail.
+    async fn files_empty_response(&self, request: RequestBuilder) -> Result<(), Error> {
+        let response = self.send(request).await?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        let bytes = response.bytes().await?;
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    /// Pin a CID recursively or not.
+    pub async fn pin_add(&self, cid: Cid, recursive: bool) -> Result<PinAddResponse, Error> {
+        self.backend().pin_add(cid, recursive).await
+    }
+
+    pub async fn pin_update(&self, old: Cid, new: Cid) -> Result<PinRmResponse, Error> {
+        self.backend().pin_update(old, new).await
+    }
+
+    /// Remove Pinned CID.
+    pub async fn pin_rm(&self, cid: Cid, recursive: bool) -> Result<PinRmResponse, Error> {
+        self.backend().pin_rm(cid, recursive).await
+    }
+
+    /// Pin a CID with a remote pinning service configured on the node.
+    ///
+    /// Wait until the service holds the data.
+    pub async fn pin_remote_add<U>(
+        &self,
+        cid: Cid,
+        service: U,
+    ) -> Result<PinRemoteAddResponse, Error>
+    where
+        U: Into<Cow<'static, str>>,
+    {
+        self.backend().pin_remote_add(cid, &service.into()).await
+    }
+
     pub async fn pin_ls(&self, pin_mode: PinMode) -> Result<PinList, Error> {
+        self.kubo_only("pin/ls")?;
+
         let url = self.base_url.join("pin/ls")?;
 
-        let bytes = self
+        let request = self
             .client
             .post(url)
-            .query(&[("type", &pin_mode.to_string())])
-            .send()
-            .await?
-            .bytes()
-            .await?;
+            .query(&[("type", &pin_mode.to_string())]);
+
+        let bytes = self.send(request).await?.bytes().await?;
 
         //println!("pin_ls Raw => {}", std::str::from_utf8(&bytes).unwrap());
 
@@ -243,44 +668,23 @@ impl IpfsService {
     }
 
     /// Serialize then add dag node to IPFS. Return a CID.
+    ///
+    /// The input codec is picked per call, DAG-CBOR keeps large integers and bytes intact
+    /// where DAG-JSON may not. The node decodes then re-encodes the data in the store codec,
+    /// see [IpfsService::block_put_encoded] to keep the exact bytes.
     pub async fn dag_put<T>(&self, node: &T, input: Codec, store: Codec) -> Result<Cid, Error>
     where
         T: ?Sized + Serialize,
     {
-        //TODO add hash option
-
         let data = match input {
             Codec::DagCbor => serde_ipld_dagcbor::to_vec(node)?,
             Codec::DagJson => serde_json::to_vec(node)?,
             Codec::DagJose => unimplemented!(),
         };
 
-        let part = Part::bytes(data);
-        let form = Form::new().part("object data", part);
-
-        let url = self.base_url.join("dag/put")?;
-
-        let bytes = self
-            .client
-            .post(url)
-            .query(&[("store-codec", store.to_string())])
-            .query(&[("input-codec", input.to_string())])
-            .query(&[("pin", "false")])
-            .multipart(form)
-            .send()
-            .await?
-            .bytes()
-            .await?;
-
-        //println!("{}", std::str::from_utf8(&bytes).unwrap());
-
-        if let Ok(res) = serde_json::from_slice::<DagPutResponse>(&bytes) {
-            return Ok(res.try_into()?);
-        }
-
-        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
-
-        Err(error.into())
+        self.backend()
+            .dag_put(data.into(), input, store, self.hash)
+            .await
     }
 
     /// Deserialize dag node from IPFS path. Return dag node.
@@ -289,113 +693,49 @@ impl IpfsService {
         U: Into<Cow<'static, str>>,
         T: ?Sized + DeserializeOwned,
     {
-        let mut origin = cid.to_string();
-
-        if let Some(path) = path {
-            origin.push_str(&path.into());
-        }
-
-        let url = self.base_url.join("dag/get")?;
+        let path = path.map(Into::into);
 
-        let bytes = self
-            .client
-            .post(url)
-            .query(&[("arg", &origin)])
-            .query(&[("output-codec", output.to_string())])
-            .send()
-            .await?
-            .bytes()
-            .await?;
+        let bytes = self.backend().dag_get(cid, path.as_deref(), output).await?;
 
         //println!("{}", std::str::from_utf8(&bytes).unwrap());
 
-        match output {
-            Codec::DagCbor => {
-                if let Ok(res) = serde_ipld_dagcbor::from_slice(&bytes) {
-                    return Ok(res);
-                }
-            }
-            Codec::DagJson => {
-                if let Ok(res) = serde_json::from_slice::<T>(&bytes) {
-                    return Ok(res);
-                }
-            }
+        let node = match output {
+            Codec::DagCbor => serde_ipld_dagcbor::from_slice(&bytes)?,
+            Codec::DagJson => serde_json::from_slice::<T>(&bytes)?,
             Codec::DagJose => unimplemented!(),
         };
 
-        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
-
-        Err(error.into())
+        Ok(node)
     }
 
     pub async fn key_gen(&self, name: impl Into<Cow<'static, str>>) -> Result<KeyPair, Error> {
-        let url = self.base_url.join("key/gen")?;
-
-        let bytes = self
-            .client
-            .post(url)
-            .query(&[("arg", name.into())])
-            .query(&[("ipns-base", "base32")])
-            .send()
-            .await?
-            .bytes()
-            .await?;
-
-        //println!("{}", std::str::from_utf8(&bytes).unwrap());
-
-        if let Ok(res) = serde_json::from_slice::<KeyPair>(&bytes) {
-            return Ok(res);
-        }
-
-        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
-
-        Err(error.into())
+        self.backend().key_gen(&name.into()).await
     }
 
     /// Returns all IPNS keys on this IPFS node.
     pub async fn key_list(&self) -> Result<KeyList, Error> {
-        let url = self.base_url.join("key/list")?;
-
-        let bytes = self
-            .client
-            .post(url)
-            .query(&[("l", "true"), ("ipns-base", "base32")])
-            .send()
-            .await?
-            .bytes()
-            .await?;
-
-        //println!("{}", std::str::from_utf8(&bytes).unwrap());
-
-        if let Ok(res) = serde_json::from_slice::<KeyListResponse>(&bytes) {
-            return Ok(res.into());
-        }
-
-        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
-
-        Err(error.into())
+        self.backend().key_list().await
     }
 
     pub async fn key_import<U>(&self, name: U, key_file: String) -> Result<KeyPair, Error>
     where
         U: Into<Cow<'static, str>>,
     {
-        let url = self.base_url.join("key/import")?;
+        self.kubo_only("key/import")?;
 
-        let part = Part::stream(key_file);
+        let url = self.base_url.join("key/import")?;
 
-        let form = Form::new().part("key", part);
+        let (content_type, body) = multipart("key", key_file.into());
 
-        let bytes = self
+        let request = self
             .client
             .post(url)
             .query(&[("arg", name.into())])
             .query(&[("ipns-base", "base32")])
-            .multipart(form)
-            .send()
-            .await?
-            .bytes()
-            .await?;
+            .header(CONTENT_TYPE, content_type)
+            .body(body);
+
+        let bytes = self.send(request).await?.bytes().await?;
 
         //println!("{}", std::str::from_utf8(&bytes).unwrap());
 
@@ -412,26 +752,7 @@ impl IpfsService {
     where
         U: Into<Cow<'static, str>>,
     {
-        let url = self.base_url.join("key/rm")?;
-
-        let bytes = self
-            .client
-            .post(url)
-            .query(&[("arg", key.into())])
-            .send()
-            .await?
-            .bytes()
-            .await?;
-
-        //println!("{}", std::str::from_utf8(&bytes).unwrap());
-
-        if let Ok(res) = serde_json::from_slice::<KeyListResponse>(&bytes) {
-            return Ok(res);
-        }
-
-        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
-
-        Err(error.into())
+        self.backend().key_rm(&key.into()).await
     }
 
     /// Publish new IPNS record.
@@ -439,45 +760,104 @@ impl IpfsService {
     where
         U: Into<Cow<'static, str>>,
     {
-        let url = self.base_url.join("name/publish")?;
+        self.backend().name_publish(cid, &key.into()).await
+    }
 
-        let bytes = self
-            .client
-            .post(url)
-            .query(&[("arg", cid.to_string())])
-            .query(&[("lifetime", "4320h")]) // 6 months
-            .query(&[("key", &key.into())])
-            .query(&[("ipns-base", "base32")])
-            .send()
-            .await?
-            .bytes()
-            .await?;
+    /// List the CIDs of all blocks linked from this one, recursively and without duplicates.
+    ///
+    /// The root CID is not included.
+    pub async fn refs(&self, cid: Cid) -> Result<Vec<Cid>, Error> {
+        self.backend().refs(cid).await
+    }
 
-        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+    /// Returns the size in bytes of a block.
+    pub async fn block_stat(&self, cid: Cid) -> Result<u64, Error> {
+        self.backend().block_stat(cid).await
+    }
 
-        if let Ok(res) = serde_json::from_slice::<NamePublishResponse>(&bytes) {
-            return Ok(res);
-        }
+    /// Return the raw data of a block.
+    pub async fn block_get(&self, cid: Cid) -> Result<Bytes, Error> {
+        self.backend().block_get(cid).await
+    }
 
-        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+    /// Store a block with the same codec and hash function as this CID. Return the block CID.
+    pub async fn block_put(&self, cid: Cid, data: Bytes) -> Result<Cid, Error> {
+        let hash = match cid.hash().code() {
+            0x12 => Hash::Sha2_256,
+            0x1e => Hash::Blake3,
+            code => return Err(Error::UnknownHash(code)),
+        };
 
-        Err(error.into())
+        self.backend().block_put(data, cid.codec(), hash).await
+    }
+
+    /// Store already encoded data as a block of this codec. Return the block CID.
+    ///
+    /// The bytes are stored as is, unlike [IpfsService::dag_put] which may re-encode them.
+    /// Signed DAG-CBOR or DAG-JOSE nodes stay valid.
+    pub async fn block_put_encoded(&self, data: Bytes, codec: Codec) -> Result<Cid, Error> {
+        self.backend()
+            .block_put(data, codec as u64, self.hash)
+            .await
     }
 
     /// Resolve IPNS name. Returns CID.
     pub async fn name_resolve(&self, addr: IPNSAddress) -> Result<Cid, Error> {
+        if let Some(routing) = self.routing.as_ref() {
+            let timeout = self.retry.timeout("name/resolve");
+
+            return routing.name_resolve(addr, timeout).await;
+        }
+
+        self.backend().name_resolve(addr).await
+    }
+
+    /// Resolve IPNS name from the routing system instead of the node cache.
+    ///
+    /// Slower, use it when a stale value would overwrite newer updates.
+    pub async fn name_resolve_nocache(&self, addr: IPNSAddress) -> Result<Cid, Error> {
+        if let Some(routing) = self.routing.as_ref() {
+            let timeout = self.retry.timeout("name/resolve");
+
+            return routing.name_resolve(addr, timeout).await;
+        }
+
+        self.backend().name_resolve_nocache(addr).await
+    }
+
+    /// Resolve IPNS name, giving up on the DHT after the timeout.
+    ///
+    /// Custom backends are given up on after the timeout too.
+    pub async fn name_resolve_timeout(
+        &self,
+        addr: IPNSAddress,
+        timeout: Duration,
+    ) -> Result<Cid, Error> {
+        if let Some(routing) = self.routing.as_ref() {
+            return routing.name_resolve(addr, Some(timeout)).await;
+        }
+
+        if let Some(backend) = self.backend.as_ref() {
+            let resolve = backend.name_resolve(addr);
+            pin_mut!(resolve);
+
+            return match future::select(resolve, futures_timer::Delay::new(timeout)).await {
+                Either::Left((result, _)) => result,
+                Either::Right(_) => Err(Error::Timeout {
+                    endpoint: String::from("name/resolve"),
+                    attempts: 1,
+                }),
+            };
+        }
+
         let url = self.base_url.join("name/resolve")?;
 
-        let bytes = self
-            .client
-            .post(url)
-            .query(&[("arg", addr.to_string())])
-            .send()
-            .await?
-            .bytes()
-            .await?;
+        let request = self.client.post(url).query(&[
+            ("arg", addr.to_string()),
+            ("dht-timeout", format!("{}ms", timeout.as_millis())),
+        ]);
 
-        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+        let bytes = self.send(request).await?.bytes().await?;
 
         if let Ok(res) = serde_json::from_slice::<NameResolveResponse>(&bytes) {
             return Ok(res.try_into()?);
@@ -488,6 +868,60 @@ impl IpfsService {
         Err(error.into())
     }
 
+    /// Returns peers providing this CID, at most the number specified.
+    pub async fn find_providers(&self, cid: Cid, count: usize) -> Result<Vec<Provider>, Error> {
+        if let Some(routing) = self.routing.as_ref() {
+            let timeout = self.retry.timeout("routing/findprovs");
+
+            let mut providers = routing.find_providers(cid, timeout).await?;
+            providers.truncate(count);
+
+            return Ok(providers);
+        }
+
+        self.kubo_only("routing/findprovs")?;
+
+        let url = self.base_url.join("routing/findprovs")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", cid.to_string())])
+            .query(&[("num-providers", count)]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        let mut providers = Vec::with_capacity(count);
+
+        for line in bytes.split(|byte| *byte == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
+
+            let res = match serde_json::from_slice::<DHTPutResponse>(line) {
+                Ok(res) => res,
+                Err(_) => match serde_json::from_slice::<IPFSError>(line) {
+                    Ok(error) => return Err(error.into()),
+                    Err(_) => continue,
+                },
+            };
+
+            // Type 4 = Provider
+            if res.dht_put_response_type != 4 {
+                continue;
+            }
+
+            providers.extend(res.responses.into_iter().map(|response| Provider {
+                schema: String::from("peer"),
+                id: response.id,
+                addrs: response.addrs,
+                protocols: Vec::new(),
+            }));
+        }
+
+        Ok(providers)
+    }
+
     /// Get node associated with IPNS key.
     pub async fn ipns_get<T>(&self, key: impl Into<Cow<'static, str>>) -> Result<(Cid, T), Error>
     where
@@ -555,19 +989,7 @@ impl IpfsService {
 
     ///Return peer id as cid v1.
     pub async fn peer_id(&self) -> Result<PeerId, Error> {
-        let url = self.base_url.join("id")?;
-
-        let bytes = self.client.post(url).send().await?.bytes().await?;
-
-        //println!("{}", std::str::from_utf8(&bytes).unwrap());
-
-        if let Ok(res) = serde_json::from_slice::<IdResponse>(&bytes) {
-            return Ok(res.try_into()?);
-        }
-
-        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
-
-        Err(error.into())
+        self.backend().peer_id().await
     }
 
     /// Send data on the specified topic.
@@ -576,21 +998,9 @@ impl IpfsService {
         T: AsRef<[u8]>,
         D: Into<Cow<'static, [u8]>>,
     {
-        let url = self.base_url.join("pubsub/pub")?;
-
-        let topic = encode(Base::Base64Url, topic);
-
-        let part = Part::bytes(data);
-        let form = Form::new().part("data", part);
-
-        self.client
-            .post(url)
-            .query(&[("arg", &topic)])
-            .multipart(form)
-            .send()
-            .await?;
+        let data = Bytes::from(data.into().into_owned());
 
-        Ok(())
+        self.backend().pubsub_pub(topic.as_ref(), data).await
     }
 
     /// Subscribe to a topic and receive pubsub messages.
@@ -598,29 +1008,123 @@ impl IpfsService {
         &self,
         topic: Vec<u8>,
     ) -> impl Stream<Item = Result<PubSubMessage, Error>> + '_ {
+        self.backend().pubsub_sub(topic)
+    }
+
+    /// Subscribe to a topic, resubscribing with exponential backoff when the connection drops.
+    ///
+    /// Messages are pulled from the daemon only as fast as the stream is polled.
+    /// A gap event is returned each time the subscription is interrupted, the stream never ends.
+    pub fn pubsub_sub_reconnect(
+        &self,
+        topic: Vec<u8>,
+        backoff: Backoff,
+    ) -> impl Stream<Item = PubSubEvent> + '_ {
+        let subscription: Option<LocalBoxStream<'_, Result<PubSubMessage, Error>>> = None;
+        let delay: Option<Duration> = None;
+        let interrupted = false;
+
+        stream::unfold(
+            (subscription, delay, interrupted),
+            move |(mut subscription, mut delay, mut interrupted)| {
+                let topic = topic.clone();
+
+                async move {
+                    loop {
+                        if let (None, Some(delay)) = (subscription.as_ref(), delay) {
+                            futures_timer::Delay::new(delay).await;
+                        }
+
+                        let sub = subscription
+                            .get_or_insert_with(|| self.pubsub_sub(topic.clone()).boxed_local());
+
+                        match sub.next().await {
+                            Some(Ok(msg)) => {
+                                delay = None;
+                                interrupted = false;
+
+                                let event = PubSubEvent::Message(msg);
+
+                                return Some((event, (subscription, delay, interrupted)));
+                            }
+                            Some(Err(_)) | None => {
+                                subscription = None;
+
+                                delay = Some(match delay {
+                                    Some(delay) => backoff.next(delay),
+                                    None => backoff.initial,
+                                });
+
+                                if !interrupted {
+                                    interrupted = true;
+
+                                    return Some((
+                                        PubSubEvent::Gap,
+                                        (subscription, delay, interrupted),
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+        )
+    }
+
+    /// Subscribe to the IPNS record topic of this address, resubscribing when the connection drops.
+    ///
+    /// A gap event is also returned before records whose sequence is not consecutive.
+    /// Records not signed by the address owner are dropped.
+    pub fn ipns_sub(
+        &self,
+        addr: IPNSAddress,
+        backoff: Backoff,
+    ) -> impl Stream<Item = PubSubEvent> + '_ {
+        let mut tracker = SequenceTracker::default();
+
+        self.pubsub_sub_reconnect(addr.to_pubsub_topic().into_bytes(), backoff)
+            .flat_map(move |event| {
+                let msg = match event {
+                    PubSubEvent::Message(msg) => msg,
+                    PubSubEvent::Gap => return stream::iter(vec![PubSubEvent::Gap]),
+                };
+
+                let record = match IPNSRecord::from_bytes(&msg.data) {
+                    Ok(record) if record.verify(addr.into()).is_ok() => record,
+                    _ => return stream::iter(vec![]),
+                };
+
+                let mut events = Vec::with_capacity(2);
+
+                if tracker.is_gap(record.get_sequence()) {
+                    events.push(PubSubEvent::Gap);
+                }
+
+                events.push(PubSubEvent::Message(msg));
+
+                stream::iter(events)
+            })
+    }
+
+    /// Stream the daemon event log as it is written.
+    pub fn log_tail(&self) -> impl Stream<Item = Result<LogEntry, Error>> + '_ {
         stream::once(async move {
-            let url = self.base_url.join("pubsub/sub")?;
+            self.kubo_only("log/tail")?;
 
-            let topic = encode(Base::Base64Url, topic);
+            let url = self.base_url.join("log/tail")?;
 
-            let response = self
-                .client
-                .post(url)
-                .query(&[("arg", topic)])
-                .send()
-                .await?;
+            let response = self.send(self.client.post(url)).await?;
 
             let stream = response.bytes_stream();
 
             let line_stream = stream
-                //TODO .err_into() require implement from reqwest error for std::io::Error
                 .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))
                 .into_async_read()
                 .lines()
                 .map(|item| match item {
                     Ok(line) => {
-                        if let Ok(response) = serde_json::from_str::<PubsubSubResponse>(&line) {
-                            return Ok(response.try_into()?);
+                        if let Ok(entry) = serde_json::from_str::<LogEntry>(&line) {
+                            return Ok(entry);
                         }
 
                         let ipfs_error = serde_json::from_str::<IPFSError>(&line)?;
@@ -635,27 +1139,61 @@ impl IpfsService {
         .try_flatten()
     }
 
+    /// Collect profiles, goroutines dump & version of the daemon. Returns a zip archive.
+    ///
+    /// CPU profiling last for the given number of seconds, Kubo default is 30.
+    pub async fn diag_profile(&self, profile_secs: Option<u64>) -> Result<Bytes, Error> {
+        self.kubo_only("diag/profile")?;
+
+        let url = self.base_url.join("diag/profile")?;
+
+        let mut request = self.client.post(url);
+
+        if let Some(secs) = profile_secs {
+            request = request.query(&[("profile-time", format!("{}s", secs))]);
+        }
+
+        let response = self.send(request).await?;
+
+        let success = response.status().is_success();
+
+        let bytes = response.bytes().await?;
+
+        if success {
+            return Ok(bytes);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    /// Fetch the raw IPNS record of this address from the routing system.
+    pub async fn routing_get(&self, addr: IPNSAddress) -> Result<Bytes, Error> {
+        self.backend().routing_get(addr).await
+    }
+
     pub async fn dht_put<D>(&self, peer_id: Cid, data: D) -> Result<DHTPutResponse, Error>
     where
         D: Into<Cow<'static, [u8]>>,
     {
+        self.kubo_only("dht/put")?;
+
         let url = self.base_url.join("dht/put")?;
 
         let key = format!("/ipns/{}", peer_id.to_string_of_base(Base::Base32Lower)?);
 
-        let part = Part::bytes(data);
-        let form = Form::new().part("value-file", part);
+        let (content_type, body) = multipart("value-file", data.into().into_owned().into());
 
-        let bytes = self
+        let request = self
             .client
             .post(url)
             .query(&[("arg", key)])
             .query(&[("verbose", "false")])
-            .multipart(form)
-            .send()
-            .await?
-            .bytes()
-            .await?;
+            .header(CONTENT_TYPE, content_type)
+            .body(body);
+
+        let bytes = self.send(request).await?.bytes().await?;
 
         //println!("{}", std::str::from_utf8(&bytes).unwrap());
 
@@ -670,3 +1208,836 @@ impl IpfsService {
         //Ok(())
     }
 }
+
+/// Kubo's HTTP API.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl IpfsBackend for IpfsService {
+    async fn block_get(&self, cid: Cid) -> Result<Bytes, Error> {
+        let url = self.base_url.join("block/get")?;
+
+        let request = self.client.post(url).query(&[("arg", cid.to_string())]);
+
+        let response = self.send(request).await?;
+
+        let success = response.status().is_success();
+
+        let bytes = response.bytes().await?;
+
+        if success {
+            return Ok(bytes);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn block_put(&self, data: Bytes, codec: u64, hash: Hash) -> Result<Cid, Error> {
+        let codec = match codec {
+            0x55 => "raw",
+            0x70 => "dag-pb",
+            0x71 => "dag-cbor",
+            0x85 => "dag-jose",
+            0x0129 => "dag-json",
+            _ => return Err(cid::Error::UnknownCodec.into()),
+        };
+
+        let (content_type, body) = multipart("data", data);
+
+        let url = self.base_url.join("block/put")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("cid-codec", codec), ("mhtype", &hash.to_string())])
+            .query(&[("pin", "false")])
+            .header(CONTENT_TYPE, content_type)
+            .body(body);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        if let Ok(res) = serde_json::from_slice::<BlockStatResponse>(&bytes) {
+            return Ok(Cid::try_from(res.key)?);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn block_stat(&self, cid: Cid) -> Result<u64, Error> {
+        let url = self.base_url.join("block/stat")?;
+
+        let request = self.client.post(url).query(&[("arg", cid.to_string())]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        if let Ok(res) = serde_json::from_slice::<BlockStatResponse>(&bytes) {
+            return Ok(res.size);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn dag_get(&self, cid: Cid, path: Option<&str>, output: Codec) -> Result<Bytes, Error> {
+        let mut origin = cid.to_string();
+
+        if let Some(path) = path {
+            origin.push_str(path);
+        }
+
+        let url = self.base_url.join("dag/get")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", &origin)])
+            .query(&[("output-codec", output.to_string())]);
+
+        let response = self.send(request).await?;
+
+        let success = response.status().is_success();
+
+        let bytes = response.bytes().await?;
+
+        if success {
+            return Ok(bytes);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn dag_put(
+        &self,
+        data: Bytes,
+        input: Codec,
+        store: Codec,
+        hash: Hash,
+    ) -> Result<Cid, Error> {
+        let (content_type, body) = multipart("object data", data);
+
+        let url = self.base_url.join("dag/put")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("store-codec", store.to_string())])
+            .query(&[("input-codec", input.to_string())])
+            .query(&[("hash", hash.to_string())])
+            .query(&[("pin", "false")])
+            .header(CONTENT_TYPE, content_type)
+            .body(body);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<DagPutResponse>(&bytes) {
+            return Ok(res.try_into()?);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn add(&self, data: Bytes, hash: Hash) -> Result<Cid, Error> {
+        let url = self.base_url.join("add")?;
+
+        let (content_type, body) = multipart("path", data);
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("pin", "false")])
+            .query(&[("cid-version", "1")])
+            .query(&[("chunker", "size-1048576")])
+            .query(&[("hash", hash.to_string())])
+            .header(CONTENT_TYPE, content_type)
+            .body(body);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<AddResponse>(&bytes) {
+            return Ok(res.try_into()?);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn cat(&self, cid: Cid, path: Option<&str>) -> Result<Bytes, Error> {
+        let url = self.base_url.join("cat")?;
+
+        let mut origin = cid.to_string();
+
+        if let Some(path) = path {
+            origin.push_str(path);
+        }
+
+        let request = self.client.post(url).query(&[("arg", &origin)]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        Ok(bytes)
+    }
+
+    async fn refs(&self, cid: Cid) -> Result<Vec<Cid>, Error> {
+        let url = self.base_url.join("refs")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", cid.to_string())])
+            .query(&[("recursive", "true"), ("unique", "true")]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        let mut cids = Vec::new();
+
+        for line in bytes.split(|byte| *byte == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Ok(res) = serde_json::from_slice::<RefsResponse>(line) {
+                if !res.error.is_empty() {
+                    return Err(Error::Ipfs(IPFSError {
+                        message: res.error,
+                        code: 0,
+                        error_type: "error".to_owned(),
+                    }));
+                }
+
+                cids.push(Cid::try_from(res.cid)?);
+
+                continue;
+            }
+
+            let error = serde_json::from_slice::<IPFSError>(line)?;
+
+            return Err(error.into());
+        }
+
+        Ok(cids)
+    }
+
+    async fn pin_add(&self, cid: Cid, recursive: bool) -> Result<PinAddResponse, Error> {
+        let url = self.base_url.join("pin/add")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", cid.to_string())])
+            .query(&[("recursive", &recursive.to_string())]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<PinAddResponse>(&bytes) {
+            return Ok(res);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn pin_rm(&self, cid: Cid, recursive: bool) -> Result<PinRmResponse, Error> {
+        let url = self.base_url.join("pin/rm")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", cid.to_string())])
+            .query(&[("recursive", &recursive.to_string())]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        //println!("pin_rm Raw => {}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<PinRmResponse>(&bytes) {
+            return Ok(res);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn pin_remote_add(&self, cid: Cid, service: &str) -> Result<PinRemoteAddResponse, Error> {
+        let url = self.base_url.join("pin/remote/add")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", cid.to_string())])
+            .query(&[("service", service)])
+            .query(&[("background", "false")]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        if let Ok(res) = serde_json::from_slice::<PinRemoteAddResponse>(&bytes) {
+            return Ok(res);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn pin_update(&self, old: Cid, new: Cid) -> Result<PinRmResponse, Error> {
+        let url = self.base_url.join("pin/update")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", old.to_string())])
+            .query(&[("arg", new.to_string())]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        //println!("pin_rm Raw => {}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<PinRmResponse>(&bytes) {
+            return Ok(res);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn name_resolve(&self, addr: IPNSAddress) -> Result<Cid, Error> {
+        let url = self.base_url.join("name/resolve")?;
+
+        let request = self.client.post(url).query(&[("arg", addr.to_string())]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<NameResolveResponse>(&bytes) {
+            return Ok(res.try_into()?);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn name_resolve_nocache(&self, addr: IPNSAddress) -> Result<Cid, Error> {
+        let url = self.base_url.join("name/resolve")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", addr.to_string())])
+            .query(&[("nocache", "true")]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        if let Ok(res) = serde_json::from_slice::<NameResolveResponse>(&bytes) {
+            return Ok(res.try_into()?);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn name_publish(&self, cid: Cid, key: &str) -> Result<NamePublishResponse, Error> {
+        let url = self.base_url.join("name/publish")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", cid.to_string())])
+            .query(&[("lifetime", "4320h")]) // 6 months
+            .query(&[("key", key)])
+            .query(&[("ipns-base", "base32")]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<NamePublishResponse>(&bytes) {
+            return Ok(res);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn key_gen(&self, name: &str) -> Result<KeyPair, Error> {
+        let url = self.base_url.join("key/gen")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", name)])
+            .query(&[("ipns-base", "base32")]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<KeyPair>(&bytes) {
+            return Ok(res);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn key_list(&self) -> Result<KeyList, Error> {
+        let url = self.base_url.join("key/list")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("l", "true"), ("ipns-base", "base32")]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<KeyListResponse>(&bytes) {
+            return Ok(res.into());
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn key_rm(&self, key: &str) -> Result<KeyListResponse, Error> {
+        let url = self.base_url.join("key/rm")?;
+
+        let request = self.client.post(url).query(&[("arg", key)]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<KeyListResponse>(&bytes) {
+            return Ok(res);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn routing_get(&self, addr: IPNSAddress) -> Result<Bytes, Error> {
+        let url = self.base_url.join("routing/get")?;
+
+        let cid: Cid = addr.into();
+        let key = format!("/ipns/{}", cid.to_string_of_base(Base::Base32Lower)?);
+
+        let request = self.client.post(url).query(&[("arg", key)]);
+
+        let response = self.send(request).await?;
+
+        let success = response.status().is_success();
+
+        let bytes = response.bytes().await?;
+
+        if success {
+            return Ok(bytes);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn peer_id(&self) -> Result<PeerId, Error> {
+        let url = self.base_url.join("id")?;
+
+        let bytes = self.send(self.client.post(url)).await?.bytes().await?;
+
+        //println!("{}", std::str::from_utf8(&bytes).unwrap());
+
+        if let Ok(res) = serde_json::from_slice::<IdResponse>(&bytes) {
+            return Ok(res.try_into()?);
+        }
+
+        let error = serde_json::from_slice::<IPFSError>(&bytes)?;
+
+        Err(error.into())
+    }
+
+    async fn pubsub_pub(&self, topic: &[u8], data: Bytes) -> Result<(), Error> {
+        let url = self.base_url.join("pubsub/pub")?;
+
+        let topic = encode(Base::Base64Url, topic);
+
+        let (content_type, body) = multipart("data", data);
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", &topic)])
+            .header(CONTENT_TYPE, content_type)
+            .body(body);
+
+        self.send(request).await?;
+
+        Ok(())
+    }
+
+    fn pubsub_sub(&self, topic: Vec<u8>) -> BackendStream<'_, Result<PubSubMessage, Error>> {
+        let stream = stream::once(async move {
+            let url = self.base_url.join("pubsub/sub")?;
+
+            let topic = encode(Base::Base64Url, topic);
+
+            let request = self.client.post(url).query(&[("arg", topic)]);
+
+            let response = self.send(request).await?;
+
+            let stream = response.bytes_stream();
+
+            let line_stream = stream
+                //TODO .err_into() require implement from reqwest error for std::io::Error
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))
+                .into_async_read()
+                .lines()
+                .map(|item| match item {
+                    Ok(line) => {
+                        if let Ok(response) = serde_json::from_str::<PubsubSubResponse>(&line) {
+                            return Ok(response.try_into()?);
+                        }
+
+                        let ipfs_error = serde_json::from_str::<IPFSError>(&line)?;
+
+                        Err(ipfs_error.into())
+                    }
+                    Err(e) => Err(e.into()),
+                });
+
+            Result::<_, Error>::Ok(line_stream)
+        })
+        .try_flatten();
+
+        Box::pin(stream)
+    }
+}
+
+/// Value of the authorization header for HTTP basic authentication.
+fn basic_auth(username: &str, password: &str) -> String {
+    let credentials = format!("{}:{}", username, password);
+
+    // Multibase prefix the encoding with a character.
+    let encoded = encode(Base::Base64Pad, credentials);
+
+    format!("Basic {}", &encoded[1..])
+}
+
+/// Encode a form of a single part, returns the content type and body.
+///
+/// Unlike reqwest forms, the body can be read back by custom fetch implementations.
+fn multipart(name: &str, data: Bytes) -> (String, Bytes) {
+    // The boundary must not appear in the data.
+    let boundary = (0u64..)
+        .map(|n| format!("defluencer-boundary-{:016x}", n))
+        .find(|boundary| {
+            !data
+                .windows(boundary.len())
+                .any(|window| window == boundary.as_bytes())
+        })
+        .expect("Unused Boundary");
+
+    let mut body = Vec::with_capacity(data.len() + 256);
+
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+            boundary, name
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(&data);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+    let content_type = format!("multipart/form-data; boundary={}", boundary);
+
+    (content_type, body.into())
+}
+
+/// Parse a line of an add request with progress, bytes are kept between lines.
+fn add_progress(line: &str, bytes: &mut u64) -> Result<AddProgress, Error> {
+    if let Ok(response) = serde_json::from_str::<AddProgressResponse>(line) {
+        if let Some(count) = response.bytes {
+            *bytes = count;
+        }
+
+        let cid = match response.hash {
+            Some(hash) => Some(Cid::try_from(hash)?),
+            None => None,
+        };
+
+        return Ok(AddProgress {
+            bytes: *bytes,
+            blocks: bytes.div_ceil(CHUNK_SIZE),
+            cid,
+        });
+    }
+
+    let ipfs_error = serde_json::from_str::<IPFSError>(line)?;
+
+    Err(ipfs_error.into())
+}
+
+/// File or directory to add, directories have no length.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, PartialEq)]
+struct DirEntry {
+    path: std::path::PathBuf,
+
+    /// Path relative to the added directory.
+    name: String,
+
+    len: Option<u64>,
+}
+
+/// Walk a directory depth first, each directory is followed by all its content.
+///
+/// Kubo expects parents before their children and the content of a directory in one run.
+#[cfg(not(target_arch = "wasm32"))]
+fn dir_entries(
+    dir: &std::path::Path,
+    prefix: &str,
+    entries: &mut Vec<DirEntry>,
+) -> std::io::Result<()> {
+    let mut children = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    children.sort_by_key(|entry| entry.file_name());
+
+    for child in children {
+        let name = format!("{}{}", prefix, child.file_name().to_string_lossy());
+        let metadata = child.metadata()?;
+
+        if metadata.is_dir() {
+            entries.push(DirEntry {
+                path: child.path(),
+                name: name.clone(),
+                len: None,
+            });
+
+            dir_entries(&child.path(), &format!("{}/", name), entries)?;
+
+            continue;
+        }
+
+        entries.push(DirEntry {
+            path: child.path(),
+            name,
+            len: Some(metadata.len()),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_progress_lines() {
+        let mut bytes = 0;
+
+        let progress = add_progress(r#"{"Name":"","Bytes":2097152}"#, &mut bytes).unwrap();
+
+        assert_eq!(progress.bytes, 2097152);
+        assert_eq!(progress.blocks, 2);
+        assert_eq!(progress.cid, None);
+
+        let progress = add_progress(r#"{"Name":"","Bytes":2097153}"#, &mut bytes).unwrap();
+
+        assert_eq!(progress.blocks, 3);
+
+        let progress = add_progress(
+            r#"{"Name":"bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku","Hash":"bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku","Size":"2097153"}"#,
+            &mut bytes,
+        )
+        .unwrap();
+
+        assert_eq!(progress.bytes, 2097153);
+        assert!(progress.cid.is_some());
+
+        assert!(matches!(
+            add_progress(
+                r#"{"Message":"unexpected EOF","Code":0,"Type":"error"}"#,
+                &mut bytes
+            ),
+            Err(Error::Ipfs(_))
+        ));
+    }
+
+    #[test]
+    fn multipart_body() {
+        let (content_type, body) = multipart("data", Bytes::from_static(b"hello"));
+
+        assert_eq!(
+            content_type,
+            "multipart/form-data; boundary=defluencer-boundary-0000000000000000"
+        );
+        assert_eq!(
+            body,
+            "--defluencer-boundary-0000000000000000\r\nContent-Disposition: form-data; name=\"data\"\r\nContent-Type: application/octet-stream\r\n\r\nhello\r\n--defluencer-boundary-0000000000000000--\r\n"
+        );
+
+        let data = Bytes::from_static(b"--defluencer-boundary-0000000000000000");
+        let (content_type, _) = multipart("data", data);
+
+        assert!(content_type.ends_with("defluencer-boundary-0000000000000001"));
+    }
+
+    #[test]
+    fn basic_auth_header() {
+        assert_eq!(
+            basic_auth("Aladdin", "open sesame"),
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+    }
+
+    /// Blocks in memory, addressed with identity hashes.
+    #[derive(Default)]
+    struct MemoryBackend {
+        blocks: std::sync::Mutex<std::collections::HashMap<Cid, Bytes>>,
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+    #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+    impl IpfsBackend for MemoryBackend {
+        async fn block_get(&self, cid: Cid) -> Result<Bytes, Error> {
+            let blocks = self.blocks.lock().unwrap();
+
+            blocks.get(&cid).cloned().ok_or_else(|| {
+                Error::Ipfs(IPFSError {
+                    message: String::from("Block not found"),
+                    code: 0,
+                    error_type: "error".to_owned(),
+                })
+            })
+        }
+
+        async fn block_put(&self, data: Bytes, codec: u64, _: Hash) -> Result<Cid, Error> {
+            let multihash = cid::multihash::Multihash::wrap(0x00, &data).expect("Small block");
+            let cid = Cid::new_v1(codec, multihash);
+
+            self.blocks.lock().unwrap().insert(cid, data);
+
+            Ok(cid)
+        }
+
+        async fn dag_get(&self, cid: Cid, path: Option<&str>, _: Codec) -> Result<Bytes, Error> {
+            if path.is_some() {
+                return Err(Error::Unsupported("dag/get path"));
+            }
+
+            self.block_get(cid).await
+        }
+
+        async fn dag_put(
+            &self,
+            data: Bytes,
+            _: Codec,
+            store: Codec,
+            hash: Hash,
+        ) -> Result<Cid, Error> {
+            self.block_put(data, store as u64, hash).await
+        }
+
+        async fn add(&self, data: Bytes, hash: Hash) -> Result<Cid, Error> {
+            self.block_put(data, 0x55, hash).await
+        }
+
+        async fn cat(&self, cid: Cid, _: Option<&str>) -> Result<Bytes, Error> {
+            self.block_get(cid).await
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn custom_backend() {
+        let ipfs = IpfsService::default().with_backend(MemoryBackend::default());
+
+        let node = vec!["Hello".to_owned(), "World".to_owned()];
+
+        let cid = ipfs
+            .dag_put(&node, Codec::DagCbor, Codec::DagCbor)
+            .await
+            .unwrap();
+
+        assert_eq!(cid.codec(), 0x71);
+
+        let res: Vec<String> = ipfs
+            .dag_get(cid, Option::<&str>::None, Codec::DagCbor)
+            .await
+            .unwrap();
+
+        assert_eq!(res, node);
+
+        let cid = ipfs
+            .add(stream::iter([Ok::<_, std::io::Error>(Bytes::from_static(
+                b"Hello World",
+            ))]))
+            .await
+            .unwrap();
+
+        let data = ipfs.cat(cid, Option::<&str>::None).await.unwrap();
+
+        assert_eq!(data, Bytes::from_static(b"Hello World"));
+
+        assert!(matches!(ipfs.peer_id().await, Err(Error::Unsupported(_))));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn dir_walk_order() {
+        let root = std::env::temp_dir().join(format!("add_dir_{}", std::process::id()));
+
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("a/b/c.txt"), b"c").unwrap();
+        std::fs::write(root.join("a/d.txt"), b"dd").unwrap();
+        std::fs::create_dir_all(root.join("e")).unwrap();
+        std::fs::write(root.join("f.txt"), b"fff").unwrap();
+
+        let mut entries = Vec::new();
+        let result = dir_entries(&root, "", &mut entries);
+
+        std::fs::remove_dir_all(&root).unwrap();
+        result.unwrap();
+
+        let walk: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.len))
+            .collect();
+
+        assert_eq!(
+            walk,
+            vec![
+                ("a", None),
+                ("a/b", None),
+                ("a/b/c.txt", Some(1)),
+                ("a/d.txt", Some(2)),
+                ("e", None),
+                ("f.txt", Some(3)),
+            ]
+        );
+    }
+}
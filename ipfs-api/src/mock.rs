@@ -0,0 +1,593 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+
+use bytes::Bytes;
+
+use cid::{multihash::Multihash, Cid};
+
+use libipld_core::ipld::Ipld;
+
+use linked_data::types::{IPNSAddress, PeerId};
+
+use multihash::{Code, MultihashDigest};
+
+use crate::{
+    backend::IpfsBackend,
+    errors::{Error, IPFSError},
+    responses::{
+        Codec, Hash, KeyList, KeyListResponse, KeyPair, NamePublishResponse, PinAddResponse,
+        PinRemoteAddResponse, PinRmResponse,
+    },
+    IpfsService,
+};
+
+const RAW: u64 = 0x55;
+const DAG_CBOR: u64 = 0x71;
+const LIB_P2P_KEY: u64 = 0x72;
+
+/// Name of the node's own key.
+const SELF_KEY: &str = "self";
+
+#[derive(Debug, Default)]
+struct State {
+    blocks: HashMap<Cid, Bytes>,
+
+    /// Pinned CIDs, true if pinned recursively.
+    pins: HashMap<Cid, bool>,
+
+    /// CIDs pinned with a remote service.
+    remote_pins: HashSet<Cid>,
+
+    keys: HashMap<String, IPNSAddress>,
+    records: HashMap<IPNSAddress, Cid>,
+
+    /// Blocks and nodes read through the API.
+    reads: usize,
+}
+
+/// IPFS node keeping blocks, pins, keys and IPNS records in memory, for tests.
+///
+/// Clones share the same node. Files are added as one raw block,
+/// only DAG-CBOR nodes are supported and they are stored as encoded.
+/// CIDs may differ from kubo's, pubsub is unsupported.
+#[derive(Debug, Clone, Default)]
+pub struct MockIpfs {
+    state: Arc<Mutex<State>>,
+}
+
+impl MockIpfs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a service using this node instead of kubo's HTTP API.
+    pub fn service(&self) -> IpfsService {
+        IpfsService::default().with_backend(self.clone())
+    }
+
+    /// Returns true if the block of this CID is stored.
+    pub fn contains(&self, cid: Cid) -> bool {
+        self.state().blocks.contains_key(&cid)
+    }
+
+    /// Number of blocks stored.
+    pub fn block_count(&self) -> usize {
+        self.state().blocks.len()
+    }
+
+    /// Number of blocks and nodes read so far, to check what was fetched.
+    pub fn reads(&self) -> usize {
+        self.state().reads
+    }
+
+    /// Returns true if this CID is pinned, recursively or not.
+    pub fn is_pinned(&self, cid: Cid) -> bool {
+        self.state().pins.contains_key(&cid)
+    }
+
+    /// Returns true if garbage collection would keep this block, pinned or linked from a recursive pin.
+    pub fn is_kept(&self, cid: Cid) -> bool {
+        let pins: Vec<(Cid, bool)> = self
+            .state()
+            .pins
+            .iter()
+            .map(|(pin, recursive)| (*pin, *recursive))
+            .collect();
+
+        pins.into_iter()
+            .any(|(pin, recursive)| pin == cid || (recursive && self.links_to(pin, cid)))
+    }
+
+    /// Returns true if this CID is pinned with a remote service.
+    pub fn is_pinned_remotely(&self, cid: Cid) -> bool {
+        self.state().remote_pins.contains(&cid)
+    }
+
+    fn links_to(&self, root: Cid, cid: Cid) -> bool {
+        let mut seen = HashSet::new();
+        let mut stack = vec![root];
+
+        while let Some(node) = stack.pop() {
+            if node == cid {
+                return true;
+            }
+
+            if node.codec() != DAG_CBOR || !seen.insert(node) {
+                continue;
+            }
+
+            if let Ok(ipld) = self.node(node) {
+                collect_links(&ipld, &mut stack);
+            }
+        }
+
+        false
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("Lock Poisoned")
+    }
+
+    fn node(&self, cid: Cid) -> Result<Ipld, Error> {
+        if cid.codec() != DAG_CBOR {
+            return Err(Error::Unsupported("dag/get of non DAG-CBOR blocks"));
+        }
+
+        let data = self
+            .state()
+            .blocks
+            .get(&cid)
+            .cloned()
+            .ok_or_else(|| ipfs_error(format!("block {} not found", cid)))?;
+
+        let ipld = serde_ipld_dagcbor::from_slice(&data)?;
+
+        Ok(ipld)
+    }
+
+    /// Returns the address of the key with this name.
+    fn key(&self, name: &str) -> Result<IPNSAddress, Error> {
+        if name == SELF_KEY {
+            return Ok(address(SELF_KEY));
+        }
+
+        self.state()
+            .keys
+            .get(name)
+            .copied()
+            .ok_or_else(|| ipfs_error(format!("no key by the name {} was found", name)))
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl IpfsBackend for MockIpfs {
+    async fn block_get(&self, cid: Cid) -> Result<Bytes, Error> {
+        let mut state = self.state();
+
+        state.reads += 1;
+
+        state
+            .blocks
+            .get(&cid)
+            .cloned()
+            .ok_or_else(|| ipfs_error(format!("block {} not found", cid)))
+    }
+
+    async fn block_put(&self, data: Bytes, codec: u64, hash: Hash) -> Result<Cid, Error> {
+        let multihash = match hash {
+            Hash::Sha2_256 => Code::Sha2_256.digest(&data),
+            Hash::Blake3 => Code::Blake3_256.digest(&data),
+        };
+
+        let cid = Cid::new_v1(codec, multihash);
+
+        self.state().blocks.insert(cid, data);
+
+        Ok(cid)
+    }
+
+    /// Follows links along the path and at its end, like kubo.
+    ///
+    /// Links are CBOR tag 42 or ```{"/": "<CID>"}``` maps, as serialized by ```IPLDLink```.
+    async fn dag_get(&self, cid: Cid, path: Option<&str>, output: Codec) -> Result<Bytes, Error> {
+        if output != Codec::DagCbor {
+            return Err(Error::Unsupported("dag/get output codec"));
+        }
+
+        let path = match path {
+            Some(path) => path,
+            None => return self.block_get(cid).await,
+        };
+
+        self.state().reads += 1;
+
+        let mut ipld = self.node(cid)?;
+
+        for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+            if let Some(link) = as_link(&ipld) {
+                ipld = self.node(link)?;
+            }
+
+            let next = match ipld {
+                Ipld::Map(mut map) => map.remove(segment),
+                Ipld::List(mut list) => match segment.parse::<usize>() {
+                    Ok(index) if index < list.len() => Some(list.swap_remove(index)),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            ipld = next.ok_or_else(|| ipfs_error(format!("no link named {:?}", segment)))?;
+        }
+
+        if let Some(link) = as_link(&ipld) {
+            return self.block_get(link).await;
+        }
+
+        let data = serde_ipld_dagcbor::to_vec(&ipld)?;
+
+        Ok(data.into())
+    }
+
+    async fn dag_put(
+        &self,
+        data: Bytes,
+        input: Codec,
+        store: Codec,
+        hash: Hash,
+    ) -> Result<Cid, Error> {
+        if input != Codec::DagCbor || store != Codec::DagCbor {
+            return Err(Error::Unsupported("dag/put codecs"));
+        }
+
+        // Refuse invalid nodes like kubo.
+        serde_ipld_dagcbor::from_slice::<Ipld>(&data)?;
+
+        self.block_put(data, DAG_CBOR, hash).await
+    }
+
+    async fn add(&self, data: Bytes, hash: Hash) -> Result<Cid, Error> {
+        self.block_put(data, RAW, hash).await
+    }
+
+    async fn cat(&self, cid: Cid, path: Option<&str>) -> Result<Bytes, Error> {
+        if path.is_some() {
+            return Err(Error::Unsupported("cat path"));
+        }
+
+        self.block_get(cid).await
+    }
+
+    async fn refs(&self, cid: Cid) -> Result<Vec<Cid>, Error> {
+        let mut refs = Vec::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![cid];
+
+        while let Some(cid) = stack.pop() {
+            if cid.codec() != DAG_CBOR {
+                continue;
+            }
+
+            let mut links = Vec::new();
+            collect_links(&self.node(cid)?, &mut links);
+
+            for link in links {
+                if seen.insert(link) {
+                    refs.push(link);
+                    stack.push(link);
+                }
+            }
+        }
+
+        Ok(refs)
+    }
+
+    async fn pin_add(&self, cid: Cid, recursive: bool) -> Result<PinAddResponse, Error> {
+        let mut state = self.state();
+
+        if !state.blocks.contains_key(&cid) {
+            return Err(ipfs_error(format!("block {} not found", cid)));
+        }
+
+        let pin = state.pins.entry(cid).or_default();
+        *pin |= recursive;
+
+        Ok(PinAddResponse {
+            pins: vec![cid.to_string()],
+            progress: None,
+        })
+    }
+
+    async fn pin_rm(&self, cid: Cid, _recursive: bool) -> Result<PinRmResponse, Error> {
+        match self.state().pins.remove(&cid) {
+            Some(_) => Ok(PinRmResponse {
+                pins: vec![cid.to_string()],
+            }),
+            None => Err(ipfs_error("not pinned or pinned indirectly".to_owned())),
+        }
+    }
+
+    async fn pin_remote_add(&self, cid: Cid, service: &str) -> Result<PinRemoteAddResponse, Error> {
+        let mut state = self.state();
+
+        if !state.blocks.contains_key(&cid) {
+            return Err(ipfs_error(format!("block {} not found", cid)));
+        }
+
+        state.remote_pins.insert(cid);
+
+        Ok(PinRemoteAddResponse {
+            cid: cid.to_string(),
+            name: service.to_owned(),
+            status: String::from("pinned"),
+        })
+    }
+
+    async fn name_resolve(&self, addr: IPNSAddress) -> Result<Cid, Error> {
+        self.state().records.get(&addr).copied().ok_or(Error::Ipns)
+    }
+
+    async fn name_publish(&self, cid: Cid, key: &str) -> Result<NamePublishResponse, Error> {
+        let addr = self.key(key)?;
+
+        self.state().records.insert(addr, cid);
+
+        Ok(NamePublishResponse {
+            name: addr.to_string(),
+            value: format!("/ipfs/{}", cid),
+        })
+    }
+
+    async fn key_gen(&self, name: &str) -> Result<KeyPair, Error> {
+        let mut state = self.state();
+
+        if name == SELF_KEY || state.keys.contains_key(name) {
+            return Err(ipfs_error(format!("key with name {} already exists", name)));
+        }
+
+        let addr = address(name);
+
+        state.keys.insert(name.to_owned(), addr);
+
+        Ok(KeyPair {
+            id: addr.to_string(),
+            name: name.to_owned(),
+        })
+    }
+
+    async fn key_list(&self) -> Result<KeyList, Error> {
+        let mut keys = self.state().keys.clone();
+
+        keys.insert(SELF_KEY.to_owned(), address(SELF_KEY));
+
+        Ok(keys)
+    }
+
+    async fn key_rm(&self, name: &str) -> Result<KeyListResponse, Error> {
+        let addr = match self.state().keys.remove(name) {
+            Some(addr) => addr,
+            None => return Err(ipfs_error(format!("no key by the name {} was found", name))),
+        };
+
+        Ok(KeyListResponse {
+            keys: vec![KeyPair {
+                id: addr.to_string(),
+                name: name.to_owned(),
+            }],
+        })
+    }
+
+    async fn peer_id(&self) -> Result<PeerId, Error> {
+        let cid: Cid = address(SELF_KEY).into();
+
+        Ok(PeerId::try_from(cid)?)
+    }
+}
+
+/// Returns the CID if this node is a link.
+fn as_link(ipld: &Ipld) -> Option<Cid> {
+    match ipld {
+        Ipld::Link(cid) => Some(*cid),
+        Ipld::Map(map) if map.len() == 1 => match map.get("/") {
+            Some(Ipld::String(text)) => Cid::try_from(text.as_str()).ok(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn collect_links(ipld: &Ipld, links: &mut Vec<Cid>) {
+    if let Some(link) = as_link(ipld) {
+        links.push(link);
+        return;
+    }
+
+    match ipld {
+        Ipld::Map(map) => map.values().for_each(|ipld| collect_links(ipld, links)),
+        Ipld::List(list) => list.iter().for_each(|ipld| collect_links(ipld, links)),
+        _ => {}
+    }
+}
+
+/// Keys are not real key pairs, addresses are derived from the key name.
+fn address(name: &str) -> IPNSAddress {
+    let multihash: Multihash = Code::Sha2_256.digest(name.as_bytes());
+
+    IPNSAddress::try_from(Cid::new_v1(LIB_P2P_KEY, multihash)).expect("Libp2p Key Codec")
+}
+
+fn ipfs_error(message: String) -> Error {
+    Error::Ipfs(IPFSError {
+        message,
+        code: 0,
+        error_type: "error".to_owned(),
+    })
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use linked_data::media::comments::{Comment, CommentChunk};
+
+    #[tokio::test]
+    async fn dag_paths() {
+        let mock = MockIpfs::new();
+        let ipfs = mock.service();
+
+        let leaf = ipfs
+            .dag_put(&"Hello World", Codec::DagCbor, Codec::DagCbor)
+            .await
+            .unwrap();
+
+        let mut map = BTreeMap::new();
+        map.insert("link".to_owned(), Ipld::Link(leaf));
+        map.insert(
+            "list".to_owned(),
+            Ipld::List(vec![Ipld::Integer(0), Ipld::Integer(1)]),
+        );
+
+        let root = ipfs
+            .dag_put(&Ipld::Map(map), Codec::DagCbor, Codec::DagCbor)
+            .await
+            .unwrap();
+
+        let text: String = ipfs
+            .dag_get(root, Some("/link"), Codec::DagCbor)
+            .await
+            .unwrap();
+        assert_eq!(text, "Hello World");
+
+        let number: u8 = ipfs
+            .dag_get(root, Some("/list/1"), Codec::DagCbor)
+            .await
+            .unwrap();
+        assert_eq!(number, 1);
+
+        assert!(ipfs
+            .dag_get::<&str, u8>(root, Some("/list/2"), Codec::DagCbor)
+            .await
+            .is_err());
+
+        assert_eq!(ipfs.refs(root).await.unwrap(), vec![leaf]);
+        assert_eq!(ipfs.block_stat(leaf).await.unwrap(), 12);
+        assert_eq!(mock.block_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn block_put_unknown_hash() {
+        let ipfs = MockIpfs::new().service();
+
+        let data = Bytes::from_static(b"Hello World");
+        // SHA2-512 is not supported.
+        let cid = Cid::new_v1(0x55, Multihash::wrap(0x13, &[0; 64]).unwrap());
+
+        assert!(matches!(
+            ipfs.block_put(cid, data).await,
+            Err(Error::UnknownHash(0x13))
+        ));
+    }
+
+    #[tokio::test]
+    async fn ipld_links() {
+        let ipfs = MockIpfs::new().service();
+
+        let comment = Comment {
+            text: String::from("Hello World"),
+            ..Default::default()
+        };
+
+        let comment_cid = ipfs
+            .dag_put(&comment, Codec::DagCbor, Codec::DagCbor)
+            .await
+            .unwrap();
+
+        let chunk = CommentChunk {
+            comments: vec![comment_cid.into()],
+        };
+
+        let chunk_cid = ipfs
+            .dag_put(&chunk, Codec::DagCbor, Codec::DagCbor)
+            .await
+            .unwrap();
+
+        let linked: Comment = ipfs
+            .dag_get(chunk_cid, Some("/comments/0"), Codec::DagCbor)
+            .await
+            .unwrap();
+        assert_eq!(linked, comment);
+
+        let text: String = ipfs
+            .dag_get(chunk_cid, Some("/comments/0/text"), Codec::DagCbor)
+            .await
+            .unwrap();
+        assert_eq!(text, "Hello World");
+
+        let stored: CommentChunk = ipfs
+            .dag_get(chunk_cid, Option::<&str>::None, Codec::DagCbor)
+            .await
+            .unwrap();
+        assert_eq!(stored, chunk);
+    }
+
+    #[tokio::test]
+    async fn pins_and_names() {
+        let mock = MockIpfs::new();
+        let ipfs = mock.service();
+
+        let old = ipfs
+            .dag_put(&1u8, Codec::DagCbor, Codec::DagCbor)
+            .await
+            .unwrap();
+        let new = ipfs
+            .dag_put(&2u8, Codec::DagCbor, Codec::DagCbor)
+            .await
+            .unwrap();
+
+        ipfs.pin_add(old, true).await.unwrap();
+        ipfs.pin_update(old, new).await.unwrap();
+
+        assert!(!mock.is_pinned(old));
+        assert!(mock.is_pinned(new));
+        assert!(ipfs.pin_rm(old, true).await.is_err());
+
+        let parent = ipfs
+            .dag_put(&Ipld::Link(new), Codec::DagCbor, Codec::DagCbor)
+            .await
+            .unwrap();
+
+        ipfs.pin_rm(new, true).await.unwrap();
+        assert!(!mock.is_kept(new));
+
+        ipfs.pin_add(parent, true).await.unwrap();
+        assert!(mock.is_kept(new));
+        assert!(!mock.is_pinned(new));
+
+        ipfs.pin_remote_add(old, "service").await.unwrap();
+        assert!(mock.is_pinned_remotely(old));
+
+        let key = ipfs.key_gen("channel").await.unwrap();
+        let addr = IPNSAddress::try_from(key.id).unwrap();
+
+        assert!(ipfs.key_gen("channel").await.is_err());
+        assert_eq!(ipfs.key_list().await.unwrap().get("channel"), Some(&addr));
+
+        assert!(matches!(ipfs.name_resolve(addr).await, Err(Error::Ipns)));
+
+        ipfs.name_publish(new, "channel").await.unwrap();
+
+        assert_eq!(ipfs.name_resolve(addr).await.unwrap(), new);
+
+        ipfs.key_rm("channel").await.unwrap();
+
+        assert!(ipfs.name_publish(old, "channel").await.is_err());
+        assert!(ipfs.peer_id().await.is_ok());
+    }
+}
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use crate::responses::PubSubMessage;
+
+/// Event of a subscription that resubscribes when the connection drops.
+pub enum PubSubEvent {
+    Message(PubSubMessage),
+
+    /// Messages may have been missed.
+    ///
+    /// The subscription was interrupted or record sequences were not consecutive.
+    Gap,
+}
+
+/// Delays between resubscription attempts, doubled after each failure.
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay to wait after this one.
+    pub fn next(&self, delay: Duration) -> Duration {
+        delay.saturating_mul(2).min(self.max).max(self.initial)
+    }
+}
+
+/// Track IPNS record sequences received over pubsub.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct SequenceTracker {
+    last: Option<u64>,
+}
+
+impl SequenceTracker {
+    /// Returns true if records were skipped since the highest sequence received.
+    ///
+    /// Older records are ignored.
+    pub fn is_gap(&mut self, sequence: u64) -> bool {
+        let gap = match self.last {
+            Some(last) if sequence <= last => return false,
+            Some(last) => sequence > last + 1,
+            None => false,
+        };
+
+        self.last = Some(sequence);
+
+        gap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let backoff = Backoff::default();
+
+        let mut delay = backoff.initial;
+
+        for _ in 0..10 {
+            delay = backoff.next(delay);
+        }
+
+        assert_eq!(delay, backoff.max);
+        assert_eq!(backoff.next(Duration::ZERO), backoff.initial);
+    }
+
+    #[test]
+    fn sequence_gaps() {
+        let mut tracker = SequenceTracker::default();
+
+        assert!(!tracker.is_gap(5));
+        assert!(!tracker.is_gap(6));
+        assert!(!tracker.is_gap(4));
+        assert!(tracker.is_gap(8));
+        assert!(!tracker.is_gap(9));
+    }
+}
@@ -14,6 +14,10 @@ use serde::{Deserialize, Serialize};
 pub struct AddResponse {
     #[serde(rename = "Hash")]
     pub hash: String,
+
+    /// Path of the file, empty for wrapping directories.
+    #[serde(rename = "Name", default)]
+    pub name: String,
 }
 
 impl TryFrom<AddResponse> for Cid {
@@ -24,6 +28,34 @@ impl TryFrom<AddResponse> for Cid {
     }
 }
 
+/// Line of an add request with progress enabled.
+///
+/// Progress lines have bytes, the last line has the hash.
+#[derive(Debug, Deserialize)]
+pub struct AddProgressResponse {
+    #[serde(rename = "Name")]
+    pub name: String,
+
+    #[serde(rename = "Bytes", default)]
+    pub bytes: Option<u64>,
+
+    #[serde(rename = "Hash", default)]
+    pub hash: Option<String>,
+}
+
+/// Progress of a file being added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddProgress {
+    /// Bytes chunked so far.
+    pub bytes: u64,
+
+    /// Blocks chunked so far, the last one may be smaller than the chunk size.
+    pub blocks: u64,
+
+    /// CID of the file, only known once added.
+    pub cid: Option<Cid>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PubsubSubResponse {
     pub from: String,
@@ -48,6 +80,35 @@ impl TryFrom<PubsubSubResponse> for PubSubMessage {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RefsResponse {
+    #[serde(rename = "Ref")]
+    pub cid: String,
+
+    #[serde(rename = "Err")]
+    pub error: String,
+}
+
+/// One line of the daemon event log.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogEntry {
+    pub ts: String,
+    pub level: String,
+    pub logger: String,
+    pub caller: String,
+    pub msg: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlockStatResponse {
+    #[serde(rename = "Key")]
+    pub key: String,
+
+    #[serde(rename = "Size")]
+    pub size: u64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct DagPutResponse {
     #[serde(rename = "Cid")]
@@ -158,6 +219,18 @@ pub struct PinRmResponse {
     pub pins: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PinRemoteAddResponse {
+    #[serde(rename = "Cid")]
+    pub cid: String,
+
+    #[serde(rename = "Name")]
+    pub name: String,
+
+    #[serde(rename = "Status")]
+    pub status: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PinLsResponse {
     #[serde(rename = "Keys")]
@@ -226,6 +299,17 @@ impl Default for Codec {
     }
 }
 
+/// Multihash function used to address new blocks.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Default, EnumString)]
+pub enum Hash {
+    #[default]
+    #[strum(serialize = "sha2-256")]
+    Sha2_256 = 0x12,
+
+    #[strum(serialize = "blake3")]
+    Blake3 = 0x1e,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct DHTPutResponse {
     #[serde(rename = "Extra")]
@@ -249,3 +333,91 @@ pub struct Response {
     #[serde(rename = "ID")]
     pub id: String,
 }
+
+/// Peer providing some content.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct Provider {
+    #[serde(rename = "Schema", default)]
+    pub schema: String,
+
+    #[serde(rename = "ID")]
+    pub id: String,
+
+    #[serde(rename = "Addrs", default)]
+    pub addrs: Vec<String>,
+
+    #[serde(rename = "Protocols", default)]
+    pub protocols: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProvidersResponse {
+    #[serde(rename = "Providers", default)]
+    pub providers: Vec<Provider>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FilesLsResponse {
+    /// Null for empty directories.
+    #[serde(rename = "Entries", default)]
+    pub entries: Option<Vec<FilesEntry>>,
+}
+
+/// File or directory in the mutable file system.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct FilesEntry {
+    #[serde(rename = "Name")]
+    pub name: String,
+
+    /// 0 for files, 1 for directories.
+    #[serde(rename = "Type")]
+    pub entry_type: u8,
+
+    #[serde(rename = "Size")]
+    pub size: u64,
+
+    #[serde(rename = "Hash")]
+    pub hash: String,
+}
+
+impl FilesEntry {
+    pub fn is_directory(&self) -> bool {
+        self.entry_type == 1
+    }
+}
+
+#[derive(Debug, Display, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum FileType {
+    File,
+    Directory,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct FilesStatResponse {
+    #[serde(rename = "Hash")]
+    pub hash: String,
+
+    /// Size of the file, zero for directories.
+    #[serde(rename = "Size")]
+    pub size: u64,
+
+    /// Size of all the blocks, children included.
+    #[serde(rename = "CumulativeSize")]
+    pub cumulative_size: u64,
+
+    #[serde(rename = "Blocks")]
+    pub blocks: u64,
+
+    #[serde(rename = "Type")]
+    pub file_type: FileType,
+}
+
+impl TryFrom<FilesStatResponse> for Cid {
+    type Error = cid::Error;
+
+    fn try_from(response: FilesStatResponse) -> Result<Self, Self::Error> {
+        Cid::try_from(response.hash)
+    }
+}
@@ -0,0 +1,110 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::pubsub::Backoff;
+
+/// Attempts and timeouts of requests to the IPFS API.
+///
+/// Requests are retried only when no response was received,
+/// errors returned by the daemon are not retried.
+/// Timeouts apply until the response starts, streamed bodies are not bounded.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts per request, the first one included.
+    pub attempts: u32,
+
+    /// Delays between attempts, doubled after each failure.
+    pub backoff: Backoff,
+
+    /// Time allowed to each attempt. None means no limit.
+    pub timeout: Option<Duration>,
+
+    /// Timeouts of specific endpoints, eg. ```name/resolve```, instead of the default one.
+    pub endpoints: HashMap<String, Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            backoff: Backoff {
+                initial: Duration::from_millis(500),
+                max: Duration::from_secs(10),
+            },
+            timeout: None,
+            endpoints: HashMap::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Time allowed to each attempt on endpoints without their own timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Time allowed to each attempt on this endpoint, eg. ```name/resolve```.
+    pub fn with_endpoint_timeout(mut self, endpoint: &str, timeout: Duration) -> Self {
+        self.endpoints.insert(endpoint.to_owned(), timeout);
+        self
+    }
+
+    /// Returns the time allowed to each attempt on this endpoint, if limited.
+    pub fn timeout(&self, endpoint: &str) -> Option<Duration> {
+        self.endpoints.get(endpoint).copied().or(self.timeout)
+    }
+
+    /// Returns the delay before the next attempt, if any are left.
+    pub(crate) fn next_delay(&self, attempt: u32, delay: Option<Duration>) -> Option<Duration> {
+        if attempt >= self.attempts {
+            return None;
+        }
+
+        let delay = match delay {
+            Some(delay) => self.backoff.next(delay),
+            None => self.backoff.initial,
+        };
+
+        Some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy() {
+        let policy = RetryPolicy::default()
+            .with_attempts(3)
+            .with_timeout(Duration::from_secs(10))
+            .with_endpoint_timeout("name/resolve", Duration::from_secs(30));
+
+        assert_eq!(policy.timeout("dag/get"), Some(Duration::from_secs(10)));
+        assert_eq!(
+            policy.timeout("name/resolve"),
+            Some(Duration::from_secs(30))
+        );
+
+        let first = policy.next_delay(1, None);
+        assert_eq!(first, Some(policy.backoff.initial));
+
+        let second = policy.next_delay(2, first);
+        assert_eq!(second, Some(policy.backoff.initial * 2));
+
+        assert_eq!(policy.next_delay(3, second), None);
+
+        assert_eq!(RetryPolicy::default().next_delay(1, None), None);
+        assert_eq!(RetryPolicy::default().timeout("add"), None);
+    }
+}
@@ -0,0 +1,116 @@
+use std::{sync::Arc, time::Duration};
+
+use bytes::Bytes;
+
+use cid::Cid;
+
+use ipns_records::IPNSRecord;
+
+use linked_data::types::IPNSAddress;
+
+use reqwest::{header::ACCEPT, Client, RequestBuilder, StatusCode, Url};
+
+use crate::{
+    errors::Error,
+    responses::{Provider, ProvidersResponse},
+};
+
+const IPNS_RECORD: &str = "application/vnd.ipfs.ipns-record";
+const JSON: &str = "application/json";
+
+/// Client for delegated routing HTTP endpoints. (/routing/v1)
+///
+/// Find providers and resolve IPNS without running a node.
+#[derive(Clone)]
+pub struct DelegatedRouting {
+    client: Client,
+    base_url: Arc<Url>,
+}
+
+impl DelegatedRouting {
+    /// Url of the endpoint, without the /routing/v1 path.
+    pub fn new(url: &str) -> Result<Self, Error> {
+        let base_url = Url::parse(url)?;
+        let base_url = Arc::from(base_url);
+
+        let client = Client::new();
+
+        Ok(Self { client, base_url })
+    }
+
+    /// Returns the raw IPNS record of this address.
+    ///
+    /// Records are not verified and timeouts are ignored in browsers.
+    pub async fn ipns_get(
+        &self,
+        addr: IPNSAddress,
+        timeout: Option<Duration>,
+    ) -> Result<Bytes, Error> {
+        let url = self.base_url.join(&format!("routing/v1/ipns/{}", addr))?;
+
+        let request = self.client.get(url).header(ACCEPT, IPNS_RECORD);
+
+        let bytes = with_timeout(request, timeout)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        Ok(bytes)
+    }
+
+    /// Resolve IPNS name, the record is verified against the address and expired records rejected.
+    pub async fn name_resolve(
+        &self,
+        addr: IPNSAddress,
+        timeout: Option<Duration>,
+    ) -> Result<Cid, Error> {
+        let bytes = self.ipns_get(addr, timeout).await?;
+
+        let record = IPNSRecord::from_bytes(&bytes)?;
+
+        record.verify(addr.into())?;
+
+        if record.is_expired()? {
+            return Err(ipns_records::Error::Expired.into());
+        }
+
+        Ok(record.get_value())
+    }
+
+    /// Returns the peers providing this CID, if any.
+    pub async fn find_providers(
+        &self,
+        cid: Cid,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Provider>, Error> {
+        let url = self
+            .base_url
+            .join(&format!("routing/v1/providers/{}", cid))?;
+
+        let request = self.client.get(url).header(ACCEPT, JSON);
+
+        let response = with_timeout(request, timeout).send().await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+
+        let bytes = response.error_for_status()?.bytes().await?;
+
+        let res = serde_json::from_slice::<ProvidersResponse>(&bytes)?;
+
+        Ok(res.providers)
+    }
+}
+
+#[allow(unused_variables)]
+fn with_timeout(request: RequestBuilder, timeout: Option<Duration>) -> RequestBuilder {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(timeout) = timeout {
+        return request.timeout(timeout);
+    }
+
+    request
+}
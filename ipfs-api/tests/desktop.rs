@@ -6,9 +6,12 @@ mod tests {
     use cid::Cid;
     use futures_util::{future::FutureExt, stream, StreamExt};
     use ipfs_api::{
-        responses::{Codec, PinMode},
+        errors::Error,
+        responses::{Codec, FileType, PinMode},
+        retry::RetryPolicy,
         IpfsService,
     };
+    use std::time::Duration;
 
     const PEER_ID: &str = "12D3KooWRsEKtLGLW9FHw7t7dDhHrMDahw3VwssNgh55vksdvfmC";
 
@@ -152,6 +155,124 @@ mod tests {
         assert_eq!(b"Hello World!", &data[0..12])
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore]
+    async fn files_roundtrip() {
+        let ipfs = IpfsService::default();
+
+        ipfs.files_write("/test/hello.txt", Bytes::from_static(b"Hello World!"), true)
+            .await
+            .unwrap();
+
+        let data = ipfs.files_read("/test/hello.txt").await.unwrap();
+
+        assert_eq!(b"Hello World!", &data[..]);
+
+        let stat = ipfs.files_stat("/test/hello.txt").await.unwrap();
+
+        assert_eq!(stat.file_type, FileType::File);
+        assert_eq!(stat.size, 12);
+
+        let cid = Cid::try_from(stat).unwrap();
+
+        ipfs.files_cp(format!("/ipfs/{}", cid), "/test/copy.txt", false)
+            .await
+            .unwrap();
+
+        let entries = ipfs.files_ls("/test").await.unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.hash == cid.to_string()));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore]
+    async fn block_roundtrip() {
+        let ipfs = IpfsService::default();
+
+        let node = TestBlock {
+            data: String::from("Hello World!"),
+        };
+
+        let data = Bytes::from(serde_ipld_dagcbor::to_vec(&node).unwrap());
+
+        let cid = ipfs
+            .block_put_encoded(data.clone(), Codec::DagCbor)
+            .await
+            .unwrap();
+
+        assert_eq!(cid.codec(), Codec::DagCbor as u64);
+
+        let block = ipfs.block_get(cid).await.unwrap();
+
+        assert_eq!(block, data);
+
+        let size = ipfs.block_stat(cid).await.unwrap();
+
+        assert_eq!(size, data.len() as u64);
+    }
+
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    struct TestSigned {
+        signature: Vec<u8>,
+        nonce: u64,
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore]
+    async fn dag_put_cbor_roundtrip() {
+        let ipfs = IpfsService::default();
+
+        let node = TestSigned {
+            signature: vec![0xff; 64],
+            nonce: u64::MAX,
+        };
+
+        let cid = ipfs
+            .dag_put(&node, Codec::DagCbor, Codec::DagCbor)
+            .await
+            .unwrap();
+
+        let new_node: TestSigned = ipfs
+            .dag_get(cid, Option::<&str>::None, Codec::DagCbor)
+            .await
+            .unwrap();
+
+        assert_eq!(node, new_node)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn unresponsive_timeout() {
+        // Accept connections but never respond.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let retry = RetryPolicy::default()
+            .with_attempts(2)
+            .with_endpoint_timeout("id", Duration::from_millis(200));
+
+        let ipfs = IpfsService::new(&format!("http://{}/api/v0/", addr))
+            .unwrap()
+            .with_retry(retry);
+
+        match ipfs.peer_id().await {
+            Err(Error::Timeout { endpoint, attempts }) => {
+                assert_eq!(endpoint, "id");
+                assert_eq!(attempts, 2);
+            }
+            Err(e) => panic!("{}", e),
+            Ok(_) => panic!("Response from an unresponsive endpoint"),
+        }
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
     #[ignore]
     async fn pin_ls() {
@@ -16,6 +16,27 @@ pub enum Error {
     #[error("Record & document information must match")]
     DataMismatch,
 
+    #[error("Sequence {sequence} must be greater than last used sequence {last}")]
+    SequenceRegression { last: u64, sequence: u64 },
+
+    #[error("Sequence number overflow")]
+    SequenceOverflow,
+
+    #[error("Record validity has expired")]
+    Expired,
+
+    #[error("UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+
+    #[error("Date Time: {0}")]
+    ParseDateTime(#[from] chrono::ParseError),
+
+    #[error("IO: {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error("Parse: {0}")]
+    ParseInt(#[from] std::num::ParseIntError),
+
     #[error("Protobuf: {0}")]
     Decode(#[from] prost::DecodeError),
 
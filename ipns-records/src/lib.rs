@@ -1,4 +1,5 @@
 mod errors;
+mod sequence;
 mod tests;
 mod traits;
 
@@ -14,9 +15,13 @@ use signature::SignatureEncoding;
 
 pub use traits::{AsyncRecordSigner, RecordSigner};
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use sequence::FileSequenceStore;
+pub use sequence::{MemorySequenceStore, SequenceStore};
+
 use std::ops::Add;
 
-use chrono::{Duration, SecondsFormat, Utc};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
 
 use cid::Cid;
 
@@ -73,6 +78,61 @@ impl CryptoKey {
             .into_vec();
         CryptoKey { r#type, data }
     }
+
+    pub fn new_rsa(public_key: &rsa::RsaPublicKey) -> Self {
+        use elliptic_curve::pkcs8::EncodePublicKey;
+
+        let r#type = KeyType::RSA as i32;
+        let data = public_key
+            .to_public_key_der()
+            .expect("Valid document")
+            .into_vec();
+        CryptoKey { r#type, data }
+    }
+
+    /// Return the IPNS address derived from this key.
+    ///
+    /// Keys 42 bytes or less are inlined using the identity hash.
+    pub fn to_address(&self) -> Cid {
+        let public_key = self.encode_to_vec(); // Protobuf encoding
+
+        let code = if public_key.len() <= 42 {
+            /* Identity */
+            0x00
+        } else {
+            /* Sha256 */
+            0x12
+        };
+
+        key_address(&public_key, code).expect("Valid Multihash")
+    }
+
+    /// Return the IPNS address derived from this key with this multihash function.
+    ///
+    /// None if the function is not supported.
+    pub fn to_address_with(&self, code: u64) -> Option<Cid> {
+        key_address(&self.encode_to_vec(), code)
+    }
+}
+
+/// Hash a protobuf encoded public key into an IPNS address.
+///
+/// Identity, SHA2-256 and BLAKE3 are supported.
+pub fn key_address(public_key: &[u8], code: u64) -> Option<Cid> {
+    let multihash = match code {
+        /* Identity */ 0x00 => Multihash::wrap(code, public_key).ok()?,
+        /* Sha256 */
+        0x12 => {
+            let hash = Sha256::new_with_prefix(public_key).finalize();
+
+            Multihash::wrap(code, &hash).ok()?
+        }
+        /* Blake3 */
+        0x1e => Multihash::wrap(code, blake3::hash(public_key).as_bytes()).ok()?,
+        _ => return None,
+    };
+
+    Some(Cid::new_v1(/* Libp2p key */ 0x72, multihash))
 }
 
 /// Validity type only valid if EOL.
@@ -158,19 +218,39 @@ impl IPNSRecord {
         self.sequence
     }
 
+    /// Return the time to live of this record in nanoseconds.
+    pub fn get_ttl(&self) -> u64 {
+        self.ttl
+    }
+
+    /// Return the date after which this record is no longer valid.
+    pub fn get_validity(&self) -> Result<DateTime<Utc>, Error> {
+        let text = std::str::from_utf8(&self.validity)?;
+
+        let date_time = DateTime::parse_from_rfc3339(text)?;
+
+        Ok(date_time.with_timezone(&Utc))
+    }
+
+    /// Return true if the validity of this record has passed.
+    pub fn is_expired(&self) -> Result<bool, Error> {
+        Ok(self.get_validity()? < Utc::now())
+    }
+
     /// Return the IPNS address of this record.
     ///
     /// Public key less than 42 bytes are store as IPNS address digest
     pub fn get_address(&self) -> Option<Cid> {
+        self.get_address_with(/* Sha256 */ 0x12)
+    }
+
+    /// Return the IPNS address of this record hashed with this multihash function.
+    pub fn get_address_with(&self, code: u64) -> Option<Cid> {
         if self.pub_key.is_empty() {
             return None;
         }
 
-        let hash = Sha256::new_with_prefix(&self.pub_key).finalize();
-        let multihash = Multihash::wrap(/* Sha256 */ 0x12, &hash).expect("Valid Multihash");
-        let cid = Cid::new_v1(/* Libp2p key */ 0x72, multihash);
-
-        Some(cid)
+        key_address(&self.pub_key, code)
     }
 
     /// Return an error if this record is not valid for the specified IPNS address.
@@ -188,13 +268,10 @@ impl IPNSRecord {
         let data = if self.pub_key.is_empty() {
             ipns_addr.hash().digest()
         } else {
-            let addr = {
-                let hash = Sha256::new_with_prefix(&self.pub_key).finalize();
-                let multihash = Multihash::wrap(/* Sha256 */ 0x12, &hash).expect("Valid Multihash");
-                Cid::new_v1(/* Libp2p key */ 0x72, multihash)
-            };
+            // The address decides which hash function was used.
+            let addr = key_address(&self.pub_key, ipns_addr.hash().code());
 
-            if addr != ipns_addr {
+            if addr != Some(ipns_addr) {
                 return Err(Error::AddressMismatch);
             }
 
@@ -223,7 +300,16 @@ impl IPNSRecord {
         signing_input_v2.extend(self.data.iter());
 
         match crypto_key.r#type() {
-            KeyType::RSA => unimplemented!(),
+            KeyType::RSA => {
+                use rsa::pkcs1v15::{Signature, VerifyingKey};
+                use rsa::RsaPublicKey;
+
+                let public_key = RsaPublicKey::from_public_key_der(&crypto_key.data)?;
+                let verif_key = VerifyingKey::<Sha256>::new(public_key);
+                let signature = Signature::try_from(self.signature_v2.as_slice())?;
+
+                verif_key.verify(&signing_input_v2, &signature)?;
+            }
             KeyType::Ed25519 => {
                 use ed25519::Signature;
                 use ed25519_dalek::VerifyingKey;
@@ -408,62 +494,78 @@ impl IPNSRecord {
         })
     }
 
-    //TODO add a new feature "web" for the logic below
+    /// Create a new IPNS record using the next sequence number for the signer's address.
+    ///
+    /// The sequence is only persisted once the record is created.
+    pub fn new_sequenced<S, U, T>(
+        cid: Cid,
+        valid_for: Duration,
+        ttl: u64,
+        signer: S,
+        store: &T,
+    ) -> Result<Self, Error>
+    where
+        S: RecordSigner<U>,
+        U: SignatureEncoding,
+        T: SequenceStore + ?Sized,
+    {
+        let addr = signer.crypto_key().to_address();
+        let sequence = store.next_sequence(addr)?;
 
-    pub fn signing_input_v1(cid: Cid, valid_for: Duration) -> Vec<u8> {
-        let value = format!("/ipfs/{}", cid.to_string()).into_bytes();
+        let record = Self::new(cid, valid_for, sequence, ttl, signer)?;
 
-        let validity = Utc::now()
-            .add(valid_for)
-            .to_rfc3339_opts(SecondsFormat::Nanos, false)
-            .into_bytes();
+        store.set_sequence(addr, sequence)?;
 
-        let validity_type = ValidityType::EOL;
+        Ok(record)
+    }
 
-        let signing_input_v1 = {
-            let mut data = Vec::with_capacity(
-                value.len() + validity.len() + 3, /* b"EOL".len() == 3 */
-            );
+    /// Create a new IPNS record using the next sequence number for the signer's address.
+    ///
+    /// The sequence is only persisted once the record is created.
+    pub async fn async_new_sequenced<S, U, T>(
+        cid: Cid,
+        valid_for: Duration,
+        ttl: u64,
+        signer: S,
+        store: &T,
+    ) -> Result<Self, Error>
+    where
+        S: AsyncRecordSigner<U>,
+        U: SignatureEncoding + Send + 'static,
+        T: SequenceStore + ?Sized,
+    {
+        let addr = signer.crypto_key().await.to_address();
+        let sequence = store.next_sequence(addr)?;
 
-            data.extend(value.iter());
-            data.extend(validity.iter());
-            data.extend(validity_type.to_string().as_bytes());
+        let record = Self::async_new(cid, valid_for, sequence, ttl, signer).await?;
 
-            data
-        };
+        store.set_sequence(addr, sequence)?;
 
-        signing_input_v1
+        Ok(record)
     }
 
-    pub fn signing_input_v2(cid: Cid, valid_for: Duration, sequence: u64, ttl: u64) -> Vec<u8> {
-        let value = format!("/ipfs/{}", cid.to_string()).into_bytes();
-
-        let validity = Utc::now()
-            .add(valid_for)
-            .to_rfc3339_opts(SecondsFormat::Nanos, false)
-            .into_bytes();
-
-        let validity_type = ValidityType::EOL;
-
-        let document = DagCborDocument {
-            value,
-            validity_type,
-            validity,
-            sequence,
-            ttl,
-        };
+    /// Return an error if this record would not supersede the last one published for this address.
+    ///
+    /// On success the record sequence becomes the last one used.
+    pub fn commit_sequence<T>(&self, ipns_addr: Cid, store: &T) -> Result<(), Error>
+    where
+        T: SequenceStore + ?Sized,
+    {
+        store.check_sequence(ipns_addr, self.sequence)?;
 
-        let data = serde_ipld_dagcbor::to_vec(&document).expect("Valid Dag Cbor");
+        store.set_sequence(ipns_addr, self.sequence)
+    }
 
-        //prefix
-        let mut signing_input_v2: Vec<u8> = vec![
-            0x69, 0x70, 0x6e, 0x73, 0x2d, 0x73, 0x69, 0x67, 0x6e, 0x61, 0x74, 0x75, 0x72, 0x65,
-            0x3a,
-        ];
+    //TODO add a new feature "web" for the logic below
 
-        signing_input_v2.extend(data.iter());
+    /// WARNING! The validity is computed on every call, use `UnsignedRecord` instead.
+    pub fn signing_input_v1(cid: Cid, valid_for: Duration) -> Vec<u8> {
+        UnsignedRecord::new(cid, valid_for, 0, 0).signing_input_v1()
+    }
 
-        signing_input_v2
+    /// WARNING! The validity is computed on every call, use `UnsignedRecord` instead.
+    pub fn signing_input_v2(cid: Cid, valid_for: Duration, sequence: u64, ttl: u64) -> Vec<u8> {
+        UnsignedRecord::new(cid, valid_for, sequence, ttl).signing_input_v2()
     }
 
     /// Create a Record from pre-computed signatures.
@@ -480,35 +582,115 @@ impl IPNSRecord {
         signature_v1: Vec<u8>,
         signature_v2: Vec<u8>,
     ) -> Result<Self, Error> {
-        let value = format!("/ipfs/{}", cid.to_string()).into_bytes();
+        UnsignedRecord::new(cid, valid_for, sequence, ttl).into_record(
+            public_key,
+            signature_v1,
+            signature_v2,
+        )
+    }
+
+    /// Protobuf encoded record, ready for the DHT or pubsub.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode_to_vec()
+    }
+}
+
+/// IPNS record waiting for its signatures.
+///
+/// The validity is fixed at creation, both signing inputs then match the assembled record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsignedRecord {
+    value: Vec<u8>,
+    validity: Vec<u8>,
+    sequence: u64,
+    ttl: u64,
+}
+
+impl UnsignedRecord {
+    pub fn new(cid: Cid, valid_for: Duration, sequence: u64, ttl: u64) -> Self {
+        let value = format!("/ipfs/{}", cid).into_bytes();
 
         let validity = Utc::now()
             .add(valid_for)
             .to_rfc3339_opts(SecondsFormat::Nanos, false)
             .into_bytes();
 
+        Self {
+            value,
+            validity,
+            sequence,
+            ttl,
+        }
+    }
+
+    pub fn signing_input_v1(&self) -> Vec<u8> {
         let validity_type = ValidityType::EOL;
 
-        let mut pub_key = public_key.encode_to_vec();
+        let mut data = Vec::with_capacity(
+            self.value.len() + self.validity.len() + 3, /* b"EOL".len() == 3 */
+        );
+
+        data.extend(self.value.iter());
+        data.extend(self.validity.iter());
+        data.extend(validity_type.to_string().as_bytes());
+
+        data
+    }
+
+    pub fn signing_input_v2(&self) -> Vec<u8> {
+        let data = self.document().expect("Valid Dag Cbor");
+
+        //prefix
+        let mut signing_input_v2: Vec<u8> = vec![
+            0x69, 0x70, 0x6e, 0x73, 0x2d, 0x73, 0x69, 0x67, 0x6e, 0x61, 0x74, 0x75, 0x72, 0x65,
+            0x3a,
+        ];
+
+        signing_input_v2.extend(data.iter());
+
+        signing_input_v2
+    }
+
+    fn document(&self) -> Result<Vec<u8>, Error> {
+        let document = DagCborDocument {
+            value: self.value.clone(),
+            validity_type: ValidityType::EOL,
+            validity: self.validity.clone(),
+            sequence: self.sequence,
+            ttl: self.ttl,
+        };
+
+        let data = serde_ipld_dagcbor::to_vec(&document)?;
+
+        Ok(data)
+    }
+
+    /// Assemble the record with signatures of both signing inputs.
+    pub fn into_record(
+        self,
+        public_key: CryptoKey,
+        signature_v1: Vec<u8>,
+        signature_v2: Vec<u8>,
+    ) -> Result<IPNSRecord, Error> {
+        let data = self.document()?;
+
+        let mut pub_key = public_key.encode_to_vec(); // Protobuf encoding
 
         if pub_key.len() <= 42 {
             pub_key.clear();
         }
 
-        let document = DagCborDocument {
-            value: value.clone(),
-            validity_type,
-            validity: validity.clone(),
+        let Self {
+            value,
+            validity,
             sequence,
             ttl,
-        };
-
-        let data = serde_ipld_dagcbor::to_vec(&document)?;
+        } = self;
 
-        Ok(Self {
+        Ok(IPNSRecord {
             value,
             signature_v1,
-            validity_type: validity_type as i32,
+            validity_type: ValidityType::EOL as i32,
             validity,
             sequence,
             ttl,
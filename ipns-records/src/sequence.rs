@@ -0,0 +1,114 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use cid::Cid;
+
+use crate::Error;
+
+/// Persistent storage of the last sequence number used per IPNS address.
+///
+/// Followers ignore records with a sequence lower or equal to the one they already have,
+/// reusing a stale sequence is the same as not publishing at all.
+pub trait SequenceStore {
+    /// Return the last sequence number used for this address, if any.
+    fn last_sequence(&self, addr: Cid) -> Result<Option<u64>, Error>;
+
+    /// Persist the sequence number used for this address.
+    fn set_sequence(&self, addr: Cid, sequence: u64) -> Result<(), Error>;
+
+    /// Return the next sequence number to use for this address.
+    fn next_sequence(&self, addr: Cid) -> Result<u64, Error> {
+        match self.last_sequence(addr)? {
+            Some(last) => last.checked_add(1).ok_or(Error::SequenceOverflow),
+            None => Ok(0),
+        }
+    }
+
+    /// Return an error if this sequence number would not supersede the last one used.
+    fn check_sequence(&self, addr: Cid, sequence: u64) -> Result<(), Error> {
+        match self.last_sequence(addr)? {
+            Some(last) if sequence <= last => Err(Error::SequenceRegression { last, sequence }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record a sequence number seen elsewhere (DHT, pubsub, other node) for this address.
+    ///
+    /// The stored sequence is only ever raised, never lowered.
+    fn observe(&self, addr: Cid, sequence: u64) -> Result<(), Error> {
+        match self.last_sequence(addr)? {
+            Some(last) if last >= sequence => Ok(()),
+            _ => self.set_sequence(addr, sequence),
+        }
+    }
+}
+
+/// In memory sequence store, state is lost when dropped.
+#[derive(Debug, Default)]
+pub struct MemorySequenceStore {
+    map: Mutex<HashMap<Cid, u64>>,
+}
+
+impl SequenceStore for MemorySequenceStore {
+    fn last_sequence(&self, addr: Cid) -> Result<Option<u64>, Error> {
+        let map = self.map.lock().expect("Lock Poisoned");
+
+        Ok(map.get(&addr).copied())
+    }
+
+    fn set_sequence(&self, addr: Cid, sequence: u64) -> Result<(), Error> {
+        let mut map = self.map.lock().expect("Lock Poisoned");
+
+        map.insert(addr, sequence);
+
+        Ok(())
+    }
+}
+
+/// Sequence store backed by a directory, one file per IPNS address.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct FileSequenceStore {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileSequenceStore {
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> Result<Self, Error> {
+        let dir = dir.into();
+
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self { dir })
+    }
+
+    fn path(&self, addr: Cid) -> std::path::PathBuf {
+        self.dir.join(addr.to_string())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SequenceStore for FileSequenceStore {
+    fn last_sequence(&self, addr: Cid) -> Result<Option<u64>, Error> {
+        let text = match std::fs::read_to_string(self.path(addr)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let sequence = text.trim().parse::<u64>()?;
+
+        Ok(Some(sequence))
+    }
+
+    fn set_sequence(&self, addr: Cid, sequence: u64) -> Result<(), Error> {
+        let path = self.path(addr);
+
+        // Write then rename so that a crash never leaves a truncated file behind.
+        let temp = path.with_extension("tmp");
+
+        std::fs::write(&temp, sequence.to_string())?;
+        std::fs::rename(temp, path)?;
+
+        Ok(())
+    }
+}
@@ -157,6 +157,40 @@ fn secp256k1_roundtrip() {
     assert!(result.is_ok())
 }
 
+#[test]
+fn unsigned_roundtrip() {
+    let value =
+        Cid::try_from("bafyreih223c6mqauz5ouolokqrofaekpuu45eblm33fm3g2rlwdkqfabo4").unwrap();
+
+    use rand_core::OsRng;
+    let mut csprng = OsRng {};
+    let signing_key = k256::ecdsa::SigningKey::random(&mut csprng);
+
+    let crypto_key = CryptoKey::new_k256(signing_key.verifying_key());
+    let addr = crypto_key.to_address();
+
+    let unsigned = UnsignedRecord::new(value, Duration::days(30), 1, 0);
+
+    // Signatures are requested one after the other, like a wallet would.
+    let sig: k256::ecdsa::Signature = signing_key.sign(&unsigned.signing_input_v1());
+    let signature_v1 = sig.to_der().as_bytes().to_vec();
+
+    std::thread::sleep(std::time::Duration::from_millis(10));
+
+    let sig: k256::ecdsa::Signature = signing_key.sign(&unsigned.signing_input_v2());
+    let signature_v2 = sig.to_der().as_bytes().to_vec();
+
+    let record = unsigned
+        .into_record(crypto_key, signature_v1, signature_v2)
+        .unwrap();
+
+    let record = IPNSRecord::from_bytes(&record.to_bytes()).unwrap();
+
+    assert_eq!(record.get_value(), value);
+    assert_eq!(record.get_sequence(), 1);
+    assert!(record.verify(addr).is_ok());
+}
+
 #[derive(Debug, Signer)]
 pub struct EcdsaSigner {
     signing_key: p256::ecdsa::SigningKey,
@@ -233,3 +267,121 @@ fn ecdsa_roundtrip() {
 
     assert!(result.is_ok())
 }
+
+#[test]
+fn rsa_roundtrip() {
+    let value =
+        Cid::try_from("bafyreih223c6mqauz5ouolokqrofaekpuu45eblm33fm3g2rlwdkqfabo4").unwrap();
+
+    use rand_core::OsRng;
+    let mut csprng = OsRng {};
+
+    // Small key, only to keep the test fast.
+    let private_key = rsa::RsaPrivateKey::new(&mut csprng, 1024).unwrap();
+    let signer = rsa::pkcs1v15::SigningKey::<Sha256>::new(private_key);
+
+    let addr = signer.crypto_key().to_address();
+
+    assert_eq!(addr.hash().code(), /* Sha256 */ 0x12);
+
+    let record = IPNSRecord::new(value, Duration::days(30), 0, 0, signer).unwrap();
+
+    let record = IPNSRecord::from_bytes(&record.to_bytes()).unwrap();
+
+    assert_eq!(record.get_address(), Some(addr));
+    assert!(record.verify(addr).is_ok());
+
+    let other = rsa::RsaPrivateKey::new(&mut csprng, 1024).unwrap();
+    let other = CryptoKey::new_rsa(&other.to_public_key()).to_address();
+
+    assert!(matches!(record.verify(other), Err(Error::AddressMismatch)));
+}
+
+#[test]
+fn blake3_address() {
+    let value =
+        Cid::try_from("bafyreih223c6mqauz5ouolokqrofaekpuu45eblm33fm3g2rlwdkqfabo4").unwrap();
+
+    use rand_core::OsRng;
+    let mut csprng = OsRng {};
+    let signing_key = p256::ecdsa::SigningKey::random(&mut csprng);
+
+    let signer = EcdsaSigner { signing_key };
+
+    let sha256_addr = signer.crypto_key().to_address();
+    let blake3_addr = signer
+        .crypto_key()
+        .to_address_with(/* Blake3 */ 0x1e)
+        .unwrap();
+
+    assert_eq!(blake3_addr.hash().code(), 0x1e);
+    assert_ne!(sha256_addr, blake3_addr);
+    assert_eq!(signer.crypto_key().to_address_with(0x13), None);
+
+    let record = IPNSRecord::new(value, Duration::days(30), 0, 0, signer).unwrap();
+
+    assert_eq!(record.get_address(), Some(sha256_addr));
+    assert_eq!(record.get_address_with(0x1e), Some(blake3_addr));
+
+    assert!(record.verify(sha256_addr).is_ok());
+    assert!(record.verify(blake3_addr).is_ok());
+
+    let other = p256::ecdsa::SigningKey::random(&mut csprng);
+    let other = EcdsaSigner { signing_key: other }
+        .crypto_key()
+        .to_address_with(0x1e)
+        .unwrap();
+
+    assert!(matches!(record.verify(other), Err(Error::AddressMismatch)));
+}
+
+#[test]
+fn sequence_monotonicity() {
+    let value =
+        Cid::try_from("bafyreih223c6mqauz5ouolokqrofaekpuu45eblm33fm3g2rlwdkqfabo4").unwrap();
+
+    let duration = Duration::days(30);
+
+    let ttl = 0;
+
+    use rand_core::OsRng;
+    let mut csprng = OsRng {};
+    let signing_key = ed25519_dalek::SigningKey::generate(&mut csprng);
+
+    let store = MemorySequenceStore::default();
+
+    let signer = Ed25519IPNSRecordSigner {
+        signing_key: signing_key.clone(),
+    };
+    let addr = signer.crypto_key().to_address();
+
+    let first = IPNSRecord::new_sequenced(value, duration, ttl, signer, &store).unwrap();
+
+    assert_eq!(first.get_sequence(), 0);
+    assert!(first.verify(addr).is_ok());
+
+    let signer = Ed25519IPNSRecordSigner {
+        signing_key: signing_key.clone(),
+    };
+
+    let second = IPNSRecord::new_sequenced(value, duration, ttl, signer, &store).unwrap();
+
+    assert_eq!(second.get_sequence(), 1);
+
+    // Reusing a stale sequence must be refused.
+    let result = first.commit_sequence(addr, &store);
+
+    assert!(matches!(
+        result,
+        Err(Error::SequenceRegression {
+            last: 1,
+            sequence: 0
+        })
+    ));
+
+    // Sequences seen elsewhere are never lowered.
+    store.observe(addr, 10).unwrap();
+    store.observe(addr, 5).unwrap();
+
+    assert_eq!(store.next_sequence(addr).unwrap(), 11);
+}
@@ -4,6 +4,10 @@ use signature::{SignatureEncoding, Signer};
 
 use async_signature::AsyncSigner;
 
+use rsa::pkcs1v15::{Signature, SigningKey};
+
+use sha2::Sha256;
+
 use crate::CryptoKey;
 
 /// Impl'd the trait is not enough to create valid records.
@@ -22,6 +26,13 @@ where
     fn crypto_key(&self) -> CryptoKey;
 }
 
+/// PKCS #1 v1.5 signatures with SHA-256, like Kubo RSA keys.
+impl RecordSigner<Signature> for SigningKey<Sha256> {
+    fn crypto_key(&self) -> CryptoKey {
+        CryptoKey::new_rsa(&self.as_ref().to_public_key())
+    }
+}
+
 #[async_trait]
 pub trait AsyncRecordSigner<S>: AsyncSigner<S>
 where
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// Where the media data is stored.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    /// Pinned on the channel primary node.
+    #[default]
+    Primary,
+
+    /// Held by a remote pinning service only, slower to load.
+    Remote,
+}
+
+/// Archival state of one media.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ArchiveRecord {
+    pub tier: Tier,
+
+    /// Name of the remote pinning service holding the media.
+    pub service: String,
+
+    /// Timestamp of the move in Unix time.
+    pub timestamp: i64,
+}
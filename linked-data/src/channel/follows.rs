@@ -1,11 +1,18 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
-use crate::types::IPNSAddress;
+use crate::types::{IPLDLink, IPNSAddress};
 
 /// List of followees.
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
 pub struct Follows {
     pub followees: HashSet<IPNSAddress>,
 }
+
+/// Named lists of followees. (friends, news, music, etc...)
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct FollowLists {
+    /// List name to link of followees.
+    pub lists: HashMap<String, IPLDLink>,
+}
@@ -0,0 +1,20 @@
+use crate::types::IPNSAddress;
+
+use serde::{Deserialize, Serialize};
+
+/// Latest channel roots known to a peer, shared with friends over pubsub.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub struct Gossip {
+    pub entries: Vec<GossipEntry>,
+}
+
+/// A channel and the IPNS record pointing to its latest known root.
+///
+/// Root CID and sequence are read from the record, it must be verified before use.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct GossipEntry {
+    pub channel: IPNSAddress,
+
+    /// Protobuf encoded IPNS record.
+    pub record: Vec<u8>,
+}
@@ -1,4 +1,4 @@
-use crate::types::{IPLDLink, PeerId};
+use crate::types::{IPLDLink, IPNSAddress, PeerId};
 
 use serde::{Deserialize, Serialize};
 
@@ -25,4 +25,75 @@ pub struct LiveSettings {
     /// Link to moderators address.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mods: Option<IPLDLink>,
+
+    /// SEC1 encoded public key signing the live segments.
+    ///
+    /// Unsigned segments are ignored when set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_key: Option<Vec<u8>>,
+
+    /// Hubs notified when a stream starts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hubs: Vec<HubAnnouncement>,
+}
+
+impl LiveSettings {
+    /// PubSub topic for the encoder statistics of the live stream.
+    pub fn stats_topic(&self) -> String {
+        format!("{}_stats", self.video_topic)
+    }
+
+    /// PubSub topic where the active chat settings are broadcast.
+    pub fn chat_settings_topic(&self) -> Option<String> {
+        self.chat_topic
+            .as_ref()
+            .map(|topic| format!("{}_settings", topic))
+    }
+
+    /// Hubs to notify when a stream starts.
+    pub fn announced_hubs(&self) -> impl Iterator<Item = IPNSAddress> + '_ {
+        self.hubs
+            .iter()
+            .filter(|announcement| announcement.enabled)
+            .map(|announcement| announcement.hub)
+    }
+}
+
+/// Aggregation hub submitted a live beacon when a stream starts.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct HubAnnouncement {
+    /// Hub channel address.
+    pub hub: IPNSAddress,
+
+    /// Disabled hubs are kept but not notified.
+    pub enabled: bool,
+}
+
+/// Live segment announcement, signed by the streaming node.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub struct SignedSegment {
+    /// Link to the video segment.
+    pub segment: IPLDLink,
+
+    /// 64 bytes compact ECDSA signature of the segment CID.
+    pub signature: Vec<u8>,
+}
+
+/// Encoder statistics, published periodically while live.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub struct EncoderStats {
+    /// Timestamp in Unix time.
+    pub timestamp: i64,
+
+    /// Output bitrate in kilobits per second.
+    pub bitrate: f64,
+
+    /// Frames per second.
+    pub fps: f64,
+
+    /// Frames dropped since the start of the stream.
+    pub dropped_frames: u64,
+
+    /// Encoding speed relative to real time.
+    pub speed: f64,
 }
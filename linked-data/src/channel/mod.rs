@@ -1,6 +1,14 @@
+pub mod archive;
 pub mod follows;
+pub mod gossip;
 pub mod live;
 pub mod moderation;
+pub mod owners;
+pub mod playlists;
+pub mod registry;
+pub mod snapshot;
+pub mod succession;
+pub mod theme;
 
 use crate::types::IPLDLink;
 
@@ -18,10 +26,62 @@ pub struct ChannelMetadata {
     ///
     /// Keys = Content CIDs
     ///
-    /// Value = HAMT containing comments
+    /// Value = Media comments, HAMT containing comments and pinned comments
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment_index: Option<IPLDLink>,
 
+    /// Link to HAMT containing the replies to comments.
+    ///
+    /// Replies are in the comment index too, under their content.
+    ///
+    /// Keys = Comment CIDs
+    ///
+    /// Value = HAMT containing replies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_index: Option<IPLDLink>,
+
+    /// Link to HAMT containing the older comments of media with many comments.
+    ///
+    /// Keys = Content CIDs
+    ///
+    /// Value = Archived comments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_archive: Option<IPLDLink>,
+
+    /// Link to counting bloom filter of the content CIDs in the comment index.
+    ///
+    /// Keys are counted once per comment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_filter: Option<IPLDLink>,
+
+    /// Link to HAMT containing the comment count of each media.
+    ///
+    /// Keys = Content CIDs
+    ///
+    /// Value = Comment count
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_counts: Option<IPLDLink>,
+
+    /// Link to HAMT containing the comments of media no longer in the content index.
+    ///
+    /// Keys = Content CIDs
+    ///
+    /// Value = HAMT containing comments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orphan_index: Option<IPLDLink>,
+
+    /// Link to HAMT containing all the comment upvotes.
+    ///
+    /// Keys = Content CIDs
+    ///
+    /// Value = HAMT with comment CIDs as keys and voters as values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upvote_index: Option<IPLDLink>,
+
+    /// Link to the comment permissions of the channel's media.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_settings: Option<IPLDLink>,
+
     /// Link to live stream settings.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub live: Option<IPLDLink>,
@@ -30,7 +90,99 @@ pub struct ChannelMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub follows: Option<IPLDLink>,
 
+    /// Link to named lists of followees.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follow_lists: Option<IPLDLink>,
+
+    /// Link to named playlists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playlists: Option<IPLDLink>,
+
     /// Pubsub channel topic for aggregation.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agregation_channel: Option<String>,
+
+    /// Link to prolly tree of registry listings.
+    ///
+    /// Keys = Topic tag + Channel address
+    ///
+    /// Value = Signed listing CID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry: Option<IPLDLink>,
+
+    /// Link to prolly tree of the content approved by this hub.
+    ///
+    /// Keys = Approval timestamp + Content CID
+    ///
+    /// Value = Content CID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hub_index: Option<IPLDLink>,
+
+    /// Link to HAMT containing the alternate versions of content.
+    ///
+    /// Keys = Content CIDs
+    ///
+    /// Value = Variants
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant_index: Option<IPLDLink>,
+
+    /// Link to prolly tree of the content permalinks.
+    ///
+    /// Keys = Slug
+    ///
+    /// Value = Content CID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slug_index: Option<IPLDLink>,
+
+    /// Link to prolly tree of the permalink of each content, the reverse of the slug index.
+    ///
+    /// Keys = Content CIDs
+    ///
+    /// Value = Slug
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permalink_index: Option<IPLDLink>,
+
+    /// Link to HAMT containing the content of each tag.
+    ///
+    /// Keys = Tag hashes
+    ///
+    /// Value = HAMT containing content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_index: Option<IPLDLink>,
+
+    /// Link to HAMT containing the attendees of events.
+    ///
+    /// Keys = Event CIDs
+    ///
+    /// Value = Attendees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rsvp_index: Option<IPLDLink>,
+
+    /// Link to HAMT containing the archival state of old media.
+    ///
+    /// Keys = Content CIDs
+    ///
+    /// Value = Archive record
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive_index: Option<IPLDLink>,
+
+    /// Link to the branding of the channel page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<IPLDLink>,
+
+    /// Link to the owners of a co-owned channel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owners: Option<IPLDLink>,
+
+    /// Link to the owners approvals of this metadata.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approvals: Option<IPLDLink>,
+
+    /// Link to a snapshot of the latest content, for fast first renders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<IPLDLink>,
+
+    /// Link to the successors of this channel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub succession: Option<IPLDLink>,
 }
@@ -1,4 +1,4 @@
-use crate::types::{Address, PeerId};
+use crate::types::{Address, IPLDLink, PeerId};
 
 use std::collections::HashSet;
 
@@ -18,6 +18,16 @@ pub struct Moderators {
     pub moderator_addrs: HashSet<Address>,
 }
 
+/// Personal list of blocked users, applied when reading.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct Blocklist {
+    /// Identities whose comments are hidden.
+    pub identities: HashSet<IPLDLink>,
+
+    /// Peers whose chat messages are hidden.
+    pub peers: HashSet<PeerId>,
+}
+
 /// Message to ban/unban a user.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Ban {
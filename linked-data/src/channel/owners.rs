@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::IPLDLink;
+
+/// Group sharing ownership of a channel.
+///
+/// Metadata updates must be approved by at least `threshold` owners.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone)]
+pub struct Owners {
+    /// Number of approvals required.
+    pub threshold: usize,
+
+    /// SEC1 encoded public keys of each owner.
+    pub keys: Vec<Vec<u8>>,
+}
+
+/// Owners signatures approving a channel metadata update.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct Approvals {
+    /// Link to the proposed channel metadata, identical to the final one minus the approvals.
+    pub proposal: IPLDLink,
+
+    /// Links to signed links of the proposal.
+    pub signatures: Vec<IPLDLink>,
+}
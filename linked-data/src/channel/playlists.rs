@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::IPLDLink;
+
+/// Content curated by a channel, in playing order.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct Playlist {
+    /// Short description of this playlist.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Link to thumbnail image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<IPLDLink>,
+
+    /// Links to the content, in playing order.
+    pub content: Vec<IPLDLink>,
+}
+
+/// Named playlists of a channel.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct Playlists {
+    /// Playlist name to link of playlist.
+    pub playlists: HashMap<String, IPLDLink>,
+}
@@ -0,0 +1,21 @@
+use crate::types::{IPLDLink, IPNSAddress};
+
+use serde::{Deserialize, Serialize};
+
+/// Request for a channel to be listed in a registry under some topics.
+///
+/// Must be signed by the identity of the channel being listed.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub struct Listing {
+    /// Creator identity link
+    pub identity: IPLDLink,
+
+    /// Timestamp at the time of publication in Unix time.
+    pub user_timestamp: i64,
+
+    /// Address of the channel being listed.
+    pub channel: IPNSAddress,
+
+    /// Topic tags the channel should be listed under.
+    pub topics: Vec<String>,
+}
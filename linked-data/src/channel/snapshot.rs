@@ -0,0 +1,42 @@
+use crate::{identity::Identity, media::Media, types::IPLDLink};
+
+use serde::{Deserialize, Serialize};
+
+/// Everything needed to render the top of a channel page, in one block.
+///
+/// Copies of linked data, the indexes remain the source of truth.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub struct Snapshot {
+    /// Timestamp in Unix time.
+    pub timestamp: i64,
+
+    /// Copy of the channel identity.
+    pub identity: Identity,
+
+    /// Link to the content index at the time of the snapshot, to continue from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_index: Option<IPLDLink>,
+
+    /// Latest content, newest first.
+    pub content: Vec<SnapshotMedia>,
+
+    /// Copies of the other identities found in the content.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identities: Vec<SnapshotIdentity>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct SnapshotMedia {
+    /// Link to the signed content, as found in the content index.
+    pub link: IPLDLink,
+
+    /// Copy of the content metadata.
+    pub media: Media,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct SnapshotIdentity {
+    pub link: IPLDLink,
+
+    pub identity: Identity,
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::{IPLDLink, IPNSAddress};
+
+/// Keys allowed to continue a channel once its creator stops updating it.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone)]
+pub struct Succession {
+    /// SEC1 encoded public keys of the successors.
+    pub keys: Vec<Vec<u8>>,
+
+    /// Seconds without a channel update before successors take over.
+    pub inactivity: i64,
+
+    /// Timestamp in Unix time of the latest channel update.
+    pub heartbeat: i64,
+}
+
+/// Claim of a successor to continue a channel at a new address.
+///
+/// Signed by one of the successor keys.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SuccessionClaim {
+    /// Address of the inactive channel.
+    pub channel: IPNSAddress,
+
+    /// Link to the latest metadata of the inactive channel.
+    pub metadata: IPLDLink,
+
+    /// Address where the successor publishes the channel from now on.
+    pub successor: IPNSAddress,
+
+    /// Timestamp in Unix time.
+    pub timestamp: i64,
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+/// Branding of a channel page.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Theme {
+    /// Accent colors, most important first. Hex RGB eg. "#1e90ff"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accent_colors: Vec<String>,
+
+    /// Preferred layout of the content list.
+    #[serde(default)]
+    pub layout: Layout,
+
+    /// Point of the identity banner to keep in view when cropped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner_focal_point: Option<FocalPoint>,
+
+    /// Buttons linking to external pages, in display order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<LinkButton>,
+}
+
+/// Layout hint for frontends, free to ignore it.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    #[default]
+    Grid,
+
+    List,
+
+    /// Latest content displayed large above the rest.
+    Featured,
+}
+
+/// Position relative to the image size, from 0.0 to 1.0 with the origin at the top left.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct FocalPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct LinkButton {
+    pub label: String,
+    pub url: String,
+}
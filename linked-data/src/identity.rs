@@ -33,6 +33,114 @@ pub struct Identity {
     /// Ethereum address
     #[serde(skip_serializing_if = "Option::is_none")]
     pub eth_addr: Option<String>,
+
+    /// X25519 public key for direct messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dm_key: Option<Vec<u8>>,
+
+    /// Pronouns, as written by the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pronouns: Option<String>,
+
+    /// Free form location.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+
+    /// External links, in display order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<ProfileLink>,
+
+    /// Order in which profile fields are displayed, omitted fields are hidden.
+    ///
+    /// When empty, frontends choose.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub display: Vec<ProfileField>,
+
+    /// Channels trusted to vouch for a new key if this one is lost.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery: Option<Recovery>,
+
+    /// Links to signed claims of external accounts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub claims: Vec<IPLDLink>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct ProfileLink {
+    pub title: String,
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ProfileField {
+    Bio,
+    Pronouns,
+    Location,
+    Links,
+}
+
+/// Claim that an account on another platform belongs to this identity.
+///
+/// The proof is a public post by the account that contains the address of the claim signer.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Claim {
+    /// Timestamp in Unix time.
+    pub user_timestamp: i64,
+
+    pub platform: Platform,
+
+    /// User name on the platform, domain name for websites.
+    pub account: String,
+
+    /// Url of the proof.
+    pub proof: String,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Platform {
+    /// Proof is a public gist.
+    GitHub,
+
+    /// Proof is a post.
+    Twitter,
+
+    /// Proof is a text file hosted on the domain.
+    Website,
+}
+
+/// Social recovery scheme, chosen before the keys are lost.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+pub struct Recovery {
+    /// Number of vouches required.
+    pub threshold: usize,
+
+    /// Channels trusted to vouch.
+    pub channels: Vec<IPNSAddress>,
+}
+
+/// Request binding a new address to an identity whose keys were lost.
+///
+/// Accepted once enough trusted channels signed it.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct RecoveryNode {
+    /// The identity being recovered.
+    pub identity: IPLDLink,
+
+    /// Ethereum address replacing the lost one.
+    pub eth_addr: String,
+
+    /// Timestamp in Unix time.
+    pub timestamp: i64,
+}
+
+/// Trusted channels signatures vouching for a recovery.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Vouches {
+    /// Link to the recovery node.
+    pub recovery: IPLDLink,
+
+    /// Links to signed links of the recovery node.
+    pub signatures: Vec<IPLDLink>,
 }
 
 //TODO Key Rotation and Management system
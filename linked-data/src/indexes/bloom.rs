@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_COUNTERS: usize = 4096;
+pub const DEFAULT_HASHES: u8 = 4;
+
+/// Counting bloom filter, keys can be removed.
+///
+/// Saturated counters are never decremented.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct BloomFilter {
+    /// Number of counters set per key.
+    pub hashes: u8,
+
+    pub counters: Vec<u8>,
+}
+
+impl Default for BloomFilter {
+    fn default() -> Self {
+        Self {
+            hashes: DEFAULT_HASHES,
+            counters: vec![0; DEFAULT_COUNTERS],
+        }
+    }
+}
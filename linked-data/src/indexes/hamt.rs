@@ -70,6 +70,10 @@ pub enum Element {
 pub struct BucketEntry {
     pub key: [u8; DIGEST_LENGTH_BYTES],
     pub value: IPLDLink,
+
+    /// Multihash code of the key, when not the root hash algorithm.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<u64>,
 }
 
 impl PartialEq for BucketEntry {
@@ -1,3 +1,4 @@
+pub mod bloom;
 pub mod date_time;
 pub mod hamt;
 pub mod log;
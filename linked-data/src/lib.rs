@@ -2,4 +2,5 @@ pub mod channel;
 pub mod identity;
 pub mod indexes;
 pub mod media;
+pub mod messaging;
 pub mod types;
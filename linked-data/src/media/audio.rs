@@ -0,0 +1,54 @@
+use crate::{
+    media::{MediaFormat, Variant},
+    types::IPLDLink,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Metadata for a podcast episode or other audio file.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug, Default)]
+pub struct Audio {
+    /// Creator identity link
+    pub identity: IPLDLink,
+
+    /// Timestamp at the time of publication in Unix time.
+    pub user_timestamp: i64,
+
+    /// Link to audio file.
+    pub audio: IPLDLink,
+
+    /// Title of this episode.
+    pub title: String,
+
+    /// Media type of the audio file, e.g. audio/mpeg.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+
+    /// Duration in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+
+    /// Size in bytes of the audio file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    /// Link to cover image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<IPLDLink>,
+
+    /// Short abstract of the episode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    /// Alternate versions of this content; dubbed, translated or re-edited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<Variant>,
+
+    /// Container and codecs of the audio file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<MediaFormat>,
+
+    /// Topics of this content, for browsing by category.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
@@ -1,4 +1,4 @@
-use crate::types::IPLDLink;
+use crate::{media::Variant, types::IPLDLink};
 
 use serde::{Deserialize, Serialize};
 
@@ -24,4 +24,16 @@ pub struct BlogPost {
     /// Number of words in the text
     #[serde(skip_serializing_if = "Option::is_none")]
     pub word_count: Option<u64>,
+
+    /// Short abstract of the text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    /// Alternate versions of this content; dubbed, translated or re-edited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<Variant>,
+
+    /// Topics of this content, for browsing by category.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
 pub struct ChatMessage {
-    /// Usualy text, ban user or add moderator.
+    /// Usualy text, ban user, add moderator or change settings.
     pub message: MessageType,
 
     /// Link to DAG-JOSE block for verification.
@@ -19,6 +19,26 @@ pub enum MessageType {
     Text(String),
     Ban(Ban),
     Mod(Moderator),
+
+    /// Link to chat settings signed by a moderator.
+    Settings(IPLDLink),
+}
+
+/// Chat rules changed live by moderators, moderators are exempt.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+pub struct ChatSettings {
+    /// Minimum number of seconds between messages of the same user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_mode: Option<u32>,
+
+    /// Only followers of the channel can chat.
+    pub followers_only: bool,
+
+    /// Messages can only contain emotes.
+    pub emote_only: bool,
+
+    /// Timestamp in Unix time, settings older than the active ones are ignored.
+    pub timestamp: i64,
 }
 
 /// The purpose of signing this data is to mitigate identity theft.
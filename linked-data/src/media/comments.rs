@@ -1,5 +1,7 @@
 use crate::types::IPLDLink;
 
+use std::collections::{HashMap, HashSet};
+
 use cid::Cid;
 
 use serde::{Deserialize, Serialize};
@@ -21,6 +23,125 @@ pub struct Comment {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub origin: Option<Cid>,
 
+    /// Link to the comment being replied to, on the same content.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<IPLDLink>,
+
     /// Text content.
     pub text: String,
 }
+
+/// Upvote on a comment.
+#[serde_as]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct Upvote {
+    /// Voter identity link
+    pub identity: IPLDLink,
+
+    /// Timestamp at the time of publication in Unix time.
+    pub user_timestamp: i64,
+
+    /// Link to the comment being upvoted.
+    #[serde_as(as = "DisplayFromStr")]
+    pub comment: Cid,
+}
+
+/// Identities that upvoted a comment.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct Voters {
+    pub voters: HashSet<IPLDLink>,
+}
+
+/// Older comments of a media, moved out of the comment index in chunks.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct ArchivedComments {
+    /// Links to comment chunks, oldest first.
+    pub chunks: Vec<IPLDLink>,
+}
+
+/// Comments archived together.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct CommentChunk {
+    pub comments: Vec<IPLDLink>,
+}
+
+/// Number of comments on a media, kept up to date so pages can show it without counting.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct CommentCount {
+    /// Archived comments included.
+    pub count: u64,
+
+    /// Timestamp of the latest comment in Unix time.
+    ///
+    /// Recomputed when the latest comment is removed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_comment: Option<i64>,
+}
+
+/// Comments of a media in the channel comment index.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct MediaComments {
+    /// Link to HAMT containing the comments not archived yet.
+    ///
+    /// Keys = Values = Comment CIDs
+    pub comments: IPLDLink,
+
+    /// Number of comments in the HAMT, archived ones excluded.
+    pub count: u64,
+
+    /// Comments shown on top of the media, in pinning order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned: Vec<IPLDLink>,
+}
+
+/// Who can comment on a media, from most to least open.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum CommentPermission {
+    #[default]
+    Everyone,
+
+    /// Users whose channel follows this one, vouched users included.
+    Followers,
+
+    /// Users whose channel is followed by this one.
+    Vouched,
+
+    Closed,
+}
+
+/// Comment permissions of a channel's media.
+#[serde_as]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct CommentSettings {
+    /// Permission of media without their own.
+    pub permission: CommentPermission,
+
+    /// Permission of each media, keyed by content CID.
+    #[serde_as(as = "HashMap<DisplayFromStr, _>")]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub media: HashMap<Cid, CommentPermission>,
+}
+
+impl CommentSettings {
+    /// Returns the permission of this media.
+    pub fn get(&self, media: Cid) -> CommentPermission {
+        self.media.get(&media).copied().unwrap_or(self.permission)
+    }
+
+    /// Set the permission of one media or of all media without their own.
+    ///
+    /// Media keep their own permission when the channel one changes, even if equal.
+    pub fn set(&mut self, media: Option<Cid>, permission: CommentPermission) {
+        match media {
+            Some(media) => {
+                self.media.insert(media, permission);
+            }
+            None => self.permission = permission,
+        }
+    }
+
+    /// Remove the permission of one media, the channel one applies again.
+    pub fn unset(&mut self, media: Cid) {
+        self.media.remove(&media);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::types::{IPLDLink, IPNSAddress};
+
+use std::collections::{HashMap, HashSet};
+
+use cid::Cid;
+
+use serde::{Deserialize, Serialize};
+
+use serde_with::{serde_as, DisplayFromStr};
+
+/// Metadata of an upcoming event or premiere.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug, Default)]
+pub struct Event {
+    /// Creator identity link
+    pub identity: IPLDLink,
+
+    /// Timestamp at the time of publication in Unix time.
+    pub user_timestamp: i64,
+
+    /// Title of this event.
+    pub title: String,
+
+    /// Start time in Unix time.
+    pub start: i64,
+
+    /// Duration in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+
+    /// Address or place name of an in person event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+
+    /// Channel streaming the event live.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<IPNSAddress>,
+
+    /// Link to thumbnail image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<IPLDLink>,
+
+    /// Short abstract of the event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    /// Topics of this content, for browsing by category.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Answer to an event invitation.
+#[serde_as]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct Rsvp {
+    /// Attendee identity link
+    pub identity: IPLDLink,
+
+    /// Timestamp at the time of publication in Unix time.
+    pub user_timestamp: i64,
+
+    /// Link to the event.
+    #[serde_as(as = "DisplayFromStr")]
+    pub event: Cid,
+
+    /// False when declining a previous answer.
+    pub attending: bool,
+}
+
+/// Identities attending an event.
+#[serde_as]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct Attendees {
+    pub attendees: HashSet<IPLDLink>,
+
+    /// Timestamp of the latest RSVP counted, keyed by identity CID.
+    #[serde_as(as = "HashMap<DisplayFromStr, _>")]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub answered: HashMap<Cid, i64>,
+}
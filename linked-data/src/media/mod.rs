@@ -1,20 +1,24 @@
+pub mod audio;
 pub mod blog;
 pub mod chat;
 pub mod comments;
+pub mod event;
 pub mod video;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{media::comments::Comment, types::IPLDLink};
 
-use self::{blog::BlogPost, video::Video};
+use self::{audio::Audio, blog::BlogPost, event::Event, video::Video};
 
-#[derive(Deserialize, PartialEq, Clone, Debug)]
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
 #[serde(untagged)]
 pub enum Media {
     Blog(BlogPost),
     Video(Video),
     Comment(Comment),
+    Event(Event),
+    Audio(Audio),
 }
 
 impl Media {
@@ -23,6 +27,8 @@ impl Media {
             Media::Blog(metadata) => metadata.user_timestamp,
             Media::Video(metadata) => metadata.user_timestamp,
             Media::Comment(metadata) => metadata.user_timestamp,
+            Media::Event(metadata) => metadata.user_timestamp,
+            Media::Audio(metadata) => metadata.user_timestamp,
         }
     }
 
@@ -31,6 +37,77 @@ impl Media {
             Media::Blog(metadata) => metadata.identity,
             Media::Video(metadata) => metadata.identity,
             Media::Comment(metadata) => metadata.identity,
+            Media::Event(metadata) => metadata.identity,
+            Media::Audio(metadata) => metadata.identity,
         }
     }
+
+    /// Title of the media, comments have none.
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            Media::Blog(metadata) => Some(&metadata.title),
+            Media::Video(metadata) => Some(&metadata.title),
+            Media::Event(metadata) => Some(&metadata.title),
+            Media::Audio(metadata) => Some(&metadata.title),
+            Media::Comment(_) => None,
+        }
+    }
+
+    /// Alternate versions declared by the creator.
+    pub fn variants(&self) -> &[Variant] {
+        match self {
+            Media::Blog(metadata) => &metadata.variants,
+            Media::Video(metadata) => &metadata.variants,
+            Media::Audio(metadata) => &metadata.variants,
+            Media::Comment(_) | Media::Event(_) => &[],
+        }
+    }
+
+    /// Tags declared by the creator, comments have none.
+    pub fn tags(&self) -> &[String] {
+        match self {
+            Media::Blog(metadata) => &metadata.tags,
+            Media::Video(metadata) => &metadata.tags,
+            Media::Event(metadata) => &metadata.tags,
+            Media::Audio(metadata) => &metadata.tags,
+            Media::Comment(_) => &[],
+        }
+    }
+}
+
+/// Alternate version of the same content.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Debug)]
+pub struct Variant {
+    /// Link to the signed media.
+    pub link: IPLDLink,
+
+    pub kind: VariantKind,
+
+    /// Language as an IETF BCP 47 tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum VariantKind {
+    Original,
+    Dubbed,
+    Translated,
+    ReEdited,
+}
+
+/// All the known variants of some content.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Debug, Default)]
+pub struct Variants {
+    pub variants: Vec<Variant>,
+}
+
+/// Container and codecs of a media file, as probed when it was added.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Debug, Default)]
+pub struct MediaFormat {
+    /// Container format, e.g. mp3 or matroska,webm.
+    pub container: String,
+
+    /// Codec of each track, e.g. h264 or opus.
+    pub codecs: Vec<String>,
 }
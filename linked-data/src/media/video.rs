@@ -1,4 +1,7 @@
-use crate::types::IPLDLink;
+use crate::{
+    media::{MediaFormat, Variant},
+    types::IPLDLink,
+};
 
 use std::collections::HashMap;
 
@@ -23,9 +26,54 @@ pub struct Video {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<f64>,
 
+    /// Total size in bytes of all segments of all tracks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    /// Number of segments in the timecode structure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_count: Option<u64>,
+
     /// Link to thumbnail image.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image: Option<IPLDLink>,
+
+    /// Short abstract of the video.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    /// Alternate versions of this content; dubbed, translated or re-edited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<Variant>,
+
+    /// Chapters ordered by start time.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<Chapter>,
+
+    /// Links to thumbnail images taken throughout the video.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thumbnails: Vec<IPLDLink>,
+
+    /// Container and codecs of the source file, before encoding.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<MediaFormat>,
+
+    /// Recorded from a live stream.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub live: bool,
+
+    /// Topics of this content, for browsing by category.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Named section of a video.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug, Default)]
+pub struct Chapter {
+    /// Start time in seconds.
+    pub start: f64,
+
+    pub title: String,
 }
 
 /// Timecode structure root CID.
@@ -34,6 +82,35 @@ pub struct Timecode {
     /// Path ../time/..
     #[serde(rename = "time")]
     pub timecode: IPLDLink,
+
+    /// Total size in bytes of all segments of all tracks, as archived.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    /// Number of segments archived.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_count: Option<u64>,
+
+    /// Sum of segment durations in seconds, from the media playlists.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+
+    /// Links to the parity of groups of segments, for repairs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parity: Vec<IPLDLink>,
+}
+
+/// Reed-Solomon parity of one track of consecutive segments.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub struct Parity {
+    /// Links to the track data of each segment, in order.
+    pub data: Vec<IPLDLink>,
+
+    /// Size in bytes of each track data, shorter ones are zero padded when coding.
+    pub sizes: Vec<u64>,
+
+    /// Links to the parity shards, as long as the longest track data.
+    pub parity: Vec<IPLDLink>,
 }
 
 /// Links all hour nodes for multiple hours of video.
@@ -0,0 +1,29 @@
+use crate::types::IPLDLink;
+
+use serde::{Deserialize, Serialize};
+
+/// Direct message between two users, encrypted before being sent.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct DirectMessage {
+    /// Sender identity link
+    pub identity: IPLDLink,
+
+    /// Timestamp at the time of sending in Unix time.
+    pub user_timestamp: i64,
+
+    /// Text content.
+    pub text: String,
+}
+
+/// Encrypted direct message, as published on the pairwise topic.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct EncryptedMessage {
+    /// X25519 public key of the sender.
+    pub sender: Vec<u8>,
+
+    /// XChaCha20-Poly1305 nonce.
+    pub nonce: Vec<u8>,
+
+    /// DAG-CBOR encoded direct message.
+    pub ciphertext: Vec<u8>,
+}